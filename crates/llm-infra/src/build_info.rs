@@ -0,0 +1,183 @@
+//! Build metadata for tracing binaries and results back to a build
+//!
+//! The values are captured by the build script of the crate being built, so
+//! they describe that binary rather than llm-infra itself:
+//!
+//! ```rust,ignore
+//! // build.rs, with llm-infra as a build-dependency
+//! fn main() {
+//!     llm_infra::build_info::emit();
+//! }
+//!
+//! // anywhere in the crate
+//! let info = llm_infra::build_info!();
+//! tracing::info!(commit = info.git_commit, "starting");
+//! ```
+//!
+//! Without a git checkout (e.g. a Docker build context without `.git`),
+//! pass `GIT_COMMIT` and `GIT_BRANCH` to the build instead. `SOURCE_DATE_EPOCH`
+//! pins the build timestamp for reproducible builds. Values that can't be
+//! determined are `"unknown"`.
+
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+/// Placeholder for values that couldn't be determined at build time
+pub const UNKNOWN: &str = "unknown";
+
+/// Environment variables set by [`emit`] and read by [`build_info!`](crate::build_info!)
+pub mod env {
+    /// Full commit hash
+    pub const GIT_COMMIT: &str = "LLM_BUILD_GIT_COMMIT";
+    /// Branch name, `HEAD` for a detached checkout
+    pub const GIT_BRANCH: &str = "LLM_BUILD_GIT_BRANCH";
+    /// RFC 3339 UTC timestamp
+    pub const TIMESTAMP: &str = "LLM_BUILD_TIMESTAMP";
+    /// `rustc --version` output
+    pub const RUSTC_VERSION: &str = "LLM_BUILD_RUSTC_VERSION";
+}
+
+/// Identity of a build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    /// Cargo package name
+    pub package: &'static str,
+    /// Cargo package version
+    pub version: &'static str,
+    /// Full commit hash
+    pub git_commit: &'static str,
+    /// Branch the build was made from
+    pub git_branch: &'static str,
+    /// When the build script last ran: the first build after the commit changed
+    pub build_timestamp: &'static str,
+    /// Compiler that built the binary
+    pub rustc_version: &'static str,
+}
+
+impl BuildInfo {
+    /// First 12 characters of the commit hash
+    pub fn short_commit(&self) -> &'static str {
+        self.git_commit.get(..12).unwrap_or(self.git_commit)
+    }
+
+    /// Build fields as key/value pairs, for stamping records such as
+    /// benchmark results
+    pub fn metadata(&self) -> [(&'static str, &'static str); 4] {
+        [
+            ("git_commit", self.git_commit),
+            ("git_branch", self.git_branch),
+            ("build_timestamp", self.build_timestamp),
+            ("rustc_version", self.rustc_version),
+        ]
+    }
+}
+
+/// The [`BuildInfo`] of the crate this macro is expanded in
+///
+/// Reads the variables set by [`emit`] in that crate's build script; without
+/// one the git and build fields are `"unknown"`.
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::build_info::BuildInfo {
+            package: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: $crate::build_info::or_unknown(option_env!("LLM_BUILD_GIT_COMMIT")),
+            git_branch: $crate::build_info::or_unknown(option_env!("LLM_BUILD_GIT_BRANCH")),
+            build_timestamp: $crate::build_info::or_unknown(option_env!("LLM_BUILD_TIMESTAMP")),
+            rustc_version: $crate::build_info::or_unknown(option_env!("LLM_BUILD_RUSTC_VERSION")),
+        }
+    };
+}
+
+/// Used by [`build_info!`](crate::build_info!)
+#[doc(hidden)]
+pub const fn or_unknown(value: Option<&'static str>) -> &'static str {
+    match value {
+        Some(value) => value,
+        None => UNKNOWN,
+    }
+}
+
+/// Capture build metadata; call from a build script
+///
+/// Prints `cargo:rustc-env` lines for [`build_info!`](crate::build_info!) and
+/// asks Cargo to rerun when the checked-out commit or the overrides change.
+pub fn emit() {
+    let non_empty = |value: String| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    let var = |name: &str| std::env::var(name).ok().and_then(non_empty);
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| non_empty(String::from_utf8_lossy(&output.stdout).into_owned()))
+    };
+
+    let commit = var("GIT_COMMIT").or_else(|| git(&["rev-parse", "HEAD"]));
+    let branch = var("GIT_BRANCH").or_else(|| git(&["rev-parse", "--abbrev-ref", "HEAD"]));
+
+    let timestamp = var("SOURCE_DATE_EPOCH")
+        .and_then(|epoch| epoch.parse().ok())
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+        .unwrap_or_else(chrono::Utc::now)
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+    // Cargo tells build scripts which compiler it uses
+    let rustc = var("RUSTC").unwrap_or_else(|| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| non_empty(String::from_utf8_lossy(&output.stdout).into_owned()));
+
+    for (name, value) in [
+        (env::GIT_COMMIT, commit),
+        (env::GIT_BRANCH, branch),
+        (env::TIMESTAMP, Some(timestamp)),
+        (env::RUSTC_VERSION, rustc_version),
+    ] {
+        if let Some(value) = value {
+            println!("cargo:rustc-env={}={}", name, value);
+        }
+    }
+
+    for name in ["GIT_COMMIT", "GIT_BRANCH", "SOURCE_DATE_EPOCH"] {
+        println!("cargo:rerun-if-env-changed={}", name);
+    }
+    // HEAD moves on checkout; the branch ref moves on commit
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        let head_ref = git(&["symbolic-ref", "-q", "HEAD"])
+            .map(|head_ref| format!("{}/{}", git_dir, head_ref))
+            .filter(|path| Path::new(path).exists());
+        if let Some(path) = head_ref {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_defaults_to_unknown() {
+        // llm-infra has no build script, so only the package fields are known
+        let info = crate::build_info!();
+        assert_eq!(info.package, "llm-infra");
+        assert_eq!(info.version, crate::VERSION);
+        assert_eq!(info.git_commit, UNKNOWN);
+        assert_eq!(info.short_commit(), UNKNOWN);
+
+        let info = BuildInfo {
+            git_commit: "0123456789abcdef0123456789abcdef01234567",
+            ..info
+        };
+        assert_eq!(info.short_commit(), "0123456789ab");
+        assert_eq!(info.metadata()[0], ("git_commit", info.git_commit));
+    }
+}
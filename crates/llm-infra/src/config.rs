@@ -234,6 +234,10 @@ pub struct UpstreamServicesConfig {
     pub policy_engine_url: String,
     /// Policy Engine timeout in milliseconds
     pub policy_engine_timeout_ms: u64,
+    /// What to do with requests while LLM Shield can't scan them
+    pub shield_failure_policy: FailurePolicy,
+    /// What to do with requests while the Policy Engine can't validate them
+    pub policy_engine_failure_policy: FailurePolicy,
 }
 
 impl Default for UpstreamServicesConfig {
@@ -245,6 +249,46 @@ impl Default for UpstreamServicesConfig {
             shield_timeout_ms: 200,
             policy_engine_url: "http://localhost:8080".to_string(),
             policy_engine_timeout_ms: 300,
+            shield_failure_policy: FailurePolicy::Open,
+            policy_engine_failure_policy: FailurePolicy::Open,
+        }
+    }
+}
+
+/// Whether requests are allowed or denied while an upstream dependency that
+/// validates them is unavailable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Allow requests through unvalidated
+    Open,
+    /// Deny requests until the dependency is back
+    Closed,
+}
+
+impl FailurePolicy {
+    /// Default policy for an environment: closed in production, open elsewhere
+    pub fn default_for(environment: Environment) -> Self {
+        match environment {
+            Environment::Production => Self::Closed,
+            _ => Self::Open,
+        }
+    }
+
+    /// Whether requests are allowed while the dependency is unavailable
+    pub fn allows(self) -> bool {
+        self == Self::Open
+    }
+}
+
+impl std::str::FromStr for FailurePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "open" | "fail-open" | "allow" => Ok(Self::Open),
+            "closed" | "fail-closed" | "deny" => Ok(Self::Closed),
+            _ => Err(format!("Unknown failure policy: {}", s)),
         }
     }
 }
@@ -253,11 +297,7 @@ impl Default for UpstreamServicesConfig {
 pub fn load_from_env() -> Result<InfraConfig, crate::errors::InfraError> {
     dotenvy::dotenv().ok();
 
-    let environment = std::env::var("NODE_ENV")
-        .or_else(|_| std::env::var("ENVIRONMENT"))
-        .unwrap_or_else(|_| "development".to_string())
-        .parse()
-        .unwrap_or_default();
+    let environment = load_environment();
 
     let log_level = std::env::var("LOG_LEVEL")
         .unwrap_or_else(|_| "info".to_string())
@@ -278,6 +318,17 @@ pub fn load_from_env() -> Result<InfraConfig, crate::errors::InfraError> {
     })
 }
 
+/// Load the deployment environment from `NODE_ENV`, `ENVIRONMENT` or
+/// `RUST_ENV`
+pub fn load_environment() -> Environment {
+    std::env::var("NODE_ENV")
+        .or_else(|_| std::env::var("ENVIRONMENT"))
+        .or_else(|_| std::env::var("RUST_ENV"))
+        .unwrap_or_else(|_| "development".to_string())
+        .parse()
+        .unwrap_or_default()
+}
+
 /// Load database configuration from environment
 pub fn load_database_config() -> Result<DatabaseConfig, crate::errors::InfraError> {
     // Check for DATABASE_URL first
@@ -359,7 +410,19 @@ fn parse_redis_url(url: &str) -> Result<RedisConfig, crate::errors::InfraError>
 }
 
 /// Load upstream services configuration from environment
+///
+/// Failure policies default to closed in production and open elsewhere, and
+/// can be set per dependency with `LLM_SHIELD_FAILURE_POLICY` and
+/// `POLICY_ENGINE_FAILURE_POLICY` (`open` or `closed`).
 pub fn load_upstream_services_config() -> UpstreamServicesConfig {
+    let default_policy = FailurePolicy::default_for(load_environment());
+    let failure_policy = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(default_policy)
+    };
+
     UpstreamServicesConfig {
         registry_url: std::env::var("LLM_REGISTRY_URL")
             .unwrap_or_else(|_| "http://localhost:8081".to_string()),
@@ -379,6 +442,8 @@ pub fn load_upstream_services_config() -> UpstreamServicesConfig {
             .ok()
             .and_then(|t| t.parse().ok())
             .unwrap_or(300),
+        shield_failure_policy: failure_policy("LLM_SHIELD_FAILURE_POLICY"),
+        policy_engine_failure_policy: failure_policy("POLICY_ENGINE_FAILURE_POLICY"),
     }
 }
 
@@ -407,3 +472,25 @@ pub fn get_num_env<T: std::str::FromStr>(name: &str, default: T) -> T {
         .and_then(|v| v.parse().ok())
         .unwrap_or(default)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_policy() {
+        assert_eq!("closed".parse(), Ok(FailurePolicy::Closed));
+        assert_eq!("Fail-Open".parse(), Ok(FailurePolicy::Open));
+        assert!("sometimes".parse::<FailurePolicy>().is_err());
+
+        assert_eq!(
+            FailurePolicy::default_for(Environment::Production),
+            FailurePolicy::Closed
+        );
+        assert_eq!(
+            FailurePolicy::default_for(Environment::Staging),
+            FailurePolicy::Open
+        );
+        assert!(FailurePolicy::Open.allows() && !FailurePolicy::Closed.allows());
+    }
+}
@@ -11,6 +11,7 @@
 //! - **Retry**: Retry logic with exponential backoff and circuit breaker
 //! - **Rate Limiting**: Distributed rate limiting using token bucket algorithm
 //! - **Errors**: Standardized error types with HTTP status code mapping
+//! - **Build Info**: Git commit, branch, build time and rustc version of a binary
 //!
 //! ## Feature Flags
 //!
@@ -23,6 +24,7 @@
 //! - `retry`: Retry logic and circuit breaker
 //! - `rate-limit`: Distributed rate limiting
 //! - `errors`: Standardized error types
+//! - `build-info`: Build metadata captured by the consuming crate's build script
 //!
 //! ## Quick Start
 //!
@@ -65,6 +67,9 @@ pub mod rate_limit;
 #[cfg(feature = "errors")]
 pub mod errors;
 
+#[cfg(feature = "build-info")]
+pub mod build_info;
+
 /// Version of the llm-infra crate
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
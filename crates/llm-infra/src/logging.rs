@@ -1,18 +1,36 @@
 //! Structured logging utilities for LLM-Dev-Ops services.
 //!
 //! Provides tracing-based logging with structured context and JSON output.
+//!
+//! Output is rate limited per callsite (see [`RateLimitLayer`]), so an error
+//! storm from a failing upstream can't flood the log pipeline. The filter can
+//! be changed at runtime through a [`LogFilterHandle`].
 
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::callsite::{Callsite, Identifier};
+use tracing::field::{FieldSet, Value};
+use tracing::metadata::Kind;
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
-    layer::SubscriberExt,
+    layer::{Context, Layer, SubscriberExt},
+    reload,
     util::SubscriberInitExt,
-    EnvFilter,
+    EnvFilter, Registry,
 };
 
 pub use tracing::{debug, error, info, trace, warn, instrument, span, Level};
 
 /// Initialize logging with the given configuration
-pub fn init(config: &crate::config::InfraConfig) -> Result<(), crate::errors::InfraError> {
+///
+/// Returns a handle for changing the filter while the process runs.
+pub fn init(
+    config: &crate::config::InfraConfig,
+) -> Result<LogFilterHandle, crate::errors::InfraError> {
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
         let level = match config.log_level {
             crate::config::LogLevel::Trace => "trace",
@@ -23,6 +41,7 @@ pub fn init(config: &crate::config::InfraConfig) -> Result<(), crate::errors::In
         };
         EnvFilter::new(level)
     });
+    let (filter, handle) = LogFilterHandle::reloadable(filter);
 
     let is_production = matches!(config.environment, crate::config::Environment::Production);
 
@@ -37,7 +56,7 @@ pub fn init(config: &crate::config::InfraConfig) -> Result<(), crate::errors::In
 
         tracing_subscriber::registry()
             .with(filter)
-            .with(fmt_layer)
+            .with(RateLimitLayer::from_env(fmt_layer))
             .try_init()
             .map_err(|e| crate::errors::InfraError::configuration(format!("Failed to initialize logging: {}", e)))?;
     } else {
@@ -50,7 +69,7 @@ pub fn init(config: &crate::config::InfraConfig) -> Result<(), crate::errors::In
 
         tracing_subscriber::registry()
             .with(filter)
-            .with(fmt_layer)
+            .with(RateLimitLayer::from_env(fmt_layer))
             .try_init()
             .map_err(|e| crate::errors::InfraError::configuration(format!("Failed to initialize logging: {}", e)))?;
     }
@@ -62,15 +81,364 @@ pub fn init(config: &crate::config::InfraConfig) -> Result<(), crate::errors::In
         "Logging initialized"
     );
 
-    Ok(())
+    Ok(handle)
 }
 
 /// Initialize logging with defaults (for simple cases)
-pub fn init_default() -> Result<(), crate::errors::InfraError> {
+pub fn init_default() -> Result<LogFilterHandle, crate::errors::InfraError> {
     let config = crate::config::load_from_env()?;
     init(&config)
 }
 
+/// Changes the log filter of a running process
+///
+/// Takes `EnvFilter` directives, the `RUST_LOG` syntax, so single modules can
+/// be turned up: `info,consumption::services::quota_manager=debug`.
+#[derive(Clone)]
+pub struct LogFilterHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+    initial: String,
+}
+
+impl std::fmt::Debug for LogFilterHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFilterHandle")
+            .field("current", &self.current())
+            .field("initial", &self.initial)
+            .finish()
+    }
+}
+
+impl LogFilterHandle {
+    /// Make `filter` reloadable; add the returned layer to the subscriber in
+    /// its place
+    pub fn reloadable(filter: EnvFilter) -> (reload::Layer<EnvFilter, Registry>, Self) {
+        let initial = filter.to_string();
+        let (layer, handle) = reload::Layer::new(filter);
+        (layer, Self { handle, initial })
+    }
+
+    /// Directives of the active filter; empty once the subscriber is dropped
+    pub fn current(&self) -> String {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Directives the process started with
+    pub fn initial(&self) -> &str {
+        &self.initial
+    }
+
+    /// Replace the filter and return the directives it replaced
+    pub fn set(&self, directives: &str) -> Result<String, crate::errors::InfraError> {
+        if directives.trim().is_empty() {
+            return Err(crate::errors::InfraError::validation(
+                "Log filter must not be empty",
+            ));
+        }
+        let filter = EnvFilter::try_new(directives).map_err(|e| {
+            crate::errors::InfraError::validation(format!("Invalid log filter: {}", e))
+        })?;
+
+        let previous = self.current();
+        self.handle.reload(filter).map_err(|e| {
+            crate::errors::InfraError::configuration(format!("Failed to reload log filter: {}", e))
+        })?;
+        info!(previous = %previous, current = %directives, "Log filter changed");
+        Ok(previous)
+    }
+
+    /// Restore the filter the process started with
+    pub fn reset(&self) -> Result<String, crate::errors::InfraError> {
+        self.set(&self.initial)
+    }
+}
+
+/// Per-callsite log rate limiting settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRateLimitConfig {
+    /// Whether events are limited at all
+    pub enabled: bool,
+    /// Most verbose level that is limited; `WARN` limits warnings and errors
+    pub level: Level,
+    /// Events a callsite may log back to back
+    pub burst: u32,
+    /// Events per second a callsite regains after its burst is used up
+    pub per_second: f64,
+    /// While a callsite is over its limit, still log one in this many events
+    /// (0 logs none)
+    pub sample_every: u64,
+}
+
+impl Default for LogRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            level: Level::WARN,
+            burst: 20,
+            per_second: 5.0,
+            sample_every: 0,
+        }
+    }
+}
+
+impl LogRateLimitConfig {
+    /// Load from `LOG_RATE_LIMIT` (default true), `LOG_RATE_LIMIT_LEVEL`
+    /// (warn), `LOG_RATE_LIMIT_BURST` (20), `LOG_RATE_LIMIT_PER_SEC` (5) and
+    /// `LOG_SAMPLE_EVERY` (0)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: crate::config::get_bool_env("LOG_RATE_LIMIT", defaults.enabled),
+            level: crate::config::get_num_env("LOG_RATE_LIMIT_LEVEL", defaults.level),
+            burst: crate::config::get_num_env("LOG_RATE_LIMIT_BURST", defaults.burst),
+            per_second: crate::config::get_num_env("LOG_RATE_LIMIT_PER_SEC", defaults.per_second),
+            sample_every: crate::config::get_num_env("LOG_SAMPLE_EVERY", defaults.sample_every),
+        }
+    }
+}
+
+/// Token bucket of one callsite
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    suppressed: u64,
+}
+
+/// Per-callsite token buckets
+#[derive(Debug)]
+struct LogRateLimiter {
+    config: LogRateLimitConfig,
+    buckets: Mutex<HashMap<Identifier, Bucket>>,
+}
+
+impl LogRateLimiter {
+    fn new(config: LogRateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether an event may be logged: `Some(n)` with the number of events
+    /// suppressed at its callsite since the last one logged, `None` to drop it
+    fn check(&self, metadata: &Metadata<'_>, now: Instant) -> Option<u64> {
+        if !self.config.enabled || *metadata.level() > self.config.level {
+            return Some(0);
+        }
+
+        let burst = f64::from(self.config.burst);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(metadata.callsite()).or_insert(Bucket {
+            tokens: burst,
+            refilled_at: now,
+            suppressed: 0,
+        });
+
+        let elapsed = now
+            .saturating_duration_since(bucket.refilled_at)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.config.per_second).min(burst);
+        bucket.refilled_at = now;
+
+        let sampled =
+            self.config.sample_every > 0 && bucket.suppressed + 1 >= self.config.sample_every;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+        } else if !sampled {
+            bucket.suppressed += 1;
+            return None;
+        }
+        Some(std::mem::take(&mut bucket.suppressed))
+    }
+}
+
+/// Callsite of the "suppressed N similar errors" summary lines
+struct SummaryCallsite;
+
+static SUMMARY_CALLSITE: SummaryCallsite = SummaryCallsite;
+
+static SUMMARY_METADATA: Metadata<'static> = Metadata::new(
+    "log suppression summary",
+    module_path!(),
+    Level::WARN,
+    Some(file!()),
+    Some(line!()),
+    Some(module_path!()),
+    FieldSet::new(
+        &["message", "suppressed", "callsite"],
+        Identifier(&SUMMARY_CALLSITE),
+    ),
+    Kind::EVENT,
+);
+
+impl Callsite for SummaryCallsite {
+    fn set_interest(&self, _: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        &SUMMARY_METADATA
+    }
+}
+
+/// Rate limits the events reaching the wrapped layer, usually the one
+/// writing logs
+///
+/// Every callsite at or above the configured level gets its own token
+/// bucket, so one noisy `error!` doesn't silence unrelated ones. When a
+/// callsite logs again after being limited, a `WARN` line first reports
+/// how many of its events were dropped ("suppressed 1234 similar errors").
+/// Layers outside the wrapper, such as OpenTelemetry export, still see
+/// every event.
+pub struct RateLimitLayer<L> {
+    inner: L,
+    limiter: LogRateLimiter,
+}
+
+impl<L> RateLimitLayer<L> {
+    /// Wrap `inner` with the given limits
+    pub fn new(inner: L, config: LogRateLimitConfig) -> Self {
+        Self {
+            inner,
+            limiter: LogRateLimiter::new(config),
+        }
+    }
+
+    /// Wrap `inner` with limits from [`LogRateLimitConfig::from_env`]
+    pub fn from_env(inner: L) -> Self {
+        Self::new(inner, LogRateLimitConfig::from_env())
+    }
+}
+
+/// Report dropped events to the inner layer
+fn emit_summary<S: Subscriber, L: Layer<S>>(
+    inner: &L,
+    suppressed: u64,
+    metadata: &Metadata<'_>,
+    ctx: Context<'_, S>,
+) {
+    let kind = match *metadata.level() {
+        Level::ERROR => "errors",
+        Level::WARN => "warnings",
+        _ => "events",
+    };
+    let message = format!("suppressed {} similar {}", suppressed, kind);
+    let callsite = format!(
+        "{}:{}",
+        metadata.file().unwrap_or(metadata.target()),
+        metadata.line().unwrap_or(0)
+    );
+
+    let fields = SUMMARY_METADATA.fields();
+    let (Some(message_field), Some(suppressed_field), Some(callsite_field)) = (
+        fields.field("message"),
+        fields.field("suppressed"),
+        fields.field("callsite"),
+    ) else {
+        return;
+    };
+    let message = tracing::field::display(message);
+    let callsite = tracing::field::display(callsite);
+    let values = [
+        (&message_field, Some(&message as &dyn Value)),
+        (&suppressed_field, Some(&suppressed as &dyn Value)),
+        (&callsite_field, Some(&callsite as &dyn Value)),
+    ];
+    let values = fields.value_set(&values);
+    inner.on_event(&Event::new(&SUMMARY_METADATA, &values), ctx);
+}
+
+impl<S: Subscriber, L: Layer<S>> Layer<S> for RateLimitLayer<L> {
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(suppressed) = self.limiter.check(event.metadata(), Instant::now()) else {
+            return;
+        };
+        if suppressed > 0 {
+            emit_summary(&self.inner, suppressed, event.metadata(), ctx.clone());
+        }
+        self.inner.on_event(event, ctx);
+    }
+
+    // Everything else goes straight to the inner layer
+
+    fn on_register_dispatch(&self, subscriber: &tracing::Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        self.inner.on_new_span(attrs, id, ctx);
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_record(
+        &self,
+        span: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: Context<'_, S>,
+    ) {
+        self.inner.on_record(span, values, ctx);
+    }
+
+    fn on_follows_from(
+        &self,
+        span: &tracing::span::Id,
+        follows: &tracing::span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        self.inner.on_follows_from(span, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_enter(&self, id: &tracing::span::Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &tracing::span::Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: tracing::span::Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &tracing::span::Id, new: &tracing::span::Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    #[doc(hidden)]
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else {
+            self.inner.downcast_raw(id)
+        }
+    }
+}
+
 /// Log a request start
 #[macro_export]
 macro_rules! log_request {
@@ -208,3 +576,118 @@ macro_rules! log_metric {
         );
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Collects the messages of the events it sees
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for Capture {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            struct Message(String);
+            impl tracing::field::Visit for Message {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    if field.name() == "message" {
+                        self.0 = format!("{:?}", value);
+                    }
+                }
+            }
+            let mut message = Message(String::new());
+            event.record(&mut message);
+            self.0.lock().unwrap().push(message.0);
+        }
+    }
+
+    #[test]
+    fn test_limiter_refills_and_samples() {
+        let limiter = LogRateLimiter::new(LogRateLimitConfig {
+            burst: 2,
+            per_second: 1.0,
+            sample_every: 3,
+            ..LogRateLimitConfig::default()
+        });
+        let metadata = &SUMMARY_METADATA;
+        let start = Instant::now();
+
+        assert_eq!(limiter.check(metadata, start), Some(0));
+        assert_eq!(limiter.check(metadata, start), Some(0));
+        assert_eq!(limiter.check(metadata, start), None);
+        assert_eq!(limiter.check(metadata, start), None);
+        // The third event over the limit is sampled
+        assert_eq!(limiter.check(metadata, start), Some(2));
+        assert_eq!(limiter.check(metadata, start), None);
+        assert_eq!(
+            limiter.check(metadata, start + Duration::from_secs(1)),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_layer_suppresses_storm_and_reports_summary() {
+        let capture = Capture::default();
+        let layer = RateLimitLayer::new(
+            capture.clone(),
+            LogRateLimitConfig {
+                burst: 3,
+                per_second: 10.0,
+                ..LogRateLimitConfig::default()
+            },
+        );
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let upstream_error = || error!("upstream unavailable");
+            for _ in 0..100 {
+                upstream_error();
+                info!("not limited");
+            }
+            std::thread::sleep(Duration::from_millis(200));
+            upstream_error();
+        });
+
+        let messages = capture.0.lock().unwrap();
+        let errors = messages
+            .iter()
+            .filter(|m| *m == "upstream unavailable")
+            .count();
+        assert_eq!(errors, 4);
+        assert_eq!(messages.iter().filter(|m| *m == "not limited").count(), 100);
+
+        // Once the callsite may log again, its dropped events are reported first
+        assert_eq!(messages[messages.len() - 2], "suppressed 97 similar errors");
+    }
+
+    #[test]
+    fn test_filter_handle_changes_level_at_runtime() {
+        let capture = Capture::default();
+        let (filter, handle) = LogFilterHandle::reloadable(EnvFilter::new("warn"));
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(capture.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            debug!("before");
+            assert_eq!(handle.set("debug").unwrap(), "warn");
+            debug!("after");
+            assert!(handle.set("info,[").is_err());
+            assert!(handle.set(" ").is_err());
+            assert_eq!(handle.reset().unwrap(), "debug");
+            debug!("reset");
+            assert_eq!(handle.current(), "warn");
+        });
+
+        let messages = capture.0.lock().unwrap();
+        assert!(messages.contains(&"after".to_string()));
+        assert!(!messages.contains(&"before".to_string()));
+        assert!(!messages.contains(&"reset".to_string()));
+    }
+}
@@ -0,0 +1,124 @@
+//! Populate a development database and Redis with fixture data
+//!
+//! ```bash
+//! seed --services 20 --consumers 200 --usage-records 50000
+//! seed --seed 7 --keys-out keys.json --skip-redis
+//! ```
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use marketplace_fixtures::{seed, Dataset, DatasetConfig};
+use sqlx::postgres::PgPoolOptions;
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Parser)]
+#[command(
+    name = "seed",
+    about = "Seed a dev database and Redis with marketplace fixtures"
+)]
+struct Cli {
+    #[arg(
+        long,
+        env = "DATABASE_URL",
+        default_value = "postgres://localhost/llm_marketplace"
+    )]
+    database_url: String,
+
+    #[arg(long, env = "REDIS_URL", default_value = "redis://localhost:6379")]
+    redis_url: String,
+
+    #[arg(long, default_value_t = 20)]
+    services: usize,
+
+    #[arg(long, default_value_t = 200)]
+    consumers: usize,
+
+    /// Services each consumer holds a key for
+    #[arg(long, default_value_t = 3)]
+    keys_per_consumer: usize,
+
+    #[arg(long, default_value_t = 50_000)]
+    usage_records: usize,
+
+    /// Days of usage history, ending at midnight UTC today
+    #[arg(long, default_value_t = 30)]
+    days: i64,
+
+    /// RNG seed; the same seed on the same day yields the same rows
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    /// Leave Redis quota counters untouched
+    #[arg(long)]
+    skip_redis: bool,
+
+    /// Write the plaintext API keys as JSON, for tests and load generators
+    #[arg(long)]
+    keys_out: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let started = Instant::now();
+
+    let dataset = Dataset::generate(&DatasetConfig {
+        services: cli.services,
+        consumers: cli.consumers,
+        keys_per_consumer: cli.keys_per_consumer,
+        usage_records: cli.usage_records,
+        days: cli.days,
+        seed: cli.seed,
+        ..DatasetConfig::default()
+    });
+    println!(
+        "Generated {} services, {} API keys, {} usage records, {} quotas",
+        dataset.services.len(),
+        dataset.api_keys.len(),
+        dataset.usage_records.len(),
+        dataset.quotas.len()
+    );
+
+    let db = PgPoolOptions::new()
+        .max_connections(4)
+        .connect(&cli.database_url)
+        .await
+        .context("Failed to connect to database")?;
+    let counts = seed::load_database(&db, &dataset).await?;
+    println!(
+        "Inserted {} services, {} API keys, {} usage records, {} quotas (existing rows skipped)",
+        counts.services, counts.api_keys, counts.usage_records, counts.quotas
+    );
+
+    if !cli.skip_redis {
+        let client = redis::Client::open(cli.redis_url.as_str()).context("Invalid Redis URL")?;
+        let mut redis = client
+            .get_connection_manager()
+            .await
+            .context("Failed to connect to Redis")?;
+        let quotas = seed::load_redis(&mut redis, &dataset).await?;
+        println!("Set {} Redis quota counters", quotas);
+    }
+
+    if let Some(path) = &cli.keys_out {
+        let keys: Vec<_> = dataset
+            .api_keys
+            .iter()
+            .map(|key| {
+                serde_json::json!({
+                    "key": key.key,
+                    "consumer_id": key.consumer_id,
+                    "service_id": key.service_id,
+                    "tier": key.tier,
+                })
+            })
+            .collect();
+        std::fs::write(path, serde_json::to_vec_pretty(&keys)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("Wrote {} API keys to {}", keys.len(), path.display());
+    }
+
+    println!("Done in {:.1}s", started.elapsed().as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Samplers shaping fixture data like production traffic
+
+use chrono::{DateTime, Duration, Timelike, Utc};
+use rand::Rng;
+
+/// Zipf-distributed index in `0..n`: a handful of consumers and services
+/// account for most of the traffic
+#[derive(Debug, Clone)]
+pub struct Zipf {
+    cumulative: Vec<f64>,
+}
+
+impl Zipf {
+    /// `exponent` around 1.0 matches typical API traffic; 0.0 is uniform
+    pub fn new(n: usize, exponent: f64) -> Self {
+        assert!(n > 0, "Zipf needs at least one element");
+
+        let mut total = 0.0;
+        let mut cumulative: Vec<f64> = (1..=n)
+            .map(|rank| {
+                total += 1.0 / (rank as f64).powf(exponent);
+                total
+            })
+            .collect();
+        for value in &mut cumulative {
+            *value /= total;
+        }
+
+        Self { cumulative }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let draw: f64 = rng.gen();
+        self.cumulative
+            .partition_point(|p| *p < draw)
+            .min(self.cumulative.len() - 1)
+    }
+}
+
+/// Standard normal sample (Box-Muller)
+fn standard_normal<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Log-normal sample around `median`; `sigma` sets how heavy the tail is
+pub fn log_normal<R: Rng>(rng: &mut R, median: f64, sigma: f64) -> f64 {
+    median * (sigma * standard_normal(rng)).exp()
+}
+
+/// Index picked with probability proportional to its weight
+pub fn weighted<R: Rng>(rng: &mut R, weights: &[f64]) -> usize {
+    let total: f64 = weights.iter().sum();
+    let mut draw = rng.gen::<f64>() * total;
+    for (index, weight) in weights.iter().enumerate() {
+        if draw < *weight {
+            return index;
+        }
+        draw -= weight;
+    }
+    weights.len() - 1
+}
+
+/// Relative traffic at an hour of the day (UTC), peaking mid-afternoon
+fn diurnal_weight(hour: u32) -> f64 {
+    let distance = hour as f64 - 14.0;
+    0.25 + 0.75 * (-(distance * distance) / 32.0).exp()
+}
+
+/// Timestamp in `[from, until)`, denser during working hours
+pub fn diurnal_timestamp<R: Rng>(
+    rng: &mut R,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let span = (until - from).num_milliseconds().max(1);
+    loop {
+        let at = from + Duration::milliseconds(rng.gen_range(0..span));
+        if rng.gen::<f64>() < diurnal_weight(at.hour()) {
+            return at;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_samplers_are_skewed() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let zipf = Zipf::new(100, 1.1);
+        let mut hits = [0usize; 100];
+        for _ in 0..10_000 {
+            hits[zipf.sample(&mut rng)] += 1;
+        }
+        assert!(hits[0] > hits[9] * 5);
+        assert!(hits[..10].iter().sum::<usize>() > 5_000);
+
+        let mut samples: Vec<f64> = (0..10_001)
+            .map(|_| log_normal(&mut rng, 400.0, 0.8))
+            .collect();
+        samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((samples[5_000] - 400.0).abs() < 40.0);
+
+        let from = DateTime::parse_from_rfc3339("2025-11-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        let until = from + Duration::days(7);
+        let afternoon = (0..2_000)
+            .map(|_| diurnal_timestamp(&mut rng, from, until))
+            .filter(|at| (12..18).contains(&at.hour()))
+            .count();
+        // A quarter of the day, but well over a quarter of the traffic
+        assert!(afternoon > 700);
+    }
+}
@@ -0,0 +1,461 @@
+//! Row factories matching the consumption service schema
+
+use chrono::{DateTime, Datelike, Duration, Utc};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::distributions::{diurnal_timestamp, log_normal, weighted, Zipf};
+
+const CATEGORIES: [&str; 5] = [
+    "chat",
+    "completion",
+    "embedding",
+    "summarization",
+    "translation",
+];
+const NAMES: [&str; 8] = [
+    "aurora", "basalt", "cirrus", "delta", "ember", "fjord", "granite", "helix",
+];
+
+/// Tiers with their share of API keys
+const TIERS: [(&str, f64); 3] = [("basic", 0.70), ("premium", 0.25), ("enterprise", 0.05)];
+
+/// Request outcomes with their share of usage records
+const STATUSES: [(&str, f64); 3] = [
+    ("success", 0.975),
+    ("error", 0.02),
+    ("marketplace_error", 0.005),
+];
+
+/// Client SDKs with their share of requests
+const SDKS: [(&str, &str, f64); 4] = [
+    ("python", "1.4.2", 0.45),
+    ("javascript", "1.0.0", 0.30),
+    ("python", "1.3.0", 0.15),
+    ("go-http-client", "1.1", 0.10),
+];
+
+/// A `services` row
+#[derive(Debug, Clone)]
+pub struct ServiceFixture {
+    pub id: Uuid,
+    pub registry_id: Uuid,
+    pub provider_id: Uuid,
+    pub name: String,
+    pub version: String,
+    pub category: String,
+    pub endpoint: String,
+    pub capabilities: Value,
+    pub pricing: Value,
+    pub sla: Value,
+    pub compliance: Value,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ServiceFixture {
+    /// Per-token rate for a tier
+    pub fn rate(&self, tier: &str) -> f64 {
+        self.pricing["rates"]
+            .as_array()
+            .and_then(|rates| rates.iter().find(|r| r["tier"] == tier))
+            .and_then(|r| r["rate"].as_f64())
+            .unwrap_or(0.0)
+    }
+}
+
+/// An `api_keys` row, with the plaintext key for use by tests and load
+/// generators
+#[derive(Debug, Clone)]
+pub struct ApiKeyFixture {
+    pub id: Uuid,
+    pub key: String,
+    /// SHA-256 of `key`, unique per key
+    pub key_hash: String,
+    pub consumer_id: Uuid,
+    pub service_id: Uuid,
+    pub tier: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A `usage_records` row
+#[derive(Debug, Clone)]
+pub struct UsageRecordFixture {
+    pub id: Uuid,
+    pub request_id: Uuid,
+    pub service_id: Uuid,
+    pub consumer_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub duration_ms: i32,
+    pub usage: Value,
+    pub cost: Value,
+    pub status: String,
+    pub error: Option<Value>,
+    pub metadata: Value,
+}
+
+impl UsageRecordFixture {
+    pub fn total_tokens(&self) -> i64 {
+        self.usage["total_tokens"].as_i64().unwrap_or(0)
+    }
+}
+
+/// A `quota_usage` row, also loaded into Redis as `quota:{consumer}:{service}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaFixture {
+    pub consumer_id: Uuid,
+    pub service_id: Uuid,
+    /// `YYYY-MM`
+    pub month: String,
+    pub used_tokens: i64,
+}
+
+/// Deterministic row factory; the same seed and anchor yield the same rows
+pub struct Factory {
+    rng: StdRng,
+    sequence: usize,
+    /// Creation times are drawn from before this instant
+    anchor: DateTime<Utc>,
+}
+
+impl Factory {
+    pub fn new(seed: u64) -> Self {
+        Self::anchored(seed, Utc::now())
+    }
+
+    pub fn anchored(seed: u64, anchor: DateTime<Utc>) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            sequence: 0,
+            anchor,
+        }
+    }
+
+    fn uuid(&mut self) -> Uuid {
+        uuid::Builder::from_random_bytes(self.rng.gen()).into_uuid()
+    }
+
+    fn recent(&mut self, days: i64) -> DateTime<Utc> {
+        self.anchor - Duration::seconds(self.rng.gen_range(1..=days.max(1) * 86_400))
+    }
+
+    pub fn consumer_id(&mut self) -> Uuid {
+        self.uuid()
+    }
+
+    /// An active per-token service
+    pub fn service(&mut self) -> ServiceFixture {
+        self.sequence += 1;
+        let category = *CATEGORIES.choose(&mut self.rng).unwrap();
+        let name = format!(
+            "{}-{}-{}",
+            NAMES.choose(&mut self.rng).unwrap(),
+            category,
+            self.sequence
+        );
+
+        // Basic per-token rate between $0.20 and $20 per million tokens
+        let basic_rate = log_normal(&mut self.rng, 2.0, 1.0).clamp(0.2, 20.0) / 1_000_000.0;
+
+        ServiceFixture {
+            id: self.uuid(),
+            registry_id: self.uuid(),
+            provider_id: self.uuid(),
+            version: format!("1.{}.0", self.rng.gen_range(0..5)),
+            endpoint: format!("http://fixtures.local/{}/v1", name),
+            name,
+            category: category.to_string(),
+            capabilities: json!({"streaming": self.rng.gen_bool(0.6), "max_context": 8192}),
+            pricing: json!({
+                "model": "per-token",
+                "rates": [
+                    {"tier": "basic", "rate": basic_rate, "unit": "token"},
+                    {"tier": "premium", "rate": basic_rate * 0.8, "unit": "token"},
+                    {"tier": "enterprise", "rate": basic_rate * 0.6, "unit": "token"},
+                ],
+            }),
+            sla: json!({
+                "availability": *[99.0, 99.5, 99.9].choose(&mut self.rng).unwrap(),
+                "max_latency_ms": *[1000, 2000, 5000].choose(&mut self.rng).unwrap(),
+                "timeout_ms": 30000,
+            }),
+            compliance: json!({"gdpr": true, "soc2": self.rng.gen_bool(0.5)}),
+            status: "active".to_string(),
+            created_at: self.recent(365),
+        }
+    }
+
+    /// A key for `consumer_id` on `service`, on a tier drawn from the usual mix
+    pub fn api_key(&mut self, consumer_id: Uuid, service: &ServiceFixture) -> ApiKeyFixture {
+        let weights: Vec<f64> = TIERS.iter().map(|(_, share)| *share).collect();
+        let tier = TIERS[weighted(&mut self.rng, &weights)].0;
+        self.api_key_on_tier(consumer_id, service, tier)
+    }
+
+    pub fn api_key_on_tier(
+        &mut self,
+        consumer_id: Uuid,
+        service: &ServiceFixture,
+        tier: &str,
+    ) -> ApiKeyFixture {
+        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+        let secret: String = (0..48)
+            .map(|_| CHARSET[self.rng.gen_range(0..CHARSET.len())] as char)
+            .collect();
+        let key = format!("llm_mk_{}", secret);
+
+        ApiKeyFixture {
+            id: self.uuid(),
+            key_hash: hex::encode(Sha256::digest(key.as_bytes())),
+            key,
+            consumer_id,
+            service_id: service.id,
+            tier: tier.to_string(),
+            created_at: self.recent(90),
+        }
+    }
+
+    /// A request made with `key` at `at`, with log-normal token counts and
+    /// latency, priced at the key's tier rate
+    pub fn usage_record(
+        &mut self,
+        key: &ApiKeyFixture,
+        service: &ServiceFixture,
+        at: DateTime<Utc>,
+    ) -> UsageRecordFixture {
+        let weights: Vec<f64> = STATUSES.iter().map(|(_, share)| *share).collect();
+        let status = STATUSES[weighted(&mut self.rng, &weights)].0;
+
+        let (prompt_tokens, completion_tokens) = if status == "success" {
+            (
+                log_normal(&mut self.rng, 400.0, 0.9).clamp(1.0, 32_000.0) as u32,
+                log_normal(&mut self.rng, 250.0, 1.0).clamp(1.0, 8_000.0) as u32,
+            )
+        } else {
+            (0, 0)
+        };
+        let total_tokens = prompt_tokens + completion_tokens;
+        let rate = service.rate(&key.tier);
+
+        let error = match status {
+            "error" => Some(json!({"code": "UPSTREAM_ERROR", "message": "Upstream returned 502"})),
+            "marketplace_error" => Some(json!({"code": "INTERNAL", "message": "Routing failed"})),
+            _ => None,
+        };
+
+        let sdk_weights: Vec<f64> = SDKS.iter().map(|(_, _, share)| *share).collect();
+        let (sdk, sdk_version, _) = SDKS[weighted(&mut self.rng, &sdk_weights)];
+
+        UsageRecordFixture {
+            id: self.uuid(),
+            request_id: self.uuid(),
+            service_id: service.id,
+            consumer_id: key.consumer_id,
+            timestamp: at,
+            duration_ms: log_normal(&mut self.rng, 450.0, 0.6).clamp(5.0, 60_000.0) as i32,
+            usage: json!({
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": total_tokens,
+            }),
+            cost: json!({
+                "amount": total_tokens as f64 * rate,
+                "currency": "USD",
+                "breakdown": {"model": "per-token", "rate": rate, "tokens": total_tokens},
+            }),
+            status: status.to_string(),
+            error,
+            metadata: json!({
+                "client": {
+                    "sdk": sdk,
+                    "sdk_version": sdk_version,
+                    "user_agent": format!("{}/{}", sdk, sdk_version),
+                },
+                "fixture": true,
+            }),
+        }
+    }
+}
+
+/// Volumes for a generated dataset
+#[derive(Debug, Clone)]
+pub struct DatasetConfig {
+    pub services: usize,
+    pub consumers: usize,
+    /// Services each consumer holds a key for
+    pub keys_per_consumer: usize,
+    pub usage_records: usize,
+    /// Usage records are spread over this many days before `until`
+    pub days: i64,
+    pub until: DateTime<Utc>,
+    pub seed: u64,
+}
+
+impl Default for DatasetConfig {
+    fn default() -> Self {
+        Self {
+            services: 20,
+            consumers: 200,
+            keys_per_consumer: 3,
+            usage_records: 50_000,
+            days: 30,
+            // Midnight UTC, so re-seeding on the same day yields the same rows
+            until: Utc::now()
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .expect("valid midnight")
+                .and_utc(),
+            seed: 42,
+        }
+    }
+}
+
+/// A consistent set of services, keys, usage and the quotas that usage implies
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub services: Vec<ServiceFixture>,
+    pub api_keys: Vec<ApiKeyFixture>,
+    pub usage_records: Vec<UsageRecordFixture>,
+    /// Current-month token totals per consumer and service
+    pub quotas: Vec<QuotaFixture>,
+}
+
+impl Dataset {
+    pub fn generate(config: &DatasetConfig) -> Self {
+        let mut factory = Factory::anchored(config.seed, config.until);
+
+        let services: Vec<ServiceFixture> = (0..config.services.max(1))
+            .map(|_| factory.service())
+            .collect();
+
+        // Popular services get more consumers
+        let service_popularity = Zipf::new(services.len(), 0.8);
+        let mut api_keys = Vec::new();
+        for _ in 0..config.consumers.max(1) {
+            let consumer_id = factory.consumer_id();
+            let wanted = config.keys_per_consumer.clamp(1, services.len());
+            let mut chosen: Vec<usize> = Vec::with_capacity(wanted);
+            while chosen.len() < wanted {
+                let index = service_popularity.sample(&mut factory.rng);
+                if !chosen.contains(&index) {
+                    chosen.push(index);
+                }
+            }
+            for index in chosen {
+                api_keys.push(factory.api_key(consumer_id, &services[index]));
+            }
+        }
+
+        // A few heavy consumers send most requests
+        let key_activity = Zipf::new(api_keys.len(), 1.1);
+        let services_by_id: HashMap<Uuid, &ServiceFixture> =
+            services.iter().map(|s| (s.id, s)).collect();
+        let until = config.until;
+        let from = until - Duration::days(config.days.max(1));
+
+        let usage_records: Vec<UsageRecordFixture> = (0..config.usage_records)
+            .map(|_| {
+                let key = &api_keys[key_activity.sample(&mut factory.rng)];
+                let at = diurnal_timestamp(&mut factory.rng, from, until);
+                factory.usage_record(key, services_by_id[&key.service_id], at)
+            })
+            .collect();
+
+        let quotas = quotas_for(&usage_records, Utc::now());
+
+        Self {
+            services,
+            api_keys,
+            usage_records,
+            quotas,
+        }
+    }
+}
+
+/// Successful tokens per consumer and service in the month containing `now`
+pub fn quotas_for(records: &[UsageRecordFixture], now: DateTime<Utc>) -> Vec<QuotaFixture> {
+    let month = format!("{}-{:02}", now.year(), now.month());
+    let mut totals: HashMap<(Uuid, Uuid), i64> = HashMap::new();
+    for record in records {
+        if record.status == "success"
+            && record.timestamp.year() == now.year()
+            && record.timestamp.month() == now.month()
+        {
+            *totals
+                .entry((record.consumer_id, record.service_id))
+                .or_default() += record.total_tokens();
+        }
+    }
+
+    let mut quotas: Vec<QuotaFixture> = totals
+        .into_iter()
+        .map(|((consumer_id, service_id), used_tokens)| QuotaFixture {
+            consumer_id,
+            service_id,
+            month: month.clone(),
+            used_tokens,
+        })
+        .collect();
+    quotas.sort_by_key(|q| (q.consumer_id, q.service_id));
+    quotas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_is_deterministic_and_consistent() {
+        let config = DatasetConfig {
+            services: 4,
+            consumers: 30,
+            keys_per_consumer: 2,
+            usage_records: 2_000,
+            days: 10,
+            seed: 7,
+            ..DatasetConfig::default()
+        };
+        let first = Dataset::generate(&config);
+        let second = Dataset::generate(&config);
+
+        assert_eq!(first.api_keys.len(), 60);
+        assert_eq!(
+            first.api_keys.iter().map(|k| &k.key).collect::<Vec<_>>(),
+            second.api_keys.iter().map(|k| &k.key).collect::<Vec<_>>()
+        );
+        assert_eq!(first.usage_records[0].id, second.usage_records[0].id);
+        assert_eq!(
+            first.usage_records[0].timestamp,
+            second.usage_records[0].timestamp
+        );
+
+        // Every record belongs to a key's consumer and service
+        for record in &first.usage_records {
+            assert!(first
+                .api_keys
+                .iter()
+                .any(|k| k.consumer_id == record.consumer_id && k.service_id == record.service_id));
+        }
+
+        let successes = first
+            .usage_records
+            .iter()
+            .filter(|r| r.status == "success")
+            .count();
+        assert!(successes > 1_900);
+
+        // Quotas add up to this month's successful tokens
+        let month_tokens: i64 = quotas_for(&first.usage_records, Utc::now())
+            .iter()
+            .map(|q| q.used_tokens)
+            .sum();
+        assert_eq!(
+            month_tokens,
+            first.quotas.iter().map(|q| q.used_tokens).sum::<i64>()
+        );
+        assert!(first.quotas.iter().all(|q| q.used_tokens > 0));
+    }
+}
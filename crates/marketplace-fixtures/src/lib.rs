@@ -0,0 +1,41 @@
+//! # marketplace-fixtures
+//!
+//! Factories and seed data for the LLM Marketplace, shared by integration
+//! tests, benchmarks and local development.
+//!
+//! - **Factories**: deterministic `Service`, `ApiKey`, `UsageRecord` and quota
+//!   rows matching the consumption service schema
+//! - **Distributions**: Zipf-skewed popularity, log-normal token counts and
+//!   latencies, and diurnal traffic so seeded data looks like production
+//! - **Seeding** (`seed` feature): bulk loaders for PostgreSQL and Redis, and
+//!   the `seed` CLI
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use marketplace_fixtures::{Dataset, DatasetConfig};
+//!
+//! let dataset = Dataset::generate(&DatasetConfig {
+//!     services: 5,
+//!     consumers: 20,
+//!     usage_records: 1_000,
+//!     ..DatasetConfig::default()
+//! });
+//!
+//! assert_eq!(dataset.services.len(), 5);
+//! assert_eq!(dataset.usage_records.len(), 1_000);
+//! ```
+//!
+//! The same `seed` and `until` produce the same rows, so re-seeding is
+//! idempotent.
+
+pub mod distributions;
+pub mod factories;
+
+#[cfg(feature = "seed")]
+pub mod seed;
+
+pub use factories::{
+    ApiKeyFixture, Dataset, DatasetConfig, Factory, QuotaFixture, ServiceFixture,
+    UsageRecordFixture,
+};
@@ -0,0 +1,192 @@
+//! Loaders writing a [`Dataset`] into PostgreSQL and Redis
+//!
+//! Rows are inserted with `ON CONFLICT DO NOTHING`, so seeding the same
+//! dataset twice leaves the database unchanged.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use redis::aio::ConnectionManager;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::collections::BTreeSet;
+
+use crate::factories::Dataset;
+
+/// Rows per INSERT statement, well below PostgreSQL's bind parameter limit
+const BATCH_SIZE: usize = 1000;
+
+/// Rows written by [`load_database`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SeedCounts {
+    pub services: u64,
+    pub api_keys: u64,
+    pub usage_records: u64,
+    pub quotas: u64,
+}
+
+/// Insert every row of the dataset, creating missing usage partitions first
+pub async fn load_database(db: &PgPool, dataset: &Dataset) -> Result<SeedCounts> {
+    let mut counts = SeedCounts::default();
+
+    for batch in dataset.services.chunks(BATCH_SIZE) {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "INSERT INTO services (id, registry_id, name, version, provider_id, category, \
+             capabilities, endpoint, pricing, sla, compliance, status, created_at, published_at) ",
+        );
+        query.push_values(batch, |mut row, service| {
+            row.push_bind(service.id)
+                .push_bind(service.registry_id)
+                .push_bind(&service.name)
+                .push_bind(&service.version)
+                .push_bind(service.provider_id)
+                .push_bind(&service.category)
+                .push_bind(&service.capabilities)
+                .push_bind(&service.endpoint)
+                .push_bind(&service.pricing)
+                .push_bind(&service.sla)
+                .push_bind(&service.compliance)
+                .push_bind(&service.status)
+                .push_bind(service.created_at)
+                .push_bind(service.created_at);
+        });
+        query.push(" ON CONFLICT DO NOTHING");
+        counts.services += query
+            .build()
+            .execute(db)
+            .await
+            .context("Failed to insert services")?
+            .rows_affected();
+    }
+
+    for batch in dataset.api_keys.chunks(BATCH_SIZE) {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "INSERT INTO api_keys (id, key_hash, consumer_id, service_id, tier, created_at, metadata) ",
+        );
+        query.push_values(batch, |mut row, key| {
+            row.push_bind(key.id)
+                .push_bind(&key.key_hash)
+                .push_bind(key.consumer_id)
+                .push_bind(key.service_id)
+                .push_bind(&key.tier)
+                .push_bind(key.created_at)
+                .push_bind(serde_json::json!({"fixture": true}));
+        });
+        query.push(" ON CONFLICT DO NOTHING");
+        counts.api_keys += query
+            .build()
+            .execute(db)
+            .await
+            .context("Failed to insert API keys")?
+            .rows_affected();
+    }
+
+    ensure_usage_partitions(db, dataset.usage_records.iter().map(|r| r.timestamp)).await?;
+
+    for batch in dataset.usage_records.chunks(BATCH_SIZE) {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "INSERT INTO usage_records (id, request_id, service_id, consumer_id, timestamp, \
+             duration_ms, usage, cost, status, error, metadata) ",
+        );
+        query.push_values(batch, |mut row, record| {
+            row.push_bind(record.id)
+                .push_bind(record.request_id)
+                .push_bind(record.service_id)
+                .push_bind(record.consumer_id)
+                .push_bind(record.timestamp)
+                .push_bind(record.duration_ms)
+                .push_bind(&record.usage)
+                .push_bind(&record.cost)
+                .push_bind(&record.status)
+                .push_bind(&record.error)
+                .push_bind(&record.metadata);
+        });
+        query.push(" ON CONFLICT DO NOTHING");
+        counts.usage_records += query
+            .build()
+            .execute(db)
+            .await
+            .context("Failed to insert usage records")?
+            .rows_affected();
+    }
+
+    for batch in dataset.quotas.chunks(BATCH_SIZE) {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "INSERT INTO quota_usage (consumer_id, service_id, month, used_tokens, updated_at) ",
+        );
+        query.push_values(batch, |mut row, quota| {
+            row.push_bind(quota.consumer_id)
+                .push_bind(quota.service_id)
+                .push_bind(&quota.month)
+                .push_bind(quota.used_tokens)
+                .push("NOW()");
+        });
+        query.push(
+            " ON CONFLICT (consumer_id, service_id, month) \
+             DO UPDATE SET used_tokens = EXCLUDED.used_tokens, updated_at = NOW()",
+        );
+        counts.quotas += query
+            .build()
+            .execute(db)
+            .await
+            .context("Failed to insert quota usage")?
+            .rows_affected();
+    }
+
+    Ok(counts)
+}
+
+/// Create the monthly `usage_records` partitions covering `timestamps`
+async fn ensure_usage_partitions(
+    db: &PgPool,
+    timestamps: impl Iterator<Item = DateTime<Utc>>,
+) -> Result<()> {
+    let months: BTreeSet<NaiveDate> = timestamps
+        .filter_map(|at| NaiveDate::from_ymd_opt(at.year(), at.month(), 1))
+        .collect();
+
+    for month in months {
+        let next = month
+            .checked_add_months(Months::new(1))
+            .context("Invalid partition month")?;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS usage_records_{} PARTITION OF usage_records \
+             FOR VALUES FROM ('{}') TO ('{}')",
+            month.format("%Y_%m"),
+            month,
+            next
+        ))
+        .execute(db)
+        .await
+        .with_context(|| format!("Failed to create usage partition for {}", month))?;
+    }
+
+    Ok(())
+}
+
+/// Set `quota:{consumer}:{service}` counters, expiring at the next monthly
+/// reset like the consumption service's own keys
+pub async fn load_redis(redis: &mut ConnectionManager, dataset: &Dataset) -> Result<u64> {
+    let now = Utc::now();
+    let reset = NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
+        .and_then(|first| first.checked_add_months(Months::new(1)))
+        .and_then(|next| next.and_hms_opt(0, 0, 0))
+        .context("Invalid quota reset time")?
+        .and_utc();
+    let ttl = (reset - now).num_seconds().max(1);
+
+    for batch in dataset.quotas.chunks(BATCH_SIZE) {
+        let mut pipe = redis::pipe();
+        for quota in batch {
+            pipe.cmd("SET")
+                .arg(format!("quota:{}:{}", quota.consumer_id, quota.service_id))
+                .arg(quota.used_tokens)
+                .arg("EX")
+                .arg(ttl)
+                .ignore();
+        }
+        pipe.query_async::<_, ()>(redis)
+            .await
+            .context("Failed to set quota counters in Redis")?;
+    }
+
+    Ok(dataset.quotas.len() as u64)
+}
@@ -0,0 +1,388 @@
+//! Admission Overhead Benchmark Adapter
+//!
+//! Measures the latency the consumption service adds in front of a provider:
+//! policy validation, shield scan, rate limiting and quota checks. Policy,
+//! shield and provider upstreams are local mock HTTP servers, so the result
+//! is the marketplace's own overhead rather than network or model latency.
+
+use crate::adapters::{BenchTarget, TargetConfig};
+use crate::benchmarks::histogram::{percentile, Histogram};
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Payload sizes (bytes) exercised by the benchmark
+const PAYLOAD_SIZES: [usize; 3] = [1024, 16 * 1024, 128 * 1024];
+
+/// Requests per payload size
+const ITERATIONS_PER_SIZE: usize = 50;
+
+/// Measured requests per payload size, with no warmup
+const DEFAULT_CONFIG: TargetConfig = TargetConfig::new(0, ITERATIONS_PER_SIZE);
+
+/// Default published overhead budget (p95, milliseconds)
+const DEFAULT_OVERHEAD_BUDGET_MS: f64 = 10.0;
+
+/// Mock upstream answering every request with a fixed JSON body
+pub(crate) struct MockUpstream {
+    pub(crate) addr: SocketAddr,
+}
+
+impl MockUpstream {
+    pub(crate) fn start(response_body: &'static str) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").context("Failed to bind mock upstream")?;
+        let addr = listener.local_addr()?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                thread::spawn(move || {
+                    if let Err(e) = serve_connection(stream, response_body) {
+                        log::debug!("Mock upstream connection closed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { addr })
+    }
+}
+
+/// Serve keep-alive requests on one connection until the client hangs up
+fn serve_connection(stream: TcpStream, response_body: &str) -> std::io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    loop {
+        let Some(_) = read_message(&mut reader)? else {
+            return Ok(());
+        };
+
+        write!(
+            writer,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            response_body.len(),
+            response_body
+        )?;
+        writer.flush()?;
+    }
+}
+
+/// Read one HTTP message, returning its body, or `None` on EOF
+fn read_message(reader: &mut impl BufRead) -> std::io::Result<Option<Vec<u8>>> {
+    let mut content_length = 0;
+    let mut line = String::new();
+    let mut first = true;
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line == "\r\n" && !first {
+            break;
+        }
+        first = false;
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+/// Blocking keep-alive HTTP client for a single mock upstream
+struct UpstreamClient {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl UpstreamClient {
+    fn connect(addr: SocketAddr) -> Result<Self> {
+        let stream = TcpStream::connect(addr).context("Failed to connect to mock upstream")?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+        })
+    }
+
+    fn post(&mut self, path: &str, body: &[u8]) -> Result<Vec<u8>> {
+        write!(
+            self.writer,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            path,
+            body.len()
+        )?;
+        self.writer.write_all(body)?;
+        self.writer.flush()?;
+
+        read_message(&mut self.reader)?.context("Mock upstream closed connection")
+    }
+}
+
+/// In-process token bucket standing in for the Redis rate limiter
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(capacity: f64, refill_per_sec: f64) -> Self {
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec,
+            last_refill: Instant::now(),
+        }
+    }
+
+    pub(crate) fn try_acquire(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Benchmark adapter for the consumption admission pipeline overhead
+pub struct AdmissionOverheadBenchmark {
+    budget_ms: f64,
+}
+
+impl AdmissionOverheadBenchmark {
+    pub fn new() -> Self {
+        let budget_ms = std::env::var("ADMISSION_OVERHEAD_BUDGET_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_OVERHEAD_BUDGET_MS);
+
+        Self { budget_ms }
+    }
+
+    fn build_payload(size: usize) -> Vec<u8> {
+        let prompt = "a".repeat(size.saturating_sub(64));
+        serde_json::to_vec(&serde_json::json!({
+            "prompt": prompt,
+            "max_tokens": 16,
+            "temperature": 0.7,
+        }))
+        .expect("payload serializes")
+    }
+
+    fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        let policy = MockUpstream::start(r#"{"allowed":true,"violations":[]}"#)?;
+        let shield = MockUpstream::start(r#"{"safe":true,"findings":[]}"#)?;
+        let provider = MockUpstream::start(
+            r#"{"text":"ok","usage":{"prompt_tokens":8,"completion_tokens":2,"total_tokens":10}}"#,
+        )?;
+
+        let mut policy_client = UpstreamClient::connect(policy.addr)?;
+        let mut shield_client = UpstreamClient::connect(shield.addr)?;
+        let mut provider_client = UpstreamClient::connect(provider.addr)?;
+
+        let mut rate_limiter = TokenBucket::new(1_000_000.0, 1_000_000.0);
+        let quota_limit: u64 = u64::MAX;
+        let mut quota_used: u64 = 0;
+
+        let mut metrics = HashMap::new();
+        let mut all_overheads = Vec::new();
+        let mut error_count = 0;
+
+        for size in PAYLOAD_SIZES {
+            let payload = Self::build_payload(size);
+            let mut overheads = Vec::with_capacity(config.measured_iterations);
+
+            log::info!("Measuring admission overhead for {} byte payloads...", size);
+
+            // Each payload size gets the full iteration and time budget so a
+            // capped run still reports every size
+            let mut started = Instant::now();
+            for i in 0..config.warmup_iterations + config.measured_iterations {
+                if i == config.warmup_iterations {
+                    started = Instant::now();
+                } else if i > config.warmup_iterations && config.expired(started) {
+                    break;
+                }
+                let warmup = i < config.warmup_iterations;
+
+                // Baseline: provider call alone
+                let start = Instant::now();
+                if let Err(e) = provider_client.post("/v1/completions", &payload) {
+                    error_count += 1;
+                    log::warn!("baseline iteration {} failed: {}", i, e);
+                    continue;
+                }
+                let direct_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+                // Full admission pipeline followed by the provider call
+                let start = Instant::now();
+                let admitted = (|| -> Result<()> {
+                    policy_client.post("/api/v1/validate", &payload)?;
+                    shield_client.post("/api/v1/scan", &payload)?;
+                    anyhow::ensure!(rate_limiter.try_acquire(), "rate limited");
+                    anyhow::ensure!(quota_used < quota_limit, "quota exceeded");
+                    provider_client.post("/v1/completions", &payload)?;
+                    quota_used += 10;
+                    Ok(())
+                })();
+                let pipeline_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+                match admitted {
+                    Ok(()) if warmup => {}
+                    Ok(()) => overheads.push((pipeline_ms - direct_ms).max(0.0)),
+                    Err(e) => {
+                        error_count += 1;
+                        log::warn!("pipeline iteration {} failed: {}", i, e);
+                    }
+                }
+            }
+
+            overheads.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let label = format!("{}kb", size / 1024);
+            metrics.insert(
+                format!("overhead_ms_p50_{}", label),
+                percentile(&overheads, 50),
+            );
+            metrics.insert(
+                format!("overhead_ms_p95_{}", label),
+                percentile(&overheads, 95),
+            );
+            metrics.insert(
+                format!("overhead_ms_p99_{}", label),
+                percentile(&overheads, 99),
+            );
+
+            all_overheads.extend(overheads);
+        }
+
+        all_overheads.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let p95 = percentile(&all_overheads, 95);
+        let operation_count = all_overheads.len();
+
+        let error_rate = if operation_count + error_count > 0 {
+            (error_count as f64) / ((operation_count + error_count) as f64)
+        } else {
+            0.0
+        };
+
+        metrics.insert(
+            "overhead_ms_p50".to_string(),
+            percentile(&all_overheads, 50),
+        );
+        metrics.insert("overhead_ms_p95".to_string(), p95);
+        metrics.insert(
+            "overhead_ms_p99".to_string(),
+            percentile(&all_overheads, 99),
+        );
+        metrics.insert("overhead_budget_ms".to_string(), self.budget_ms);
+        metrics.insert(
+            "within_budget".to_string(),
+            if p95 <= self.budget_ms { 1.0 } else { 0.0 },
+        );
+        metrics.insert("operation_count".to_string(), operation_count as f64);
+        metrics.insert("error_rate".to_string(), error_rate);
+
+        if p95 > self.budget_ms {
+            log::warn!(
+                "Admission overhead p95 {:.2}ms exceeds budget of {:.2}ms",
+                p95,
+                self.budget_ms
+            );
+        }
+
+        let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+        result.add_histogram(
+            "overhead_ms".to_string(),
+            Histogram::from_samples(&all_overheads),
+        );
+
+        result.add_metadata("wrapper_type".to_string(), "mock_http".to_string());
+        result.add_metadata("test_suite".to_string(), "admission_overhead".to_string());
+        result.add_metadata(
+            "payload_sizes".to_string(),
+            PAYLOAD_SIZES
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        result.add_metadata("iterations".to_string(), operation_count.to_string());
+
+        if let Ok(hostname) = hostname::get() {
+            if let Some(hostname_str) = hostname.to_str() {
+                result.add_metadata("hostname".to_string(), hostname_str.to_string());
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Default for AdmissionOverheadBenchmark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BenchTarget for AdmissionOverheadBenchmark {
+    fn id(&self) -> &str {
+        "marketplace_admission_overhead"
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace", "consumption"]
+    }
+
+    fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&DEFAULT_CONFIG)
+    }
+
+    fn config(&self) -> Option<TargetConfig> {
+        Some(DEFAULT_CONFIG)
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        log::info!("Running admission overhead benchmark");
+        self.execute_benchmark_suite(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_id() {
+        let bench = AdmissionOverheadBenchmark::new();
+        assert_eq!(bench.id(), "marketplace_admission_overhead");
+    }
+
+    #[test]
+    fn test_reports_overhead_percentiles() {
+        let result = AdmissionOverheadBenchmark::new().run().unwrap();
+        assert!(result.get_metric("overhead_ms_p95").is_some());
+        assert!(result.get_metric("overhead_ms_p99_128kb").is_some());
+        assert_eq!(result.get_metric("error_rate"), Some(0.0));
+    }
+}
@@ -0,0 +1,505 @@
+//! Consumption API Benchmark Adapter
+//!
+//! Drives a running consumption service's `POST /api/v1/consume/:serviceId`
+//! endpoint over HTTP, so latencies are end to end: auth, admission, the
+//! provider call and the response, as a consumer sees them. Concurrent
+//! workers issue a configurable mix of request sizes and the benchmark
+//! reports latency percentiles overall and per request size, throughput, and
+//! how many requests failed or were throttled.
+//!
+//! Without a configured URL the endpoint is a local mock HTTP server, which
+//! measures the harness itself.
+//!
+//! Requests go through async reqwest, so this is an [`AsyncBenchTarget`];
+//! the registry runs it through [`BlockingTarget`](crate::adapters::BlockingTarget).
+
+use crate::adapters::admission_overhead::MockUpstream;
+use crate::adapters::{AsyncBenchTarget, Dependency, TargetConfig};
+use crate::benchmarks::histogram::{percentile, Histogram};
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Default timed requests per run
+const DEFAULT_ITERATIONS: usize = 200;
+
+/// Untimed requests before measuring
+const WARMUP_ITERATIONS: usize = 10;
+
+/// Default requests in flight at once
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Request mix used when none is configured, as `profile:weight` pairs
+const DEFAULT_MIX: &str = "small:6,medium:3,large:1";
+
+/// Service id sent to the mock endpoint
+const MOCK_SERVICE_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+/// Completion returned by the mock endpoint
+const MOCK_RESPONSE: &str = r#"{"request_id":"00000000-0000-0000-0000-000000000000","response":{"text":"ok"},"usage":{"prompt_tokens":64,"completion_tokens":16,"total_tokens":80},"cost":0.0001}"#;
+
+/// Size of one kind of consume request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestProfile {
+    pub name: &'static str,
+    pub prompt_bytes: usize,
+    pub max_tokens: u32,
+}
+
+/// Request sizes a mix can draw from
+pub const REQUEST_PROFILES: [RequestProfile; 3] = [
+    RequestProfile {
+        name: "small",
+        prompt_bytes: 256,
+        max_tokens: 64,
+    },
+    RequestProfile {
+        name: "medium",
+        prompt_bytes: 2048,
+        max_tokens: 256,
+    },
+    RequestProfile {
+        name: "large",
+        prompt_bytes: 16384,
+        max_tokens: 1024,
+    },
+];
+
+/// Parse a request mix like `small:6,medium:3,large:1`
+///
+/// A profile without a weight has weight 1. Returns each profile with its
+/// weight, in the order given.
+pub fn parse_mix(spec: &str) -> Result<Vec<(RequestProfile, usize)>> {
+    let mut mix = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, weight) = match entry.split_once(':') {
+            Some((name, weight)) => (
+                name.trim(),
+                weight
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Invalid weight in request mix entry '{}'", entry))?,
+            ),
+            None => (entry, 1),
+        };
+        let Some(profile) = REQUEST_PROFILES.iter().find(|p| p.name == name) else {
+            bail!(
+                "Unknown request profile '{}'; expected one of small, medium, large",
+                name
+            );
+        };
+        if weight > 0 {
+            mix.push((*profile, weight));
+        }
+    }
+
+    if mix.is_empty() {
+        bail!("Request mix '{}' has no requests", spec);
+    }
+    Ok(mix)
+}
+
+/// Profile index for each slot of a cycle through the mix, with the
+/// profiles interleaved in proportion to their weights rather than in runs
+fn schedule(mix: &[(RequestProfile, usize)]) -> Vec<usize> {
+    let total: usize = mix.iter().map(|(_, weight)| weight).sum();
+    let mut credit = vec![0i64; mix.len()];
+    (0..total)
+        .map(|_| {
+            for (i, (_, weight)) in mix.iter().enumerate() {
+                credit[i] += *weight as i64;
+            }
+            // Most credit goes next, the earliest profile on ties
+            let next = (0..mix.len())
+                .max_by_key(|i| (credit[*i], std::cmp::Reverse(*i)))
+                .unwrap();
+            credit[next] -= total as i64;
+            next
+        })
+        .collect()
+}
+
+fn prompt(bytes: usize) -> String {
+    const TEXT: &str = "Summarize the following support ticket in two sentences. ";
+    TEXT.repeat(bytes / TEXT.len() + 1)[..bytes].to_string()
+}
+
+/// Outcome of one request
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    profile: usize,
+    /// End-to-end latency in ms, for 2xx responses
+    latency_ms: Option<f64>,
+    throttled: bool,
+}
+
+/// Benchmark adapter for the consumption service's consume endpoint
+pub struct ConsumptionApiBenchmark {
+    base_url: Option<String>,
+    service_id: Option<String>,
+    api_key: Option<String>,
+    mix: String,
+    concurrency: usize,
+    iterations: usize,
+}
+
+impl ConsumptionApiBenchmark {
+    pub fn new() -> Self {
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let positive = |name: &str| env(name).and_then(|v| v.parse().ok()).filter(|n| *n > 0);
+
+        let mut bench = Self::with_config(
+            env("CONSUMPTION_BENCH_URL"),
+            env("CONSUMPTION_BENCH_MIX").unwrap_or_else(|| DEFAULT_MIX.to_string()),
+            positive("CONSUMPTION_BENCH_CONCURRENCY").unwrap_or(DEFAULT_CONCURRENCY),
+            positive("CONSUMPTION_BENCH_ITERATIONS").unwrap_or(DEFAULT_ITERATIONS),
+        );
+        bench.service_id = env("CONSUMPTION_BENCH_SERVICE_ID");
+        bench.api_key = env("CONSUMPTION_BENCH_API_KEY");
+        bench
+    }
+
+    pub fn with_config(
+        base_url: Option<String>,
+        mix: String,
+        concurrency: usize,
+        iterations: usize,
+    ) -> Self {
+        Self {
+            base_url,
+            service_id: None,
+            api_key: None,
+            mix,
+            concurrency,
+            iterations,
+        }
+    }
+
+    /// Benchmark a running consumption service at `base_url`
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Issue `count` requests from `concurrency` workers, each taking the
+    /// next slot of the schedule until the count or the time budget runs out
+    async fn drive(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        bodies: &Arc<Vec<serde_json::Value>>,
+        schedule: &Arc<Vec<usize>>,
+        count: usize,
+        max_duration: Option<Duration>,
+    ) -> Vec<Sample> {
+        let next = Arc::new(AtomicUsize::new(0));
+        let started = Instant::now();
+        let mut workers = JoinSet::new();
+
+        for _ in 0..self.concurrency.min(count) {
+            let client = client.clone();
+            let url = url.to_string();
+            let api_key = self.api_key.clone();
+            let bodies = Arc::clone(bodies);
+            let schedule = Arc::clone(schedule);
+            let next = Arc::clone(&next);
+
+            workers.spawn(async move {
+                let mut samples = Vec::new();
+                loop {
+                    let slot = next.fetch_add(1, Ordering::Relaxed);
+                    if slot >= count || max_duration.is_some_and(|max| started.elapsed() >= max) {
+                        return samples;
+                    }
+                    let profile = schedule[slot % schedule.len()];
+
+                    let mut request = client.post(&url).json(&bodies[profile]);
+                    if let Some(key) = &api_key {
+                        request = request.bearer_auth(key);
+                    }
+
+                    let start = Instant::now();
+                    let sample = match request.send().await {
+                        Ok(response) => {
+                            let status = response.status();
+                            // Read the full body so latency runs to the last byte
+                            let complete = response.bytes().await.is_ok();
+                            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+                            Sample {
+                                profile,
+                                latency_ms: (complete && status.is_success()).then_some(elapsed),
+                                throttled: status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+                            }
+                        }
+                        Err(e) => {
+                            log::debug!("Consume request failed: {}", e);
+                            Sample {
+                                profile,
+                                latency_ms: None,
+                                throttled: false,
+                            }
+                        }
+                    };
+                    samples.push(sample);
+                }
+            });
+        }
+
+        let mut samples = Vec::with_capacity(count);
+        while let Some(worker) = workers.join_next().await {
+            match worker {
+                Ok(worker_samples) => samples.extend(worker_samples),
+                Err(e) => log::warn!("Consume worker failed: {}", e),
+            }
+        }
+        samples
+    }
+
+    async fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        let mix = parse_mix(&self.mix)?;
+        let schedule = Arc::new(schedule(&mix));
+        let bodies: Arc<Vec<serde_json::Value>> = Arc::new(
+            mix.iter()
+                .map(|(profile, _)| {
+                    serde_json::json!({
+                        "prompt": prompt(profile.prompt_bytes),
+                        "max_tokens": profile.max_tokens,
+                    })
+                })
+                .collect(),
+        );
+
+        // The mock stands in for an unconfigured deployment until the run ends
+        let (base_url, service_id, _mock) = match &self.base_url {
+            Some(url) => {
+                let Some(service_id) = self.service_id.clone() else {
+                    bail!("CONSUMPTION_BENCH_SERVICE_ID must be set with CONSUMPTION_BENCH_URL");
+                };
+                (url.trim_end_matches('/').to_string(), service_id, None)
+            }
+            None => {
+                let mock = MockUpstream::start(MOCK_RESPONSE)?;
+                let url = format!("http://{}", mock.addr);
+                (url, MOCK_SERVICE_ID.to_string(), Some(mock))
+            }
+        };
+        let url = format!("{}/api/v1/consume/{}", base_url, service_id);
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .pool_max_idle_per_host(self.concurrency)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        log::info!(
+            "Driving {} with {} concurrent workers, mix {}...",
+            url,
+            self.concurrency,
+            self.mix
+        );
+        self.drive(
+            &client,
+            &url,
+            &bodies,
+            &schedule,
+            config.warmup_iterations,
+            None,
+        )
+        .await;
+        let started = Instant::now();
+        let samples = self
+            .drive(
+                &client,
+                &url,
+                &bodies,
+                &schedule,
+                config.measured_iterations,
+                config.max_duration,
+            )
+            .await;
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let mut metrics = HashMap::new();
+        let mut all_latencies: Vec<f64> = samples.iter().filter_map(|s| s.latency_ms).collect();
+        all_latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        for (index, (profile, _)) in mix.iter().enumerate() {
+            let mut latencies: Vec<f64> = samples
+                .iter()
+                .filter(|s| s.profile == index)
+                .filter_map(|s| s.latency_ms)
+                .collect();
+            latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            metrics.insert(
+                format!("{}_latency_p50", profile.name),
+                percentile(&latencies, 50),
+            );
+            metrics.insert(
+                format!("{}_latency_p95", profile.name),
+                percentile(&latencies, 95),
+            );
+            metrics.insert(
+                format!("{}_latency_p99", profile.name),
+                percentile(&latencies, 99),
+            );
+        }
+
+        let operation_count = all_latencies.len();
+        let attempts = samples.len() as f64;
+        let errors = samples.len() - operation_count;
+        let throttled = samples.iter().filter(|s| s.throttled).count();
+        let mean = if operation_count > 0 {
+            all_latencies.iter().sum::<f64>() / operation_count as f64
+        } else {
+            0.0
+        };
+
+        metrics.insert("latency_p50".to_string(), percentile(&all_latencies, 50));
+        metrics.insert("latency_p95".to_string(), percentile(&all_latencies, 95));
+        metrics.insert("latency_p99".to_string(), percentile(&all_latencies, 99));
+        metrics.insert("latency_mean".to_string(), mean);
+        metrics.insert(
+            "throughput_rps".to_string(),
+            if elapsed > 0.0 {
+                operation_count as f64 / elapsed
+            } else {
+                0.0
+            },
+        );
+        metrics.insert("operation_count".to_string(), operation_count as f64);
+        metrics.insert(
+            "error_rate".to_string(),
+            if attempts > 0.0 {
+                errors as f64 / attempts
+            } else {
+                0.0
+            },
+        );
+        metrics.insert(
+            "throttled_rate".to_string(),
+            if attempts > 0.0 {
+                throttled as f64 / attempts
+            } else {
+                0.0
+            },
+        );
+        metrics.insert("concurrency".to_string(), self.concurrency as f64);
+
+        let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+        result.add_histogram(
+            "latency".to_string(),
+            Histogram::from_samples(&all_latencies),
+        );
+
+        let wrapper = if self.base_url.is_some() {
+            "http"
+        } else {
+            "mock_http"
+        };
+        result.add_metadata("wrapper_type".to_string(), wrapper.to_string());
+        result.add_metadata("test_suite".to_string(), "consumption_api".to_string());
+        result.add_metadata("base_url".to_string(), base_url);
+        result.add_metadata("service_id".to_string(), service_id);
+        result.add_metadata(
+            "request_mix".to_string(),
+            mix.iter()
+                .map(|(profile, weight)| format!("{}:{}", profile.name, weight))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        result.add_metadata("concurrency".to_string(), self.concurrency.to_string());
+        result.add_metadata(
+            "iterations".to_string(),
+            config.measured_iterations.to_string(),
+        );
+
+        if let Ok(hostname) = hostname::get() {
+            if let Some(hostname_str) = hostname.to_str() {
+                result.add_metadata("hostname".to_string(), hostname_str.to_string());
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Default for ConsumptionApiBenchmark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AsyncBenchTarget for ConsumptionApiBenchmark {
+    fn id(&self) -> &str {
+        "marketplace_consumption_api"
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace", "consumption"]
+    }
+
+    async fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&TargetConfig::new(WARMUP_ITERATIONS, self.iterations))
+            .await
+    }
+
+    /// `CONSUMPTION_BENCH_ITERATIONS` sets the default measured iterations
+    fn config(&self) -> Option<TargetConfig> {
+        Some(TargetConfig::new(WARMUP_ITERATIONS, self.iterations))
+    }
+
+    async fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        log::info!("Running consumption API benchmark");
+        self.execute_benchmark_suite(config).await
+    }
+
+    /// A configured deployment must be reachable; an unset one is mocked
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.base_url
+            .as_deref()
+            .map(|url| Dependency::service("consumption", url))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_mix_interleaves_by_weight() {
+        let mix = parse_mix("small:3, large").unwrap();
+        assert_eq!(
+            mix,
+            vec![(REQUEST_PROFILES[0], 3), (REQUEST_PROFILES[2], 1)]
+        );
+        assert_eq!(schedule(&mix), vec![0, 0, 1, 0]);
+
+        let even = parse_mix("small:1,medium:1,large:0").unwrap();
+        assert_eq!(schedule(&even), vec![0, 1]);
+
+        assert!(parse_mix("tiny:2").is_err());
+        assert!(parse_mix("small:x").is_err());
+        assert!(parse_mix("small:0").is_err());
+        assert_eq!(prompt(300).len(), 300);
+    }
+
+    #[tokio::test]
+    async fn test_drives_mock_consume_endpoint() {
+        let bench = ConsumptionApiBenchmark::with_config(None, DEFAULT_MIX.to_string(), 4, 50);
+        let result = bench.run().await.unwrap();
+
+        assert_eq!(result.get_metric("operation_count"), Some(50.0));
+        assert_eq!(result.get_metric("error_rate"), Some(0.0));
+        assert_eq!(result.get_metric("throttled_rate"), Some(0.0));
+        assert!(result.get_metric("large_latency_p99").unwrap() > 0.0);
+        assert_eq!(result.histograms["latency"].count, 50);
+    }
+}
@@ -0,0 +1,134 @@
+//! Environment dependencies of benchmark adapters
+//!
+//! Adapters declare what they need from the machine: a toolchain command,
+//! a file, a reachable service. The runner probes these before running and
+//! skips targets with unmet dependencies, so a machine without the
+//! TypeScript toolchain reports skips instead of adapters full of errors.
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Something a benchmark target needs to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dependency {
+    /// A command that must start and exit successfully, e.g.
+    /// `node --check wrapper.ts` for a Node version that runs TypeScript
+    Command { program: String, args: Vec<String> },
+    /// A file the adapter reads or executes
+    File(PathBuf),
+    /// A service that must accept TCP connections, e.g. a Redis URL or the
+    /// base URL of a service under test
+    Service { name: String, url: String },
+}
+
+impl Dependency {
+    pub fn command(program: &str, args: &[&str]) -> Self {
+        Dependency::Command {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    pub fn service(name: &str, url: &str) -> Self {
+        Dependency::Service {
+            name: name.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    /// Check the dependency; `Err` holds why it is unmet
+    pub fn probe(&self) -> Result<(), String> {
+        match self {
+            Dependency::Command { program, args } => {
+                let output = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::null())
+                    .output()
+                    .map_err(|e| format!("`{}` is not available: {}", program, e))?;
+                if output.status.success() {
+                    return Ok(());
+                }
+
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let detail = stderr
+                    .lines()
+                    .find(|line| line.contains("Error"))
+                    .or_else(|| stderr.lines().find(|line| !line.trim().is_empty()))
+                    .map(|line| format!(": {}", line.trim()))
+                    .unwrap_or_default();
+                Err(format!("`{} {}` failed{}", program, args.join(" "), detail))
+            }
+            Dependency::File(path) => {
+                if path.is_file() {
+                    Ok(())
+                } else {
+                    Err(format!("{} not found", path.display()))
+                }
+            }
+            Dependency::Service { name, url } => {
+                let addr = service_addr(url)
+                    .ok_or_else(|| format!("{} URL {} is invalid", name, url))?;
+                let reachable = addr
+                    .to_socket_addrs()
+                    .ok()
+                    .into_iter()
+                    .flatten()
+                    .any(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok());
+                if reachable {
+                    Ok(())
+                } else {
+                    Err(format!("{} at {} is not reachable", name, url))
+                }
+            }
+        }
+    }
+}
+
+/// `host:port` of a service URL, with the scheme's default port
+fn service_addr(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let port = url.port_or_known_default().or(match url.scheme() {
+        "redis" | "rediss" => Some(6379),
+        "postgres" | "postgresql" => Some(5432),
+        _ => None,
+    })?;
+    Some(format!("{}:{}", host, port))
+}
+
+/// Probe dependencies in order and return why the first unmet one failed.
+/// Later dependencies usually build on earlier ones (the wrapper file before
+/// the command that loads it), so only the first reason is reported.
+pub fn first_unmet(dependencies: &[Dependency]) -> Option<String> {
+    dependencies
+        .iter()
+        .find_map(|dependency| dependency.probe().err())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_probe_reports_unmet_dependencies() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let reachable = Dependency::service("target", &url);
+        assert_eq!(first_unmet(std::slice::from_ref(&reachable)), None);
+        assert_eq!(service_addr("redis://cache.internal").as_deref(), Some("cache.internal:6379"));
+
+        let missing_file = Dependency::File(PathBuf::from("/nonexistent/wrapper.ts"));
+        assert_eq!(
+            first_unmet(&[reachable, missing_file, Dependency::service("redis", "not a url")]),
+            Some("/nonexistent/wrapper.ts not found".to_string())
+        );
+
+        let reason = first_unmet(&[Dependency::command("marketplace-no-such-binary", &[])]);
+        assert!(reason.unwrap().starts_with("`marketplace-no-such-binary` is not available"));
+    }
+}
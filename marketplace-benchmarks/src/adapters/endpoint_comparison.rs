@@ -0,0 +1,423 @@
+//! Endpoint Comparison Benchmark Adapter
+//!
+//! Differential mode for comparing two deployments of the same service, such
+//! as current production and a canary. Requests to the baseline and the
+//! candidate are interleaved within one run, alternating which goes first,
+//! and each pair's latency difference is recorded. Statistics are computed
+//! over those paired differences, so load and network drift during the run
+//! affect both targets alike instead of showing up as a regression.
+//!
+//! Without configured URLs both targets are local mock HTTP servers, which
+//! measures the harness itself and should report a delta close to zero.
+//!
+//! Requests go through async reqwest, so this is an [`AsyncBenchTarget`];
+//! the registry runs it through [`BlockingTarget`](crate::adapters::BlockingTarget).
+
+use crate::adapters::admission_overhead::MockUpstream;
+use crate::adapters::{AsyncBenchTarget, Dependency, TargetConfig};
+use crate::benchmarks::histogram::percentile;
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Default timed request pairs per operation
+const DEFAULT_ITERATIONS: usize = 200;
+
+/// Untimed request pairs per operation before measuring
+const WARMUP_ITERATIONS: usize = 5;
+
+/// Operations compared when none are configured
+const DEFAULT_PATHS: &str = "/health";
+
+/// z-value for a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Paired-difference statistics for one operation, in milliseconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairedStats {
+    pub pairs: usize,
+    pub baseline_p50: f64,
+    pub candidate_p50: f64,
+    /// Mean of candidate minus baseline
+    pub delta_mean: f64,
+    pub delta_p50: f64,
+    pub delta_p95: f64,
+    /// Half-width of the 95% confidence interval around `delta_mean`
+    pub delta_ci95: f64,
+    /// `delta_mean` relative to the baseline mean, in percent
+    pub delta_pct: f64,
+}
+
+impl PairedStats {
+    /// Summarize `(baseline, candidate)` latency pairs
+    pub fn from_pairs(pairs: &[(f64, f64)]) -> Self {
+        let n = pairs.len();
+        if n == 0 {
+            return Self {
+                pairs: 0,
+                baseline_p50: 0.0,
+                candidate_p50: 0.0,
+                delta_mean: 0.0,
+                delta_p50: 0.0,
+                delta_p95: 0.0,
+                delta_ci95: 0.0,
+                delta_pct: 0.0,
+            };
+        }
+
+        let baseline = sorted(pairs.iter().map(|(b, _)| *b).collect());
+        let candidate = sorted(pairs.iter().map(|(_, c)| *c).collect());
+        let deltas = sorted(pairs.iter().map(|(b, c)| c - b).collect());
+
+        let delta_mean = deltas.iter().sum::<f64>() / n as f64;
+        let baseline_mean = baseline.iter().sum::<f64>() / n as f64;
+        let delta_ci95 = if n > 1 {
+            let variance =
+                deltas.iter().map(|d| (d - delta_mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+            Z_95 * variance.sqrt() / (n as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        Self {
+            pairs: n,
+            baseline_p50: percentile(&baseline, 50),
+            candidate_p50: percentile(&candidate, 50),
+            delta_mean,
+            delta_p50: percentile(&deltas, 50),
+            delta_p95: percentile(&deltas, 95),
+            delta_ci95,
+            delta_pct: if baseline_mean > 0.0 {
+                delta_mean / baseline_mean * 100.0
+            } else {
+                0.0
+            },
+        }
+    }
+
+    /// Whether the confidence interval excludes zero
+    pub fn significant(&self) -> bool {
+        self.pairs > 1 && self.delta_mean.abs() > self.delta_ci95
+    }
+}
+
+fn sorted(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values
+}
+
+/// Metric-name label for a path, e.g. `/api/v1/quota/x` -> `api_v1_quota_x`
+fn operation_label(path: &str) -> String {
+    let label: String = path
+        .trim_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if label.is_empty() {
+        "root".to_string()
+    } else {
+        label
+    }
+}
+
+/// Benchmark adapter comparing two base URLs with interleaved requests
+pub struct EndpointComparisonBenchmark {
+    baseline_url: Option<String>,
+    candidate_url: Option<String>,
+    paths: Vec<String>,
+    iterations: usize,
+    api_key: Option<String>,
+}
+
+impl EndpointComparisonBenchmark {
+    pub fn new() -> Self {
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let paths = env("COMPARE_PATHS").unwrap_or_else(|| DEFAULT_PATHS.to_string());
+        let iterations = env("COMPARE_ITERATIONS")
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_ITERATIONS);
+
+        let mut bench = Self::with_config(
+            env("COMPARE_BASELINE_URL"),
+            env("COMPARE_CANDIDATE_URL"),
+            paths
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            iterations,
+        );
+        bench.api_key = env("COMPARE_API_KEY");
+        bench
+    }
+
+    pub fn with_config(
+        baseline_url: Option<String>,
+        candidate_url: Option<String>,
+        paths: Vec<String>,
+        iterations: usize,
+    ) -> Self {
+        Self {
+            baseline_url,
+            candidate_url,
+            paths,
+            iterations,
+            api_key: None,
+        }
+    }
+
+    /// Compare `baseline_url` with `candidate_url` instead of the configured
+    /// endpoints
+    pub fn with_endpoints(mut self, baseline_url: String, candidate_url: String) -> Self {
+        self.baseline_url = Some(baseline_url);
+        self.candidate_url = Some(candidate_url);
+        self
+    }
+
+    /// Time one GET; `None` on transport errors and non-2xx responses
+    async fn timed_get(&self, client: &reqwest::Client, url: &str) -> Option<f64> {
+        let mut request = client.get(url);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let start = Instant::now();
+        let response = request.send().await.ok()?;
+        let ok = response.status().is_success();
+        // Read the full body so both targets are timed to the last byte
+        let _ = response.bytes().await.ok()?;
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+
+        ok.then_some(elapsed)
+    }
+
+    /// Interleaved request pairs for one path. Even iterations hit the
+    /// baseline first and odd ones the candidate first, cancelling any
+    /// advantage from going second on a warm connection.
+    async fn measure_path(
+        &self,
+        client: &reqwest::Client,
+        baseline: &str,
+        candidate: &str,
+        path: &str,
+        config: &TargetConfig,
+    ) -> (Vec<(f64, f64)>, usize) {
+        let baseline_url = format!("{}{}", baseline, path);
+        let candidate_url = format!("{}{}", candidate, path);
+        let mut pairs = Vec::with_capacity(config.measured_iterations);
+        let mut errors = 0;
+        let mut started = Instant::now();
+
+        for i in 0..config.warmup_iterations + config.measured_iterations {
+            if i == config.warmup_iterations {
+                started = Instant::now();
+            } else if i > config.warmup_iterations && config.expired(started) {
+                break;
+            }
+            let (b, c) = if i % 2 == 0 {
+                let b = self.timed_get(client, &baseline_url).await;
+                (b, self.timed_get(client, &candidate_url).await)
+            } else {
+                let c = self.timed_get(client, &candidate_url).await;
+                (self.timed_get(client, &baseline_url).await, c)
+            };
+
+            if i < config.warmup_iterations {
+                continue;
+            }
+            match (b, c) {
+                (Some(b), Some(c)) => pairs.push((b, c)),
+                _ => errors += 1,
+            }
+        }
+
+        (pairs, errors)
+    }
+
+    async fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        // Mocks stand in for unconfigured targets and live until the run ends
+        let mut mocks = Vec::new();
+        let mut target = |url: &Option<String>| -> Result<String> {
+            if let Some(url) = url {
+                return Ok(url.trim_end_matches('/').to_string());
+            }
+            let mock = MockUpstream::start(r#"{"status":"ok"}"#)?;
+            let url = format!("http://{}", mock.addr);
+            mocks.push(mock);
+            Ok(url)
+        };
+        let baseline = target(&self.baseline_url)?;
+        let candidate = target(&self.candidate_url)?;
+        let mocked = !mocks.is_empty();
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .pool_max_idle_per_host(1)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        let mut metrics = HashMap::new();
+        let mut all_pairs = Vec::new();
+        let mut error_count = 0;
+        let mut significant = 0;
+
+        for path in &self.paths {
+            log::info!("Comparing {} on {} vs {}...", path, baseline, candidate);
+            let (pairs, errors) = self
+                .measure_path(&client, &baseline, &candidate, path, config)
+                .await;
+            let stats = PairedStats::from_pairs(&pairs);
+            let label = operation_label(path);
+
+            metrics.insert(format!("{}_baseline_ms_p50", label), stats.baseline_p50);
+            metrics.insert(format!("{}_candidate_ms_p50", label), stats.candidate_p50);
+            metrics.insert(format!("{}_delta_ms_mean", label), stats.delta_mean);
+            metrics.insert(format!("{}_delta_ms_p50", label), stats.delta_p50);
+            metrics.insert(format!("{}_delta_ms_p95", label), stats.delta_p95);
+            metrics.insert(format!("{}_delta_ms_ci95", label), stats.delta_ci95);
+            metrics.insert(format!("{}_delta_pct", label), stats.delta_pct);
+            metrics.insert(
+                format!("{}_significant", label),
+                if stats.significant() { 1.0 } else { 0.0 },
+            );
+
+            if stats.significant() {
+                significant += 1;
+                log::warn!(
+                    "{}: candidate differs from baseline by {:+.2}ms ({:+.1}%, ±{:.2}ms)",
+                    path,
+                    stats.delta_mean,
+                    stats.delta_pct,
+                    stats.delta_ci95
+                );
+            }
+
+            error_count += errors;
+            all_pairs.extend(pairs);
+        }
+
+        let overall = PairedStats::from_pairs(&all_pairs);
+        metrics.insert("delta_ms_mean".to_string(), overall.delta_mean);
+        metrics.insert("delta_ms_ci95".to_string(), overall.delta_ci95);
+        metrics.insert("delta_pct".to_string(), overall.delta_pct);
+        metrics.insert("significant_operations".to_string(), significant as f64);
+
+        let operation_count = all_pairs.len() * 2;
+        let attempts = (all_pairs.len() + error_count) as f64;
+        metrics.insert("operation_count".to_string(), operation_count as f64);
+        metrics.insert(
+            "error_rate".to_string(),
+            if attempts > 0.0 {
+                error_count as f64 / attempts
+            } else {
+                0.0
+            },
+        );
+
+        let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+
+        let wrapper = if mocked { "mock_http" } else { "http" };
+        result.add_metadata("wrapper_type".to_string(), wrapper.to_string());
+        result.add_metadata("test_suite".to_string(), "endpoint_comparison".to_string());
+        result.add_metadata("baseline_url".to_string(), baseline);
+        result.add_metadata("candidate_url".to_string(), candidate);
+        result.add_metadata("paths".to_string(), self.paths.join(","));
+        result.add_metadata(
+            "iterations".to_string(),
+            config.measured_iterations.to_string(),
+        );
+
+        if let Ok(hostname) = hostname::get() {
+            if let Some(hostname_str) = hostname.to_str() {
+                result.add_metadata("hostname".to_string(), hostname_str.to_string());
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Default for EndpointComparisonBenchmark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AsyncBenchTarget for EndpointComparisonBenchmark {
+    fn id(&self) -> &str {
+        "marketplace_endpoint_comparison"
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace", "consumption"]
+    }
+
+    async fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&TargetConfig::new(WARMUP_ITERATIONS, self.iterations))
+            .await
+    }
+
+    /// `COMPARE_ITERATIONS` sets the default measured iterations
+    fn config(&self) -> Option<TargetConfig> {
+        Some(TargetConfig::new(WARMUP_ITERATIONS, self.iterations))
+    }
+
+    async fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        log::info!("Running endpoint comparison benchmark");
+        self.execute_benchmark_suite(config).await
+    }
+
+    /// Configured targets must be reachable; unset ones are mocked
+    fn dependencies(&self) -> Vec<Dependency> {
+        [
+            ("baseline", &self.baseline_url),
+            ("candidate", &self.candidate_url),
+        ]
+        .into_iter()
+        .filter_map(|(name, url)| url.as_deref().map(|url| Dependency::service(name, url)))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paired_stats_remove_shared_drift() {
+        // Both targets slow down together; the candidate is always 2ms slower
+        let pairs: Vec<(f64, f64)> = (0..100)
+            .map(|i| {
+                let drift = (i % 10) as f64 * 5.0;
+                (10.0 + drift, 12.0 + drift)
+            })
+            .collect();
+
+        let stats = PairedStats::from_pairs(&pairs);
+        assert_eq!(stats.delta_mean, 2.0);
+        assert_eq!(stats.delta_ci95, 0.0);
+        assert!(stats.significant());
+        assert_eq!(operation_label("/api/v1/quota"), "api_v1_quota");
+    }
+
+    #[tokio::test]
+    async fn test_compares_mock_targets() {
+        let bench =
+            EndpointComparisonBenchmark::with_config(None, None, vec!["/health".to_string()], 20);
+        let result = bench.run().await.unwrap();
+
+        assert_eq!(result.get_metric("operation_count"), Some(40.0));
+        assert_eq!(result.get_metric("error_rate"), Some(0.0));
+        assert!(result.get_metric("health_delta_ms_ci95").is_some());
+    }
+}
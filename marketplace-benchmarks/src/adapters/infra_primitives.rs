@@ -0,0 +1,340 @@
+//! Infra Primitives Benchmark Adapter
+//!
+//! Measures the shared `llm-infra` building blocks that sit on every
+//! upstream call: the cost `with_retry` adds to a call that succeeds first
+//! time, the cost of its retry path, and circuit breaker state transitions
+//! with many threads recording outcomes at once. Calls are in-process no-ops,
+//! so the numbers are the primitives' own overhead.
+//!
+//! `llm-infra` declares a `cache` feature but ships no cache client yet, so
+//! cache operations are not covered here.
+
+use crate::adapters::{BenchTarget, TargetConfig};
+use crate::benchmarks::histogram::percentile;
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{Context, Result};
+use llm_infra::retry::{with_retry, CircuitBreaker, CircuitBreakerConfig, CircuitState, RetryConfig};
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+/// Calls per timed batch; per-call figures are batch time / batch size
+const BATCH_SIZE: usize = 1000;
+
+/// Timed batches for the retry measurements
+const DEFAULT_BATCHES: usize = 200;
+
+/// Calls that take the retry path (each sleeps at least one timer tick)
+const RETRY_PATH_CALLS: usize = 50;
+
+/// Default number of threads sharing one circuit breaker
+const DEFAULT_BREAKER_THREADS: usize = 8;
+
+/// Operations per thread in the circuit breaker measurement
+const BREAKER_OPS_PER_THREAD: usize = 50_000;
+
+/// One in this many recorded outcomes is a failure
+const FAILURE_EVERY: usize = 4;
+
+/// Benchmark adapter for llm-infra retry and circuit breaker overhead
+pub struct InfraPrimitivesBenchmark {
+    batches: usize,
+    breaker_threads: usize,
+}
+
+impl InfraPrimitivesBenchmark {
+    pub fn new() -> Self {
+        let batches = std::env::var("INFRA_PRIMITIVES_BATCHES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_BATCHES);
+        let breaker_threads = std::env::var("INFRA_PRIMITIVES_THREADS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_BREAKER_THREADS);
+
+        Self::with_config(batches, breaker_threads)
+    }
+
+    pub fn with_config(batches: usize, breaker_threads: usize) -> Self {
+        Self {
+            batches,
+            breaker_threads,
+        }
+    }
+
+    fn sorted(mut values: Vec<f64>) -> Vec<f64> {
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values
+    }
+
+    /// Retry policy with no backoff, so the retry path measures the
+    /// machinery rather than the configured delay
+    fn retry_config() -> RetryConfig {
+        RetryConfig {
+            max_retries: 3,
+            initial_delay_ms: 0,
+            max_delay_ms: 0,
+            jitter: false,
+            ..RetryConfig::default()
+        }
+    }
+
+    /// Per-call nanoseconds of a direct call and of the same call through
+    /// `with_retry`, one sample per measured batch
+    fn measure_retry_happy_path(
+        runtime: &tokio::runtime::Runtime,
+        target: &TargetConfig,
+    ) -> (Vec<f64>, Vec<f64>) {
+        let config = Self::retry_config();
+        let mut direct = Vec::with_capacity(target.measured_iterations);
+        let mut wrapped = Vec::with_capacity(target.measured_iterations);
+        let mut started = Instant::now();
+
+        for batch in 0..target.warmup_iterations + target.measured_iterations {
+            if batch == target.warmup_iterations {
+                direct.clear();
+                wrapped.clear();
+                started = Instant::now();
+            } else if batch > target.warmup_iterations && target.expired(started) {
+                break;
+            }
+
+            let begin = Instant::now();
+            runtime.block_on(async {
+                for i in 0..BATCH_SIZE {
+                    let result: Result<usize, io::Error> = async { Ok(i) }.await;
+                    std::hint::black_box(result.ok());
+                }
+            });
+            direct.push(begin.elapsed().as_nanos() as f64 / BATCH_SIZE as f64);
+
+            let begin = Instant::now();
+            runtime.block_on(async {
+                for i in 0..BATCH_SIZE {
+                    let result = with_retry(|| async move { Ok::<_, io::Error>(i) }, &config).await;
+                    std::hint::black_box(result.ok());
+                }
+            });
+            wrapped.push(begin.elapsed().as_nanos() as f64 / BATCH_SIZE as f64);
+        }
+
+        (direct, wrapped)
+    }
+
+    /// Microseconds per call that fails once with a retryable error and
+    /// then succeeds
+    fn measure_retry_path(runtime: &tokio::runtime::Runtime) -> Vec<f64> {
+        let config = Self::retry_config();
+
+        (0..RETRY_PATH_CALLS)
+            .map(|_| {
+                let mut attempts = 0;
+                let begin = Instant::now();
+                let result = runtime.block_on(with_retry(
+                    || {
+                        attempts += 1;
+                        let fail = attempts == 1;
+                        async move {
+                            if fail {
+                                Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset"))
+                            } else {
+                                Ok(())
+                            }
+                        }
+                    },
+                    &config,
+                ));
+                std::hint::black_box(result.ok());
+                begin.elapsed().as_secs_f64() * 1_000_000.0
+            })
+            .collect()
+    }
+
+    /// Threads share one breaker, each checking it and recording a mix of
+    /// successes and failures. Any failure opens it and a zero reset timeout
+    /// lets the next check probe, so it keeps cycling through closed, open
+    /// and half-open. Returns per-operation nanoseconds (one
+    /// sample per batch), open transitions observed and total operations/sec.
+    fn measure_breaker(threads: usize) -> (Vec<f64>, u64, f64) {
+        let breaker = Arc::new(CircuitBreaker::new(
+            "bench",
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                reset_timeout_ms: 0,
+                success_threshold: 2,
+            },
+        ));
+        let opened = Arc::new(AtomicU64::new(0));
+        let start = Instant::now();
+
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let breaker = breaker.clone();
+                let opened = opened.clone();
+                thread::spawn(move || {
+                    let mut samples = Vec::with_capacity(BREAKER_OPS_PER_THREAD / BATCH_SIZE);
+                    let mut batch_start = Instant::now();
+
+                    for i in 0..BREAKER_OPS_PER_THREAD {
+                        if breaker.allow_request() {
+                            let before = breaker.state();
+                            if (i + t) % FAILURE_EVERY == 0 {
+                                breaker.record_failure();
+                            } else {
+                                breaker.record_success();
+                            }
+                            if before != CircuitState::Open && breaker.state() == CircuitState::Open {
+                                opened.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+
+                        if (i + 1) % BATCH_SIZE == 0 {
+                            samples.push(batch_start.elapsed().as_nanos() as f64 / BATCH_SIZE as f64);
+                            batch_start = Instant::now();
+                        }
+                    }
+
+                    samples
+                })
+            })
+            .collect();
+
+        let samples: Vec<f64> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect();
+        let elapsed = start.elapsed().as_secs_f64();
+        let ops = (threads * BREAKER_OPS_PER_THREAD) as f64;
+
+        (samples, opened.load(Ordering::Relaxed), ops / elapsed)
+    }
+
+    fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to build benchmark runtime")?;
+
+        log::info!("Measuring with_retry overhead...");
+        let (direct, wrapped) = Self::measure_retry_happy_path(&runtime, config);
+        let overhead = Self::sorted(
+            wrapped
+                .iter()
+                .zip(&direct)
+                .map(|(wrapped, direct)| (wrapped - direct).max(0.0))
+                .collect(),
+        );
+        let wrapped = Self::sorted(wrapped);
+
+        log::info!("Measuring with_retry retry path...");
+        let retry_path = Self::sorted(Self::measure_retry_path(&runtime));
+
+        log::info!("Measuring circuit breaker under contention...");
+        let (baseline, _, _) = Self::measure_breaker(1);
+        let baseline = Self::sorted(baseline);
+        let (contended, opened, ops_per_sec) = Self::measure_breaker(self.breaker_threads);
+        let contended = Self::sorted(contended);
+
+        let mut metrics = HashMap::new();
+
+        metrics.insert("retry_call_ns_p50".to_string(), percentile(&wrapped, 50));
+        metrics.insert("retry_call_ns_p99".to_string(), percentile(&wrapped, 99));
+        metrics.insert("retry_overhead_ns_p50".to_string(), percentile(&overhead, 50));
+        metrics.insert("retry_overhead_ns_p99".to_string(), percentile(&overhead, 99));
+        metrics.insert("retry_path_us_p50".to_string(), percentile(&retry_path, 50));
+        metrics.insert("retry_path_us_p99".to_string(), percentile(&retry_path, 99));
+
+        let baseline_p99 = percentile(&baseline, 99);
+        let contended_p99 = percentile(&contended, 99);
+        metrics.insert("breaker_op_ns_p50".to_string(), percentile(&contended, 50));
+        metrics.insert("breaker_op_ns_p99".to_string(), contended_p99);
+        metrics.insert("uncontended_breaker_op_ns_p99".to_string(), baseline_p99);
+        metrics.insert(
+            "breaker_contention_ratio".to_string(),
+            if baseline_p99 > 0.0 { contended_p99 / baseline_p99 } else { 0.0 },
+        );
+        metrics.insert("breaker_ops_per_sec".to_string(), ops_per_sec);
+        metrics.insert("breaker_open_transitions".to_string(), opened as f64);
+
+        let operations = direct.len() * BATCH_SIZE * 2
+            + RETRY_PATH_CALLS
+            + (self.breaker_threads + 1) * BREAKER_OPS_PER_THREAD;
+        metrics.insert("operation_count".to_string(), operations as f64);
+        metrics.insert("error_rate".to_string(), 0.0);
+
+        let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+
+        result.add_metadata("wrapper_type".to_string(), "in_process".to_string());
+        result.add_metadata("test_suite".to_string(), "infra_primitives".to_string());
+        result.add_metadata("llm_infra_version".to_string(), llm_infra::VERSION.to_string());
+        result.add_metadata("batch_size".to_string(), BATCH_SIZE.to_string());
+        result.add_metadata("breaker_threads".to_string(), self.breaker_threads.to_string());
+        result.add_metadata("iterations".to_string(), operations.to_string());
+
+        if let Ok(hostname) = hostname::get() {
+            if let Some(hostname_str) = hostname.to_str() {
+                result.add_metadata("hostname".to_string(), hostname_str.to_string());
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Default for InfraPrimitivesBenchmark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BenchTarget for InfraPrimitivesBenchmark {
+    fn id(&self) -> &str {
+        "llm_infra_primitives"
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        vec!["infra"]
+    }
+
+    fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&TargetConfig::new(0, self.batches))
+    }
+
+    /// Iterations are happy-path retry batches; `INFRA_PRIMITIVES_BATCHES`
+    /// sets the default
+    fn config(&self) -> Option<TargetConfig> {
+        Some(TargetConfig::new(0, self.batches))
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        log::info!("Running llm-infra primitives benchmark");
+        self.execute_benchmark_suite(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_id() {
+        let bench = InfraPrimitivesBenchmark::new();
+        assert_eq!(bench.id(), "llm_infra_primitives");
+    }
+
+    #[test]
+    fn test_reports_retry_and_breaker_metrics() {
+        let result = InfraPrimitivesBenchmark::with_config(5, 2).run().unwrap();
+
+        assert!(result.get_metric("retry_overhead_ns_p50").is_some());
+        assert!(result.get_metric("retry_path_us_p99").unwrap() > 0.0);
+        assert!(result.get_metric("breaker_open_transitions").unwrap() > 0.0);
+        assert!(result.get_metric("breaker_ops_per_sec").unwrap() > 0.0);
+    }
+}
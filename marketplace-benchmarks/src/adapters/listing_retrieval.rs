@@ -2,14 +2,20 @@
 //!
 //! Benchmarks service listing and retrieval operations by invoking TypeScript CLI wrappers.
 
+use crate::benchmarks::histogram::Histogram;
 use crate::benchmarks::result::BenchmarkResult;
-use crate::adapters::BenchTarget;
-use anyhow::{Context, Result};
+use crate::adapters::node_pool::NodeWrapperPool;
+use crate::adapters::{BenchTarget, Dependency, TargetConfig};
+use anyhow::Result;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::process::Command;
 use std::time::Instant;
 
+/// Measured iterations across all operations by default
+const DEFAULT_ITERATIONS: usize = 70;
+
+const DEFAULT_CONFIG: TargetConfig = TargetConfig::new(0, DEFAULT_ITERATIONS);
+
 #[derive(Debug, Deserialize)]
 struct CliMetrics {
     operation: String,
@@ -22,48 +28,46 @@ struct CliMetrics {
 
 /// Benchmark adapter for service listing retrieval operations
 pub struct ListingRetrievalBenchmark {
-    wrapper_path: String,
+    wrapper: NodeWrapperPool,
 }
 
 impl ListingRetrievalBenchmark {
     pub fn new() -> Self {
         let workspace_root = std::env::var("CARGO_MANIFEST_DIR")
             .unwrap_or_else(|_| ".".to_string());
-        let wrapper_path = format!("{}/ts-wrappers/listing-cli.ts", workspace_root);
+        Self::with_wrapper(format!("{}/ts-wrappers/listing-cli.ts", workspace_root))
+    }
 
-        Self { wrapper_path }
+    /// Run against a different build of the wrapper script
+    pub fn with_wrapper(wrapper_path: String) -> Self {
+        Self {
+            wrapper: NodeWrapperPool::new(wrapper_path),
+        }
     }
 
     fn run_cli_operation(&self, operation: &str, args: &[&str]) -> Result<CliMetrics> {
-        let mut cmd_args = vec!["--no-warnings", &self.wrapper_path, operation];
-        cmd_args.extend(args);
-
-        let output = Command::new("node")
-            .args(&cmd_args)
-            .output()
-            .context("Failed to execute TypeScript wrapper")?;
+        self.wrapper.call(operation, args)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("CLI operation failed: {}", stderr);
+    fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        // Start wrapper processes before timing anything
+        self.wrapper.warm_up()?;
+        for _ in 0..config.warmup_iterations {
+            self.run_cli_operation("list_all", &[])?;
         }
+        let started = Instant::now();
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let metrics: CliMetrics = serde_json::from_str(&stdout)
-            .context("Failed to parse CLI output")?;
-
-        Ok(metrics)
-    }
-
-    fn execute_benchmark_suite(&self) -> Result<BenchmarkResult> {
         let mut all_durations = Vec::new();
         let mut total_items = 0;
         let mut operation_count = 0;
         let mut error_count = 0;
 
-        // Test 1: List all services (10 iterations)
+        // Test 1: List all services (10 of 70 iterations by default)
         log::info!("Running list_all operation...");
-        for i in 0..10 {
+        for i in (0..10).cycle().take(config.share(10, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let start = Instant::now();
             match self.run_cli_operation("list_all", &[]) {
                 Ok(metrics) => {
@@ -80,10 +84,13 @@ impl ListingRetrievalBenchmark {
             }
         }
 
-        // Test 2: Search by category (20 iterations with different categories)
+        // Test 2: Search by category (20 of 70 iterations by default, with different categories)
         log::info!("Running search_category operation...");
         let categories = ["ai-models", "data-processing", "analytics", "storage"];
-        for i in 0..20 {
+        for i in (0..20).cycle().take(config.share(20, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let category = categories[i % categories.len()];
             let start = Instant::now();
             match self.run_cli_operation("search_category", &[category]) {
@@ -99,9 +106,12 @@ impl ListingRetrievalBenchmark {
             }
         }
 
-        // Test 3: Get by ID (30 iterations)
+        // Test 3: Get by ID (30 of 70 iterations by default)
         log::info!("Running get_by_id operation...");
-        for i in 0..30 {
+        for i in (0..30).cycle().take(config.share(30, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let service_id = format!("svc_{:06}", i * 10);
             let start = Instant::now();
             match self.run_cli_operation("get_by_id", &[&service_id]) {
@@ -117,9 +127,12 @@ impl ListingRetrievalBenchmark {
             }
         }
 
-        // Test 4: Paginated listing (10 iterations)
+        // Test 4: Paginated listing (10 of 70 iterations by default)
         log::info!("Running paginated operation...");
-        for i in 0..10 {
+        for i in (0..10).cycle().take(config.share(10, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let start = Instant::now();
             match self.run_cli_operation("paginated", &["20", "5"]) {
                 Ok(metrics) => {
@@ -180,9 +193,12 @@ impl ListingRetrievalBenchmark {
         metrics.insert("total_items_processed".to_string(), total_items as f64);
 
         let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+        result.add_histogram("latency".to_string(), Histogram::from_samples(&all_durations));
 
         // Add metadata
-        result.add_metadata("wrapper_type".to_string(), "node_cli".to_string());
+        let wrapper_type = if self.wrapper.size() > 0 { "node_pool" } else { "node_cli" };
+        result.add_metadata("wrapper_type".to_string(), wrapper_type.to_string());
+        result.add_metadata("node_pool_size".to_string(), self.wrapper.size().to_string());
         result.add_metadata("test_suite".to_string(), "listing_retrieval".to_string());
         result.add_metadata("iterations".to_string(), len.to_string());
 
@@ -207,9 +223,29 @@ impl BenchTarget for ListingRetrievalBenchmark {
         "marketplace_listing_retrieval"
     }
 
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace"]
+    }
+
     fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&DEFAULT_CONFIG)
+    }
+
+    fn config(&self) -> Option<TargetConfig> {
+        Some(DEFAULT_CONFIG)
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
         log::info!("Running listing retrieval benchmark");
-        self.execute_benchmark_suite()
+        self.execute_benchmark_suite(config)
+    }
+
+    fn cancel(&self) {
+        self.wrapper.cancel();
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.wrapper.dependencies()
     }
 }
 
@@ -2,14 +2,20 @@
 //!
 //! Benchmarks service manifest validation and schema checking operations.
 
+use crate::benchmarks::histogram::Histogram;
 use crate::benchmarks::result::BenchmarkResult;
-use crate::adapters::BenchTarget;
-use anyhow::{Context, Result};
+use crate::adapters::node_pool::NodeWrapperPool;
+use crate::adapters::{BenchTarget, Dependency, TargetConfig};
+use anyhow::Result;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::process::Command;
 use std::time::Instant;
 
+/// Measured iterations across all operations by default
+const DEFAULT_ITERATIONS: usize = 80;
+
+const DEFAULT_CONFIG: TargetConfig = TargetConfig::new(0, DEFAULT_ITERATIONS);
+
 #[derive(Debug, Deserialize)]
 struct CliMetrics {
     operation: String,
@@ -33,40 +39,35 @@ struct ValidationStats {
 
 /// Benchmark adapter for metadata validation operations
 pub struct MetadataValidationBenchmark {
-    wrapper_path: String,
+    wrapper: NodeWrapperPool,
 }
 
 impl MetadataValidationBenchmark {
     pub fn new() -> Self {
         let workspace_root = std::env::var("CARGO_MANIFEST_DIR")
             .unwrap_or_else(|_| ".".to_string());
-        let wrapper_path = format!("{}/ts-wrappers/validation-cli.ts", workspace_root);
+        Self::with_wrapper(format!("{}/ts-wrappers/validation-cli.ts", workspace_root))
+    }
 
-        Self { wrapper_path }
+    /// Run against a different build of the wrapper script
+    pub fn with_wrapper(wrapper_path: String) -> Self {
+        Self {
+            wrapper: NodeWrapperPool::new(wrapper_path),
+        }
     }
 
     fn run_cli_operation(&self, operation: &str, args: &[&str]) -> Result<CliMetrics> {
-        let mut cmd_args = vec!["--no-warnings", &self.wrapper_path, operation];
-        cmd_args.extend(args);
-
-        let output = Command::new("node")
-            .args(&cmd_args)
-            .output()
-            .context("Failed to execute TypeScript wrapper")?;
+        self.wrapper.call(operation, args)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("CLI operation failed: {}", stderr);
+    fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        // Start wrapper processes before timing anything
+        self.wrapper.warm_up()?;
+        for _ in 0..config.warmup_iterations {
+            self.run_cli_operation("single", &["valid"])?;
         }
+        let started = Instant::now();
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let metrics: CliMetrics = serde_json::from_str(&stdout)
-            .context("Failed to parse CLI output")?;
-
-        Ok(metrics)
-    }
-
-    fn execute_benchmark_suite(&self) -> Result<BenchmarkResult> {
         let mut all_durations = Vec::new();
         let mut total_items = 0;
         let mut operation_count = 0;
@@ -76,9 +77,12 @@ impl MetadataValidationBenchmark {
         let mut total_failed = 0;
         let mut total_warnings = 0;
 
-        // Test 1: Single valid manifest validation (30 iterations)
+        // Test 1: Single valid manifest validation (30 of 80 iterations by default)
         log::info!("Running single validation (valid)...");
-        for i in 0..30 {
+        for i in (0..30).cycle().take(config.share(30, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let start = Instant::now();
             match self.run_cli_operation("single", &["valid"]) {
                 Ok(metrics) => {
@@ -103,9 +107,12 @@ impl MetadataValidationBenchmark {
             }
         }
 
-        // Test 2: Single invalid manifest validation (20 iterations)
+        // Test 2: Single invalid manifest validation (20 of 80 iterations by default)
         log::info!("Running single validation (invalid)...");
-        for i in 0..20 {
+        for i in (0..20).cycle().take(config.share(20, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let start = Instant::now();
             match self.run_cli_operation("single", &["invalid"]) {
                 Ok(metrics) => {
@@ -127,12 +134,15 @@ impl MetadataValidationBenchmark {
             }
         }
 
-        // Test 3: Batch validation with varying valid ratios (15 iterations)
+        // Test 3: Batch validation with varying valid ratios (15 of 80 iterations by default)
         log::info!("Running batch validation...");
         let batch_sizes = [50, 100, 200];
         let valid_ratios = [0.9, 0.8, 0.7, 0.6, 0.5];
 
-        for i in 0..15 {
+        for i in (0..15).cycle().take(config.share(15, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let batch_size = batch_sizes[i % batch_sizes.len()].to_string();
             let valid_ratio = valid_ratios[i % valid_ratios.len()].to_string();
             let start = Instant::now();
@@ -160,9 +170,12 @@ impl MetadataValidationBenchmark {
             }
         }
 
-        // Test 4: Schema compliance validation (15 iterations)
+        // Test 4: Schema compliance validation (15 of 80 iterations by default)
         log::info!("Running schema compliance validation...");
-        for i in 0..15 {
+        for i in (0..15).cycle().take(config.share(15, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let mode = if i % 2 == 0 { "strict" } else { "normal" };
             let args = if mode == "strict" {
                 vec![mode]
@@ -246,9 +259,12 @@ impl MetadataValidationBenchmark {
         metrics.insert("validation_warnings".to_string(), total_warnings as f64);
 
         let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+        result.add_histogram("latency".to_string(), Histogram::from_samples(&all_durations));
 
         // Add metadata
-        result.add_metadata("wrapper_type".to_string(), "node_cli".to_string());
+        let wrapper_type = if self.wrapper.size() > 0 { "node_pool" } else { "node_cli" };
+        result.add_metadata("wrapper_type".to_string(), wrapper_type.to_string());
+        result.add_metadata("node_pool_size".to_string(), self.wrapper.size().to_string());
         result.add_metadata("test_suite".to_string(), "metadata_validation".to_string());
         result.add_metadata("iterations".to_string(), len.to_string());
         result.add_metadata("total_checks".to_string(), total_validation_checks.to_string());
@@ -274,9 +290,29 @@ impl BenchTarget for MetadataValidationBenchmark {
         "marketplace_metadata_validation"
     }
 
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace"]
+    }
+
     fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&DEFAULT_CONFIG)
+    }
+
+    fn config(&self) -> Option<TargetConfig> {
+        Some(DEFAULT_CONFIG)
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
         log::info!("Running metadata validation benchmark");
-        self.execute_benchmark_suite()
+        self.execute_benchmark_suite(config)
+    }
+
+    fn cancel(&self) {
+        self.wrapper.cancel();
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.wrapper.dependencies()
     }
 }
 
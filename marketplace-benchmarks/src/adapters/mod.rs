@@ -4,24 +4,48 @@
 //! and provides a registry of all available benchmark targets.
 
 use crate::benchmarks::result::BenchmarkResult;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
 
 // Marketplace benchmark adapters
+pub mod admission_overhead;
+pub mod consumption_api;
+pub mod endpoint_comparison;
+pub mod infra_primitives;
 pub mod listing_retrieval;
 pub mod registry_lookup;
 pub mod metadata_validation;
 pub mod search_queries;
+pub mod tenant_contention;
 
+// Shared wrapper process pool for Node-based adapters
+pub mod node_pool;
+
+// Environment dependencies probed before a target runs
+pub mod deps;
+
+// Runtime registry of the targets a runner can pick from
+pub mod registry;
+
+pub use admission_overhead::AdmissionOverheadBenchmark;
+pub use consumption_api::ConsumptionApiBenchmark;
+pub use endpoint_comparison::EndpointComparisonBenchmark;
+pub use infra_primitives::InfraPrimitivesBenchmark;
 pub use listing_retrieval::ListingRetrievalBenchmark;
 pub use registry_lookup::RegistryLookupBenchmark;
 pub use metadata_validation::MetadataValidationBenchmark;
 pub use search_queries::SearchQueriesBenchmark;
+pub use tenant_contention::TenantContentionBenchmark;
+pub use deps::Dependency;
+pub use registry::TargetRegistry;
 
 /// Trait that all benchmark targets must implement
 ///
 /// Each benchmark adapter implements this trait to provide a unique identifier
-/// and an execution method that returns standardized results.
-pub trait BenchTarget {
+/// and an execution method that returns standardized results. Targets are
+/// `Send + Sync` so the runner can spread them over worker threads.
+pub trait BenchTarget: Send + Sync {
     /// Returns the unique identifier for this benchmark target
     ///
     /// This ID is used in filenames, reports, and logs to identify the benchmark.
@@ -37,6 +61,164 @@ pub trait BenchTarget {
     ///
     /// A `Result` containing the `BenchmarkResult` or an error if the benchmark fails
     fn run(&self) -> Result<BenchmarkResult>;
+
+    /// Returns what this target needs from the environment
+    ///
+    /// The runner probes these before running and skips the target, with the
+    /// reason, when one is unmet. Defaults to none.
+    fn dependencies(&self) -> Vec<Dependency> {
+        Vec::new()
+    }
+
+    /// Returns the groups this target belongs to, e.g. `marketplace` or
+    /// `infra`, for selecting targets by tag. Defaults to none.
+    fn tags(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// Returns the target's default iteration budget, or `None` when its
+    /// iterations can't be changed
+    ///
+    /// The runner applies its overrides (e.g. `--iterations`) to this and
+    /// runs the target with [`run_with`](Self::run_with).
+    fn config(&self) -> Option<TargetConfig> {
+        None
+    }
+
+    /// Executes the benchmark within an iteration budget
+    ///
+    /// Targets that return a [`config`](Self::config) implement this, and
+    /// `run` as `run_with` on their defaults. Defaults to [`run`](Self::run).
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        let _ = config;
+        self.run()
+    }
+
+    /// Stops a run that exceeded the runner's timeout; called from another
+    /// thread while the run is in progress
+    ///
+    /// Targets driving child processes kill them here so the hung run
+    /// returns. The runner moves on to the next target either way. Defaults
+    /// to nothing.
+    fn cancel(&self) {}
+}
+
+/// Iteration budget of a target run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetConfig {
+    /// Untimed iterations before measuring, to warm caches and connections
+    pub warmup_iterations: usize,
+    /// Timed iterations, spread over the target's operations in the same
+    /// proportions as its defaults
+    pub measured_iterations: usize,
+    /// Stop measuring once this much time has passed, even if iterations
+    /// remain
+    pub max_duration: Option<Duration>,
+}
+
+impl TargetConfig {
+    pub const fn new(warmup_iterations: usize, measured_iterations: usize) -> Self {
+        Self {
+            warmup_iterations,
+            measured_iterations,
+            max_duration: None,
+        }
+    }
+
+    /// Iterations of an operation that takes `default` of `default_total`
+    /// measured iterations by default; at least one
+    pub fn share(&self, default: usize, default_total: usize) -> usize {
+        let share = (self.measured_iterations * default + default_total / 2) / default_total.max(1);
+        share.max(1)
+    }
+
+    /// Whether `max_duration` has passed since measuring started
+    pub fn expired(&self, started: Instant) -> bool {
+        self.max_duration
+            .is_some_and(|max_duration| started.elapsed() >= max_duration)
+    }
+}
+
+/// Async variant of [`BenchTarget`] for adapters that talk to HTTP services
+///
+/// Implementations drive their requests on tokio instead of blocking a
+/// thread per request. [`run_async_targets`](crate::run_async_targets) runs
+/// them as tasks on the caller's runtime; wrapped in [`BlockingTarget`] they
+/// join the registry and run alongside the synchronous targets.
+#[async_trait]
+pub trait AsyncBenchTarget: Send + Sync {
+    /// Returns the unique identifier for this benchmark target
+    fn id(&self) -> &str;
+
+    /// Executes the benchmark and returns the result
+    async fn run(&self) -> Result<BenchmarkResult>;
+
+    /// Returns what this target needs from the environment; see
+    /// [`BenchTarget::dependencies`]
+    fn dependencies(&self) -> Vec<Dependency> {
+        Vec::new()
+    }
+
+    /// Returns the groups this target belongs to; see [`BenchTarget::tags`]
+    fn tags(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// Returns the target's default iteration budget; see
+    /// [`BenchTarget::config`]
+    fn config(&self) -> Option<TargetConfig> {
+        None
+    }
+
+    /// Executes the benchmark within an iteration budget; see
+    /// [`BenchTarget::run_with`]
+    async fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        let _ = config;
+        self.run().await
+    }
+}
+
+/// Runs an [`AsyncBenchTarget`] as a [`BenchTarget`], on a single-threaded
+/// runtime of its own for each run
+///
+/// Must not be run from within a tokio runtime; async callers use
+/// [`run_async_targets`](crate::run_async_targets) instead.
+pub struct BlockingTarget<T>(pub T);
+
+impl<T: AsyncBenchTarget> BlockingTarget<T> {
+    fn block_on<R>(&self, future: impl std::future::Future<Output = Result<R>>) -> Result<R> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to build benchmark runtime")?
+            .block_on(future)
+    }
+}
+
+impl<T: AsyncBenchTarget> BenchTarget for BlockingTarget<T> {
+    fn id(&self) -> &str {
+        self.0.id()
+    }
+
+    fn run(&self) -> Result<BenchmarkResult> {
+        self.block_on(self.0.run())
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.0.dependencies()
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        self.0.tags()
+    }
+
+    fn config(&self) -> Option<TargetConfig> {
+        self.0.config()
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        self.block_on(self.0.run_with(config))
+    }
 }
 
 /// Example benchmark target for demonstration and testing
@@ -58,6 +240,10 @@ impl BenchTarget for ExampleBenchmark {
         &self.id
     }
 
+    fn tags(&self) -> Vec<&str> {
+        vec!["example"]
+    }
+
     fn run(&self) -> Result<BenchmarkResult> {
         use std::collections::HashMap;
 
@@ -90,10 +276,12 @@ impl BenchTarget for ExampleBenchmark {
     }
 }
 
-/// Returns all registered benchmark targets
+/// Returns all built-in benchmark targets
 ///
-/// This function serves as the central registry for all benchmark adapters.
-/// When new benchmark targets are implemented, they should be added to this function.
+/// This function lists the benchmark adapters of this crate, and backs
+/// [`TargetRegistry::builtin`]. When new benchmark targets are implemented
+/// here, they should be added to this function; downstream crates register
+/// theirs on a [`TargetRegistry`] instead.
 ///
 /// # Returns
 ///
@@ -117,9 +305,41 @@ pub fn all_targets() -> Vec<Box<dyn BenchTarget>> {
         Box::new(RegistryLookupBenchmark::new()),
         Box::new(MetadataValidationBenchmark::new()),
         Box::new(SearchQueriesBenchmark::new()),
+        // Consumption service benchmarks
+        Box::new(AdmissionOverheadBenchmark::new()),
+        Box::new(TenantContentionBenchmark::new()),
+        Box::new(BlockingTarget(EndpointComparisonBenchmark::new())),
+        Box::new(BlockingTarget(ConsumptionApiBenchmark::new())),
+        // Shared infrastructure benchmarks
+        Box::new(InfraPrimitivesBenchmark::new()),
     ]
 }
 
+/// Returns the registered targets with the given ids, in registry order
+///
+/// An empty `ids` selects every target. Unknown ids are an error rather than
+/// being skipped, so a typo in CI doesn't silently run nothing.
+///
+/// # Example
+///
+/// ```
+/// use marketplace_benchmarks::select_targets;
+///
+/// let targets = select_targets(&["example-benchmark".to_string()]).unwrap();
+/// assert_eq!(targets.len(), 1);
+/// ```
+pub fn select_targets(ids: &[String]) -> Result<Vec<Box<dyn BenchTarget>>> {
+    let targets = all_targets();
+    if let Some(unknown) = ids.iter().find(|id| !targets.iter().any(|t| t.id() == id.as_str())) {
+        bail!("Unknown benchmark target '{}'; see the 'list' command", unknown);
+    }
+
+    Ok(targets
+        .into_iter()
+        .filter(|target| ids.is_empty() || ids.iter().any(|id| id == target.id()))
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +355,25 @@ mod tests {
         assert!(result.get_metric("throughput").is_some());
     }
 
+    #[test]
+    fn test_target_config_share_keeps_proportions() {
+        let defaults = TargetConfig::new(0, 70);
+        assert_eq!(defaults.share(10, 70), 10);
+        assert_eq!(defaults.share(30, 70), 30);
+
+        let smoke = TargetConfig::new(0, 7);
+        assert_eq!(smoke.share(30, 70), 3);
+        assert_eq!(smoke.share(1, 70), 1);
+
+        let soak = TargetConfig {
+            max_duration: Some(Duration::ZERO),
+            ..TargetConfig::new(2, 7000)
+        };
+        assert_eq!(soak.share(20, 70), 2000);
+        assert!(soak.expired(Instant::now()));
+        assert!(!smoke.expired(Instant::now()));
+    }
+
     #[test]
     fn test_all_targets() {
         let targets = all_targets();
@@ -145,6 +384,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_select_targets() {
+        assert_eq!(select_targets(&[]).unwrap().len(), all_targets().len());
+
+        let selected = select_targets(&["example-benchmark".to_string()]).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].id(), "example-benchmark");
+
+        assert!(select_targets(&["no-such-target".to_string()]).is_err());
+    }
+
     #[test]
     fn test_benchmark_execution() {
         let targets = all_targets();
@@ -0,0 +1,363 @@
+//! Persistent Node wrapper process pool
+//!
+//! Spawning `node` for every operation makes process startup dominate the
+//! measured latency. The pool keeps wrapper processes running in `--serve`
+//! mode and talks to them with newline-delimited JSON over stdin/stdout:
+//!
+//! ```text
+//! -> {"id": 1, "operation": "lookup", "args": ["mdl_00000"]}
+//! <- {"id": 1, "result": {"operation": "lookup_by_id", "durationMs": 0.4, ...}}
+//! ```
+//!
+//! The pool size is read from `BENCH_NODE_POOL_SIZE` (default 2). A size of
+//! 0 disables the pool and spawns a process per operation, which is useful
+//! for comparing against older results.
+//!
+//! [`NodeWrapperPool::cancel`] kills every process the pool started, so a
+//! run blocked on a hung process returns once the runner's timeout expires.
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::adapters::Dependency;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// Pool size used when `BENCH_NODE_POOL_SIZE` is unset
+pub const DEFAULT_POOL_SIZE: usize = 2;
+
+#[derive(Serialize)]
+struct WrapperRequest<'a> {
+    id: u64,
+    operation: &'a str,
+    args: &'a [&'a str],
+}
+
+#[derive(Deserialize)]
+struct WrapperResponse {
+    id: Option<u64>,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+}
+
+/// A `node` process that can be killed from another thread
+type SharedChild = Arc<Mutex<Child>>;
+
+/// A wrapper process running in `--serve` mode
+struct WrapperProcess {
+    child: SharedChild,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl WrapperProcess {
+    fn spawn(wrapper_path: &str) -> Result<Self> {
+        let mut child = Command::new("node")
+            .args(["--no-warnings", wrapper_path, "--serve"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start wrapper process: {}", wrapper_path))?;
+
+        let stdin = child.stdin.take().context("Wrapper stdin not captured")?;
+        let stdout = child.stdout.take().context("Wrapper stdout not captured")?;
+
+        Ok(Self {
+            child: Arc::new(Mutex::new(child)),
+            stdin,
+            stdout: BufReader::new(stdout),
+            next_id: 1,
+        })
+    }
+
+    /// Send one request; errors here mean the process is no longer usable
+    fn call(&mut self, operation: &str, args: &[&str]) -> Result<WrapperResponse> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut line = serde_json::to_string(&WrapperRequest { id, operation, args })?;
+        line.push('\n');
+        self.stdin
+            .write_all(line.as_bytes())
+            .and_then(|_| self.stdin.flush())
+            .context("Failed to send request to wrapper process")?;
+
+        let mut response = String::new();
+        let read = self
+            .stdout
+            .read_line(&mut response)
+            .context("Failed to read response from wrapper process")?;
+        if read == 0 {
+            bail!("Wrapper process exited");
+        }
+
+        parse_response(id, &response)
+    }
+}
+
+impl Drop for WrapperProcess {
+    fn drop(&mut self) {
+        let mut child = self.child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+fn parse_response(id: u64, line: &str) -> Result<WrapperResponse> {
+    let response: WrapperResponse =
+        serde_json::from_str(line).context("Failed to parse wrapper response")?;
+
+    if response.id != Some(id) {
+        bail!("Wrapper response id {:?} does not match request {}", response.id, id);
+    }
+
+    Ok(response)
+}
+
+impl WrapperResponse {
+    fn into_result(self) -> Result<serde_json::Value> {
+        if let Some(error) = self.error {
+            bail!("CLI operation failed: {}", error);
+        }
+        self.result.context("Wrapper response has no result")
+    }
+}
+
+struct PoolState {
+    idle: Vec<WrapperProcess>,
+    /// Processes alive, idle or checked out
+    spawned: usize,
+}
+
+/// Pool of persistent wrapper processes for one TypeScript wrapper
+pub struct NodeWrapperPool {
+    wrapper_path: String,
+    size: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+    /// Every process started, for `cancel`
+    children: Mutex<Vec<Weak<Mutex<Child>>>>,
+    cancelled: AtomicBool,
+}
+
+impl NodeWrapperPool {
+    /// Create a pool for a wrapper script, sized from `BENCH_NODE_POOL_SIZE`
+    pub fn new(wrapper_path: String) -> Self {
+        let size = std::env::var("BENCH_NODE_POOL_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_POOL_SIZE);
+
+        Self::with_size(wrapper_path, size)
+    }
+
+    /// Create a pool with an explicit size; processes are started on demand
+    pub fn with_size(wrapper_path: String, size: usize) -> Self {
+        Self {
+            wrapper_path,
+            size,
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                spawned: 0,
+            }),
+            available: Condvar::new(),
+            children: Mutex::new(Vec::new()),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// The wrapper script, then a `node` that can load it: one that strips
+    /// TypeScript types, or with a loader set in `NODE_OPTIONS`
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        vec![
+            Dependency::File(PathBuf::from(&self.wrapper_path)),
+            Dependency::command("node", &["--no-warnings", "--check", &self.wrapper_path]),
+        ]
+    }
+
+    /// Maximum number of wrapper processes (0 = process per operation)
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Start all pool processes up front, so process startup isn't
+    /// attributed to the first measured operations. Clears an earlier
+    /// [`cancel`](Self::cancel), as each run starts here.
+    pub fn warm_up(&self) -> Result<()> {
+        self.cancelled.store(false, Ordering::SeqCst);
+        let mut state = self.state.lock().unwrap();
+        while state.spawned < self.size {
+            let process = self.spawn()?;
+            state.idle.push(process);
+            state.spawned += 1;
+        }
+        Ok(())
+    }
+
+    /// Kill every wrapper process, idle or busy, and fail operations until
+    /// the next [`warm_up`](Self::warm_up), so a run blocked on a hung
+    /// process returns instead of starting new ones
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        for child in self.children.lock().unwrap().drain(..) {
+            if let Some(child) = child.upgrade() {
+                let _ = child.lock().unwrap().kill();
+            }
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.spawned -= state.idle.len();
+        state.idle.clear();
+        drop(state);
+        self.available.notify_all();
+        log::warn!("Killed wrapper processes for {}", self.wrapper_path);
+    }
+
+    /// Run an operation and deserialize its metrics
+    pub fn call<T: DeserializeOwned>(&self, operation: &str, args: &[&str]) -> Result<T> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            bail!("Cancelled after the run timed out");
+        }
+        let value = if self.size == 0 {
+            self.call_once(operation, args)?
+        } else {
+            self.call_pooled(operation, args)?
+        };
+
+        serde_json::from_value(value).context("Failed to parse CLI output")
+    }
+
+    fn call_pooled(&self, operation: &str, args: &[&str]) -> Result<serde_json::Value> {
+        let mut process = self.checkout()?;
+        let response = process.call(operation, args);
+
+        let mut state = self.state.lock().unwrap();
+        match &response {
+            Ok(_) => state.idle.push(process),
+            Err(e) => {
+                log::warn!("Discarding wrapper process for {}: {}", self.wrapper_path, e);
+                state.spawned -= 1;
+                drop(process);
+            }
+        }
+        drop(state);
+        self.available.notify_one();
+
+        response?.into_result()
+    }
+
+    fn checkout(&self) -> Result<WrapperProcess> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(process) = state.idle.pop() {
+                return Ok(process);
+            }
+
+            if state.spawned < self.size {
+                state.spawned += 1;
+                drop(state);
+
+                log::debug!("Starting wrapper process for {}", self.wrapper_path);
+                return self.spawn().inspect_err(|_| {
+                    self.state.lock().unwrap().spawned -= 1;
+                    self.available.notify_one();
+                });
+            }
+
+            state = self.available.wait(state).unwrap();
+        }
+    }
+
+    fn spawn(&self) -> Result<WrapperProcess> {
+        let process = WrapperProcess::spawn(&self.wrapper_path)?;
+        self.track(&process.child);
+        Ok(process)
+    }
+
+    /// Remember a process for `cancel`, forgetting exited ones
+    fn track(&self, child: &SharedChild) {
+        let mut children = self.children.lock().unwrap();
+        children.retain(|child| child.strong_count() > 0);
+        children.push(Arc::downgrade(child));
+    }
+
+    /// Spawn a process for a single operation (pool disabled)
+    fn call_once(&self, operation: &str, args: &[&str]) -> Result<serde_json::Value> {
+        let mut cmd_args = vec!["--no-warnings", &self.wrapper_path, operation];
+        cmd_args.extend(args);
+
+        let mut child = Command::new("node")
+            .args(&cmd_args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute TypeScript wrapper")?;
+        let mut stdout = child.stdout.take().context("Wrapper stdout not captured")?;
+        let mut stderr = child.stderr.take().context("Wrapper stderr not captured")?;
+        let child = Arc::new(Mutex::new(child));
+        self.track(&child);
+
+        // Drain stderr alongside stdout so neither pipe fills up
+        let errors = thread::spawn(move || {
+            let mut errors = String::new();
+            let _ = stderr.read_to_string(&mut errors);
+            errors
+        });
+        let mut output = String::new();
+        stdout
+            .read_to_string(&mut output)
+            .context("Failed to read CLI output")?;
+        let status = wait(&child)?;
+        let errors = errors.join().unwrap_or_default();
+
+        if !status.success() {
+            bail!("CLI operation failed: {}", errors);
+        }
+
+        serde_json::from_str(&output).context("Failed to parse CLI output")
+    }
+}
+
+/// Wait for a process without holding its lock, so `cancel` can still kill
+/// it
+fn wait(child: &SharedChild) -> Result<ExitStatus> {
+    loop {
+        if let Some(status) = child
+            .lock()
+            .unwrap()
+            .try_wait()
+            .context("Failed to wait for TypeScript wrapper")?
+        {
+            return Ok(status);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let value = parse_response(3, r#"{"id":3,"result":{"durationMs":1.5}}"#)
+            .and_then(WrapperResponse::into_result)
+            .unwrap();
+        assert_eq!(value["durationMs"], 1.5);
+
+        let err = parse_response(3, r#"{"id":3,"error":"Error: Unknown operation: x"}"#)
+            .and_then(WrapperResponse::into_result)
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown operation"));
+
+        assert!(parse_response(4, r#"{"id":3,"result":{}}"#).is_err());
+        assert!(parse_response(3, "not json").is_err());
+    }
+}
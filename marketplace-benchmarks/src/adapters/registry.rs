@@ -0,0 +1,224 @@
+//! Benchmark target registry
+//!
+//! A [`TargetRegistry`] holds the targets a runner can pick from. The
+//! built-in registry has every adapter in this crate; downstream crates
+//! register their own targets next to them, or start from an empty one,
+//! without forking [`all_targets`]. Targets are looked up by id and grouped
+//! by the [`tags`](BenchTarget::tags) they declare, e.g. `marketplace` or
+//! `infra`.
+
+use crate::adapters::{all_targets, BenchTarget};
+use crate::benchmarks::run::{run_targets, BenchmarkRunSummary, RunConfig};
+use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Targets available to a run, in registration order
+///
+/// Targets are shared so the runner can hand a run to a thread of its own
+/// and stop waiting for it after a timeout.
+#[derive(Default)]
+pub struct TargetRegistry {
+    targets: Vec<Arc<dyn BenchTarget>>,
+}
+
+impl TargetRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry of every target in this crate
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use marketplace_benchmarks::TargetRegistry;
+    ///
+    /// let registry = TargetRegistry::builtin();
+    /// assert!(registry.get("example-benchmark").is_some());
+    /// ```
+    pub fn builtin() -> Self {
+        Self {
+            targets: all_targets().into_iter().map(Arc::from).collect(),
+        }
+    }
+
+    /// Add a target after the registered ones; ids must be unique
+    pub fn register(&mut self, target: Box<dyn BenchTarget>) -> Result<()> {
+        if self.get(target.id()).is_some() {
+            bail!("Benchmark target '{}' is already registered", target.id());
+        }
+        log::debug!("Registered benchmark target {}", target.id());
+        self.targets.push(Arc::from(target));
+        Ok(())
+    }
+
+    /// The registered targets, in registration order
+    pub fn into_targets(self) -> Vec<Arc<dyn BenchTarget>> {
+        self.targets
+    }
+
+    /// Take a target out of the registry
+    pub fn remove(&mut self, id: &str) -> Option<Arc<dyn BenchTarget>> {
+        let index = self.targets.iter().position(|target| target.id() == id)?;
+        Some(self.targets.remove(index))
+    }
+
+    /// The target with an id
+    pub fn get(&self, id: &str) -> Option<&dyn BenchTarget> {
+        self.iter().find(|target| target.id() == id)
+    }
+
+    /// Every target, in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &dyn BenchTarget> {
+        self.targets.iter().map(|target| target.as_ref())
+    }
+
+    /// Ids of every target, in registration order
+    pub fn ids(&self) -> Vec<&str> {
+        self.iter().map(|target| target.id()).collect()
+    }
+
+    /// Targets declaring a tag
+    pub fn tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a dyn BenchTarget> {
+        self.iter()
+            .filter(move |target| target.tags().contains(&tag))
+    }
+
+    /// Every tag declared by a target, sorted
+    pub fn tags(&self) -> Vec<&str> {
+        let tags: BTreeSet<&str> = self.iter().flat_map(|target| target.tags()).collect();
+        tags.into_iter().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Targets with one of `ids` and one of `tags`, in registration order
+    ///
+    /// An empty list doesn't filter. Unknown ids and tags are an error
+    /// rather than being skipped, so a typo in CI doesn't silently run
+    /// nothing.
+    pub fn select(&self, ids: &[String], tags: &[String]) -> Result<Vec<Arc<dyn BenchTarget>>> {
+        if let Some(unknown) = ids.iter().find(|id| self.get(id).is_none()) {
+            bail!(
+                "Unknown benchmark target '{}'; see the 'list' command",
+                unknown
+            );
+        }
+        let known = self.tags();
+        if let Some(unknown) = tags.iter().find(|tag| !known.contains(&tag.as_str())) {
+            bail!(
+                "No benchmark target is tagged '{}'; see the 'list' command",
+                unknown
+            );
+        }
+
+        Ok(self
+            .targets
+            .iter()
+            .filter(|target| ids.is_empty() || ids.iter().any(|id| id == target.id()))
+            .filter(|target| {
+                tags.is_empty()
+                    || target
+                        .tags()
+                        .iter()
+                        .any(|tag| tags.iter().any(|t| t == tag))
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Run the targets `config` selects. Only an unknown target id or tag
+    /// is an error; target failures are collected in the summary.
+    pub fn run(&self, config: &RunConfig) -> Result<BenchmarkRunSummary> {
+        let targets = self.select(&config.targets, &config.tags)?;
+        log::info!(
+            "Starting benchmark run for {} targets with {} jobs",
+            targets.len(),
+            config.jobs.max(1)
+        );
+        Ok(run_targets(
+            targets,
+            config.fail_fast,
+            config.jobs,
+            &config.overrides,
+            config.runs,
+            config.timeout,
+            config.profile_dir.as_deref(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::ExampleBenchmark;
+
+    #[test]
+    fn test_register_and_lookup() {
+        let mut registry = TargetRegistry::new();
+        assert!(registry.is_empty());
+
+        registry
+            .register(Box::new(ExampleBenchmark::new("downstream".to_string())))
+            .unwrap();
+        assert!(registry
+            .register(Box::new(ExampleBenchmark::new("downstream".to_string())))
+            .is_err());
+
+        assert_eq!(registry.ids(), ["downstream"]);
+        assert_eq!(registry.get("downstream").unwrap().id(), "downstream");
+        assert!(registry.get("missing").is_none());
+        assert!(registry.remove("downstream").is_some());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_select_by_id_and_tag() {
+        let registry = TargetRegistry::builtin();
+        assert_eq!(registry.len(), all_targets().len());
+        assert!(registry.tags().contains(&"marketplace"));
+        assert!(registry.tags().contains(&"infra"));
+
+        let infra = registry.select(&[], &["infra".to_string()]).unwrap();
+        assert_eq!(infra.len(), 1);
+        assert_eq!(infra[0].id(), "llm_infra_primitives");
+        assert_eq!(registry.tagged("infra").count(), 1);
+
+        let marketplace = registry.select(&[], &["marketplace".to_string()]).unwrap();
+        assert!(marketplace
+            .iter()
+            .all(|target| target.id().starts_with("marketplace_")));
+
+        let none = registry
+            .select(
+                &["llm_infra_primitives".to_string()],
+                &["marketplace".to_string()],
+            )
+            .unwrap();
+        assert!(none.is_empty());
+
+        assert!(registry
+            .select(&["no-such-target".to_string()], &[])
+            .is_err());
+        assert!(registry.select(&[], &["no-such-tag".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_run_registry() {
+        let mut registry = TargetRegistry::new();
+        registry
+            .register(Box::new(ExampleBenchmark::new("downstream".to_string())))
+            .unwrap();
+
+        let summary = registry.run(&RunConfig::default()).unwrap();
+        assert_eq!(summary.succeeded, ["downstream"]);
+    }
+}
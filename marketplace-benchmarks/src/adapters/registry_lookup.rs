@@ -2,14 +2,20 @@
 //!
 //! Benchmarks model registry lookup and resolution operations.
 
+use crate::benchmarks::histogram::Histogram;
 use crate::benchmarks::result::BenchmarkResult;
-use crate::adapters::BenchTarget;
-use anyhow::{Context, Result};
+use crate::adapters::node_pool::NodeWrapperPool;
+use crate::adapters::{BenchTarget, Dependency, TargetConfig};
+use anyhow::Result;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::process::Command;
 use std::time::Instant;
 
+/// Measured iterations across all operations by default
+const DEFAULT_ITERATIONS: usize = 135;
+
+const DEFAULT_CONFIG: TargetConfig = TargetConfig::new(0, DEFAULT_ITERATIONS);
+
 #[derive(Debug, Deserialize)]
 struct CliMetrics {
     operation: String,
@@ -22,48 +28,46 @@ struct CliMetrics {
 
 /// Benchmark adapter for model registry lookup operations
 pub struct RegistryLookupBenchmark {
-    wrapper_path: String,
+    wrapper: NodeWrapperPool,
 }
 
 impl RegistryLookupBenchmark {
     pub fn new() -> Self {
         let workspace_root = std::env::var("CARGO_MANIFEST_DIR")
             .unwrap_or_else(|_| ".".to_string());
-        let wrapper_path = format!("{}/ts-wrappers/registry-cli.ts", workspace_root);
+        Self::with_wrapper(format!("{}/ts-wrappers/registry-cli.ts", workspace_root))
+    }
 
-        Self { wrapper_path }
+    /// Run against a different build of the wrapper script
+    pub fn with_wrapper(wrapper_path: String) -> Self {
+        Self {
+            wrapper: NodeWrapperPool::new(wrapper_path),
+        }
     }
 
     fn run_cli_operation(&self, operation: &str, args: &[&str]) -> Result<CliMetrics> {
-        let mut cmd_args = vec!["--no-warnings", &self.wrapper_path, operation];
-        cmd_args.extend(args);
-
-        let output = Command::new("node")
-            .args(&cmd_args)
-            .output()
-            .context("Failed to execute TypeScript wrapper")?;
+        self.wrapper.call(operation, args)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("CLI operation failed: {}", stderr);
+    fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        // Start wrapper processes before timing anything
+        self.wrapper.warm_up()?;
+        for _ in 0..config.warmup_iterations {
+            self.run_cli_operation("lookup", &["mdl_00000"])?;
         }
+        let started = Instant::now();
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let metrics: CliMetrics = serde_json::from_str(&stdout)
-            .context("Failed to parse CLI output")?;
-
-        Ok(metrics)
-    }
-
-    fn execute_benchmark_suite(&self) -> Result<BenchmarkResult> {
         let mut all_durations = Vec::new();
         let mut total_items = 0;
         let mut operation_count = 0;
         let mut error_count = 0;
 
-        // Test 1: Model lookup by ID (50 iterations)
+        // Test 1: Model lookup by ID (50 of 135 iterations by default)
         log::info!("Running lookup operation...");
-        for i in 0..50 {
+        for i in (0..50).cycle().take(config.share(50, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let model_id = format!("mdl_{:05}", i * 5);
             let start = Instant::now();
             match self.run_cli_operation("lookup", &[&model_id]) {
@@ -81,9 +85,12 @@ impl RegistryLookupBenchmark {
             }
         }
 
-        // Test 2: Version resolution (30 iterations)
+        // Test 2: Version resolution (30 of 135 iterations by default)
         log::info!("Running resolve_version operation...");
-        for i in 0..30 {
+        for i in (0..30).cycle().take(config.share(30, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let model_id = format!("mdl_{:05}", i * 3);
             let version = format!("{}.{}.0", i / 10, i % 10 / 2);
             let start = Instant::now();
@@ -100,10 +107,13 @@ impl RegistryLookupBenchmark {
             }
         }
 
-        // Test 3: Search models (20 iterations with different filters)
+        // Test 3: Search models (20 of 135 iterations by default, with different filters)
         log::info!("Running search operation...");
         let categories = ["text-generation", "image-classification", "translation", "summarization"];
-        for i in 0..20 {
+        for i in (0..20).cycle().take(config.share(20, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let category = categories[i % categories.len()];
             let min_score = ((i % 5) * 10 + 50).to_string();
             let start = Instant::now();
@@ -120,9 +130,12 @@ impl RegistryLookupBenchmark {
             }
         }
 
-        // Test 4: Get model versions (25 iterations)
+        // Test 4: Get model versions (25 of 135 iterations by default)
         log::info!("Running get_versions operation...");
-        for i in 0..25 {
+        for i in (0..25).cycle().take(config.share(25, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let model_id = format!("mdl_{:05}", i * 4);
             let start = Instant::now();
             match self.run_cli_operation("get_versions", &[&model_id]) {
@@ -138,9 +151,12 @@ impl RegistryLookupBenchmark {
             }
         }
 
-        // Test 5: Bulk lookup (10 iterations)
+        // Test 5: Bulk lookup (10 of 135 iterations by default)
         log::info!("Running bulk_lookup operation...");
-        for i in 0..10 {
+        for i in (0..10).cycle().take(config.share(10, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let count = ((i + 1) * 20).to_string();
             let start = Instant::now();
             match self.run_cli_operation("bulk_lookup", &[&count]) {
@@ -202,9 +218,12 @@ impl RegistryLookupBenchmark {
         metrics.insert("total_items_processed".to_string(), total_items as f64);
 
         let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+        result.add_histogram("latency".to_string(), Histogram::from_samples(&all_durations));
 
         // Add metadata
-        result.add_metadata("wrapper_type".to_string(), "node_cli".to_string());
+        let wrapper_type = if self.wrapper.size() > 0 { "node_pool" } else { "node_cli" };
+        result.add_metadata("wrapper_type".to_string(), wrapper_type.to_string());
+        result.add_metadata("node_pool_size".to_string(), self.wrapper.size().to_string());
         result.add_metadata("test_suite".to_string(), "registry_lookup".to_string());
         result.add_metadata("iterations".to_string(), len.to_string());
 
@@ -229,9 +248,29 @@ impl BenchTarget for RegistryLookupBenchmark {
         "marketplace_registry_lookup"
     }
 
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace"]
+    }
+
     fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&DEFAULT_CONFIG)
+    }
+
+    fn config(&self) -> Option<TargetConfig> {
+        Some(DEFAULT_CONFIG)
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
         log::info!("Running registry lookup benchmark");
-        self.execute_benchmark_suite()
+        self.execute_benchmark_suite(config)
+    }
+
+    fn cancel(&self) {
+        self.wrapper.cancel();
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.wrapper.dependencies()
     }
 }
 
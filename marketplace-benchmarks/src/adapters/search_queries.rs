@@ -2,14 +2,20 @@
 //!
 //! Benchmarks discovery search operations including full-text, faceted, and recommendation queries.
 
+use crate::benchmarks::histogram::Histogram;
 use crate::benchmarks::result::BenchmarkResult;
-use crate::adapters::BenchTarget;
-use anyhow::{Context, Result};
+use crate::adapters::node_pool::NodeWrapperPool;
+use crate::adapters::{BenchTarget, Dependency, TargetConfig};
+use anyhow::Result;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::process::Command;
 use std::time::Instant;
 
+/// Measured iterations across all operations by default
+const DEFAULT_ITERATIONS: usize = 80;
+
+const DEFAULT_CONFIG: TargetConfig = TargetConfig::new(0, DEFAULT_ITERATIONS);
+
 #[derive(Debug, Deserialize)]
 struct CliMetrics {
     operation: String,
@@ -34,40 +40,35 @@ struct SearchStats {
 
 /// Benchmark adapter for discovery search operations
 pub struct SearchQueriesBenchmark {
-    wrapper_path: String,
+    wrapper: NodeWrapperPool,
 }
 
 impl SearchQueriesBenchmark {
     pub fn new() -> Self {
         let workspace_root = std::env::var("CARGO_MANIFEST_DIR")
             .unwrap_or_else(|_| ".".to_string());
-        let wrapper_path = format!("{}/ts-wrappers/search-cli.ts", workspace_root);
+        Self::with_wrapper(format!("{}/ts-wrappers/search-cli.ts", workspace_root))
+    }
 
-        Self { wrapper_path }
+    /// Run against a different build of the wrapper script
+    pub fn with_wrapper(wrapper_path: String) -> Self {
+        Self {
+            wrapper: NodeWrapperPool::new(wrapper_path),
+        }
     }
 
     fn run_cli_operation(&self, operation: &str, args: &[&str]) -> Result<CliMetrics> {
-        let mut cmd_args = vec!["--no-warnings", &self.wrapper_path, operation];
-        cmd_args.extend(args);
-
-        let output = Command::new("node")
-            .args(&cmd_args)
-            .output()
-            .context("Failed to execute TypeScript wrapper")?;
+        self.wrapper.call(operation, args)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("CLI operation failed: {}", stderr);
+    fn execute_benchmark_suite(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        // Start wrapper processes before timing anything
+        self.wrapper.warm_up()?;
+        for _ in 0..config.warmup_iterations {
+            self.run_cli_operation("aggregate", &[])?;
         }
+        let started = Instant::now();
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let metrics: CliMetrics = serde_json::from_str(&stdout)
-            .context("Failed to parse CLI output")?;
-
-        Ok(metrics)
-    }
-
-    fn execute_benchmark_suite(&self) -> Result<BenchmarkResult> {
         let mut all_durations = Vec::new();
         let mut total_items = 0;
         let mut operation_count = 0;
@@ -77,7 +78,7 @@ impl SearchQueriesBenchmark {
         let mut sum_avg_scores = 0.0_f64;
         let mut score_count = 0;
 
-        // Test 1: Full-text search with different queries (25 iterations)
+        // Test 1: Full-text search with different queries (25 of 80 iterations by default)
         log::info!("Running full-text search...");
         let search_queries = [
             "text generation",
@@ -92,7 +93,10 @@ impl SearchQueriesBenchmark {
             "nlp",
         ];
 
-        for i in 0..25 {
+        for i in (0..25).cycle().take(config.share(25, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let query = search_queries[i % search_queries.len()];
             let limit = ((i % 3) * 10 + 10).to_string();
             let start = Instant::now();
@@ -120,12 +124,15 @@ impl SearchQueriesBenchmark {
             }
         }
 
-        // Test 2: Faceted search with different filters (20 iterations)
+        // Test 2: Faceted search with different filters (20 of 80 iterations by default)
         log::info!("Running faceted search...");
         let categories = ["ai-models", "data-processing", "analytics", "storage", "compute"];
         let tag_sets = ["nlp,vision", "audio", "multimodal,analytics", "nlp"];
 
-        for i in 0..20 {
+        for i in (0..20).cycle().take(config.share(20, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let mut args = vec![];
 
             if i % 3 != 0 {
@@ -157,9 +164,12 @@ impl SearchQueriesBenchmark {
             }
         }
 
-        // Test 3: Recommendation queries (15 iterations)
+        // Test 3: Recommendation queries (15 of 80 iterations by default)
         log::info!("Running recommendation queries...");
-        for i in 0..15 {
+        for i in (0..15).cycle().take(config.share(15, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let user_id = format!("user_{}", i);
             let limit = ((i % 2) * 5 + 10).to_string();
             let start = Instant::now();
@@ -177,9 +187,12 @@ impl SearchQueriesBenchmark {
             }
         }
 
-        // Test 4: Category aggregation (10 iterations)
+        // Test 4: Category aggregation (10 of 80 iterations by default)
         log::info!("Running category aggregation...");
-        for i in 0..10 {
+        for i in (0..10).cycle().take(config.share(10, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let start = Instant::now();
 
             match self.run_cli_operation("aggregate", &[]) {
@@ -195,9 +208,12 @@ impl SearchQueriesBenchmark {
             }
         }
 
-        // Test 5: Multi-query search (10 iterations)
+        // Test 5: Multi-query search (10 of 80 iterations by default)
         log::info!("Running multi-query search...");
-        for i in 0..10 {
+        for i in (0..10).cycle().take(config.share(10, DEFAULT_ITERATIONS)) {
+            if config.expired(started) {
+                break;
+            }
             let queries = match i % 3 {
                 0 => vec!["text", "image", "audio"],
                 1 => vec!["nlp", "vision"],
@@ -276,9 +292,12 @@ impl SearchQueriesBenchmark {
         metrics.insert("avg_search_score".to_string(), avg_search_score);
 
         let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+        result.add_histogram("latency".to_string(), Histogram::from_samples(&all_durations));
 
         // Add metadata
-        result.add_metadata("wrapper_type".to_string(), "node_cli".to_string());
+        let wrapper_type = if self.wrapper.size() > 0 { "node_pool" } else { "node_cli" };
+        result.add_metadata("wrapper_type".to_string(), wrapper_type.to_string());
+        result.add_metadata("node_pool_size".to_string(), self.wrapper.size().to_string());
         result.add_metadata("test_suite".to_string(), "search_queries".to_string());
         result.add_metadata("iterations".to_string(), len.to_string());
         result.add_metadata("search_types".to_string(), "full_text,faceted,recommendations,aggregation,multi_query".to_string());
@@ -304,9 +323,29 @@ impl BenchTarget for SearchQueriesBenchmark {
         "marketplace_search_queries"
     }
 
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace"]
+    }
+
     fn run(&self) -> Result<BenchmarkResult> {
+        self.run_with(&DEFAULT_CONFIG)
+    }
+
+    fn config(&self) -> Option<TargetConfig> {
+        Some(DEFAULT_CONFIG)
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
         log::info!("Running search queries benchmark");
-        self.execute_benchmark_suite()
+        self.execute_benchmark_suite(config)
+    }
+
+    fn cancel(&self) {
+        self.wrapper.cancel();
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.wrapper.dependencies()
     }
 }
 
@@ -0,0 +1,377 @@
+//! Tenant Contention Benchmark Adapter
+//!
+//! Simulates many tenants with mixed tiers issuing interleaved requests
+//! against the rate limiter and quota manager at the same time. Each tenant
+//! offers more load than its tier allows, so the limiter decides who gets
+//! through. The benchmark reports how close each tenant gets to its
+//! entitlement (fairness) and how much admission latency grows under
+//! contention compared to a single uncontended tenant.
+//!
+//! Limiter and quota state live behind one shared lock each, standing in for
+//! the single Redis instance every consumption replica talks to.
+
+use crate::adapters::admission_overhead::TokenBucket;
+use crate::adapters::BenchTarget;
+use crate::benchmarks::histogram::percentile;
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default number of simulated tenants
+const DEFAULT_TENANTS: usize = 12;
+
+/// Default measurement window per run
+const DEFAULT_DURATION_MS: u64 = 2000;
+
+/// Offered load relative to each tenant's entitled rate
+const OVERSUBSCRIPTION: f64 = 2.0;
+
+/// Tokens charged against the quota per admitted request
+const TOKENS_PER_REQUEST: i64 = 10;
+
+/// Tier limits mirroring the consumption service's `ServiceTier`
+#[derive(Debug, Clone, Copy)]
+struct Tier {
+    name: &'static str,
+    rate_per_sec: f64,
+    burst: f64,
+    quota: i64,
+}
+
+const TIERS: [Tier; 3] = [
+    Tier {
+        name: "basic",
+        rate_per_sec: 10.0,
+        burst: 20.0,
+        quota: 100_000,
+    },
+    Tier {
+        name: "premium",
+        rate_per_sec: 100.0,
+        burst: 200.0,
+        quota: 10_000_000,
+    },
+    Tier {
+        name: "enterprise",
+        rate_per_sec: 1000.0,
+        burst: 2000.0,
+        quota: 1_000_000_000,
+    },
+];
+
+/// Shared admission state, one lock per backing store
+struct AdmissionState {
+    buckets: Mutex<HashMap<usize, TokenBucket>>,
+    quota_used: Mutex<HashMap<usize, i64>>,
+}
+
+impl AdmissionState {
+    /// Buckets start drained, so a run measures the sustained rate rather
+    /// than the burst allowance
+    fn new(tenants: &[Tier]) -> Self {
+        let buckets = tenants
+            .iter()
+            .enumerate()
+            .map(|(id, tier)| {
+                let mut bucket = TokenBucket::new(tier.burst, tier.rate_per_sec);
+                while bucket.try_acquire() {}
+                (id, bucket)
+            })
+            .collect();
+
+        Self {
+            buckets: Mutex::new(buckets),
+            quota_used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Rate limit then quota check, as in the consumption pipeline
+    fn admit(&self, tenant: usize, tier: &Tier) -> bool {
+        let allowed = self
+            .buckets
+            .lock()
+            .unwrap()
+            .get_mut(&tenant)
+            .map(TokenBucket::try_acquire)
+            .unwrap_or(false);
+        if !allowed {
+            return false;
+        }
+
+        let mut quota_used = self.quota_used.lock().unwrap();
+        let used = quota_used.entry(tenant).or_insert(0);
+        if *used + TOKENS_PER_REQUEST > tier.quota {
+            return false;
+        }
+        *used += TOKENS_PER_REQUEST;
+        true
+    }
+}
+
+/// What one tenant observed during a run
+#[derive(Debug, Default)]
+struct TenantOutcome {
+    attempts: usize,
+    admitted: usize,
+    latencies_us: Vec<f64>,
+}
+
+/// Benchmark adapter for multi-tenant rate limiter and quota contention
+pub struct TenantContentionBenchmark {
+    tenants: usize,
+    duration: Duration,
+}
+
+impl TenantContentionBenchmark {
+    pub fn new() -> Self {
+        let tenants = std::env::var("TENANT_CONTENTION_TENANTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_TENANTS);
+        let duration_ms = std::env::var("TENANT_CONTENTION_DURATION_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_DURATION_MS);
+
+        Self::with_config(tenants, Duration::from_millis(duration_ms))
+    }
+
+    pub fn with_config(tenants: usize, duration: Duration) -> Self {
+        Self { tenants, duration }
+    }
+
+    /// Jain's fairness index: 1.0 when every value is equal, 1/n when one
+    /// tenant gets everything
+    fn fairness_index(values: &[f64]) -> f64 {
+        let sum: f64 = values.iter().sum();
+        let sum_sq: f64 = values.iter().map(|v| v * v).sum();
+        if sum_sq == 0.0 {
+            return 0.0;
+        }
+        (sum * sum) / (values.len() as f64 * sum_sq)
+    }
+
+    /// Fraction of its entitled rate a tenant achieved
+    fn entitlement_ratio(admitted: usize, tier: &Tier, duration: Duration) -> f64 {
+        admitted as f64 / duration.as_secs_f64() / tier.rate_per_sec
+    }
+
+    /// Run tenants concurrently, each offering paced load above its entitlement
+    fn run_tenants(tiers: &[Tier], duration: Duration) -> Vec<TenantOutcome> {
+        let state = Arc::new(AdmissionState::new(tiers));
+        let start = Instant::now();
+
+        let handles: Vec<_> = tiers
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(tenant, tier)| {
+                let state = state.clone();
+                thread::spawn(move || {
+                    let interval =
+                        Duration::from_secs_f64(1.0 / (tier.rate_per_sec * OVERSUBSCRIPTION));
+                    let mut outcome = TenantOutcome::default();
+                    let mut next = start;
+
+                    while start.elapsed() < duration {
+                        let now = Instant::now();
+                        if now < next {
+                            thread::sleep(next - now);
+                        }
+                        next += interval;
+
+                        let begin = Instant::now();
+                        let admitted = state.admit(tenant, &tier);
+                        outcome
+                            .latencies_us
+                            .push(begin.elapsed().as_secs_f64() * 1_000_000.0);
+
+                        outcome.attempts += 1;
+                        if admitted {
+                            outcome.admitted += 1;
+                        }
+                    }
+
+                    outcome
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    }
+
+    fn execute_benchmark_suite(&self) -> Result<BenchmarkResult> {
+        let tiers: Vec<Tier> = (0..self.tenants).map(|i| TIERS[i % TIERS.len()]).collect();
+
+        // Baseline: a single enterprise tenant alone, so the locks are
+        // never contended
+        log::info!("Measuring uncontended admission latency...");
+        let mut baseline = Self::run_tenants(&TIERS[TIERS.len() - 1..], self.duration / 4)
+            .pop()
+            .unwrap_or_default()
+            .latencies_us;
+        baseline.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        log::info!(
+            "Running {} tenants concurrently for {:?}...",
+            self.tenants,
+            self.duration
+        );
+        let outcomes = Self::run_tenants(&tiers, self.duration);
+
+        let ratios: Vec<f64> = outcomes
+            .iter()
+            .zip(&tiers)
+            .map(|(outcome, tier)| Self::entitlement_ratio(outcome.admitted, tier, self.duration))
+            .collect();
+
+        let mut latencies: Vec<f64> = outcomes
+            .iter()
+            .flat_map(|outcome| outcome.latencies_us.iter().copied())
+            .collect();
+        latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let attempts: usize = outcomes.iter().map(|o| o.attempts).sum();
+        let admitted: usize = outcomes.iter().map(|o| o.admitted).sum();
+
+        let mut metrics = HashMap::new();
+
+        for tier in TIERS {
+            let tier_ratios: Vec<f64> = ratios
+                .iter()
+                .zip(&tiers)
+                .filter(|(_, t)| t.name == tier.name)
+                .map(|(ratio, _)| *ratio)
+                .collect();
+            if tier_ratios.is_empty() {
+                continue;
+            }
+
+            let avg_ratio = tier_ratios.iter().sum::<f64>() / tier_ratios.len() as f64;
+            metrics.insert(format!("entitlement_ratio_{}", tier.name), avg_ratio);
+            metrics.insert(
+                format!("achieved_rps_{}", tier.name),
+                avg_ratio * tier.rate_per_sec,
+            );
+            metrics.insert(format!("entitled_rps_{}", tier.name), tier.rate_per_sec);
+        }
+
+        let baseline_p99 = percentile(&baseline, 99);
+        let contended_p99 = percentile(&latencies, 99);
+
+        metrics.insert("fairness_index".to_string(), Self::fairness_index(&ratios));
+        metrics.insert(
+            "min_entitlement_ratio".to_string(),
+            ratios.iter().copied().fold(f64::INFINITY, f64::min),
+        );
+        metrics.insert(
+            "max_entitlement_ratio".to_string(),
+            ratios.iter().copied().fold(0.0, f64::max),
+        );
+        metrics.insert("admission_us_p50".to_string(), percentile(&latencies, 50));
+        metrics.insert("admission_us_p95".to_string(), percentile(&latencies, 95));
+        metrics.insert("admission_us_p99".to_string(), contended_p99);
+        metrics.insert("uncontended_admission_us_p99".to_string(), baseline_p99);
+        metrics.insert(
+            "contention_latency_ratio".to_string(),
+            if baseline_p99 > 0.0 {
+                contended_p99 / baseline_p99
+            } else {
+                0.0
+            },
+        );
+        metrics.insert(
+            "throttled_rate".to_string(),
+            if attempts > 0 {
+                (attempts - admitted) as f64 / attempts as f64
+            } else {
+                0.0
+            },
+        );
+        metrics.insert("tenants".to_string(), self.tenants as f64);
+        metrics.insert("operation_count".to_string(), attempts as f64);
+        metrics.insert("error_rate".to_string(), 0.0);
+
+        let mut result = BenchmarkResult::new(self.id().to_string(), metrics);
+
+        result.add_metadata("wrapper_type".to_string(), "in_process".to_string());
+        result.add_metadata("test_suite".to_string(), "tenant_contention".to_string());
+        result.add_metadata(
+            "duration_ms".to_string(),
+            self.duration.as_millis().to_string(),
+        );
+        result.add_metadata("oversubscription".to_string(), OVERSUBSCRIPTION.to_string());
+        result.add_metadata("iterations".to_string(), attempts.to_string());
+
+        if let Ok(hostname) = hostname::get() {
+            if let Some(hostname_str) = hostname.to_str() {
+                result.add_metadata("hostname".to_string(), hostname_str.to_string());
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Default for TenantContentionBenchmark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BenchTarget for TenantContentionBenchmark {
+    fn id(&self) -> &str {
+        "marketplace_tenant_contention"
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        vec!["marketplace", "consumption"]
+    }
+
+    fn run(&self) -> Result<BenchmarkResult> {
+        log::info!("Running tenant contention benchmark");
+        self.execute_benchmark_suite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_id() {
+        let bench = TenantContentionBenchmark::new();
+        assert_eq!(bench.id(), "marketplace_tenant_contention");
+    }
+
+    #[test]
+    fn test_fairness_index() {
+        assert_eq!(
+            TenantContentionBenchmark::fairness_index(&[1.0, 1.0, 1.0]),
+            1.0
+        );
+        assert_eq!(
+            TenantContentionBenchmark::fairness_index(&[1.0, 0.0, 0.0, 0.0]),
+            0.25
+        );
+    }
+
+    #[test]
+    fn test_reports_fairness_per_tier() {
+        let result = TenantContentionBenchmark::with_config(6, Duration::from_millis(300))
+            .run()
+            .unwrap();
+
+        let fairness = result.get_metric("fairness_index").unwrap();
+        assert!(fairness > 0.0 && fairness <= 1.0);
+        assert!(result.get_metric("entitlement_ratio_enterprise").is_some());
+        assert!(result.get_metric("throttled_rate").unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,361 @@
+//! Baseline comparison and regression detection
+//!
+//! Compares a candidate result set with a baseline, metric by metric, and
+//! flags regressions against thresholds declared one per line:
+//!
+//! ```text
+//! # comments start with '#'
+//! latency_p95 > +10%      # regression if it grows by more than 10%
+//! throughput < -5%        # regression if it drops by more than 5%
+//! error_rate > +0.01      # without '%' the limit is an absolute change
+//! *_ns_p99 > +10%         # '*' matches any part of a metric name
+//! ```
+//!
+//! The first matching rule applies to a metric, so specific rules go before
+//! wildcards. Metrics no rule matches are compared but never flagged. When a
+//! set holds several results for a target, the latest one is used.
+
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Default location of the regression thresholds config
+pub const DEFAULT_THRESHOLDS_PATH: &str = "benchmarks/regression_thresholds.conf";
+
+/// Which way a metric has to move to regress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Increase,
+    Decrease,
+}
+
+/// A regression rule: `pattern > +limit[%]` or `pattern < -limit[%]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegressionThreshold {
+    pub pattern: String,
+    pub direction: Direction,
+    /// Allowed change, always positive
+    pub limit: f64,
+    /// Whether `limit` is a percentage of the baseline or an absolute change
+    pub percent: bool,
+}
+
+impl RegressionThreshold {
+    /// Parse a single rule
+    pub fn parse(rule: &str) -> Result<Self> {
+        let (pattern, direction, limit) = if let Some((pattern, limit)) = rule.split_once('>') {
+            (pattern, Direction::Increase, limit.trim().strip_prefix('+'))
+        } else if let Some((pattern, limit)) = rule.split_once('<') {
+            (pattern, Direction::Decrease, limit.trim().strip_prefix('-'))
+        } else {
+            bail!("Expected 'metric > +N%' or 'metric < -N%', got: {}", rule);
+        };
+
+        let pattern = pattern.trim();
+        if pattern.is_empty()
+            || !pattern
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '*')
+        {
+            bail!("Invalid metric pattern: {:?}", pattern);
+        }
+
+        let limit = limit.with_context(|| {
+            format!("Limit must be signed ('> +N' or '< -N'): {}", rule)
+        })?;
+        let (limit, percent) = match limit.strip_suffix('%') {
+            Some(limit) => (limit, true),
+            None => (limit, false),
+        };
+        let limit: f64 = limit
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid limit in: {}", rule))?;
+        if !limit.is_finite() || limit < 0.0 {
+            bail!("Limit must be a non-negative number: {}", rule);
+        }
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            direction,
+            limit,
+            percent,
+        })
+    }
+
+    /// Whether the rule applies to a metric name
+    pub fn matches(&self, metric: &str) -> bool {
+        glob_match(&self.pattern, metric)
+    }
+
+    /// Whether a change breaks the rule; percentage rules can't judge a
+    /// metric whose baseline is zero
+    pub fn is_regression(&self, delta: f64, delta_percent: Option<f64>) -> bool {
+        let change = if self.percent {
+            match delta_percent {
+                Some(percent) => percent,
+                None => return false,
+            }
+        } else {
+            delta
+        };
+
+        match self.direction {
+            Direction::Increase => change > self.limit,
+            Direction::Decrease => -change > self.limit,
+        }
+    }
+}
+
+impl fmt::Display for RegressionThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (op, sign) = match self.direction {
+            Direction::Increase => ('>', '+'),
+            Direction::Decrease => ('<', '-'),
+        };
+        let unit = if self.percent { "%" } else { "" };
+        write!(f, "{} {} {}{}{}", self.pattern, op, sign, self.limit, unit)
+    }
+}
+
+/// `*` matches any run of characters, everything else matches itself
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Parse thresholds from config text, ignoring blank lines and `#` comments
+pub fn parse_thresholds(config: &str) -> Result<Vec<RegressionThreshold>> {
+    config
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or("").trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| {
+            RegressionThreshold::parse(line).with_context(|| format!("Line {}", number))
+        })
+        .collect()
+}
+
+/// Load thresholds from a config file
+///
+/// With no explicit path, uses `DEFAULT_THRESHOLDS_PATH` and returns no
+/// thresholds if it doesn't exist.
+pub fn load_thresholds(path: Option<&Path>) -> Result<Vec<RegressionThreshold>> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let default = Path::new(DEFAULT_THRESHOLDS_PATH);
+            if !default.exists() {
+                return Ok(Vec::new());
+            }
+            default
+        }
+    };
+
+    let config = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read regression thresholds: {:?}", path))?;
+    let thresholds = parse_thresholds(&config)
+        .with_context(|| format!("Failed to parse regression thresholds: {:?}", path))?;
+
+    log::info!("Loaded {} regression thresholds from {:?}", thresholds.len(), path);
+    Ok(thresholds)
+}
+
+/// A metric's values in the baseline and candidate sets
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricDelta {
+    pub target_id: String,
+    pub metric: String,
+    pub baseline: f64,
+    pub candidate: f64,
+    pub delta: f64,
+    /// Relative change in percent, `None` when the baseline is zero
+    pub delta_percent: Option<f64>,
+    /// The rule applied to this metric, if any
+    pub threshold: Option<String>,
+    pub regression: bool,
+}
+
+/// Outcome of comparing a candidate result set with a baseline
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ComparisonReport {
+    /// Metrics present in both sets, sorted by target and metric
+    pub deltas: Vec<MetricDelta>,
+    /// Targets in the baseline without a candidate result
+    pub missing_targets: Vec<String>,
+    /// Targets with a candidate result but no baseline
+    pub new_targets: Vec<String>,
+}
+
+impl ComparisonReport {
+    pub fn regressions(&self) -> impl Iterator<Item = &MetricDelta> {
+        self.deltas.iter().filter(|delta| delta.regression)
+    }
+
+    pub fn has_regressions(&self) -> bool {
+        self.regressions().next().is_some()
+    }
+}
+
+/// Latest result of each target
+fn latest_by_target(results: &[BenchmarkResult]) -> BTreeMap<&str, &BenchmarkResult> {
+    let mut latest: BTreeMap<&str, &BenchmarkResult> = BTreeMap::new();
+    for result in results {
+        let entry = latest.entry(&result.target_id).or_insert(result);
+        if result.timestamp > entry.timestamp {
+            *entry = result;
+        }
+    }
+    latest
+}
+
+/// Compare every metric the two sets share and flag regressions
+pub fn compare_results(
+    baseline: &[BenchmarkResult],
+    candidate: &[BenchmarkResult],
+    thresholds: &[RegressionThreshold],
+) -> ComparisonReport {
+    let baseline = latest_by_target(baseline);
+    let candidate = latest_by_target(candidate);
+    let mut report = ComparisonReport::default();
+
+    for (target_id, base) in &baseline {
+        let Some(head) = candidate.get(target_id) else {
+            report.missing_targets.push(target_id.to_string());
+            continue;
+        };
+
+        let metrics: BTreeSet<&String> = base.metrics.keys().collect();
+        for metric in metrics {
+            let (Some(base_value), Some(head_value)) =
+                (base.get_metric(metric), head.get_metric(metric))
+            else {
+                continue;
+            };
+
+            let delta = head_value - base_value;
+            let delta_percent = (base_value != 0.0).then(|| delta / base_value.abs() * 100.0);
+            let threshold = thresholds.iter().find(|threshold| threshold.matches(metric));
+
+            report.deltas.push(MetricDelta {
+                target_id: target_id.to_string(),
+                metric: metric.clone(),
+                baseline: base_value,
+                candidate: head_value,
+                delta,
+                delta_percent,
+                threshold: threshold.map(|threshold| threshold.to_string()),
+                regression: threshold
+                    .is_some_and(|threshold| threshold.is_regression(delta, delta_percent)),
+            });
+        }
+    }
+
+    report.new_targets = candidate
+        .keys()
+        .filter(|target_id| !baseline.contains_key(*target_id))
+        .map(|target_id| target_id.to_string())
+        .collect();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn result(target: &str, metrics: &[(&str, f64)]) -> BenchmarkResult {
+        let metrics: HashMap<String, f64> =
+            metrics.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        BenchmarkResult::new(target.to_string(), metrics)
+    }
+
+    #[test]
+    fn test_parse_thresholds() {
+        let thresholds = parse_thresholds(
+            "# comment\nlatency_p95 > +10%\nthroughput < -5%  # drops\nerror_rate > +0.01\n",
+        )
+        .unwrap();
+        assert_eq!(thresholds.len(), 3);
+        assert_eq!(thresholds[0].to_string(), "latency_p95 > +10%");
+        assert_eq!(thresholds[1].direction, Direction::Decrease);
+        assert!(!thresholds[2].percent);
+
+        assert!(parse_thresholds("latency_p95 > 10%").is_err());
+        assert!(parse_thresholds("latency_p95 = +10%").is_err());
+        assert!(parse_thresholds("latency p95 > +10%").is_err());
+
+        assert!(glob_match("*_ns_p99", "breaker_op_ns_p99"));
+        assert!(glob_match("latency_*", "latency_p50"));
+        assert!(!glob_match("latency_*_ms", "latency_p50"));
+        assert!(!glob_match("a*a", "a"));
+    }
+
+    #[test]
+    fn test_compare_results_flags_regressions() {
+        let thresholds = parse_thresholds(
+            "latency_p95 > +10%\nthroughput < -5%\nerror_rate > +0.01\nlatency_* > +50%",
+        )
+        .unwrap();
+
+        let baseline = vec![
+            result(
+                "search",
+                &[
+                    ("latency_p50", 10.0),
+                    ("latency_p95", 20.0),
+                    ("throughput", 1000.0),
+                    ("error_rate", 0.0),
+                ],
+            ),
+            result("retired", &[("latency_p95", 5.0)]),
+        ];
+        let candidate = vec![
+            result(
+                "search",
+                &[
+                    ("latency_p50", 14.0),
+                    ("latency_p95", 23.0),
+                    ("throughput", 960.0),
+                    ("error_rate", 0.02),
+                ],
+            ),
+            result("new", &[("latency_p95", 1.0)]),
+        ];
+
+        let report = compare_results(&baseline, &candidate, &thresholds);
+        assert_eq!(report.missing_targets, vec!["retired"]);
+        assert_eq!(report.new_targets, vec!["new"]);
+
+        let regressions: Vec<&str> = report.regressions().map(|d| d.metric.as_str()).collect();
+        // p50 is +40%, under the wildcard's 50%; throughput is -4%
+        assert_eq!(regressions, vec!["error_rate", "latency_p95"]);
+
+        let p95 = report.deltas.iter().find(|d| d.metric == "latency_p95").unwrap();
+        assert_eq!(p95.delta, 3.0);
+        assert_eq!(p95.delta_percent, Some(15.0));
+        assert_eq!(p95.threshold.as_deref(), Some("latency_p95 > +10%"));
+    }
+}
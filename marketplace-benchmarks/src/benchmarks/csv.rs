@@ -0,0 +1,178 @@
+//! CSV export
+//!
+//! Flattens results into one row per target and metric, for spreadsheets
+//! and BI tools. Every row carries the result's timestamp and a column per
+//! metadata key found in any of the results, empty where a result lacks it.
+//! Fields are quoted as in RFC 4180.
+
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Columns preceding the metadata columns
+const FIXED_COLUMNS: [&str; 4] = ["target_id", "metric", "value", "timestamp"];
+
+/// Renders results as CSV, rows sorted by target and metric
+///
+/// # Example
+///
+/// ```
+/// use marketplace_benchmarks::{generate_csv, BenchmarkResult};
+/// use std::collections::HashMap;
+///
+/// let mut metrics = HashMap::new();
+/// metrics.insert("latency_p50".to_string(), 12.5);
+///
+/// let result = BenchmarkResult::new("api-gateway".to_string(), metrics);
+/// let csv = generate_csv(&[result]);
+/// assert!(csv.starts_with("target_id,metric,value,timestamp\n"));
+/// assert!(csv.contains("api-gateway,latency_p50,12.5,"));
+/// ```
+pub fn generate_csv(results: &[BenchmarkResult]) -> String {
+    let metadata_keys: BTreeSet<&str> = results
+        .iter()
+        .flat_map(|result| result.metadata.keys().map(String::as_str))
+        .filter(|key| !FIXED_COLUMNS.contains(key))
+        .collect();
+
+    let header: Vec<&str> = FIXED_COLUMNS
+        .iter()
+        .copied()
+        .chain(metadata_keys.iter().copied())
+        .collect();
+    let mut out = row(header);
+
+    let mut sorted: Vec<&BenchmarkResult> = results.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.target_id
+            .cmp(&b.target_id)
+            .then(a.timestamp.cmp(&b.timestamp))
+    });
+
+    for result in sorted {
+        let timestamp = result.timestamp.to_rfc3339();
+        let mut metrics: Vec<(&String, &f64)> = result.metrics.iter().collect();
+        metrics.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (metric, value) in metrics {
+            // Non-finite values are left empty rather than written as NaN
+            let value = if value.is_finite() {
+                value.to_string()
+            } else {
+                String::new()
+            };
+            let fields = [
+                result.target_id.as_str(),
+                metric.as_str(),
+                value.as_str(),
+                timestamp.as_str(),
+            ]
+            .into_iter()
+            .chain(
+                metadata_keys
+                    .iter()
+                    .map(|key| result.metadata.get(*key).map(String::as_str).unwrap_or("")),
+            );
+            out.push_str(&row(fields));
+        }
+    }
+
+    out
+}
+
+/// Writes results as CSV to `path`, creating its parent directory
+pub fn export_results(results: &[BenchmarkResult], path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output directory: {:?}", parent))?;
+    }
+
+    fs::write(path, generate_csv(results))
+        .with_context(|| format!("Failed to write CSV to {:?}", path))?;
+
+    log::info!("Exported {} results to {:?}", results.len(), path);
+    Ok(())
+}
+
+/// One CSV line
+fn row<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let fields: Vec<String> = fields.into_iter().map(escape).collect();
+    fields.join(",") + "\n"
+}
+
+/// Quote a field holding a separator, quote or line break
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_rows_and_metadata_columns() {
+        let mut search = BenchmarkResult::new(
+            "search".to_string(),
+            HashMap::from([
+                ("throughput".to_string(), 950.0),
+                ("latency_p95".to_string(), 21.5),
+                ("error_rate".to_string(), f64::NAN),
+            ]),
+        );
+        search.add_metadata("git_commit".to_string(), "abc123".to_string());
+        search.add_metadata("notes".to_string(), "warm cache, \"fast\" disk".to_string());
+
+        let mut gateway = BenchmarkResult::new(
+            "api-gateway".to_string(),
+            HashMap::from([("latency_p50".to_string(), 12.0)]),
+        );
+        gateway.add_metadata("hostname".to_string(), "bench-1".to_string());
+
+        let csv = generate_csv(&[search.clone(), gateway.clone()]);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "target_id,metric,value,timestamp,git_commit,hostname,notes"
+        );
+        assert_eq!(
+            lines[1],
+            format!(
+                "api-gateway,latency_p50,12,{},,bench-1,",
+                gateway.timestamp.to_rfc3339()
+            )
+        );
+        let search_row = |metric: &str, value: &str| {
+            format!(
+                "search,{},{},{},abc123,,\"warm cache, \"\"fast\"\" disk\"",
+                metric,
+                value,
+                search.timestamp.to_rfc3339()
+            )
+        };
+        assert_eq!(lines[2], search_row("error_rate", ""));
+        assert_eq!(lines[3], search_row("latency_p95", "21.5"));
+        assert_eq!(lines[4], search_row("throughput", "950"));
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn test_export_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("exports").join("results.csv");
+        let result = BenchmarkResult::new(
+            "search".to_string(),
+            HashMap::from([("latency_p95".to_string(), 21.5)]),
+        );
+
+        export_results(std::slice::from_ref(&result), &path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), generate_csv(&[result]));
+    }
+}
@@ -0,0 +1,356 @@
+//! Derived metric rules
+//!
+//! Derived metrics are declared in a config file, one rule per line:
+//!
+//! ```text
+//! # comments start with '#'
+//! cost_per_1k_ops = estimated_cost_usd / (operation_count / 1000)
+//! p99_to_p50 = latency_p99 / latency_p50
+//! ```
+//!
+//! After a run, each rule is evaluated against every result's metrics and
+//! the value is added as a regular metric. Rules run in file order, so a
+//! rule may reference metrics derived by earlier rules. A rule is skipped
+//! for results missing one of its inputs, or when the value isn't finite
+//! (e.g. division by zero).
+
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Default location of the derived metrics config
+pub const DEFAULT_DERIVED_METRICS_PATH: &str = "benchmarks/derived_metrics.conf";
+
+/// Metadata key listing the derived metrics added to a result
+pub const DERIVED_METRICS_METADATA_KEY: &str = "derived_metrics";
+
+/// A parsed arithmetic expression over metric names
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Metric(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Expr {
+    fn eval(&self, metrics: &HashMap<String, f64>) -> Option<f64> {
+        match self {
+            Expr::Number(value) => Some(*value),
+            Expr::Metric(name) => metrics.get(name).copied(),
+            Expr::Neg(inner) => inner.eval(metrics).map(|value| -value),
+            Expr::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(metrics)?, rhs.eval(metrics)?);
+                Some(match op {
+                    Op::Add => lhs + rhs,
+                    Op::Sub => lhs - rhs,
+                    Op::Mul => lhs * rhs,
+                    Op::Div => lhs / rhs,
+                })
+            }
+        }
+    }
+}
+
+/// A derived metric rule: `name = expression`
+#[derive(Debug, Clone)]
+pub struct DerivedMetric {
+    pub name: String,
+    pub expression: String,
+    parsed: Expr,
+}
+
+impl DerivedMetric {
+    /// Parse a single `name = expression` rule
+    pub fn parse(rule: &str) -> Result<Self> {
+        let (name, expression) = rule
+            .split_once('=')
+            .with_context(|| format!("Expected 'name = expression', got: {}", rule))?;
+
+        let name = name.trim();
+        if !is_identifier(name) {
+            bail!("Invalid derived metric name: {:?}", name);
+        }
+
+        let expression = expression.trim();
+        let parsed = Parser::new(expression)
+            .parse()
+            .with_context(|| format!("Invalid expression for {}", name))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            expression: expression.to_string(),
+            parsed,
+        })
+    }
+
+    /// Evaluate against a set of metrics; `None` if an input is missing or
+    /// the result isn't finite
+    pub fn evaluate(&self, metrics: &HashMap<String, f64>) -> Option<f64> {
+        self.parsed.eval(metrics).filter(|value| value.is_finite())
+    }
+}
+
+/// Parse rules from config text, ignoring blank lines and `#` comments
+pub fn parse_derived_metrics(config: &str) -> Result<Vec<DerivedMetric>> {
+    config
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or("").trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| {
+            DerivedMetric::parse(line).with_context(|| format!("Line {}", number))
+        })
+        .collect()
+}
+
+/// Load rules from a config file
+///
+/// With no explicit path, uses `DEFAULT_DERIVED_METRICS_PATH` and returns no
+/// rules if it doesn't exist.
+pub fn load_derived_metrics(path: Option<&Path>) -> Result<Vec<DerivedMetric>> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let default = Path::new(DEFAULT_DERIVED_METRICS_PATH);
+            if !default.exists() {
+                return Ok(Vec::new());
+            }
+            default
+        }
+    };
+
+    let config = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read derived metrics config: {:?}", path))?;
+    let rules = parse_derived_metrics(&config)
+        .with_context(|| format!("Failed to parse derived metrics config: {:?}", path))?;
+
+    log::info!("Loaded {} derived metric rules from {:?}", rules.len(), path);
+    Ok(rules)
+}
+
+/// Add derived metrics to each result
+pub fn apply_derived_metrics(results: &mut [BenchmarkResult], rules: &[DerivedMetric]) {
+    if rules.is_empty() {
+        return;
+    }
+
+    for result in results.iter_mut() {
+        let mut derived = Vec::new();
+
+        for rule in rules {
+            match rule.evaluate(&result.metrics) {
+                Some(value) => {
+                    result.add_metric(rule.name.clone(), value);
+                    derived.push(rule.name.as_str());
+                }
+                None => log::debug!(
+                    "Skipping derived metric {} for {}: missing input or non-finite value",
+                    rule.name,
+                    result.target_id
+                ),
+            }
+        }
+
+        if !derived.is_empty() {
+            result.add_metadata(DERIVED_METRICS_METADATA_KEY.to_string(), derived.join(","));
+        }
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Recursive-descent parser:
+///
+/// ```text
+/// expr   := term (('+' | '-') term)*
+/// term   := unary (('*' | '/') unary)*
+/// unary  := '-' unary | atom
+/// atom   := number | identifier | '(' expr ')'
+/// ```
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<Expr> {
+        let expr = self.expr()?;
+        self.skip_whitespace();
+        if let Some(c) = self.peek() {
+            bail!("Unexpected '{}' at position {}", c, self.pos);
+        }
+        Ok(expr)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            self.pos += c.len_utf8();
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        if self.eat('(') {
+            let inner = self.expr()?;
+            if !self.eat(')') {
+                bail!("Expected ')' at position {}", self.pos);
+            }
+            return Ok(inner);
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let literal = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                literal
+                    .parse()
+                    .map(Expr::Number)
+                    .with_context(|| format!("Invalid number: {}", literal))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                Ok(Expr::Metric(name.to_string()))
+            }
+            Some(c) => bail!("Unexpected '{}' at position {}", c, self.pos),
+            None => bail!("Unexpected end of expression"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(values: &[(&str, f64)]) -> HashMap<String, f64> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_evaluate_expression() {
+        let rule =
+            DerivedMetric::parse("cost_per_1k_ops = estimated_cost_usd / (operation_count/1000)")
+                .unwrap();
+        assert_eq!(rule.name, "cost_per_1k_ops");
+
+        let value = rule.evaluate(&metrics(&[
+            ("estimated_cost_usd", 0.5),
+            ("operation_count", 2000.0),
+        ]));
+        assert_eq!(value, Some(0.25));
+
+        let rule = DerivedMetric::parse("x = -2 + 3 * (latency_p99 - 1) / 2").unwrap();
+        assert_eq!(rule.evaluate(&metrics(&[("latency_p99", 5.0)])), Some(4.0));
+    }
+
+    #[test]
+    fn test_missing_input_and_division_by_zero_are_skipped() {
+        let rules = parse_derived_metrics(
+            "# ratios\nratio = a / b\n\nscaled = ratio * 100  # percent\n",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 2);
+
+        let mut results = vec![
+            BenchmarkResult::new("one".to_string(), metrics(&[("a", 1.0), ("b", 4.0)])),
+            BenchmarkResult::new("two".to_string(), metrics(&[("a", 1.0), ("b", 0.0)])),
+            BenchmarkResult::new("three".to_string(), metrics(&[("a", 1.0)])),
+        ];
+        apply_derived_metrics(&mut results, &rules);
+
+        assert_eq!(results[0].get_metric("scaled"), Some(25.0));
+        assert_eq!(
+            results[0].get_metadata(DERIVED_METRICS_METADATA_KEY),
+            Some(&"ratio,scaled".to_string())
+        );
+        assert_eq!(results[1].get_metric("ratio"), None);
+        assert_eq!(results[2].get_metric("ratio"), None);
+        assert!(results[2].metadata.is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_derived_metrics("no_equals_sign").is_err());
+        assert!(parse_derived_metrics("1bad = a").is_err());
+        assert!(parse_derived_metrics("x = (a + b").is_err());
+        assert!(parse_derived_metrics("x = a $ b").is_err());
+        assert!(parse_derived_metrics("x = a b").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! Mergeable sample histograms
+//!
+//! Percentiles can't be combined once computed: the p99 of two workers is
+//! not the mean of their p99s. Adapters that collect samples also store them
+//! as a [`Histogram`] on the result, and merging histograms pools the
+//! samples so percentiles of a distributed run stay exact to within a bucket.
+//!
+//! Buckets are logarithmic, each 1% wider than the previous, so a quantile
+//! is within 1% of the true sample value however wide the range is.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Relative width of a bucket
+const BUCKET_GROWTH: f64 = 1.01;
+
+/// Sample distribution with logarithmic buckets
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Samples that are zero or negative, which have no logarithmic bucket
+    #[serde(default)]
+    pub zeros: u64,
+    /// Sample count per bucket index; bucket `i` holds `[1.01^i, 1.01^(i+1))`
+    pub buckets: BTreeMap<i32, u64>,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Histogram of a sample set; non-finite samples are ignored
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let mut histogram = Self::new();
+        for &sample in samples {
+            histogram.record(sample);
+        }
+        histogram
+    }
+
+    pub fn record(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += value;
+
+        if value <= 0.0 {
+            self.zeros += 1;
+        } else {
+            *self.buckets.entry(bucket_index(value)).or_insert(0) += 1;
+        }
+    }
+
+    /// Pool another histogram's samples into this one
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            self.min = other.min;
+            self.max = other.max;
+        } else {
+            self.min = self.min.min(other.min);
+            self.max = self.max.max(other.max);
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.zeros += other.zeros;
+        for (index, count) in &other.buckets {
+            *self.buckets.entry(*index).or_insert(0) += count;
+        }
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    /// Sample at `percent`, ranked like the adapters' percentiles (the
+    /// sample at index `count * percent / 100` of the sorted set)
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((self.count as f64 * percent / 100.0) as u64).min(self.count - 1);
+        if rank < self.zeros {
+            return Some(self.min.min(0.0));
+        }
+
+        let mut seen = self.zeros;
+        for (index, count) in &self.buckets {
+            seen += count;
+            if rank < seen {
+                // Geometric midpoint of the bucket, never outside the samples
+                let value = BUCKET_GROWTH.powf(*index as f64 + 0.5);
+                return Some(value.clamp(self.min, self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+/// Sample at `pct` of an ascending slice, 0.0 when it's empty; p100 is the
+/// largest sample
+pub fn percentile(sorted: &[f64], pct: usize) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted[((sorted.len() * pct) / 100).min(sorted.len() - 1)]
+}
+
+fn bucket_index(value: f64) -> i32 {
+    (value.ln() / BUCKET_GROWTH.ln()).floor() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_percentiles_match_pooled_samples() {
+        let fast: Vec<f64> = (1..=900).map(|i| i as f64 / 100.0).collect();
+        let slow: Vec<f64> = (1..=100).map(|i| 100.0 + i as f64).collect();
+
+        let mut merged = Histogram::from_samples(&fast);
+        merged.merge(&Histogram::from_samples(&slow));
+        merged.record(0.0);
+
+        let mut pooled: Vec<f64> = fast.iter().chain(&slow).copied().collect();
+        pooled.push(0.0);
+        pooled.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(merged.count, 1001);
+        assert_eq!(merged.min, 0.0);
+        assert_eq!(merged.max, 200.0);
+        assert_eq!(merged.percentile(0.0), Some(0.0));
+        for percent in [50.0, 90.0, 95.0, 99.0, 100.0] {
+            let exact = pooled[((pooled.len() as f64 * percent / 100.0) as usize).min(1000)];
+            let estimate = merged.percentile(percent).unwrap();
+            assert!(
+                (estimate - exact).abs() <= exact * 0.01,
+                "p{}: {} vs {}",
+                percent,
+                estimate,
+                exact
+            );
+        }
+        assert!(Histogram::new().percentile(50.0).is_none());
+    }
+
+    #[test]
+    fn test_percentile_of_sorted_samples() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+
+        assert_eq!(percentile(&sorted, 0), 1.0);
+        assert_eq!(percentile(&sorted, 50), 3.0);
+        assert_eq!(percentile(&sorted, 99), 4.0);
+        assert_eq!(percentile(&sorted, 100), 4.0);
+        assert_eq!(percentile(&[], 99), 0.0);
+    }
+}
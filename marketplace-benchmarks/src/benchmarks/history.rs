@@ -0,0 +1,361 @@
+//! Result history and metric trends
+//!
+//! Saved results are ingested into a history store that answers trend
+//! queries such as "`latency_p95` of `marketplace_search_queries` over the
+//! last 30 runs", oldest point first, for trend charts and regression
+//! baselines.
+//!
+//! A run contributes one point per target and metric, so ingesting the same
+//! results directory again only adds the runs saved since. Stores are
+//! pluggable through [`HistoryStore`]: [`MemoryHistory`] keeps the history for
+//! the life of the process, and `SqliteHistory` (feature `sqlite`) keeps it in
+//! a SQLite database across invocations.
+
+use crate::benchmarks::io::load_benchmark_results;
+use crate::benchmarks::result::BenchmarkResult;
+use crate::dashboard::runs::run_id;
+#[cfg(feature = "sqlite")]
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// A target's metric over time
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendQuery {
+    pub target_id: String,
+    pub metric: String,
+    /// Only the most recent runs
+    pub last_runs: Option<usize>,
+    /// Only runs recorded at or after this time
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl TrendQuery {
+    pub fn new(target_id: impl Into<String>, metric: impl Into<String>) -> Self {
+        Self {
+            target_id: target_id.into(),
+            metric: metric.into(),
+            last_runs: None,
+            since: None,
+        }
+    }
+
+    pub fn last_runs(mut self, runs: usize) -> Self {
+        self.last_runs = Some(runs);
+        self
+    }
+
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+}
+
+/// One run's value of a metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrendPoint {
+    pub run_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+}
+
+/// Storage for result history
+#[async_trait]
+pub trait HistoryStore: Send + Sync {
+    /// Add results to the history, returning how many new points were stored.
+    /// Results of a run already in the history are ignored.
+    async fn ingest(&self, results: &[BenchmarkResult]) -> Result<usize>;
+
+    /// Points matching `query`, oldest first
+    async fn trend(&self, query: &TrendQuery) -> Result<Vec<TrendPoint>>;
+}
+
+/// Ingest every result saved in `dir`, returning how many new points were
+/// stored
+pub async fn ingest_dir(store: &dyn HistoryStore, dir: &Path) -> Result<usize> {
+    let results = load_benchmark_results(Some(dir))?;
+    store.ingest(&results).await
+}
+
+/// Median of a trend, a baseline that a single outlier run doesn't move
+pub fn trend_baseline(points: &[TrendPoint]) -> Option<f64> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let mut values: Vec<f64> = points.iter().map(|point| point.value).collect();
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    })
+}
+
+/// The points a result contributes, one per finite metric
+fn points(result: &BenchmarkResult) -> impl Iterator<Item = (&String, TrendPoint)> + '_ {
+    let run = run_id(result);
+    let git_commit = result.get_metadata("git_commit").cloned();
+    result
+        .metrics
+        .iter()
+        .filter(|(_, value)| value.is_finite())
+        .map(move |(metric, value)| {
+            (
+                metric,
+                TrendPoint {
+                    run_id: run.clone(),
+                    timestamp: result.timestamp,
+                    value: *value,
+                    git_commit: git_commit.clone(),
+                },
+            )
+        })
+}
+
+/// History kept in memory, keyed by target, metric and run
+#[derive(Default)]
+pub struct MemoryHistory {
+    points: RwLock<BTreeMap<(String, String, String), TrendPoint>>,
+}
+
+impl MemoryHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl HistoryStore for MemoryHistory {
+    async fn ingest(&self, results: &[BenchmarkResult]) -> Result<usize> {
+        let mut stored = self.points.write().unwrap();
+        let mut added = 0;
+        for result in results {
+            for (metric, point) in points(result) {
+                let key = (result.target_id.clone(), metric.clone(), point.run_id.clone());
+                if let Entry::Vacant(entry) = stored.entry(key) {
+                    entry.insert(point);
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+
+    async fn trend(&self, query: &TrendQuery) -> Result<Vec<TrendPoint>> {
+        let stored = self.points.read().unwrap();
+        let mut trend: Vec<TrendPoint> = stored
+            .iter()
+            .filter(|((target, metric, _), _)| {
+                *target == query.target_id && *metric == query.metric
+            })
+            .map(|(_, point)| point.clone())
+            .filter(|point| query.since.is_none_or(|since| point.timestamp >= since))
+            .collect();
+
+        trend.sort_by_key(|point| point.timestamp);
+        if let Some(last) = query.last_runs {
+            trend.drain(..trend.len().saturating_sub(last));
+        }
+        Ok(trend)
+    }
+}
+
+/// History in a SQLite database
+#[cfg(feature = "sqlite")]
+#[derive(Clone)]
+pub struct SqliteHistory {
+    pool: sqlx::SqlitePool,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteHistory {
+    /// Open the database at `path`, creating it and the history table if
+    /// needed
+    pub async fn open(path: &Path) -> Result<Self> {
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .with_context(|| format!("Failed to open history database {}", path.display()))?;
+
+        Self::with_pool(pool).await
+    }
+
+    /// Use an existing pool, creating the history table if needed
+    pub async fn with_pool(pool: sqlx::SqlitePool) -> Result<Self> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS benchmark_history (
+                target_id TEXT NOT NULL,
+                metric TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                value REAL NOT NULL,
+                git_commit TEXT,
+                PRIMARY KEY (target_id, metric, run_id)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .context("Failed to create benchmark_history table")?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_benchmark_history_trend ON benchmark_history (target_id, metric, timestamp)",
+        )
+        .execute(&pool)
+        .await
+        .context("Failed to create benchmark_history index")?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait]
+impl HistoryStore for SqliteHistory {
+    async fn ingest(&self, results: &[BenchmarkResult]) -> Result<usize> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+        let mut added = 0;
+        for result in results {
+            for (metric, point) in points(result) {
+                let inserted = sqlx::query(
+                    r#"
+                    INSERT INTO benchmark_history (target_id, metric, run_id, timestamp, value, git_commit)
+                    VALUES (?, ?, ?, ?, ?, ?)
+                    ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(&result.target_id)
+                .bind(metric)
+                .bind(&point.run_id)
+                .bind(point.timestamp)
+                .bind(point.value)
+                .bind(&point.git_commit)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to store history for {}", result.target_id))?;
+                added += inserted.rows_affected() as usize;
+            }
+        }
+        tx.commit().await.context("Failed to commit history")?;
+
+        Ok(added)
+    }
+
+    async fn trend(&self, query: &TrendQuery) -> Result<Vec<TrendPoint>> {
+        let rows: Vec<(String, DateTime<Utc>, f64, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT run_id, timestamp, value, git_commit
+            FROM benchmark_history
+            WHERE target_id = ? AND metric = ? AND (? IS NULL OR timestamp >= ?)
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&query.target_id)
+        .bind(&query.metric)
+        .bind(query.since)
+        .bind(query.since)
+        .bind(query.last_runs.map_or(-1, |runs| runs as i64))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to query benchmark history")?;
+
+        Ok(rows
+            .into_iter()
+            .rev()
+            .map(|(run_id, timestamp, value, git_commit)| TrendPoint {
+                run_id,
+                timestamp,
+                value,
+                git_commit,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::RUN_ID_METADATA_KEY;
+    use chrono::Duration;
+    use std::collections::HashMap;
+
+    fn runs() -> Vec<BenchmarkResult> {
+        let start = Utc::now() - Duration::days(10);
+        [20.0, 22.0, 80.0, 21.0, 23.0]
+            .into_iter()
+            .enumerate()
+            .map(|(index, p95)| {
+                let metrics = HashMap::from([
+                    ("latency_p95".to_string(), p95),
+                    ("throughput".to_string(), f64::NAN),
+                ]);
+                let mut result = BenchmarkResult::new("search".to_string(), metrics);
+                result.timestamp = start + Duration::days(index as i64);
+                result.add_metadata(RUN_ID_METADATA_KEY.to_string(), format!("run-{}", index));
+                result
+            })
+            .collect()
+    }
+
+    async fn check_store(store: &dyn HistoryStore) {
+        let results = runs();
+        assert_eq!(store.ingest(&results[..3]).await.unwrap(), 3);
+        assert_eq!(store.ingest(&results).await.unwrap(), 2);
+
+        let query = TrendQuery::new("search", "latency_p95");
+        let trend = store.trend(&query).await.unwrap();
+        let values: Vec<f64> = trend.iter().map(|point| point.value).collect();
+        assert_eq!(values, vec![20.0, 22.0, 80.0, 21.0, 23.0]);
+        assert_eq!(trend_baseline(&trend), Some(22.0));
+
+        let last = store.trend(&query.clone().last_runs(2)).await.unwrap();
+        assert_eq!(last[0].run_id, "run-3");
+        assert_eq!(last[1].run_id, "run-4");
+        assert_eq!(trend_baseline(&last), Some(22.0));
+
+        let since = store
+            .trend(&query.since(results[3].timestamp))
+            .await
+            .unwrap();
+        assert_eq!(since.len(), 2);
+
+        let missing = TrendQuery::new("search", "throughput");
+        assert!(store.trend(&missing).await.unwrap().is_empty());
+        assert_eq!(trend_baseline(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_history() {
+        check_store(&MemoryHistory::new()).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteHistory::open(&dir.path().join("history.db"))
+            .await
+            .unwrap();
+        check_store(&store).await;
+    }
+}
@@ -3,15 +3,36 @@
 //! This module provides functions for saving benchmark results to disk
 //! and loading them back. Results are stored as JSON files with timestamps
 //! in their filenames for easy tracking and comparison.
+//!
+//! Results committed to git should be saved as canonical JSON: object keys
+//! sorted and floats rounded to [`CANONICAL_DECIMALS`], so that saving the
+//! same result twice gives the same bytes and diffs only show changed
+//! values.
 
+use crate::benchmarks::merge::MANIFEST_FILE_NAME;
+use crate::benchmarks::migrate;
 use crate::benchmarks::result::BenchmarkResult;
 use anyhow::{Context, Result};
+use serde_json::{Number, Value};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 /// Default output directory for raw benchmark results
 pub const DEFAULT_RAW_OUTPUT_DIR: &str = "benchmarks/output/raw";
 
+/// Decimals floats are rounded to in canonical JSON
+pub const CANONICAL_DECIMALS: i32 = 6;
+
+/// How saved results are serialized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// Pretty-printed in serialization order, at full precision
+    #[default]
+    Pretty,
+    /// Pretty-printed with sorted keys and rounded floats
+    Canonical,
+}
+
 /// Saves a benchmark result to a JSON file
 ///
 /// The file is saved in the raw output directory with a filename format:
@@ -42,6 +63,26 @@ pub const DEFAULT_RAW_OUTPUT_DIR: &str = "benchmarks/output/raw";
 pub fn save_benchmark_result(
     result: &BenchmarkResult,
     output_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    save_result(result, output_dir, JsonStyle::Pretty)
+}
+
+/// Saves a benchmark result as canonical JSON
+///
+/// Like [`save_benchmark_result`], but keys are sorted and floats rounded
+/// to [`CANONICAL_DECIMALS`], so results stored in git diff cleanly. Loading
+/// the file back gives the rounded values.
+pub fn save_benchmark_result_canonical(
+    result: &BenchmarkResult,
+    output_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    save_result(result, output_dir, JsonStyle::Canonical)
+}
+
+fn save_result(
+    result: &BenchmarkResult,
+    output_dir: Option<&Path>,
+    style: JsonStyle,
 ) -> Result<PathBuf> {
     let dir = output_dir
         .map(|p| p.to_path_buf())
@@ -52,13 +93,14 @@ pub fn save_benchmark_result(
         .with_context(|| format!("Failed to create output directory: {:?}", dir))?;
 
     // Generate filename with timestamp
-    let timestamp_str = result.timestamp.format("%Y%m%d_%H%M%S");
-    let filename = format!("{}_{}.json", result.target_id, timestamp_str);
-    let filepath = dir.join(filename);
+    let filepath = dir.join(format!("{}.json", file_stem(result)));
 
     // Serialize and write to file
-    let json = serde_json::to_string_pretty(result)
-        .with_context(|| format!("Failed to serialize result for {}", result.target_id))?;
+    let json = match style {
+        JsonStyle::Pretty => serde_json::to_string_pretty(result),
+        JsonStyle::Canonical => to_canonical_json(result),
+    }
+    .with_context(|| format!("Failed to serialize result for {}", result.target_id))?;
 
     fs::write(&filepath, json)
         .with_context(|| format!("Failed to write result to {:?}", filepath))?;
@@ -67,6 +109,52 @@ pub fn save_benchmark_result(
     Ok(filepath)
 }
 
+/// Name a result's files are saved under, without extension: the target id
+/// and the result's timestamp
+pub(crate) fn file_stem(result: &BenchmarkResult) -> String {
+    format!("{}_{}", result.target_id, result.timestamp.format("%Y%m%d_%H%M%S"))
+}
+
+/// A result as canonical JSON: pretty-printed, object keys sorted, floats
+/// rounded to [`CANONICAL_DECIMALS`] and a trailing newline
+pub fn to_canonical_json(result: &BenchmarkResult) -> serde_json::Result<String> {
+    // `serde_json::Map` keeps its keys sorted, whatever order the struct's
+    // HashMaps iterate in
+    let mut value = serde_json::to_value(result)?;
+    round_floats(&mut value);
+    let mut json = serde_json::to_string_pretty(&value)?;
+    json.push('\n');
+    Ok(json)
+}
+
+fn round_floats(value: &mut Value) {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            if let Some(rounded) = number.as_f64().map(round_float).and_then(Number::from_f64) {
+                *number = rounded;
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(round_floats),
+        Value::Object(map) => map.values_mut().for_each(round_floats),
+        _ => {}
+    }
+}
+
+fn round_float(value: f64) -> f64 {
+    let scale = 10f64.powi(CANONICAL_DECIMALS);
+    // Beyond this, scaling would lose more precision than rounding removes
+    if value.abs() >= 1e15 / scale {
+        return value;
+    }
+    let rounded = (value * scale).round() / scale;
+    // No "-0.0" for tiny negative values
+    if rounded == 0.0 {
+        0.0
+    } else {
+        rounded
+    }
+}
+
 /// Loads all benchmark results from a directory
 ///
 /// Scans the specified directory for JSON files and attempts to deserialize
@@ -107,8 +195,10 @@ pub fn load_benchmark_results(input_dir: Option<&Path>) -> Result<Vec<BenchmarkR
         let entry = entry.with_context(|| format!("Failed to read directory entry in {:?}", dir))?;
         let path = entry.path();
 
-        // Only process JSON files
-        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+        // Only process JSON files, other than the manifest of a merged run
+        if path.extension().and_then(|s| s.to_str()) != Some("json")
+            || path.file_name().and_then(|s| s.to_str()) == Some(MANIFEST_FILE_NAME)
+        {
             continue;
         }
 
@@ -127,7 +217,8 @@ pub fn load_benchmark_results(input_dir: Option<&Path>) -> Result<Vec<BenchmarkR
     Ok(results)
 }
 
-/// Loads a single benchmark result from a JSON file
+/// Loads a single benchmark result from a JSON file, upgrading results
+/// saved with an older schema version
 ///
 /// # Arguments
 ///
@@ -140,7 +231,7 @@ fn load_benchmark_result(filepath: &Path) -> Result<BenchmarkResult> {
     let contents = fs::read_to_string(filepath)
         .with_context(|| format!("Failed to read file: {:?}", filepath))?;
 
-    let result: BenchmarkResult = serde_json::from_str(&contents)
+    let result = migrate::result_from_str(&contents)
         .with_context(|| format!("Failed to deserialize JSON from: {:?}", filepath))?;
 
     Ok(result)
@@ -161,11 +252,21 @@ fn load_benchmark_result(filepath: &Path) -> Result<BenchmarkResult> {
 pub fn save_all_results(
     results: &[BenchmarkResult],
     output_dir: Option<&Path>,
+) -> Result<Vec<PathBuf>> {
+    save_all_results_with(results, output_dir, JsonStyle::Pretty)
+}
+
+/// Saves multiple benchmark results in a JSON style, e.g.
+/// [`JsonStyle::Canonical`] for results stored in git
+pub fn save_all_results_with(
+    results: &[BenchmarkResult],
+    output_dir: Option<&Path>,
+    style: JsonStyle,
 ) -> Result<Vec<PathBuf>> {
     let mut paths = Vec::with_capacity(results.len());
 
     for result in results {
-        let path = save_benchmark_result(result, output_dir)?;
+        let path = save_result(result, output_dir, style)?;
         paths.push(path);
     }
 
@@ -175,6 +276,7 @@ pub fn save_all_results(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::benchmarks::result::SCHEMA_VERSION;
     use std::collections::HashMap;
     use tempfile::TempDir;
 
@@ -218,6 +320,22 @@ mod tests {
         assert_eq!(results.len(), 3);
     }
 
+    #[test]
+    fn test_load_result_with_older_schema() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("legacy_20240601_120000.json");
+        fs::write(
+            &path,
+            r#"{"target_id": "legacy", "metrics": {"throughput": 900.0}, "timestamp": "2024-06-01T12:00:00Z"}"#,
+        )
+        .unwrap();
+
+        let results = load_benchmark_results(Some(temp_dir.path())).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].schema_version, SCHEMA_VERSION);
+        assert_eq!(results[0].get_metric("throughput"), Some(900.0));
+    }
+
     #[test]
     fn test_load_from_nonexistent_directory() {
         let results = load_benchmark_results(Some(Path::new("/nonexistent/path"))).unwrap();
@@ -244,4 +362,42 @@ mod tests {
             assert!(path.exists());
         }
     }
+
+    #[test]
+    fn test_canonical_json_is_stable() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut metrics = HashMap::new();
+        for (i, name) in ["throughput", "latency_p99", "error_rate", "latency_p50"]
+            .iter()
+            .enumerate()
+        {
+            metrics.insert(name.to_string(), 0.1 + 0.2 * i as f64);
+        }
+        metrics.insert("drift".to_string(), -0.000_000_1);
+        let mut result = BenchmarkResult::new("canonical".to_string(), metrics);
+        result.add_metadata("zone".to_string(), "b".to_string());
+        result.add_metadata("arch".to_string(), "x86_64".to_string());
+
+        let json = to_canonical_json(&result).unwrap();
+        assert!(json.ends_with("}\n"));
+        assert!(json.contains(r#""latency_p99": 0.3,"#));
+        assert!(json.contains(r#""latency_p50": 0.7,"#));
+        assert!(json.contains(r#""drift": 0.0,"#));
+        let positions: Vec<usize> = ["drift", "error_rate", "latency_p50", "latency_p99"]
+            .iter()
+            .map(|key| json.find(key).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(json.find("\"arch\"").unwrap() < json.find("\"zone\"").unwrap());
+
+        // The same result serializes to the same bytes, whatever the
+        // HashMaps' iteration order
+        let reloaded: BenchmarkResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(to_canonical_json(&reloaded).unwrap(), json);
+
+        let paths =
+            save_all_results_with(&[result], Some(temp_dir.path()), JsonStyle::Canonical).unwrap();
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), json);
+    }
 }
@@ -0,0 +1,310 @@
+//! JUnit XML export
+//!
+//! Renders results as JUnit test cases so CI systems (Jenkins, GitLab) show
+//! benchmark regressions as failed tests. Each target becomes a test suite
+//! and each of its metrics a test case, which fails when the baseline
+//! comparison flags it. A target that failed to run is a suite with an
+//! errored `run` case, and a skipped target a suite with a skipped one.
+//! Values in case output and failure messages are rounded by [`Precision`].
+
+use crate::benchmarks::compare::{ComparisonReport, MetricDelta};
+use crate::benchmarks::precision::Precision;
+use crate::benchmarks::result::BenchmarkResult;
+use crate::benchmarks::run::{SkippedTarget, TargetFailure};
+use std::collections::HashMap;
+
+/// Name of the `<testsuites>` element and prefix of every classname
+const SUITE_NAME: &str = "marketplace-benchmarks";
+
+/// Outcome of a test case
+enum CaseOutcome<'a> {
+    Passed,
+    Failed(String),
+    Errored(&'a str),
+    Skipped(&'a str),
+}
+
+struct TestCase<'a> {
+    name: &'a str,
+    output: Option<String>,
+    outcome: CaseOutcome<'a>,
+}
+
+/// Renders results as JUnit XML; with a comparison, regressed metrics are
+/// failed test cases
+///
+/// # Example
+///
+/// ```
+/// use marketplace_benchmarks::{generate_junit_report, BenchmarkResult};
+/// use std::collections::HashMap;
+///
+/// let mut metrics = HashMap::new();
+/// metrics.insert("latency_p50".to_string(), 12.5);
+///
+/// let result = BenchmarkResult::new("api-gateway".to_string(), metrics);
+/// let xml = generate_junit_report(&[result], None);
+/// assert!(xml.contains(r#"<testcase name="latency_p50""#));
+/// ```
+pub fn generate_junit_report(
+    results: &[BenchmarkResult],
+    comparison: Option<&ComparisonReport>,
+) -> String {
+    generate_run_junit_report(results, &[], &[], comparison, &Precision::default())
+}
+
+/// Renders a run as JUnit XML, including the targets that failed or were
+/// skipped
+pub fn generate_run_junit_report(
+    results: &[BenchmarkResult],
+    failed: &[TargetFailure],
+    skipped: &[SkippedTarget],
+    comparison: Option<&ComparisonReport>,
+    precision: &Precision,
+) -> String {
+    let deltas: HashMap<(&str, &str), &MetricDelta> = comparison
+        .into_iter()
+        .flat_map(|comparison| &comparison.deltas)
+        .map(|delta| ((delta.target_id.as_str(), delta.metric.as_str()), delta))
+        .collect();
+
+    let mut suites = String::new();
+    let mut totals = [0usize; 4];
+    let mut push_suite = |target_id: &str, timestamp: Option<String>, cases: &[TestCase]| {
+        let counts = count(cases);
+        for (total, count) in totals.iter_mut().zip(counts) {
+            *total += count;
+        }
+        suites.push_str(&render_suite(target_id, timestamp, counts, cases));
+    };
+
+    for result in results {
+        let mut metrics: Vec<(&String, &f64)> = result.metrics.iter().collect();
+        metrics.sort_by(|a, b| a.0.cmp(b.0));
+
+        let cases: Vec<TestCase> = metrics
+            .into_iter()
+            .map(|(metric, value)| {
+                let delta = deltas.get(&(result.target_id.as_str(), metric.as_str()));
+                metric_case(metric, *value, delta.copied(), precision)
+            })
+            .collect();
+        let timestamp = result.timestamp.format("%Y-%m-%dT%H:%M:%S").to_string();
+        push_suite(&result.target_id, Some(timestamp), &cases);
+    }
+    for failure in failed {
+        let case = TestCase {
+            name: "run",
+            output: None,
+            outcome: CaseOutcome::Errored(&failure.error),
+        };
+        push_suite(&failure.target_id, None, &[case]);
+    }
+    for target in skipped {
+        let case = TestCase {
+            name: "run",
+            output: None,
+            outcome: CaseOutcome::Skipped(&target.reason),
+        };
+        push_suite(&target.target_id, None, &[case]);
+    }
+
+    let [tests, failures, errors, skipped] = totals;
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\">\n\
+         {}</testsuites>\n",
+        SUITE_NAME, tests, failures, errors, skipped, suites
+    )
+}
+
+/// A metric's test case, failed if the comparison flags it
+fn metric_case<'a>(
+    metric: &'a str,
+    value: f64,
+    delta: Option<&MetricDelta>,
+    precision: &Precision,
+) -> TestCase<'a> {
+    let Some(delta) = delta else {
+        return TestCase {
+            name: metric,
+            output: Some(format!("{} = {}", metric, precision.format(metric, value))),
+            outcome: CaseOutcome::Passed,
+        };
+    };
+
+    let change = match delta.delta_percent {
+        Some(percent) => format!("{:+.2}%", percent),
+        None => precision.format_signed(metric, delta.delta),
+    };
+    let summary = format!(
+        "{} = {}, baseline {} ({})",
+        metric,
+        precision.format(metric, delta.candidate),
+        precision.format(metric, delta.baseline),
+        change
+    );
+    let outcome = if delta.regression {
+        CaseOutcome::Failed(format!(
+            "{} regressed past {}",
+            summary,
+            delta.threshold.as_deref().unwrap_or("its threshold")
+        ))
+    } else {
+        CaseOutcome::Passed
+    };
+
+    TestCase {
+        name: metric,
+        output: Some(summary),
+        outcome,
+    }
+}
+
+/// Tests, failures, errors and skipped cases
+fn count(cases: &[TestCase]) -> [usize; 4] {
+    let mut counts = [cases.len(), 0, 0, 0];
+    for case in cases {
+        match case.outcome {
+            CaseOutcome::Passed => {}
+            CaseOutcome::Failed(_) => counts[1] += 1,
+            CaseOutcome::Errored(_) => counts[2] += 1,
+            CaseOutcome::Skipped(_) => counts[3] += 1,
+        }
+    }
+    counts
+}
+
+fn render_suite(
+    target_id: &str,
+    timestamp: Option<String>,
+    [tests, failures, errors, skipped]: [usize; 4],
+    cases: &[TestCase],
+) -> String {
+    let target_id = escape(target_id);
+    let timestamp = timestamp
+        .map(|timestamp| format!(" timestamp=\"{}\"", timestamp))
+        .unwrap_or_default();
+    let mut xml = format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\"{}>\n",
+        target_id, tests, failures, errors, skipped, timestamp
+    );
+
+    for case in cases {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}.{}\"",
+            escape(case.name),
+            SUITE_NAME,
+            target_id
+        ));
+        let body = match &case.outcome {
+            CaseOutcome::Passed => String::new(),
+            CaseOutcome::Failed(message) => format!(
+                "      <failure message=\"{}\" type=\"regression\"/>\n",
+                escape(message)
+            ),
+            CaseOutcome::Errored(error) => format!(
+                "      <error message=\"{}\" type=\"failure\"/>\n",
+                escape(error)
+            ),
+            CaseOutcome::Skipped(reason) => {
+                format!("      <skipped message=\"{}\"/>\n", escape(reason))
+            }
+        };
+        let output = case
+            .output
+            .as_deref()
+            .map(|output| format!("      <system-out>{}</system-out>\n", escape(output)))
+            .unwrap_or_default();
+
+        if body.is_empty() && output.is_empty() {
+            xml.push_str("/>\n");
+        } else {
+            xml.push_str(&format!(">\n{}{}    </testcase>\n", body, output));
+        }
+    }
+
+    xml.push_str("  </testsuite>\n");
+    xml
+}
+
+/// Escape text for XML attributes and character data, dropping characters
+/// XML 1.0 can't represent
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmarks::compare::{compare_results, parse_thresholds};
+
+    fn result(target: &str, metrics: &[(&str, f64)]) -> BenchmarkResult {
+        let metrics: HashMap<String, f64> =
+            metrics.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        BenchmarkResult::new(target.to_string(), metrics)
+    }
+
+    #[test]
+    fn test_regressions_are_failed_test_cases() {
+        let thresholds = parse_thresholds("latency_p95 > +10%").unwrap();
+        let baseline = vec![result(
+            "search",
+            &[("latency_p95", 20.0), ("throughput", 900.0)],
+        )];
+        let candidate = vec![result(
+            "search",
+            &[("latency_p95", 23.0), ("throughput", 1000.0)],
+        )];
+        let comparison = compare_results(&baseline, &candidate, &thresholds);
+
+        let failed = vec![TargetFailure {
+            target_id: "registry".to_string(),
+            error: "connection refused <127.0.0.1:8080>".to_string(),
+            timed_out: false,
+        }];
+        let skipped = vec![SkippedTarget {
+            target_id: "node_adapter".to_string(),
+            reason: "`node` is not available".to_string(),
+        }];
+        let xml = generate_run_junit_report(
+            &candidate,
+            &failed,
+            &skipped,
+            Some(&comparison),
+            &Precision::default(),
+        );
+
+        assert!(xml.contains(
+            r#"<testsuites name="marketplace-benchmarks" tests="4" failures="1" errors="1" skipped="1">"#
+        ));
+        assert!(xml.contains(
+            r#"<failure message="latency_p95 = 23.00, baseline 20.00 (+15.00%) regressed past latency_p95 &gt; +10%" type="regression"/>"#
+        ));
+        assert!(xml
+            .contains(r#"<testcase name="throughput" classname="marketplace-benchmarks.search">"#));
+        assert!(xml.contains("throughput = 1000, baseline 900 (+11.11%)"));
+        assert!(xml.contains(
+            r#"<error message="connection refused &lt;127.0.0.1:8080&gt;" type="failure"/>"#
+        ));
+        assert!(xml.contains(r#"<skipped message="`node` is not available"/>"#));
+        assert_eq!(
+            xml.matches("<testcase ").count(),
+            xml.matches("</testcase>").count()
+        );
+    }
+}
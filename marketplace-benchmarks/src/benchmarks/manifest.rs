@@ -0,0 +1,417 @@
+//! Suite manifest
+//!
+//! A `benchmarks.toml` manifest declares which targets a suite runs, their
+//! parameters and where the output goes, instead of running every target in
+//! the registry with its defaults:
+//!
+//! ```toml
+//! [suite]
+//! jobs = 2
+//! runs = 3
+//! continue_on_error = true
+//! timeout_secs = 600
+//!
+//! [output]
+//! dir = "benchmarks/output/raw"
+//! report = "benchmarks/output/summary.md"
+//!
+//! [[target]]
+//! id = "marketplace_search_queries"
+//! measured_iterations = 200
+//! wrapper = "ts-wrappers/search-cli.ts"
+//!
+//! [[target]]
+//! id = "marketplace_endpoint_comparison"
+//! baseline_endpoint = "http://staging:8080"
+//! endpoint = "http://canary:8080"
+//! max_duration_secs = 60
+//! ```
+//!
+//! Targets run in manifest order; a manifest without targets runs every
+//! registered one. Targets come from a [`TargetRegistry`], the built-in one
+//! unless the caller passes its own. Unknown target ids, unknown keys and
+//! parameters a target can't take are errors, so a typo doesn't silently
+//! fall back to defaults.
+
+use crate::adapters::{
+    BenchTarget, BlockingTarget, ConsumptionApiBenchmark, Dependency, EndpointComparisonBenchmark,
+    ListingRetrievalBenchmark, MetadataValidationBenchmark, RegistryLookupBenchmark,
+    SearchQueriesBenchmark, TargetConfig, TargetRegistry,
+};
+use crate::benchmarks::result::BenchmarkResult;
+use crate::benchmarks::run::{run_targets, BenchmarkRunSummary, TargetOverrides};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default location of the suite manifest
+pub const DEFAULT_MANIFEST_PATH: &str = "benchmarks.toml";
+
+/// A parsed `benchmarks.toml`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteManifest {
+    #[serde(default)]
+    pub suite: SuiteSettings,
+    #[serde(default)]
+    pub output: OutputSettings,
+    /// Targets to run, in order; empty runs every registered target
+    #[serde(default, rename = "target")]
+    pub targets: Vec<TargetSpec>,
+}
+
+/// How the suite runs
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteSettings {
+    /// Keep running the remaining targets when one fails
+    #[serde(default)]
+    pub continue_on_error: bool,
+    /// Targets run at once; defaults to one
+    pub jobs: Option<usize>,
+    /// Times each target is run, for statistics across runs; defaults to one
+    pub runs: Option<usize>,
+    /// Seconds a target may take before it's cancelled and recorded as
+    /// timed out; unlimited by default
+    pub timeout_secs: Option<u64>,
+}
+
+/// Where results go
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSettings {
+    /// Directory for raw results
+    pub dir: Option<PathBuf>,
+    /// Markdown report written after the run
+    pub report: Option<PathBuf>,
+}
+
+/// A target and its parameters; unset fields keep the target's defaults
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetSpec {
+    pub id: String,
+    pub warmup_iterations: Option<usize>,
+    pub measured_iterations: Option<usize>,
+    pub max_duration_secs: Option<f64>,
+    /// TypeScript wrapper script, for the Node-based targets
+    pub wrapper: Option<PathBuf>,
+    /// Service base URL; the candidate for the endpoint comparison
+    pub endpoint: Option<String>,
+    /// Baseline base URL of the endpoint comparison
+    pub baseline_endpoint: Option<String>,
+}
+
+impl TargetSpec {
+    /// The iteration budget changes the spec asks for
+    fn overrides(&self) -> Result<TargetOverrides> {
+        let max_duration = match self.max_duration_secs {
+            Some(secs) if secs > 0.0 && secs.is_finite() => Some(Duration::from_secs_f64(secs)),
+            Some(secs) => bail!("max_duration_secs must be positive, got {}", secs),
+            None => None,
+        };
+        if self.measured_iterations == Some(0) {
+            bail!("measured_iterations must be at least 1");
+        }
+
+        Ok(TargetOverrides {
+            warmup_iterations: self.warmup_iterations,
+            measured_iterations: self.measured_iterations,
+            max_duration,
+        })
+    }
+
+    /// Build the target with its parameters applied, taking targets without
+    /// parameters from the registry
+    fn build(&self, registry: &mut TargetRegistry) -> Result<Arc<dyn BenchTarget>> {
+        let mut wrapper = self.wrapper.clone();
+        let mut endpoint = self.endpoint.clone();
+        let mut baseline_endpoint = self.baseline_endpoint.clone();
+
+        let target: Arc<dyn BenchTarget> = match self.id.as_str() {
+            "marketplace_listing_retrieval" => node_target(
+                wrapper.take(),
+                ListingRetrievalBenchmark::new,
+                ListingRetrievalBenchmark::with_wrapper,
+            ),
+            "marketplace_registry_lookup" => node_target(
+                wrapper.take(),
+                RegistryLookupBenchmark::new,
+                RegistryLookupBenchmark::with_wrapper,
+            ),
+            "marketplace_metadata_validation" => node_target(
+                wrapper.take(),
+                MetadataValidationBenchmark::new,
+                MetadataValidationBenchmark::with_wrapper,
+            ),
+            "marketplace_search_queries" => node_target(
+                wrapper.take(),
+                SearchQueriesBenchmark::new,
+                SearchQueriesBenchmark::with_wrapper,
+            ),
+            "marketplace_consumption_api" => {
+                let bench = ConsumptionApiBenchmark::new();
+                Arc::new(BlockingTarget(match endpoint.take() {
+                    Some(url) => bench.with_base_url(url),
+                    None => bench,
+                }))
+            }
+            "marketplace_endpoint_comparison" => {
+                let bench = EndpointComparisonBenchmark::new();
+                Arc::new(BlockingTarget(
+                    match (baseline_endpoint.take(), endpoint.take()) {
+                        (Some(baseline), Some(candidate)) => {
+                            bench.with_endpoints(baseline, candidate)
+                        }
+                        (None, None) => bench,
+                        _ => bail!("{} takes both endpoint and baseline_endpoint", self.id),
+                    },
+                ))
+            }
+            id => registry.remove(id).with_context(|| {
+                format!("Unknown benchmark target '{}'; see the 'list' command", id)
+            })?,
+        };
+
+        for (name, unused) in [
+            ("wrapper", wrapper.is_some()),
+            ("endpoint", endpoint.is_some()),
+            ("baseline_endpoint", baseline_endpoint.is_some()),
+        ] {
+            if unused {
+                bail!("{} takes no {}", self.id, name);
+            }
+        }
+
+        let overrides = self.overrides()?;
+        if overrides.is_empty() {
+            return Ok(target);
+        }
+        if target.config().is_none() {
+            bail!("{} has fixed iterations", self.id);
+        }
+        Ok(Arc::new(Configured { target, overrides }))
+    }
+}
+
+/// A Node-based target, on its default wrapper unless one is given
+fn node_target<T: BenchTarget + 'static>(
+    wrapper: Option<PathBuf>,
+    new: fn() -> T,
+    with_wrapper: fn(String) -> T,
+) -> Arc<dyn BenchTarget> {
+    match wrapper {
+        Some(path) => Arc::new(with_wrapper(path.to_string_lossy().into_owned())),
+        None => Arc::new(new()),
+    }
+}
+
+/// A target whose default iteration budget is replaced by the manifest's
+struct Configured {
+    target: Arc<dyn BenchTarget>,
+    overrides: TargetOverrides,
+}
+
+impl BenchTarget for Configured {
+    fn id(&self) -> &str {
+        self.target.id()
+    }
+
+    fn run(&self) -> Result<BenchmarkResult> {
+        match self.config() {
+            Some(config) => self.target.run_with(&config),
+            None => self.target.run(),
+        }
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        self.target.dependencies()
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        self.target.tags()
+    }
+
+    fn config(&self) -> Option<TargetConfig> {
+        self.target
+            .config()
+            .map(|defaults| self.overrides.apply(defaults))
+    }
+
+    fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+        self.target.run_with(config)
+    }
+
+    fn cancel(&self) {
+        self.target.cancel();
+    }
+}
+
+impl SuiteManifest {
+    /// Parse a manifest from TOML
+    pub fn parse(manifest: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(manifest)?;
+
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = manifest
+            .targets
+            .iter()
+            .find(|target| !seen.insert(target.id.as_str()))
+        {
+            bail!("Target '{}' is declared twice", duplicate.id);
+        }
+        if manifest.suite.timeout_secs == Some(0) {
+            bail!("timeout_secs must be at least 1");
+        }
+        Ok(manifest)
+    }
+
+    /// Build the declared targets from the built-in registry
+    pub fn build_targets(&self) -> Result<Vec<Arc<dyn BenchTarget>>> {
+        self.build_targets_from(TargetRegistry::builtin())
+    }
+
+    /// Build the declared targets, or every target of the registry when none
+    /// are
+    pub fn build_targets_from(
+        &self,
+        mut registry: TargetRegistry,
+    ) -> Result<Vec<Arc<dyn BenchTarget>>> {
+        if self.targets.is_empty() {
+            return Ok(registry.into_targets());
+        }
+        self.targets
+            .iter()
+            .map(|spec| {
+                spec.build(&mut registry)
+                    .with_context(|| format!("Target '{}'", spec.id))
+            })
+            .collect()
+    }
+
+    /// Run the suite on the built-in registry. Only an invalid target is an
+    /// error; target failures are collected in the summary.
+    pub fn run(&self) -> Result<BenchmarkRunSummary> {
+        self.run_on(TargetRegistry::builtin())
+    }
+
+    /// Run the suite on the targets of `registry`
+    pub fn run_on(&self, registry: TargetRegistry) -> Result<BenchmarkRunSummary> {
+        let targets = self.build_targets_from(registry)?;
+        let jobs = self.suite.jobs.unwrap_or(1);
+        log::info!(
+            "Starting benchmark suite of {} targets with {} jobs",
+            targets.len(),
+            jobs.max(1)
+        );
+        Ok(run_targets(
+            targets,
+            !self.suite.continue_on_error,
+            jobs,
+            &TargetOverrides::default(),
+            self.suite.runs.unwrap_or(1),
+            self.suite.timeout_secs.map(Duration::from_secs),
+            None,
+        ))
+    }
+}
+
+/// Load the suite manifest
+///
+/// With no explicit path, uses `DEFAULT_MANIFEST_PATH` and returns the
+/// default manifest, running every registered target, if it doesn't exist.
+pub fn load_manifest(path: Option<&Path>) -> Result<SuiteManifest> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let default = Path::new(DEFAULT_MANIFEST_PATH);
+            if !default.exists() {
+                return Ok(SuiteManifest::default());
+            }
+            default
+        }
+    };
+
+    let manifest = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read suite manifest: {:?}", path))?;
+    let manifest = SuiteManifest::parse(&manifest)
+        .with_context(|| format!("Failed to parse suite manifest: {:?}", path))?;
+
+    log::info!("Loaded suite manifest from {:?}", path);
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = SuiteManifest::parse(
+            r#"
+            [suite]
+            jobs = 2
+            continue_on_error = true
+
+            [output]
+            dir = "ci-results"
+
+            [[target]]
+            id = "marketplace_search_queries"
+            measured_iterations = 50
+            wrapper = "ts-wrappers/search-cli.ts"
+
+            [[target]]
+            id = "example-benchmark"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.suite.jobs, Some(2));
+        assert!(manifest.suite.continue_on_error);
+        assert_eq!(manifest.output.dir, Some(PathBuf::from("ci-results")));
+        assert_eq!(manifest.targets.len(), 2);
+        assert_eq!(manifest.targets[0].measured_iterations, Some(50));
+
+        let targets = manifest.build_targets().unwrap();
+        let ids: Vec<&str> = targets.iter().map(|target| target.id()).collect();
+        assert_eq!(ids, ["marketplace_search_queries", "example-benchmark"]);
+        assert_eq!(targets[0].config().unwrap().measured_iterations, 50);
+
+        assert_eq!(
+            SuiteManifest::default().build_targets().unwrap().len(),
+            TargetRegistry::builtin().len()
+        );
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        let invalid = [
+            "[suite]\nparallel = 2",
+            "[suite]\ntimeout_secs = 0",
+            "[[target]]\nid = \"no-such-target\"",
+            "[[target]]\nid = \"example-benchmark\"\n[[target]]\nid = \"example-benchmark\"",
+            "[[target]]\nid = \"example-benchmark\"\nwrapper = \"cli.ts\"",
+            "[[target]]\nid = \"example-benchmark\"\nmeasured_iterations = 5",
+            "[[target]]\nid = \"marketplace_endpoint_comparison\"\nendpoint = \"http://a\"",
+            "[[target]]\nid = \"marketplace_search_queries\"\nmax_duration_secs = 0",
+        ];
+        for manifest in invalid {
+            let built = SuiteManifest::parse(manifest).and_then(|m| m.build_targets());
+            assert!(built.is_err(), "{}", manifest);
+        }
+    }
+
+    #[test]
+    fn test_run_manifest() {
+        let manifest = SuiteManifest::parse("[[target]]\nid = \"example-benchmark\"").unwrap();
+        let summary = manifest.run().unwrap();
+
+        assert_eq!(summary.succeeded, ["example-benchmark"]);
+        assert_eq!(summary.results[0].target_id, "example-benchmark");
+    }
+}
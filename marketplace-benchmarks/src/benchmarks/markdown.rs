@@ -2,11 +2,51 @@
 //!
 //! This module provides functionality to generate human-readable markdown
 //! reports from benchmark results. Reports include formatted tables,
-//! summaries, and metadata.
+//! summaries, and metadata. Metrics with sample statistics are shown with
+//! their 95% confidence interval, and unstable ones are listed separately.
+//! Values are rounded per metric by [`Precision`].
+//!
+//! Targets are grouped by category, the most specific of the tags the
+//! runner records in their metadata (e.g. `consumption` for a target tagged
+//! `marketplace, consumption`). A summary table shows each target's headline
+//! metrics, and with a baseline the same metrics are shown side by side.
 
+use crate::benchmarks::compare::{compare_results, ComparisonReport, MetricDelta};
+use crate::benchmarks::precision::Precision;
 use crate::benchmarks::result::BenchmarkResult;
+use crate::benchmarks::run::{SkippedTarget, TAGS_METADATA_KEY};
+use crate::benchmarks::stats::UNSTABLE_RELATIVE_CI;
 use anyhow::Result;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+
+/// Category of results without tags
+pub const UNCATEGORIZED: &str = "other";
+
+/// Columns of the summary table: heading and the metric names it shows,
+/// the first one a result has
+const HEADLINE_METRICS: [(&str, &[&str]); 4] = [
+    ("p50", &["latency_p50"]),
+    ("p95", &["latency_p95"]),
+    ("Throughput", &["throughput", "throughput_rps"]),
+    ("Error Rate", &["error_rate"]),
+];
+
+/// Category of a result: the last, most specific tag it was run with
+pub fn category(result: &BenchmarkResult) -> &str {
+    result
+        .get_metadata(TAGS_METADATA_KEY)
+        .and_then(|tags| tags.split(',').map(str::trim).rfind(|tag| !tag.is_empty()))
+        .unwrap_or(UNCATEGORIZED)
+}
+
+/// Results by category, in category order and run order within each
+fn by_category(results: &[BenchmarkResult]) -> BTreeMap<&str, Vec<&BenchmarkResult>> {
+    let mut groups: BTreeMap<&str, Vec<&BenchmarkResult>> = BTreeMap::new();
+    for result in results {
+        groups.entry(category(result)).or_default().push(result);
+    }
+    groups
+}
 
 /// Generates a markdown report from a collection of benchmark results
 ///
@@ -37,6 +77,30 @@ use std::collections::HashSet;
 /// println!("{}", report);
 /// ```
 pub fn generate_markdown_report(results: &[BenchmarkResult]) -> Result<String> {
+    generate_run_report(results, &[], None, &Precision::default())
+}
+
+/// Generates a markdown report with the results' headline metrics side by
+/// side with a baseline set's, plus every shared metric's change
+///
+/// No regression thresholds apply; use [`generate_run_report`] with a
+/// [`ComparisonReport`] from [`compare_results`] to flag regressions.
+pub fn generate_markdown_report_with_baseline(
+    results: &[BenchmarkResult],
+    baseline: &[BenchmarkResult],
+) -> Result<String> {
+    let comparison = compare_results(baseline, results, &[]);
+    generate_run_report(results, &[], Some(&comparison), &Precision::default())
+}
+
+/// Generates a markdown report for a run, followed by the comparison with a
+/// baseline when given, and the targets the run skipped and why
+pub fn generate_run_report(
+    results: &[BenchmarkResult],
+    skipped: &[SkippedTarget],
+    comparison: Option<&ComparisonReport>,
+    precision: &Precision,
+) -> Result<String> {
     let mut report = String::new();
 
     // Header
@@ -51,6 +115,15 @@ pub fn generate_markdown_report(results: &[BenchmarkResult]) -> Result<String> {
         ));
     }
     report.push_str(&format!("**Total Benchmarks:** {}\n\n", results.len()));
+    if !skipped.is_empty() {
+        report.push_str(&format!("**Skipped:** {}\n\n", skipped.len()));
+    }
+    if let Some(comparison) = comparison {
+        report.push_str(&format!(
+            "**Regressions:** {}\n\n",
+            comparison.regressions().count()
+        ));
+    }
 
     // Add system information if available
     if let Some(first_result) = results.first() {
@@ -66,44 +139,24 @@ pub fn generate_markdown_report(results: &[BenchmarkResult]) -> Result<String> {
         report.push('\n');
     }
 
-    // Collect all unique metric keys
-    let mut all_metric_keys: HashSet<String> = HashSet::new();
-    for result in results {
-        for key in result.metrics.keys() {
-            all_metric_keys.insert(key.clone());
-        }
-    }
-    let mut sorted_keys: Vec<String> = all_metric_keys.into_iter().collect();
-    sorted_keys.sort();
+    let groups = by_category(results);
+    let grouped = groups.len() > 1;
+    push_summary(&mut report, &groups, precision);
 
-    // Results table
+    // Results tables, one per category when there are several
     report.push_str("## Benchmark Results\n\n");
-    report.push_str("| Target | ");
-    for key in &sorted_keys {
-        report.push_str(&format!("{} | ", key));
-    }
-    report.push_str("\n|--------|");
-    for _ in &sorted_keys {
-        report.push_str("--------|");
-    }
-    report.push('\n');
-
-    for result in results {
-        report.push_str(&format!("| {} | ", result.target_id));
-        for key in &sorted_keys {
-            if let Some(value) = result.get_metric(key) {
-                report.push_str(&format!("{:.2} | ", value));
-            } else {
-                report.push_str("N/A | ");
-            }
+    for (category, group) in &groups {
+        if grouped {
+            report.push_str(&format!("### {}\n\n", category));
         }
-        report.push('\n');
+        push_results_table(&mut report, group, precision);
     }
-    report.push('\n');
+
+    push_unstable_metrics(&mut report, results, precision);
 
     // Detailed results
     report.push_str("## Detailed Results\n\n");
-    for result in results {
+    for result in groups.values().flatten() {
         report.push_str(&format!("### {}\n\n", result.target_id));
         report.push_str(&format!(
             "**Timestamp:** {}\n\n",
@@ -116,7 +169,21 @@ pub fn generate_markdown_report(results: &[BenchmarkResult]) -> Result<String> {
         metric_keys.sort();
         for key in metric_keys {
             if let Some(value) = result.get_metric(key) {
-                report.push_str(&format!("- {}: {:.2}\n", key, value));
+                report.push_str(&format!(
+                    "- {}: {}",
+                    key,
+                    format_metric(result, key, value, precision)
+                ));
+                if let Some(stats) = result.stats.get(key) {
+                    report.push_str(&format!(
+                        " (n={}, stddev {}, min {}, max {})",
+                        stats.count,
+                        precision.format(key, stats.stddev),
+                        precision.format(key, stats.min),
+                        precision.format(key, stats.max)
+                    ));
+                }
+                report.push('\n');
             }
         }
         report.push('\n');
@@ -135,6 +202,23 @@ pub fn generate_markdown_report(results: &[BenchmarkResult]) -> Result<String> {
         }
     }
 
+    if let Some(comparison) = comparison {
+        push_comparison(&mut report, comparison, precision);
+    }
+
+    if !skipped.is_empty() {
+        report.push_str("## Skipped Targets\n\n");
+        report.push_str("| Target | Reason |\n|--------|--------|\n");
+        for target in skipped {
+            report.push_str(&format!(
+                "| {} | {} |\n",
+                target.target_id,
+                target.reason.replace('|', "\\|")
+            ));
+        }
+        report.push('\n');
+    }
+
     // Footer
     report.push_str("---\n\n");
     report.push_str("*Report generated by marketplace-benchmarks*\n");
@@ -142,6 +226,283 @@ pub fn generate_markdown_report(results: &[BenchmarkResult]) -> Result<String> {
     Ok(report)
 }
 
+/// The first of `names` a result has, with its value
+fn headline(result: &BenchmarkResult, names: &[&'static str]) -> Option<(&'static str, f64)> {
+    names
+        .iter()
+        .find_map(|name| Some((*name, result.get_metric(name)?)))
+}
+
+/// The delta of the first of `names` a target has
+fn headline_delta<'a>(deltas: &[&'a MetricDelta], names: &[&str]) -> Option<&'a MetricDelta> {
+    names
+        .iter()
+        .find_map(|name| deltas.iter().copied().find(|delta| delta.metric == *name))
+}
+
+/// Appends each target's headline metrics, with their category when the
+/// results span several; left out when no result has any
+fn push_summary(
+    report: &mut String,
+    groups: &BTreeMap<&str, Vec<&BenchmarkResult>>,
+    precision: &Precision,
+) {
+    let any_headline = groups.values().flatten().any(|result| {
+        HEADLINE_METRICS
+            .iter()
+            .any(|(_, names)| headline(result, names).is_some())
+    });
+    if !any_headline {
+        return;
+    }
+    let grouped = groups.len() > 1;
+
+    report.push_str("## Summary\n\n| Target | ");
+    if grouped {
+        report.push_str("Category | ");
+    }
+    for (heading, _) in HEADLINE_METRICS {
+        report.push_str(&format!("{} | ", heading));
+    }
+    report.push_str("\n|--------|");
+    if grouped {
+        report.push_str("--------|");
+    }
+    report.push_str(&"--------|".repeat(HEADLINE_METRICS.len()));
+    report.push('\n');
+
+    for (category, group) in groups {
+        for result in group {
+            report.push_str(&format!("| {} | ", result.target_id));
+            if grouped {
+                report.push_str(&format!("{} | ", category));
+            }
+            for (_, names) in HEADLINE_METRICS {
+                match headline(result, names) {
+                    Some((name, value)) => report.push_str(&format!(
+                        "{} | ",
+                        format_metric(result, name, value, precision)
+                    )),
+                    None => report.push_str("N/A | "),
+                }
+            }
+            report.push('\n');
+        }
+    }
+    report.push('\n');
+}
+
+/// Appends a table of every metric of the results
+fn push_results_table(report: &mut String, results: &[&BenchmarkResult], precision: &Precision) {
+    // Collect all unique metric keys
+    let mut all_metric_keys: HashSet<&String> = HashSet::new();
+    for result in results {
+        all_metric_keys.extend(result.metrics.keys());
+    }
+    let mut sorted_keys: Vec<&String> = all_metric_keys.into_iter().collect();
+    sorted_keys.sort();
+
+    report.push_str("| Target | ");
+    for key in &sorted_keys {
+        report.push_str(&format!("{} | ", key));
+    }
+    report.push_str("\n|--------|");
+    for _ in &sorted_keys {
+        report.push_str("--------|");
+    }
+    report.push('\n');
+
+    for result in results {
+        report.push_str(&format!("| {} | ", result.target_id));
+        for key in &sorted_keys {
+            if let Some(value) = result.get_metric(key) {
+                report.push_str(&format!(
+                    "{} | ",
+                    format_metric(result, key, value, precision)
+                ));
+            } else {
+                report.push_str("N/A | ");
+            }
+        }
+        report.push('\n');
+    }
+    report.push('\n');
+}
+
+/// A metric's value, with the half-width of its 95% confidence interval when
+/// it was sampled more than once
+fn format_metric(result: &BenchmarkResult, key: &str, value: f64, precision: &Precision) -> String {
+    match result
+        .stats
+        .get(key)
+        .and_then(|stats| stats.ci95_half_width())
+    {
+        Some(half_width) => format!(
+            "{} ± {}",
+            precision.format(key, value),
+            precision.format(key, half_width)
+        ),
+        None => precision.format(key, value),
+    }
+}
+
+/// Appends the metrics whose confidence interval is too wide to trust a
+/// single comparison with
+fn push_unstable_metrics(report: &mut String, results: &[BenchmarkResult], precision: &Precision) {
+    let mut unstable = Vec::new();
+    for result in results {
+        let mut keys: Vec<&String> = result.stats.keys().collect();
+        keys.sort();
+        for key in keys {
+            let stats = &result.stats[key];
+            if let (true, Some([low, high])) = (stats.is_unstable(), stats.ci95) {
+                unstable.push(format!(
+                    "| {} | {} | {} | {} – {} | {} |\n",
+                    result.target_id,
+                    key,
+                    precision.format(key, stats.mean),
+                    precision.format(key, low),
+                    precision.format(key, high),
+                    stats.count
+                ));
+            }
+        }
+    }
+    if unstable.is_empty() {
+        return;
+    }
+
+    report.push_str("## Unstable Metrics\n\n");
+    report.push_str(&format!(
+        "95% confidence interval wider than ±{:.0}% of the mean; differences in these metrics may be noise.\n\n",
+        UNSTABLE_RELATIVE_CI * 100.0
+    ));
+    report.push_str("| Target | Metric | Mean | 95% CI | Samples |\n");
+    report.push_str("|--------|--------|--------|--------|--------|\n");
+    for row in unstable {
+        report.push_str(&row);
+    }
+    report.push('\n');
+}
+
+/// Appends the baseline comparison: regressions first, then every metric
+fn push_comparison(report: &mut String, comparison: &ComparisonReport, precision: &Precision) {
+    let percent = |delta: Option<f64>| match delta {
+        Some(delta) => format!("{:+.1}%", delta),
+        None => "N/A".to_string(),
+    };
+
+    report.push_str("## Baseline Comparison\n\n");
+    push_side_by_side(report, comparison, precision);
+
+    let regressions: Vec<_> = comparison.regressions().collect();
+    if regressions.is_empty() {
+        report.push_str("No regressions against the configured thresholds.\n\n");
+    } else {
+        report.push_str("### Regressions\n\n");
+        report.push_str("| Target | Metric | Baseline | Candidate | Change | Threshold |\n");
+        report.push_str("|--------|--------|--------|--------|--------|--------|\n");
+        for delta in regressions {
+            report.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                delta.target_id,
+                delta.metric,
+                precision.format(&delta.metric, delta.baseline),
+                precision.format(&delta.metric, delta.candidate),
+                percent(delta.delta_percent),
+                delta.threshold.as_deref().unwrap_or("")
+            ));
+        }
+        report.push('\n');
+    }
+
+    if !comparison.missing_targets.is_empty() {
+        report.push_str(&format!(
+            "**Missing from candidate:** {}\n\n",
+            comparison.missing_targets.join(", ")
+        ));
+    }
+    if !comparison.new_targets.is_empty() {
+        report.push_str(&format!(
+            "**New (no baseline):** {}\n\n",
+            comparison.new_targets.join(", ")
+        ));
+    }
+
+    if !comparison.deltas.is_empty() {
+        report.push_str("### All Metrics\n\n");
+        report.push_str("| Target | Metric | Baseline | Candidate | Change |\n");
+        report.push_str("|--------|--------|--------|--------|--------|\n");
+        for delta in &comparison.deltas {
+            report.push_str(&format!(
+                "| {} | {} | {} | {} | {}{} |\n",
+                delta.target_id,
+                delta.metric,
+                precision.format(&delta.metric, delta.baseline),
+                precision.format(&delta.metric, delta.candidate),
+                percent(delta.delta_percent),
+                if delta.regression {
+                    " (regression)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        report.push('\n');
+    }
+}
+
+/// Appends each compared target's headline metrics as baseline → candidate
+fn push_side_by_side(report: &mut String, comparison: &ComparisonReport, precision: &Precision) {
+    let mut targets: BTreeMap<&str, Vec<&MetricDelta>> = BTreeMap::new();
+    for delta in &comparison.deltas {
+        targets.entry(&delta.target_id).or_default().push(delta);
+    }
+    let rows: Vec<(&str, Vec<Option<&MetricDelta>>)> = targets
+        .iter()
+        .map(|(target_id, deltas)| {
+            let cells = HEADLINE_METRICS
+                .iter()
+                .map(|(_, names)| headline_delta(deltas, names))
+                .collect::<Vec<_>>();
+            (*target_id, cells)
+        })
+        .filter(|(_, cells)| cells.iter().any(Option::is_some))
+        .collect();
+    if rows.is_empty() {
+        return;
+    }
+
+    report.push_str("### Side by Side\n\nBaseline → candidate.\n\n| Target | ");
+    for (heading, _) in HEADLINE_METRICS {
+        report.push_str(&format!("{} | ", heading));
+    }
+    report.push_str("\n|--------|");
+    report.push_str(&"--------|".repeat(HEADLINE_METRICS.len()));
+    report.push('\n');
+
+    for (target_id, cells) in rows {
+        report.push_str(&format!("| {} | ", target_id));
+        for cell in cells {
+            match cell {
+                Some(delta) => report.push_str(&format!(
+                    "{} → {}{}{} | ",
+                    precision.format(&delta.metric, delta.baseline),
+                    precision.format(&delta.metric, delta.candidate),
+                    delta
+                        .delta_percent
+                        .map(|percent| format!(" ({:+.1}%)", percent))
+                        .unwrap_or_default(),
+                    if delta.regression { " ⚠" } else { "" }
+                )),
+                None => report.push_str("N/A | "),
+            }
+        }
+        report.push('\n');
+    }
+    report.push('\n');
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,6 +515,45 @@ mod tests {
         assert!(report.contains("**Total Benchmarks:** 0"));
     }
 
+    #[test]
+    fn test_run_report_lists_skipped_targets() {
+        let skipped = vec![SkippedTarget {
+            target_id: "marketplace_search_queries".to_string(),
+            reason: "`node --check search-cli.ts` failed: TypeError".to_string(),
+        }];
+        let report = generate_run_report(&[], &skipped, None, &Precision::default()).unwrap();
+        assert!(report.contains("**Skipped:** 1"));
+        assert!(report.contains("## Skipped Targets"));
+        assert!(report.contains(
+            "| marketplace_search_queries | `node --check search-cli.ts` failed: TypeError |"
+        ));
+    }
+
+    #[test]
+    fn test_run_report_includes_comparison() {
+        use crate::benchmarks::compare::{compare_results, parse_thresholds};
+
+        let result = |p95: f64| {
+            let metrics = HashMap::from([("latency_p95".to_string(), p95)]);
+            BenchmarkResult::new("search".to_string(), metrics)
+        };
+        let thresholds = parse_thresholds("latency_p95 > +10%").unwrap();
+        let comparison = compare_results(&[result(20.0)], &[result(23.0)], &thresholds);
+
+        let report = generate_run_report(
+            &[result(23.0)],
+            &[],
+            Some(&comparison),
+            &Precision::default(),
+        )
+        .unwrap();
+        assert!(report.contains("**Regressions:** 1"));
+        assert!(report.contains("## Baseline Comparison"));
+        assert!(report
+            .contains("| search | latency_p95 | 20.00 | 23.00 | +15.0% | latency_p95 > +10% |"));
+        assert!(report.contains("| search | latency_p95 | 20.00 | 23.00 | +15.0% (regression) |"));
+    }
+
     #[test]
     fn test_generate_single_result_report() {
         let mut metrics = HashMap::new();
@@ -169,7 +569,7 @@ mod tests {
         assert!(report.contains("latency_p50"));
         assert!(report.contains("throughput"));
         assert!(report.contains("12.50"));
-        assert!(report.contains("1000.00"));
+        assert!(report.contains("| test-target | 12.50 | 1000 |"));
     }
 
     #[test]
@@ -192,6 +592,29 @@ mod tests {
         assert!(report.contains("N/A")); // target-1 doesn't have throughput
     }
 
+    #[test]
+    fn test_report_shows_sample_statistics() {
+        use crate::benchmarks::stats::MetricSample;
+
+        let mut result = BenchmarkResult::new("search".to_string(), HashMap::new());
+        result.add_samples(
+            "latency_p95".to_string(),
+            MetricSample::new([10.0, 20.0, 30.0]),
+        );
+        result.add_samples(
+            "throughput".to_string(),
+            MetricSample::new([100.0, 101.0, 99.0]),
+        );
+
+        let report = generate_markdown_report(&[result]).unwrap();
+        assert!(report.contains("| search | 20.00 ± 24.84 | 100 ± 2 |"));
+        assert!(report
+            .contains("- latency_p95: 20.00 ± 24.84 (n=3, stddev 10.00, min 10.00, max 30.00)"));
+        assert!(report.contains("## Unstable Metrics"));
+        assert!(report.contains("| search | latency_p95 | 20.00 | -4.84 – 44.84 | 3 |"));
+        assert!(!report.contains("| search | throughput |"));
+    }
+
     #[test]
     fn test_report_with_metadata() {
         let mut metrics = HashMap::new();
@@ -201,11 +624,7 @@ mod tests {
         metadata.insert("version".to_string(), "1.0.0".to_string());
         metadata.insert("hostname".to_string(), "test-host".to_string());
 
-        let result = BenchmarkResult::with_metadata(
-            "test-target".to_string(),
-            metrics,
-            metadata,
-        );
+        let result = BenchmarkResult::with_metadata("test-target".to_string(), metrics, metadata);
 
         let report = generate_markdown_report(&[result]).unwrap();
 
@@ -213,4 +632,73 @@ mod tests {
         assert!(report.contains("**Metadata:**"));
         assert!(report.contains("version: 1.0.0"));
     }
+
+    #[test]
+    fn test_report_groups_targets_by_category() {
+        let result = |target: &str, tags: &str, metrics: &[(&str, f64)]| {
+            let metrics = metrics
+                .iter()
+                .map(|(name, value)| (name.to_string(), *value))
+                .collect();
+            let mut result = BenchmarkResult::new(target.to_string(), metrics);
+            result.add_metadata(TAGS_METADATA_KEY.to_string(), tags.to_string());
+            result
+        };
+        let results = [
+            result(
+                "search",
+                "marketplace",
+                &[("latency_p50", 4.2), ("throughput_rps", 142.6)],
+            ),
+            result("kv", "infra", &[("get_ns_p99", 310.0)]),
+            result(
+                "consume",
+                "marketplace,consumption",
+                &[("latency_p95", 8.7), ("error_rate", 0.0)],
+            ),
+        ];
+
+        assert_eq!(category(&results[2]), "consumption");
+        let untagged = BenchmarkResult::new("untagged".to_string(), HashMap::new());
+        assert_eq!(category(&untagged), UNCATEGORIZED);
+
+        let report = generate_markdown_report(&results).unwrap();
+        assert!(report.contains(
+            "| Target | Category | p50 | p95 | Throughput | Error Rate | \n|--------|--------|"
+        ));
+        assert!(report.contains("| consume | consumption | N/A | 8.70 | N/A | 0.000 | "));
+        assert!(report.contains("| search | marketplace | 4.20 | N/A | 143 | N/A | "));
+        // Categories in order, each with a table of its own metrics
+        let consumption = report.find("### consumption").unwrap();
+        let infra = report.find("### infra").unwrap();
+        let marketplace = report.find("### marketplace").unwrap();
+        assert!(consumption < infra && infra < marketplace);
+        assert!(report.contains("### infra\n\n| Target | get_ns_p99 | \n"));
+
+        let baseline = [result(
+            "search",
+            "marketplace",
+            &[("latency_p50", 5.0), ("throughput_rps", 120.0)],
+        )];
+        let report = generate_markdown_report_with_baseline(&results, &baseline).unwrap();
+        assert!(report.contains("### Side by Side"));
+        assert!(
+            report.contains("| search | 5.00 → 4.20 (-16.0%) | N/A | 120 → 143 (+18.8%) | N/A | ")
+        );
+        assert!(report.contains("**New (no baseline):** consume, kv"));
+    }
+
+    #[test]
+    fn test_report_uses_configured_precision() {
+        let metrics = HashMap::from([
+            ("latency_p99".to_string(), 12.34567),
+            ("error_rate".to_string(), 0.0123456),
+        ]);
+        let result = BenchmarkResult::new("search".to_string(), metrics);
+        let precision = Precision::parse("latency_p99 = 4").unwrap();
+
+        let report = generate_run_report(&[result], &[], None, &precision).unwrap();
+        assert!(report.contains("| search | 0.012 | 12.3457 |"));
+        assert!(report.contains("- latency_p99: 12.3457\n"));
+    }
 }
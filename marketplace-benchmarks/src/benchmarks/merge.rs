@@ -0,0 +1,423 @@
+//! Merging results from distributed benchmark workers
+//!
+//! Each worker runs part of the suite (or all of it) and saves partial
+//! results tagged with its `worker_id`. Merging combines the results of each
+//! target into one, metric by metric:
+//!
+//! - percentiles, means, minimums and maximums of a histogram family
+//!   (`latency_p99` with a `latency` histogram) are recomputed from the
+//!   pooled samples
+//! - counters (`*_count`, `*_total`, `total_*`) are summed
+//! - throughput (`throughput*`, `*_per_sec`, `*_rps`) is the total
+//!   operations over the total time spent, i.e. the harmonic mean weighted
+//!   by operation count
+//! - other `*_min` and `*_max` metrics keep the extreme value
+//! - everything else is averaged, weighted by operation count
+//!
+//! Raw [`MetricSample`]s recorded by every worker are pooled and their
+//! statistics recomputed; the metric itself still follows the rules above.
+//!
+//! Percentiles without a histogram on every input can only be averaged; the
+//! run manifest lists them as approximate.
+
+use crate::benchmarks::histogram::Histogram;
+use crate::benchmarks::result::BenchmarkResult;
+use crate::benchmarks::stats::MetricSample;
+use crate::dashboard::RUN_ID_METADATA_KEY;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Metadata key identifying the worker that produced a result
+pub const WORKER_ID_METADATA_KEY: &str = "worker_id";
+
+/// Metadata key listing the workers a merged result was combined from
+pub const WORKER_IDS_METADATA_KEY: &str = "worker_ids";
+
+/// File name of the run manifest written next to merged results
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// A worker's contribution to a merged run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerSummary {
+    pub worker_id: String,
+    pub hostname: Option<String>,
+    pub git_commit: Option<String>,
+    pub targets: Vec<String>,
+    pub result_count: usize,
+}
+
+/// Describes a run merged from several workers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub run_id: String,
+    pub merged_at: DateTime<Utc>,
+    pub workers: Vec<WorkerSummary>,
+    pub targets: Vec<String>,
+    /// `target.metric` of the percentiles averaged without histograms
+    pub approximate_metrics: Vec<String>,
+}
+
+/// Consolidated results of a distributed run
+#[derive(Debug, Clone)]
+pub struct MergedRun {
+    /// One result per target, sorted by target id
+    pub results: Vec<BenchmarkResult>,
+    pub manifest: RunManifest,
+}
+
+/// How a metric is combined across results
+#[derive(Debug, PartialEq)]
+enum MergeRule<'a> {
+    Percentile(&'a Histogram, f64),
+    HistogramMean(&'a Histogram),
+    HistogramMin(&'a Histogram),
+    HistogramMax(&'a Histogram),
+    Sum,
+    Throughput,
+    Min,
+    Max,
+    Mean,
+    ApproximatePercentile,
+}
+
+/// Worker that produced a result: its `worker_id`, else its hostname
+pub fn worker_id(result: &BenchmarkResult) -> String {
+    result
+        .get_metadata(WORKER_ID_METADATA_KEY)
+        .or_else(|| result.get_metadata("hostname"))
+        .cloned()
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Merge partial results from any number of workers into one result per
+/// target, tagged with `run_id`
+pub fn merge_results(results: &[BenchmarkResult], run_id: &str) -> MergedRun {
+    let mut by_target: BTreeMap<&str, Vec<&BenchmarkResult>> = BTreeMap::new();
+    let mut workers: BTreeMap<String, WorkerSummary> = BTreeMap::new();
+
+    for result in results {
+        by_target.entry(&result.target_id).or_default().push(result);
+
+        let id = worker_id(result);
+        let worker = workers.entry(id.clone()).or_insert_with(|| WorkerSummary {
+            worker_id: id,
+            hostname: result.get_metadata("hostname").cloned(),
+            git_commit: result.get_metadata("git_commit").cloned(),
+            targets: Vec::new(),
+            result_count: 0,
+        });
+        if !worker.targets.contains(&result.target_id) {
+            worker.targets.push(result.target_id.clone());
+        }
+        worker.result_count += 1;
+    }
+
+    let mut approximate_metrics = Vec::new();
+    let merged: Vec<BenchmarkResult> = by_target
+        .values()
+        .map(|parts| {
+            let (mut result, approximate) = merge_target(parts);
+            result.add_metadata(RUN_ID_METADATA_KEY.to_string(), run_id.to_string());
+            approximate_metrics.extend(
+                approximate
+                    .into_iter()
+                    .map(|metric| format!("{}.{}", result.target_id, metric)),
+            );
+            result
+        })
+        .collect();
+
+    let commits: BTreeSet<&String> = workers
+        .values()
+        .filter_map(|w| w.git_commit.as_ref())
+        .collect();
+    if commits.len() > 1 {
+        log::warn!(
+            "Merging results from {} different builds; metrics may not be comparable",
+            commits.len()
+        );
+    }
+
+    let mut workers: Vec<WorkerSummary> = workers.into_values().collect();
+    for worker in &mut workers {
+        worker.targets.sort();
+    }
+
+    MergedRun {
+        manifest: RunManifest {
+            run_id: run_id.to_string(),
+            merged_at: Utc::now(),
+            workers,
+            targets: merged
+                .iter()
+                .map(|result| result.target_id.clone())
+                .collect(),
+            approximate_metrics,
+        },
+        results: merged,
+    }
+}
+
+/// Merge the results of one target, returning the metrics that could only
+/// be approximated
+fn merge_target(parts: &[&BenchmarkResult]) -> (BenchmarkResult, Vec<String>) {
+    let weights: Vec<f64> = parts.iter().map(|part| sample_weight(part)).collect();
+
+    // Pool a histogram family only if every part recorded it, otherwise the
+    // pooled percentiles would miss the samples of the other parts
+    let mut histograms: BTreeMap<String, Histogram> = BTreeMap::new();
+    for prefix in parts[0].histograms.keys() {
+        if parts
+            .iter()
+            .all(|part| part.histograms.contains_key(prefix))
+        {
+            let mut pooled = Histogram::new();
+            for part in parts {
+                pooled.merge(&part.histograms[prefix]);
+            }
+            histograms.insert(prefix.clone(), pooled);
+        }
+    }
+
+    let names: BTreeSet<&String> = parts.iter().flat_map(|part| part.metrics.keys()).collect();
+    let mut metrics = HashMap::new();
+    let mut approximate = Vec::new();
+
+    for name in names {
+        let values: Vec<(f64, f64)> = parts
+            .iter()
+            .zip(&weights)
+            .filter_map(|(part, weight)| part.get_metric(name).map(|value| (value, *weight)))
+            .collect();
+
+        let rule = merge_rule(name, &histograms);
+        let value = match rule {
+            MergeRule::Percentile(histogram, percent) => histogram.percentile(percent),
+            MergeRule::HistogramMean(histogram) => histogram.mean(),
+            MergeRule::HistogramMin(histogram) => Some(histogram.min),
+            MergeRule::HistogramMax(histogram) => Some(histogram.max),
+            MergeRule::Sum => Some(values.iter().map(|(value, _)| value).sum()),
+            MergeRule::Throughput => Some(harmonic_mean(&values)),
+            MergeRule::Min => values.iter().map(|(value, _)| *value).reduce(f64::min),
+            MergeRule::Max => values.iter().map(|(value, _)| *value).reduce(f64::max),
+            MergeRule::Mean | MergeRule::ApproximatePercentile => Some(weighted_mean(&values)),
+        };
+        if rule == MergeRule::ApproximatePercentile && parts.len() > 1 {
+            approximate.push(name.clone());
+        }
+        if let Some(value) = value {
+            metrics.insert(name.clone(), value);
+        }
+    }
+
+    let mut result =
+        BenchmarkResult::with_metadata(parts[0].target_id.clone(), metrics, common_metadata(parts));
+    result.timestamp = parts
+        .iter()
+        .map(|part| part.timestamp)
+        .max()
+        .unwrap_or(result.timestamp);
+    result.histograms = histograms;
+
+    for name in parts[0].samples.keys() {
+        if parts.iter().all(|part| part.samples.contains_key(name)) {
+            let mut pooled = MetricSample::default();
+            for part in parts {
+                pooled.merge(&part.samples[name]);
+            }
+            if let Some(stats) = pooled.stats() {
+                result.stats.insert(name.clone(), stats);
+                result.samples.insert(name.clone(), pooled);
+            }
+        }
+    }
+
+    let worker_ids: BTreeSet<String> = parts.iter().map(|part| worker_id(part)).collect();
+    result.add_metadata(
+        WORKER_IDS_METADATA_KEY.to_string(),
+        worker_ids.into_iter().collect::<Vec<_>>().join(","),
+    );
+    result.add_metadata("merged_results".to_string(), parts.len().to_string());
+
+    (result, approximate)
+}
+
+fn merge_rule<'a>(metric: &str, histograms: &'a BTreeMap<String, Histogram>) -> MergeRule<'a> {
+    for (prefix, histogram) in histograms {
+        let Some(stat) = metric
+            .strip_prefix(prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('_'))
+        else {
+            continue;
+        };
+        match stat {
+            "mean" | "avg" => return MergeRule::HistogramMean(histogram),
+            "min" => return MergeRule::HistogramMin(histogram),
+            "max" => return MergeRule::HistogramMax(histogram),
+            _ => {}
+        }
+        if let Some(percent) = stat.strip_prefix('p').and_then(|p| p.parse().ok()) {
+            return MergeRule::Percentile(histogram, percent);
+        }
+    }
+
+    if metric.ends_with("_count") || metric.ends_with("_total") || metric.starts_with("total_") {
+        MergeRule::Sum
+    } else if metric.starts_with("throughput")
+        || metric.ends_with("_per_sec")
+        || metric.ends_with("_rps")
+    {
+        MergeRule::Throughput
+    } else if metric.ends_with("_min") {
+        MergeRule::Min
+    } else if metric.ends_with("_max") {
+        MergeRule::Max
+    } else if metric.split('_').any(is_percentile_label) {
+        MergeRule::ApproximatePercentile
+    } else {
+        MergeRule::Mean
+    }
+}
+
+/// `p50`, `p99`, `p999`, ...
+fn is_percentile_label(part: &str) -> bool {
+    part.len() > 1 && part.starts_with('p') && part[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Operations a result measured: its histogram sample count, else its
+/// `operation_count` metric or `iterations` metadata
+fn sample_weight(result: &BenchmarkResult) -> f64 {
+    let weight = result
+        .histograms
+        .values()
+        .map(|histogram| histogram.count as f64)
+        .next()
+        .or_else(|| result.get_metric("operation_count"))
+        .or_else(|| {
+            result
+                .get_metadata("iterations")
+                .and_then(|n| n.parse().ok())
+        })
+        .unwrap_or(1.0);
+    if weight > 0.0 {
+        weight
+    } else {
+        1.0
+    }
+}
+
+fn weighted_mean(values: &[(f64, f64)]) -> f64 {
+    let total: f64 = values.iter().map(|(_, weight)| weight).sum();
+    values
+        .iter()
+        .map(|(value, weight)| value * weight)
+        .sum::<f64>()
+        / total
+}
+
+/// Total operations over total time, when every part measured a rate
+fn harmonic_mean(values: &[(f64, f64)]) -> f64 {
+    if values.iter().any(|(value, _)| *value <= 0.0) {
+        return weighted_mean(values);
+    }
+    let operations: f64 = values.iter().map(|(_, weight)| weight).sum();
+    let time: f64 = values.iter().map(|(value, weight)| weight / value).sum();
+    operations / time
+}
+
+/// Metadata all parts agree on; per-worker values such as the hostname are
+/// dropped
+fn common_metadata(parts: &[&BenchmarkResult]) -> HashMap<String, String> {
+    let mut metadata = parts[0].metadata.clone();
+    metadata.retain(|key, value| {
+        key != WORKER_ID_METADATA_KEY
+            && parts
+                .iter()
+                .all(|part| part.get_metadata(key) == Some(value))
+    });
+    metadata
+}
+
+/// Write the manifest of a merged run into `dir`
+pub fn save_manifest(manifest: &RunManifest, dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory: {:?}", dir))?;
+
+    let path = dir.join(MANIFEST_FILE_NAME);
+    let json =
+        serde_json::to_string_pretty(manifest).context("Failed to serialize run manifest")?;
+    fs::write(&path, json).with_context(|| format!("Failed to write manifest to {:?}", path))?;
+
+    log::info!("Saved run manifest to: {:?}", path);
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker_result(worker: &str, latencies: &[f64], extra: &[(&str, f64)]) -> BenchmarkResult {
+        let total: f64 = latencies.iter().sum();
+        let mut metrics: HashMap<String, f64> =
+            extra.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        metrics.insert("operation_count".to_string(), latencies.len() as f64);
+        metrics.insert(
+            "throughput_rps".to_string(),
+            latencies.len() as f64 / (total / 1000.0),
+        );
+        metrics.insert("latency_p99".to_string(), 0.0);
+
+        let mut result = BenchmarkResult::new("search".to_string(), metrics);
+        result.add_histogram("latency".to_string(), Histogram::from_samples(latencies));
+        result.add_metadata(WORKER_ID_METADATA_KEY.to_string(), worker.to_string());
+        result.add_metadata("hostname".to_string(), format!("{}.local", worker));
+        result.add_metadata("test_suite".to_string(), "search_queries".to_string());
+        result
+    }
+
+    #[test]
+    fn test_merge_pools_histograms_and_weights_throughput() {
+        // A fast worker with many samples and a slow one with few
+        let mut fast = worker_result("a", &[10.0; 90], &[("error_rate", 0.0), ("cache_p99", 2.0)]);
+        let mut slow = worker_result(
+            "b",
+            &[100.0; 10],
+            &[("error_rate", 0.1), ("cache_p99", 4.0)],
+        );
+        fast.add_samples("startup_ms".to_string(), MetricSample::new([10.0, 12.0]));
+        slow.add_samples("startup_ms".to_string(), MetricSample::new([14.0]));
+
+        let merged = merge_results(&[fast, slow], "run-1");
+        assert_eq!(merged.results.len(), 1);
+        let result = &merged.results[0];
+
+        assert_eq!(result.get_metric("operation_count"), Some(100.0));
+        // 100 operations in 900ms + 1000ms
+        let throughput = result.get_metric("throughput_rps").unwrap();
+        assert!((throughput - 100.0 / 1.9).abs() < 1e-9);
+        // The pooled p99 is a slow sample, not an average of the workers' p99s
+        let p99 = result.get_metric("latency_p99").unwrap();
+        assert!((p99 - 100.0).abs() <= 1.0);
+        assert!((result.get_metric("error_rate").unwrap() - 0.01).abs() < 1e-12);
+        assert_eq!(result.histograms["latency"].count, 100);
+        // Samples are pooled; the metric is still weighted by operations
+        assert_eq!(result.stats["startup_ms"].count, 3);
+        assert_eq!(result.stats["startup_ms"].mean, 12.0);
+        assert!((result.get_metric("startup_ms").unwrap() - 11.3).abs() < 1e-9);
+
+        assert_eq!(result.get_metadata(WORKER_IDS_METADATA_KEY).unwrap(), "a,b");
+        assert_eq!(result.get_metadata(RUN_ID_METADATA_KEY).unwrap(), "run-1");
+        assert_eq!(result.get_metadata("test_suite").unwrap(), "search_queries");
+        assert!(result.get_metadata("hostname").is_none());
+
+        let manifest = &merged.manifest;
+        assert_eq!(manifest.workers.len(), 2);
+        assert_eq!(manifest.workers[1].hostname.as_deref(), Some("b.local"));
+        assert_eq!(manifest.targets, vec!["search"]);
+        assert_eq!(manifest.approximate_metrics, vec!["search.cache_p99"]);
+    }
+}
@@ -0,0 +1,126 @@
+//! Schema migration of saved results
+//!
+//! Every result carries the `schema_version` it was written with. Loading a
+//! saved result runs it through the migrations from its version up to
+//! [`SCHEMA_VERSION`], so results written before a field was added or
+//! renamed keep loading:
+//!
+//! | Version | Change |
+//! |---------|--------|
+//! | 1 | Results without a `schema_version`; `metadata` may be missing |
+//! | 2 | Adds `schema_version` |
+//!
+//! A change that older files can't deserialize into bumps
+//! [`SCHEMA_VERSION`] and appends a step to `MIGRATIONS` that rewrites the
+//! previous version's JSON. Results from a newer version than this build
+//! knows are rejected rather than read wrongly.
+
+use crate::benchmarks::result::{BenchmarkResult, SCHEMA_VERSION};
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+
+/// Version of results saved before `schema_version` existed
+pub const UNVERSIONED_SCHEMA: u32 = 1;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// Step `i` upgrades a result from version `i + 1` to `i + 2`
+const MIGRATIONS: [Migration; (SCHEMA_VERSION - UNVERSIONED_SCHEMA) as usize] = [v1_to_v2];
+
+fn v1_to_v2(result: &mut Map<String, Value>) {
+    let metadata = result.entry("metadata").or_insert(Value::Null);
+    if metadata.is_null() {
+        *metadata = Value::Object(Map::new());
+    }
+}
+
+/// Version a saved result was written with
+pub fn schema_version(value: &Value) -> Result<u32> {
+    match value.get("schema_version") {
+        None | Some(Value::Null) => Ok(UNVERSIONED_SCHEMA),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= UNVERSIONED_SCHEMA)
+            .with_context(|| format!("Invalid schema_version {}", version)),
+    }
+}
+
+/// Upgrade a saved result's JSON to the current schema
+pub fn migrate(mut value: Value) -> Result<Value> {
+    let version = schema_version(&value)?;
+    if version > SCHEMA_VERSION {
+        bail!(
+            "Result has schema version {}, newer than the supported {}",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+
+    let Some(result) = value.as_object_mut() else {
+        bail!("Result is not a JSON object");
+    };
+    for migration in &MIGRATIONS[(version - UNVERSIONED_SCHEMA) as usize..] {
+        migration(result);
+    }
+    result.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+
+    if version < SCHEMA_VERSION {
+        log::debug!(
+            "Migrated result from schema version {} to {}",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(value)
+}
+
+/// Deserialize a saved result of any supported schema version
+pub fn result_from_value(value: Value) -> Result<BenchmarkResult> {
+    serde_json::from_value(migrate(value)?).context("Result doesn't match the current schema")
+}
+
+/// Parse a saved result of any supported schema version
+pub fn result_from_str(json: &str) -> Result<BenchmarkResult> {
+    result_from_value(serde_json::from_str(json).context("Invalid JSON")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unversioned_result_is_migrated() {
+        let result = result_from_str(
+            r#"{
+                "target_id": "legacy",
+                "metrics": {"latency_p50": 12.5},
+                "timestamp": "2024-06-01T12:00:00Z"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(result.schema_version, SCHEMA_VERSION);
+        assert_eq!(result.get_metric("latency_p50"), Some(12.5));
+        assert!(result.metadata.is_empty());
+
+        let current = BenchmarkResult::new("current".to_string(), Default::default());
+        let json = serde_json::to_string(&current).unwrap();
+        assert_eq!(result_from_str(&json).unwrap().target_id, "current");
+    }
+
+    #[test]
+    fn test_rejects_unknown_versions() {
+        let future = json!({
+            "schema_version": SCHEMA_VERSION + 1,
+            "target_id": "future",
+            "metrics": {},
+            "timestamp": "2030-01-01T00:00:00Z"
+        });
+        assert!(migrate(future).is_err());
+        assert!(migrate(json!({"schema_version": 0})).is_err());
+        assert!(migrate(json!({"schema_version": "2"})).is_err());
+        assert!(migrate(json!([1, 2])).is_err());
+    }
+}
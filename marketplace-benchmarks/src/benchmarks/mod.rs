@@ -4,11 +4,63 @@
 //! - Result structures for storing benchmark data
 //! - Markdown report generation
 //! - File I/O utilities for saving and loading results
+//! - Derived metric rules evaluated after a run
+//! - Soak mode for long-running leak detection
+//! - Suite execution with per-target failure tracking
+//! - Baseline comparison and regression detection
+//! - JUnit XML export for CI
+//! - Mergeable sample histograms
+//! - Merging results from distributed workers
+//! - Sample statistics for repeated runs
+//! - Result history and metric trends
+//! - CSV export
+//! - Metric display precision
+//! - Suite manifests declaring targets and their parameters
+//! - CPU, memory and file descriptor sampling while targets run
+//! - Schema migration of saved results
+//! - Flamegraphs of targets with the `profiling` feature
 
 pub mod result;
 pub mod markdown;
 pub mod io;
+pub mod derived;
+pub mod soak;
+pub mod run;
+pub mod compare;
+pub mod junit;
+pub mod histogram;
+pub mod merge;
+pub mod stats;
+pub mod history;
+pub mod csv;
+pub mod precision;
+pub mod manifest;
+pub mod resources;
+pub mod migrate;
+pub mod profiling;
 
-pub use result::BenchmarkResult;
-pub use markdown::generate_markdown_report;
-pub use io::{save_benchmark_result, load_benchmark_results};
+pub use result::{BenchmarkResult, SCHEMA_VERSION};
+pub use markdown::{generate_markdown_report, generate_markdown_report_with_baseline};
+pub use io::{
+    save_all_results_with, save_benchmark_result, save_benchmark_result_canonical,
+    load_benchmark_results, to_canonical_json, JsonStyle,
+};
+pub use derived::{apply_derived_metrics, load_derived_metrics, DerivedMetric};
+pub use soak::{run_soak, SoakConfig, SoakReport, SoakThresholds};
+pub use run::{
+    run_async_targets, BenchmarkRunSummary, RunConfig, TargetFailure, TargetOverrides,
+};
+pub use compare::{compare_results, load_thresholds, ComparisonReport, RegressionThreshold};
+pub use junit::{generate_junit_report, generate_run_junit_report};
+pub use histogram::Histogram;
+pub use merge::{merge_results, save_manifest, MergedRun, RunManifest, WorkerSummary};
+pub use stats::{aggregate_runs, MetricSample, MetricStats};
+pub use csv::{export_results, generate_csv};
+pub use precision::{load_precision, Precision, Unit};
+pub use manifest::{load_manifest, OutputSettings, SuiteManifest, SuiteSettings, TargetSpec};
+pub use resources::{ResourceSampler, ResourceUsage};
+pub use migrate::{migrate, result_from_str, result_from_value};
+pub use profiling::Profiler;
+pub use history::{
+    ingest_dir, trend_baseline, HistoryStore, MemoryHistory, TrendPoint, TrendQuery,
+};
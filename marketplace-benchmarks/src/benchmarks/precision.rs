@@ -0,0 +1,243 @@
+//! Metric display precision
+//!
+//! Reports round each metric by the unit its name implies, so values don't
+//! change width between runs and report diffs only show real changes:
+//!
+//! | Unit | Metrics | Decimals |
+//! |------|---------|----------|
+//! | `ms` | `latency_*`, `*_ms`, `*_ms_*` | 2 |
+//! | `rps` | `throughput*`, `*_rps`, `*_per_sec` | 0 |
+//! | `percent` | `*_pct`, `*_percent`, `*_rate` | 3 |
+//! | `other` | everything else | 2 |
+//!
+//! A config file overrides them, one `key = decimals` per line, where the
+//! key is a unit or a metric pattern:
+//!
+//! ```text
+//! # comments start with '#'
+//! ms = 3                  # every millisecond metric
+//! latency_p99 = 4         # one metric
+//! *_ns_p99 = 0            # '*' matches any part of a metric name
+//! ```
+//!
+//! The first matching pattern applies to a metric, before its unit's rule.
+//! Only the displayed value is rounded; stored results keep full precision.
+
+use crate::benchmarks::compare::glob_match;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Default location of the precision config
+pub const DEFAULT_PRECISION_PATH: &str = "benchmarks/precision.conf";
+
+/// Most decimals a rule may ask for; f64 has no more significant digits
+const MAX_DECIMALS: usize = 15;
+
+/// Unit of a metric, inferred from its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Milliseconds,
+    RequestsPerSecond,
+    Percent,
+    Other,
+}
+
+impl Unit {
+    const ALL: [Unit; 4] = [
+        Unit::Milliseconds,
+        Unit::RequestsPerSecond,
+        Unit::Percent,
+        Unit::Other,
+    ];
+
+    /// The unit a metric name implies
+    pub fn of(metric: &str) -> Self {
+        if metric.starts_with("throughput")
+            || metric.ends_with("_rps")
+            || metric.ends_with("_per_sec")
+        {
+            Unit::RequestsPerSecond
+        } else if metric.ends_with("_pct")
+            || metric.ends_with("_percent")
+            || metric.ends_with("_rate")
+        {
+            Unit::Percent
+        } else if metric.starts_with("latency_")
+            || metric.ends_with("_ms")
+            || metric.contains("_ms_")
+        {
+            Unit::Milliseconds
+        } else {
+            Unit::Other
+        }
+    }
+
+    /// Name used for the unit in the config
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Milliseconds => "ms",
+            Unit::RequestsPerSecond => "rps",
+            Unit::Percent => "percent",
+            Unit::Other => "other",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Decimals to display each metric with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Precision {
+    /// Decimals per unit, indexed by `Unit::index`
+    units: [usize; 4],
+    /// Metric patterns and their decimals, in config order
+    metrics: Vec<(String, usize)>,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self {
+            units: [2, 0, 3, 2],
+            metrics: Vec::new(),
+        }
+    }
+}
+
+impl Precision {
+    /// Parse a config over the defaults, ignoring blank lines and `#`
+    /// comments
+    pub fn parse(config: &str) -> Result<Self> {
+        let mut precision = Self::default();
+        for (i, line) in config.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            precision
+                .apply(line)
+                .with_context(|| format!("Line {}", i + 1))?;
+        }
+        Ok(precision)
+    }
+
+    fn apply(&mut self, rule: &str) -> Result<()> {
+        let Some((key, decimals)) = rule.split_once('=') else {
+            bail!(
+                "Expected 'metric = decimals' or 'unit = decimals', got: {}",
+                rule
+            );
+        };
+        let key = key.trim();
+        let decimals: usize = decimals
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid decimals in: {}", rule))?;
+        if decimals > MAX_DECIMALS {
+            bail!("At most {} decimals are supported: {}", MAX_DECIMALS, rule);
+        }
+
+        if let Some(unit) = Unit::ALL.iter().find(|unit| unit.as_str() == key) {
+            self.units[unit.index()] = decimals;
+            return Ok(());
+        }
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '*')
+        {
+            bail!("Invalid metric pattern: {:?}", key);
+        }
+        self.metrics.push((key.to_string(), decimals));
+        Ok(())
+    }
+
+    /// Decimals a metric is displayed with
+    pub fn decimals(&self, metric: &str) -> usize {
+        self.metrics
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, metric))
+            .map(|(_, decimals)| *decimals)
+            .unwrap_or(self.units[Unit::of(metric).index()])
+    }
+
+    /// A value of `metric`, rounded for display
+    pub fn format(&self, metric: &str, value: f64) -> String {
+        format!("{:.*}", self.decimals(metric), value)
+    }
+
+    /// A change in `metric`, rounded for display and always signed
+    pub fn format_signed(&self, metric: &str, delta: f64) -> String {
+        format!("{:+.*}", self.decimals(metric), delta)
+    }
+}
+
+/// Load the precision config
+///
+/// With no explicit path, uses `DEFAULT_PRECISION_PATH` and returns the
+/// defaults if it doesn't exist.
+pub fn load_precision(path: Option<&Path>) -> Result<Precision> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let default = Path::new(DEFAULT_PRECISION_PATH);
+            if !default.exists() {
+                return Ok(Precision::default());
+            }
+            default
+        }
+    };
+
+    let config = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read precision config: {:?}", path))?;
+    let precision = Precision::parse(&config)
+        .with_context(|| format!("Failed to parse precision config: {:?}", path))?;
+
+    log::info!("Loaded metric precision from {:?}", path);
+    Ok(precision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_precision_by_unit() {
+        let precision = Precision::default();
+
+        assert_eq!(precision.format("latency_p95", 12.34567), "12.35");
+        assert_eq!(precision.format("overhead_ms_p99", 0.5), "0.50");
+        assert_eq!(precision.format("throughput", 950.4), "950");
+        assert_eq!(precision.format("breaker_ops_per_sec", 1234.5678), "1235");
+        assert_eq!(precision.format("error_rate", 0.01234), "0.012");
+        assert_eq!(precision.format("delta_pct", 3.0), "3.000");
+        assert_eq!(precision.format("operation_count", 42.0), "42.00");
+        assert_eq!(precision.format_signed("throughput", -12.6), "-13");
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let precision = Precision::parse(
+            "# more precision for tail latency\n\
+             latency_p99 = 4\n\
+             *_ns_p99 = 0   # whole nanoseconds\n\
+             \n\
+             ms = 3\n\
+             rps = 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(precision.decimals("latency_p99"), 4);
+        assert_eq!(precision.decimals("latency_p50"), 3);
+        assert_eq!(precision.decimals("breaker_op_ns_p99"), 0);
+        assert_eq!(precision.decimals("throughput_rps"), 1);
+        assert_eq!(precision.decimals("error_rate"), 3);
+
+        assert!(Precision::parse("latency_p99 4").is_err());
+        assert!(Precision::parse("latency_p99 = -1").is_err());
+        assert!(Precision::parse("latency_p99 = 20").is_err());
+        assert!(Precision::parse("latency p99 = 2").is_err());
+    }
+}
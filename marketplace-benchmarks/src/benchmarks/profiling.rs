@@ -0,0 +1,147 @@
+//! Flamegraphs of slow targets
+//!
+//! Built with the `profiling` feature, a run with a `profile_dir` samples the
+//! call stacks of every target with pprof while it runs and writes them as a
+//! flamegraph SVG to that directory. The SVG is named like the result JSON,
+//! `<target_id>_<timestamp>.svg`, so with the results' output directory it
+//! lands next to the JSON, and its path is recorded in the result's
+//! [`FLAMEGRAPH_METADATA_KEY`] metadata.
+//!
+//! pprof samples the whole process, and only one profile can be taken at a
+//! time: with `jobs > 1`, a target that starts while another is being
+//! profiled runs without a flamegraph. Time spent in child processes such as
+//! the `node` pool shows up only as the adapter waiting on them.
+//!
+//! Without the feature no flamegraphs are written.
+
+use crate::benchmarks::result::BenchmarkResult;
+#[cfg(feature = "profiling")]
+use anyhow::Context;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Metadata key of the path of a result's flamegraph
+pub const FLAMEGRAPH_METADATA_KEY: &str = "flamegraph";
+
+/// Stack samples per second; off a round number so sampling doesn't run in
+/// lockstep with periodic work
+#[cfg(feature = "profiling")]
+pub const SAMPLE_FREQUENCY: i32 = 99;
+
+/// Libraries whose frames are left out, since unwinding through them can
+/// deadlock in the signal handler
+#[cfg(feature = "profiling")]
+const BLOCKLIST: [&str; 4] = ["libc", "libgcc", "pthread", "vdso"];
+
+/// Samples the process's call stacks until finished
+pub struct Profiler {
+    #[cfg(feature = "profiling")]
+    guard: pprof::ProfilerGuard<'static>,
+    #[cfg(feature = "profiling")]
+    dir: PathBuf,
+}
+
+impl Profiler {
+    /// Start sampling for a flamegraph to be written to `dir`; `None` if
+    /// another profile is being taken
+    #[cfg(feature = "profiling")]
+    pub fn start(dir: &Path) -> Option<Self> {
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(SAMPLE_FREQUENCY)
+            .blocklist(&BLOCKLIST)
+            .build();
+        match guard {
+            Ok(guard) => Some(Self {
+                guard,
+                dir: dir.to_path_buf(),
+            }),
+            Err(e) => {
+                log::warn!("Not profiling: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Without the `profiling` feature there is nothing to sample with
+    #[cfg(not(feature = "profiling"))]
+    pub fn start(_dir: &Path) -> Option<Self> {
+        static WARNED: std::sync::Once = std::sync::Once::new();
+        WARNED.call_once(|| {
+            log::warn!("Built without the profiling feature; not writing flamegraphs");
+        });
+        None
+    }
+
+    /// Stop sampling, write the flamegraph of `result` and record its path
+    pub fn finish(self, result: &mut BenchmarkResult) {
+        match self.write_flamegraph(result) {
+            Ok(path) => {
+                log::info!("Saved flamegraph of {} to: {:?}", result.target_id, path);
+                result.add_metadata(
+                    FLAMEGRAPH_METADATA_KEY.to_string(),
+                    path.display().to_string(),
+                );
+            }
+            Err(e) => {
+                log::warn!(
+                    "Failed to write flamegraph of {}: {:#}",
+                    result.target_id,
+                    e
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "profiling")]
+    fn write_flamegraph(&self, result: &BenchmarkResult) -> Result<PathBuf> {
+        let report = self
+            .guard
+            .report()
+            .build()
+            .context("Failed to build profile")?;
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create output directory: {:?}", self.dir))?;
+        let path = self
+            .dir
+            .join(format!("{}.svg", crate::benchmarks::io::file_stem(result)));
+        let file =
+            std::fs::File::create(&path).with_context(|| format!("Failed to create {:?}", path))?;
+        report
+            .flamegraph(file)
+            .context("Failed to render flamegraph")?;
+        Ok(path)
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    fn write_flamegraph(&self, _result: &BenchmarkResult) -> Result<PathBuf> {
+        anyhow::bail!("built without the profiling feature")
+    }
+}
+
+#[cfg(all(test, feature = "profiling"))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_flamegraph_next_to_result() {
+        let temp_dir = TempDir::new().unwrap();
+        let profiler = Profiler::start(temp_dir.path()).unwrap();
+        // A second profile can't be taken at the same time
+        assert!(Profiler::start(temp_dir.path()).is_none());
+
+        let mut sum = 0u64;
+        let start = std::time::Instant::now();
+        while start.elapsed() < std::time::Duration::from_millis(200) {
+            sum = sum.wrapping_add(std::hint::black_box(sum) ^ 7);
+        }
+
+        let mut result = BenchmarkResult::new("busy".to_string(), Default::default());
+        profiler.finish(&mut result);
+        let path = PathBuf::from(result.get_metadata(FLAMEGRAPH_METADATA_KEY).unwrap());
+        assert_eq!(path.parent(), Some(temp_dir.path()));
+        assert!(path.to_str().unwrap().ends_with(".svg"));
+        assert!(std::fs::read_to_string(&path).unwrap().contains("<svg"));
+    }
+}
@@ -0,0 +1,272 @@
+//! Resource sampling during benchmark runs
+//!
+//! A [`ResourceSampler`] runs on a thread of its own next to each target and
+//! records the process's CPU time, resident memory and open file
+//! descriptors, plus system-wide CPU, every [`SAMPLE_INTERVAL`]. When the
+//! target finishes, the samples are summarized into these metrics:
+//!
+//! | Metric | Meaning |
+//! |--------|---------|
+//! | `cpu_percent_avg` | Process CPU time over wall time; above 100 when several cores are busy |
+//! | `cpu_percent_peak` | Highest process CPU over one sample interval |
+//! | `system_cpu_percent_avg` | Busy share of all cores, from `/proc/stat` |
+//! | `memory_mb_avg`, `memory_mb_peak` | Resident set size |
+//! | `open_fds_peak` | Open file descriptors |
+//!
+//! Samples are always taken when the target starts and when it finishes, so
+//! short targets still get averages. The numbers cover the benchmark process
+//! only: child processes such as the `node` pool aren't included, and with
+//! `jobs > 1` targets running at the same time share the process totals.
+//!
+//! Sampling reads `/proc` and is only available on Linux; elsewhere no
+//! resource metrics are attached.
+
+use crate::benchmarks::result::BenchmarkResult;
+use serde::Serialize;
+use std::fs;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Time between samples while a target runs
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Clock ticks per second of `/proc` CPU times (`USER_HZ`), 100 on every
+/// Linux architecture
+const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+/// Process and system counters at one point in time
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    rss_mb: Option<f64>,
+    open_fds: Option<f64>,
+    /// User and system CPU time of the process so far
+    process_cpu_secs: Option<f64>,
+    /// Busy and total ticks of all cores so far
+    system_ticks: Option<(u64, u64)>,
+}
+
+impl Sample {
+    fn capture() -> Self {
+        Self {
+            at: Instant::now(),
+            rss_mb: rss_mb(),
+            open_fds: open_fds(),
+            process_cpu_secs: process_cpu_secs(),
+            system_ticks: system_ticks(),
+        }
+    }
+}
+
+/// Resident set size from `/proc/self/status`, in megabytes
+pub(crate) fn rss_mb() -> Option<f64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kb: f64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    Some(kb / 1024.0)
+}
+
+fn open_fds() -> Option<f64> {
+    Some(fs::read_dir("/proc/self/fd").ok()?.count() as f64)
+}
+
+/// `utime + stime` from `/proc/self/stat`, in seconds
+fn process_cpu_secs() -> Option<f64> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces; fields are counted after it
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) as f64 / CLOCK_TICKS_PER_SEC)
+}
+
+/// Busy and total ticks from the aggregate `cpu` line of `/proc/stat`
+fn system_ticks() -> Option<(u64, u64)> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+    parse_system_ticks(stat.lines().next()?)
+}
+
+fn parse_system_ticks(line: &str) -> Option<(u64, u64)> {
+    let ticks = line
+        .strip_prefix("cpu ")?
+        .split_whitespace()
+        .map(|field| field.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    // user nice system idle iowait irq softirq steal; guest time is already
+    // counted in user and nice
+    let total: u64 = ticks.iter().take(8).sum();
+    let idle = ticks.get(3)? + ticks.get(4).unwrap_or(&0);
+    Some((total - idle, total))
+}
+
+/// Summary of the resources a target used
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    pub samples: usize,
+    pub cpu_percent_avg: Option<f64>,
+    pub cpu_percent_peak: Option<f64>,
+    pub system_cpu_percent_avg: Option<f64>,
+    pub memory_mb_avg: Option<f64>,
+    pub memory_mb_peak: Option<f64>,
+    pub open_fds_peak: Option<f64>,
+}
+
+impl ResourceUsage {
+    fn from_samples(samples: &[Sample]) -> Self {
+        let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+            return Self::default();
+        };
+
+        let rss: Vec<f64> = samples.iter().filter_map(|sample| sample.rss_mb).collect();
+        let fds = samples.iter().filter_map(|sample| sample.open_fds);
+
+        let process_percent = |from: &Sample, to: &Sample| {
+            let wall = to.at.duration_since(from.at).as_secs_f64();
+            let cpu = to.process_cpu_secs? - from.process_cpu_secs?;
+            (wall > 0.0).then(|| cpu / wall * 100.0)
+        };
+        let cpu_percent_peak = samples
+            .windows(2)
+            .filter_map(|pair| process_percent(&pair[0], &pair[1]))
+            .reduce(f64::max);
+
+        let system_cpu_percent_avg = first.system_ticks.zip(last.system_ticks).and_then(
+            |((busy_from, total_from), (busy_to, total_to))| {
+                let total = total_to.checked_sub(total_from)?;
+                (total > 0).then(|| (busy_to - busy_from) as f64 / total as f64 * 100.0)
+            },
+        );
+
+        Self {
+            samples: samples.len(),
+            cpu_percent_avg: process_percent(first, last),
+            cpu_percent_peak,
+            system_cpu_percent_avg,
+            memory_mb_avg: (!rss.is_empty()).then(|| rss.iter().sum::<f64>() / rss.len() as f64),
+            memory_mb_peak: rss.iter().copied().reduce(f64::max),
+            open_fds_peak: fds.reduce(f64::max),
+        }
+    }
+
+    /// Metrics that could be measured, by name
+    pub fn metrics(&self) -> Vec<(&'static str, f64)> {
+        [
+            ("cpu_percent_avg", self.cpu_percent_avg),
+            ("cpu_percent_peak", self.cpu_percent_peak),
+            ("system_cpu_percent_avg", self.system_cpu_percent_avg),
+            ("memory_mb_avg", self.memory_mb_avg),
+            ("memory_mb_peak", self.memory_mb_peak),
+            ("open_fds_peak", self.open_fds_peak),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+
+    /// Add the metrics to a result, keeping any the target measured itself
+    pub fn attach(&self, result: &mut BenchmarkResult) {
+        let metrics = self.metrics();
+        if metrics.is_empty() {
+            return;
+        }
+        for (name, value) in metrics {
+            if result.get_metric(name).is_none() {
+                result.add_metric(name.to_string(), value);
+            }
+        }
+        result.add_metadata("resource_samples".to_string(), self.samples.to_string());
+    }
+}
+
+/// Samples resources on a background thread until stopped
+pub struct ResourceSampler {
+    stop: mpsc::Sender<()>,
+    sampler: JoinHandle<Vec<Sample>>,
+}
+
+impl ResourceSampler {
+    /// Start sampling every `interval`
+    pub fn start(interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let first = Sample::capture();
+        let sampler = thread::spawn(move || {
+            let mut samples = vec![first];
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                samples.push(Sample::capture());
+            }
+            samples.push(Sample::capture());
+            samples
+        });
+        Self { stop, sampler }
+    }
+
+    /// Stop sampling and summarize the samples
+    pub fn stop(self) -> ResourceUsage {
+        drop(self.stop);
+        match self.sampler.join() {
+            Ok(samples) => ResourceUsage::from_samples(&samples),
+            Err(_) => {
+                log::warn!("Resource sampler thread panicked");
+                ResourceUsage::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(start: Instant, secs: f64, rss_mb: f64, cpu_secs: f64, ticks: (u64, u64)) -> Sample {
+        Sample {
+            at: start + Duration::from_secs_f64(secs),
+            rss_mb: Some(rss_mb),
+            open_fds: Some(10.0 + secs),
+            process_cpu_secs: Some(cpu_secs),
+            system_ticks: Some(ticks),
+        }
+    }
+
+    #[test]
+    fn test_usage_from_samples() {
+        let start = Instant::now();
+        let usage = ResourceUsage::from_samples(&[
+            sample(start, 0.0, 100.0, 5.0, (1_000, 4_000)),
+            // One core busy for the first second, a quarter of one after
+            sample(start, 1.0, 160.0, 6.0, (1_100, 4_400)),
+            sample(start, 2.0, 120.0, 6.25, (1_300, 4_800)),
+        ]);
+
+        assert_eq!(usage.samples, 3);
+        assert_eq!(usage.cpu_percent_avg, Some(62.5));
+        assert_eq!(usage.cpu_percent_peak, Some(100.0));
+        assert_eq!(usage.system_cpu_percent_avg, Some(37.5));
+        assert_eq!(usage.memory_mb_avg, Some(380.0 / 3.0));
+        assert_eq!(usage.memory_mb_peak, Some(160.0));
+        assert_eq!(usage.open_fds_peak, Some(12.0));
+
+        let mut result = BenchmarkResult::new("target".to_string(), Default::default());
+        result.add_metric("memory_mb_peak".to_string(), 42.0);
+        usage.attach(&mut result);
+        assert_eq!(result.get_metric("memory_mb_peak"), Some(42.0));
+        assert_eq!(result.get_metric("cpu_percent_avg"), Some(62.5));
+        assert_eq!(result.get_metadata("resource_samples").unwrap(), "3");
+
+        assert!(ResourceUsage::from_samples(&[]).metrics().is_empty());
+    }
+
+    #[test]
+    fn test_parse_system_ticks() {
+        assert_eq!(
+            parse_system_ticks("cpu  100 5 50 800 40 3 2 0 0 0"),
+            Some((160, 1000))
+        );
+        assert_eq!(parse_system_ticks("cpu0 100 5 50 800"), None);
+    }
+}
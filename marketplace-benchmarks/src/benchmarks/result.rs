@@ -4,9 +4,15 @@
 //! benchmark targets must return. It provides a standardized format for
 //! capturing performance metrics, metadata, and timestamps.
 
+use crate::benchmarks::histogram::Histogram;
+use crate::benchmarks::stats::{MetricSample, MetricStats};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+/// Schema version of results written by this build; older saved results are
+/// upgraded on load by [`migrate`](crate::benchmarks::migrate)
+pub const SCHEMA_VERSION: u32 = 2;
 
 /// Represents the result of a single benchmark execution
 ///
@@ -14,6 +20,11 @@ use std::collections::HashMap;
 /// It captures the target identifier, performance metrics, and execution metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkResult {
+    /// Version of the result schema; results saved before versioning read
+    /// as version 1
+    #[serde(default = "unversioned_schema")]
+    pub schema_version: u32,
+
     /// Unique identifier for the benchmark target (e.g., "api-gateway", "redis")
     pub target_id: String,
 
@@ -32,6 +43,22 @@ pub struct BenchmarkResult {
     /// Optional metadata about the benchmark run
     #[serde(default)]
     pub metadata: HashMap<String, String>,
+
+    /// Sample distributions behind percentile metrics, keyed by the metric
+    /// prefix (e.g. "latency" for latency_p50..p99), so results from several
+    /// workers can be merged without averaging percentiles
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub histograms: BTreeMap<String, Histogram>,
+
+    /// Raw samples behind metrics measured more than once (e.g. one value
+    /// per run), keyed by metric name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub samples: BTreeMap<String, MetricSample>,
+
+    /// Mean, standard deviation, range and 95% confidence interval of each
+    /// metric in `samples`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stats: BTreeMap<String, MetricStats>,
 }
 
 impl BenchmarkResult {
@@ -59,10 +86,14 @@ impl BenchmarkResult {
     /// ```
     pub fn new(target_id: String, metrics: HashMap<String, f64>) -> Self {
         Self {
+            schema_version: SCHEMA_VERSION,
             target_id,
             metrics,
             timestamp: Utc::now(),
             metadata: HashMap::new(),
+            histograms: BTreeMap::new(),
+            samples: BTreeMap::new(),
+            stats: BTreeMap::new(),
         }
     }
 
@@ -79,10 +110,14 @@ impl BenchmarkResult {
         metadata: HashMap<String, String>,
     ) -> Self {
         Self {
+            schema_version: SCHEMA_VERSION,
             target_id,
             metrics,
             timestamp: Utc::now(),
             metadata,
+            histograms: BTreeMap::new(),
+            samples: BTreeMap::new(),
+            stats: BTreeMap::new(),
         }
     }
 
@@ -96,6 +131,32 @@ impl BenchmarkResult {
         self.metadata.insert(key, value);
     }
 
+    /// Attaches the sample distribution of a percentile metric family
+    pub fn add_histogram(&mut self, prefix: String, histogram: Histogram) {
+        self.histograms.insert(prefix, histogram);
+    }
+
+    /// Records the raw samples of a metric; the metric becomes their mean
+    /// and its statistics are computed from them. An empty sample set is
+    /// ignored.
+    pub fn add_samples(&mut self, key: String, sample: MetricSample) {
+        let Some(stats) = sample.stats() else {
+            return;
+        };
+        self.metrics.insert(key.clone(), stats.mean);
+        self.stats.insert(key.clone(), stats);
+        self.samples.insert(key, sample);
+    }
+
+    /// Records the build that produced the result (`git_commit`,
+    /// `git_branch`, `build_timestamp`, `rustc_version`), so results can be
+    /// traced to the exact benchmark binary
+    pub fn stamp_build_info(&mut self) {
+        for (key, value) in llm_infra::build_info!().metadata() {
+            self.metadata.insert(key.to_string(), value.to_string());
+        }
+    }
+
     /// Gets a metric value by key
     pub fn get_metric(&self, key: &str) -> Option<f64> {
         self.metrics.get(key).copied()
@@ -107,6 +168,10 @@ impl BenchmarkResult {
     }
 }
 
+fn unversioned_schema() -> u32 {
+    crate::benchmarks::migrate::UNVERSIONED_SCHEMA
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +215,25 @@ mod tests {
         assert_eq!(result.get_metric("new_metric"), Some(42.0));
     }
 
+    #[test]
+    fn test_add_samples() {
+        let mut result = BenchmarkResult::new("test".to_string(), HashMap::new());
+        result.add_samples(
+            "latency_p95".to_string(),
+            MetricSample::new([10.0, 12.0, 14.0]),
+        );
+        result.add_samples("empty".to_string(), MetricSample::default());
+
+        assert_eq!(result.get_metric("latency_p95"), Some(12.0));
+        assert_eq!(result.stats["latency_p95"].stddev, 2.0);
+        assert_eq!(result.get_metric("empty"), None);
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains("\"ci95\""));
+        let parsed: BenchmarkResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.stats, result.stats);
+    }
+
     #[test]
     fn test_serialization() {
         let mut metrics = HashMap::new();
@@ -0,0 +1,926 @@
+//! Suite execution with per-target failure tracking
+//!
+//! A failing adapter either stops the suite (`fail_fast`) or is recorded and
+//! the remaining targets still run, so one broken target doesn't throw away
+//! the results of the others. Targets whose declared dependencies are
+//! missing are skipped with the reason rather than run into failures.
+//!
+//! With `jobs > 1` targets run on a pool of worker threads. Each target runs
+//! on its own worker with panics caught, and outcomes are collected in
+//! registry order, so the summary doesn't depend on which worker finished
+//! first.
+//!
+//! [`TargetOverrides`] change the iteration budget of every target that
+//! declares a [`TargetConfig`], e.g. for a quick smoke pass or a long one.
+//!
+//! With `runs > 1` each target is run that many times back to back and the
+//! runs are combined by [`aggregate_runs`]: every metric is the mean of the
+//! runs, with its standard deviation and 95% confidence interval. A failure
+//! in any run fails the target.
+//!
+//! With a `timeout` each target runs on a thread of its own and is abandoned
+//! once the timeout expires: the runner calls [`BenchTarget::cancel`], which
+//! kills the adapter's child processes, records the target as failed with
+//! `timed_out` set and moves on, so a hung `node` process can't stall the
+//! suite. Timeouts count as failures for `fail_fast`.
+//!
+//! [`run_async_targets`] does the same for [`AsyncBenchTarget`]s on the
+//! caller's tokio runtime, with tasks in place of worker threads.
+//!
+//! Every target runs alongside a [`ResourceSampler`], and the CPU, memory
+//! and file descriptor metrics it measured are added to the target's result.
+//! Results also record the target's tags, which reports group them by.
+//!
+//! With a `profile_dir`, each target also runs under a [`Profiler`] that
+//! writes its flamegraph there (feature `profiling`).
+
+use crate::adapters::deps::{first_unmet, Dependency};
+use crate::adapters::{AsyncBenchTarget, BenchTarget, TargetConfig, TargetRegistry};
+use crate::benchmarks::profiling::Profiler;
+use crate::benchmarks::resources::{ResourceSampler, SAMPLE_INTERVAL};
+use crate::benchmarks::result::BenchmarkResult;
+use crate::benchmarks::stats::aggregate_runs;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const FAIL_FAST_SKIP_REASON: &str = "not run after an earlier failure";
+
+/// Metadata key of the comma-separated tags of the target a result is from
+pub const TAGS_METADATA_KEY: &str = "tags";
+
+/// Options for [`run_all_benchmarks_with`](crate::run_all_benchmarks_with)
+#[derive(Debug, Clone, Default)]
+pub struct RunConfig {
+    /// Stop at the first failing target; the rest are reported as skipped
+    pub fail_fast: bool,
+
+    /// Target ids to run; empty runs every registered target
+    pub targets: Vec<String>,
+
+    /// Only run targets with one of these tags; empty doesn't filter
+    pub tags: Vec<String>,
+
+    /// Worker threads running targets concurrently; 0 and 1 run them one at
+    /// a time on the calling thread
+    pub jobs: usize,
+
+    /// Changes to every target's iteration budget
+    pub overrides: TargetOverrides,
+
+    /// Times each target is run, for statistics across runs; 0 and 1 run it
+    /// once
+    pub runs: usize,
+
+    /// Longest a target may take, all runs included; `None` waits forever
+    pub timeout: Option<Duration>,
+
+    /// Directory to write a flamegraph of each target to, usually the
+    /// results' output directory; needs the `profiling` feature
+    pub profile_dir: Option<PathBuf>,
+}
+
+/// Changes to the [`TargetConfig`] of every target; unset fields keep each
+/// target's default. Targets without a config ignore them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TargetOverrides {
+    pub warmup_iterations: Option<usize>,
+    pub measured_iterations: Option<usize>,
+    pub max_duration: Option<Duration>,
+}
+
+impl TargetOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// A target's defaults with the overrides applied
+    pub fn apply(&self, defaults: TargetConfig) -> TargetConfig {
+        TargetConfig {
+            warmup_iterations: self.warmup_iterations.unwrap_or(defaults.warmup_iterations),
+            measured_iterations: self
+                .measured_iterations
+                .unwrap_or(defaults.measured_iterations),
+            max_duration: self.max_duration.or(defaults.max_duration),
+        }
+    }
+}
+
+/// A target whose run returned an error or timed out
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetFailure {
+    pub target_id: String,
+    pub error: String,
+    /// Whether the target was abandoned after the run's timeout
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+}
+
+/// A target that was not run
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedTarget {
+    pub target_id: String,
+    pub reason: String,
+}
+
+/// Outcome of a suite run
+#[derive(Debug, Default, Serialize)]
+pub struct BenchmarkRunSummary {
+    /// Results of the targets that succeeded, in run order
+    pub results: Vec<BenchmarkResult>,
+    pub succeeded: Vec<String>,
+    pub failed: Vec<TargetFailure>,
+    /// Targets with an unmet dependency, or not run because an earlier one
+    /// failed in fail-fast mode
+    pub skipped: Vec<SkippedTarget>,
+}
+
+impl BenchmarkRunSummary {
+    /// Whether no target failed; targets skipped for their environment don't
+    /// count as failures
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Run the configured targets of the built-in registry. Only an unknown
+/// target id or tag is an error; target failures are collected in the
+/// summary.
+pub fn run_with(config: &RunConfig) -> Result<BenchmarkRunSummary> {
+    TargetRegistry::builtin().run(config)
+}
+
+/// What happened to one target
+enum TargetOutcome {
+    Completed(BenchmarkResult),
+    Failed(String),
+    TimedOut(Duration),
+    Skipped(String),
+}
+
+impl TargetOutcome {
+    /// Whether the outcome stops a fail-fast run
+    fn is_failure(&self) -> bool {
+        matches!(self, TargetOutcome::Failed(_) | TargetOutcome::TimedOut(_))
+    }
+}
+
+pub(crate) fn run_targets(
+    targets: Vec<Arc<dyn BenchTarget>>,
+    fail_fast: bool,
+    jobs: usize,
+    overrides: &TargetOverrides,
+    runs: usize,
+    timeout: Option<Duration>,
+    profile_dir: Option<&Path>,
+) -> BenchmarkRunSummary {
+    // Probe everything up front so missing dependencies show before a long run
+    let unmet: Vec<Option<String>> = targets
+        .iter()
+        .map(|target| unmet_dependency(target.id(), &target.dependencies()))
+        .collect();
+
+    let jobs = jobs.clamp(1, targets.len().max(1));
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let outcomes: Vec<Mutex<Option<TargetOutcome>>> =
+        targets.iter().map(|_| Mutex::new(None)).collect();
+
+    // Workers claim targets in registry order until none are left
+    let work = || loop {
+        let index = next.fetch_add(1, Ordering::SeqCst);
+        let Some(target) = targets.get(index) else {
+            break;
+        };
+
+        let outcome = if fail_fast && stop.load(Ordering::SeqCst) {
+            TargetOutcome::Skipped(FAIL_FAST_SKIP_REASON.to_string())
+        } else if let Some(reason) = &unmet[index] {
+            TargetOutcome::Skipped(reason.clone())
+        } else {
+            let outcome = run_isolated(target, overrides, runs, timeout, profile_dir);
+            if outcome.is_failure() {
+                stop.store(true, Ordering::SeqCst);
+            }
+            outcome
+        };
+        *outcomes[index].lock().unwrap() = Some(outcome);
+    };
+
+    if jobs == 1 {
+        work();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(work);
+            }
+        });
+    }
+
+    let outcomes = targets.iter().zip(outcomes).map(|(target, outcome)| {
+        let outcome = outcome.into_inner().unwrap();
+        let outcome = outcome.expect("every target is claimed by a worker");
+        (target.id().to_string(), outcome)
+    });
+    summarize(outcomes, jobs)
+}
+
+/// Run async targets as tasks on the current tokio runtime, at most
+/// `config.jobs` at once
+///
+/// Otherwise like [`run_with`]: `config.targets` selects targets by id and
+/// `config.tags` by tag, outcomes keep the order of `targets`, errors and
+/// panics are failures, and targets with unmet dependencies are skipped.
+pub async fn run_async_targets(
+    targets: Vec<Arc<dyn AsyncBenchTarget>>,
+    config: &RunConfig,
+) -> Result<BenchmarkRunSummary> {
+    if let Some(unknown) = config
+        .targets
+        .iter()
+        .find(|id| !targets.iter().any(|target| target.id() == id.as_str()))
+    {
+        bail!("Unknown benchmark target '{}'", unknown);
+    }
+    let targets: Vec<Arc<dyn AsyncBenchTarget>> = targets
+        .into_iter()
+        .filter(|target| {
+            config.targets.is_empty() || config.targets.iter().any(|id| id == target.id())
+        })
+        .filter(|target| {
+            config.tags.is_empty()
+                || target
+                    .tags()
+                    .iter()
+                    .any(|tag| config.tags.iter().any(|t| t == tag))
+        })
+        .collect();
+
+    let jobs = config.jobs.clamp(1, targets.len().max(1));
+    log::info!(
+        "Starting async benchmark run for {} targets with {} jobs",
+        targets.len(),
+        jobs
+    );
+
+    // Dependency probes connect with blocking sockets
+    let probed = targets.clone();
+    let unmet = tokio::task::spawn_blocking(move || {
+        probed
+            .iter()
+            .map(|target| unmet_dependency(target.id(), &target.dependencies()))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .context("Dependency probe panicked")?;
+
+    let slots = Arc::new(Semaphore::new(jobs));
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut tasks = Vec::with_capacity(targets.len());
+    for (target, unmet) in targets.iter().zip(unmet) {
+        // Claim a slot before spawning so targets start in order
+        let slot = Arc::clone(&slots)
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let (target, stop) = (Arc::clone(target), Arc::clone(&stop));
+        let (fail_fast, overrides, runs, timeout) = (
+            config.fail_fast,
+            config.overrides,
+            config.runs,
+            config.timeout,
+        );
+        let profile_dir = config.profile_dir.clone();
+
+        tasks.push(tokio::spawn(async move {
+            let _slot = slot;
+            if fail_fast && stop.load(Ordering::SeqCst) {
+                TargetOutcome::Skipped(FAIL_FAST_SKIP_REASON.to_string())
+            } else if let Some(reason) = unmet {
+                TargetOutcome::Skipped(reason)
+            } else {
+                let outcome =
+                    run_async_isolated(target, &overrides, runs, timeout, profile_dir).await;
+                if outcome.is_failure() {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                outcome
+            }
+        }));
+    }
+
+    let mut outcomes = Vec::with_capacity(tasks.len());
+    for (target, task) in targets.iter().zip(tasks) {
+        let outcome = task
+            .await
+            .unwrap_or_else(|e| TargetOutcome::Failed(format!("task failed: {}", e)));
+        outcomes.push((target.id().to_string(), outcome));
+    }
+    Ok(summarize(outcomes, jobs))
+}
+
+/// Why a target can't run, logged as a skip
+fn unmet_dependency(target_id: &str, dependencies: &[Dependency]) -> Option<String> {
+    let reason = first_unmet(dependencies);
+    if let Some(reason) = &reason {
+        log::warn!("Skipping benchmark {}: {}", target_id, reason);
+    }
+    reason
+}
+
+/// Collect outcomes, given in run order, into a summary
+fn summarize(
+    outcomes: impl IntoIterator<Item = (String, TargetOutcome)>,
+    jobs: usize,
+) -> BenchmarkRunSummary {
+    let mut summary = BenchmarkRunSummary::default();
+    for (target_id, outcome) in outcomes {
+        match outcome {
+            TargetOutcome::Completed(mut result) => {
+                result.add_metadata("parallel_jobs".to_string(), jobs.to_string());
+                result.stamp_build_info();
+                summary.succeeded.push(target_id);
+                summary.results.push(result);
+            }
+            TargetOutcome::Failed(error) => {
+                summary.failed.push(TargetFailure {
+                    target_id,
+                    error,
+                    timed_out: false,
+                });
+            }
+            TargetOutcome::TimedOut(timeout) => {
+                summary.failed.push(TargetFailure {
+                    target_id,
+                    error: format!("timed out after {:?}", timeout),
+                    timed_out: true,
+                });
+            }
+            TargetOutcome::Skipped(reason) => {
+                summary.skipped.push(SkippedTarget { target_id, reason });
+            }
+        }
+    }
+
+    log::info!(
+        "Benchmarks completed: {} succeeded, {} failed, {} skipped",
+        summary.succeeded.len(),
+        summary.failed.len(),
+        summary.skipped.len()
+    );
+    summary
+}
+
+/// Run one target `runs` times within its iteration budget and `timeout`,
+/// turning a panic into a failure so it can't take down the worker or the
+/// other targets
+fn run_isolated(
+    target: &Arc<dyn BenchTarget>,
+    overrides: &TargetOverrides,
+    runs: usize,
+    timeout: Option<Duration>,
+    profile_dir: Option<&Path>,
+) -> TargetOutcome {
+    log::info!("Running benchmark: {}", target.id());
+    let config = target_config(target.id(), target.config(), overrides);
+    let Some(timeout) = timeout else {
+        return run_repeated(target.as_ref(), config, runs, profile_dir);
+    };
+
+    // A hung run keeps its thread; the runner only stops waiting for it
+    let (sender, receiver) = mpsc::channel();
+    let running = Arc::clone(target);
+    let profile_dir = profile_dir.map(Path::to_path_buf);
+    let spawned = thread::Builder::new()
+        .name(format!("bench-{}", target.id()))
+        .spawn(move || {
+            let outcome = run_repeated(running.as_ref(), config, runs, profile_dir.as_deref());
+            let _ = sender.send(outcome);
+        });
+    if let Err(e) = spawned {
+        return TargetOutcome::Failed(format!("failed to start target thread: {}", e));
+    }
+
+    match receiver.recv_timeout(timeout) {
+        Ok(outcome) => outcome,
+        Err(RecvTimeoutError::Timeout) => {
+            log::error!(
+                "Benchmark {} timed out after {:?}; cancelling it",
+                target.id(),
+                timeout
+            );
+            target.cancel();
+            TargetOutcome::TimedOut(timeout)
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            TargetOutcome::Failed("target thread exited without a result".to_string())
+        }
+    }
+}
+
+fn run_repeated(
+    target: &dyn BenchTarget,
+    config: Option<TargetConfig>,
+    runs: usize,
+    profile_dir: Option<&Path>,
+) -> TargetOutcome {
+    let run = || match &config {
+        Some(config) => target.run_with(config),
+        None => target.run(),
+    };
+    let profiler = profile_dir.and_then(Profiler::start);
+    let sampler = ResourceSampler::start(SAMPLE_INTERVAL);
+    let mut results = Vec::with_capacity(runs.max(1));
+    for _ in 0..runs.max(1) {
+        match panic::catch_unwind(AssertUnwindSafe(run)) {
+            Ok(result) => results.push(result),
+            Err(payload) => return panicked(target.id(), payload),
+        }
+    }
+    let usage = sampler.stop();
+    let result = combine(results).map(|mut result| {
+        usage.attach(&mut result);
+        stamp_tags(&mut result, target.tags().join(","));
+        if let Some(profiler) = profiler {
+            profiler.finish(&mut result);
+        }
+        result
+    });
+    finish(target.id(), result, config)
+}
+
+/// Run one async target `runs` times in a task of its own, so a panic
+/// fails only the target, aborting the task after `timeout`
+async fn run_async_isolated(
+    target: Arc<dyn AsyncBenchTarget>,
+    overrides: &TargetOverrides,
+    runs: usize,
+    timeout: Option<Duration>,
+    profile_dir: Option<PathBuf>,
+) -> TargetOutcome {
+    let target_id = target.id().to_string();
+    log::info!("Running benchmark: {}", target_id);
+    let config = target_config(&target_id, target.config(), overrides);
+    let tags = target.tags().join(",");
+
+    let profiler = profile_dir.as_deref().and_then(Profiler::start);
+    let sampler = ResourceSampler::start(SAMPLE_INTERVAL);
+    let mut run = tokio::spawn(async move {
+        let mut results = Vec::with_capacity(runs.max(1));
+        for _ in 0..runs.max(1) {
+            results.push(match &config {
+                Some(config) => target.run_with(config).await,
+                None => target.run().await,
+            });
+        }
+        results
+    });
+    let joined = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, &mut run).await {
+            Ok(joined) => joined,
+            Err(_) => {
+                log::error!(
+                    "Benchmark {} timed out after {:?}; aborting it",
+                    target_id,
+                    timeout
+                );
+                run.abort();
+                return TargetOutcome::TimedOut(timeout);
+            }
+        },
+        None => run.await,
+    };
+    let usage = sampler.stop();
+    match joined {
+        Ok(results) => {
+            let result = combine(results).map(|mut result| {
+                usage.attach(&mut result);
+                stamp_tags(&mut result, tags);
+                if let Some(profiler) = profiler {
+                    profiler.finish(&mut result);
+                }
+                result
+            });
+            finish(&target_id, result, config)
+        }
+        Err(e) if e.is_panic() => panicked(&target_id, e.into_panic()),
+        Err(e) => TargetOutcome::Failed(format!("task failed: {}", e)),
+    }
+}
+
+/// Record a target's comma-separated tags, unless the target set its own
+fn stamp_tags(result: &mut BenchmarkResult, tags: String) {
+    if !tags.is_empty() && result.get_metadata(TAGS_METADATA_KEY).is_none() {
+        result.add_metadata(TAGS_METADATA_KEY.to_string(), tags);
+    }
+}
+
+/// The first failed run's error, else the runs aggregated into one result
+fn combine(results: Vec<Result<BenchmarkResult>>) -> Result<BenchmarkResult> {
+    let results = results.into_iter().collect::<Result<Vec<_>>>()?;
+    aggregate_runs(results).context("no runs")
+}
+
+/// A target's iteration budget with the overrides applied
+fn target_config(
+    target_id: &str,
+    defaults: Option<TargetConfig>,
+    overrides: &TargetOverrides,
+) -> Option<TargetConfig> {
+    if defaults.is_none() && !overrides.is_empty() {
+        log::warn!(
+            "Benchmark {} has fixed iterations; ignoring overrides",
+            target_id
+        );
+    }
+    defaults.map(|defaults| overrides.apply(defaults))
+}
+
+fn finish(
+    target_id: &str,
+    result: Result<BenchmarkResult>,
+    config: Option<TargetConfig>,
+) -> TargetOutcome {
+    match result {
+        Ok(mut result) => {
+            log::info!("Benchmark {} completed successfully", target_id);
+            if let Some(config) = config {
+                stamp_target_config(&mut result, &config);
+            }
+            TargetOutcome::Completed(result)
+        }
+        Err(e) => {
+            log::error!("Benchmark {} failed: {:#}", target_id, e);
+            TargetOutcome::Failed(format!("{:#}", e))
+        }
+    }
+}
+
+fn panicked(target_id: &str, payload: Box<dyn Any + Send>) -> TargetOutcome {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    log::error!("Benchmark {} panicked: {}", target_id, message);
+    TargetOutcome::Failed(format!("panicked: {}", message))
+}
+
+/// Record the iteration budget a result was measured with
+fn stamp_target_config(result: &mut BenchmarkResult, config: &TargetConfig) {
+    result.add_metadata(
+        "warmup_iterations".to_string(),
+        config.warmup_iterations.to_string(),
+    );
+    result.add_metadata(
+        "measured_iterations".to_string(),
+        config.measured_iterations.to_string(),
+    );
+    if let Some(max_duration) = config.max_duration {
+        result.add_metadata(
+            "max_duration_ms".to_string(),
+            max_duration.as_millis().to_string(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::{Dependency, ExampleBenchmark};
+    use std::path::PathBuf;
+
+    struct Broken;
+
+    impl BenchTarget for Broken {
+        fn id(&self) -> &str {
+            "broken"
+        }
+
+        fn run(&self) -> Result<BenchmarkResult> {
+            anyhow::bail!("adapter crashed")
+        }
+    }
+
+    struct NeedsWrapper;
+
+    impl BenchTarget for NeedsWrapper {
+        fn id(&self) -> &str {
+            "needs-wrapper"
+        }
+
+        fn run(&self) -> Result<BenchmarkResult> {
+            unreachable!("dependencies are unmet")
+        }
+
+        fn dependencies(&self) -> Vec<Dependency> {
+            vec![Dependency::File(PathBuf::from("/nonexistent/wrapper.ts"))]
+        }
+    }
+
+    struct Slow(&'static str, u64);
+
+    impl BenchTarget for Slow {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn run(&self) -> Result<BenchmarkResult> {
+            std::thread::sleep(std::time::Duration::from_millis(self.1));
+            ExampleBenchmark::new(self.0.to_string()).run()
+        }
+    }
+
+    struct Panics;
+
+    impl BenchTarget for Panics {
+        fn id(&self) -> &str {
+            "panics"
+        }
+
+        fn run(&self) -> Result<BenchmarkResult> {
+            panic!("index out of bounds")
+        }
+    }
+
+    /// Reports the measured iterations it was asked for
+    struct Counted;
+
+    impl BenchTarget for Counted {
+        fn id(&self) -> &str {
+            "counted"
+        }
+
+        fn run(&self) -> Result<BenchmarkResult> {
+            unreachable!("the runner calls run_with for configurable targets")
+        }
+
+        fn config(&self) -> Option<TargetConfig> {
+            Some(TargetConfig::new(5, 100))
+        }
+
+        fn run_with(&self, config: &TargetConfig) -> Result<BenchmarkResult> {
+            let mut metrics = std::collections::HashMap::new();
+            metrics.insert(
+                "operation_count".to_string(),
+                config.measured_iterations as f64,
+            );
+            Ok(BenchmarkResult::new(self.id().to_string(), metrics))
+        }
+    }
+
+    /// Reports 10, 20, 30, ... on successive runs
+    struct Drifts(AtomicUsize);
+
+    impl BenchTarget for Drifts {
+        fn id(&self) -> &str {
+            "drifts"
+        }
+
+        fn run(&self) -> Result<BenchmarkResult> {
+            let run = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            let mut metrics = std::collections::HashMap::new();
+            metrics.insert("latency_p50".to_string(), run as f64 * 10.0);
+            Ok(BenchmarkResult::new(self.id().to_string(), metrics))
+        }
+    }
+
+    /// Sleeps on the runtime, then panics if it has no time budget
+    struct Sleeps(&'static str, u64);
+
+    #[async_trait::async_trait]
+    impl AsyncBenchTarget for Sleeps {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        async fn run(&self) -> Result<BenchmarkResult> {
+            tokio::time::sleep(Duration::from_millis(self.1)).await;
+            if self.1 == 0 {
+                panic!("no time budget");
+            }
+            ExampleBenchmark::new(self.0.to_string()).run()
+        }
+    }
+
+    /// Blocks until cancelled, like an adapter stuck on a hung process
+    struct Hangs(AtomicBool);
+
+    impl BenchTarget for Hangs {
+        fn id(&self) -> &str {
+            "hangs"
+        }
+
+        fn run(&self) -> Result<BenchmarkResult> {
+            while !self.0.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(5));
+            }
+            anyhow::bail!("wrapper process killed")
+        }
+
+        fn cancel(&self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn suite() -> Vec<Arc<dyn BenchTarget>> {
+        vec![
+            Arc::new(ExampleBenchmark::new("first".to_string())),
+            Arc::new(Broken),
+            Arc::new(ExampleBenchmark::new("last".to_string())),
+        ]
+    }
+
+    #[test]
+    fn test_continue_on_error_keeps_other_results() {
+        let summary = run_targets(
+            suite(),
+            false,
+            1,
+            &TargetOverrides::default(),
+            1,
+            None,
+            None,
+        );
+        assert_eq!(summary.succeeded, vec!["first", "last"]);
+        assert_eq!(summary.results.len(), 2);
+        assert_eq!(
+            summary.results[0].get_metadata(TAGS_METADATA_KEY).unwrap(),
+            "example"
+        );
+        assert_eq!(summary.failed[0].target_id, "broken");
+        assert_eq!(summary.failed[0].error, "adapter crashed");
+        assert!(summary.skipped.is_empty());
+        assert!(!summary.is_success());
+    }
+
+    #[test]
+    fn test_fail_fast_skips_remaining_targets() {
+        let summary = run_targets(suite(), true, 1, &TargetOverrides::default(), 1, None, None);
+        assert_eq!(summary.succeeded, vec!["first"]);
+        assert_eq!(summary.skipped[0].target_id, "last");
+        assert_eq!(summary.skipped[0].reason, "not run after an earlier failure");
+    }
+
+    #[test]
+    fn test_unmet_dependencies_skip_target() {
+        let targets: Vec<Arc<dyn BenchTarget>> = vec![
+            Arc::new(NeedsWrapper),
+            Arc::new(ExampleBenchmark::new("first".to_string())),
+        ];
+        let summary = run_targets(targets, true, 1, &TargetOverrides::default(), 1, None, None);
+        assert_eq!(summary.succeeded, vec!["first"]);
+        assert_eq!(summary.skipped[0].target_id, "needs-wrapper");
+        assert_eq!(summary.skipped[0].reason, "/nonexistent/wrapper.ts not found");
+        assert!(summary.is_success());
+    }
+
+    #[test]
+    fn test_overrides_replace_target_defaults() {
+        let overrides = TargetOverrides {
+            measured_iterations: Some(10),
+            max_duration: Some(Duration::from_secs(30)),
+            ..TargetOverrides::default()
+        };
+        let targets: Vec<Arc<dyn BenchTarget>> = vec![
+            Arc::new(Counted),
+            Arc::new(ExampleBenchmark::new("fixed".to_string())),
+        ];
+        let summary = run_targets(targets, true, 1, &overrides, 1, None, None);
+
+        let counted = &summary.results[0];
+        assert_eq!(counted.get_metric("operation_count"), Some(10.0));
+        let metadata = |key| counted.get_metadata(key).map(String::as_str);
+        assert_eq!(metadata("warmup_iterations"), Some("5"));
+        assert_eq!(metadata("measured_iterations"), Some("10"));
+        assert_eq!(metadata("max_duration_ms"), Some("30000"));
+        assert_eq!(summary.results[1].get_metadata("measured_iterations"), None);
+    }
+
+    #[test]
+    fn test_repeated_runs_report_statistics() {
+        let targets: Vec<Arc<dyn BenchTarget>> = vec![Arc::new(Drifts(AtomicUsize::new(0)))];
+        let summary = run_targets(targets, true, 1, &TargetOverrides::default(), 3, None, None);
+
+        let result = &summary.results[0];
+        assert_eq!(result.get_metric("latency_p50"), Some(20.0));
+        assert_eq!(result.samples["latency_p50"].values, vec![10.0, 20.0, 30.0]);
+        assert_eq!(result.stats["latency_p50"].stddev, 10.0);
+        assert!(result.stats["latency_p50"].is_unstable());
+        assert_eq!(result.get_metadata("runs").map(String::as_str), Some("3"));
+
+        let summary = run_targets(
+            suite(),
+            false,
+            1,
+            &TargetOverrides::default(),
+            2,
+            None,
+            None,
+        );
+        assert_eq!(summary.succeeded, vec!["first", "last"]);
+        assert_eq!(summary.failed[0].error, "adapter crashed");
+    }
+
+    #[test]
+    fn test_timed_out_target_is_cancelled() {
+        let hangs = Arc::new(Hangs(AtomicBool::new(false)));
+        let targets: Vec<Arc<dyn BenchTarget>> = vec![
+            hangs.clone(),
+            Arc::new(ExampleBenchmark::new("next".to_string())),
+        ];
+        let timeout = Some(Duration::from_millis(100));
+        let summary = run_targets(
+            targets,
+            false,
+            1,
+            &TargetOverrides::default(),
+            1,
+            timeout,
+            None,
+        );
+
+        assert!(hangs.0.load(Ordering::SeqCst));
+        assert_eq!(summary.succeeded, vec!["next"]);
+        assert_eq!(summary.failed[0].target_id, "hangs");
+        assert_eq!(summary.failed[0].error, "timed out after 100ms");
+        assert!(summary.failed[0].timed_out);
+    }
+
+    #[test]
+    fn test_parallel_run_keeps_registry_order() {
+        let targets: Vec<Arc<dyn BenchTarget>> = vec![
+            Arc::new(Slow("slow", 300)),
+            Arc::new(Panics),
+            Arc::new(Slow("medium", 200)),
+            Arc::new(Slow("fast", 0)),
+        ];
+        let started = std::time::Instant::now();
+        let summary = run_targets(
+            targets,
+            false,
+            4,
+            &TargetOverrides::default(),
+            1,
+            None,
+            None,
+        );
+
+        // Sequentially this would take at least 500ms
+        assert!(started.elapsed() < std::time::Duration::from_millis(450));
+        assert_eq!(summary.succeeded, vec!["slow", "medium", "fast"]);
+        let ids: Vec<&str> = summary.results.iter().map(|r| r.target_id.as_str()).collect();
+        assert_eq!(ids, vec!["slow", "medium", "fast"]);
+        assert_eq!(summary.results[0].get_metadata("parallel_jobs").map(String::as_str), Some("4"));
+        assert!(summary.results[0].get_metadata("git_commit").is_some());
+        assert_eq!(summary.failed[0].target_id, "panics");
+        assert_eq!(summary.failed[0].error, "panicked: index out of bounds");
+    }
+
+    #[tokio::test]
+    async fn test_async_targets_share_one_thread() {
+        let targets: Vec<Arc<dyn AsyncBenchTarget>> = vec![
+            Arc::new(Sleeps("slow", 300)),
+            Arc::new(Sleeps("panics", 0)),
+            Arc::new(Sleeps("medium", 200)),
+            Arc::new(Sleeps("unselected", 1)),
+        ];
+        let config = RunConfig {
+            targets: ["slow", "panics", "medium"].map(String::from).to_vec(),
+            jobs: 3,
+            ..RunConfig::default()
+        };
+        let started = std::time::Instant::now();
+        let summary = run_async_targets(targets.clone(), &config).await.unwrap();
+
+        // The test runtime has a single thread; sequentially this takes 500ms
+        assert!(started.elapsed() < Duration::from_millis(450));
+        assert_eq!(summary.succeeded, vec!["slow", "medium"]);
+        let jobs = summary.results[0].get_metadata("parallel_jobs");
+        assert_eq!(jobs.map(String::as_str), Some("3"));
+        assert_eq!(summary.failed[0].target_id, "panics");
+        assert_eq!(summary.failed[0].error, "panicked: no time budget");
+
+        let config = RunConfig {
+            targets: vec!["slow".to_string()],
+            timeout: Some(Duration::from_millis(50)),
+            ..RunConfig::default()
+        };
+        let summary = run_async_targets(targets.clone(), &config).await.unwrap();
+        assert!(summary.failed[0].timed_out);
+
+        let config = RunConfig {
+            targets: vec!["missing".to_string()],
+            ..RunConfig::default()
+        };
+        assert!(run_async_targets(targets, &config).await.is_err());
+    }
+}
@@ -0,0 +1,442 @@
+//! Soak mode: long-running leak detection
+//!
+//! A soak run executes the selected targets back to back for hours while a
+//! sampler thread records the process's resident memory, open file
+//! descriptors and TCP connections. After the run, a least-squares line is
+//! fitted to each resource over time and the run fails when a slope exceeds
+//! its threshold.
+//!
+//! Samples taken during the warmup window are kept in the report but left
+//! out of the fit, since connection pools and allocator arenas grow on
+//! their own at first. A slope only counts as a leak when the fit explains
+//! enough of the variance (`min_r_squared`); a noisy but flat series is not
+//! growth.
+//!
+//! Resource sampling reads `/proc/self` and is only available on Linux.
+
+use crate::adapters::BenchTarget;
+use crate::benchmarks::resources::rss_mb;
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Target id under which soak results are saved
+pub const SOAK_TARGET_ID: &str = "soak";
+
+/// Resources tracked during a soak run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    RssMb,
+    OpenFds,
+    TcpConnections,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 3] = [Resource::RssMb, Resource::OpenFds, Resource::TcpConnections];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Resource::RssMb => "rss_mb",
+            Resource::OpenFds => "open_fds",
+            Resource::TcpConnections => "tcp_connections",
+        }
+    }
+}
+
+/// Resource usage at one point in the run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceSample {
+    pub elapsed_secs: f64,
+    pub rss_mb: Option<f64>,
+    pub open_fds: Option<f64>,
+    pub tcp_connections: Option<f64>,
+}
+
+impl ResourceSample {
+    /// Sample the current process
+    pub fn capture(elapsed: Duration) -> Self {
+        let sockets = socket_inodes();
+        Self {
+            elapsed_secs: elapsed.as_secs_f64(),
+            rss_mb: rss_mb(),
+            open_fds: sockets.as_ref().map(|(fds, _)| *fds as f64),
+            tcp_connections: sockets.and_then(|(_, inodes)| tcp_connections(&inodes)),
+        }
+    }
+
+    pub fn get(&self, resource: Resource) -> Option<f64> {
+        match resource {
+            Resource::RssMb => self.rss_mb,
+            Resource::OpenFds => self.open_fds,
+            Resource::TcpConnections => self.tcp_connections,
+        }
+    }
+}
+
+/// Number of open file descriptors, and the inodes of those that are sockets
+fn socket_inodes() -> Option<(usize, HashSet<String>)> {
+    let mut fds = 0;
+    let mut inodes = HashSet::new();
+    for entry in fs::read_dir("/proc/self/fd").ok()?.flatten() {
+        fds += 1;
+        if let Ok(target) = fs::read_link(entry.path()) {
+            let target = target.to_string_lossy();
+            if let Some(inode) = target
+                .strip_prefix("socket:[")
+                .and_then(|s| s.strip_suffix(']'))
+            {
+                inodes.insert(inode.to_string());
+            }
+        }
+    }
+    Some((fds, inodes))
+}
+
+/// TCP sockets owned by this process, matched by inode against the
+/// namespace-wide `/proc/self/net/tcp{,6}` tables
+fn tcp_connections(inodes: &HashSet<String>) -> Option<f64> {
+    let mut count = 0;
+    let mut readable = false;
+    for table in ["/proc/self/net/tcp", "/proc/self/net/tcp6"] {
+        let Ok(contents) = fs::read_to_string(table) else {
+            continue;
+        };
+        readable = true;
+        count += contents
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().nth(9))
+            .filter(|inode| inodes.contains(*inode))
+            .count();
+    }
+    readable.then_some(count as f64)
+}
+
+/// Least-squares line through a resource series
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Trend {
+    /// Growth per hour, in the resource's unit
+    pub slope_per_hour: f64,
+    pub intercept: f64,
+    /// Share of the variance explained by the line (0.0 to 1.0)
+    pub r_squared: f64,
+    pub samples: usize,
+}
+
+/// Fit a line through `(hours, value)` points; needs at least three points
+/// spread over time
+pub fn fit_trend(points: &[(f64, f64)]) -> Option<Trend> {
+    if points.len() < 3 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in points {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    // A perfectly flat series has no variance to explain
+    let r_squared = if syy == 0.0 {
+        0.0
+    } else {
+        (sxy * sxy) / (sxx * syy)
+    };
+
+    Some(Trend {
+        slope_per_hour: slope,
+        intercept: mean_y - slope * mean_x,
+        r_squared,
+        samples: points.len(),
+    })
+}
+
+/// Maximum growth per hour before a soak run fails
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SoakThresholds {
+    pub rss_mb_per_hour: f64,
+    pub fds_per_hour: f64,
+    pub connections_per_hour: f64,
+    /// Fits explaining less of the variance than this are treated as noise
+    pub min_r_squared: f64,
+}
+
+impl Default for SoakThresholds {
+    fn default() -> Self {
+        Self {
+            rss_mb_per_hour: 20.0,
+            fds_per_hour: 10.0,
+            connections_per_hour: 10.0,
+            min_r_squared: 0.5,
+        }
+    }
+}
+
+impl SoakThresholds {
+    pub fn max_slope(&self, resource: Resource) -> f64 {
+        match resource {
+            Resource::RssMb => self.rss_mb_per_hour,
+            Resource::OpenFds => self.fds_per_hour,
+            Resource::TcpConnections => self.connections_per_hour,
+        }
+    }
+}
+
+/// Soak run settings
+#[derive(Debug, Clone)]
+pub struct SoakConfig {
+    pub duration: Duration,
+    pub sample_interval: Duration,
+    pub warmup: Duration,
+    pub thresholds: SoakThresholds,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(4 * 3600),
+            sample_interval: Duration::from_secs(30),
+            warmup: Duration::from_secs(5 * 60),
+            thresholds: SoakThresholds::default(),
+        }
+    }
+}
+
+/// Iterations and failures of one target during a soak run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetRuns {
+    pub iterations: u64,
+    pub errors: u64,
+}
+
+/// Outcome of a soak run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakReport {
+    pub duration_secs: f64,
+    pub warmup_secs: f64,
+    pub thresholds: SoakThresholds,
+    pub targets: BTreeMap<String, TargetRuns>,
+    pub samples: Vec<ResourceSample>,
+    /// Fitted trends, keyed by resource; missing when the resource couldn't
+    /// be sampled or too few samples followed the warmup
+    pub trends: BTreeMap<Resource, Trend>,
+    pub violations: Vec<String>,
+}
+
+impl SoakReport {
+    /// Fit trends over the post-warmup samples and check them against the
+    /// thresholds
+    pub fn evaluate(
+        samples: Vec<ResourceSample>,
+        targets: BTreeMap<String, TargetRuns>,
+        duration: Duration,
+        warmup: Duration,
+        thresholds: SoakThresholds,
+    ) -> Self {
+        let warmup_secs = warmup.as_secs_f64();
+        let mut trends = BTreeMap::new();
+        let mut violations = Vec::new();
+
+        for resource in Resource::ALL {
+            let points: Vec<(f64, f64)> = samples
+                .iter()
+                .filter(|sample| sample.elapsed_secs >= warmup_secs)
+                .filter_map(|sample| Some((sample.elapsed_secs / 3600.0, sample.get(resource)?)))
+                .collect();
+            let Some(trend) = fit_trend(&points) else {
+                continue;
+            };
+
+            let max_slope = thresholds.max_slope(resource);
+            if trend.slope_per_hour > max_slope && trend.r_squared >= thresholds.min_r_squared {
+                violations.push(format!(
+                    "{} grew {:.2}/hour (limit {:.2}/hour, r² {:.2})",
+                    resource.as_str(),
+                    trend.slope_per_hour,
+                    max_slope,
+                    trend.r_squared
+                ));
+            }
+            trends.insert(resource, trend);
+        }
+
+        Self {
+            duration_secs: duration.as_secs_f64(),
+            warmup_secs,
+            thresholds,
+            targets,
+            samples,
+            trends,
+            violations,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Summary as a regular result, so soak runs show up in reports and the
+    /// dashboard
+    pub fn to_benchmark_result(&self) -> BenchmarkResult {
+        let mut metrics = HashMap::new();
+        metrics.insert("duration_secs".to_string(), self.duration_secs);
+        metrics.insert("samples".to_string(), self.samples.len() as f64);
+        metrics.insert(
+            "iterations".to_string(),
+            self.targets
+                .values()
+                .map(|runs| runs.iterations as f64)
+                .sum(),
+        );
+        metrics.insert(
+            "errors".to_string(),
+            self.targets.values().map(|runs| runs.errors as f64).sum(),
+        );
+        metrics.insert("passed".to_string(), if self.passed() { 1.0 } else { 0.0 });
+
+        for resource in Resource::ALL {
+            let values: Vec<f64> = self
+                .samples
+                .iter()
+                .filter_map(|sample| sample.get(resource))
+                .collect();
+            if let (Some(first), Some(last)) = (values.first(), values.last()) {
+                metrics.insert(format!("{}_start", resource.as_str()), *first);
+                metrics.insert(format!("{}_end", resource.as_str()), *last);
+            }
+            if let Some(trend) = self.trends.get(&resource) {
+                metrics.insert(
+                    format!("{}_per_hour", resource.as_str()),
+                    trend.slope_per_hour,
+                );
+                metrics.insert(format!("{}_r_squared", resource.as_str()), trend.r_squared);
+            }
+        }
+
+        let targets: Vec<&str> = self.targets.keys().map(String::as_str).collect();
+        let mut result = BenchmarkResult::new(SOAK_TARGET_ID.to_string(), metrics);
+        result.add_metadata("soak_targets".to_string(), targets.join(","));
+        result.stamp_build_info();
+        if !self.passed() {
+            result.add_metadata("violations".to_string(), self.violations.join("; "));
+        }
+        result
+    }
+}
+
+/// Run `targets` round-robin until `config.duration` elapses, sampling
+/// resources on a separate thread so long benchmark runs don't stretch the
+/// sampling interval
+pub fn run_soak(targets: Vec<Box<dyn BenchTarget>>, config: &SoakConfig) -> Result<SoakReport> {
+    if targets.is_empty() {
+        bail!("No benchmark targets selected for the soak run");
+    }
+    if config.sample_interval.is_zero() {
+        bail!("Soak sample interval must be greater than zero");
+    }
+
+    let started = Instant::now();
+    let (stop, stopped) = mpsc::channel::<()>();
+    let interval = config.sample_interval;
+    let sampler = thread::spawn(move || {
+        let mut samples = vec![ResourceSample::capture(started.elapsed())];
+        while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            samples.push(ResourceSample::capture(started.elapsed()));
+        }
+        samples.push(ResourceSample::capture(started.elapsed()));
+        samples
+    });
+
+    let mut runs: BTreeMap<String, TargetRuns> = targets
+        .iter()
+        .map(|target| (target.id().to_string(), TargetRuns::default()))
+        .collect();
+
+    'soak: loop {
+        for target in &targets {
+            if started.elapsed() >= config.duration {
+                break 'soak;
+            }
+            let entry = runs.entry(target.id().to_string()).or_default();
+            entry.iterations += 1;
+            if let Err(e) = target.run() {
+                // Keep going: failures late in a soak are often the leak itself
+                entry.errors += 1;
+                log::warn!("Soak iteration of {} failed: {}", target.id(), e);
+            }
+        }
+        log::debug!("Soak at {:.0}s", started.elapsed().as_secs_f64());
+    }
+
+    drop(stop);
+    let samples = sampler
+        .join()
+        .map_err(|_| anyhow::anyhow!("Resource sampler thread panicked"))?;
+
+    Ok(SoakReport::evaluate(
+        samples,
+        runs,
+        started.elapsed(),
+        config.warmup,
+        config.thresholds,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_trend_flags_only_steady_growth() {
+        let trend = fit_trend(&[(0.0, 100.0), (1.0, 110.0), (2.0, 120.0), (3.0, 130.0)]).unwrap();
+        assert!((trend.slope_per_hour - 10.0).abs() < 1e-9);
+        assert!((trend.intercept - 100.0).abs() < 1e-9);
+        assert!((trend.r_squared - 1.0).abs() < 1e-9);
+        assert!(fit_trend(&[(0.0, 1.0), (1.0, 2.0)]).is_none());
+
+        // Two hours sampled every minute: RSS leaks 50 MB/hour, fds are flat,
+        // connections jump around without trending
+        let samples: Vec<ResourceSample> = (0..=120)
+            .map(|minute| ResourceSample {
+                elapsed_secs: minute as f64 * 60.0,
+                rss_mb: Some(200.0 + minute as f64 * 50.0 / 60.0),
+                open_fds: Some(42.0),
+                tcp_connections: Some(if minute % 2 == 0 { 4.0 } else { 40.0 }),
+            })
+            .collect();
+        let report = SoakReport::evaluate(
+            samples,
+            BTreeMap::new(),
+            Duration::from_secs(7200),
+            Duration::from_secs(600),
+            SoakThresholds::default(),
+        );
+
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.violations[0].starts_with("rss_mb"));
+        assert!((report.trends[&Resource::RssMb].slope_per_hour - 50.0).abs() < 1e-6);
+        assert_eq!(report.trends[&Resource::OpenFds].slope_per_hour, 0.0);
+        assert!(report.trends[&Resource::TcpConnections].r_squared < 0.1);
+
+        let result = report.to_benchmark_result();
+        assert_eq!(result.get_metric("passed"), Some(0.0));
+        assert_eq!(result.get_metric("rss_mb_start"), Some(200.0));
+        assert!(result.metadata.contains_key("violations"));
+    }
+}
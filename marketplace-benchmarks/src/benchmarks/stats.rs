@@ -0,0 +1,255 @@
+//! Sample statistics for repeated measurements
+//!
+//! A single run's number can't tell a real change from noise. Adapters can
+//! submit the raw samples behind a metric as a [`MetricSample`], and the
+//! runner does so for every metric when a target is run several times
+//! (`RunConfig::runs`). The result then carries the mean as the metric value
+//! plus [`MetricStats`]: standard deviation, range and a 95% confidence
+//! interval of the mean.
+//!
+//! A metric whose confidence interval is wider than ±10% of its mean is
+//! [unstable](MetricStats::is_unstable); reports flag it so a flaky number
+//! isn't mistaken for a regression or an improvement.
+
+use crate::benchmarks::histogram::Histogram;
+use crate::benchmarks::result::BenchmarkResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Metadata key recording how many runs a result aggregates
+pub const RUNS_METADATA_KEY: &str = "runs";
+
+/// Largest confidence interval half-width, relative to the mean, of a
+/// stable metric
+pub const UNSTABLE_RELATIVE_CI: f64 = 0.10;
+
+/// Two-sided 95% critical values of Student's t for 1 to 30 degrees of
+/// freedom
+const T_CRITICAL_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// Raw samples of one metric, e.g. one value per run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricSample {
+    pub values: Vec<f64>,
+}
+
+/// Summary of a [`MetricSample`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricStats {
+    pub count: usize,
+    pub mean: f64,
+    /// Sample standard deviation (n - 1 denominator); 0 for one sample
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+    /// 95% confidence interval of the mean, from two samples on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci95: Option<[f64; 2]>,
+}
+
+impl MetricSample {
+    /// Sample set of the finite `values`
+    pub fn new(values: impl IntoIterator<Item = f64>) -> Self {
+        Self {
+            values: values
+                .into_iter()
+                .filter(|value| value.is_finite())
+                .collect(),
+        }
+    }
+
+    /// Pool another sample set into this one
+    pub fn merge(&mut self, other: &MetricSample) {
+        self.values.extend_from_slice(&other.values);
+    }
+
+    pub fn stats(&self) -> Option<MetricStats> {
+        let count = self.values.len();
+        if count == 0 {
+            return None;
+        }
+
+        let mean = self.values.iter().sum::<f64>() / count as f64;
+        let stddev = if count > 1 {
+            let squares: f64 = self.values.iter().map(|value| (value - mean).powi(2)).sum();
+            (squares / (count - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let ci95 = (count > 1).then(|| {
+            let half_width = t_critical_95(count - 1) * stddev / (count as f64).sqrt();
+            [mean - half_width, mean + half_width]
+        });
+
+        Some(MetricStats {
+            count,
+            mean,
+            stddev,
+            min: self.values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: self
+                .values
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max),
+            ci95,
+        })
+    }
+}
+
+impl MetricStats {
+    /// Half-width of the 95% confidence interval
+    pub fn ci95_half_width(&self) -> Option<f64> {
+        self.ci95.map(|[low, high]| (high - low) / 2.0)
+    }
+
+    /// Whether the confidence interval is wider than
+    /// [`UNSTABLE_RELATIVE_CI`] of the mean. A zero mean is unstable as soon
+    /// as the samples differ.
+    pub fn is_unstable(&self) -> bool {
+        match self.ci95_half_width() {
+            Some(half_width) if self.mean != 0.0 => {
+                half_width / self.mean.abs() > UNSTABLE_RELATIVE_CI
+            }
+            Some(half_width) => half_width > 0.0,
+            None => false,
+        }
+    }
+}
+
+/// Student's t for a two-sided 95% interval, falling back to the normal
+/// distribution's 1.96 for large samples
+fn t_critical_95(degrees_of_freedom: usize) -> f64 {
+    match degrees_of_freedom {
+        0 => f64::NAN,
+        df if df <= T_CRITICAL_95.len() => T_CRITICAL_95[df - 1],
+        df if df <= 40 => 2.021,
+        df if df <= 60 => 2.000,
+        df if df <= 120 => 1.980,
+        _ => 1.960,
+    }
+}
+
+/// Combine repeated runs of one target into a single result
+///
+/// Every metric the first run reported becomes the mean over the runs that
+/// reported it, with the per-run values as its samples. Metrics with
+/// adapter-submitted samples in every run pool those samples instead.
+/// Histograms are pooled; metadata and timestamp are the first run's.
+pub fn aggregate_runs(runs: Vec<BenchmarkResult>) -> Option<BenchmarkResult> {
+    let mut runs = runs.into_iter();
+    let first = runs.next()?;
+    let rest: Vec<BenchmarkResult> = runs.collect();
+    if rest.is_empty() {
+        return Some(first);
+    }
+
+    let mut result = first.clone();
+    let all: Vec<&BenchmarkResult> = std::iter::once(&first).chain(&rest).collect();
+
+    for prefix in first.histograms.keys() {
+        if all.iter().all(|run| run.histograms.contains_key(prefix)) {
+            let mut pooled = Histogram::new();
+            for run in &all {
+                pooled.merge(&run.histograms[prefix]);
+            }
+            result.histograms.insert(prefix.clone(), pooled);
+        } else {
+            result.histograms.remove(prefix);
+        }
+    }
+
+    let mut samples: BTreeMap<String, MetricSample> = BTreeMap::new();
+    for name in first.metrics.keys() {
+        let sample = if all.iter().all(|run| run.samples.contains_key(name)) {
+            let mut pooled = MetricSample::default();
+            for run in &all {
+                pooled.merge(&run.samples[name]);
+            }
+            pooled
+        } else {
+            MetricSample::new(all.iter().filter_map(|run| run.get_metric(name)))
+        };
+        samples.insert(name.clone(), sample);
+    }
+    for (name, sample) in samples {
+        result.add_samples(name, sample);
+    }
+
+    result.add_metadata(RUNS_METADATA_KEY.to_string(), all.len().to_string());
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_stats_of_samples() {
+        let stats = MetricSample::new([10.0, 12.0, 11.0, 13.0, 9.0, f64::NAN])
+            .stats()
+            .unwrap();
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.mean, 11.0);
+        assert!((stats.stddev - 2.5f64.sqrt()).abs() < 1e-9);
+        assert_eq!((stats.min, stats.max), (9.0, 13.0));
+
+        // t(4) = 2.776, so the half-width is 2.776 * 1.581 / sqrt(5)
+        let half_width = stats.ci95_half_width().unwrap();
+        assert!((half_width - 1.963).abs() < 1e-3, "{}", half_width);
+        assert!(stats.is_unstable());
+
+        let steady = MetricSample::new([100.0, 101.0, 99.0, 100.0])
+            .stats()
+            .unwrap();
+        assert!(!steady.is_unstable());
+
+        let single = MetricSample::new([4.0]).stats().unwrap();
+        assert_eq!((single.stddev, single.ci95), (0.0, None));
+        assert!(!single.is_unstable());
+        assert!(MetricSample::default().stats().is_none());
+    }
+
+    #[test]
+    fn test_aggregate_runs() {
+        let run = |p50: f64, latencies: &[f64]| {
+            let mut metrics = HashMap::from([("latency_p50".to_string(), p50)]);
+            metrics.insert("error_rate".to_string(), 0.0);
+            let mut result = BenchmarkResult::new("search".to_string(), metrics);
+            result.add_histogram("latency".to_string(), Histogram::from_samples(latencies));
+            result.add_samples(
+                "batch_ms".to_string(),
+                MetricSample::new(latencies.to_vec()),
+            );
+            result
+        };
+
+        let result = aggregate_runs(vec![
+            run(10.0, &[1.0, 2.0]),
+            run(14.0, &[3.0]),
+            run(12.0, &[4.0, 5.0]),
+        ])
+        .unwrap();
+
+        assert_eq!(result.get_metric("latency_p50"), Some(12.0));
+        assert_eq!(result.stats["latency_p50"].count, 3);
+        assert_eq!(result.stats["latency_p50"].stddev, 2.0);
+        assert_eq!(result.samples["latency_p50"].values, vec![10.0, 14.0, 12.0]);
+        assert_eq!(result.get_metric("batch_ms"), Some(3.0));
+        assert_eq!(result.stats["batch_ms"].count, 5);
+        assert!(!result.stats["error_rate"].is_unstable());
+        assert_eq!(result.histograms["latency"].count, 5);
+        assert_eq!(
+            result.get_metadata(RUNS_METADATA_KEY).map(String::as_str),
+            Some("3")
+        );
+
+        let single = aggregate_runs(vec![run(10.0, &[1.0])]).unwrap();
+        assert!(single.get_metadata(RUNS_METADATA_KEY).is_none());
+        assert!(aggregate_runs(Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,722 @@
+//! `marketplace-benchmarks` CLI
+//!
+//! Runs selected benchmark targets, lists them and renders reports, for CI
+//! jobs that need a single target without writing a custom main.
+
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use marketplace_benchmarks::benchmarks::merge::WORKER_ID_METADATA_KEY;
+use marketplace_benchmarks::dashboard::RUN_ID_METADATA_KEY;
+use marketplace_benchmarks::{
+    apply_derived_metrics, compare_results, generate_csv, generate_run_junit_report,
+    generate_run_report, ingest_dir, load_benchmark_results, load_derived_metrics, load_precision,
+    load_thresholds, merge_results, run_all_benchmarks_with, save_all_results_with, save_manifest,
+    trend_baseline, BenchmarkResult, ComparisonReport, HistoryStore, JsonStyle, MemoryHistory,
+    Precision, RunConfig, SkippedTarget, TargetFailure, TargetOverrides, TargetRegistry,
+    TrendPoint, TrendQuery,
+};
+#[cfg(feature = "sqlite")]
+use marketplace_benchmarks::SqliteHistory;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "marketplace-benchmarks")]
+#[command(about = "Run and report LLM Marketplace benchmarks", long_about = None)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    /// Enable verbose logging
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Metric display precision config (defaults to benchmarks/precision.conf if present)
+    #[arg(long, global = true)]
+    precision: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Run benchmark targets and save their results
+    Run {
+        /// Target id to run; repeat or comma-separate for several (defaults to all)
+        #[arg(short, long = "target", value_delimiter = ',')]
+        targets: Vec<String>,
+
+        /// Only run targets with this tag, e.g. `infra`; repeat or
+        /// comma-separate for several
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Output directory for raw results
+        #[arg(short, long, default_value = "benchmarks/output/raw")]
+        output_dir: PathBuf,
+
+        /// Format of the summary printed after the run
+        #[arg(short, long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+
+        /// Derived metrics config (defaults to benchmarks/derived_metrics.conf if present)
+        #[arg(short, long)]
+        derived_metrics: Option<PathBuf>,
+
+        /// Keep running the remaining targets when one fails; the command
+        /// still exits non-zero after saving the successful results
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Run up to this many targets at once; results keep registry order.
+        /// Concurrent targets compete for CPU, so compare results only with
+        /// runs using the same value (recorded as `parallel_jobs`)
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
+
+        /// Tag results with this worker id, for merging with other workers'
+        /// results
+        #[arg(long)]
+        worker: Option<String>,
+
+        /// Only run shard I of N (1-based) of the selected targets, e.g. `2/3`
+        #[arg(long, value_parser = parse_shard)]
+        shard: Option<(usize, usize)>,
+
+        /// Warmup iterations for every target that supports iteration control
+        #[arg(long)]
+        warmup: Option<usize>,
+
+        /// Measured iterations for every target that supports iteration
+        /// control, e.g. a low value for a smoke pass
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        iterations: Option<u64>,
+
+        /// Stop each target's measured iterations after this many seconds
+        #[arg(long, value_parser = parse_duration_secs)]
+        max_duration_secs: Option<Duration>,
+
+        /// Cancel a target still running after this many seconds, all runs
+        /// included, and record it as timed out
+        #[arg(long, value_parser = parse_duration_secs)]
+        timeout_secs: Option<Duration>,
+
+        /// Run each target this many times and report every metric's mean,
+        /// standard deviation and 95% confidence interval across the runs
+        #[arg(long, default_value_t = 1)]
+        runs: usize,
+
+        /// Save results as canonical JSON (sorted keys, rounded floats) for
+        /// storing in git
+        #[arg(long)]
+        canonical: bool,
+
+        /// Write a flamegraph SVG of each target next to its result JSON
+        #[cfg(feature = "profiling")]
+        #[arg(long)]
+        profile: bool,
+
+        #[command(flatten)]
+        compare: CompareArgs,
+    },
+
+    /// Merge results saved by several workers into one run
+    Merge {
+        /// Directory of worker results; repeat for several
+        #[arg(short, long = "input-dir", required = true)]
+        input_dirs: Vec<PathBuf>,
+
+        /// Output directory for the merged results and run manifest
+        #[arg(short, long, default_value = "benchmarks/output/merged")]
+        output_dir: PathBuf,
+
+        /// Run id of the merged run (defaults to the current time)
+        #[arg(long)]
+        run_id: Option<String>,
+
+        #[arg(short, long, value_enum, default_value_t = Format::Markdown)]
+        format: Format,
+
+        /// Derived metrics config (defaults to benchmarks/derived_metrics.conf if present)
+        #[arg(short, long)]
+        derived_metrics: Option<PathBuf>,
+
+        /// Save merged results as canonical JSON (sorted keys, rounded
+        /// floats) for storing in git
+        #[arg(long)]
+        canonical: bool,
+
+        #[command(flatten)]
+        compare: CompareArgs,
+    },
+
+    /// List the registered benchmark targets and their tags
+    List {
+        /// Only list targets with this tag; repeat or comma-separate for
+        /// several
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
+
+        #[arg(short, long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+
+    /// Render stored results
+    Report {
+        /// Only include these target ids (defaults to all)
+        #[arg(short, long = "target", value_delimiter = ',')]
+        targets: Vec<String>,
+
+        /// Directory containing raw results
+        #[arg(short, long, default_value = "benchmarks/output/raw")]
+        input_dir: PathBuf,
+
+        #[arg(short, long, value_enum, default_value_t = Format::Markdown)]
+        format: Format,
+
+        /// Write the report to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Derived metrics config (defaults to benchmarks/derived_metrics.conf if present)
+        #[arg(short, long)]
+        derived_metrics: Option<PathBuf>,
+
+        #[command(flatten)]
+        compare: CompareArgs,
+    },
+
+    /// Show a target's metric across stored runs, oldest first
+    History {
+        /// Target id
+        #[arg(short, long)]
+        target: String,
+
+        /// Metric name, e.g. latency_p95
+        #[arg(short, long)]
+        metric: String,
+
+        /// Only the most recent runs
+        #[arg(long, default_value_t = 30)]
+        last: usize,
+
+        /// Directory of raw results to ingest into the history first
+        #[arg(short, long, default_value = "benchmarks/output/raw")]
+        input_dir: PathBuf,
+
+        /// SQLite database keeping the history across invocations; without
+        /// it the history is just the results in the input directory
+        #[cfg(feature = "sqlite")]
+        #[arg(long)]
+        history_db: Option<PathBuf>,
+
+        #[arg(short, long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+/// Comparison of the results with a stored baseline
+#[derive(Args)]
+struct CompareArgs {
+    /// Directory of baseline results to compare against
+    #[arg(long)]
+    baseline_dir: Option<PathBuf>,
+
+    /// Regression thresholds (defaults to benchmarks/regression_thresholds.conf if present)
+    #[arg(long)]
+    thresholds: Option<PathBuf>,
+
+    /// Exit non-zero when a metric regresses past its threshold
+    #[arg(long, requires = "baseline_dir")]
+    fail_on_regression: bool,
+}
+
+impl CompareArgs {
+    /// Compare with the baseline, restricted to `targets` when non-empty
+    fn compare(
+        &self,
+        results: &[BenchmarkResult],
+        targets: &[String],
+    ) -> Result<Option<ComparisonReport>> {
+        let Some(baseline_dir) = &self.baseline_dir else {
+            return Ok(None);
+        };
+
+        let baseline: Vec<BenchmarkResult> = load_benchmark_results(Some(baseline_dir))?
+            .into_iter()
+            .filter(|result| targets.is_empty() || targets.contains(&result.target_id))
+            .collect();
+        if baseline.is_empty() {
+            anyhow::bail!("No baseline results in {}", baseline_dir.display());
+        }
+
+        let thresholds = load_thresholds(self.thresholds.as_deref())?;
+        Ok(Some(compare_results(&baseline, results, &thresholds)))
+    }
+
+    /// Fail if requested and the comparison found regressions
+    fn check(&self, comparison: Option<&ComparisonReport>) -> Result<()> {
+        let regressions = comparison.map_or(0, |c| c.regressions().count());
+        if self.fail_on_regression && regressions > 0 {
+            anyhow::bail!("{} metrics regressed against the baseline", regressions);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// One line per target with its metrics
+    Text,
+    /// Results as a JSON array
+    Json,
+    /// The markdown summary report
+    Markdown,
+    /// JUnit XML with a test case per metric, failed when it regressed
+    Junit,
+    /// CSV with a row per target and metric, for spreadsheets
+    Csv,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let log_level = if cli.verbose { "debug" } else { "info" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level)).init();
+    let precision = load_precision(cli.precision.as_deref())?;
+
+    match cli.command {
+        Commands::Run {
+            targets,
+            tags,
+            output_dir,
+            format,
+            derived_metrics,
+            continue_on_error,
+            jobs,
+            worker,
+            shard,
+            warmup,
+            iterations,
+            max_duration_secs,
+            timeout_secs,
+            runs,
+            canonical,
+            #[cfg(feature = "profiling")]
+            profile,
+            compare,
+        } => {
+            let rules = load_derived_metrics(derived_metrics.as_deref())?;
+            let run_id = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+
+            let targets = match shard {
+                Some(shard) => shard_targets(targets, &tags, shard)?,
+                None => targets,
+            };
+            let summary = run_all_benchmarks_with(&RunConfig {
+                fail_fast: !continue_on_error,
+                targets: targets.clone(),
+                tags,
+                jobs,
+                overrides: TargetOverrides {
+                    warmup_iterations: warmup,
+                    measured_iterations: iterations.map(|n| n as usize),
+                    max_duration: max_duration_secs,
+                },
+                runs,
+                timeout: timeout_secs,
+                #[cfg(feature = "profiling")]
+                profile_dir: profile.then(|| output_dir.clone()),
+                #[cfg(not(feature = "profiling"))]
+                profile_dir: None,
+            })?;
+            if !continue_on_error {
+                if let Some(failure) = summary.failed.first() {
+                    anyhow::bail!("Benchmark {} failed: {}", failure.target_id, failure.error);
+                }
+            }
+
+            let mut results = summary.results;
+            for result in &mut results {
+                result.add_metadata(RUN_ID_METADATA_KEY.to_string(), run_id.clone());
+                if let Some(worker) = &worker {
+                    stamp_worker(result, worker);
+                }
+            }
+            apply_derived_metrics(&mut results, &rules);
+
+            let paths = save_all_results_with(&results, Some(&output_dir), json_style(canonical))?;
+            log::info!("Saved {} result files to {:?} (run {})", paths.len(), output_dir, run_id);
+
+            let comparison = compare.compare(&results, &targets)?;
+            print!(
+                "{}",
+                render(
+                    &results,
+                    &summary.failed,
+                    &summary.skipped,
+                    comparison.as_ref(),
+                    format,
+                    &precision,
+                )?
+            );
+            if matches!(format, Format::Json | Format::Junit | Format::Csv) {
+                // Keep stdout a plain results array, XML or CSV document
+                for target in &summary.skipped {
+                    eprintln!("{} skipped: {}", target.target_id, target.reason);
+                }
+                eprint!("{}", regression_lines(comparison.as_ref()));
+            }
+
+            if !summary.failed.is_empty() {
+                for failure in &summary.failed {
+                    eprintln!("{} failed: {}", failure.target_id, failure.error);
+                }
+                anyhow::bail!(
+                    "{} of {} benchmarks failed",
+                    summary.failed.len(),
+                    summary.failed.len() + summary.succeeded.len()
+                );
+            }
+            compare.check(comparison.as_ref())?;
+        }
+
+        Commands::List { tags, format } => {
+            let registry = TargetRegistry::builtin();
+            let targets = registry.select(&[], &tags)?;
+            match format {
+                Format::Json => {
+                    let ids: Vec<&str> = targets.iter().map(|target| target.id()).collect();
+                    println!("{}", serde_json::to_string_pretty(&json!(ids))?)
+                }
+                Format::Text | Format::Markdown | Format::Junit | Format::Csv => {
+                    for target in targets {
+                        println!("{} [{}]", target.id(), target.tags().join(", "));
+                    }
+                }
+            }
+        }
+
+        Commands::Report {
+            targets,
+            input_dir,
+            format,
+            output,
+            derived_metrics,
+            compare,
+        } => {
+            let mut results: Vec<BenchmarkResult> = load_benchmark_results(Some(&input_dir))?
+                .into_iter()
+                .filter(|result| targets.is_empty() || targets.contains(&result.target_id))
+                .collect();
+            if results.is_empty() {
+                anyhow::bail!("No matching benchmark results in {}", input_dir.display());
+            }
+
+            let rules = load_derived_metrics(derived_metrics.as_deref())?;
+            apply_derived_metrics(&mut results, &rules);
+
+            let comparison = compare.compare(&results, &targets)?;
+            let report = render(&results, &[], &[], comparison.as_ref(), format, &precision)?;
+            match output {
+                Some(path) => write_report(&path, &report)?,
+                None => print!("{}", report),
+            }
+            if matches!(format, Format::Json | Format::Junit | Format::Csv) {
+                eprint!("{}", regression_lines(comparison.as_ref()));
+            }
+            compare.check(comparison.as_ref())?;
+        }
+
+        Commands::Merge {
+            input_dirs,
+            output_dir,
+            run_id,
+            format,
+            derived_metrics,
+            canonical,
+            compare,
+        } => {
+            let mut results = Vec::new();
+            for dir in &input_dirs {
+                let loaded = load_benchmark_results(Some(dir))?;
+                if loaded.is_empty() {
+                    anyhow::bail!("No benchmark results in {}", dir.display());
+                }
+                results.extend(loaded);
+            }
+
+            let run_id =
+                run_id.unwrap_or_else(|| chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string());
+            let mut merged = merge_results(&results, &run_id);
+            let rules = load_derived_metrics(derived_metrics.as_deref())?;
+            apply_derived_metrics(&mut merged.results, &rules);
+            for metric in &merged.manifest.approximate_metrics {
+                log::warn!(
+                    "{} has no histogram on every worker; averaged instead",
+                    metric
+                );
+            }
+
+            save_all_results_with(&merged.results, Some(&output_dir), json_style(canonical))?;
+            save_manifest(&merged.manifest, &output_dir)?;
+            log::info!(
+                "Merged {} results from {} workers into {:?} (run {})",
+                results.len(),
+                merged.manifest.workers.len(),
+                output_dir,
+                run_id
+            );
+
+            let comparison = compare.compare(&merged.results, &[])?;
+            print!(
+                "{}",
+                render(
+                    &merged.results,
+                    &[],
+                    &[],
+                    comparison.as_ref(),
+                    format,
+                    &precision,
+                )?
+            );
+            if matches!(format, Format::Json | Format::Junit | Format::Csv) {
+                eprint!("{}", regression_lines(comparison.as_ref()));
+            }
+            compare.check(comparison.as_ref())?;
+        }
+
+        Commands::History {
+            target,
+            metric,
+            last,
+            input_dir,
+            #[cfg(feature = "sqlite")]
+            history_db,
+            format,
+        } => {
+            let query = TrendQuery::new(target, metric).last_runs(last);
+            let trend = tokio::runtime::Runtime::new()?.block_on(async {
+                #[cfg(feature = "sqlite")]
+                let store: Box<dyn HistoryStore> = match &history_db {
+                    Some(path) => Box::new(SqliteHistory::open(path).await?),
+                    None => Box::new(MemoryHistory::new()),
+                };
+                #[cfg(not(feature = "sqlite"))]
+                let store: Box<dyn HistoryStore> = Box::new(MemoryHistory::new());
+                if input_dir.exists() {
+                    let added = ingest_dir(store.as_ref(), &input_dir).await?;
+                    log::info!("Ingested {} new points from {:?}", added, input_dir);
+                }
+                store.trend(&query).await
+            })?;
+            if trend.is_empty() {
+                anyhow::bail!("No history for {} {}", query.target_id, query.metric);
+            }
+
+            print!("{}", render_trend(&query, &trend, format)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse `I/N` with 1 <= I <= N
+fn parse_shard(value: &str) -> std::result::Result<(usize, usize), String> {
+    let parsed = value
+        .split_once('/')
+        .and_then(|(index, count)| Some((index.parse().ok()?, count.parse().ok()?)));
+    match parsed {
+        Some((index, count)) if index >= 1 && index <= count => Ok((index, count)),
+        _ => Err(format!("expected I/N with 1 <= I <= N, got {:?}", value)),
+    }
+}
+
+/// Parse a positive number of seconds, fractions allowed
+fn parse_duration_secs(value: &str) -> std::result::Result<Duration, String> {
+    match value.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(format!(
+            "expected a positive number of seconds, got {:?}",
+            value
+        )),
+    }
+}
+
+/// Every Nth of the targets selected by id and tag (all when none are),
+/// starting at the Ith, in registry order
+fn shard_targets(
+    targets: Vec<String>,
+    tags: &[String],
+    (index, count): (usize, usize),
+) -> Result<Vec<String>> {
+    let ids: Vec<String> = TargetRegistry::builtin()
+        .select(&targets, tags)?
+        .iter()
+        .map(|target| target.id().to_string())
+        .collect();
+
+    let shard: Vec<String> = ids.into_iter().skip(index - 1).step_by(count).collect();
+    if shard.is_empty() {
+        anyhow::bail!("Shard {}/{} has no targets", index, count);
+    }
+    log::info!("Shard {}/{} runs: {}", index, count, shard.join(", "));
+    Ok(shard)
+}
+
+/// Record which machine produced a result
+fn stamp_worker(result: &mut BenchmarkResult, worker: &str) {
+    result.add_metadata(WORKER_ID_METADATA_KEY.to_string(), worker.to_string());
+    if result.get_metadata("hostname").is_none() {
+        if let Some(hostname) = hostname::get().ok().and_then(|h| h.into_string().ok()) {
+            result.add_metadata("hostname".to_string(), hostname);
+        }
+    }
+    if let Ok(cpus) = std::thread::available_parallelism() {
+        result.add_metadata("cpu_count".to_string(), cpus.to_string());
+    }
+}
+
+fn json_style(canonical: bool) -> JsonStyle {
+    if canonical {
+        JsonStyle::Canonical
+    } else {
+        JsonStyle::Pretty
+    }
+}
+
+/// Render results in the requested format; text and markdown also list the
+/// targets a run skipped and the baseline regressions, JUnit the failed and
+/// skipped targets as test cases
+fn render(
+    results: &[BenchmarkResult],
+    failed: &[TargetFailure],
+    skipped: &[SkippedTarget],
+    comparison: Option<&ComparisonReport>,
+    format: Format,
+    precision: &Precision,
+) -> Result<String> {
+    match format {
+        Format::Markdown => generate_run_report(results, skipped, comparison, precision),
+        Format::Junit => Ok(generate_run_junit_report(
+            results, failed, skipped, comparison, precision,
+        )),
+        Format::Json => Ok(serde_json::to_string_pretty(results)? + "\n"),
+        Format::Csv => Ok(generate_csv(results)),
+        Format::Text => {
+            let mut out = String::new();
+            for result in results {
+                let mut metrics: Vec<_> = result.metrics.iter().collect();
+                metrics.sort_by(|a, b| a.0.cmp(b.0));
+                let metrics: Vec<String> = metrics
+                    .iter()
+                    .map(|(key, value)| {
+                        let half_width = result
+                            .stats
+                            .get(key.as_str())
+                            .and_then(|stats| stats.ci95_half_width());
+                        match half_width {
+                            Some(half_width) => format!(
+                                "{}={}±{}",
+                                key,
+                                precision.format(key, **value),
+                                precision.format(key, half_width)
+                            ),
+                            None => format!("{}={}", key, precision.format(key, **value)),
+                        }
+                    })
+                    .collect();
+                out.push_str(&format!("{}  {}\n", result.target_id, metrics.join(" ")));
+            }
+            for target in skipped {
+                out.push_str(&format!("{}  skipped: {}\n", target.target_id, target.reason));
+            }
+            out.push_str(&regression_lines(comparison));
+            Ok(out)
+        }
+    }
+}
+
+/// A metric's trend with its median baseline
+fn render_trend(query: &TrendQuery, trend: &[TrendPoint], format: Format) -> Result<String> {
+    let baseline = trend_baseline(trend);
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(&json!({
+            "target_id": query.target_id,
+            "metric": query.metric,
+            "baseline": baseline,
+            "points": trend,
+        }))? + "\n"),
+        Format::Markdown => {
+            let mut out = format!("## {} {}\n\n", query.target_id, query.metric);
+            out.push_str("| Run | Timestamp | Value | Commit |\n");
+            out.push_str("|-----|-----------|-------|--------|\n");
+            for point in trend {
+                out.push_str(&format!(
+                    "| {} | {} | {:.3} | {} |\n",
+                    point.run_id,
+                    point.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+                    point.value,
+                    point.git_commit.as_deref().unwrap_or("-")
+                ));
+            }
+            if let Some(baseline) = baseline {
+                out.push_str(&format!("\n**Baseline (median):** {:.3}\n", baseline));
+            }
+            Ok(out)
+        }
+        Format::Csv => {
+            let mut out = "run_id,timestamp,value,git_commit\n".to_string();
+            for point in trend {
+                out.push_str(&format!(
+                    "{},{},{},{}\n",
+                    point.run_id,
+                    point.timestamp.to_rfc3339(),
+                    point.value,
+                    point.git_commit.as_deref().unwrap_or("")
+                ));
+            }
+            Ok(out)
+        }
+        Format::Text | Format::Junit => {
+            let mut out = String::new();
+            for point in trend {
+                out.push_str(&format!(
+                    "{}  {}  {:.3}\n",
+                    point.timestamp.to_rfc3339(),
+                    point.run_id,
+                    point.value
+                ));
+            }
+            if let Some(baseline) = baseline {
+                out.push_str(&format!("baseline (median)  {:.3}\n", baseline));
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// One line per regressed metric
+fn regression_lines(comparison: Option<&ComparisonReport>) -> String {
+    let mut out = String::new();
+    for delta in comparison.into_iter().flat_map(|c| c.regressions()) {
+        out.push_str(&format!(
+            "{}  regressed: {} {:.3} -> {:.3} ({})\n",
+            delta.target_id,
+            delta.metric,
+            delta.baseline,
+            delta.candidate,
+            delta.threshold.as_deref().unwrap_or("")
+        ));
+    }
+    out
+}
+
+fn write_report(path: &Path, report: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, report).with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Report saved to: {}", path.display());
+    Ok(())
+}
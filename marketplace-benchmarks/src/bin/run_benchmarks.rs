@@ -3,12 +3,18 @@
 //! This binary provides a command-line interface for running benchmarks,
 //! generating reports, and managing benchmark results.
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::{Parser, Subcommand};
+use marketplace_benchmarks::dashboard::{self, ResultStore, RUN_ID_METADATA_KEY};
 use marketplace_benchmarks::{
-    run_all_benchmarks, generate_markdown_report, save_all_results, load_benchmark_results,
+    generate_run_report, load_manifest, load_precision, save_all_results,
+    load_benchmark_results,
+    apply_derived_metrics, load_derived_metrics, run_soak, select_targets, SoakConfig,
+    SoakThresholds,
 };
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "run_benchmarks")]
@@ -21,23 +27,63 @@ struct Cli {
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Metric display precision config (defaults to benchmarks/precision.conf if present)
+    #[arg(long, global = true)]
+    precision: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Run all registered benchmarks
+    /// Run the benchmark suite: the manifest's targets, or all registered ones
     Run {
-        /// Output directory for raw results
-        #[arg(short, long, default_value = "benchmarks/output/raw")]
-        output_dir: PathBuf,
+        /// Suite manifest (defaults to benchmarks.toml if present); the
+        /// options below override its settings
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+
+        /// Output directory for raw results [default: benchmarks/output/raw]
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
 
-        /// Generate markdown report after running
+        /// Generate markdown report after running; implied by a manifest
+        /// report path
         #[arg(short, long)]
         report: bool,
 
-        /// Path for the markdown report
-        #[arg(short = 'm', long, default_value = "benchmarks/output/summary.md")]
-        markdown_path: PathBuf,
+        /// Path for the markdown report [default: benchmarks/output/summary.md]
+        #[arg(short = 'm', long)]
+        markdown_path: Option<PathBuf>,
+
+        /// Derived metrics config (defaults to benchmarks/derived_metrics.conf if present)
+        #[arg(short, long)]
+        derived_metrics: Option<PathBuf>,
+
+        /// Keep running the remaining targets when one fails; the command
+        /// still exits non-zero after saving the successful results
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Run up to this many targets at once; results keep suite order.
+        /// Concurrent targets compete for CPU, so compare results only with
+        /// runs using the same value (recorded as `parallel_jobs`)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Run each target this many times and report every metric's mean,
+        /// standard deviation and 95% confidence interval across the runs
+        #[arg(long)]
+        runs: Option<usize>,
+
+        /// Cancel a target still running after this many seconds and record
+        /// it as timed out
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        timeout_secs: Option<u64>,
+
+        /// Also store results in Postgres
+        #[cfg(feature = "postgres")]
+        #[arg(long)]
+        database_url: Option<String>,
     },
 
     /// Generate a markdown report from existing results
@@ -49,10 +95,69 @@ enum Commands {
         /// Output path for the markdown report
         #[arg(short, long, default_value = "benchmarks/output/summary.md")]
         output_path: PathBuf,
+
+        /// Derived metrics config (defaults to benchmarks/derived_metrics.conf if present)
+        #[arg(short, long)]
+        derived_metrics: Option<PathBuf>,
+    },
+
+    /// Run targets continuously and fail on steady resource growth
+    Soak {
+        /// Comma-separated target ids (defaults to all targets)
+        #[arg(short, long, value_delimiter = ',')]
+        targets: Vec<String>,
+
+        /// How long to keep the targets running
+        #[arg(long, default_value_t = 240)]
+        duration_mins: u64,
+
+        /// Seconds between resource samples
+        #[arg(long, default_value_t = 30)]
+        interval_secs: u64,
+
+        /// Minutes excluded from the trend fit while pools and caches fill
+        #[arg(long, default_value_t = 5)]
+        warmup_mins: u64,
+
+        /// Maximum resident memory growth, in MB per hour
+        #[arg(long, default_value_t = 20.0)]
+        max_rss_mb_per_hour: f64,
+
+        /// Maximum open file descriptor growth per hour
+        #[arg(long, default_value_t = 10.0)]
+        max_fds_per_hour: f64,
+
+        /// Maximum TCP connection growth per hour
+        #[arg(long, default_value_t = 10.0)]
+        max_connections_per_hour: f64,
+
+        /// Output directory for the soak summary result
+        #[arg(short, long, default_value = "benchmarks/output/raw")]
+        output_dir: PathBuf,
+
+        /// Path for the full soak report with every sample
+        #[arg(long, default_value = "benchmarks/output/soak.json")]
+        report_path: PathBuf,
     },
 
     /// List all available benchmark targets
     List,
+
+    /// Serve the comparison dashboard over stored results
+    Serve {
+        /// Input directory containing benchmark results
+        #[arg(short, long, default_value = "benchmarks/output/raw")]
+        input_dir: PathBuf,
+
+        /// Read results from Postgres instead of the input directory
+        #[cfg(feature = "postgres")]
+        #[arg(long)]
+        database_url: Option<String>,
+
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:8090")]
+        addr: SocketAddr,
+    },
 }
 
 fn main() -> Result<()> {
@@ -64,29 +169,91 @@ fn main() -> Result<()> {
 
     match cli.command {
         Commands::Run {
+            manifest,
             output_dir,
             report,
             markdown_path,
+            derived_metrics,
+            continue_on_error,
+            jobs,
+            runs,
+            timeout_secs,
+            #[cfg(feature = "postgres")]
+            database_url,
         } => {
             log::info!("Starting benchmark run");
+            let rules = load_derived_metrics(derived_metrics.as_deref())?;
+            let run_id = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+
+            // Command-line options take precedence over the manifest
+            let mut manifest = load_manifest(manifest.as_deref())?;
+            manifest.suite.continue_on_error |= continue_on_error;
+            manifest.suite.jobs = jobs.or(manifest.suite.jobs);
+            manifest.suite.runs = runs.or(manifest.suite.runs);
+            manifest.suite.timeout_secs = timeout_secs.or(manifest.suite.timeout_secs);
+            let continue_on_error = manifest.suite.continue_on_error;
+            let output_dir = output_dir
+                .or(manifest.output.dir.clone())
+                .unwrap_or_else(|| PathBuf::from("benchmarks/output/raw"));
+            let report = report || manifest.output.report.is_some();
+            let markdown_path = markdown_path
+                .or(manifest.output.report.clone())
+                .unwrap_or_else(|| PathBuf::from("benchmarks/output/summary.md"));
 
-            // Run all benchmarks
-            let results = run_all_benchmarks()?;
-            log::info!("Completed {} benchmarks", results.len());
+            // Run the suite, tagging results so the dashboard can group them
+            let summary = manifest.run()?;
+            if !continue_on_error {
+                if let Some(failure) = summary.failed.first() {
+                    bail!("Benchmark {} failed: {}", failure.target_id, failure.error);
+                }
+            }
+            let mut results = summary.results;
+            for result in &mut results {
+                result.add_metadata(RUN_ID_METADATA_KEY.to_string(), run_id.clone());
+            }
+            log::info!("Completed {} benchmarks (run {})", results.len(), run_id);
+
+            // Add derived metrics before saving so they are stored with the run
+            apply_derived_metrics(&mut results, &rules);
 
             // Save results to disk
             let paths = save_all_results(&results, Some(&output_dir))?;
             log::info!("Saved {} result files to {:?}", paths.len(), output_dir);
 
+            #[cfg(feature = "postgres")]
+            if let Some(database_url) = database_url {
+                tokio::runtime::Runtime::new()?.block_on(async {
+                    ResultStore::connect(&database_url).await?.save(&results).await
+                })?;
+                log::info!("Stored {} results in Postgres", results.len());
+            }
+
             // Generate markdown report if requested
             if report {
-                let markdown = generate_markdown_report(&results)?;
+                let precision = load_precision(cli.precision.as_deref())?;
+                let markdown = generate_run_report(&results, &summary.skipped, None, &precision)?;
                 std::fs::create_dir_all(markdown_path.parent().unwrap())?;
                 std::fs::write(&markdown_path, markdown)?;
                 log::info!("Generated markdown report at {:?}", markdown_path);
                 println!("\nReport saved to: {}", markdown_path.display());
             }
 
+            for skipped in &summary.skipped {
+                println!("  {} skipped: {}", skipped.target_id, skipped.reason);
+            }
+
+            if !summary.failed.is_empty() {
+                for failure in &summary.failed {
+                    eprintln!("  {} failed: {}", failure.target_id, failure.error);
+                }
+                bail!(
+                    "{} of {} benchmarks failed; results of the others were saved to {}",
+                    summary.failed.len(),
+                    summary.failed.len() + summary.succeeded.len(),
+                    output_dir.display()
+                );
+            }
+
             println!("\nBenchmark run completed successfully!");
             println!("Results saved to: {}", output_dir.display());
         }
@@ -94,11 +261,12 @@ fn main() -> Result<()> {
         Commands::Report {
             input_dir,
             output_path,
+            derived_metrics,
         } => {
             log::info!("Generating report from existing results");
 
             // Load existing results
-            let results = load_benchmark_results(Some(&input_dir))?;
+            let mut results = load_benchmark_results(Some(&input_dir))?;
             if results.is_empty() {
                 log::warn!("No benchmark results found in {:?}", input_dir);
                 println!("No benchmark results found. Run benchmarks first with 'run' command.");
@@ -107,8 +275,12 @@ fn main() -> Result<()> {
 
             log::info!("Loaded {} benchmark results", results.len());
 
+            let rules = load_derived_metrics(derived_metrics.as_deref())?;
+            apply_derived_metrics(&mut results, &rules);
+
             // Generate and save markdown report
-            let markdown = generate_markdown_report(&results)?;
+            let precision = load_precision(cli.precision.as_deref())?;
+            let markdown = generate_run_report(&results, &[], None, &precision)?;
             std::fs::create_dir_all(output_path.parent().unwrap())?;
             std::fs::write(&output_path, markdown)?;
 
@@ -116,15 +288,95 @@ fn main() -> Result<()> {
             println!("Report saved to: {}", output_path.display());
         }
 
+        Commands::Soak {
+            targets,
+            duration_mins,
+            interval_secs,
+            warmup_mins,
+            max_rss_mb_per_hour,
+            max_fds_per_hour,
+            max_connections_per_hour,
+            output_dir,
+            report_path,
+        } => {
+            let selected = select_targets(&targets)?;
+
+            let config = SoakConfig {
+                duration: Duration::from_secs(duration_mins * 60),
+                sample_interval: Duration::from_secs(interval_secs),
+                warmup: Duration::from_secs(warmup_mins * 60),
+                thresholds: SoakThresholds {
+                    rss_mb_per_hour: max_rss_mb_per_hour,
+                    fds_per_hour: max_fds_per_hour,
+                    connections_per_hour: max_connections_per_hour,
+                    ..SoakThresholds::default()
+                },
+            };
+            log::info!("Soaking {} targets for {} minutes", selected.len(), duration_mins);
+            let report = run_soak(selected, &config)?;
+
+            let run_id = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+            let mut result = report.to_benchmark_result();
+            result.add_metadata(RUN_ID_METADATA_KEY.to_string(), run_id);
+            save_all_results(&[result], Some(&output_dir))?;
+
+            if let Some(parent) = report_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&report_path, serde_json::to_string_pretty(&report)?)
+                .with_context(|| format!("Failed to write {}", report_path.display()))?;
+
+            println!("\nSoak report saved to: {}", report_path.display());
+            for (resource, trend) in &report.trends {
+                println!(
+                    "  {:<16} {:>10.2}/hour  (r² {:.2}, {} samples)",
+                    resource.as_str(),
+                    trend.slope_per_hour,
+                    trend.r_squared,
+                    trend.samples
+                );
+            }
+            if !report.passed() {
+                bail!("Soak run failed: {}", report.violations.join("; "));
+            }
+            println!("\nSoak run passed: no resource growth above thresholds");
+        }
+
         Commands::List => {
             println!("Available benchmark targets:\n");
 
-            let targets = marketplace_benchmarks::all_targets();
-            for (i, target) in targets.iter().enumerate() {
-                println!("  {}. {}", i + 1, target.id());
+            let registry = marketplace_benchmarks::TargetRegistry::builtin();
+            for (i, target) in registry.iter().enumerate() {
+                println!(
+                    "  {}. {} [{}]",
+                    i + 1,
+                    target.id(),
+                    target.tags().join(", ")
+                );
             }
 
-            println!("\nTotal: {} benchmarks", targets.len());
+            println!("\nTotal: {} benchmarks", registry.len());
+        }
+
+        Commands::Serve {
+            input_dir,
+            #[cfg(feature = "postgres")]
+            database_url,
+            addr,
+        } => {
+            let precision = load_precision(cli.precision.as_deref())?;
+            tokio::runtime::Runtime::new()?.block_on(async {
+                #[cfg(feature = "postgres")]
+                let store = match database_url {
+                    Some(database_url) => ResultStore::connect(&database_url).await?,
+                    None => ResultStore::Files(input_dir),
+                };
+                #[cfg(not(feature = "postgres"))]
+                let store = ResultStore::Files(input_dir);
+
+                println!("Dashboard available at http://{}", addr);
+                dashboard::serve(store, precision, addr).await
+            })?;
         }
     }
 
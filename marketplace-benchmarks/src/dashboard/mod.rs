@@ -0,0 +1,163 @@
+//! Benchmark comparison dashboard
+//!
+//! A small HTTP server over stored benchmark results. It serves JSON APIs
+//! for browsing runs, comparing two runs, and reading metric time series,
+//! plus a single-page HTML UI built on those APIs.
+//!
+//! Endpoints:
+//! - `GET /` - HTML dashboard
+//! - `GET /api/runs` - run summaries, newest first
+//! - `GET /api/runs/:run_id` - results of one run
+//! - `GET /api/compare?base=&head=` - per-metric deltas between two runs
+//! - `GET /api/series?target=&metric=` - a target's metric across runs
+//! - `GET /api/targets` - targets, the metrics they report and the decimals
+//!   each metric is displayed with
+
+pub mod runs;
+pub mod store;
+
+use crate::benchmarks::precision::Precision;
+use crate::benchmarks::result::BenchmarkResult;
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+pub use runs::{MetricComparison, RunSummary, SeriesPoint, RUN_ID_METADATA_KEY};
+pub use store::ResultStore;
+
+/// Embedded dashboard page
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Error returned by the dashboard APIs as `{"error": "..."}`
+struct ApiError(StatusCode, String);
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        log::error!("Dashboard request failed: {:#}", e);
+        Self(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+#[derive(Deserialize)]
+struct CompareQuery {
+    base: String,
+    head: String,
+}
+
+#[derive(Deserialize)]
+struct SeriesQuery {
+    target: String,
+    metric: String,
+}
+
+#[derive(Serialize)]
+struct TargetMetrics {
+    target_id: String,
+    metrics: Vec<String>,
+    /// Decimals per metric, so the UI rounds like the reports do
+    decimals: BTreeMap<String, usize>,
+}
+
+/// Build the dashboard router over a result store
+pub fn router(store: ResultStore, precision: Precision) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/api/runs", get(list_runs))
+        .route("/api/runs/:run_id", get(get_run))
+        .route("/api/compare", get(compare))
+        .route("/api/series", get(series))
+        .route("/api/targets", get(targets))
+        .layer(Extension(Arc::new(precision)))
+        .with_state(store)
+}
+
+/// Serve the dashboard until the process is stopped
+pub async fn serve(
+    store: ResultStore,
+    precision: Precision,
+    addr: SocketAddr,
+) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    log::info!("Benchmark dashboard listening on http://{}", addr);
+    axum::serve(listener, router(store, precision)).await?;
+    Ok(())
+}
+
+async fn index() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+async fn list_runs(State(store): State<ResultStore>) -> ApiResult<Vec<RunSummary>> {
+    let results = store.load().await?;
+    Ok(Json(runs::summarize_runs(&results)))
+}
+
+async fn get_run(
+    State(store): State<ResultStore>,
+    Path(run_id): Path<String>,
+) -> ApiResult<Vec<BenchmarkResult>> {
+    let results = runs::run_results(&store.load().await?, &run_id);
+    if results.is_empty() {
+        return Err(ApiError(StatusCode::NOT_FOUND, format!("Run {} not found", run_id)));
+    }
+    Ok(Json(results))
+}
+
+async fn compare(
+    State(store): State<ResultStore>,
+    Query(query): Query<CompareQuery>,
+) -> ApiResult<Vec<MetricComparison>> {
+    let results = store.load().await?;
+    Ok(Json(runs::compare_runs(&results, &query.base, &query.head)))
+}
+
+async fn series(
+    State(store): State<ResultStore>,
+    Query(query): Query<SeriesQuery>,
+) -> ApiResult<Vec<SeriesPoint>> {
+    let results = store.load().await?;
+    Ok(Json(runs::metric_series(&results, &query.target, &query.metric)))
+}
+
+async fn targets(
+    State(store): State<ResultStore>,
+    Extension(precision): Extension<Arc<Precision>>,
+) -> ApiResult<Vec<TargetMetrics>> {
+    let mut targets: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for result in store.load().await? {
+        targets
+            .entry(result.target_id)
+            .or_default()
+            .extend(result.metrics.into_keys());
+    }
+
+    Ok(Json(
+        targets
+            .into_iter()
+            .map(|(target_id, metrics)| TargetMetrics {
+                target_id,
+                decimals: metrics
+                    .iter()
+                    .map(|metric| (metric.clone(), precision.decimals(metric)))
+                    .collect(),
+                metrics: metrics.into_iter().collect(),
+            })
+            .collect(),
+    ))
+}
@@ -0,0 +1,209 @@
+//! Run grouping, comparison, and time series over stored results
+//!
+//! Results are stored one file (or row) per target. A run is the set of
+//! results sharing the `run_id` metadata written by the `run` command;
+//! older results without it are grouped by the minute they were recorded.
+
+use crate::benchmarks::result::BenchmarkResult;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Metadata key identifying the run a result belongs to
+pub const RUN_ID_METADATA_KEY: &str = "run_id";
+
+/// Summary of a single benchmark run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    pub targets: Vec<String>,
+    pub result_count: usize,
+}
+
+/// A metric's values in two runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricComparison {
+    pub target_id: String,
+    pub metric: String,
+    pub base: Option<f64>,
+    pub head: Option<f64>,
+    pub delta: Option<f64>,
+    /// Relative change in percent, `None` when the base value is missing or zero
+    pub delta_percent: Option<f64>,
+}
+
+/// One point of a metric time series
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesPoint {
+    pub run_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Run identifier of a result
+pub fn run_id(result: &BenchmarkResult) -> String {
+    result
+        .get_metadata(RUN_ID_METADATA_KEY)
+        .cloned()
+        .unwrap_or_else(|| result.timestamp.format("%Y%m%d_%H%M").to_string())
+}
+
+/// Summaries of all runs, newest first
+pub fn summarize_runs(results: &[BenchmarkResult]) -> Vec<RunSummary> {
+    let mut runs: BTreeMap<String, RunSummary> = BTreeMap::new();
+
+    for result in results {
+        let id = run_id(result);
+        let run = runs.entry(id.clone()).or_insert_with(|| RunSummary {
+            run_id: id,
+            started_at: result.timestamp,
+            targets: Vec::new(),
+            result_count: 0,
+        });
+
+        run.started_at = run.started_at.min(result.timestamp);
+        run.result_count += 1;
+        if !run.targets.contains(&result.target_id) {
+            run.targets.push(result.target_id.clone());
+        }
+    }
+
+    let mut runs: Vec<RunSummary> = runs.into_values().collect();
+    for run in &mut runs {
+        run.targets.sort();
+    }
+    runs.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+    runs
+}
+
+/// Results of a run, one per target (the latest if a target ran twice)
+pub fn run_results(results: &[BenchmarkResult], run: &str) -> Vec<BenchmarkResult> {
+    let mut by_target: BTreeMap<&str, &BenchmarkResult> = BTreeMap::new();
+
+    for result in results.iter().filter(|result| run_id(result) == run) {
+        let entry = by_target.entry(&result.target_id).or_insert(result);
+        if result.timestamp > entry.timestamp {
+            *entry = result;
+        }
+    }
+
+    by_target.into_values().cloned().collect()
+}
+
+/// Compare every metric of two runs, sorted by target and metric
+pub fn compare_runs(results: &[BenchmarkResult], base: &str, head: &str) -> Vec<MetricComparison> {
+    let base = run_results(results, base);
+    let head = run_results(results, head);
+
+    let lookup = |run: &[BenchmarkResult], target: &str, metric: &str| {
+        run.iter()
+            .find(|result| result.target_id == target)
+            .and_then(|result| result.get_metric(metric))
+    };
+
+    let keys: BTreeSet<(String, String)> = base
+        .iter()
+        .chain(head.iter())
+        .flat_map(|result| {
+            result
+                .metrics
+                .keys()
+                .map(move |metric| (result.target_id.clone(), metric.clone()))
+        })
+        .collect();
+
+    keys.into_iter()
+        .map(|(target_id, metric)| {
+            let base_value = lookup(&base, &target_id, &metric);
+            let head_value = lookup(&head, &target_id, &metric);
+            let delta = base_value.zip(head_value).map(|(b, h)| h - b);
+            let delta_percent = base_value
+                .zip(delta)
+                .filter(|(b, _)| *b != 0.0)
+                .map(|(b, d)| d / b.abs() * 100.0);
+
+            MetricComparison {
+                target_id,
+                metric,
+                base: base_value,
+                head: head_value,
+                delta,
+                delta_percent,
+            }
+        })
+        .collect()
+}
+
+/// Values of a target's metric across all runs, oldest first
+pub fn metric_series(results: &[BenchmarkResult], target: &str, metric: &str) -> Vec<SeriesPoint> {
+    let mut points: Vec<SeriesPoint> = results
+        .iter()
+        .filter(|result| result.target_id == target)
+        .filter_map(|result| {
+            result.get_metric(metric).map(|value| SeriesPoint {
+                run_id: run_id(result),
+                timestamp: result.timestamp,
+                value,
+            })
+        })
+        .collect();
+
+    points.sort_by_key(|point| point.timestamp);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn result(target: &str, run: &str, metrics: &[(&str, f64)]) -> BenchmarkResult {
+        let metrics: HashMap<String, f64> = metrics
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect();
+        let mut result = BenchmarkResult::new(target.to_string(), metrics);
+        result.add_metadata(RUN_ID_METADATA_KEY.to_string(), run.to_string());
+        result
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let results = vec![
+            result("search", "a", &[("latency_p50", 10.0), ("errors", 0.0)]),
+            result("registry", "a", &[("throughput", 100.0)]),
+            result("search", "b", &[("latency_p50", 12.5), ("errors", 2.0)]),
+        ];
+
+        let runs = summarize_runs(&results);
+        assert_eq!(runs.len(), 2);
+
+        let comparison = compare_runs(&results, "a", "b");
+        assert_eq!(comparison.len(), 3);
+
+        assert_eq!(comparison[0].target_id, "registry");
+        assert_eq!(comparison[0].head, None);
+        assert_eq!(comparison[0].delta, None);
+
+        assert_eq!(comparison[1].metric, "errors");
+        assert_eq!(comparison[1].delta, Some(2.0));
+        assert_eq!(comparison[1].delta_percent, None);
+
+        assert_eq!(comparison[2].metric, "latency_p50");
+        assert_eq!(comparison[2].delta_percent, Some(25.0));
+    }
+
+    #[test]
+    fn test_metric_series() {
+        let results = vec![
+            result("search", "a", &[("latency_p50", 10.0)]),
+            result("search", "b", &[("throughput", 5.0)]),
+            result("search", "c", &[("latency_p50", 11.0)]),
+        ];
+
+        let series = metric_series(&results, "search", "latency_p50");
+        let values: Vec<f64> = series.iter().map(|point| point.value).collect();
+        assert_eq!(values, vec![10.0, 11.0]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Storage backends the dashboard reads results from
+//!
+//! Results are reloaded on every request so that runs written while the
+//! server is up show up without a restart.
+
+use crate::benchmarks::io::load_benchmark_results;
+use crate::benchmarks::result::BenchmarkResult;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Where stored benchmark results live
+#[derive(Clone)]
+pub enum ResultStore {
+    /// JSON files written by `save_benchmark_result`
+    Files(PathBuf),
+
+    /// `benchmark_results` table in Postgres
+    #[cfg(feature = "postgres")]
+    Postgres(sqlx::PgPool),
+}
+
+impl ResultStore {
+    /// Connect to Postgres and make sure the results table exists
+    #[cfg(feature = "postgres")]
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await
+            .context("Failed to connect to results database")?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS benchmark_results (
+                id BIGSERIAL PRIMARY KEY,
+                target_id TEXT NOT NULL,
+                timestamp TIMESTAMPTZ NOT NULL,
+                metrics JSONB NOT NULL,
+                metadata JSONB NOT NULL DEFAULT '{}'
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .context("Failed to create benchmark_results table")?;
+
+        Ok(Self::Postgres(pool))
+    }
+
+    /// Load every stored result
+    pub async fn load(&self) -> Result<Vec<BenchmarkResult>> {
+        match self {
+            Self::Files(dir) => {
+                let dir = dir.clone();
+                tokio::task::spawn_blocking(move || load_benchmark_results(Some(&dir)))
+                    .await
+                    .context("Result loading task failed")?
+            }
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => {
+                let rows: Vec<sqlx::types::Json<serde_json::Value>> = sqlx::query_scalar(
+                    r#"
+                    SELECT jsonb_build_object(
+                        'target_id', target_id,
+                        'timestamp', timestamp,
+                        'metrics', metrics,
+                        'metadata', metadata
+                    )
+                    FROM benchmark_results
+                    ORDER BY timestamp
+                    "#,
+                )
+                .fetch_all(pool)
+                .await
+                .context("Failed to load benchmark results")?;
+
+                rows.into_iter()
+                    .map(|row| crate::benchmarks::migrate::result_from_value(row.0))
+                    .collect()
+            }
+        }
+    }
+
+    /// Store results (used by the `run` command when writing to Postgres)
+    pub async fn save(&self, results: &[BenchmarkResult]) -> Result<()> {
+        match self {
+            Self::Files(dir) => {
+                let dir = dir.clone();
+                let results = results.to_vec();
+                tokio::task::spawn_blocking(move || {
+                    crate::benchmarks::io::save_all_results(&results, Some(&dir)).map(|_| ())
+                })
+                .await
+                .context("Result saving task failed")?
+            }
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => {
+                for result in results {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO benchmark_results (target_id, timestamp, metrics, metadata)
+                        VALUES ($1, $2, $3, $4)
+                        "#,
+                    )
+                    .bind(&result.target_id)
+                    .bind(result.timestamp)
+                    .bind(sqlx::types::Json(&result.metrics))
+                    .bind(sqlx::types::Json(&result.metadata))
+                    .execute(pool)
+                    .await
+                    .with_context(|| format!("Failed to store result for {}", result.target_id))?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -6,58 +6,134 @@
 
 pub mod adapters;
 pub mod benchmarks;
+pub mod dashboard;
 
 // Re-export commonly used types
-pub use adapters::{BenchTarget, all_targets};
-pub use benchmarks::result::BenchmarkResult;
-pub use benchmarks::markdown::generate_markdown_report;
-pub use benchmarks::io::{save_benchmark_result, load_benchmark_results};
+pub use adapters::{
+    AsyncBenchTarget, BenchTarget, BlockingTarget, Dependency, TargetConfig, TargetRegistry,
+    all_targets, select_targets,
+};
+pub use benchmarks::result::{BenchmarkResult, SCHEMA_VERSION};
+pub use benchmarks::markdown::{
+    generate_markdown_report, generate_markdown_report_with_baseline, generate_run_report,
+};
+pub use benchmarks::io::{
+    save_benchmark_result, save_benchmark_result_canonical, save_all_results,
+    save_all_results_with, load_benchmark_results, to_canonical_json, JsonStyle,
+};
+pub use benchmarks::derived::{apply_derived_metrics, load_derived_metrics, DerivedMetric};
+pub use benchmarks::soak::{run_soak, SoakConfig, SoakReport, SoakThresholds};
+pub use benchmarks::run::{
+    run_async_targets, BenchmarkRunSummary, RunConfig, SkippedTarget, TargetFailure,
+    TargetOverrides,
+};
+pub use benchmarks::compare::{
+    compare_results, load_thresholds, ComparisonReport, MetricDelta, RegressionThreshold,
+};
+pub use benchmarks::junit::{generate_junit_report, generate_run_junit_report};
+pub use benchmarks::histogram::Histogram;
+pub use benchmarks::merge::{merge_results, save_manifest, MergedRun, RunManifest, WorkerSummary};
+pub use benchmarks::stats::{aggregate_runs, MetricSample, MetricStats};
+pub use benchmarks::csv::{export_results, generate_csv};
+pub use benchmarks::precision::{load_precision, Precision, Unit};
+pub use benchmarks::manifest::{
+    load_manifest, OutputSettings, SuiteManifest, SuiteSettings, TargetSpec,
+};
+pub use benchmarks::history::{
+    ingest_dir, trend_baseline, HistoryStore, MemoryHistory, TrendPoint, TrendQuery,
+};
+#[cfg(feature = "sqlite")]
+pub use benchmarks::history::SqliteHistory;
 
 use anyhow::Result;
 
-/// Main entrypoint to run all registered benchmarks
+/// Main entrypoint to run a benchmark suite
 ///
-/// This function executes all benchmark targets registered in the system,
-/// collects their results, and returns them for further processing.
+/// This function executes the targets the manifest declares, with their
+/// parameters, collects their results, and returns them for further
+/// processing. The default manifest runs every registered target.
 ///
 /// # Returns
 ///
 /// A `Result` containing a vector of `BenchmarkResult` for all executed benchmarks,
-/// or an error if any benchmark fails critically.
+/// or the error of the first target that fails. Use [`SuiteManifest::run`]
+/// to keep the results of the other targets.
 ///
 /// # Example
 ///
 /// ```no_run
-/// use marketplace_benchmarks::run_all_benchmarks;
+/// use marketplace_benchmarks::{load_manifest, run_all_benchmarks};
 ///
 /// fn main() -> anyhow::Result<()> {
-///     let results = run_all_benchmarks()?;
+///     let manifest = load_manifest(None)?;
+///     let results = run_all_benchmarks(&manifest)?;
 ///     println!("Completed {} benchmarks", results.len());
 ///     Ok(())
 /// }
 /// ```
-pub fn run_all_benchmarks() -> Result<Vec<BenchmarkResult>> {
-    log::info!("Starting benchmark run for all registered targets");
+pub fn run_all_benchmarks(manifest: &SuiteManifest) -> Result<Vec<BenchmarkResult>> {
+    let summary = manifest.run()?;
+    if let Some(failure) = summary.failed.first() {
+        anyhow::bail!("Benchmark {} failed: {}", failure.target_id, failure.error);
+    }
+    Ok(summary.results)
+}
 
-    let targets = all_targets();
-    let mut results = Vec::with_capacity(targets.len());
+/// Runs only the benchmark targets with the given ids
+///
+/// An empty slice runs every registered target.
+/// Fails before running anything if an id is not registered.
+///
+/// # Example
+///
+/// ```no_run
+/// use marketplace_benchmarks::run_benchmarks;
+///
+/// fn main() -> anyhow::Result<()> {
+///     let results = run_benchmarks(&["marketplace_search_queries".to_string()])?;
+///     println!("Completed {} benchmarks", results.len());
+///     Ok(())
+/// }
+/// ```
+pub fn run_benchmarks(target_ids: &[String]) -> Result<Vec<BenchmarkResult>> {
+    let summary = run_all_benchmarks_with(&RunConfig {
+        fail_fast: true,
+        targets: target_ids.to_vec(),
+        ..RunConfig::default()
+    })?;
 
-    for target in targets {
-        log::info!("Running benchmark: {}", target.id());
-        match target.run() {
-            Ok(result) => {
-                log::info!("Benchmark {} completed successfully", target.id());
-                results.push(result);
-            }
-            Err(e) => {
-                log::error!("Benchmark {} failed: {}", target.id(), e);
-                return Err(e);
-            }
-        }
+    if let Some(failure) = summary.failed.first() {
+        anyhow::bail!("Benchmark {} failed: {}", failure.target_id, failure.error);
     }
+    Ok(summary.results)
+}
 
-    log::info!("All benchmarks completed. Total: {}", results.len());
-    Ok(results)
+/// Runs benchmarks according to `config`, recording each target's outcome
+///
+/// Unlike [`run_all_benchmarks`], a failing target doesn't discard the other
+/// results: without `fail_fast` every target runs and failures are listed in
+/// the summary; with it, the targets after the first failure are skipped.
+/// Targets whose [`dependencies`](BenchTarget::dependencies) are unmet are
+/// skipped with the reason. With `jobs > 1` targets run concurrently on a
+/// worker pool; the summary lists them in registry order either way. Only an
+/// unknown target id is returned as an error.
+///
+/// # Example
+///
+/// ```no_run
+/// use marketplace_benchmarks::{run_all_benchmarks_with, RunConfig};
+///
+/// fn main() -> anyhow::Result<()> {
+///     let summary = run_all_benchmarks_with(&RunConfig::default())?;
+///     for failure in &summary.failed {
+///         eprintln!("{} failed: {}", failure.target_id, failure.error);
+///     }
+///     println!("Completed {} benchmarks", summary.results.len());
+///     Ok(())
+/// }
+/// ```
+pub fn run_all_benchmarks_with(config: &RunConfig) -> Result<BenchmarkRunSummary> {
+    benchmarks::run::run_with(config)
 }
 
 #[cfg(test)]
@@ -5,6 +5,8 @@
  * Simulates service repository search operations
  */
 
+import { createInterface } from 'readline';
+
 interface Service {
   id: string;
   name: string;
@@ -152,8 +154,7 @@ async function paginatedListing(pageSize: number, pages: number): Promise<Benchm
   }
 }
 
-async function main() {
-  const args = process.argv.slice(2);
+async function runOperation(args: string[]): Promise<BenchmarkMetrics> {
   const operation = args[0] || 'list_all';
 
   let result: BenchmarkMetrics;
@@ -174,10 +175,48 @@ async function main() {
       result = await paginatedListing(pageSize, pages);
       break;
     default:
-      console.error(`Unknown operation: ${operation}`);
-      process.exit(1);
+      throw new Error(`Unknown operation: ${operation}`);
+  }
+
+  return result;
+}
+
+/**
+ * Serve operations as newline-delimited JSON over stdin/stdout, so a single
+ * process handles many operations without per-operation startup cost.
+ *
+ * Request:  {"id": 1, "operation": "<operation>", "args": ["..."]}
+ * Response: {"id": 1, "result": {...}} or {"id": 1, "error": "..."}
+ */
+async function serve() {
+  const lines = createInterface({ input: process.stdin, crlfDelay: Infinity });
+
+  for await (const line of lines) {
+    if (!line.trim()) {
+      continue;
+    }
+
+    let id: unknown = null;
+    try {
+      const request = JSON.parse(line);
+      id = request.id ?? null;
+      const result = await runOperation([request.operation, ...(request.args ?? [])]);
+      process.stdout.write(JSON.stringify({ id, result }) + '\n');
+    } catch (error) {
+      process.stdout.write(JSON.stringify({ id, error: String(error) }) + '\n');
+    }
+  }
+}
+
+async function main() {
+  const args = process.argv.slice(2);
+
+  if (args[0] === '--serve') {
+    await serve();
+    return;
   }
 
+  const result = await runOperation(args);
   console.log(JSON.stringify(result, null, 2));
 }
 
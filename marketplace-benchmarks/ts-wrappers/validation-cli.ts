@@ -5,6 +5,8 @@
  * Simulates service manifest validation, schema checking, and compliance verification
  */
 
+import { createInterface } from 'readline';
+
 interface ValidationRule {
   name: string;
   validate: (data: any) => boolean;
@@ -255,8 +257,7 @@ async function validateSchemaCompliance(strictMode: boolean = false): Promise<Be
   }
 }
 
-async function main() {
-  const args = process.argv.slice(2);
+async function runOperation(args: string[]): Promise<BenchmarkMetrics> {
   const operation = args[0] || 'single';
 
   let result: BenchmarkMetrics;
@@ -276,10 +277,48 @@ async function main() {
       result = await validateSchemaCompliance(strictMode);
       break;
     default:
-      console.error(`Unknown operation: ${operation}`);
-      process.exit(1);
+      throw new Error(`Unknown operation: ${operation}`);
+  }
+
+  return result;
+}
+
+/**
+ * Serve operations as newline-delimited JSON over stdin/stdout, so a single
+ * process handles many operations without per-operation startup cost.
+ *
+ * Request:  {"id": 1, "operation": "<operation>", "args": ["..."]}
+ * Response: {"id": 1, "result": {...}} or {"id": 1, "error": "..."}
+ */
+async function serve() {
+  const lines = createInterface({ input: process.stdin, crlfDelay: Infinity });
+
+  for await (const line of lines) {
+    if (!line.trim()) {
+      continue;
+    }
+
+    let id: unknown = null;
+    try {
+      const request = JSON.parse(line);
+      id = request.id ?? null;
+      const result = await runOperation([request.operation, ...(request.args ?? [])]);
+      process.stdout.write(JSON.stringify({ id, result }) + '\n');
+    } catch (error) {
+      process.stdout.write(JSON.stringify({ id, error: String(error) }) + '\n');
+    }
+  }
+}
+
+async function main() {
+  const args = process.argv.slice(2);
+
+  if (args[0] === '--serve') {
+    await serve();
+    return;
   }
 
+  const result = await runOperation(args);
   console.log(JSON.stringify(result, null, 2));
 }
 
@@ -0,0 +1,3 @@
+fn main() {
+    llm_infra::build_info::emit();
+}
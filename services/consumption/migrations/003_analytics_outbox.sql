@@ -0,0 +1,18 @@
+-- Durable analytics event log (outbox) for downstream replay
+CREATE TABLE IF NOT EXISTS analytics_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(100) NOT NULL,
+    service_id UUID,
+    consumer_id UUID,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Indexes for cursor pagination and filtering
+CREATE INDEX idx_analytics_outbox_consumer ON analytics_outbox(consumer_id, id);
+CREATE INDEX idx_analytics_outbox_service ON analytics_outbox(service_id, id);
+CREATE INDEX idx_analytics_outbox_event_type ON analytics_outbox(event_type, id);
+CREATE INDEX idx_analytics_outbox_created ON analytics_outbox(created_at DESC);
+
+COMMENT ON TABLE analytics_outbox IS 'Append-only log of analytics events, replayable via /api/v1/analytics/events';
+COMMENT ON COLUMN analytics_outbox.id IS 'Monotonic cursor used for replay pagination';
@@ -0,0 +1,28 @@
+-- Incidents group related SLA violations for alerting
+CREATE TABLE IF NOT EXISTS incidents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    service_id UUID NOT NULL,
+    metric VARCHAR(100) NOT NULL,
+    severity VARCHAR(50) NOT NULL CHECK (severity IN ('warning', 'critical')),
+    status VARCHAR(50) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'acknowledged', 'resolved')),
+    violation_count BIGINT NOT NULL DEFAULT 1,
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    acknowledged_at TIMESTAMP WITH TIME ZONE,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT fk_incident_service
+        FOREIGN KEY (service_id)
+        REFERENCES services(id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_incidents_service_status ON incidents(service_id, status);
+CREATE INDEX idx_incidents_status_last_seen ON incidents(status, last_seen_at);
+
+-- At most one unresolved incident per service/metric
+CREATE UNIQUE INDEX idx_incidents_active_unique
+    ON incidents(service_id, metric)
+    WHERE status <> 'resolved';
+
+COMMENT ON TABLE incidents IS 'Groups related SLA violations into incidents with open/acknowledged/resolved lifecycle';
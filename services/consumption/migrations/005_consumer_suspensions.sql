@@ -0,0 +1,8 @@
+-- Consumers suspended by operators; their API keys are rejected while suspended
+CREATE TABLE IF NOT EXISTS consumer_suspensions (
+    consumer_id UUID PRIMARY KEY,
+    reason TEXT,
+    suspended_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE consumer_suspensions IS 'Consumers blocked from the consumption API by an operator';
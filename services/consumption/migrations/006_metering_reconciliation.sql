@@ -0,0 +1,28 @@
+-- Tokens invoiced per service per day, reported by the billing system
+CREATE TABLE IF NOT EXISTS invoiced_usage (
+    day DATE NOT NULL,
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    tokens BIGINT NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (day, service_id)
+);
+
+-- Daily cross-check of token counts between metering sources
+CREATE TABLE IF NOT EXISTS metering_reconciliations (
+    day DATE NOT NULL,
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    usage_tokens BIGINT NOT NULL,
+    quota_tokens BIGINT NOT NULL,
+    analytics_tokens BIGINT NOT NULL,
+    invoiced_tokens BIGINT,
+    max_drift DOUBLE PRECISION NOT NULL,
+    status VARCHAR(50) NOT NULL CHECK (status IN ('ok', 'discrepancy')),
+    checked_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (day, service_id)
+);
+
+CREATE INDEX idx_metering_reconciliations_status ON metering_reconciliations(status, day DESC);
+
+COMMENT ON TABLE metering_reconciliations IS 'Per-day comparison of usage records, quota increments, analytics events and invoices';
@@ -0,0 +1,15 @@
+-- Sandboxed WebAssembly plugins (request/response transforms and policies)
+CREATE TABLE IF NOT EXISTS plugins (
+    name VARCHAR(255) NOT NULL,
+    version VARCHAR(50) NOT NULL,
+    kind VARCHAR(50) NOT NULL CHECK (kind IN ('request_transform', 'response_transform', 'policy')),
+    module BYTEA NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    -- Published versions are immutable; services pin an exact version
+    PRIMARY KEY (name, version)
+);
+
+-- Plugins pinned per service, run in order:
+-- [{"name": "...", "version": "...", "capabilities": ["log"], "fuel": 1000000, "memory_mb": 8}]
+ALTER TABLE services ADD COLUMN IF NOT EXISTS plugins JSONB NOT NULL DEFAULT '[]';
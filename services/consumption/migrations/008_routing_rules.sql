@@ -0,0 +1,16 @@
+-- Per-service routing rules evaluated at admission, in position order
+CREATE TABLE IF NOT EXISTS routing_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    -- Rhai expression over tier, prompt_chars, max_tokens, model, hour, weekday
+    condition TEXT NOT NULL,
+    -- {"type": "route", "endpoint": ...} | {"type": "priority", "priority": ...}
+    -- | {"type": "sandbox"} | {"type": "reject", "reason": ...}
+    action JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    UNIQUE (service_id, position)
+);
@@ -0,0 +1,32 @@
+-- Per-organization data residency: usage records and audit logs of pinned
+-- organizations are stored only in their region's schema
+CREATE TABLE IF NOT EXISTS data_residency_pins (
+    organization_id UUID PRIMARY KEY,
+    region VARCHAR(50) NOT NULL,
+    pinned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Consumers belong to an organization through their API key metadata
+CREATE INDEX IF NOT EXISTS idx_api_keys_organization
+    ON api_keys ((metadata->>'organization_id'));
+
+-- Regional storage (keep in sync with DATA_RESIDENCY_REGIONS)
+CREATE SCHEMA IF NOT EXISTS residency_eu;
+CREATE SCHEMA IF NOT EXISTS residency_us;
+
+CREATE TABLE IF NOT EXISTS residency_eu.usage_records (LIKE public.usage_records INCLUDING DEFAULTS);
+CREATE TABLE IF NOT EXISTS residency_us.usage_records (LIKE public.usage_records INCLUDING DEFAULTS);
+CREATE TABLE IF NOT EXISTS residency_eu.audit_logs (LIKE public.audit_logs INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS residency_us.audit_logs (LIKE public.audit_logs INCLUDING ALL);
+
+CREATE INDEX IF NOT EXISTS idx_usage_records_service ON residency_eu.usage_records(service_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_usage_records_consumer ON residency_eu.usage_records(consumer_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_usage_records_service ON residency_us.usage_records(service_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_usage_records_consumer ON residency_us.usage_records(consumer_id, timestamp DESC);
+
+-- Cross-region view for aggregate-only jobs (SLA monitoring, metering
+-- reconciliation); per-consumer reads must go to the consumer's region
+CREATE OR REPLACE VIEW usage_records_all AS
+    SELECT * FROM public.usage_records
+    UNION ALL SELECT * FROM residency_eu.usage_records
+    UNION ALL SELECT * FROM residency_us.usage_records;
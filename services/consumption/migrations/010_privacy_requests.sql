@@ -0,0 +1,12 @@
+-- GDPR data subject requests (export and erasure) with signed reports
+CREATE TABLE IF NOT EXISTS privacy_requests (
+    id UUID PRIMARY KEY,
+    kind VARCHAR(50) NOT NULL CHECK (kind IN ('export', 'erasure')),
+    -- SHA-256 of the consumer id; the id itself is not retained
+    subject_hash VARCHAR(64) NOT NULL,
+    signature VARCHAR(128) NOT NULL,
+    report JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_privacy_requests_subject ON privacy_requests(subject_hash, created_at DESC);
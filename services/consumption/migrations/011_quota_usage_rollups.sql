@@ -0,0 +1,14 @@
+-- Hourly token consumption per consumer and service, rolled up from
+-- usage_records so quota history can be served without scanning raw usage
+CREATE TABLE IF NOT EXISTS quota_usage_rollups (
+    consumer_id UUID NOT NULL,
+    service_id UUID NOT NULL REFERENCES services(id),
+    hour TIMESTAMP WITH TIME ZONE NOT NULL,
+    tokens BIGINT NOT NULL DEFAULT 0,
+    requests BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    PRIMARY KEY (consumer_id, service_id, hour)
+);
+
+CREATE INDEX IF NOT EXISTS idx_quota_usage_rollups_service ON quota_usage_rollups(service_id, hour);
@@ -0,0 +1,12 @@
+-- Monthly SLA compliance reports, stored once the month is complete
+CREATE TABLE IF NOT EXISTS sla_reports (
+    consumer_id UUID NOT NULL,
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    month DATE NOT NULL, -- First day of the report month
+    report JSONB NOT NULL,
+    generated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (consumer_id, service_id, month)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sla_reports_service ON sla_reports(service_id, month DESC);
@@ -0,0 +1,7 @@
+-- Per-service configuration of the built-in PII pre-filter
+CREATE TABLE IF NOT EXISTS pii_filters (
+    service_id UUID PRIMARY KEY REFERENCES services(id) ON DELETE CASCADE,
+    -- {"mode": "off" | "redact" | "block", "categories": ["email", "ssn", "credit_card"]}
+    config JSONB NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
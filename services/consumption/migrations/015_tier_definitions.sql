@@ -0,0 +1,7 @@
+-- Tier limits overriding the compiled-in defaults, keyed by tier name
+CREATE TABLE IF NOT EXISTS tier_definitions (
+    name VARCHAR(50) PRIMARY KEY,
+    -- {"rate_limit": 1000, "burst_capacity": 2000, "quota_limit": 1000000000, "overage": "block" | "allow"}
+    limits JSONB NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
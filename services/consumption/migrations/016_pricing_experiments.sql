@@ -0,0 +1,27 @@
+-- Pricing experiments: alternative price books served to buckets of a
+-- service's consumers alongside its default pricing
+CREATE TABLE IF NOT EXISTS pricing_experiments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'running', -- running, stopped
+    -- [{"variant": "discount", "weight": 2500, "pricing": {"model": ..., "rates": [...]}}]
+    -- weights are basis points of consumers; the remainder stays on default pricing
+    books JSONB NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    stopped_at TIMESTAMP WITH TIME ZONE
+);
+
+-- At most one running experiment per service, so a request's price is unambiguous
+CREATE UNIQUE INDEX IF NOT EXISTS idx_pricing_experiments_running
+    ON pricing_experiments(service_id) WHERE status = 'running';
+
+-- Consumers pinned to a variant regardless of their bucket
+CREATE TABLE IF NOT EXISTS pricing_experiment_cohorts (
+    experiment_id UUID NOT NULL REFERENCES pricing_experiments(id) ON DELETE CASCADE,
+    consumer_id UUID NOT NULL,
+    variant VARCHAR(100) NOT NULL,
+    assigned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (experiment_id, consumer_id)
+);
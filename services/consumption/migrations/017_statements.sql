@@ -0,0 +1,15 @@
+-- Monthly per-consumer statements, stored once the month is complete
+CREATE TABLE IF NOT EXISTS statements (
+    consumer_id UUID NOT NULL,
+    month DATE NOT NULL, -- First day of the statement month
+    statement JSONB NOT NULL,
+    generated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    -- Set once the statement-ready notification was sent
+    notified_at TIMESTAMP WITH TIME ZONE,
+
+    PRIMARY KEY (consumer_id, month)
+);
+
+CREATE INDEX IF NOT EXISTS idx_statements_unnotified
+    ON statements(month)
+    WHERE notified_at IS NULL;
@@ -0,0 +1,29 @@
+-- Upstream provider API keys, encrypted with PROVIDER_CREDENTIAL_KEY (AES-256-GCM)
+CREATE TABLE IF NOT EXISTS provider_credentials (
+    service_id UUID PRIMARY KEY REFERENCES services(id) ON DELETE CASCADE,
+    header_name VARCHAR(100) NOT NULL,
+    -- Prepended to the secret in the header value, e.g. 'Bearer '
+    prefix VARCHAR(50) NOT NULL DEFAULT '',
+    ciphertext BYTEA NOT NULL,
+    nonce BYTEA NOT NULL,
+    -- Fingerprint of the master key the secret was encrypted with
+    key_id VARCHAR(16) NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    rotated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_accessed_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Append-only record of every credential change and decryption
+CREATE TABLE IF NOT EXISTS provider_credential_audit (
+    id BIGSERIAL PRIMARY KEY,
+    service_id UUID NOT NULL,
+    action VARCHAR(50) NOT NULL, -- stored, rotated, deleted, decrypted, decrypt_failed
+    actor VARCHAR(255) NOT NULL,
+    version INTEGER,
+    request_id UUID,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_provider_credential_audit_service
+    ON provider_credential_audit(service_id, occurred_at DESC);
@@ -0,0 +1,8 @@
+-- Per-service response post-processing stages, run after the upstream call
+CREATE TABLE IF NOT EXISTS response_pipelines (
+    service_id UUID PRIMARY KEY REFERENCES services(id) ON DELETE CASCADE,
+    -- {"shield_scan": bool, "profanity_mask": bool, "extra_profanity": [...],
+    --  "json_validation": bool, "inject_metadata": bool}
+    config JSONB NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,42 @@
+-- Append-only ledger of every quota-affecting event; replaying a month
+-- rebuilds the Redis quota counters and backs billing disputes
+CREATE TABLE IF NOT EXISTS quota_events (
+    id BIGSERIAL PRIMARY KEY,
+    consumer_id UUID NOT NULL,
+    service_id UUID NOT NULL,
+    month VARCHAR(7) NOT NULL, -- YYYY-MM, as in quota_usage
+    kind VARCHAR(20) NOT NULL
+        CHECK (kind IN ('consumption', 'reset', 'adjustment', 'overage_grant')),
+    -- Change to used tokens; for a reset, minus the usage it cleared
+    used_delta BIGINT NOT NULL DEFAULT 0,
+    -- Change to the extra allowance on top of the tier quota
+    granted_delta BIGINT NOT NULL DEFAULT 0,
+    request_id UUID,
+    actor VARCHAR(255),
+    reason TEXT,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_quota_events_pair
+    ON quota_events(consumer_id, service_id, month, id);
+CREATE INDEX IF NOT EXISTS idx_quota_events_month ON quota_events(month);
+
+-- A retried usage update must not count the same request twice
+CREATE UNIQUE INDEX IF NOT EXISTS idx_quota_events_request
+    ON quota_events(request_id) WHERE kind = 'consumption';
+
+-- Events are never rewritten; only privacy erasure may re-attribute them
+CREATE OR REPLACE FUNCTION quota_events_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE'
+        OR (to_jsonb(NEW) - 'consumer_id') <> (to_jsonb(OLD) - 'consumer_id') THEN
+        RAISE EXCEPTION 'quota_events is append-only';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS quota_events_append_only ON quota_events;
+CREATE TRIGGER quota_events_append_only
+    BEFORE UPDATE OR DELETE ON quota_events
+    FOR EACH ROW EXECUTE FUNCTION quota_events_append_only();
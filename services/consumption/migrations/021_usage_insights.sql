@@ -0,0 +1,10 @@
+-- Per-service hour-of-week usage heatmap, sustained peak windows and
+-- suggested rate limit profiles, computed by the usage_insights job from
+-- quota_usage_rollups
+CREATE TABLE IF NOT EXISTS usage_insights (
+    service_id UUID PRIMARY KEY REFERENCES services(id),
+    window_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    window_end TIMESTAMP WITH TIME ZONE NOT NULL,
+    insights JSONB NOT NULL,
+    computed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,16 @@
+-- Per-organization billing anchor: quotas reset on this day of the month
+-- (the last day of shorter months). Organizations without a row keep
+-- calendar-month resets, so existing consumers are unaffected until an
+-- anchor is set for their organization.
+CREATE TABLE IF NOT EXISTS billing_anchors (
+    organization_id UUID PRIMARY KEY,
+    anchor_day SMALLINT NOT NULL CHECK (anchor_day BETWEEN 1 AND 31),
+    -- Period in progress when the anchor last changed, extended or cut to
+    -- the first new anchor date in a later month than it started; usage
+    -- already counted stays under its month label
+    transition_start TIMESTAMP WITH TIME ZONE,
+    transition_end TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CHECK ((transition_start IS NULL) = (transition_end IS NULL))
+);
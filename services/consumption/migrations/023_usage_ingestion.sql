@@ -0,0 +1,19 @@
+-- Usage reported by providers that meter their own traffic (edge inference).
+-- One row per provider record, so a batch retried after a timeout or partial
+-- failure never charges the same record twice.
+CREATE TABLE IF NOT EXISTS ingested_usage (
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    -- Provider's id for the record, unique per service
+    external_id VARCHAR(255) NOT NULL,
+    -- request_id of the usage record the ingested record became
+    request_id UUID NOT NULL,
+    consumer_id UUID NOT NULL,
+    -- When the provider served the request
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    ingested_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (service_id, external_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingested_usage_consumer
+    ON ingested_usage(consumer_id, ingested_at DESC);
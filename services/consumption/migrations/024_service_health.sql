@@ -0,0 +1,15 @@
+-- Health of each service's upstream endpoint, separate from its lifecycle
+-- `services.status`. Replicas count consecutive upstream and canary failures
+-- in memory and write a row when the status changes; the others pick the
+-- change up at their next reload. Services without a row are active.
+CREATE TABLE IF NOT EXISTS service_health (
+    service_id UUID PRIMARY KEY REFERENCES services(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'active',
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    -- Provider endpoint notified of status changes
+    webhook_url TEXT,
+
+    CONSTRAINT valid_health_status CHECK (status IN ('active', 'degraded', 'unavailable'))
+);
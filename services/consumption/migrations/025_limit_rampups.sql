@@ -0,0 +1,10 @@
+-- Per-organization warm-up of new consumers' limits: rate limits, burst and
+-- quota start at start_percent of the tier's limits on the consumer's first
+-- API key and grow linearly to the full limits over ramp_days. Organizations
+-- without a row, and consumers older than the ramp, get full limits.
+CREATE TABLE IF NOT EXISTS limit_rampups (
+    organization_id UUID PRIMARY KEY,
+    start_percent SMALLINT NOT NULL CHECK (start_percent BETWEEN 1 AND 100),
+    ramp_days INTEGER NOT NULL CHECK (ramp_days BETWEEN 1 AND 365),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,8 @@
+-- Per-organization locale of rendered SLA reports and statements (number
+-- and date formats, translated strings). Organizations without a row get
+-- the default en-US rendering.
+CREATE TABLE IF NOT EXISTS organization_locales (
+    organization_id UUID PRIMARY KEY,
+    locale VARCHAR(35) NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,11 @@
+-- Negotiated rate limits of individual consumers on a service. A row
+-- replaces the tier's rate limit and burst capacity for that pair; pairs
+-- without one keep their tier's limits.
+CREATE TABLE IF NOT EXISTS rate_limit_overrides (
+    consumer_id UUID NOT NULL,
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    rate_limit BIGINT NOT NULL CHECK (rate_limit > 0),
+    burst_capacity INTEGER NOT NULL CHECK (burst_capacity > 0),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (consumer_id, service_id)
+);
@@ -0,0 +1,5 @@
+-- Rate limiting algorithm per service: the token bucket allows bursts up to
+-- the tier's burst capacity, the sliding window strictly counts requests in
+-- the last minute (rate_limit * 60 of them).
+ALTER TABLE services ADD COLUMN IF NOT EXISTS rate_limit_algorithm VARCHAR(50) NOT NULL DEFAULT 'token_bucket'
+    CHECK (rate_limit_algorithm IN ('token_bucket', 'sliding_window'));
@@ -0,0 +1,4 @@
+-- Replicas of a service: {"strategy": "weighted_round_robin" | "least_latency",
+-- "endpoints": [{"url": ..., "weight": ...}]}. Services without any are
+-- routed to services.endpoint.
+ALTER TABLE services ADD COLUMN IF NOT EXISTS endpoints JSONB NOT NULL DEFAULT '{}';
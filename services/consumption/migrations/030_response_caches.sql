@@ -0,0 +1,8 @@
+-- Per-service response cache settings; cached responses themselves live in
+-- Redis under response_cache:{service_id}:{request hash}
+CREATE TABLE IF NOT EXISTS response_caches (
+    service_id UUID PRIMARY KEY REFERENCES services(id) ON DELETE CASCADE,
+    -- {"enabled": bool, "ttl_secs": int}
+    config JSONB NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
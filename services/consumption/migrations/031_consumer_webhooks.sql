@@ -0,0 +1,18 @@
+-- Consumer-registered webhooks for quota, SLA and API key events.
+-- Deliveries are queued as webhook_delivery jobs and signed with the secret.
+CREATE TABLE IF NOT EXISTS consumer_webhooks (
+    id UUID PRIMARY KEY,
+    consumer_id UUID NOT NULL,
+    url TEXT NOT NULL,
+    secret VARCHAR(100) NOT NULL,
+    -- quota.warning, quota.exhausted, sla.violation, api_key.expiring
+    events TEXT[] NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_consumer_webhooks_consumer ON consumer_webhooks(consumer_id);
+
+-- Set once the key's owner was told it's about to expire
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS expiry_notified_at TIMESTAMP WITH TIME ZONE;
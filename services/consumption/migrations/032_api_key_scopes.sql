@@ -0,0 +1,4 @@
+-- Scopes an API key is limited to (consume, read:usage, read:billing,
+-- manage:keys, manage:webhooks, manage:incidents, manage:privacy).
+-- NULL grants every scope, as keys created before scopes existed had.
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS scopes TEXT[];
@@ -0,0 +1,7 @@
+-- Per-service LLM-Shield content scanning settings
+CREATE TABLE IF NOT EXISTS content_scans (
+    service_id UUID PRIMARY KEY REFERENCES services(id) ON DELETE CASCADE,
+    -- {"scan_prompts": bool, "fail_closed": bool}
+    config JSONB NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,29 @@
+-- Hourly and daily usage per consumer, service and status, rolled up from
+-- usage_records so usage stats and SLA status don't scan raw usage
+CREATE TABLE IF NOT EXISTS usage_rollups (
+    consumer_id UUID NOT NULL,
+    service_id UUID NOT NULL REFERENCES services(id),
+    -- 'hour' or 'day'
+    granularity VARCHAR(8) NOT NULL,
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    tokens BIGINT NOT NULL DEFAULT 0,
+    cost DOUBLE PRECISION NOT NULL DEFAULT 0,
+    -- Sum of request durations, for average latencies
+    duration_ms BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    PRIMARY KEY (consumer_id, service_id, granularity, bucket, status)
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_rollups_service ON usage_rollups(service_id, granularity, bucket);
+
+-- Range of whole hours usage_rollups covers; reads outside it scan
+-- usage_records
+CREATE TABLE IF NOT EXISTS usage_rollup_coverage (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    rolled_up_from TIMESTAMP WITH TIME ZONE NOT NULL,
+    rolled_up_until TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
@@ -0,0 +1,34 @@
+-- Monthly usage_records partitions are created ahead of time, and expired
+-- ones archived or dropped, by the service's partition maintenance task.
+-- Rows outside every monthly partition (e.g. late records) land in the
+-- default partition until the service creates their month's partition.
+CREATE TABLE IF NOT EXISTS public.usage_records_default PARTITION OF public.usage_records DEFAULT;
+
+-- Regional copies were created unpartitioned; rebuild them partitioned.
+-- Existing rows go to the default partition and are split into monthly
+-- partitions on the next maintenance run.
+DROP VIEW IF EXISTS usage_records_all;
+
+ALTER TABLE residency_eu.usage_records RENAME TO usage_records_unpartitioned;
+CREATE TABLE residency_eu.usage_records (LIKE public.usage_records INCLUDING DEFAULTS)
+    PARTITION BY RANGE (timestamp);
+CREATE TABLE residency_eu.usage_records_default PARTITION OF residency_eu.usage_records DEFAULT;
+INSERT INTO residency_eu.usage_records SELECT * FROM residency_eu.usage_records_unpartitioned;
+DROP TABLE residency_eu.usage_records_unpartitioned;
+
+ALTER TABLE residency_us.usage_records RENAME TO usage_records_unpartitioned;
+CREATE TABLE residency_us.usage_records (LIKE public.usage_records INCLUDING DEFAULTS)
+    PARTITION BY RANGE (timestamp);
+CREATE TABLE residency_us.usage_records_default PARTITION OF residency_us.usage_records DEFAULT;
+INSERT INTO residency_us.usage_records SELECT * FROM residency_us.usage_records_unpartitioned;
+DROP TABLE residency_us.usage_records_unpartitioned;
+
+CREATE INDEX IF NOT EXISTS idx_usage_records_service ON residency_eu.usage_records(service_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_usage_records_consumer ON residency_eu.usage_records(consumer_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_usage_records_service ON residency_us.usage_records(service_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_usage_records_consumer ON residency_us.usage_records(consumer_id, timestamp DESC);
+
+CREATE OR REPLACE VIEW usage_records_all AS
+    SELECT * FROM public.usage_records
+    UNION ALL SELECT * FROM residency_eu.usage_records
+    UNION ALL SELECT * FROM residency_us.usage_records;
@@ -0,0 +1,641 @@
+//! Operational CLI for the consumption service
+//!
+//! Talks to the admin API by default. With `--direct` it connects to
+//! PostgreSQL and Redis itself, for when the service is down; jobs and key
+//! rotation need the running service and are only available through the API.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use redis::AsyncCommands;
+use serde_json::Value;
+use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Quota periods as the service computes them, so ledger entries written
+/// here land in the same period
+#[path = "../services/billing_anchor.rs"]
+#[allow(dead_code)]
+mod billing_anchor;
+
+use billing_anchor::BillingAnchors;
+
+#[derive(Parser)]
+#[command(name = "marketplace-admin")]
+#[command(about = "Operational tasks for the LLM Marketplace consumption service", long_about = None)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    /// Base URL of the consumption service
+    #[arg(
+        long,
+        env = "MARKETPLACE_ADMIN_URL",
+        default_value = "http://localhost:3000",
+        global = true
+    )]
+    api_url: String,
+
+    /// Admin API token
+    #[arg(long, env = "ADMIN_API_TOKEN", hide_env_values = true, global = true)]
+    token: Option<String>,
+
+    /// Bypass the admin API and operate on the database and Redis directly
+    #[arg(long, global = true)]
+    direct: bool,
+
+    /// Database URL used with --direct
+    #[arg(
+        long,
+        env = "DATABASE_URL",
+        default_value = "postgres://localhost/llm_marketplace",
+        global = true
+    )]
+    database_url: String,
+
+    /// Redis URL used with --direct
+    #[arg(
+        long,
+        env = "REDIS_URL",
+        default_value = "redis://localhost:6379",
+        global = true
+    )]
+    redis_url: String,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List consumers with key counts and suspension state
+    Consumers {
+        #[arg(short, long, default_value_t = 100)]
+        limit: i64,
+    },
+
+    /// Suspend a consumer, rejecting all of their API keys
+    Suspend {
+        consumer_id: Uuid,
+
+        /// Reason recorded with the suspension
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+
+    /// Lift a consumer suspension
+    Unsuspend { consumer_id: Uuid },
+
+    /// Reset the monthly quota counter for a consumer/service pair
+    ResetQuota { consumer_id: Uuid, service_id: Uuid },
+
+    /// Reset the rate limit window for a consumer/service pair
+    ResetRateLimit { consumer_id: Uuid, service_id: Uuid },
+
+    /// Revoke an API key and issue a replacement (API only)
+    RotateKey { key_id: Uuid },
+
+    /// Run a background job immediately (API only)
+    RunJob { job: Job },
+
+    /// Show recent SLA violations
+    Violations {
+        /// Only show violations for this service
+        #[arg(short, long)]
+        service_id: Option<Uuid>,
+
+        #[arg(short, long, default_value_t = 20)]
+        limit: i64,
+
+        /// Keep polling for new violations
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Poll interval in seconds with --follow
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Job {
+    SlaMonitor,
+    PersistQuotas,
+    RebuildQuotas,
+    RollupQuotaUsage,
+    RollupUsage,
+    GenerateSlaReports,
+    GenerateStatements,
+    ResolveIncidents,
+    ReconcileMetering,
+    EnforceResidency,
+    MaintainUsagePartitions,
+    SweepJobQueue,
+    AuditRedis,
+}
+
+impl Job {
+    fn path(&self) -> &'static str {
+        match self {
+            Job::SlaMonitor => "sla-monitor",
+            Job::PersistQuotas => "persist-quotas",
+            Job::RebuildQuotas => "rebuild-quotas",
+            Job::RollupQuotaUsage => "rollup-quota-usage",
+            Job::RollupUsage => "rollup-usage",
+            Job::GenerateSlaReports => "generate-sla-reports",
+            Job::GenerateStatements => "generate-statements",
+            Job::ResolveIncidents => "resolve-incidents",
+            Job::ReconcileMetering => "reconcile-metering",
+            Job::EnforceResidency => "enforce-residency",
+            Job::MaintainUsagePartitions => "maintain-usage-partitions",
+            Job::SweepJobQueue => "sweep-job-queue",
+            Job::AuditRedis => "audit-redis",
+        }
+    }
+}
+
+/// Admin API client
+struct ApiBackend {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl ApiBackend {
+    fn new(base_url: String, token: Option<String>) -> Result<Self> {
+        let token = token.context("ADMIN_API_TOKEN or --token is required for the admin API")?;
+
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value> {
+        let mut request = self
+            .client
+            .request(method, format!("{}/api/v1/admin{}", self.base_url, path))
+            .header("X-Admin-Token", &self.token);
+
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await.context("Failed to reach admin API")?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+
+        if !status.is_success() {
+            anyhow::bail!("Admin API returned {}: {}", status, text);
+        }
+
+        if text.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        serde_json::from_str(&text).context("Failed to parse admin API response")
+    }
+}
+
+/// Direct database and Redis access
+struct DirectBackend {
+    db: PgPool,
+    redis_url: String,
+}
+
+impl DirectBackend {
+    async fn connect(database_url: &str, redis_url: String) -> Result<Self> {
+        let db = PgPoolOptions::new()
+            .max_connections(2)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect(database_url)
+            .await
+            .context("Failed to connect to database")?;
+
+        Ok(Self { db, redis_url })
+    }
+
+    async fn redis_del(&self, key: String) -> Result<()> {
+        let client = redis::Client::open(self.redis_url.as_str())?;
+        let mut conn = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .context("Failed to connect to Redis")?;
+        conn.del::<_, ()>(key)
+            .await
+            .context("Failed to delete Redis key")?;
+        Ok(())
+    }
+
+    /// Clear a quota counter and record the reset in the quota ledger
+    async fn reset_quota(&self, consumer_id: Uuid, service_id: Uuid) -> Result<()> {
+        let client = redis::Client::open(self.redis_url.as_str())?;
+        let mut conn = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .context("Failed to connect to Redis")?;
+        let cleared: Option<i64> = redis::cmd("GETDEL")
+            .arg(format!("quota:{}:{}", consumer_id, service_id))
+            .query_async(&mut conn)
+            .await
+            .context("Failed to delete Redis key")?;
+
+        let period = BillingAnchors::new(Arc::new(self.db.clone()))
+            .for_consumer(consumer_id)
+            .await?
+            .period(Utc::now());
+
+        sqlx::query(
+            r#"
+            INSERT INTO quota_events (consumer_id, service_id, month, kind, used_delta, actor)
+            VALUES ($1, $2, $3, 'reset', $4, 'cli')
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(period.label())
+        .bind(-cleared.unwrap_or(0))
+        .execute(&self.db)
+        .await
+        .context("Failed to record quota reset")?;
+
+        Ok(())
+    }
+
+    async fn consumers(&self, limit: i64) -> Result<Value> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                k.consumer_id,
+                COUNT(*) FILTER (WHERE k.revoked_at IS NULL) AS active_keys,
+                COUNT(*) AS total_keys,
+                MAX(k.created_at) AS last_key_created_at,
+                s.suspended_at,
+                s.reason AS suspension_reason
+            FROM api_keys k
+            LEFT JOIN consumer_suspensions s ON s.consumer_id = k.consumer_id
+            GROUP BY k.consumer_id, s.suspended_at, s.reason
+            ORDER BY last_key_created_at DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list consumers")?;
+
+        let consumers = rows
+            .iter()
+            .map(|row| {
+                serde_json::json!({
+                    "consumer_id": row.get::<Uuid, _>("consumer_id"),
+                    "active_keys": row.get::<i64, _>("active_keys"),
+                    "total_keys": row.get::<i64, _>("total_keys"),
+                    "last_key_created_at": row.get::<DateTime<Utc>, _>("last_key_created_at"),
+                    "suspended_at": row.get::<Option<DateTime<Utc>>, _>("suspended_at"),
+                    "suspension_reason": row.get::<Option<String>, _>("suspension_reason"),
+                })
+            })
+            .collect();
+
+        Ok(Value::Array(consumers))
+    }
+
+    async fn violations(
+        &self,
+        since: Option<DateTime<Utc>>,
+        service_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Value> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, service_id, metric, threshold, actual, timestamp, severity
+            FROM sla_violations
+            WHERE ($1::timestamptz IS NULL OR timestamp > $1)
+                AND ($2::uuid IS NULL OR service_id = $2)
+            ORDER BY timestamp DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(since)
+        .bind(service_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list SLA violations")?;
+
+        let violations = rows
+            .iter()
+            .map(|row| {
+                serde_json::json!({
+                    "id": row.get::<Uuid, _>("id"),
+                    "service_id": row.get::<Uuid, _>("service_id"),
+                    "metric": row.get::<String, _>("metric"),
+                    "threshold": row.get::<f64, _>("threshold"),
+                    "actual": row.get::<f64, _>("actual"),
+                    "timestamp": row.get::<DateTime<Utc>, _>("timestamp"),
+                    "severity": row.get::<String, _>("severity"),
+                })
+            })
+            .collect();
+
+        Ok(Value::Array(violations))
+    }
+}
+
+enum Backend {
+    Api(ApiBackend),
+    Direct(DirectBackend),
+}
+
+impl Backend {
+    async fn fetch_violations(
+        &self,
+        since: Option<DateTime<Utc>>,
+        service_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Value> {
+        match self {
+            Backend::Api(api) => {
+                let mut query = vec![format!("limit={}", limit)];
+                if let Some(since) = since {
+                    query.push(format!("since={}", urlencode(&since.to_rfc3339())));
+                }
+                if let Some(service_id) = service_id {
+                    query.push(format!("service_id={}", service_id));
+                }
+                api.send(
+                    reqwest::Method::GET,
+                    &format!("/violations?{}", query.join("&")),
+                    None,
+                )
+                .await
+            }
+            Backend::Direct(direct) => direct.violations(since, service_id, limit).await,
+        }
+    }
+}
+
+/// Percent-encode the characters RFC 3339 timestamps can contain
+fn urlencode(value: &str) -> String {
+    value.replace('+', "%2B").replace(':', "%3A")
+}
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn print_violation(violation: &Value) {
+    println!(
+        "{}  {:<8}  service={}  {}={} (threshold {})",
+        violation["timestamp"].as_str().unwrap_or("-"),
+        violation["severity"].as_str().unwrap_or("-"),
+        violation["service_id"].as_str().unwrap_or("-"),
+        violation["metric"].as_str().unwrap_or("-"),
+        violation["actual"],
+        violation["threshold"],
+    );
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let backend = if cli.direct {
+        Backend::Direct(DirectBackend::connect(&cli.database_url, cli.redis_url).await?)
+    } else {
+        Backend::Api(ApiBackend::new(cli.api_url, cli.token)?)
+    };
+
+    match (cli.command, &backend) {
+        (Commands::Consumers { limit }, Backend::Api(api)) => {
+            let consumers = api
+                .send(
+                    reqwest::Method::GET,
+                    &format!("/consumers?limit={}", limit),
+                    None,
+                )
+                .await?;
+            print_json(&consumers)?;
+        }
+        (Commands::Consumers { limit }, Backend::Direct(direct)) => {
+            print_json(&direct.consumers(limit).await?)?;
+        }
+
+        (
+            Commands::Suspend {
+                consumer_id,
+                reason,
+            },
+            Backend::Api(api),
+        ) => {
+            api.send(
+                reqwest::Method::POST,
+                &format!("/consumers/{}/suspend", consumer_id),
+                Some(serde_json::json!({ "reason": reason })),
+            )
+            .await?;
+            println!("Consumer {} suspended", consumer_id);
+        }
+        (
+            Commands::Suspend {
+                consumer_id,
+                reason,
+            },
+            Backend::Direct(direct),
+        ) => {
+            sqlx::query(
+                r#"
+                INSERT INTO consumer_suspensions (consumer_id, reason)
+                VALUES ($1, $2)
+                ON CONFLICT (consumer_id) DO NOTHING
+                "#,
+            )
+            .bind(consumer_id)
+            .bind(reason)
+            .execute(&direct.db)
+            .await
+            .context("Failed to suspend consumer")?;
+            println!("Consumer {} suspended", consumer_id);
+        }
+
+        (Commands::Unsuspend { consumer_id }, Backend::Api(api)) => {
+            api.send(
+                reqwest::Method::DELETE,
+                &format!("/consumers/{}/suspend", consumer_id),
+                None,
+            )
+            .await?;
+            println!("Consumer {} unsuspended", consumer_id);
+        }
+        (Commands::Unsuspend { consumer_id }, Backend::Direct(direct)) => {
+            sqlx::query("DELETE FROM consumer_suspensions WHERE consumer_id = $1")
+                .bind(consumer_id)
+                .execute(&direct.db)
+                .await
+                .context("Failed to unsuspend consumer")?;
+            println!("Consumer {} unsuspended", consumer_id);
+        }
+
+        (
+            Commands::ResetQuota {
+                consumer_id,
+                service_id,
+            },
+            Backend::Api(api),
+        ) => {
+            api.send(
+                reqwest::Method::POST,
+                &format!(
+                    "/consumers/{}/services/{}/quota/reset",
+                    consumer_id, service_id
+                ),
+                None,
+            )
+            .await?;
+            println!(
+                "Quota reset for consumer {} on service {}",
+                consumer_id, service_id
+            );
+        }
+        (
+            Commands::ResetQuota {
+                consumer_id,
+                service_id,
+            },
+            Backend::Direct(direct),
+        ) => {
+            direct.reset_quota(consumer_id, service_id).await?;
+            println!(
+                "Quota reset for consumer {} on service {}",
+                consumer_id, service_id
+            );
+        }
+
+        (
+            Commands::ResetRateLimit {
+                consumer_id,
+                service_id,
+            },
+            Backend::Api(api),
+        ) => {
+            api.send(
+                reqwest::Method::POST,
+                &format!(
+                    "/consumers/{}/services/{}/rate-limit/reset",
+                    consumer_id, service_id
+                ),
+                None,
+            )
+            .await?;
+            println!(
+                "Rate limit reset for consumer {} on service {}",
+                consumer_id, service_id
+            );
+        }
+        (
+            Commands::ResetRateLimit {
+                consumer_id,
+                service_id,
+            },
+            Backend::Direct(direct),
+        ) => {
+            direct
+                .redis_del(format!("ratelimit:{}:{}", consumer_id, service_id))
+                .await?;
+            direct
+                .redis_del(format!("ratelimit:{}:{}:window", consumer_id, service_id))
+                .await?;
+            println!(
+                "Rate limit reset for consumer {} on service {}",
+                consumer_id, service_id
+            );
+        }
+
+        (Commands::RotateKey { key_id }, Backend::Api(api)) => {
+            let replacement = api
+                .send(
+                    reqwest::Method::POST,
+                    &format!("/keys/{}/rotate", key_id),
+                    None,
+                )
+                .await?;
+            print_json(&replacement)?;
+        }
+
+        (Commands::RunJob { job }, Backend::Api(api)) => {
+            let result = api
+                .send(
+                    reqwest::Method::POST,
+                    &format!("/jobs/{}", job.path()),
+                    None,
+                )
+                .await?;
+            print_json(&result)?;
+        }
+
+        (Commands::RotateKey { .. } | Commands::RunJob { .. }, Backend::Direct(_)) => {
+            anyhow::bail!("This command requires the admin API; run it without --direct");
+        }
+
+        (
+            Commands::Violations {
+                service_id,
+                limit,
+                follow,
+                interval,
+            },
+            backend,
+        ) => {
+            let violations = backend.fetch_violations(None, service_id, limit).await?;
+            let mut since = None;
+
+            // Newest first from the API; print oldest first like a log
+            if let Some(list) = violations.as_array() {
+                for violation in list.iter().rev() {
+                    print_violation(violation);
+                }
+                since = list
+                    .first()
+                    .and_then(|v| v["timestamp"].as_str())
+                    .map(str::to_string);
+            }
+
+            if !follow {
+                return Ok(());
+            }
+
+            loop {
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+
+                let since_ts = since
+                    .as_deref()
+                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                    .map(|dt| dt.with_timezone(&Utc));
+
+                let violations = backend
+                    .fetch_violations(since_ts, service_id, limit)
+                    .await?;
+                if let Some(list) = violations.as_array() {
+                    for violation in list.iter().rev() {
+                        print_violation(violation);
+                    }
+                    if let Some(newest) = list.first().and_then(|v| v["timestamp"].as_str()) {
+                        since = Some(newest.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
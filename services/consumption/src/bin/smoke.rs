@@ -0,0 +1,413 @@
+//! Post-deploy smoke tests for the consumption service
+//!
+//! Runs a declarative suite of HTTP checks against a deployed instance and
+//! exits non-zero if any fail, so it can gate a CD pipeline. The built-in
+//! suite (`smoke/default.json`) covers health, auth failure paths, a sandbox
+//! consume and the quota endpoint; `--suite` runs a different file.
+//!
+//! `${NAME}` in paths, headers and bodies is replaced from the environment.
+//! Checks that reference an unset variable are skipped.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SUITE: &str = include_str!("../../smoke/default.json");
+
+#[derive(Parser)]
+#[command(name = "smoke")]
+#[command(about = "Smoke-test a deployed LLM Marketplace consumption service", long_about = None)]
+#[command(version)]
+struct Cli {
+    /// Base URL of the deployed service
+    #[arg(long, env = "SMOKE_BASE_URL", default_value = "http://localhost:3000")]
+    base_url: String,
+
+    /// Suite file to run instead of the built-in suite
+    #[arg(long)]
+    suite: Option<PathBuf>,
+
+    /// Write results as JUnit XML to this path
+    #[arg(long)]
+    junit: Option<PathBuf>,
+
+    /// Per-check request timeout in seconds
+    #[arg(long, default_value_t = 10)]
+    timeout: u64,
+
+    /// Treat skipped checks as failures
+    #[arg(long)]
+    fail_on_skip: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Suite {
+    name: String,
+    checks: Vec<Check>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Check {
+    name: String,
+    #[serde(default = "default_method")]
+    method: String,
+    path: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Option<Value>,
+    expect: Expectation,
+}
+
+#[derive(Debug, Deserialize)]
+struct Expectation {
+    status: u16,
+    /// Dot-separated paths that must be present in the JSON response
+    #[serde(default)]
+    json_fields: Vec<String>,
+    /// Response headers that must be present
+    #[serde(default)]
+    headers: Vec<String>,
+    #[serde(default)]
+    body_contains: Option<String>,
+    #[serde(default)]
+    max_latency_ms: Option<u64>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+#[derive(Debug)]
+struct CheckResult {
+    name: String,
+    duration: Duration,
+    outcome: Outcome,
+}
+
+/// Replace `${NAME}` with its value; returns the first unset name on failure
+fn substitute(template: &str, vars: &impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        let value = vars(name).ok_or_else(|| name.to_string())?;
+
+        out.push_str(&rest[..start]);
+        out.push_str(&value);
+        rest = &rest[start + 3 + len..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn substitute_json(value: &Value, vars: &impl Fn(&str) -> Option<String>) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) => Value::String(substitute(s, vars)?),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_json(item, vars))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), substitute_json(v, vars)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Substituted path, headers and body of a check
+#[allow(clippy::type_complexity)]
+fn prepare(
+    check: &Check,
+    vars: &impl Fn(&str) -> Option<String>,
+) -> Result<(String, Vec<(String, String)>, Option<Value>), String> {
+    let path = substitute(&check.path, vars)?;
+    let headers = check
+        .headers
+        .iter()
+        .map(|(k, v)| Ok((k.clone(), substitute(v, vars)?)))
+        .collect::<Result<_, String>>()?;
+    let body = check.body.as_ref().map(|b| substitute_json(b, vars)).transpose()?;
+    Ok((path, headers, body))
+}
+
+/// Compare a response with the expectation; returns every mismatch
+fn evaluate(
+    expect: &Expectation,
+    status: u16,
+    headers: &HeaderMap,
+    body: &str,
+    latency: Duration,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if status != expect.status {
+        problems.push(format!(
+            "expected status {}, got {}: {}",
+            expect.status,
+            status,
+            body.chars().take(200).collect::<String>()
+        ));
+    }
+
+    for name in &expect.headers {
+        if !headers.contains_key(name.as_str()) {
+            problems.push(format!("missing header {}", name));
+        }
+    }
+
+    if let Some(needle) = &expect.body_contains {
+        if !body.contains(needle.as_str()) {
+            problems.push(format!("body does not contain {:?}", needle));
+        }
+    }
+
+    if !expect.json_fields.is_empty() {
+        match serde_json::from_str::<Value>(body) {
+            Ok(json) => {
+                for field in &expect.json_fields {
+                    let found = field
+                        .split('.')
+                        .try_fold(&json, |value, key| value.get(key))
+                        .is_some_and(|value| !value.is_null());
+                    if !found {
+                        problems.push(format!("missing field {}", field));
+                    }
+                }
+            }
+            Err(_) => problems.push("response is not JSON".to_string()),
+        }
+    }
+
+    if let Some(max) = expect.max_latency_ms {
+        if latency.as_millis() as u64 > max {
+            problems.push(format!("took {} ms (max {} ms)", latency.as_millis(), max));
+        }
+    }
+
+    problems
+}
+
+async fn run_check(client: &reqwest::Client, base_url: &str, check: &Check) -> CheckResult {
+    let vars = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let begin = Instant::now();
+    let result = |outcome| CheckResult {
+        name: check.name.clone(),
+        duration: begin.elapsed(),
+        outcome,
+    };
+
+    let (path, headers, body) = match prepare(check, &vars) {
+        Ok(prepared) => prepared,
+        Err(name) => return result(Outcome::Skipped(format!("{} is not set", name))),
+    };
+
+    let method = match reqwest::Method::from_bytes(check.method.to_uppercase().as_bytes()) {
+        Ok(method) => method,
+        Err(_) => return result(Outcome::Failed(format!("invalid method {}", check.method))),
+    };
+
+    let mut request = client.request(method, format!("{}{}", base_url, path));
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return result(Outcome::Failed(format!("request failed: {}", e))),
+    };
+    let status = response.status().as_u16();
+    let response_headers = response.headers().clone();
+    let text = response.text().await.unwrap_or_default();
+    let latency = begin.elapsed();
+
+    let problems = evaluate(&check.expect, status, &response_headers, &text, latency);
+    if problems.is_empty() {
+        result(Outcome::Passed)
+    } else {
+        result(Outcome::Failed(problems.join("; ")))
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn junit_report(suite: &str, results: &[CheckResult]) -> String {
+    let failures = results
+        .iter()
+        .filter(|r| matches!(r.outcome, Outcome::Failed(_)))
+        .count();
+    let skipped = results
+        .iter()
+        .filter(|r| matches!(r.outcome, Outcome::Skipped(_)))
+        .count();
+    let total: f64 = results.iter().map(|r| r.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites>\n  <testsuite name=\"smoke.{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        xml_escape(suite),
+        results.len(),
+        failures,
+        skipped,
+        total
+    ));
+
+    for result in results {
+        let open = format!(
+            "    <testcase classname=\"smoke.{}\" name=\"{}\" time=\"{:.3}\"",
+            xml_escape(suite),
+            xml_escape(&result.name),
+            result.duration.as_secs_f64()
+        );
+        match &result.outcome {
+            Outcome::Passed => xml.push_str(&format!("{}/>\n", open)),
+            Outcome::Failed(message) => xml.push_str(&format!(
+                "{}>\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                open,
+                xml_escape(message)
+            )),
+            Outcome::Skipped(message) => xml.push_str(&format!(
+                "{}>\n      <skipped message=\"{}\"/>\n    </testcase>\n",
+                open,
+                xml_escape(message)
+            )),
+        }
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let suite: Suite = match &cli.suite {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read suite {}", path.display()))?;
+            serde_json::from_str(&text)
+                .with_context(|| format!("Failed to parse suite {}", path.display()))?
+        }
+        None => serde_json::from_str(DEFAULT_SUITE).context("Failed to parse built-in suite")?,
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(cli.timeout))
+        .user_agent(concat!("marketplace-smoke/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let base_url = cli.base_url.trim_end_matches('/');
+
+    println!("Running {} checks from suite {} against {}", suite.checks.len(), suite.name, base_url);
+
+    let mut results = Vec::with_capacity(suite.checks.len());
+    for check in &suite.checks {
+        let result = run_check(&client, base_url, check).await;
+        let ms = result.duration.as_millis();
+        match &result.outcome {
+            Outcome::Passed => println!("PASS  {} ({} ms)", result.name, ms),
+            Outcome::Failed(message) => println!("FAIL  {} ({} ms): {}", result.name, ms, message),
+            Outcome::Skipped(message) => println!("SKIP  {}: {}", result.name, message),
+        }
+        results.push(result);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|r| matches!(r.outcome, Outcome::Failed(_)))
+        .count();
+    let skipped = results
+        .iter()
+        .filter(|r| matches!(r.outcome, Outcome::Skipped(_)))
+        .count();
+    println!(
+        "\n{} passed, {} failed, {} skipped",
+        results.len() - failed - skipped,
+        failed,
+        skipped
+    );
+
+    if let Some(path) = &cli.junit {
+        std::fs::write(path, junit_report(&suite.name, &results))
+            .with_context(|| format!("Failed to write JUnit report {}", path.display()))?;
+    }
+
+    if failed > 0 || (cli.fail_on_skip && skipped > 0) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute_reports_unset_variables() {
+        let vars = |name: &str| (name == "SERVICE").then(|| "abc".to_string());
+
+        assert_eq!(substitute("/quota/${SERVICE}", &vars), Ok("/quota/abc".to_string()));
+        assert_eq!(substitute("Bearer ${KEY}", &vars), Err("KEY".to_string()));
+        assert_eq!(substitute("/health", &vars), Ok("/health".to_string()));
+    }
+
+    #[test]
+    fn test_builtin_suite_parses_and_evaluates() {
+        let suite: Suite = serde_json::from_str(DEFAULT_SUITE).unwrap();
+        let consume = suite.checks.iter().find(|c| c.name == "sandbox consume").unwrap();
+
+        let mut headers = HeaderMap::new();
+        for name in ["x-usage-total-tokens", "x-cost-amount", "x-quota-remaining", "x-request-id"] {
+            headers.insert(name, "1".parse().unwrap());
+        }
+        let body = r#"{"request_id": "x", "usage": {"total_tokens": 3}, "cost": {"amount": 0.1}}"#;
+        assert!(evaluate(&consume.expect, 200, &headers, body, Duration::ZERO).is_empty());
+
+        let empty = HeaderMap::new();
+        let problems = evaluate(&consume.expect, 429, &empty, r#"{"usage": {}}"#, Duration::ZERO);
+        assert_eq!(problems.len(), 8);
+
+        let results = vec![CheckResult {
+            name: "a <b>".to_string(),
+            duration: Duration::from_millis(5),
+            outcome: Outcome::Failed("expected \"200\"".to_string()),
+        }];
+        let xml = junit_report(&suite.name, &results);
+        assert!(xml.contains("failures=\"1\""));
+        assert!(xml.contains("name=\"a &lt;b&gt;\""));
+        assert!(xml.contains("message=\"expected &quot;200&quot;\""));
+    }
+}
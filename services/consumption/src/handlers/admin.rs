@@ -0,0 +1,1660 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        ApiKeyResponse, ConsumerSummary, RateLimitAlgorithm, SLAViolation, SdkVersionUsage,
+        ServiceTier, TierLimits,
+    },
+    services::{
+        usage_insights, BalancingStrategy, BillingAnchor, ContentScanConfig,
+        CreateExperimentRequest, CredentialAuditEntry, CredentialMetadata, DeadLetter,
+        EndpointPool, EndpointStatus, InvoicedUsage, LimitRampup, OrganizationLocale,
+        PiiFilterConfig, PluginKind, PluginPin, PricingExperiment, QuotaCounters, QuotaLedgerEntry,
+        QuotaPeriod, RateLimitOverride, ReconciliationResult, RedisAuditReport, ResidencyPin,
+        ResponseCacheConfig, ResponsePipelineConfig, RoutingRule, ServiceHealthRecord,
+        SimulationReport, SimulationRequest, StoreCredentialRequest, VariantResult,
+    },
+    AppState, Result,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendRequest {
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViolationsQuery {
+    since: Option<DateTime<Utc>>,
+    service_id: Option<Uuid>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SdkVersionsQuery {
+    since: Option<DateTime<Utc>>,
+    consumer_id: Option<Uuid>,
+    sdk: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedisAuditQuery {
+    /// Run a fresh audit instead of returning the last one
+    #[serde(default)]
+    refresh: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    day: Option<NaiveDate>,
+    #[serde(default)]
+    discrepancies_only: bool,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuotaChangeRequest {
+    tokens: i64,
+    reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuotaLedgerQuery {
+    /// YYYY-MM, defaults to the current quota month
+    month: Option<String>,
+    #[serde(default)]
+    include_consumption: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuotaAdjustmentResponse {
+    pub used_tokens: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OverageGrantResponse {
+    pub granted_tokens: i64,
+}
+
+#[derive(Deserialize)]
+pub struct RotateCredentialRequest {
+    secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResidencyRequest {
+    region: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BillingAnchorRequest {
+    anchor_day: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BillingAnchorResponse {
+    pub organization_id: Uuid,
+    pub anchor_day: u32,
+    /// Period in progress; `end` is when quotas next reset
+    pub current_period: QuotaPeriod,
+    /// Period bridging the previous anchor, while it lasts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<QuotaPeriod>,
+}
+
+impl BillingAnchorResponse {
+    fn new(organization_id: Uuid, anchor: BillingAnchor) -> Self {
+        let current_period = anchor.period(Utc::now());
+        Self {
+            organization_id,
+            anchor_day: anchor.anchor_day,
+            current_period,
+            transition: anchor
+                .transition
+                .filter(|transition| *transition == current_period),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimitOverrideResponse {
+    pub consumer_id: Uuid,
+    pub service_id: Uuid,
+    #[serde(flatten)]
+    pub limits: RateLimitOverride,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LimitRampupResponse {
+    pub organization_id: Uuid,
+    #[serde(flatten)]
+    pub rampup: LimitRampup,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrganizationLocaleRequest {
+    /// Locale tag, e.g. `de-DE`
+    locale: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HealthWebhookRequest {
+    /// Provider endpoint notified of status changes; `null` clears it
+    webhook_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IngestKeyResponse {
+    pub service_id: Uuid,
+    /// HMAC-SHA256 key the provider signs usage batches with
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PinCohortRequest {
+    variant: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PublishPluginQuery {
+    kind: String,
+}
+
+/// `EnvFilter` directives, e.g. `info,consumption::services::quota_manager=debug`
+#[derive(Debug, Deserialize)]
+pub struct LogLevelRequest {
+    pub filter: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogLevelResponse {
+    pub filter: String,
+    /// Filter the process started with, restored by `DELETE`
+    pub initial: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobResponse {
+    pub job: String,
+    pub affected: Option<usize>,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+fn internal_error(message: &str, e: anyhow::Error) -> (StatusCode, String) {
+    error!(error = %e, "{}", message);
+    (StatusCode::INTERNAL_SERVER_ERROR, message.to_string())
+}
+
+/// Operator named in the optional `X-Admin-Actor` header, for audit trails
+fn admin_actor(headers: &HeaderMap) -> String {
+    headers
+        .get("X-Admin-Actor")
+        .and_then(|value| value.to_str().ok())
+        .filter(|actor| !actor.is_empty())
+        .map(|actor| format!("admin:{}", actor))
+        .unwrap_or_else(|| "admin".to_string())
+}
+
+fn no_credential() -> (StatusCode, String) {
+    (
+        StatusCode::NOT_FOUND,
+        "No provider credential for service".to_string(),
+    )
+}
+
+fn credential_error(message: &str, e: anyhow::Error) -> (StatusCode, String) {
+    let reason = e.to_string();
+    if reason.starts_with("Invalid") {
+        return (StatusCode::BAD_REQUEST, reason);
+    }
+    if reason.contains("not configured") {
+        return (StatusCode::SERVICE_UNAVAILABLE, reason);
+    }
+    internal_error(message, e)
+}
+
+/// List consumers with key counts and suspension state
+#[instrument(skip(state))]
+pub async fn list_consumers(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<ConsumerSummary>>> {
+    let consumers = state
+        .admin_service
+        .list_consumers(query.limit.clamp(1, 1000))
+        .await
+        .map_err(|e| internal_error("Failed to list consumers", e))?;
+
+    Ok(Json(consumers))
+}
+
+/// Suspend a consumer, rejecting all of their API keys
+#[instrument(skip(state, request))]
+pub async fn suspend_consumer(
+    State(state): State<AppState>,
+    Path(consumer_id): Path<Uuid>,
+    Json(request): Json<SuspendRequest>,
+) -> Result<StatusCode> {
+    let suspended = state
+        .admin_service
+        .suspend_consumer(consumer_id, request.reason.as_deref())
+        .await
+        .map_err(|e| internal_error("Failed to suspend consumer", e))?;
+
+    if !suspended {
+        return Err((
+            StatusCode::CONFLICT,
+            "Consumer already suspended".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lift a consumer suspension
+#[instrument(skip(state))]
+pub async fn unsuspend_consumer(
+    State(state): State<AppState>,
+    Path(consumer_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    let unsuspended = state
+        .admin_service
+        .unsuspend_consumer(consumer_id)
+        .await
+        .map_err(|e| internal_error("Failed to unsuspend consumer", e))?;
+
+    if !unsuspended {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Consumer is not suspended".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Raw Redis quota counters of a consumer/service pair, without redis-cli
+#[instrument(skip(state))]
+pub async fn get_quota_counters(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<QuotaCounters>> {
+    let counters = state
+        .quota_manager
+        .counters(consumer_id, service_id)
+        .await
+        .map_err(|e| internal_error("Failed to read quota counters", e))?;
+
+    Ok(Json(counters))
+}
+
+/// Reset the monthly quota counter for a consumer/service pair
+#[instrument(skip(state, headers))]
+pub async fn reset_quota(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
+) -> Result<StatusCode> {
+    state
+        .quota_manager
+        .reset_quota(consumer_id, service_id, &admin_actor(&headers))
+        .await
+        .map_err(|e| internal_error("Failed to reset quota", e))?;
+
+    info!(consumer_id = %consumer_id, service_id = %service_id, "Quota reset by admin");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn validate_quota_change(request: &QuotaChangeRequest) -> Result<()> {
+    if request.reason.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "reason is required".to_string()));
+    }
+    if request.tokens == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "tokens must not be zero".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Correct a consumer's used tokens for the month; negative refunds usage
+#[instrument(skip(state, headers))]
+pub async fn adjust_quota(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
+    Json(request): Json<QuotaChangeRequest>,
+) -> Result<Json<QuotaAdjustmentResponse>> {
+    validate_quota_change(&request)?;
+
+    let used_tokens = state
+        .quota_manager
+        .adjust_quota(
+            consumer_id,
+            service_id,
+            request.tokens,
+            &admin_actor(&headers),
+            request.reason.trim(),
+        )
+        .await
+        .map_err(|e| internal_error("Failed to adjust quota", e))?;
+
+    info!(
+        consumer_id = %consumer_id,
+        service_id = %service_id,
+        tokens = request.tokens,
+        "Quota adjusted by admin"
+    );
+
+    Ok(Json(QuotaAdjustmentResponse { used_tokens }))
+}
+
+/// Grant tokens on top of the tier quota until the next monthly reset
+#[instrument(skip(state, headers))]
+pub async fn grant_quota_overage(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
+    Json(request): Json<QuotaChangeRequest>,
+) -> Result<Json<OverageGrantResponse>> {
+    validate_quota_change(&request)?;
+    if request.tokens < 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "tokens must be positive".to_string(),
+        ));
+    }
+
+    let granted_tokens = state
+        .quota_manager
+        .grant_overage(
+            consumer_id,
+            service_id,
+            request.tokens,
+            &admin_actor(&headers),
+            request.reason.trim(),
+        )
+        .await
+        .map_err(|e| internal_error("Failed to grant quota overage", e))?;
+
+    info!(
+        consumer_id = %consumer_id,
+        service_id = %service_id,
+        tokens = request.tokens,
+        "Quota overage granted by admin"
+    );
+
+    Ok(Json(OverageGrantResponse { granted_tokens }))
+}
+
+/// Quota ledger of a consumer/service pair for a month, oldest first, with
+/// the balance after each event. Resets, adjustments and overage grants
+/// only, unless `include_consumption` is set.
+#[instrument(skip(state))]
+pub async fn quota_ledger(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<QuotaLedgerQuery>,
+) -> Result<Json<Vec<QuotaLedgerEntry>>> {
+    if let Some(month) = &query.month {
+        if NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_err() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "month must be formatted as YYYY-MM".to_string(),
+            ));
+        }
+    }
+
+    let entries = state
+        .quota_manager
+        .ledger_entries(
+            consumer_id,
+            service_id,
+            query.month.as_deref(),
+            query.include_consumption,
+        )
+        .await
+        .map_err(|e| internal_error("Failed to load quota ledger", e))?;
+
+    Ok(Json(entries))
+}
+
+/// Reset the rate limit window for a consumer/service pair
+#[instrument(skip(state))]
+pub async fn reset_rate_limit(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    state
+        .rate_limiter
+        .reset_rate_limit(consumer_id, service_id)
+        .await
+        .map_err(|e| internal_error("Failed to reset rate limit", e))?;
+
+    info!(consumer_id = %consumer_id, service_id = %service_id, "Rate limit reset by admin");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn no_rate_limit_override() -> (StatusCode, String) {
+    (
+        StatusCode::NOT_FOUND,
+        "Consumer has no rate limit override for this service".to_string(),
+    )
+}
+
+/// Negotiated rate limit of a consumer on a service
+#[instrument(skip(state))]
+pub async fn get_rate_limit_override(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<RateLimitOverrideResponse>> {
+    let limits = state
+        .rate_limit_overrides
+        .get(consumer_id, service_id)
+        .await
+        .map_err(|e| internal_error("Failed to get rate limit override", e))?
+        .ok_or_else(no_rate_limit_override)?;
+
+    Ok(Json(RateLimitOverrideResponse {
+        consumer_id,
+        service_id,
+        limits,
+    }))
+}
+
+/// Give a consumer a negotiated rate limit and burst capacity on a service,
+/// in place of their tier's
+#[instrument(skip(state))]
+pub async fn set_rate_limit_override(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+    Json(limits): Json<RateLimitOverride>,
+) -> Result<Json<RateLimitOverrideResponse>> {
+    limits
+        .validate()
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    state
+        .rate_limit_overrides
+        .set(consumer_id, service_id, limits)
+        .await
+        .map_err(|e| internal_error("Failed to set rate limit override", e))?;
+
+    Ok(Json(RateLimitOverrideResponse {
+        consumer_id,
+        service_id,
+        limits,
+    }))
+}
+
+/// Put a consumer back on their tier's rate limit
+#[instrument(skip(state))]
+pub async fn delete_rate_limit_override(
+    State(state): State<AppState>,
+    Path((consumer_id, service_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    let deleted = state
+        .rate_limit_overrides
+        .remove(consumer_id, service_id)
+        .await
+        .map_err(|e| internal_error("Failed to delete rate limit override", e))?;
+
+    if !deleted {
+        return Err(no_rate_limit_override());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Rotate an API key on behalf of its consumer
+#[instrument(skip(state))]
+pub async fn rotate_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<Uuid>,
+) -> Result<Json<ApiKeyResponse>> {
+    let rotated = state
+        .api_key_manager
+        .rotate_key(key_id, None, chrono::Duration::zero())
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("not found") {
+                return (StatusCode::NOT_FOUND, e.to_string());
+            }
+            internal_error("Failed to rotate API key", e)
+        })?;
+
+    Ok(Json(rotated.key))
+}
+
+/// Run a background job immediately
+#[instrument(skip(state))]
+pub async fn trigger_job(
+    State(state): State<AppState>,
+    Path(job): Path<String>,
+) -> Result<Json<JobResponse>> {
+    info!(job = %job, "Job triggered by admin");
+
+    let affected = match job.as_str() {
+        "sla-monitor" => state.sla_monitor.monitor_all_services().await.map(|_| None),
+        "persist-quotas" => state.quota_manager.persist_quotas().await.map(|_| None),
+        "rebuild-quotas" => state.quota_manager.rebuild_quotas().await.map(Some),
+        "rollup-quota-usage" => state
+            .quota_manager
+            .rollup_usage(Utc::now() - Duration::hours(2))
+            .await
+            .map(|rows| Some(rows as usize)),
+        "rollup-usage" => state
+            .usage_rollups
+            .rollup(Utc::now() - Duration::hours(2))
+            .await
+            .map(|rows| Some(rows as usize)),
+        "generate-sla-reports" => state.sla_reports.generate_previous_month().await.map(Some),
+        "generate-statements" => state.statements.generate_previous_month().await.map(Some),
+        "resolve-incidents" => state
+            .alert_manager
+            .resolve_stale_incidents()
+            .await
+            .map(Some),
+        "reconcile-metering" => state
+            .metering_reconciler
+            .reconcile_yesterday()
+            .await
+            .map(|results| Some(results.len())),
+        "enforce-residency" => state
+            .data_residency
+            .enforce_residency()
+            .await
+            .map(|moved| Some(moved as usize)),
+        "maintain-usage-partitions" => state
+            .usage_partitions
+            .maintain()
+            .await
+            .map(|report| Some(report.changes())),
+        "sweep-job-queue" => state.job_queue.sweep().await.map(Some),
+        "compute-usage-insights" => state
+            .usage_insights
+            .compute_all(usage_insights::DEFAULT_WEEKS)
+            .await
+            .map(Some),
+        "audit-redis" => state
+            .redis_audit
+            .run()
+            .await
+            .map(|report| Some(report.warnings())),
+        _ => {
+            return Err((StatusCode::NOT_FOUND, format!("Unknown job: {}", job)));
+        }
+    }
+    .map_err(|e| internal_error("Job failed", e))?;
+
+    Ok(Json(JobResponse { job, affected }))
+}
+
+/// Recent SLA violations across services, newest first
+#[instrument(skip(state))]
+pub async fn list_violations(
+    State(state): State<AppState>,
+    Query(query): Query<ViolationsQuery>,
+) -> Result<Json<Vec<SLAViolation>>> {
+    let violations = state
+        .admin_service
+        .recent_violations(query.since, query.service_id, query.limit.clamp(1, 1000))
+        .await
+        .map_err(|e| internal_error("Failed to list SLA violations", e))?;
+
+    Ok(Json(violations))
+}
+
+/// SDK version distribution per consumer; defaults to the last 30 days
+#[instrument(skip(state))]
+pub async fn list_sdk_versions(
+    State(state): State<AppState>,
+    Query(query): Query<SdkVersionsQuery>,
+) -> Result<Json<Vec<SdkVersionUsage>>> {
+    let since = query
+        .since
+        .unwrap_or_else(|| Utc::now() - Duration::days(30));
+    let usage = state
+        .admin_service
+        .sdk_versions(
+            since,
+            query.consumer_id,
+            query.sdk.as_deref(),
+            query.limit.clamp(1, 1000),
+        )
+        .await
+        .map_err(|e| internal_error("Failed to aggregate SDK versions", e))?;
+
+    Ok(Json(usage))
+}
+
+/// Redis keyspace audit per key pattern; runs one if none has run yet
+#[instrument(skip(state))]
+pub async fn get_redis_audit(
+    State(state): State<AppState>,
+    Query(query): Query<RedisAuditQuery>,
+) -> Result<Json<RedisAuditReport>> {
+    if !query.refresh {
+        if let Some(report) = state.redis_audit.last_report() {
+            return Ok(Json(report));
+        }
+    }
+
+    let report = state
+        .redis_audit
+        .run()
+        .await
+        .map_err(|e| internal_error("Failed to audit Redis keyspace", e))?;
+
+    Ok(Json(report))
+}
+
+/// Metering reconciliation status, newest day first
+#[instrument(skip(state))]
+pub async fn list_reconciliations(
+    State(state): State<AppState>,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<Json<Vec<ReconciliationResult>>> {
+    let results = state
+        .metering_reconciler
+        .list_results(
+            query.day,
+            query.discrepancies_only,
+            query.limit.clamp(1, 1000),
+        )
+        .await
+        .map_err(|e| internal_error("Failed to list reconciliation results", e))?;
+
+    Ok(Json(results))
+}
+
+/// Record invoiced token totals for a day and re-run its reconciliation
+#[instrument(skip(state, entries))]
+pub async fn record_invoiced_usage(
+    State(state): State<AppState>,
+    Path(day): Path<NaiveDate>,
+    Json(entries): Json<Vec<InvoicedUsage>>,
+) -> Result<Json<Vec<ReconciliationResult>>> {
+    state
+        .metering_reconciler
+        .record_invoiced_usage(day, &entries)
+        .await
+        .map_err(|e| internal_error("Failed to record invoiced usage", e))?;
+
+    let results = state
+        .metering_reconciler
+        .reconcile_day(day)
+        .await
+        .map_err(|e| internal_error("Failed to reconcile metering", e))?;
+
+    Ok(Json(results))
+}
+
+/// Publish a Wasm plugin version (raw module as the request body)
+#[instrument(skip(state, wasm))]
+pub async fn publish_plugin(
+    State(state): State<AppState>,
+    Path((name, version)): Path<(String, String)>,
+    Query(query): Query<PublishPluginQuery>,
+    wasm: Bytes,
+) -> Result<StatusCode> {
+    let kind = PluginKind::parse(&query.kind).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Unknown plugin kind: {}", query.kind),
+        )
+    })?;
+
+    state
+        .plugin_runtime
+        .publish_plugin(&name, &version, kind, &wasm)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("already published") {
+                return (StatusCode::CONFLICT, e.to_string());
+            }
+            (StatusCode::BAD_REQUEST, format!("{:#}", e))
+        })?;
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Plugins pinned for a service
+#[instrument(skip(state))]
+pub async fn get_service_plugins(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<Vec<PluginPin>>> {
+    let pins = state
+        .plugin_runtime
+        .service_plugins(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to load service plugins", e))?;
+
+    Ok(Json(pins))
+}
+
+/// Replace the plugin versions pinned for a service
+#[instrument(skip(state, pins))]
+pub async fn set_service_plugins(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(pins): Json<Vec<PluginPin>>,
+) -> Result<Json<Vec<PluginPin>>> {
+    state
+        .plugin_runtime
+        .set_service_plugins(service_id, pins.clone())
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("not found") || e.to_string().contains("not published") {
+                return (StatusCode::NOT_FOUND, e.to_string());
+            }
+            internal_error("Failed to update service plugins", e)
+        })?;
+
+    Ok(Json(pins))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RateLimitAlgorithmConfig {
+    algorithm: RateLimitAlgorithm,
+}
+
+/// Algorithm a service's rate limit is enforced with
+#[instrument(skip(state))]
+pub async fn get_rate_limit_algorithm(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<RateLimitAlgorithmConfig>> {
+    let algorithm = state
+        .rate_limiter
+        .algorithm(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to load rate limit algorithm", e))?;
+
+    Ok(Json(RateLimitAlgorithmConfig { algorithm }))
+}
+
+/// Switch a service between the token bucket and the sliding window; takes
+/// effect on all replicas within 30 seconds
+#[instrument(skip(state))]
+pub async fn set_rate_limit_algorithm(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(config): Json<RateLimitAlgorithmConfig>,
+) -> Result<Json<RateLimitAlgorithmConfig>> {
+    state
+        .rate_limiter
+        .set_algorithm(service_id, config.algorithm)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("not found") {
+                return (StatusCode::NOT_FOUND, e.to_string());
+            }
+            internal_error("Failed to update rate limit algorithm", e)
+        })?;
+
+    Ok(Json(config))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceEndpoints {
+    strategy: BalancingStrategy,
+    endpoints: Vec<EndpointStatus>,
+}
+
+/// A service's endpoints, with this replica's view of their circuits and
+/// latency
+#[instrument(skip(state))]
+pub async fn get_service_endpoints(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<ServiceEndpoints>> {
+    let (pool, endpoints) = state
+        .endpoint_balancer
+        .statuses(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to load service endpoints", e))?;
+
+    Ok(Json(ServiceEndpoints {
+        strategy: pool.strategy,
+        endpoints,
+    }))
+}
+
+/// Replace a service's endpoints and balancing strategy; an empty list
+/// routes to the service's own endpoint again
+#[instrument(skip(state))]
+pub async fn set_service_endpoints(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(pool): Json<EndpointPool>,
+) -> Result<Json<EndpointPool>> {
+    pool.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    state
+        .endpoint_balancer
+        .set_pool(service_id, pool.clone())
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("not found") {
+                return (StatusCode::NOT_FOUND, e.to_string());
+            }
+            internal_error("Failed to update service endpoints", e)
+        })?;
+
+    Ok(Json(pool))
+}
+
+/// Routing rules for a service, in evaluation order
+#[instrument(skip(state))]
+pub async fn list_routing_rules(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<Vec<RoutingRule>>> {
+    let rules = state
+        .routing_rules
+        .list_rules(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to list routing rules", e))?;
+
+    Ok(Json(rules))
+}
+
+/// Replace a service's routing rules; takes effect on all replicas within seconds
+#[instrument(skip(state, rules))]
+pub async fn replace_routing_rules(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(rules): Json<Vec<RoutingRule>>,
+) -> Result<Json<Vec<RoutingRule>>> {
+    state
+        .routing_rules
+        .replace_rules(service_id, &rules)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("does not compile") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            internal_error("Failed to update routing rules", e)
+        })?;
+
+    Ok(Json(rules))
+}
+
+/// Built-in PII filter configuration for a service
+#[instrument(skip(state))]
+pub async fn get_pii_filter(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<PiiFilterConfig>> {
+    let config = state
+        .pii_filter
+        .get_config(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to get PII filter", e))?;
+
+    Ok(Json(config))
+}
+
+/// Set a service's PII filter; takes effect on all replicas within seconds
+#[instrument(skip(state))]
+pub async fn set_pii_filter(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(config): Json<PiiFilterConfig>,
+) -> Result<Json<PiiFilterConfig>> {
+    state
+        .pii_filter
+        .set_config(service_id, &config)
+        .await
+        .map_err(|e| internal_error("Failed to update PII filter", e))?;
+
+    Ok(Json(config))
+}
+
+/// LLM-Shield content scanning configuration for a service
+#[instrument(skip(state))]
+pub async fn get_content_scan(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<ContentScanConfig>> {
+    let config = state
+        .content_scanner
+        .get_config(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to get content scanning", e))?;
+
+    Ok(Json(config))
+}
+
+/// Set a service's content scanning; takes effect on all replicas within seconds
+#[instrument(skip(state))]
+pub async fn set_content_scan(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(config): Json<ContentScanConfig>,
+) -> Result<Json<ContentScanConfig>> {
+    state
+        .content_scanner
+        .set_config(service_id, &config)
+        .await
+        .map_err(|e| internal_error("Failed to update content scanning", e))?;
+
+    Ok(Json(config))
+}
+
+/// Response pipeline configuration for a service
+#[instrument(skip(state))]
+pub async fn get_response_pipeline(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<ResponsePipelineConfig>> {
+    let config = state
+        .response_pipeline
+        .get_config(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to load response pipeline", e))?;
+
+    Ok(Json(config))
+}
+
+/// Set a service's response pipeline; takes effect on all replicas within seconds
+#[instrument(skip(state))]
+pub async fn set_response_pipeline(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(config): Json<ResponsePipelineConfig>,
+) -> Result<Json<ResponsePipelineConfig>> {
+    state
+        .response_pipeline
+        .set_config(service_id, &config)
+        .await
+        .map_err(|e| {
+            if e.to_string().starts_with("Invalid") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            internal_error("Failed to update response pipeline", e)
+        })?;
+
+    Ok(Json(config))
+}
+
+/// Response cache settings for a service
+#[instrument(skip(state))]
+pub async fn get_response_cache(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<ResponseCacheConfig>> {
+    let config = state
+        .response_cache
+        .get_config(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to load response cache", e))?;
+
+    Ok(Json(config))
+}
+
+/// Turn a service's response cache on or off or change its TTL; responses
+/// cached so far are dropped
+#[instrument(skip(state))]
+pub async fn set_response_cache(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(config): Json<ResponseCacheConfig>,
+) -> Result<Json<ResponseCacheConfig>> {
+    config
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    state
+        .response_cache
+        .set_config(service_id, &config)
+        .await
+        .map_err(|e| internal_error("Failed to update response cache", e))?;
+
+    Ok(Json(config))
+}
+
+/// Drop a service's cached responses, e.g. after the provider changed its
+/// model behind the same endpoint
+#[instrument(skip(state))]
+pub async fn purge_response_cache(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let purged = state
+        .response_cache
+        .purge(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to purge response cache", e))?;
+
+    Ok(Json(serde_json::json!({ "purged": purged })))
+}
+
+/// Provider credential metadata for a service; the secret is never returned
+#[instrument(skip(state))]
+pub async fn get_provider_credential(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<CredentialMetadata>> {
+    let metadata = state
+        .credential_vault
+        .metadata(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to load provider credential", e))?
+        .ok_or_else(no_credential)?;
+
+    Ok(Json(metadata))
+}
+
+/// Store or replace a service's provider credential
+#[instrument(skip(state, headers, request))]
+pub async fn store_provider_credential(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(request): Json<StoreCredentialRequest>,
+) -> Result<Json<CredentialMetadata>> {
+    let metadata = state
+        .credential_vault
+        .store(service_id, &request, &admin_actor(&headers))
+        .await
+        .map_err(|e| credential_error("Failed to store provider credential", e))?;
+
+    Ok(Json(metadata))
+}
+
+/// Swap in a new secret for a service's existing provider credential
+#[instrument(skip(state, headers, request))]
+pub async fn rotate_provider_credential(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(request): Json<RotateCredentialRequest>,
+) -> Result<Json<CredentialMetadata>> {
+    let metadata = state
+        .credential_vault
+        .rotate(service_id, &request.secret, &admin_actor(&headers))
+        .await
+        .map_err(|e| credential_error("Failed to rotate provider credential", e))?
+        .ok_or_else(no_credential)?;
+
+    Ok(Json(metadata))
+}
+
+/// Delete a service's provider credential
+#[instrument(skip(state, headers))]
+pub async fn delete_provider_credential(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<StatusCode> {
+    let deleted = state
+        .credential_vault
+        .delete(service_id, &admin_actor(&headers))
+        .await
+        .map_err(|e| internal_error("Failed to delete provider credential", e))?;
+
+    if !deleted {
+        return Err(no_credential());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Audit trail of a service's provider credential, newest first
+#[instrument(skip(state))]
+pub async fn provider_credential_audit(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<CredentialAuditEntry>>> {
+    let entries = state
+        .credential_vault
+        .audit_log(service_id, query.limit.clamp(1, 1000))
+        .await
+        .map_err(|e| internal_error("Failed to load provider credential audit log", e))?;
+
+    Ok(Json(entries))
+}
+
+/// Stored upstream health of every service that changed status or has a
+/// webhook; services not listed are active
+#[instrument(skip(state))]
+pub async fn list_service_health(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ServiceHealthRecord>>> {
+    let records = state
+        .service_health
+        .list()
+        .await
+        .map_err(|e| internal_error("Failed to load service health", e))?;
+
+    Ok(Json(records))
+}
+
+/// Set or clear the provider webhook notified of a service's status changes
+#[instrument(skip(state))]
+pub async fn set_service_health_webhook(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(request): Json<HealthWebhookRequest>,
+) -> Result<Json<ServiceHealthRecord>> {
+    if let Some(url) = &request.webhook_url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid webhook URL: {}", url),
+            ));
+        }
+    }
+
+    let record = state
+        .service_health
+        .set_webhook(service_id, request.webhook_url)
+        .await
+        .map_err(|e| internal_error("Failed to store service health webhook", e))?;
+
+    Ok(Json(record))
+}
+
+/// Key the service's provider signs ingested usage batches with
+#[instrument(skip(state))]
+pub async fn get_ingest_key(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<IngestKeyResponse>> {
+    if !state.usage_ingestor.enabled() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Usage ingestion is not enabled".to_string(),
+        ));
+    }
+
+    let key = state
+        .usage_ingestor
+        .service_key(service_id)
+        .map_err(|e| internal_error("Failed to derive ingest key", e))?;
+
+    Ok(Json(IngestKeyResponse { service_id, key }))
+}
+
+/// Current limits of every tier
+#[instrument(skip(state))]
+pub async fn list_tiers(
+    State(state): State<AppState>,
+) -> Result<Json<BTreeMap<&'static str, TierLimits>>> {
+    Ok(Json(state.tier_catalog.all()))
+}
+
+/// Set a tier's limits; other replicas pick them up at their next reload
+#[instrument(skip(state))]
+pub async fn set_tier_limits(
+    State(state): State<AppState>,
+    Path(tier): Path<String>,
+    Json(limits): Json<TierLimits>,
+) -> Result<Json<TierLimits>> {
+    let tier = ServiceTier::from_name(&tier)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Unknown tier: {}", tier)))?;
+    limits
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    state
+        .tier_catalog
+        .set_limits(&tier, &limits)
+        .await
+        .map_err(|e| internal_error("Failed to update tier limits", e))?;
+
+    Ok(Json(limits))
+}
+
+/// Replay historical traffic against hypothetical tier limits
+#[instrument(skip(state))]
+pub async fn simulate_rate_limits(
+    State(state): State<AppState>,
+    Json(request): Json<SimulationRequest>,
+) -> Result<Json<SimulationReport>> {
+    let report = state
+        .rate_limit_simulator
+        .simulate(&request)
+        .await
+        .map_err(|e| {
+            if e.to_string().starts_with("Invalid simulation") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            internal_error("Rate limit simulation failed", e)
+        })?;
+
+    Ok(Json(report))
+}
+
+/// Pricing experiments of a service, newest first
+#[instrument(skip(state))]
+pub async fn list_pricing_experiments(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+) -> Result<Json<Vec<PricingExperiment>>> {
+    let experiments = state
+        .pricing_experiments
+        .list(service_id)
+        .await
+        .map_err(|e| internal_error("Failed to list pricing experiments", e))?;
+
+    Ok(Json(experiments))
+}
+
+/// Start a pricing experiment; takes effect on all replicas within seconds
+#[instrument(skip(state, request))]
+pub async fn create_pricing_experiment(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Json(request): Json<CreateExperimentRequest>,
+) -> Result<(StatusCode, Json<PricingExperiment>)> {
+    let experiment = state
+        .pricing_experiments
+        .create(service_id, &request)
+        .await
+        .map_err(|e| {
+            if e.to_string().starts_with("Invalid experiment") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            if e.to_string().contains("already has a running") {
+                return (StatusCode::CONFLICT, e.to_string());
+            }
+            internal_error("Failed to create pricing experiment", e)
+        })?;
+
+    Ok((StatusCode::CREATED, Json(experiment)))
+}
+
+/// Stop a pricing experiment, returning its consumers to default pricing
+#[instrument(skip(state))]
+pub async fn stop_pricing_experiment(
+    State(state): State<AppState>,
+    Path(experiment_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    let stopped = state
+        .pricing_experiments
+        .stop(experiment_id)
+        .await
+        .map_err(|e| internal_error("Failed to stop pricing experiment", e))?;
+
+    if !stopped {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Running pricing experiment not found".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Pin a consumer to a variant of a running pricing experiment
+#[instrument(skip(state))]
+pub async fn pin_pricing_cohort(
+    State(state): State<AppState>,
+    Path((experiment_id, consumer_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<PinCohortRequest>,
+) -> Result<StatusCode> {
+    state
+        .pricing_experiments
+        .pin_consumer(experiment_id, consumer_id, &request.variant)
+        .await
+        .map_err(|e| {
+            if e.to_string().starts_with("Invalid experiment") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            if e.to_string().contains("not found") {
+                return (StatusCode::NOT_FOUND, e.to_string());
+            }
+            internal_error("Failed to pin consumer to pricing variant", e)
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Requests, tokens and revenue per variant of a pricing experiment
+#[instrument(skip(state))]
+pub async fn pricing_experiment_results(
+    State(state): State<AppState>,
+    Path(experiment_id): Path<Uuid>,
+) -> Result<Json<Vec<VariantResult>>> {
+    let results = state
+        .pricing_experiments
+        .results(experiment_id)
+        .await
+        .map_err(|e| internal_error("Failed to load pricing experiment results", e))?;
+
+    Ok(Json(results))
+}
+
+/// Jobs that exhausted their retries, newest first
+#[instrument(skip(state))]
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<DeadLetter>>> {
+    let dead_letters = state
+        .job_queue
+        .dead_letters(query.limit.clamp(1, 1000))
+        .await
+        .map_err(|e| internal_error("Failed to list dead letters", e))?;
+
+    Ok(Json(dead_letters))
+}
+
+/// Put a dead-lettered job back on the queue with fresh attempts
+#[instrument(skip(state))]
+pub async fn requeue_dead_letter(
+    State(state): State<AppState>,
+    Path(dead_letter_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    let requeued = state
+        .job_queue
+        .requeue_dead_letter(dead_letter_id)
+        .await
+        .map_err(|e| internal_error("Failed to requeue job", e))?;
+
+    if !requeued {
+        return Err((StatusCode::NOT_FOUND, "Dead letter not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Residency pin for an organization
+#[instrument(skip(state))]
+pub async fn get_residency_pin(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Json<ResidencyPin>> {
+    let pin = state
+        .data_residency
+        .get_pin(organization_id)
+        .await
+        .map_err(|e| internal_error("Failed to get residency pin", e))?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Organization {} has no residency pin", organization_id),
+            )
+        })?;
+
+    Ok(Json(pin))
+}
+
+/// Pin an organization's usage and audit data to a region, moving existing rows
+#[instrument(skip(state))]
+pub async fn pin_organization(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+    Json(request): Json<ResidencyRequest>,
+) -> Result<Json<ResidencyPin>> {
+    let pin = state
+        .data_residency
+        .pin_organization(organization_id, &request.region)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("not configured") {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "{}; available regions: {}",
+                        e,
+                        state.data_residency.regions().join(", ")
+                    ),
+                );
+            }
+            internal_error("Failed to pin organization", e)
+        })?;
+
+    Ok(Json(pin))
+}
+
+/// Billing anchor of an organization; calendar months unless one was set
+#[instrument(skip(state))]
+pub async fn get_billing_anchor(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Json<BillingAnchorResponse>> {
+    let anchor = state
+        .quota_manager
+        .billing_anchors()
+        .get(organization_id)
+        .await
+        .map_err(|e| internal_error("Failed to get billing anchor", e))?
+        .unwrap_or_default();
+
+    Ok(Json(BillingAnchorResponse::new(organization_id, anchor)))
+}
+
+/// Move an organization's quota resets to a day of the month; the period in
+/// progress runs on to the first reset on the new day
+#[instrument(skip(state))]
+pub async fn set_billing_anchor(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+    Json(request): Json<BillingAnchorRequest>,
+) -> Result<Json<BillingAnchorResponse>> {
+    if !(1..=31).contains(&request.anchor_day) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "anchor_day must be between 1 and 31".to_string(),
+        ));
+    }
+
+    let anchor = state
+        .quota_manager
+        .set_billing_anchor(organization_id, request.anchor_day)
+        .await
+        .map_err(|e| internal_error("Failed to set billing anchor", e))?;
+
+    Ok(Json(BillingAnchorResponse::new(organization_id, anchor)))
+}
+
+fn no_limit_rampup() -> (StatusCode, String) {
+    (
+        StatusCode::NOT_FOUND,
+        "Organization has no limit ramp-up".to_string(),
+    )
+}
+
+/// Limit ramp-up of an organization's new consumers
+#[instrument(skip(state))]
+pub async fn get_limit_rampup(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Json<LimitRampupResponse>> {
+    let rampup = state
+        .quota_manager
+        .limit_rampups()
+        .get(organization_id)
+        .await
+        .map_err(|e| internal_error("Failed to get limit ramp-up", e))?
+        .ok_or_else(no_limit_rampup)?;
+
+    Ok(Json(LimitRampupResponse {
+        organization_id,
+        rampup,
+    }))
+}
+
+/// Start an organization's new consumers at a percentage of their tier
+/// limits, growing to the full limits over a number of days
+#[instrument(skip(state))]
+pub async fn set_limit_rampup(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+    Json(rampup): Json<LimitRampup>,
+) -> Result<Json<LimitRampupResponse>> {
+    rampup
+        .validate()
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    state
+        .quota_manager
+        .limit_rampups()
+        .set(organization_id, rampup)
+        .await
+        .map_err(|e| internal_error("Failed to set limit ramp-up", e))?;
+
+    Ok(Json(LimitRampupResponse {
+        organization_id,
+        rampup,
+    }))
+}
+
+/// Give an organization's consumers their full tier limits
+#[instrument(skip(state))]
+pub async fn delete_limit_rampup(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    let deleted = state
+        .quota_manager
+        .limit_rampups()
+        .remove(organization_id)
+        .await
+        .map_err(|e| internal_error("Failed to delete limit ramp-up", e))?;
+
+    if !deleted {
+        return Err(no_limit_rampup());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Locale an organization's reports and statements are rendered in
+#[instrument(skip(state))]
+pub async fn get_organization_locale(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Json<OrganizationLocale>> {
+    let locale = state
+        .report_locales
+        .get(organization_id)
+        .await
+        .map_err(|e| internal_error("Failed to get organization locale", e))?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!(
+                    "Organization has no locale; reports use {}",
+                    state.report_locales.locales().default_locale().tag
+                ),
+            )
+        })?;
+
+    Ok(Json(locale))
+}
+
+/// Render an organization's reports and statements in a locale
+#[instrument(skip(state))]
+pub async fn set_organization_locale(
+    State(state): State<AppState>,
+    Path(organization_id): Path<Uuid>,
+    Json(request): Json<OrganizationLocaleRequest>,
+) -> Result<Json<OrganizationLocale>> {
+    let locales = state.report_locales.locales();
+    if locales.get(&request.locale).is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Unknown locale: {} (available: {})",
+                request.locale,
+                locales.tags().join(", ")
+            ),
+        ));
+    }
+
+    let locale = state
+        .report_locales
+        .set(organization_id, &request.locale)
+        .await
+        .map_err(|e| internal_error("Failed to set organization locale", e))?;
+
+    Ok(Json(locale))
+}
+
+/// Log filter of this replica
+#[instrument(skip(state))]
+pub async fn get_log_level(State(state): State<AppState>) -> Result<Json<LogLevelResponse>> {
+    Ok(Json(LogLevelResponse {
+        filter: state.log_filter.current(),
+        initial: state.log_filter.initial().to_string(),
+        previous: None,
+    }))
+}
+
+/// Change this replica's log filter until restart or reset; other replicas
+/// keep theirs
+#[instrument(skip(state, headers))]
+pub async fn set_log_level(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<LogLevelRequest>,
+) -> Result<Json<LogLevelResponse>> {
+    let previous = state
+        .log_filter
+        .set(&request.filter)
+        .map_err(log_filter_error)?;
+    info!(actor = %admin_actor(&headers), filter = %request.filter, "Log filter changed");
+
+    Ok(Json(LogLevelResponse {
+        filter: state.log_filter.current(),
+        initial: state.log_filter.initial().to_string(),
+        previous: Some(previous),
+    }))
+}
+
+/// Restore the log filter this replica started with
+#[instrument(skip(state, headers))]
+pub async fn reset_log_level(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<LogLevelResponse>> {
+    let previous = state.log_filter.reset().map_err(log_filter_error)?;
+    info!(actor = %admin_actor(&headers), "Log filter reset");
+
+    Ok(Json(LogLevelResponse {
+        filter: state.log_filter.current(),
+        initial: state.log_filter.initial().to_string(),
+        previous: Some(previous),
+    }))
+}
+
+fn log_filter_error(e: llm_infra::errors::InfraError) -> (StatusCode, String) {
+    let status =
+        StatusCode::from_u16(e.status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, e.message)
+}
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{
+    services::{AnalyticsOutbox, OutboxPage, UsageInsights},
+    AppState, Result,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsEventsQuery {
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    since: Option<String>,
+    /// Comma-separated list of event types to include
+    event_types: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+/// Replay analytics events from the durable outbox
+#[instrument(skip(state))]
+pub async fn get_analytics_events(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsEventsQuery>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> Result<Json<OutboxPage>> {
+    let event_types: Vec<String> = query
+        .event_types
+        .as_deref()
+        .map(|types| {
+            types
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let after_id = query
+        .since
+        .as_deref()
+        .map(AnalyticsOutbox::parse_cursor)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let page = state
+        .analytics_outbox
+        .list_events(consumer_id, after_id, &event_types, query.limit)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to read analytics events");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve analytics events".to_string(),
+            )
+        })?;
+
+    Ok(Json(page))
+}
+
+/// Usage heatmap, peak windows and suggested rate limit profiles for a
+/// service, for its provider
+#[instrument(skip(state))]
+pub async fn get_usage_insights(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> Result<Json<UsageInsights>> {
+    let internal_error = |e: anyhow::Error| {
+        error!(error = %e, "Failed to read usage insights");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to retrieve usage insights".to_string(),
+        )
+    };
+
+    // Callers other than the provider can't tell the service exists
+    if !state
+        .usage_insights
+        .is_provider(service_id, consumer_id)
+        .await
+        .map_err(internal_error)?
+    {
+        return Err((StatusCode::NOT_FOUND, "Service not found".to_string()));
+    }
+
+    let insights = state
+        .usage_insights
+        .get(service_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                "Usage insights have not been computed for this service yet".to_string(),
+            )
+        })?;
+
+    Ok(Json(insights))
+}
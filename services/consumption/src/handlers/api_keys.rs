@@ -1,29 +1,54 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
-use tracing::{error, info, instrument};
+use chrono::Duration;
+use tracing::{info, instrument, warn};
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
-    models::{ApiKey, ApiKeyResponse, CreateApiKeyRequest},
-    services::ApiKeyManager,
-    AppState, Result,
+    models::{
+        ApiKey, ApiKeyResponse, ApiKeyScope, Caller, CreateApiKeyRequest, RotateApiKeyRequest,
+        RotatedApiKey,
+    },
+    services::{api_key_manager::MAX_ROTATION_GRACE_SECS, ApiKeyManager},
+    utils::{AppError, AppResult},
+    AppState,
 };
 
 /// Create a new API key
-#[instrument(skip(state, request))]
+///
+/// A key can't grant scopes the key creating it lacks.
+#[instrument(skip(state, caller, request))]
 pub async fn create_api_key(
     State(state): State<AppState>,
     consumer_id: Uuid, // Injected by auth middleware
+    Extension(caller): Extension<Caller>,
     Json(request): Json<CreateApiKeyRequest>,
-) -> Result<Json<ApiKeyResponse>> {
+) -> AppResult<Json<ApiKeyResponse>> {
     // Validate request
     request
         .validate()
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
+        .map_err(|e| AppError::InvalidRequest(format!("Invalid request: {}", e)))?;
+
+    let requested = request
+        .scopes
+        .clone()
+        .unwrap_or_else(|| ApiKeyScope::ALL.to_vec());
+    if requested.is_empty() {
+        return Err(AppError::InvalidRequest(
+            "Invalid request: scopes must not be empty".to_string(),
+        ));
+    }
+    let missing = caller.missing_scopes(&requested);
+    if !missing.is_empty() {
+        return Err(AppError::Authorization(format!(
+            "API key can't grant scopes it is missing: {}",
+            missing.join(", ")
+        )));
+    }
 
     info!(
         consumer_id = %consumer_id,
@@ -35,14 +60,7 @@ pub async fn create_api_key(
     let api_key_response = state
         .api_key_manager
         .create_api_key(consumer_id, request)
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to create API key");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to create API key: {}", e),
-            )
-        })?;
+        .await?;
 
     Ok(Json(api_key_response))
 }
@@ -53,7 +71,7 @@ pub async fn revoke_api_key(
     State(state): State<AppState>,
     Path(key_id): Path<Uuid>,
     consumer_id: Uuid, // Injected by auth middleware
-) -> Result<StatusCode> {
+) -> AppResult<StatusCode> {
     info!(
         consumer_id = %consumer_id,
         key_id = %key_id,
@@ -63,35 +81,69 @@ pub async fn revoke_api_key(
     state
         .api_key_manager
         .revoke_key(key_id, consumer_id)
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to revoke API key");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to revoke API key: {}", e),
-            )
-        })?;
+        .await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Rotate an API key without downtime: the replacement is returned and the
+/// old key keeps working for the grace period
+#[instrument(skip(state, caller, request))]
+pub async fn rotate_consumer_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<Uuid>,
+    consumer_id: Uuid, // Injected by auth middleware
+    Extension(caller): Extension<Caller>,
+    request: Option<Json<RotateApiKeyRequest>>,
+) -> AppResult<Json<RotatedApiKey>> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let grace = match request.grace_period_secs {
+        Some(secs) if secs > MAX_ROTATION_GRACE_SECS => {
+            return Err(AppError::InvalidRequest(format!(
+                "Invalid request: grace_period_secs must be at most {}",
+                MAX_ROTATION_GRACE_SECS
+            )));
+        }
+        Some(secs) => Duration::seconds(secs as i64),
+        None => state.api_key_manager.rotation_grace(),
+    };
+
+    info!(
+        consumer_id = %consumer_id,
+        key_id = %key_id,
+        grace_secs = grace.num_seconds(),
+        "Rotating API key"
+    );
+
+    let rotated = state
+        .api_key_manager
+        .rotate_key(key_id, Some(&caller), grace)
+        .await?;
+
+    if let Err(e) = state
+        .analytics_streamer
+        .record_api_key_rotated(
+            consumer_id,
+            rotated.key.service_id,
+            rotated.previous_key_id,
+            rotated.key.id,
+            rotated.previous_key_expires_at,
+        )
+        .await
+    {
+        warn!(error = %e, "Failed to record API key rotation");
+    }
+
+    Ok(Json(rotated))
+}
+
 /// List all API keys for the authenticated consumer
 #[instrument(skip(state))]
 pub async fn list_api_keys(
     State(state): State<AppState>,
     consumer_id: Uuid, // Injected by auth middleware
-) -> Result<Json<Vec<ApiKey>>> {
-    let keys = state
-        .api_key_manager
-        .list_keys(consumer_id)
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to list API keys");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to retrieve API keys".to_string(),
-            )
-        })?;
+) -> AppResult<Json<Vec<ApiKey>>> {
+    let keys = state.api_key_manager.list_keys(consumer_id).await?;
 
     Ok(Json(keys))
 }
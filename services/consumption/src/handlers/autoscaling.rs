@@ -0,0 +1,9 @@
+use axum::{extract::State, Json};
+
+use crate::{services::AutoscalingSnapshot, AppState};
+
+/// Scaling signals for KEDA/HPA (in-flight requests, queue depth,
+/// upstream saturation and per-tier admission rates)
+pub async fn get_autoscaling_signals(State(state): State<AppState>) -> Json<AutoscalingSnapshot> {
+    Json(state.autoscaling.snapshot())
+}
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{AppState, Result};
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceQuery {
+    /// `json` (default) or `csv`
+    format: Option<String>,
+}
+
+/// Get the consumer's invoice for a month, itemized per service and
+/// pricing bracket
+#[instrument(skip(state))]
+pub async fn get_invoice(
+    State(state): State<AppState>,
+    Path(month): Path<String>,
+    Query(query): Query<InvoiceQuery>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> Result<Response> {
+    let month = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid month: {} (expected YYYY-MM)", month),
+        )
+    })?;
+
+    let csv = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid invoice format: {}", other),
+            ));
+        }
+    };
+
+    let invoice = state
+        .invoices
+        .get_invoice(consumer_id, month)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("has not started") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            error!(error = %e, "Failed to get invoice");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to generate invoice".to_string(),
+            )
+        })?;
+
+    if csv {
+        let disposition = format!("attachment; filename=\"{}.csv\"", invoice.invoice_number);
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv".to_string()),
+                (header::CONTENT_DISPOSITION, disposition),
+            ],
+            invoice.to_csv(),
+        )
+            .into_response());
+    }
+    Ok(Json(invoice).into_response())
+}
@@ -1,30 +1,376 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
-    Json,
+    http::{HeaderMap, HeaderName, HeaderValue},
+    Extension, Json,
 };
-use tracing::{debug, error, info, instrument};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
-    models::{ConsumeRequest, ConsumeResponse},
-    services::{QuotaManager, RateLimiter, RequestRouter, UsageMeter},
+    middleware::metrics::record,
+    models::{
+        ApiKey, Caller, ConsumeRequest, ConsumeResponse, CostInfo, QuotaStatus, RequestTimings,
+        Service, UsageInfo,
+    },
+    services::{
+        apply_parameter_policy, response_pipeline::wants_json, CachedResponse, ClientInfo,
+        ConcurrencyPermit, RequestPluginOutcome, ResponseContext, ResponsePipelineOutcome,
+        RoutingContext, ScanVerdict,
+    },
+    utils::AppError,
     AppState, Result,
 };
 
+pub(super) fn plugin_error(e: anyhow::Error) -> AppError {
+    AppError::Plugin(format!("{:#}", e))
+}
+
+/// Accounting headers set on every successful consume response, so clients
+/// and gateways can meter requests without parsing the body
+pub const USAGE_TOTAL_TOKENS_HEADER: HeaderName = HeaderName::from_static("x-usage-total-tokens");
+pub const COST_AMOUNT_HEADER: HeaderName = HeaderName::from_static("x-cost-amount");
+pub const QUOTA_REMAINING_HEADER: HeaderName = HeaderName::from_static("x-quota-remaining");
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// `HIT` or `MISS`, set only for services with the response cache on
+pub const CACHE_HEADER: HeaderName = HeaderName::from_static("x-cache");
+
+/// All usage headers, for CORS `Access-Control-Expose-Headers`
+pub const USAGE_HEADERS: [HeaderName; 5] = [
+    USAGE_TOTAL_TOKENS_HEADER,
+    COST_AMOUNT_HEADER,
+    QUOTA_REMAINING_HEADER,
+    REQUEST_ID_HEADER,
+    CACHE_HEADER,
+];
+
+/// Successful pipeline result shared by all API versions
+#[derive(Debug)]
+pub struct ConsumeOutcome {
+    pub request_id: Uuid,
+    pub response: Value,
+    pub usage: UsageInfo,
+    pub cost: CostInfo,
+    pub latency_ms: u64,
+    pub timings: RequestTimings,
+    /// Tokens left in the monthly quota after this request, floored at zero
+    pub quota_remaining: i64,
+    /// Whether the response came from the service's response cache; `None`
+    /// when the service doesn't cache responses
+    pub cached: Option<bool>,
+}
+
+impl ConsumeOutcome {
+    /// The usage header set; values match the body's `usage.total_tokens`,
+    /// `cost.amount` and `request_id`
+    pub fn usage_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USAGE_TOTAL_TOKENS_HEADER,
+            HeaderValue::from(self.usage.total_tokens),
+        );
+        headers.insert(QUOTA_REMAINING_HEADER, HeaderValue::from(self.quota_remaining));
+        // Float and UUID formatting only produce visible ASCII
+        if let Ok(value) = HeaderValue::from_str(&self.cost.amount.to_string()) {
+            headers.insert(COST_AMOUNT_HEADER, value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&self.request_id.to_string()) {
+            headers.insert(REQUEST_ID_HEADER, value);
+        }
+        if let Some(cached) = self.cached {
+            let value = if cached { "HIT" } else { "MISS" };
+            headers.insert(CACHE_HEADER, HeaderValue::from_static(value));
+        }
+        headers
+    }
+}
+
 /// Main consumption endpoint - proxies request to LLM service
-#[instrument(skip(state, request))]
+#[instrument(skip(state, headers, request))]
 pub async fn consume_service(
     State(state): State<AppState>,
     Path(service_id): Path<Uuid>,
-    consumer_id: Uuid, // Injected by auth middleware
+    Extension(caller): Extension<Caller>, // Injected by auth middleware
+    headers: HeaderMap,
     Json(request): Json<ConsumeRequest>,
-) -> Result<Json<ConsumeResponse>> {
+) -> Result<(HeaderMap, Json<ConsumeResponse>)> {
+    let client = ClientInfo::from_headers(&headers);
+    let outcome = execute_consumption(&state, service_id, &caller, &client, request, None).await?;
+
+    Ok((outcome.usage_headers(), Json(ConsumeResponse {
+        request_id: outcome.request_id,
+        response: outcome.response,
+        usage: outcome.usage,
+        cost: outcome.cost,
+        latency_ms: outcome.latency_ms,
+    })))
+}
+
+/// A request that passed admission, ready to be routed
+pub struct Admission {
+    /// The service, with the endpoint the routing rules picked
+    pub service: Service,
+    /// The request after the parameter policy, PII filter and plugins
+    pub request: ConsumeRequest,
+    /// `metadata.model`, else the service name
+    pub model: String,
+    pub quota_status: QuotaStatus,
+    pub started: Instant,
+    pub admission_ms: u64,
+    /// The consumer's in-flight slot, held until the request is done
+    pub permit: ConcurrencyPermit,
+}
+
+/// Run the consumption pipeline: admission, routing, metering and analytics
+///
+/// Provider calls time out after the service's SLA timeout unless
+/// `upstream_timeout` allows them longer.
+pub async fn execute_consumption(
+    state: &AppState,
+    service_id: Uuid,
+    caller: &Caller,
+    client: &ClientInfo,
+    request: ConsumeRequest,
+    upstream_timeout: Option<Duration>,
+) -> std::result::Result<ConsumeOutcome, AppError> {
+    let consumer_id = caller.consumer_id;
+    let Admission {
+        service,
+        request,
+        model,
+        quota_status,
+        started,
+        admission_ms,
+        permit: _permit,
+    } = admit_consumption(state, service_id, caller, request).await?;
+
+    // Identical requests are answered from the service's response cache,
+    // without calling the provider or charging any tokens
+    let caching = state
+        .response_cache
+        .is_enabled(service_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to load response cache config, not caching");
+            false
+        });
+    let cache_hit = if caching {
+        let hit = state
+            .response_cache
+            .get(service_id, &request)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(error = %e, "Response cache lookup failed");
+                None
+            });
+        record::response_cache_lookup(service_id, hit.is_some());
+        hit
+    } else {
+        None
+    };
+
+    let request_id = Uuid::new_v4();
+    let served_from_cache = cache_hit.is_some();
+    let (response_data, usage, latency_ms, cost) = match cache_hit {
+        Some(hit) => {
+            let cost = CostInfo {
+                amount: 0.0,
+                currency: "USD".to_string(),
+                breakdown: serde_json::json!({
+                    "cache": "hit",
+                    "cached_tokens": hit.usage.total_tokens,
+                }),
+            };
+            let usage = UsageInfo {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+            };
+            (hit.response, usage, 0, cost)
+        }
+        None => {
+            // Route request to LLM service
+            let routed = match upstream_timeout {
+                Some(timeout) => {
+                    state
+                        .request_router
+                        .route_with_timeout(&service, &request, request_id, consumer_id, timeout)
+                        .await
+                }
+                None => {
+                    state
+                        .request_router
+                        .route_with_circuit_breaker(&service, &request, request_id, consumer_id)
+                        .await
+                }
+            };
+            let (response_data, usage, latency_ms) = match routed {
+                Ok(routed) => {
+                    state.service_health.record_success(service_id).await;
+                    routed
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to route request");
+                    state
+                        .service_health
+                        .record_failure(service_id, &e.to_string())
+                        .await;
+                    return Err(AppError::Upstream(format!("Service error: {}", e)));
+                }
+            };
+
+            let cost = meter_consumption(
+                state,
+                &service,
+                consumer_id,
+                client,
+                request_id,
+                &usage,
+                latency_ms,
+                &quota_status,
+            )
+            .await?;
+
+            // Cache the provider's response; transforms and response stages
+            // run again on every hit
+            if caching {
+                tokio::spawn({
+                    let response_cache = state.response_cache.clone();
+                    let request = request.clone();
+                    let cached = CachedResponse {
+                        response: response_data.clone(),
+                        usage: usage.clone(),
+                    };
+                    async move {
+                        if let Err(e) = response_cache.put(service_id, &request, &cached).await {
+                            warn!(error = %e, "Failed to cache response");
+                        }
+                    }
+                });
+            }
+
+            (response_data, usage, latency_ms, cost)
+        }
+    };
+
+    // Response transforms run after metering so usage is never lost
+    let response_data = state
+        .plugin_runtime
+        .run_response_plugins(service_id, response_data)
+        .await
+        .map_err(plugin_error)?;
+
+    // Built-in response stages run last, on what the consumer will receive
+    let (pipeline_outcome, response_stages) = state
+        .response_pipeline
+        .process(
+            ResponseContext {
+                service_id,
+                consumer_id,
+                request_id,
+                model: &model,
+                json_mode: wants_json(&request.metadata),
+            },
+            response_data,
+        )
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Response pipeline failed");
+            AppError::Internal("Response pipeline failed".to_string())
+        })?;
+
+    let response_data = match pipeline_outcome {
+        ResponsePipelineOutcome::Passed(response) => response,
+        ResponsePipelineOutcome::Blocked { reason, filters } => {
+            state
+                .analytics_streamer
+                .record_content_blocked(service_id, consumer_id, "response", filters)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to record blocked response");
+                })
+                .ok();
+            return Err(AppError::ResponseBlocked(format!(
+                "Response withheld by content moderation: {}",
+                reason
+            )));
+        }
+        ResponsePipelineOutcome::ScanUnavailable => {
+            return Err(AppError::ServiceUnavailable(
+                "Content moderation is unavailable".to_string(),
+            ));
+        }
+        ResponsePipelineOutcome::InvalidJson { reason } => {
+            return Err(AppError::InvalidJsonResponse(format!(
+                "Provider returned invalid JSON: {}",
+                reason
+            )));
+        }
+    };
+
+    let timings = RequestTimings {
+        admission_ms,
+        provider_ms: latency_ms,
+        post_processing_ms: (started.elapsed().as_millis() as u64)
+            .saturating_sub(admission_ms + latency_ms),
+        response_stages,
+    };
+
+    // Track marketplace overhead against the service's budget off the hot path
+    tokio::spawn({
+        let sla_monitor = state.sla_monitor.clone();
+        let service = service.clone();
+        async move {
+            if let Err(e) = sla_monitor
+                .record_overhead(&service, timings.overhead_ms())
+                .await
+            {
+                error!(error = %e, "Failed to record marketplace overhead");
+            }
+        }
+    });
+
+    info!(
+        request_id = %request_id,
+        service_id = %service_id,
+        consumer_id = %consumer_id,
+        latency_ms = latency_ms,
+        overhead_ms = timings.overhead_ms(),
+        tokens = usage.total_tokens,
+        cost = cost.amount,
+        "Request completed successfully"
+    );
+
+    Ok(ConsumeOutcome {
+        request_id,
+        response: response_data,
+        quota_remaining: (quota_status.remaining_tokens - i64::from(usage.total_tokens)).max(0),
+        usage,
+        cost,
+        latency_ms,
+        timings,
+        cached: caching.then_some(served_from_cache),
+    })
+}
+
+/// Admit a request: validation, tier parameter policy, PII filter, routing
+/// rules, rate limit, quota, request plugins and concurrency limit
+pub async fn admit_consumption(
+    state: &AppState,
+    service_id: Uuid,
+    caller: &Caller,
+    mut request: ConsumeRequest,
+) -> std::result::Result<Admission, AppError> {
+    let started = Instant::now();
+    let consumer_id = caller.consumer_id;
+
     // Validate request
     request
         .validate()
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
+        .map_err(|e| AppError::InvalidRequest(format!("Invalid request: {}", e)))?;
 
     info!(
         service_id = %service_id,
@@ -33,7 +379,7 @@ pub async fn consume_service(
     );
 
     // Get service details
-    let service = sqlx::query_as(
+    let mut service: Service = sqlx::query_as(
         r#"
         SELECT id, name, version, endpoint, status, pricing, sla, created_at
         FROM services
@@ -42,47 +388,185 @@ pub async fn consume_service(
     )
     .bind(service_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error!(error = %e, "Database error");
-        (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
-    })?
-    .ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            format!("Service {} not found", service_id),
-        )
-    })?;
+    .await?
+    .ok_or_else(|| AppError::ServiceNotFound(format!("Service {} not found", service_id)))?;
 
-    // Get API key to determine tier
-    // In production, this would come from authentication middleware
-    let api_key = sqlx::query_as(
-        r#"
-        SELECT id, key_hash, consumer_id, service_id, tier,
-               created_at, expires_at, revoked_at, metadata
-        FROM api_keys
-        WHERE consumer_id = $1 AND service_id = $2
-        AND revoked_at IS NULL
-        ORDER BY created_at DESC
-        LIMIT 1
-        "#,
-    )
-    .bind(consumer_id)
-    .bind(service_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        error!(error = %e, "Failed to get API key");
-        (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
-    })?
-    .ok_or_else(|| {
-        (
-            StatusCode::FORBIDDEN,
-            "No valid API key found for this service".to_string(),
+    // Fail fast instead of waiting on an endpoint that keeps failing
+    if !state.service_health.admits(service_id) {
+        return Err(AppError::ServiceUnavailable(format!(
+            "Service {} is unavailable",
+            service_id
+        )));
+    }
+
+    // Tier from the identity provider token, else the consumer's API key
+    let tier = match &caller.tier {
+        Some(tier) => tier.clone(),
+        None => {
+            let api_key: ApiKey = sqlx::query_as(
+                r#"
+                SELECT id, key_hash, consumer_id, service_id, tier,
+                       created_at, expires_at, revoked_at, metadata, scopes
+                FROM api_keys
+                WHERE consumer_id = $1 AND service_id = $2
+                AND revoked_at IS NULL
+                ORDER BY created_at DESC
+                LIMIT 1
+                "#,
+            )
+            .bind(consumer_id)
+            .bind(service_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or(AppError::NoApiKey)?;
+
+            api_key.get_tier()
+        }
+    };
+
+    // Tier parameter policy: default max_tokens, then reject or clamp
+    // parameters the tier doesn't allow
+    match apply_parameter_policy(
+        &state.tier_catalog.parameters(&tier),
+        tier.as_str(),
+        &mut request,
+    ) {
+        Ok(clamped) => {
+            for parameter in clamped {
+                record::parameter_policy(tier.as_str(), parameter, "clamped");
+            }
+        }
+        Err(violation) => {
+            record::parameter_policy(tier.as_str(), violation.parameter, "rejected");
+            state.autoscaling.record_admission(&tier, false);
+            return Err(AppError::ParameterNotAllowed(violation.message));
+        }
+    }
+
+    // Built-in PII pre-filter (for deployments without LLM-Shield)
+    let pii_scan = state
+        .pii_filter
+        .scan(service_id, &request.prompt)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "PII filter failed");
+            AppError::Internal("PII filter failed".to_string())
+        })?;
+
+    if let Some(scan) = pii_scan.filter(|scan| scan.detected()) {
+        let action = scan.mode.as_str();
+        for (category, count) in &scan.counts {
+            record::pii_detections(service_id, category.as_str(), action, *count);
+        }
+        state
+            .analytics_streamer
+            .record_pii_detected(
+                service_id,
+                consumer_id,
+                action.to_string(),
+                scan.counts
+                    .iter()
+                    .map(|(category, count)| (category.as_str().to_string(), *count))
+                    .collect(),
+            )
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to record PII detection");
+            })
+            .ok();
+
+        if scan.blocked() {
+            state.autoscaling.record_admission(&tier, false);
+            let categories: Vec<&str> = scan.counts.keys().map(|c| c.as_str()).collect();
+            return Err(AppError::PiiDetected(format!(
+                "Prompt contains personal data: {}",
+                categories.join(", ")
+            )));
+        }
+
+        if let Some(redacted) = scan.redacted {
+            request.prompt = redacted;
+        }
+    }
+
+    // LLM-Shield prompt scan, for services that turned it on
+    let prompt_scan = state
+        .content_scanner
+        .scan_prompt(service_id, consumer_id, &request.prompt)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Content scan failed");
+            AppError::Internal("Content scan failed".to_string())
+        })?;
+
+    match prompt_scan {
+        Some(ScanVerdict::Blocked { filters }) => {
+            state.autoscaling.record_admission(&tier, false);
+            let reason = filters.join(", ");
+            state
+                .analytics_streamer
+                .record_content_blocked(service_id, consumer_id, "prompt", filters)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to record blocked prompt");
+                })
+                .ok();
+            return Err(AppError::PromptBlocked(format!(
+                "Prompt blocked by content moderation: {}",
+                reason
+            )));
+        }
+        Some(ScanVerdict::Unavailable) => {
+            state.autoscaling.record_admission(&tier, false);
+            return Err(AppError::ServiceUnavailable(
+                "Content moderation is unavailable".to_string(),
+            ));
+        }
+        Some(ScanVerdict::Redacted { text, .. }) => request.prompt = text,
+        Some(ScanVerdict::Passed | ScanVerdict::Warned { .. }) | None => {}
+    }
+
+    // Evaluate the service's routing rules before any admission state is consumed
+    let model = request
+        .metadata
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or(&service.name)
+        .to_string();
+    let routing = state
+        .routing_rules
+        .evaluate(
+            service_id,
+            &RoutingContext::new(tier.as_str(), &request.prompt, request.max_tokens, &model),
         )
-    })?;
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Routing rules evaluation failed");
+            AppError::Internal("Routing rules evaluation failed".to_string())
+        })?;
 
-    let tier = api_key.get_tier();
+    if let Some(reason) = routing.reject {
+        state.autoscaling.record_admission(&tier, false);
+        return Err(AppError::RoutingRejected(format!(
+            "Request rejected by routing rules: {}",
+            reason
+        )));
+    }
+
+    if let Some(endpoint) = routing.endpoint {
+        service.endpoint = endpoint;
+    }
+
+    if routing.priority.is_some() || routing.sandbox {
+        if !request.metadata.is_object() {
+            request.metadata = serde_json::json!({});
+        }
+        request.metadata["routing"] = serde_json::json!({
+            "priority": routing.priority,
+            "sandbox": routing.sandbox,
+            "rules": routing.matched,
+        });
+    }
 
     // Check rate limit
     let rate_limit_status = state
@@ -91,20 +575,20 @@ pub async fn consume_service(
         .await
         .map_err(|e| {
             error!(error = %e, "Rate limit check failed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Rate limit check failed".to_string(),
-            )
+            AppError::Internal("Rate limit check failed".to_string())
         })?;
 
+    // With queueing on, wait for the rate limit instead of rejecting outright
+    let rate_limit_status = state
+        .rate_limit_queue
+        .wait(consumer_id, service_id, &tier, rate_limit_status)
+        .await;
+
     if rate_limit_status.exceeded {
-        return Err((
-            StatusCode::TOO_MANY_REQUESTS,
-            format!(
-                "Rate limit exceeded. Retry after {} seconds",
-                rate_limit_status.retry_after_seconds.unwrap_or(60)
-            ),
-        ));
+        state.autoscaling.record_admission(&tier, false);
+        return Err(AppError::RateLimitExceeded {
+            retry_after_seconds: rate_limit_status.retry_after_seconds.unwrap_or(60),
+        });
     }
 
     // Check quota
@@ -114,48 +598,115 @@ pub async fn consume_service(
         .await
         .map_err(|e| {
             error!(error = %e, "Quota check failed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Quota check failed".to_string(),
-            )
+            AppError::Internal("Quota check failed".to_string())
         })?;
 
-    if quota_status.exceeded {
-        return Err((
-            StatusCode::PAYMENT_REQUIRED,
-            format!(
-                "Quota exceeded. Used {}/{} tokens. Resets at {}",
-                quota_status.used_tokens, quota_status.total_tokens, quota_status.reset_at
-            ),
-        ));
+    if !quota_status.admits() {
+        state.autoscaling.record_admission(&tier, false);
+        return Err(AppError::QuotaExceeded(format!(
+            "Quota exceeded. Used {}/{} tokens. Resets at {}",
+            quota_status.used_tokens, quota_status.total_tokens, quota_status.reset_at
+        )));
     }
 
-    // Route request to LLM service
-    let request_id = Uuid::new_v4();
-    let (response_data, usage, latency_ms) = state
-        .request_router
-        .route_with_circuit_breaker(&service, &request, request_id, consumer_id)
+    // Service plugins: request transforms and custom policies, in pin order
+    let request: ConsumeRequest = match state
+        .plugin_runtime
+        .run_request_plugins(service_id, serde_json::to_value(&request).unwrap_or_default())
+        .await
+        .map_err(plugin_error)?
+    {
+        RequestPluginOutcome::Allowed(transformed) => {
+            let transformed: ConsumeRequest = serde_json::from_value(transformed)
+                .map_err(|e| plugin_error(anyhow::anyhow!("Plugin produced an invalid request: {}", e)))?;
+            transformed.validate().map_err(|e| {
+                plugin_error(anyhow::anyhow!("Plugin produced an invalid request: {}", e))
+            })?;
+            transformed
+        }
+        RequestPluginOutcome::Denied { plugin, reason } => {
+            state.autoscaling.record_admission(&tier, false);
+            return Err(AppError::PolicyDenied(format!(
+                "Request denied by plugin {}: {}",
+                plugin, reason
+            )));
+        }
+    };
+
+    // Take an in-flight slot last, so requests rejected above never hold one
+    let max_concurrency = state
+        .tier_catalog
+        .limits(&tier)
+        .max_concurrency
+        .unwrap_or_else(|| tier.max_concurrency());
+    let permit = state
+        .concurrency_limiter
+        .acquire(consumer_id, service_id, max_concurrency)
         .await
         .map_err(|e| {
-            error!(error = %e, "Failed to route request");
-            (
-                StatusCode::BAD_GATEWAY,
-                format!("Service error: {}", e),
-            )
+            error!(error = %e, "Concurrency limit check failed");
+            AppError::Internal("Concurrency limit check failed".to_string())
         })?;
+    let Some(permit) = permit else {
+        record::concurrency_limit_exceeded(service_id, tier.as_str());
+        state.autoscaling.record_admission(&tier, false);
+        return Err(AppError::ConcurrencyLimitExceeded {
+            limit: max_concurrency,
+        });
+    };
+
+    state.autoscaling.record_admission(&tier, true);
+
+    Ok(Admission {
+        service,
+        request,
+        model,
+        quota_status,
+        started,
+        admission_ms: started.elapsed().as_millis() as u64,
+        permit,
+    })
+}
+
+/// Meter a routed request: price it, record usage, update the quota and
+/// stream it to analytics. Only pricing failures fail the request.
+pub async fn meter_consumption(
+    state: &AppState,
+    service: &Service,
+    consumer_id: Uuid,
+    client: &ClientInfo,
+    request_id: Uuid,
+    usage: &UsageInfo,
+    latency_ms: u64,
+    quota_status: &QuotaStatus,
+) -> std::result::Result<CostInfo, AppError> {
+    let service_id = service.id;
+
+    // Price from the consumer's book if the service runs a pricing experiment
+    let price_assignment = state
+        .pricing_experiments
+        .assign(service_id, consumer_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to resolve pricing experiment, using default pricing");
+            None
+        });
+    let price_book = price_assignment.as_ref().and_then(|a| a.pricing.as_ref());
 
     // Calculate cost
     let cost = state
         .usage_meter
-        .calculate_cost(&service.pricing.0, &usage)
+        .calculate_cost(price_book.unwrap_or(&service.pricing.0), usage)
         .map_err(|e| {
             error!(error = %e, "Failed to calculate cost");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Cost calculation failed".to_string(),
-            )
+            AppError::Internal("Cost calculation failed".to_string())
         })?;
 
+    let mut metadata = client.to_metadata();
+    if let Some(assignment) = &price_assignment {
+        metadata.get_or_insert_with(|| serde_json::json!({}))["experiment"] = assignment.annotation();
+    }
+
     // Record usage
     state
         .usage_meter
@@ -167,6 +718,8 @@ pub async fn consume_service(
             latency_ms as i32,
             "success".to_string(),
             None,
+            metadata,
+            price_book,
         )
         .await
         .map_err(|e| {
@@ -176,9 +729,9 @@ pub async fn consume_service(
         .ok();
 
     // Update quota
-    state
+    let used_tokens = state
         .quota_manager
-        .update_quota(consumer_id, service_id, &usage)
+        .update_quota(consumer_id, service_id, request_id, usage)
         .await
         .map_err(|e| {
             error!(error = %e, "Failed to update quota");
@@ -186,21 +739,81 @@ pub async fn consume_service(
         })
         .ok();
 
-    info!(
-        request_id = %request_id,
-        service_id = %service_id,
-        consumer_id = %consumer_id,
-        latency_ms = latency_ms,
-        tokens = usage.total_tokens,
-        cost = cost.amount,
-        "Request completed successfully"
-    );
+    // Quota webhooks for the request that crossed 80% or 100%
+    if let Some(used_tokens) = used_tokens {
+        tokio::spawn({
+            let webhooks = state.webhook_dispatcher.clone();
+            let quota_status = quota_status.clone();
+            let used_before = used_tokens - i64::from(usage.total_tokens);
+            async move {
+                webhooks
+                    .quota_usage(&quota_status, used_before, used_tokens)
+                    .await;
+            }
+        });
+    }
 
-    Ok(Json(ConsumeResponse {
-        request_id,
-        response: response_data,
-        usage,
-        cost,
-        latency_ms,
-    }))
+    // Stream to analytics (also the source of truth for metering reconciliation)
+    state
+        .analytics_streamer
+        .record_consumption(
+            request_id,
+            service_id,
+            consumer_id,
+            latency_ms,
+            usage.clone(),
+            cost.clone(),
+            "success".to_string(),
+        )
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to record analytics event");
+        })
+        .ok();
+
+    Ok(cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_headers_match_body() {
+        let outcome = ConsumeOutcome {
+            request_id: Uuid::nil(),
+            response: Value::Null,
+            usage: UsageInfo {
+                prompt_tokens: 12,
+                completion_tokens: 30,
+                total_tokens: 42,
+            },
+            cost: CostInfo {
+                amount: 0.00126,
+                currency: "USD".to_string(),
+                breakdown: Value::Null,
+            },
+            latency_ms: 250,
+            timings: RequestTimings::default(),
+            quota_remaining: 99_958,
+            cached: None,
+        };
+
+        let headers = outcome.usage_headers();
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers[USAGE_TOTAL_TOKENS_HEADER], "42");
+        assert_eq!(headers[COST_AMOUNT_HEADER], "0.00126");
+        assert_eq!(headers[QUOTA_REMAINING_HEADER], "99958");
+        assert_eq!(
+            headers[REQUEST_ID_HEADER],
+            "00000000-0000-0000-0000-000000000000"
+        );
+        assert!(!headers.contains_key(CACHE_HEADER));
+
+        let hit = ConsumeOutcome {
+            cached: Some(true),
+            ..outcome
+        };
+        assert_eq!(hit.usage_headers()[CACHE_HEADER], "HIT");
+    }
 }
@@ -55,7 +55,7 @@ pub async fn consume_service_enhanced(
     let api_key = sqlx::query_as(
         r#"
         SELECT id, key_hash, consumer_id, service_id, tier,
-               created_at, expires_at, revoked_at, metadata
+               created_at, expires_at, revoked_at, metadata, scopes
         FROM api_keys
         WHERE consumer_id = $1 AND service_id = $2
         AND revoked_at IS NULL
@@ -147,7 +147,7 @@ pub async fn consume_service_enhanced(
                 service_id,
                 consumer_id,
                 format!("{:?}", tier),
-                tier.rate_limit() as u32,
+                rate_limit_status.limit as u32,
             )
             .await
             .ok();
@@ -174,7 +174,7 @@ pub async fn consume_service_enhanced(
             )
         })?;
 
-    if quota_status.exceeded {
+    if !quota_status.admits() {
         // Record to analytics
         state
             .analytics_streamer
@@ -234,6 +234,8 @@ pub async fn consume_service_enhanced(
             latency_ms as i32,
             "success".to_string(),
             None,
+            None,
+            None,
         )
         .await
         .map_err(|e| {
@@ -244,7 +246,7 @@ pub async fn consume_service_enhanced(
     // STEP 7: Update quota
     state
         .quota_manager
-        .update_quota(consumer_id, service_id, &usage)
+        .update_quota(consumer_id, service_id, request_id, &usage)
         .await
         .map_err(|e| {
             error!(error = %e, "Failed to update quota");
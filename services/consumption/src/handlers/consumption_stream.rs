@@ -0,0 +1,147 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use super::consumption::{
+    admit_consumption, meter_consumption, plugin_error, Admission, REQUEST_ID_HEADER,
+};
+use crate::{
+    models::{Caller, ConsumeRequest},
+    services::ClientInfo,
+    utils::AppError,
+    AppState,
+};
+
+/// Streaming consumption endpoint - passes the provider's streamed response
+/// through as it arrives
+///
+/// Admission is the same as `/api/v1/consume/:serviceId`, and the request
+/// holds its in-flight slot until the stream ends. Usage is only known once
+/// the stream ends, so it's metered then, off the request, and
+/// the response carries no usage headers besides `X-Request-ID`. Services
+/// with response transforms or response pipeline stages can't stream, as
+/// those need the whole response.
+#[instrument(skip(state, headers, request))]
+pub async fn consume_service_stream(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Extension(caller): Extension<Caller>, // Injected by auth middleware
+    headers: HeaderMap,
+    Json(request): Json<ConsumeRequest>,
+) -> Result<Response, AppError> {
+    let consumer_id = caller.consumer_id;
+    let client = ClientInfo::from_headers(&headers);
+
+    if post_processes_responses(&state, service_id).await? {
+        return Err(AppError::InvalidRequest(format!(
+            "Service {} post-processes responses and can't stream; use /api/v1/consume/{}",
+            service_id, service_id
+        )));
+    }
+
+    let admission = admit_consumption(&state, service_id, &caller, request).await?;
+
+    let request_id = Uuid::new_v4();
+    let stream = match state
+        .request_router
+        .route_request_streaming(
+            &admission.service,
+            &admission.request,
+            request_id,
+            consumer_id,
+        )
+        .await
+    {
+        Ok(stream) => {
+            state.service_health.record_success(service_id).await;
+            stream
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to route streaming request");
+            state
+                .service_health
+                .record_failure(service_id, &e.to_string())
+                .await;
+            return Err(AppError::Upstream(format!("Service error: {}", e)));
+        }
+    };
+
+    // Meter once the stream ended, including streams the client abandoned
+    tokio::spawn({
+        let state = state.clone();
+        let Admission {
+            service,
+            quota_status,
+            permit,
+            ..
+        } = admission;
+        let (usage, first_byte_ms) = (stream.usage, stream.latency_ms);
+        async move {
+            let usage = usage.await;
+            // The request is no longer in flight once its stream ended
+            drop(permit);
+            let Ok((usage, latency_ms)) = usage else {
+                error!(request_id = %request_id, "Stream ended without usage");
+                return;
+            };
+            match meter_consumption(
+                &state,
+                &service,
+                consumer_id,
+                &client,
+                request_id,
+                &usage,
+                latency_ms,
+                &quota_status,
+            )
+            .await
+            {
+                Ok(cost) => info!(
+                    request_id = %request_id,
+                    service_id = %service.id,
+                    consumer_id = %consumer_id,
+                    first_byte_ms = first_byte_ms,
+                    latency_ms = latency_ms,
+                    tokens = usage.total_tokens,
+                    cost = cost.amount,
+                    "Streamed request completed"
+                ),
+                Err(e) => error!(error = %e, request_id = %request_id, "Failed to meter stream"),
+            }
+        }
+    });
+
+    let mut response = Body::from_stream(stream.body).into_response();
+    let headers = response.headers_mut();
+    if let Ok(content_type) = HeaderValue::from_str(&stream.content_type) {
+        headers.insert(CONTENT_TYPE, content_type);
+    }
+    if let Ok(value) = HeaderValue::from_str(&request_id.to_string()) {
+        headers.insert(REQUEST_ID_HEADER, value);
+    }
+    Ok(response)
+}
+
+/// Whether the service rewrites or moderates responses after the provider
+async fn post_processes_responses(state: &AppState, service_id: Uuid) -> Result<bool, AppError> {
+    let transforms = state
+        .plugin_runtime
+        .transforms_responses(service_id)
+        .await
+        .map_err(plugin_error)?;
+    let pipeline = state
+        .response_pipeline
+        .is_enabled(service_id)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to load response pipeline");
+            AppError::Internal("Response pipeline failed".to_string())
+        })?;
+    Ok(transforms || pipeline)
+}
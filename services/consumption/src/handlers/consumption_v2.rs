@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
+};
+use futures::stream;
+use std::convert::Infallible;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::consumption::execute_consumption;
+use crate::{
+    models::{Caller, ConsumeRequestV2, ConsumeResponseV2},
+    services::ClientInfo,
+    utils::AppError,
+    AppState,
+};
+
+/// Consumption endpoint (v2) - adds timings, structured errors and streaming
+#[instrument(skip(state, headers, request))]
+pub async fn consume_service_v2(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Extension(caller): Extension<Caller>, // Injected by auth middleware
+    headers: HeaderMap,
+    Json(request): Json<ConsumeRequestV2>,
+) -> Result<Response, AppError> {
+    let stream_response = request.stream;
+    let client = ClientInfo::from_headers(&headers);
+    let outcome =
+        execute_consumption(&state, service_id, &caller, &client, request.into(), None).await?;
+    let usage_headers = outcome.usage_headers();
+
+    let body = ConsumeResponseV2 {
+        request_id: outcome.request_id,
+        response: outcome.response,
+        usage: outcome.usage,
+        cost: outcome.cost,
+        latency_ms: outcome.latency_ms,
+        timings: outcome.timings,
+    };
+
+    if !stream_response {
+        return Ok((usage_headers, Json(body)).into_response());
+    }
+
+    // Providers are still called non-streaming, so the completion arrives as a
+    // single `response` event followed by `done` with usage, cost and timings
+    let events = vec![
+        Event::default()
+            .event("response")
+            .json_data(&body.response),
+        Event::default().event("done").json_data(serde_json::json!({
+            "request_id": body.request_id,
+            "usage": body.usage,
+            "cost": body.cost,
+            "latency_ms": body.latency_ms,
+            "timings": body.timings,
+        })),
+    ];
+
+    let events = events
+        .into_iter()
+        .filter_map(|event| event.ok())
+        .map(Ok::<_, Infallible>);
+
+    Ok((usage_headers, Sse::new(stream::iter(events))).into_response())
+}
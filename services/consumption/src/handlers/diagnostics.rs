@@ -0,0 +1,115 @@
+use axum::{
+    extract::{FromRef, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+
+use crate::{
+    services::{DependencyHealth, HealthReport, ProbeStatus, StartupReport},
+    AppState, Result,
+};
+
+/// Liveness and readiness probes. Merged after the auth layer: Kubernetes
+/// probes send no credentials.
+pub fn probe_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    DependencyHealth: FromRef<S>,
+{
+    Router::new()
+        .route("/health/live", get(get_liveness))
+        .route("/health/ready", get(get_readiness))
+}
+
+/// Liveness probe: the process is up and serving requests. Dependencies
+/// aren't checked, so an outage of one doesn't get replicas restarted.
+pub async fn get_liveness() -> Json<HealthReport> {
+    Json(HealthReport::new(Vec::new()))
+}
+
+/// Readiness probe: Postgres, Redis, LLM-Policy-Engine and LLM-Shield, each
+/// with its latency. `503` while a critical dependency is down, so
+/// Kubernetes stops routing traffic to the replica.
+pub async fn get_readiness(
+    State(health): State<DependencyHealth>,
+) -> (StatusCode, Json<HealthReport>) {
+    let report = health.readiness().await;
+    let status = match report.status {
+        ProbeStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        ProbeStatus::Up | ProbeStatus::Degraded => StatusCode::OK,
+    };
+    (status, Json(report))
+}
+
+/// Startup timing breakdown (config, DB pool, Redis, quota load, route build)
+pub async fn get_startup_report(State(state): State<AppState>) -> Result<Json<StartupReport>> {
+    state
+        .startup_report
+        .get()
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Startup in progress".to_string(),
+            )
+        })
+}
+
+/// Git commit, branch, build time and compiler of the running binary
+pub async fn get_version() -> Json<llm_infra::build_info::BuildInfo> {
+    Json(llm_infra::build_info!())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, middleware::Next, response::Response};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    use crate::services::{PolicyEngineClient, ShieldClient};
+
+    /// Rejects every request, like `auth_middleware` without credentials
+    async fn deny(_request: Request<Body>, _next: Next) -> Response {
+        Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_probes_need_no_credentials() {
+        // Nothing listens on these, so readiness reports the service down
+        let health = DependencyHealth::new(
+            PgPool::connect_lazy("postgres://127.0.0.1:1/marketplace").unwrap(),
+            redis::Client::open("redis://127.0.0.1:1").unwrap(),
+            PolicyEngineClient::new("http://127.0.0.1:1".to_string()),
+            ShieldClient::new("http://127.0.0.1:1".to_string()),
+        );
+        // Same layering as main: API routes behind auth, probes merged after
+        let app = Router::new()
+            .route("/api/v1/keys", get(|| async { "keys" }))
+            .layer(axum::middleware::from_fn(deny))
+            .merge(probe_routes())
+            .with_state(health);
+
+        let status = |path: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::get(path).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        assert_eq!(status("/api/v1/keys").await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status("/health/live").await, StatusCode::OK);
+        assert_eq!(
+            status("/health/ready").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{models::Incident, AppState, Result};
+
+#[derive(Debug, Deserialize)]
+pub struct IncidentsQuery {
+    service_id: Option<Uuid>,
+    /// One of open, acknowledged, resolved
+    status: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+/// List SLA incidents, newest first
+#[instrument(skip(state))]
+pub async fn list_incidents(
+    State(state): State<AppState>,
+    Query(query): Query<IncidentsQuery>,
+) -> Result<Json<Vec<Incident>>> {
+    if let Some(status) = query.status.as_deref() {
+        if !matches!(status, "open" | "acknowledged" | "resolved") {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid incident status: {}", status),
+            ));
+        }
+    }
+
+    let incidents = state
+        .alert_manager
+        .list_incidents(query.service_id, query.status.as_deref(), query.limit.clamp(1, 1000))
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list incidents");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve incidents".to_string(),
+            )
+        })?;
+
+    Ok(Json(incidents))
+}
+
+/// Acknowledge an open incident
+#[instrument(skip(state))]
+pub async fn acknowledge_incident(
+    State(state): State<AppState>,
+    Path(incident_id): Path<Uuid>,
+) -> Result<Json<Incident>> {
+    let incident = state
+        .alert_manager
+        .acknowledge(incident_id)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to acknowledge incident");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to acknowledge incident".to_string(),
+            )
+        })?
+        .ok_or((
+            StatusCode::CONFLICT,
+            "Incident not found or not open".to_string(),
+        ))?;
+
+    Ok(Json(incident))
+}
+
+/// Resolve an open or acknowledged incident
+#[instrument(skip(state))]
+pub async fn resolve_incident(
+    State(state): State<AppState>,
+    Path(incident_id): Path<Uuid>,
+) -> Result<Json<Incident>> {
+    let incident = state
+        .alert_manager
+        .resolve(incident_id)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to resolve incident");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to resolve incident".to_string(),
+            )
+        })?
+        .ok_or((
+            StatusCode::CONFLICT,
+            "Incident not found or already resolved".to_string(),
+        ))?;
+
+    Ok(Json(incident))
+}
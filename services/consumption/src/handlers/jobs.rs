@@ -0,0 +1,316 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::consumption::execute_consumption;
+use crate::{
+    models::{ApiErrorBody, Caller, ConsumeRequest, ConsumeResponseV2, ConsumptionJobStatus},
+    services::{ClientInfo, Job, JobHandler},
+    utils::{AppError, AppResult},
+    AppState,
+};
+
+/// Job kind of async consumption requests
+pub const CONSUMPTION_JOB: &str = "consumption";
+
+/// A queued consume request, as stored in the job payload
+#[derive(Debug, Serialize, Deserialize)]
+struct ConsumptionJob {
+    service_id: Uuid,
+    caller: Caller,
+    client: ClientInfo,
+    request: ConsumeRequest,
+}
+
+/// What a finished job stored as its result
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ConsumptionJobOutcome {
+    Failed(ApiErrorBody),
+    Succeeded(ConsumeResponseV2),
+}
+
+fn job_not_found(job_id: Uuid) -> AppError {
+    AppError::NotFound(format!("Job {} not found", job_id))
+}
+
+/// Queue a consume request to run in the background; poll
+/// `/api/v1/jobs/:jobId` for the response
+///
+/// The request runs through the same pipeline as `/api/v1/consume/:serviceId`,
+/// but the provider may take up to `ASYNC_JOB_TIMEOUT_SECS` instead of the
+/// service's SLA timeout.
+#[instrument(skip(state, headers, request))]
+pub async fn create_consumption_job(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Extension(caller): Extension<Caller>, // Injected by auth middleware
+    headers: HeaderMap,
+    Json(request): Json<ConsumeRequest>,
+) -> AppResult<(StatusCode, Json<ConsumptionJobStatus>)> {
+    // Reject malformed requests now rather than when the job runs
+    request
+        .validate()
+        .map_err(|e| AppError::InvalidRequest(format!("Invalid request: {}", e)))?;
+
+    let consumer_id = caller.consumer_id;
+    let payload = ConsumptionJob {
+        service_id,
+        caller,
+        client: ClientInfo::from_headers(&headers),
+        request,
+    };
+    let payload = serde_json::to_value(&payload)
+        .map_err(|e| AppError::Internal(format!("Failed to queue request: {}", e)))?;
+    let job_id = state.job_queue.enqueue(CONSUMPTION_JOB, payload).await?;
+
+    info!(
+        job_id = %job_id,
+        service_id = %service_id,
+        consumer_id = %consumer_id,
+        "Consumption job queued"
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ConsumptionJobStatus {
+            job_id,
+            service_id,
+            status: "queued".to_string(),
+            attempts: 0,
+            result: None,
+            error: None,
+            created_at: Utc::now(),
+            completed_at: None,
+        }),
+    ))
+}
+
+/// Status of one of the authenticated consumer's consumption jobs, with the
+/// consume response or error once it finished
+#[instrument(skip(state))]
+pub async fn get_consumption_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> AppResult<Json<ConsumptionJobStatus>> {
+    let job = state
+        .job_queue
+        .get(job_id)
+        .await?
+        .filter(|job| job.kind == CONSUMPTION_JOB)
+        .ok_or_else(|| job_not_found(job_id))?;
+    let payload: ConsumptionJob = serde_json::from_value(job.payload.0.clone())
+        .map_err(|e| AppError::Internal(format!("Invalid job payload: {}", e)))?;
+    // Other consumers' jobs don't exist as far as the caller can tell
+    if payload.caller.consumer_id != consumer_id {
+        return Err(job_not_found(job_id));
+    }
+
+    Ok(Json(job_status(&job, payload.service_id)))
+}
+
+/// Map a job row to the API status. Jobs whose request failed finish as
+/// succeeded runs with the error as their result.
+fn job_status(job: &Job, service_id: Uuid) -> ConsumptionJobStatus {
+    let outcome = job
+        .result
+        .as_ref()
+        .and_then(|result| serde_json::from_value(result.0.clone()).ok());
+
+    let (status, result, error) = match (job.status.as_str(), outcome) {
+        ("succeeded", Some(ConsumptionJobOutcome::Succeeded(response))) => {
+            ("succeeded", Some(response), None)
+        }
+        ("succeeded", Some(ConsumptionJobOutcome::Failed(body))) => {
+            ("failed", None, Some(body.error))
+        }
+        ("succeeded" | "failed", _) => {
+            let message = job
+                .last_error
+                .clone()
+                .unwrap_or_else(|| "Job failed".to_string());
+            let error = AppError::Internal(message).to_response().error;
+            ("failed", None, Some(error))
+        }
+        (status, _) => (status, None, None),
+    };
+
+    ConsumptionJobStatus {
+        job_id: job.id,
+        service_id,
+        status: status.to_string(),
+        attempts: job.attempts,
+        result,
+        error,
+        created_at: job.created_at,
+        completed_at: job.completed_at,
+    }
+}
+
+/// Whether a failed request may succeed if run again later
+fn is_transient(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::Database(_)
+            | AppError::Redis(_)
+            | AppError::RateLimitExceeded { .. }
+            | AppError::ConcurrencyLimitExceeded { .. }
+            | AppError::ServiceUnavailable(_)
+            | AppError::Upstream(_)
+            | AppError::Timeout(_)
+    )
+}
+
+/// Runs queued consumption jobs through the consumption pipeline
+#[derive(Clone)]
+pub struct ConsumptionJobRunner {
+    state: AppState,
+    upstream_timeout: Duration,
+}
+
+impl ConsumptionJobRunner {
+    /// `upstream_timeout` replaces the services' SLA timeouts for provider
+    /// calls made by jobs
+    pub fn new(state: AppState, upstream_timeout: Duration) -> Self {
+        Self {
+            state,
+            upstream_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl JobHandler for ConsumptionJobRunner {
+    async fn run(&self, job: &Job) -> anyhow::Result<Value> {
+        let payload: ConsumptionJob =
+            serde_json::from_value(job.payload.0.clone()).context("Invalid job payload")?;
+
+        let outcome = execute_consumption(
+            &self.state,
+            payload.service_id,
+            &payload.caller,
+            &payload.client,
+            payload.request,
+            Some(self.upstream_timeout),
+        )
+        .await;
+
+        let outcome = match outcome {
+            Ok(outcome) => ConsumptionJobOutcome::Succeeded(ConsumeResponseV2 {
+                request_id: outcome.request_id,
+                response: outcome.response,
+                usage: outcome.usage,
+                cost: outcome.cost,
+                latency_ms: outcome.latency_ms,
+                timings: outcome.timings,
+            }),
+            // Retried with backoff while attempts remain; rate limits and
+            // provider outages often clear up by then
+            Err(e) if is_transient(&e) && job.attempts < job.max_attempts => {
+                return Err(anyhow::Error::new(e));
+            }
+            Err(e) => {
+                error!(
+                    job_id = %job.id,
+                    error = %e,
+                    code = e.error_type(),
+                    "Consumption job failed"
+                );
+                ConsumptionJobOutcome::Failed(e.to_response())
+            }
+        };
+
+        Ok(serde_json::to_value(outcome)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(status: &str, result: Option<Value>) -> Job {
+        serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "kind": CONSUMPTION_JOB,
+            "payload": {},
+            "status": status,
+            "attempts": 1,
+            "max_attempts": 5,
+            "run_at": "2025-11-01T00:00:00Z",
+            "last_error": "Lease expired on final attempt",
+            "result": result,
+            "created_at": "2025-11-01T00:00:00Z",
+            "updated_at": "2025-11-01T00:00:00Z",
+            "completed_at": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_job_status() {
+        let service_id = Uuid::new_v4();
+
+        let running = job_status(&job("running", None), service_id);
+        assert_eq!(running.status, "running");
+        assert!(running.result.is_none() && running.error.is_none());
+
+        let quota = AppError::QuotaExceeded("Monthly quota exceeded".to_string()).to_response();
+        let rejected = job_status(
+            &job("succeeded", Some(serde_json::to_value(quota).unwrap())),
+            service_id,
+        );
+        assert_eq!(rejected.status, "failed");
+        assert_eq!(rejected.error.unwrap().code, "quota_exceeded");
+
+        let dead_lettered = job_status(&job("failed", None), service_id);
+        assert_eq!(dead_lettered.status, "failed");
+        assert_eq!(
+            dead_lettered.error.unwrap().message,
+            "Lease expired on final attempt"
+        );
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        assert!(is_transient(&AppError::Upstream("503".to_string())));
+        assert!(is_transient(&AppError::RateLimitExceeded {
+            retry_after_seconds: 1
+        }));
+        assert!(!is_transient(&AppError::QuotaExceeded(String::new())));
+        assert!(!is_transient(&AppError::PolicyDenied(String::new())));
+    }
+
+    #[test]
+    fn test_payload_identifies_consumer() {
+        let consumer_id = Uuid::new_v4();
+        let payload = serde_json::to_value(ConsumptionJob {
+            service_id: Uuid::new_v4(),
+            caller: Caller {
+                consumer_id,
+                scopes: None,
+                tier: None,
+            },
+            client: ClientInfo::from_headers(&HeaderMap::new()),
+            request: serde_json::from_value(serde_json::json!({ "prompt": "Hi" })).unwrap(),
+        })
+        .unwrap();
+
+        // Privacy export and erasure find the consumer's jobs by this path
+        assert_eq!(
+            payload["caller"]["consumer_id"],
+            consumer_id.to_string().as_str()
+        );
+    }
+}
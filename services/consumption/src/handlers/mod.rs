@@ -1,9 +1,53 @@
+pub mod admin;
+pub mod analytics;
 pub mod api_keys;
+pub mod autoscaling;
+pub mod billing;
 pub mod consumption;
+pub mod consumption_stream;
+pub mod consumption_v2;
+pub mod diagnostics;
+pub mod incidents;
+pub mod jobs;
+pub mod privacy;
 pub mod quota;
+pub mod sla_reports;
+pub mod statements;
 pub mod usage;
+pub mod webhooks;
 
-pub use api_keys::{create_api_key, list_api_keys, revoke_api_key};
+pub use admin::{
+    adjust_quota, create_pricing_experiment, delete_limit_rampup, delete_provider_credential,
+    delete_rate_limit_override, get_billing_anchor, get_content_scan, get_ingest_key,
+    get_limit_rampup, get_log_level, get_organization_locale, get_pii_filter,
+    get_provider_credential, get_quota_counters, get_rate_limit_algorithm, get_rate_limit_override,
+    get_redis_audit, get_residency_pin, get_response_cache, get_response_pipeline,
+    get_service_endpoints, get_service_plugins, grant_quota_overage, list_consumers,
+    list_dead_letters, list_pricing_experiments, list_reconciliations, list_routing_rules,
+    list_sdk_versions, list_service_health, list_tiers, list_violations, pin_organization,
+    pin_pricing_cohort, pricing_experiment_results, provider_credential_audit, publish_plugin,
+    purge_response_cache, quota_ledger, record_invoiced_usage, replace_routing_rules,
+    requeue_dead_letter, reset_log_level, reset_quota, reset_rate_limit, rotate_api_key,
+    rotate_provider_credential, set_billing_anchor, set_content_scan, set_limit_rampup,
+    set_log_level, set_organization_locale, set_pii_filter, set_rate_limit_algorithm,
+    set_rate_limit_override, set_response_cache, set_response_pipeline, set_service_endpoints,
+    set_service_health_webhook, set_service_plugins, set_tier_limits, simulate_rate_limits,
+    stop_pricing_experiment, store_provider_credential, suspend_consumer, trigger_job,
+    unsuspend_consumer,
+};
+pub use analytics::{get_analytics_events, get_usage_insights};
+pub use api_keys::{create_api_key, list_api_keys, revoke_api_key, rotate_consumer_api_key};
+pub use autoscaling::get_autoscaling_signals;
+pub use billing::get_invoice;
 pub use consumption::consume_service;
-pub use quota::get_quota_status;
-pub use usage::get_usage_stats;
+pub use consumption_stream::consume_service_stream;
+pub use consumption_v2::consume_service_v2;
+pub use diagnostics::{get_startup_report, get_version, probe_routes};
+pub use incidents::{acknowledge_incident, list_incidents, resolve_incident};
+pub use jobs::{create_consumption_job, get_consumption_job};
+pub use privacy::{erase_personal_data, export_personal_data};
+pub use quota::{get_quota_history, get_quota_status};
+pub use sla_reports::get_sla_report;
+pub use statements::get_statement;
+pub use usage::{get_usage_stats, ingest_usage, list_usage_records};
+pub use webhooks::{create_webhook, delete_webhook, get_webhook, list_webhooks, update_webhook};
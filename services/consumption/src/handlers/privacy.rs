@@ -0,0 +1,67 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Deserialize;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    services::{ErasureReport, PrivacyExport, Signed},
+    AppState, Result,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ErasureRequest {
+    /// Must be `true`; erasure deletes the caller's API keys and cannot be undone
+    #[serde(default)]
+    confirm: bool,
+}
+
+fn privacy_error(message: &str, e: anyhow::Error) -> (StatusCode, String) {
+    if e.to_string().contains("signing key not configured") {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Privacy requests are not enabled".to_string(),
+        );
+    }
+    error!(error = %e, "{}", message);
+    (StatusCode::INTERNAL_SERVER_ERROR, message.to_string())
+}
+
+/// Export the caller's personal data as a signed JSON document
+#[instrument(skip(state))]
+pub async fn export_personal_data(
+    State(state): State<AppState>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> Result<Json<Signed<PrivacyExport>>> {
+    let export = state
+        .privacy_service
+        .export(consumer_id)
+        .await
+        .map_err(|e| privacy_error("Failed to export personal data", e))?;
+
+    Ok(Json(export))
+}
+
+/// Erase the caller's personal data and return a signed completion report
+#[instrument(skip(state, request))]
+pub async fn erase_personal_data(
+    State(state): State<AppState>,
+    consumer_id: Uuid, // Injected by auth middleware
+    Json(request): Json<ErasureRequest>,
+) -> Result<Json<Signed<ErasureReport>>> {
+    if !request.confirm {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Erasure must be confirmed with \"confirm\": true".to_string(),
+        ));
+    }
+
+    info!(consumer_id = %consumer_id, "Erasure requested");
+
+    let report = state
+        .privacy_service
+        .erase(consumer_id)
+        .await
+        .map_err(|e| privacy_error("Failed to erase personal data", e))?;
+
+    Ok(Json(report))
+}
@@ -1,29 +1,44 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use tracing::{error, instrument};
 use uuid::Uuid;
 
 use crate::{
-    models::QuotaStatus,
-    services::QuotaManager,
+    models::{ApiKey, Caller, QuotaGranularity, QuotaHistory, QuotaStatus, ServiceTier},
+    services::{history_window_count, QuotaManager, MAX_HISTORY_WINDOWS},
     AppState, Result,
 };
 
-/// Get quota status for a service
-#[instrument(skip(state))]
-pub async fn get_quota_status(
-    State(state): State<AppState>,
-    Path(service_id): Path<Uuid>,
-    consumer_id: Uuid, // Injected by auth middleware
-) -> Result<Json<QuotaStatus>> {
-    // Get API key to determine tier
-    let api_key = sqlx::query_as(
+#[derive(Debug, Deserialize)]
+pub struct QuotaHistoryQuery {
+    #[serde(default)]
+    granularity: QuotaGranularity,
+    /// Defaults to the start of the current quota period
+    from: Option<DateTime<Utc>>,
+    /// Defaults to now
+    to: Option<DateTime<Utc>>,
+}
+
+/// Tier of the caller's token, else of the consumer's newest active API key
+/// for a service
+pub(crate) async fn consumer_tier(
+    state: &AppState,
+    caller: &Caller,
+    service_id: Uuid,
+) -> Result<ServiceTier> {
+    if let Some(tier) = &caller.tier {
+        return Ok(tier.clone());
+    }
+
+    let api_key: ApiKey = sqlx::query_as(
         r#"
         SELECT id, key_hash, consumer_id, service_id, tier,
-               created_at, expires_at, revoked_at, metadata
+               created_at, expires_at, revoked_at, metadata, scopes
         FROM api_keys
         WHERE consumer_id = $1 AND service_id = $2
         AND revoked_at IS NULL
@@ -31,7 +46,7 @@ pub async fn get_quota_status(
         LIMIT 1
         "#,
     )
-    .bind(consumer_id)
+    .bind(caller.consumer_id)
     .bind(service_id)
     .fetch_optional(&state.db)
     .await
@@ -46,7 +61,18 @@ pub async fn get_quota_status(
         )
     })?;
 
-    let tier = api_key.get_tier();
+    Ok(api_key.get_tier())
+}
+
+/// Get quota status for a service
+#[instrument(skip(state))]
+pub async fn get_quota_status(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Extension(caller): Extension<Caller>, // Injected by auth middleware
+) -> Result<Json<QuotaStatus>> {
+    let consumer_id = caller.consumer_id;
+    let tier = consumer_tier(&state, &caller, service_id).await?;
 
     let quota_status = state
         .quota_manager
@@ -62,3 +88,75 @@ pub async fn get_quota_status(
 
     Ok(Json(quota_status))
 }
+
+/// Get quota consumption per window for a service
+#[instrument(skip(state))]
+pub async fn get_quota_history(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Query(query): Query<QuotaHistoryQuery>,
+    Extension(caller): Extension<Caller>, // Injected by auth middleware
+) -> Result<Json<QuotaHistory>> {
+    let consumer_id = caller.consumer_id;
+    let now = Utc::now();
+    let to = query.to.unwrap_or(now).min(now);
+    let from = match query.from {
+        Some(from) => from,
+        None => {
+            state
+                .quota_manager
+                .current_period(consumer_id)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to get quota period");
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Quota history unavailable".to_string(),
+                    )
+                })?
+                .start
+        }
+    };
+
+    if from >= to {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "from must be before to".to_string(),
+        ));
+    }
+    if history_window_count(query.granularity, from, to) > MAX_HISTORY_WINDOWS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Range spans more than {} {} windows",
+                MAX_HISTORY_WINDOWS,
+                query.granularity.as_str()
+            ),
+        ));
+    }
+
+    let tier = consumer_tier(&state, &caller, service_id).await?;
+
+    let points = state
+        .quota_manager
+        .usage_history(consumer_id, service_id, &tier, query.granularity, from, to)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to load quota history");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Quota history unavailable".to_string(),
+            )
+        })?;
+
+    Ok(Json(QuotaHistory {
+        service_id,
+        consumer_id,
+        total_tokens: state.tier_catalog.limits(&tier).quota_limit,
+        tier,
+        granularity: query.granularity,
+        from,
+        to,
+        points,
+    }))
+}
@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{
+    handlers::quota::consumer_tier,
+    models::{Caller, ServiceTier},
+    services::Locale,
+    AppState, Result,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct SlaReportQuery {
+    /// `html` (default) or `json`
+    format: Option<String>,
+    /// Locale of the HTML report, e.g. `de-DE`; defaults to the
+    /// organization's
+    locale: Option<String>,
+}
+
+/// Locale a document is rendered in: the requested one, else the consumer's
+/// organization's
+pub(crate) async fn report_locale(
+    state: &AppState,
+    consumer_id: Uuid,
+    requested: Option<&str>,
+) -> Result<Arc<Locale>> {
+    let locales = state.report_locales.locales();
+    if let Some(tag) = requested {
+        return locales.get(tag).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Unknown locale: {} (available: {})",
+                    tag,
+                    locales.tags().join(", ")
+                ),
+            )
+        });
+    }
+
+    Ok(state
+        .report_locales
+        .for_consumer(consumer_id)
+        .await
+        .unwrap_or_else(|e| {
+            error!(error = %e, "Failed to get report locale");
+            locales.default_locale()
+        }))
+}
+
+/// Get the monthly SLA compliance report for a service (enterprise tier)
+#[instrument(skip(state))]
+pub async fn get_sla_report(
+    State(state): State<AppState>,
+    Path((service_id, month)): Path<(Uuid, String)>,
+    Query(query): Query<SlaReportQuery>,
+    Extension(caller): Extension<Caller>, // Injected by auth middleware
+) -> Result<Response> {
+    let consumer_id = caller.consumer_id;
+    let month = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid month: {} (expected YYYY-MM)", month),
+        )
+    })?;
+
+    let as_json = match query.format.as_deref() {
+        None | Some("html") => false,
+        Some("json") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid report format: {}", other),
+            ));
+        }
+    };
+
+    if consumer_tier(&state, &caller, service_id).await? != ServiceTier::Enterprise {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "SLA reports are available on the enterprise tier".to_string(),
+        ));
+    }
+
+    let report = state
+        .sla_reports
+        .get_report(consumer_id, service_id, month)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("has not started") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            error!(error = %e, "Failed to get SLA report");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to generate SLA report".to_string(),
+            )
+        })?;
+
+    if as_json {
+        Ok(Json(report).into_response())
+    } else {
+        let locale = report_locale(&state, consumer_id, query.locale.as_deref()).await?;
+        Ok(Html(report.to_html(&locale)).into_response())
+    }
+}
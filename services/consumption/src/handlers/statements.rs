@@ -0,0 +1,81 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{handlers::sla_reports::report_locale, AppState, Result};
+
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    /// `json` (default), `csv` or `html`
+    format: Option<String>,
+    /// Locale of the HTML statement, e.g. `de-DE`; defaults to the
+    /// organization's
+    locale: Option<String>,
+}
+
+enum StatementFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+/// Get the consumer's monthly statement across all services
+#[instrument(skip(state))]
+pub async fn get_statement(
+    State(state): State<AppState>,
+    Path(month): Path<String>,
+    Query(query): Query<StatementQuery>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> Result<Response> {
+    let month = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid month: {} (expected YYYY-MM)", month),
+        )
+    })?;
+
+    let format = match query.format.as_deref() {
+        None | Some("json") => StatementFormat::Json,
+        Some("csv") => StatementFormat::Csv,
+        Some("html") => StatementFormat::Html,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid statement format: {}", other),
+            ));
+        }
+    };
+
+    let statement = state
+        .statements
+        .get_statement(consumer_id, month)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("has not started") {
+                return (StatusCode::BAD_REQUEST, e.to_string());
+            }
+            error!(error = %e, "Failed to get statement");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to generate statement".to_string(),
+            )
+        })?;
+
+    match format {
+        StatementFormat::Json => Ok(Json(statement).into_response()),
+        StatementFormat::Csv => {
+            Ok(([(header::CONTENT_TYPE, "text/csv")], statement.to_csv()).into_response())
+        }
+        StatementFormat::Html => {
+            let locale = report_locale(&state, consumer_id, query.locale.as_deref()).await?;
+            Ok(Html(statement.to_html(&locale)).into_response())
+        }
+    }
+}
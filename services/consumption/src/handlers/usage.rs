@@ -1,18 +1,33 @@
 use axum::{
+    body::{Body, Bytes},
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
+use futures::stream;
 use serde::Deserialize;
-use tracing::{error, instrument};
+use tracing::{error, instrument, warn};
 use uuid::Uuid;
 
 use crate::{
-    models::UsageStats,
-    services::UsageMeter,
+    models::{UsageRecord, UsageStats},
+    services::{
+        usage_ingest::MAX_BATCH_RECORDS,
+        usage_meter::{parse_record_cursor, MAX_RECORDS_PAGE_SIZE},
+        IngestReport, UsageBatch, UsageMeter, UsageRecordFilter, UsageRecordPage,
+    },
+    utils::{AppError, AppResult},
     AppState, Result,
 };
 
+/// Hex HMAC-SHA256 of the raw batch body, made with the service's ingest key
+pub const USAGE_SIGNATURE_HEADER: &str = "X-Usage-Signature";
+
 #[derive(Debug, Deserialize)]
 pub struct UsageQuery {
     #[serde(default = "default_days")]
@@ -45,3 +60,170 @@ pub async fn get_usage_stats(
 
     Ok(Json(stats))
 }
+
+/// Content type of streamed usage record exports
+const NDJSON: &str = "application/x-ndjson";
+
+#[derive(Debug, Deserialize)]
+pub struct UsageRecordsQuery {
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    cursor: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    status: Option<String>,
+    min_cost: Option<f64>,
+    #[serde(default = "default_records_limit")]
+    limit: i64,
+}
+
+fn default_records_limit() -> i64 {
+    100
+}
+
+fn usage_records_error(e: anyhow::Error) -> (StatusCode, String) {
+    error!(error = %e, "Failed to list usage records");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to retrieve usage records".to_string(),
+    )
+}
+
+/// Raw usage records of a service, oldest first, to reconcile individual
+/// requests
+///
+/// Returns a page of at most `limit` records and the cursor of the next one.
+/// With `Accept: application/x-ndjson`, every record after the cursor is
+/// streamed instead, one JSON object per line.
+#[instrument(skip(state, headers))]
+pub async fn list_usage_records(
+    State(state): State<AppState>,
+    Path(service_id): Path<Uuid>,
+    Query(query): Query<UsageRecordsQuery>,
+    headers: HeaderMap,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> Result<Response> {
+    let filter = UsageRecordFilter {
+        from: query.from,
+        to: query.to,
+        status: query.status,
+        min_cost: query.min_cost,
+    };
+    let ndjson = headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(NDJSON));
+    let limit = if ndjson {
+        MAX_RECORDS_PAGE_SIZE
+    } else {
+        query.limit
+    };
+
+    let after = query
+        .cursor
+        .as_deref()
+        .map(parse_record_cursor)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    // The first page is read before responding, so a database error still
+    // gets an error status
+    let meter = state.usage_meter.clone();
+    let page = meter
+        .list_records(consumer_id, service_id, &filter, after, limit)
+        .await
+        .map_err(usage_records_error)?;
+
+    if !ndjson {
+        return Ok(Json(page).into_response());
+    }
+
+    // Each page is written once the next one is read; a failed read ends the
+    // stream early, so the client sees an incomplete response
+    let pages = stream::unfold(Some(page), move |page| {
+        let (meter, filter) = (meter.clone(), filter.clone());
+        async move {
+            let page: UsageRecordPage = page?;
+            let lines = match ndjson_lines(&page.records) {
+                Ok(lines) => lines,
+                Err(e) => return Some((Err(e), None)),
+            };
+            if !page.has_more {
+                return Some((Ok(lines), None));
+            }
+
+            let after = page
+                .records
+                .last()
+                .map(|record| (record.timestamp, record.id));
+            match meter
+                .list_records(consumer_id, service_id, &filter, after, limit)
+                .await
+            {
+                Ok(next) => Some((Ok(lines), Some(next))),
+                Err(e) => {
+                    error!(error = %e, "Usage records export failed");
+                    Some((Err(e), None))
+                }
+            }
+        }
+    });
+
+    let mut response = Body::from_stream(pages).into_response();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(NDJSON));
+    Ok(response)
+}
+
+/// Records as newline-delimited JSON
+fn ndjson_lines(records: &[UsageRecord]) -> anyhow::Result<Bytes> {
+    let mut lines = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut lines, record)?;
+        lines.push(b'\n');
+    }
+    Ok(Bytes::from(lines))
+}
+
+/// Ingest a signed batch of usage metered by the service's provider
+#[instrument(skip(state, headers, body))]
+pub async fn ingest_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Json<IngestReport>> {
+    if !state.usage_ingestor.enabled() {
+        return Err(AppError::ServiceUnavailable(
+            "Usage ingestion is not enabled".to_string(),
+        ));
+    }
+
+    let batch: UsageBatch = serde_json::from_slice(&body)
+        .map_err(|e| AppError::InvalidRequest(format!("Invalid usage batch: {}", e)))?;
+
+    let signature = headers
+        .get(USAGE_SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| {
+            AppError::Authentication(format!("Missing {} header", USAGE_SIGNATURE_HEADER))
+        })?;
+    if !state
+        .usage_ingestor
+        .verify(batch.service_id, &body, signature)?
+    {
+        warn!(service_id = %batch.service_id, "Rejected usage batch with invalid signature");
+        return Err(AppError::Authentication(
+            "Invalid usage batch signature".to_string(),
+        ));
+    }
+
+    if batch.records.len() > MAX_BATCH_RECORDS {
+        return Err(AppError::InvalidRequest(format!(
+            "Usage batch has {} records, at most {} are accepted",
+            batch.records.len(),
+            MAX_BATCH_RECORDS
+        )));
+    }
+
+    Ok(Json(state.usage_ingestor.ingest(batch).await?))
+}
@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    services::{CreatedWebhook, Webhook, WebhookRequest},
+    utils::{AppError, AppResult},
+    AppState,
+};
+
+fn webhook_not_found(webhook_id: Uuid) -> AppError {
+    AppError::NotFound(format!("Webhook {} not found", webhook_id))
+}
+
+/// Register a webhook; the response carries the signing secret, which is
+/// never returned again
+#[instrument(skip(state, request))]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    consumer_id: Uuid, // Injected by auth middleware
+    Json(request): Json<WebhookRequest>,
+) -> AppResult<(StatusCode, Json<CreatedWebhook>)> {
+    let webhook = state
+        .webhook_dispatcher
+        .create(consumer_id, request)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// List the authenticated consumer's webhooks
+#[instrument(skip(state))]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> AppResult<Json<Vec<Webhook>>> {
+    let webhooks = state.webhook_dispatcher.list(consumer_id).await?;
+
+    Ok(Json(webhooks))
+}
+
+/// Get one of the authenticated consumer's webhooks
+#[instrument(skip(state))]
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<Uuid>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> AppResult<Json<Webhook>> {
+    let webhook = state
+        .webhook_dispatcher
+        .get(consumer_id, webhook_id)
+        .await?
+        .ok_or_else(|| webhook_not_found(webhook_id))?;
+
+    Ok(Json(webhook))
+}
+
+/// Change a webhook's URL, events or active flag
+#[instrument(skip(state, request))]
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<Uuid>,
+    consumer_id: Uuid, // Injected by auth middleware
+    Json(request): Json<WebhookRequest>,
+) -> AppResult<Json<Webhook>> {
+    let webhook = state
+        .webhook_dispatcher
+        .update(consumer_id, webhook_id, request)
+        .await?
+        .ok_or_else(|| webhook_not_found(webhook_id))?;
+
+    Ok(Json(webhook))
+}
+
+/// Delete a webhook
+#[instrument(skip(state))]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<Uuid>,
+    consumer_id: Uuid, // Injected by auth middleware
+) -> AppResult<StatusCode> {
+    if !state
+        .webhook_dispatcher
+        .delete(consumer_id, webhook_id)
+        .await?
+    {
+        return Err(webhook_not_found(webhook_id));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -2,17 +2,21 @@ mod handlers;
 mod middleware;
 mod models;
 mod services;
+mod utils;
 
 use axum::{
     extract::FromRef,
     http::StatusCode,
     middleware as axum_middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
+use llm_infra::config::load_upstream_services_config;
+use llm_infra::logging::LogFilterHandle;
 use redis::aio::ConnectionManager;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tower::ServiceBuilder;
 use tower_http::{
@@ -20,11 +24,23 @@ use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
+use middleware::metrics::HTTP_REQUESTS_IN_FLIGHT;
 use services::{
-    AnalyticsStreamer, ApiKeyManager, PolicyClient, PolicyEngineClient, QuotaManager,
-    RateLimiter, RegistryClient, RequestRouter, SLAMonitor, ShieldClient, UsageMeter,
+    shutdown, AdminService, AlertManager, AlertWebhook, AnalyticsOutbox, AnalyticsSink,
+    AnalyticsStreamer, ApiKeyManager, AutoscalingSignals, ClientCertAuth, ConcurrencyLimiter,
+    ContentScanner, CredentialVault, DataResidency, DependencyHealth, EndpointBalancer,
+    InvoiceGenerator, JobHandler, JobQueue, JobQueueConfig, Locales, MeteringReconciler,
+    MtlsConfig, MtlsMode, OidcConfig, OidcVerifier, OverflowStrategy, PiiFilter, PluginRuntime,
+    PolicyClient, PolicyEngineClient, PricingExperiments, PrivacyService, QuotaManager,
+    QuotaPreload, RateLimitOverrides, RateLimitQueue, RateLimitQueueConfig, RateLimitSimulator,
+    RateLimiter, RedisAudit, RegistryClient, ReportLocales, RequestRouter, ResponseCache,
+    ResponsePipeline, RoutingRulesEngine, SLAMonitor, ServiceHealth, SharedStartupReport,
+    ShieldClient, ShutdownReport, SlaReportGenerator, StartupReport, StatementGenerator,
+    SyntheticCanary, TierCatalog, TlsServer, UpstreamRecorder, UsageIngestor,
+    UsageInsightsAnalyzer, UsageMeter, UsagePartitionConfig, UsagePartitions, UsageRollups,
+    WebhookDispatcher, USAGE_INSIGHTS_JOB, WEBHOOK_DELIVERY_JOB,
 };
 
 /// Application state shared across handlers
@@ -32,14 +48,58 @@ use services::{
 pub struct AppState {
     pub db: PgPool,
     pub redis: ConnectionManager,
+    pub tier_catalog: TierCatalog,
     pub rate_limiter: RateLimiter,
+    pub rate_limit_queue: RateLimitQueue,
+    pub rate_limit_overrides: RateLimitOverrides,
+    pub rate_limit_simulator: RateLimitSimulator,
+    pub concurrency_limiter: ConcurrencyLimiter,
     pub quota_manager: QuotaManager,
     pub usage_meter: UsageMeter,
+    pub usage_rollups: UsageRollups,
+    pub usage_partitions: UsagePartitions,
+    pub usage_ingestor: UsageIngestor,
     pub api_key_manager: ApiKeyManager,
+    /// Verifies identity provider bearer tokens; `None` accepts API keys only
+    pub oidc: Option<OidcVerifier>,
+    /// Identifies consumers by client certificate; `None` when mTLS is off
+    pub mtls: Option<ClientCertAuth>,
     pub request_router: RequestRouter,
+    pub endpoint_balancer: EndpointBalancer,
+    pub service_health: ServiceHealth,
+    pub credential_vault: CredentialVault,
     pub sla_monitor: SLAMonitor,
+    pub sla_reports: SlaReportGenerator,
+    pub statements: StatementGenerator,
+    pub invoices: InvoiceGenerator,
+    pub report_locales: ReportLocales,
+    pub alert_manager: AlertManager,
     pub policy_client: PolicyClient,
     pub analytics_streamer: AnalyticsStreamer,
+    pub analytics_outbox: AnalyticsOutbox,
+    pub admin_service: AdminService,
+    pub autoscaling: AutoscalingSignals,
+    pub metering_reconciler: MeteringReconciler,
+    pub plugin_runtime: PluginRuntime,
+    pub routing_rules: RoutingRulesEngine,
+    pub pricing_experiments: PricingExperiments,
+    pub pii_filter: PiiFilter,
+    pub content_scanner: ContentScanner,
+    pub response_cache: ResponseCache,
+    pub response_pipeline: ResponsePipeline,
+    pub job_queue: JobQueue,
+    pub webhook_dispatcher: WebhookDispatcher,
+    pub usage_insights: UsageInsightsAnalyzer,
+    pub data_residency: DataResidency,
+    pub privacy_service: PrivacyService,
+    pub redis_audit: RedisAudit,
+    pub startup_report: SharedStartupReport,
+    /// Dependency checks behind the readiness probe
+    pub dependency_health: DependencyHealth,
+    /// Log filter, adjustable at runtime through the admin API
+    pub log_filter: LogFilterHandle,
+    /// Token required by the admin API; `None` disables it
+    pub admin_token: Option<String>,
     // Phase 2B: Runtime consumption adapters for upstream LLM-Dev-Ops services
     pub registry_client: RegistryClient,
     pub shield_client: ShieldClient,
@@ -51,17 +111,26 @@ pub type Result<T> = std::result::Result<T, (StatusCode, String)>;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let mut startup = StartupReport::begin();
+
     // Load environment variables
     dotenv::dotenv().ok();
 
     // Initialize tracing
-    middleware::init_tracing()
+    let log_filter = middleware::init_tracing()
         .map_err(|e| anyhow::anyhow!("Failed to initialize tracing: {}", e))?;
 
-    info!("Starting LLM Marketplace Consumption Service");
+    let build = llm_infra::build_info!();
+    info!(
+        version = build.version,
+        git_commit = build.git_commit,
+        git_branch = build.git_branch,
+        "Starting LLM Marketplace Consumption Service"
+    );
 
     // Initialize Prometheus metrics
     let _registry = middleware::init_metrics();
+    startup.mark("config");
 
     // Database connection
     let database_url = std::env::var("DATABASE_URL")
@@ -69,14 +138,21 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Connecting to database: {}", database_url);
 
+    // Fewer warm connections shorten cold starts; the pool grows on demand
+    let db_min_connections = std::env::var("DATABASE_MIN_CONNECTIONS")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(10);
+
     let db = PgPoolOptions::new()
         .max_connections(100)
-        .min_connections(10)
+        .min_connections(db_min_connections)
         .acquire_timeout(std::time::Duration::from_secs(5))
         .connect(&database_url)
         .await?;
 
     info!("Database connection established");
+    startup.mark("db_pool");
 
     // Redis connection
     let redis_url = std::env::var("REDIS_URL")
@@ -88,48 +164,186 @@ async fn main() -> anyhow::Result<()> {
     let redis = redis_client.get_tokio_connection_manager().await?;
 
     info!("Redis connection established");
+    startup.mark("redis");
+
+    // Tier limits from the database, falling back to compiled-in defaults
+    let tier_catalog = TierCatalog::new(db.clone());
+    match tier_catalog.reload().await {
+        Ok(overridden) => info!(overridden = overridden, "Tier catalog loaded"),
+        Err(e) => error!(error = %e, "Failed to load tier catalog, using defaults"),
+    }
+
+    let tier_reload_secs = std::env::var("TIER_CATALOG_RELOAD_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(30);
+    let tier_catalog_clone = tier_catalog.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(tier_reload_secs));
+        loop {
+            interval.tick().await;
+            if let Err(e) = tier_catalog_clone.reload().await {
+                error!(error = %e, "Tier catalog reload failed");
+            }
+        }
+    });
+    startup.mark("tier_catalog");
 
     // Initialize services
-    let rate_limiter = RateLimiter::new(redis.clone());
-    let quota_manager = QuotaManager::new(redis.clone(), db.clone());
-    let usage_meter = UsageMeter::new(db.clone());
-    let api_key_manager = ApiKeyManager::new(db.clone());
-    let request_router = RequestRouter::new();
-    let sla_monitor = SLAMonitor::new(db.clone());
+    let rate_limit_simulator = RateLimitSimulator::new(db.clone(), tier_catalog.clone());
+    let quota_manager = QuotaManager::new(redis.clone(), db.clone(), tier_catalog.clone())
+        .with_preload(QuotaPreload::from_env());
+    let rate_limit_overrides = RateLimitOverrides::new(db.clone(), redis.clone());
+    let rate_limiter = RateLimiter::new(redis.clone(), tier_catalog.clone())
+        .with_rampups(quota_manager.limit_rampups().clone())
+        .with_overrides(rate_limit_overrides.clone())
+        .with_algorithms(db.clone());
+    let rate_limit_queue =
+        RateLimitQueue::new(rate_limiter.clone(), RateLimitQueueConfig::from_env());
+    if rate_limit_queue.enabled() {
+        rate_limit_queue.spawn_worker();
+    }
+    let concurrency_queue_ms = std::env::var("CONCURRENCY_QUEUE_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+    let concurrency_limiter = ConcurrencyLimiter::new(redis.clone())
+        .with_queue_timeout(std::time::Duration::from_millis(concurrency_queue_ms));
+    let data_residency = DataResidency::new(db.clone());
+    let usage_meter = UsageMeter::new(db.clone(), data_residency.clone());
+    let rotation_grace_secs = std::env::var("API_KEY_ROTATION_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(24 * 3600);
+    let api_key_manager = ApiKeyManager::new(db.clone())
+        .with_rotation_grace(chrono::Duration::seconds(rotation_grace_secs));
+    // Enterprises can authenticate with tokens from their identity provider
+    // instead of API keys when OIDC_ISSUER is set
+    let oidc = OidcConfig::from_env().map(OidcVerifier::new);
+    // Consumers on internal networks can authenticate with client
+    // certificates instead when MTLS_MODE is set
+    let mtls_config = MtlsConfig::from_env()?;
+    let mtls = mtls_config.as_ref().map(ClientCertAuth::new);
+
+    // Provider API keys, injected into upstream requests by the router
+    let credential_vault = CredentialVault::new(db.clone())?;
+    // Services with several endpoints are balanced across them, failing
+    // over from endpoints whose circuit opened
+    let endpoint_balancer = EndpointBalancer::new(db.clone());
+    let mut request_router = RequestRouter::new()
+        .with_credentials(credential_vault.clone())
+        .with_balancer(endpoint_balancer.clone());
+    if let Some(recorder) = UpstreamRecorder::from_env()? {
+        warn!(dir = ?recorder.dir(), "Recording upstream calls as fixtures");
+        request_router = request_router.with_recorder(recorder);
+    }
+
+    // Upstream health: consecutive failures degrade and then disable a
+    // service; statuses changed by other replicas are picked up on reload
+    let mut service_health = ServiceHealth::new(db.clone());
+    if let Ok(webhook_url) = std::env::var("SERVICE_HEALTH_WEBHOOK_URL") {
+        service_health = service_health.with_webhook(webhook_url);
+    }
+    if let Err(e) = service_health.reload().await {
+        error!(error = %e, "Failed to load service health");
+    }
+    let service_health_clone = service_health.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10));
+        loop {
+            interval.tick().await;
+            if let Err(e) = service_health_clone.reload().await {
+                error!(error = %e, "Service health reload failed");
+            }
+        }
+    });
+
+    // Upstream LLM-Dev-Ops URLs and failure policies, fail-closed in production
+    let upstream = load_upstream_services_config();
+    info!(
+        shield = ?upstream.shield_failure_policy,
+        policy_engine = ?upstream.policy_engine_failure_policy,
+        "Upstream failure policies"
+    );
 
     // Initialize Policy Engine client (existing - for real-time validation)
-    let policy_engine_url = std::env::var("POLICY_ENGINE_URL")
-        .unwrap_or_else(|_| "http://localhost:8080".to_string());
-    let policy_client = PolicyClient::new(policy_engine_url.clone());
+    let policy_client = PolicyClient::new(upstream.policy_engine_url.clone())
+        .with_failure_policy(upstream.policy_engine_failure_policy);
+
+    // Initialize Analytics streamer with durable outbox for replay,
+    // delivering to Kafka when KAFKA_BROKERS is set
+    let analytics_outbox = AnalyticsOutbox::new(db.clone());
+    let analytics_streamer = AnalyticsStreamer::with_outbox(
+        10000, // 10K event buffer
+        OverflowStrategy::from_env(),
+        analytics_outbox.clone(),
+        AnalyticsSink::from_env()?,
+    );
+
+    // Scaling signals (in-flight, queue depth, upstream saturation, admission rates)
+    let autoscaling = AutoscalingSignals::new(analytics_streamer.clone());
+
+    // Alert manager groups SLA violations into incidents
+    let mut alert_manager = AlertManager::new(db.clone());
+    if let Ok(webhook_url) = std::env::var("INCIDENT_WEBHOOK_URL") {
+        alert_manager = alert_manager.with_webhook(webhook_url);
+    }
+
+    // Durable Postgres job queue; workers only run for registered job kinds
+    let job_queue = JobQueue::new(db.clone(), JobQueueConfig::from_env());
+
+    // Consumer webhooks, delivered as jobs so failed deliveries are retried
+    // and dead-lettered
+    let webhook_dispatcher = WebhookDispatcher::new(db.clone(), job_queue.clone());
 
-    // Initialize Analytics streamer
-    let analytics_streamer = AnalyticsStreamer::new(10000); // 10K event buffer
+    // SLA monitor emits violations through the analytics streamer
+    let mut sla_monitor =
+        SLAMonitor::new(db.clone(), analytics_streamer.clone(), alert_manager.clone())
+            .with_consumer_webhooks(webhook_dispatcher.clone());
+    if let Ok(webhook_url) = std::env::var("SLA_WEBHOOK_URL") {
+        sla_monitor = sla_monitor.with_alert_webhook(webhook_url);
+    }
+
+    // Admin API is only served when a token is configured
+    let admin_service = AdminService::new(db.clone());
+    let admin_token = std::env::var("ADMIN_API_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
 
     // Phase 2B: Initialize upstream LLM-Dev-Ops service consumers
     // These are thin adapters for runtime consumption of metadata and rules
 
     // LLM-Registry: Model metadata, versions, and exchangeable assets
-    let registry_url = std::env::var("LLM_REGISTRY_URL")
-        .unwrap_or_else(|_| "http://localhost:8081".to_string());
-    let registry_client = RegistryClient::new(registry_url);
+    let registry_client = RegistryClient::new(upstream.registry_url);
     info!("LLM-Registry client initialized");
 
     // LLM-Shield: Filter packs, safety rules, and shielding metadata
-    let shield_url = std::env::var("LLM_SHIELD_URL")
-        .unwrap_or_else(|_| "http://localhost:8082".to_string());
-    let shield_client = ShieldClient::new(shield_url);
+    let shield_client =
+        ShieldClient::new(upstream.shield_url).with_failure_policy(upstream.shield_failure_policy);
     info!("LLM-Shield client initialized");
 
     // LLM-Policy-Engine: Policy bundles, enforcement metadata, and compliance rules
-    let policy_engine_client = PolicyEngineClient::new(policy_engine_url);
+    let policy_engine_client = PolicyEngineClient::new(upstream.policy_engine_url)
+        .with_failure_policy(upstream.policy_engine_failure_policy);
     info!("LLM-Policy-Engine client initialized");
 
-    // Load quotas from database to Redis on startup
-    info!("Loading quotas from database");
-    quota_manager.load_quotas().await?;
+    startup.mark("services");
+
+    // Load quotas from database to Redis on startup, unless loaded on first access
+    startup.quota_preload = quota_manager.preload();
+    match quota_manager.preload() {
+        QuotaPreload::Eager => {
+            info!("Loading quotas from database");
+            quota_manager.load_quotas().await?;
+        }
+        QuotaPreload::Lazy => info!("Quota preload is lazy, loading quotas on first access"),
+    }
+    startup.mark("quota_load");
 
     // Spawn background SLA monitoring task
     let sla_monitor_clone = sla_monitor.clone();
+    let alert_manager_clone = alert_manager.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300)); // 5 minutes
         loop {
@@ -137,27 +351,536 @@ async fn main() -> anyhow::Result<()> {
             if let Err(e) = sla_monitor_clone.monitor_all_services().await {
                 error!(error = %e, "SLA monitoring task failed");
             }
+            if let Err(e) = alert_manager_clone.resolve_stale_incidents().await {
+                error!(error = %e, "Failed to resolve stale incidents");
+            }
+        }
+    });
+
+    // Roll up recent usage into hourly quota history every 5 minutes; the
+    // previous hours are recomputed to catch late-arriving usage records
+    let quota_manager_clone = quota_manager.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            let since = chrono::Utc::now() - chrono::Duration::hours(2);
+            if let Err(e) = quota_manager_clone.rollup_usage(since).await {
+                error!(error = %e, "Quota usage rollup failed");
+            }
+        }
+    });
+
+    // Roll up usage into hourly and daily stats rollups every 5 minutes,
+    // recomputing the previous hours like the quota rollup
+    let usage_rollups = UsageRollups::new(db.clone());
+    let usage_rollups_clone = usage_rollups.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            let since = chrono::Utc::now() - chrono::Duration::hours(2);
+            if let Err(e) = usage_rollups_clone.rollup(since).await {
+                error!(error = %e, "Usage rollup failed");
+            }
+        }
+    });
+
+    // Monthly SLA reports for enterprise consumers; the daily check stores
+    // last month's reports once and is a no-op afterwards
+    let sla_reports = SlaReportGenerator::new(db.clone());
+    let sla_reports_clone = sla_reports.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(24 * 3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = sla_reports_clone.generate_previous_month().await {
+                error!(error = %e, "SLA report generation failed");
+            }
+        }
+    });
+
+    // Monthly consumer statements, announced on STATEMENT_WEBHOOK_URL; the
+    // daily run also retries notifications that failed to deliver
+    let mut statements = StatementGenerator::new(db.clone(), sla_reports.clone());
+    if let Ok(webhook_url) = std::env::var("STATEMENT_WEBHOOK_URL") {
+        statements = statements.with_webhook(webhook_url);
+    }
+    let statements_clone = statements.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(24 * 3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = statements_clone.generate_previous_month().await {
+                error!(error = %e, "Statement generation failed");
+            }
+        }
+    });
+
+    // Monthly invoices built on statements; INVOICE_TAX_RATE is a flat
+    // placeholder rate until tax is calculated per jurisdiction
+    let invoice_tax_rate = std::env::var("INVOICE_TAX_RATE")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let invoices =
+        InvoiceGenerator::new(db.clone(), statements.clone()).with_tax_rate(invoice_tax_rate);
+
+    // Per-organization locales of rendered reports and statements; locale
+    // files in LOCALES_DIR add to or override the built-in ones
+    let report_locales = ReportLocales::new(db.clone(), Locales::from_env()?);
+    info!(locales = ?report_locales.locales().tags(), "Report locales loaded");
+
+    // Spawn daily metering reconciliation (re-checks the previous day every
+    // 6 hours so late-arriving analytics and invoices are picked up)
+    let metering_reconciler = MeteringReconciler::new(db.clone(), quota_manager.clone());
+    let reconciler_clone = metering_reconciler.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(6 * 3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = reconciler_clone.reconcile_yesterday().await {
+                error!(error = %e, "Metering reconciliation failed");
+            }
+        }
+    });
+
+    // Hourly sweep moving rows of pinned organizations into their region
+    let residency_clone = data_residency.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = residency_clone.enforce_residency().await {
+                error!(error = %e, "Data residency enforcement failed");
+            }
         }
     });
 
+    // Hourly usage_records partition maintenance: monthly partitions are
+    // created ahead of time and expired ones archived or dropped
+    let usage_partitions = UsagePartitions::new(
+        db.clone(),
+        data_residency.clone(),
+        UsagePartitionConfig::from_env(),
+    );
+    info!(config = ?usage_partitions.config(), "Usage record partition maintenance configured");
+    let usage_partitions_clone = usage_partitions.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = usage_partitions_clone.maintain().await {
+                error!(error = %e, "Usage record partition maintenance failed");
+            }
+        }
+    });
+
+    // GDPR export and erasure (reports signed with PRIVACY_SIGNING_KEY)
+    let privacy_service = PrivacyService::new(db.clone(), redis.clone(), data_residency.clone());
+
+    // Sandboxed per-service Wasm transform and policy plugins
+    let plugin_runtime = PluginRuntime::new(db.clone())?;
+    info!("Plugin runtime initialized");
+
+    // Per-service routing rules, hot-reloaded from the database
+    let routing_rules = RoutingRulesEngine::new(db.clone());
+
+    // A/B price books, hot-reloaded from the database
+    let pricing_experiments = PricingExperiments::new(db.clone());
+
+    // Provider-metered usage (batches signed with keys derived from
+    // USAGE_INGEST_SIGNING_KEY)
+    let usage_ingestor = UsageIngestor::new(
+        db.clone(),
+        usage_meter.clone(),
+        quota_manager.clone(),
+        analytics_streamer.clone(),
+        pricing_experiments.clone(),
+    );
+
+    // Built-in PII pre-filter, configured per service
+    let pii_filter = PiiFilter::new(db.clone());
+
+    // Per-service LLM-Shield scanning of prompts and, through the response
+    // pipeline, responses
+    let content_scanner = ContentScanner::new(db.clone(), shield_client.clone());
+
+    // Response post-processing (Shield scan, profanity, JSON mode, metadata)
+    let response_pipeline = ResponsePipeline::new(db.clone(), content_scanner.clone());
+
+    // Opt-in per-service cache of provider responses to identical requests
+    let response_cache = ResponseCache::new(db.clone(), redis.clone());
+
+    let mut job_handlers: HashMap<String, Arc<dyn JobHandler>> = HashMap::new();
+
+    // Hour-of-week usage heatmaps and peak windows per service
+    let usage_insights = UsageInsightsAnalyzer::new(db.clone());
+    job_handlers.insert(
+        USAGE_INSIGHTS_JOB.to_string(),
+        Arc::new(usage_insights.clone()),
+    );
+    job_handlers.insert(
+        WEBHOOK_DELIVERY_JOB.to_string(),
+        Arc::new(webhook_dispatcher.clone()),
+    );
+    job_queue.spawn_workers(job_handlers);
+
+    // Queue a daily usage insights run over every service with usage
+    let job_queue_clone = job_queue.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(24 * 3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = job_queue_clone
+                .enqueue(USAGE_INSIGHTS_JOB, serde_json::json!({}))
+                .await
+            {
+                error!(error = %e, "Failed to queue usage insights job");
+            }
+        }
+    });
+
+    // Tell consumers about API keys expiring within a week
+    let webhook_dispatcher_clone = webhook_dispatcher.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = webhook_dispatcher_clone.notify_expiring_keys().await {
+                error!(error = %e, "Failed to queue API key expiry webhooks");
+            }
+        }
+    });
+
+    // Dead-letter jobs lost mid-run on their last attempt, prune finished jobs
+    let job_queue_clone = job_queue.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = job_queue_clone.sweep().await {
+                error!(error = %e, "Job queue sweep failed");
+            }
+        }
+    });
+
+    // Periodic keyspace audit flagging Redis keys that never expire or leak
+    let redis_audit = RedisAudit::new(redis.clone());
+    let redis_audit_interval = std::env::var("REDIS_AUDIT_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(3600);
+    if redis_audit_interval > 0 {
+        let redis_audit_clone = redis_audit.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(redis_audit_interval));
+            loop {
+                interval.tick().await;
+                if let Err(e) = redis_audit_clone.run().await {
+                    error!(error = %e, "Redis keyspace audit failed");
+                }
+            }
+        });
+    }
+
+    // Spawn synthetic canary probing every active service
+    if std::env::var("CANARY_ENABLED").map(|v| v == "true").unwrap_or(false) {
+        let canary = SyntheticCanary::new(
+            db.clone(),
+            rate_limiter.clone(),
+            request_router.clone(),
+            usage_meter.clone(),
+            sla_monitor.clone(),
+        )
+        .with_service_health(service_health.clone());
+        let canary_interval = std::env::var("CANARY_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60);
+
+        info!(consumer_id = %canary.consumer_id(), "Synthetic canary enabled");
+
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(canary_interval));
+            loop {
+                interval.tick().await;
+                if let Err(e) = canary.probe_all_services().await {
+                    error!(error = %e, "Canary probe run failed");
+                }
+            }
+        });
+    }
+
+    startup.mark("background_tasks");
+
+    let dependency_health = DependencyHealth::new(
+        db.clone(),
+        redis_client.clone(),
+        policy_engine_client.clone(),
+        shield_client.clone(),
+    );
+    // Filled in once the server is listening
+    let startup_report = SharedStartupReport::default();
+
     // Create application state
     let state = AppState {
         db,
         redis,
+        tier_catalog,
         rate_limiter,
+        rate_limit_queue,
+        rate_limit_overrides,
+        rate_limit_simulator,
+        concurrency_limiter,
         quota_manager,
         usage_meter,
+        usage_rollups,
+        usage_partitions,
+        usage_ingestor,
         api_key_manager,
+        oidc,
+        mtls,
         request_router,
+        endpoint_balancer,
+        service_health,
+        credential_vault,
         sla_monitor,
+        sla_reports,
+        statements,
+        invoices,
+        report_locales,
+        alert_manager,
         policy_client,
         analytics_streamer,
+        analytics_outbox,
+        admin_service,
+        autoscaling,
+        metering_reconciler,
+        plugin_runtime,
+        routing_rules,
+        pricing_experiments,
+        pii_filter,
+        content_scanner,
+        response_cache,
+        response_pipeline,
+        job_queue,
+        webhook_dispatcher,
+        usage_insights,
+        data_residency,
+        privacy_service,
+        redis_audit,
+        dependency_health,
+        startup_report: startup_report.clone(),
+        log_filter,
+        admin_token,
         // Phase 2B: Upstream LLM-Dev-Ops service consumers
         registry_client,
         shield_client,
         policy_engine_client,
     };
 
+    // Async consumption jobs get their own workers, so long generations
+    // don't hold up webhook deliveries and insights runs
+    let async_job_workers = std::env::var("ASYNC_JOB_WORKERS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(8);
+    let async_job_timeout_secs = std::env::var("ASYNC_JOB_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(600);
+    let mut consumption_job_handlers: HashMap<String, Arc<dyn JobHandler>> = HashMap::new();
+    consumption_job_handlers.insert(
+        handlers::jobs::CONSUMPTION_JOB.to_string(),
+        Arc::new(handlers::jobs::ConsumptionJobRunner::new(
+            state.clone(),
+            std::time::Duration::from_secs(async_job_timeout_secs),
+        )),
+    );
+    state
+        .job_queue
+        .spawn_pool("consumption", async_job_workers, consumption_job_handlers);
+
+    // Admin API for operational tooling (marketplace-admin)
+    let admin_routes = Router::new()
+        .route("/api/v1/admin/consumers", get(handlers::list_consumers))
+        .route(
+            "/api/v1/admin/consumers/:consumerId/suspend",
+            post(handlers::suspend_consumer).delete(handlers::unsuspend_consumer),
+        )
+        .route(
+            "/api/v1/admin/consumers/:consumerId/services/:serviceId/quota/reset",
+            post(handlers::reset_quota),
+        )
+        .route(
+            "/api/v1/admin/consumers/:consumerId/services/:serviceId/quota/adjustments",
+            post(handlers::adjust_quota),
+        )
+        .route(
+            "/api/v1/admin/consumers/:consumerId/services/:serviceId/quota/grants",
+            post(handlers::grant_quota_overage),
+        )
+        .route(
+            "/api/v1/admin/consumers/:consumerId/services/:serviceId/quota/ledger",
+            get(handlers::quota_ledger),
+        )
+        .route(
+            "/api/v1/admin/consumers/:consumerId/services/:serviceId/rate-limit/reset",
+            post(handlers::reset_rate_limit),
+        )
+        .route(
+            "/api/v1/admin/quota/:consumerId/:serviceId",
+            get(handlers::get_quota_counters)
+                .put(handlers::grant_quota_overage)
+                .delete(handlers::reset_quota),
+        )
+        .route(
+            "/api/v1/admin/limits/:consumerId/:serviceId",
+            get(handlers::get_rate_limit_override)
+                .put(handlers::set_rate_limit_override)
+                .delete(handlers::delete_rate_limit_override),
+        )
+        .route("/api/v1/admin/keys/:keyId/rotate", post(handlers::rotate_api_key))
+        .route("/api/v1/admin/jobs/:job", post(handlers::trigger_job))
+        .route("/api/v1/admin/violations", get(handlers::list_violations))
+        .route("/api/v1/admin/sdk-versions", get(handlers::list_sdk_versions))
+        .route("/api/v1/admin/redis/audit", get(handlers::get_redis_audit))
+        .route(
+            "/api/v1/admin/reconciliation",
+            get(handlers::list_reconciliations),
+        )
+        .route(
+            "/api/v1/admin/reconciliation/:day/invoices",
+            post(handlers::record_invoiced_usage),
+        )
+        .route(
+            "/api/v1/admin/plugins/:name/:version",
+            put(handlers::publish_plugin),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/plugins",
+            get(handlers::get_service_plugins).put(handlers::set_service_plugins),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/rate-limit-algorithm",
+            get(handlers::get_rate_limit_algorithm).put(handlers::set_rate_limit_algorithm),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/endpoints",
+            get(handlers::get_service_endpoints).put(handlers::set_service_endpoints),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/pii-filter",
+            get(handlers::get_pii_filter).put(handlers::set_pii_filter),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/content-scan",
+            get(handlers::get_content_scan).put(handlers::set_content_scan),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/response-cache",
+            get(handlers::get_response_cache).put(handlers::set_response_cache),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/response-cache/entries",
+            delete(handlers::purge_response_cache),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/response-pipeline",
+            get(handlers::get_response_pipeline).put(handlers::set_response_pipeline),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/routing-rules",
+            get(handlers::list_routing_rules).put(handlers::replace_routing_rules),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/pricing-experiments",
+            get(handlers::list_pricing_experiments).post(handlers::create_pricing_experiment),
+        )
+        .route(
+            "/api/v1/admin/pricing-experiments/:id/stop",
+            post(handlers::stop_pricing_experiment),
+        )
+        .route(
+            "/api/v1/admin/pricing-experiments/:id/cohorts/:consumerId",
+            put(handlers::pin_pricing_cohort),
+        )
+        .route(
+            "/api/v1/admin/pricing-experiments/:id/results",
+            get(handlers::pricing_experiment_results),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/credential",
+            get(handlers::get_provider_credential)
+                .put(handlers::store_provider_credential)
+                .delete(handlers::delete_provider_credential),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/credential/rotate",
+            post(handlers::rotate_provider_credential),
+        )
+        .route("/api/v1/admin/service-health", get(handlers::list_service_health))
+        .route(
+            "/api/v1/admin/services/:serviceId/health/webhook",
+            put(handlers::set_service_health_webhook),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/ingest-key",
+            get(handlers::get_ingest_key),
+        )
+        .route(
+            "/api/v1/admin/services/:serviceId/credential/audit",
+            get(handlers::provider_credential_audit),
+        )
+        .route(
+            "/api/v1/admin/log-level",
+            get(handlers::get_log_level)
+                .put(handlers::set_log_level)
+                .delete(handlers::reset_log_level),
+        )
+        .route("/api/v1/admin/tiers", get(handlers::list_tiers))
+        .route("/api/v1/admin/tiers/:tier", put(handlers::set_tier_limits))
+        .route(
+            "/api/v1/admin/rate-limits/simulate",
+            post(handlers::simulate_rate_limits),
+        )
+        .route(
+            "/api/v1/admin/job-queue/dead-letters",
+            get(handlers::list_dead_letters),
+        )
+        .route(
+            "/api/v1/admin/job-queue/dead-letters/:id/requeue",
+            post(handlers::requeue_dead_letter),
+        )
+        .route(
+            "/api/v1/admin/organizations/:organizationId/residency",
+            get(handlers::get_residency_pin).put(handlers::pin_organization),
+        )
+        .route(
+            "/api/v1/admin/organizations/:organizationId/billing-anchor",
+            get(handlers::get_billing_anchor).put(handlers::set_billing_anchor),
+        )
+        .route(
+            "/api/v1/admin/organizations/:organizationId/limit-rampup",
+            get(handlers::get_limit_rampup)
+                .put(handlers::set_limit_rampup)
+                .delete(handlers::delete_limit_rampup),
+        )
+        .route(
+            "/api/v1/admin/organizations/:organizationId/locale",
+            get(handlers::get_organization_locale).put(handlers::set_organization_locale),
+        )
+        .route_layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            middleware::admin_auth_middleware,
+        ));
+
+    // v1 routes superseded by v2 announce their sunset date
+    let api_deprecation = middleware::ApiDeprecation::from_env();
+
     // Build application router
     let app = Router::new()
         // Health check endpoint (no auth)
@@ -168,14 +891,78 @@ async fn main() -> anyhow::Result<()> {
             "/api/v1/consume/:serviceId",
             post(handlers::consume_service),
         )
+        .route(
+            "/api/v1/consume/:serviceId/stream",
+            post(handlers::consume_service_stream),
+        )
+        .route(
+            "/api/v2/consume/:serviceId",
+            post(handlers::consume_service_v2),
+        )
+        // Queued with POST /jobs/:serviceId, polled with GET /jobs/:jobId
+        .route(
+            "/api/v1/jobs/:id",
+            post(handlers::create_consumption_job).get(handlers::get_consumption_job),
+        )
         .route("/api/v1/quota/:serviceId", get(handlers::get_quota_status))
+        .route(
+            "/api/v1/quota/:serviceId/history",
+            get(handlers::get_quota_history),
+        )
         .route("/api/v1/usage/:serviceId", get(handlers::get_usage_stats))
+        .route(
+            "/api/v1/usage/:serviceId/records",
+            get(handlers::list_usage_records),
+        )
+        .route(
+            "/api/v1/sla/:serviceId/reports/:month",
+            get(handlers::get_sla_report),
+        )
+        .route("/api/v1/statements/:month", get(handlers::get_statement))
+        .route(
+            "/api/v1/billing/invoices/:month",
+            get(handlers::get_invoice),
+        )
+        .route("/api/v1/analytics/events", get(handlers::get_analytics_events))
+        .route(
+            "/api/v1/analytics/services/:serviceId/usage-insights",
+            get(handlers::get_usage_insights),
+        )
+        .route("/api/v1/incidents", get(handlers::list_incidents))
+        .route(
+            "/api/v1/incidents/:incidentId/acknowledge",
+            post(handlers::acknowledge_incident),
+        )
+        .route(
+            "/api/v1/incidents/:incidentId/resolve",
+            post(handlers::resolve_incident),
+        )
+        .route("/api/v1/privacy/export", post(handlers::export_personal_data))
+        .route("/api/v1/privacy/erasure", post(handlers::erase_personal_data))
         .route("/api/v1/keys", post(handlers::create_api_key))
         .route("/api/v1/keys", get(handlers::list_api_keys))
         .route("/api/v1/keys/:keyId", delete(handlers::revoke_api_key))
+        .route(
+            "/api/v1/keys/:keyId/rotate",
+            post(handlers::rotate_consumer_api_key),
+        )
+        .route(
+            "/api/v1/webhooks",
+            post(handlers::create_webhook).get(handlers::list_webhooks),
+        )
+        .route(
+            "/api/v1/webhooks/:webhookId",
+            get(handlers::get_webhook)
+                .put(handlers::update_webhook)
+                .delete(handlers::delete_webhook),
+        )
         // Apply middleware
         .layer(
             ServiceBuilder::new()
+                .layer(axum_middleware::from_fn_with_state(
+                    api_deprecation,
+                    middleware::deprecation_middleware,
+                ))
                 .layer(axum_middleware::from_fn_with_state(
                     state.clone(),
                     middleware::auth_middleware,
@@ -187,30 +974,131 @@ async fn main() -> anyhow::Result<()> {
                     CorsLayer::new()
                         .allow_origin(Any)
                         .allow_methods(Any)
-                        .allow_headers(Any),
+                        .allow_headers(Any)
+                        // Let browser clients read the usage headers
+                        .expose_headers(handlers::consumption::USAGE_HEADERS),
                 ),
         )
-        .with_state(state);
+        .merge(admin_routes)
+        // Provider usage batches authenticate with their signature
+        .route("/api/v1/usage/ingest", post(handlers::ingest_usage))
+        // Kubernetes probes, past the auth layer so they need no token
+        .merge(handlers::probe_routes())
+        // Scaling signals for KEDA/HPA, startup timings and build info (no auth)
+        .route("/autoscaling/signals", get(handlers::get_autoscaling_signals))
+        .route("/diagnostics/startup", get(handlers::get_startup_report))
+        .route("/version", get(handlers::get_version))
+        .with_state(state.clone());
+    startup.mark("route_build");
 
     // Start server
     let port = std::env::var("PORT")
         .unwrap_or_else(|_| "3000".to_string())
         .parse::<u16>()?;
 
+    // The service terminates TLS itself only to verify client certificates
+    let tls_server = match mtls_config.as_ref().map(|config| &config.mode) {
+        Some(MtlsMode::Terminate {
+            cert,
+            key,
+            client_ca,
+        }) => Some(TlsServer::new(cert, key, client_ca)?),
+        _ => None,
+    };
+
     let addr = format!("0.0.0.0:{}", port);
-    info!("Starting server on {}", addr);
+    info!(tls = tls_server.is_some(), "Starting server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
+    startup.mark("bind");
+    let _ = startup_report.set(startup.finish());
 
-    axum::serve(listener, app)
-        .await?;
+    let (signal_tx, signal_rx) = tokio::sync::oneshot::channel();
+    let server = tokio::spawn(async move {
+        let on_signal = async move {
+            let _ = signal_tx.send(shutdown::signal().await);
+        };
+        match tls_server {
+            Some(tls_server) => tls_server.serve(listener, app, on_signal).await,
+            None => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(on_signal)
+                    .await
+            }
+        }
+    });
+
+    // The sender is only dropped without a signal if the server exits on its own
+    let Ok(signal) = signal_rx.await else {
+        server.await??;
+        return Ok(());
+    };
 
-    // Shutdown tracing
-    middleware::shutdown_tracing();
+    graceful_shutdown(signal, server, &state).await;
 
     Ok(())
 }
 
+/// Drain requests and flush buffered state, then emit a shutdown report
+async fn graceful_shutdown(
+    signal: &'static str,
+    server: tokio::task::JoinHandle<std::io::Result<()>>,
+    state: &AppState,
+) {
+    let drain_timeout = std::env::var("SHUTDOWN_DRAIN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(30);
+    let webhook = std::env::var("SHUTDOWN_WEBHOOK_URL").ok().map(AlertWebhook::new);
+
+    let mut report = ShutdownReport::begin(signal, HTTP_REQUESTS_IN_FLIGHT.get());
+
+    let drained = report
+        .phase("drain_requests", async {
+            match tokio::time::timeout(tokio::time::Duration::from_secs(drain_timeout), server).await {
+                Ok(result) => result?.map(|_| true).map_err(anyhow::Error::from),
+                Err(_) => Ok(false),
+            }
+        })
+        .await;
+    report.requests_abandoned = match drained {
+        Some(true) => 0,
+        _ => HTTP_REQUESTS_IN_FLIGHT.get(),
+    };
+    report.requests_drained = (report.requests_in_flight - report.requests_abandoned).max(0);
+
+    if let Some(interrupted) = report
+        .phase("release_jobs", state.job_queue.release_leases())
+        .await
+    {
+        report.jobs_interrupted = interrupted;
+    }
+
+    let analytics = state.analytics_streamer.clone();
+    if let Some(flushed) = report
+        .phase("flush_analytics", async { Ok(analytics.flush().await) })
+        .await
+    {
+        report.analytics_events_flushed = flushed;
+    }
+    let channel = state.analytics_streamer.metrics();
+    report.analytics_events_unflushed = channel.current_length;
+    report.analytics_events_dropped = channel.dropped_events;
+
+    report
+        .phase("persist_quotas", state.quota_manager.persist_quotas())
+        .await;
+
+    report
+        .phase("shutdown_tracing", async {
+            middleware::shutdown_tracing();
+            Ok(())
+        })
+        .await;
+
+    report.finish(webhook.as_ref()).await;
+}
+
 /// Health check endpoint
 async fn health_check() -> &'static str {
     "OK"
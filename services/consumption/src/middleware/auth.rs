@@ -1,15 +1,20 @@
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
+    http::{Method, StatusCode},
     middleware::Next,
     response::Response,
 };
 use tracing::{debug, warn};
 use uuid::Uuid;
 
-use crate::AppState;
+use crate::{
+    models::{ApiKeyScope, Caller},
+    services::OidcVerifier,
+    AppState,
+};
 
-/// Authentication middleware - extracts and validates API key
+/// Authentication middleware - extracts and validates the API key, or the
+/// identity provider token or client certificate when configured
 pub async fn auth_middleware(
     State(state): State<AppState>,
     mut request: Request,
@@ -26,34 +31,237 @@ pub async fn auth_middleware(
             } else {
                 None
             }
-        })
-        .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                "Missing or invalid Authorization header".to_string(),
-            )
-        })?;
+        });
+
+    let caller = match api_key {
+        Some(api_key) => authenticate_bearer(&state, &api_key).await?,
+        // Without an Authorization header, a client certificate may identify
+        // the consumer when mTLS is on
+        None => {
+            let consumer_id = state
+                .mtls
+                .as_ref()
+                .and_then(|mtls| mtls.consumer_id(&request))
+                .ok_or_else(|| {
+                    (
+                        StatusCode::UNAUTHORIZED,
+                        "Missing or invalid Authorization header".to_string(),
+                    )
+                })?
+                .map_err(|e| {
+                    warn!(error = %e, "Client certificate validation failed");
+                    (
+                        StatusCode::UNAUTHORIZED,
+                        "Invalid client certificate".to_string(),
+                    )
+                })?;
+            let caller = Caller {
+                consumer_id,
+                scopes: None,
+                tier: None,
+            };
+            reject_suspended(&state, caller, "Invalid client certificate").await?
+        }
+    };
+
+    let scope = required_scope(request.method(), request.uri().path());
+    if !caller.has_scope(scope) {
+        warn!(
+            consumer_id = %caller.consumer_id,
+            scope = scope.as_str(),
+            "Credential lacks the scope of the route"
+        );
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!(
+                "Credential is missing the required scope: {}",
+                scope.as_str()
+            ),
+        ));
+    }
+
+    debug!(consumer_id = %caller.consumer_id, "Authentication successful");
+
+    // Insert consumer_id and the caller into request extensions for use in handlers
+    request.extensions_mut().insert(caller.consumer_id);
+    request.extensions_mut().insert(caller);
+
+    Ok(next.run(request).await)
+}
+
+/// Caller of an API key, or of an identity provider token when OIDC is
+/// configured
+async fn authenticate_bearer(
+    state: &AppState,
+    credential: &str,
+) -> Result<Caller, (StatusCode, String)> {
+    match &state.oidc {
+        Some(oidc) if OidcVerifier::is_token(credential) => {
+            let caller = oidc.verify(credential).await.map_err(|e| {
+                warn!(error = %e, "Bearer token validation failed");
+                (StatusCode::UNAUTHORIZED, "Invalid bearer token".to_string())
+            })?;
+            reject_suspended(state, caller, "Invalid bearer token").await
+        }
+        _ => {
+            debug!(
+                api_key_prefix = &credential[..10.min(credential.len())],
+                "Validating API key"
+            );
 
-    debug!(api_key_prefix = &api_key[..10.min(api_key.len())], "Validating API key");
+            // Validate API key
+            let api_key_record = state
+                .api_key_manager
+                .validate_key(credential)
+                .await
+                .map_err(|e| {
+                    warn!(error = %e, "API key validation failed");
+                    (StatusCode::UNAUTHORIZED, "Invalid API key".to_string())
+                })?;
+            Ok(Caller::from(&api_key_record))
+        }
+    }
+}
 
-    // Validate API key
-    let api_key_record = state
+/// Reject callers of suspended consumers. API keys are checked on
+/// validation; tokens and certificates aren't stored, so they're checked here.
+async fn reject_suspended(
+    state: &AppState,
+    caller: Caller,
+    rejection: &str,
+) -> Result<Caller, (StatusCode, String)> {
+    let suspended = state
         .api_key_manager
-        .validate_key(&api_key)
+        .is_suspended(caller.consumer_id)
         .await
         .map_err(|e| {
-            warn!(error = %e, "API key validation failed");
-            (StatusCode::UNAUTHORIZED, "Invalid API key".to_string())
+            warn!(error = %e, "Suspension check failed");
+            (StatusCode::UNAUTHORIZED, rejection.to_string())
         })?;
+    if suspended {
+        warn!(consumer_id = %caller.consumer_id, "Rejected suspended consumer");
+        return Err((StatusCode::UNAUTHORIZED, rejection.to_string()));
+    }
+    Ok(caller)
+}
+
+/// Scope an API key needs for a route of the consumer API. Routes not listed
+/// only read the consumer's usage.
+fn required_scope(method: &Method, path: &str) -> ApiKeyScope {
+    let path = path
+        .strip_prefix("/api/v1")
+        .or_else(|| path.strip_prefix("/api/v2"))
+        .unwrap_or(path);
+    let section = path.split('/').nth(1).unwrap_or_default();
+
+    match section {
+        // Job results are consume responses
+        "consume" | "jobs" => ApiKeyScope::Consume,
+        "statements" | "billing" => ApiKeyScope::ReadBilling,
+        "keys" => ApiKeyScope::ManageKeys,
+        "webhooks" => ApiKeyScope::ManageWebhooks,
+        "privacy" => ApiKeyScope::ManagePrivacy,
+        "incidents" if method != Method::GET => ApiKeyScope::ManageIncidents,
+        _ => ApiKeyScope::ReadUsage,
+    }
+}
+
+/// Admin authentication middleware - requires the `X-Admin-Token` header to
+/// match `ADMIN_API_TOKEN`. The admin API is disabled when no token is set.
+pub async fn admin_auth_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let expected = state.admin_token.as_deref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "Admin API is disabled".to_string(),
+        )
+    })?;
 
-    // Insert consumer_id into request extensions for use in handlers
-    request.extensions_mut().insert(api_key_record.consumer_id);
+    let provided = request
+        .headers()
+        .get("X-Admin-Token")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
 
-    debug!(
-        consumer_id = %api_key_record.consumer_id,
-        service_id = %api_key_record.service_id,
-        "Authentication successful"
-    );
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        warn!("Rejected admin request with invalid token");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid admin token".to_string()));
+    }
 
     Ok(next.run(request).await)
 }
+
+/// Compare secrets without short-circuiting on the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+
+    #[test]
+    fn test_required_scope() {
+        let cases = [
+            (Method::POST, "/api/v1/consume/abc", ApiKeyScope::Consume),
+            (
+                Method::POST,
+                "/api/v1/consume/abc/stream",
+                ApiKeyScope::Consume,
+            ),
+            (Method::POST, "/api/v2/consume/abc", ApiKeyScope::Consume),
+            (Method::POST, "/api/v1/jobs/abc", ApiKeyScope::Consume),
+            (Method::GET, "/api/v1/jobs/abc", ApiKeyScope::Consume),
+            (
+                Method::GET,
+                "/api/v1/quota/abc/history",
+                ApiKeyScope::ReadUsage,
+            ),
+            (Method::GET, "/api/v1/usage/abc", ApiKeyScope::ReadUsage),
+            (Method::GET, "/api/v1/incidents", ApiKeyScope::ReadUsage),
+            (
+                Method::POST,
+                "/api/v1/incidents/abc/resolve",
+                ApiKeyScope::ManageIncidents,
+            ),
+            (
+                Method::GET,
+                "/api/v1/statements/2025-11",
+                ApiKeyScope::ReadBilling,
+            ),
+            (
+                Method::GET,
+                "/api/v1/billing/invoices/2025-11",
+                ApiKeyScope::ReadBilling,
+            ),
+            (Method::DELETE, "/api/v1/keys/abc", ApiKeyScope::ManageKeys),
+            (
+                Method::PUT,
+                "/api/v1/webhooks/abc",
+                ApiKeyScope::ManageWebhooks,
+            ),
+            (
+                Method::POST,
+                "/api/v1/privacy/erasure",
+                ApiKeyScope::ManagePrivacy,
+            ),
+        ];
+        for (method, path, scope) in cases {
+            assert_eq!(required_scope(&method, path), scope, "{} {}", method, path);
+        }
+    }
+}
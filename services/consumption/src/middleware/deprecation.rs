@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use tracing::warn;
+
+/// v1 route prefixes superseded by a v2 route, as (v1 prefix, v2 prefix)
+const SUCCESSOR_ROUTES: &[(&str, &str)] = &[("/api/v1/consume/", "/api/v2/consume/")];
+
+/// Deprecation announcement for v1 routes that have a v2 successor
+#[derive(Clone, Default)]
+pub struct ApiDeprecation {
+    /// Pre-formatted `Sunset` header (HTTP-date), if a sunset date is set
+    sunset: Option<HeaderValue>,
+}
+
+impl ApiDeprecation {
+    /// Read the v1 sunset date from `API_V1_SUNSET` (RFC 3339 or YYYY-MM-DD)
+    pub fn from_env() -> Self {
+        let sunset = std::env::var("API_V1_SUNSET").ok().and_then(|value| {
+            let parsed = parse_sunset(&value);
+            if parsed.is_none() {
+                warn!(value = %value, "Ignoring invalid API_V1_SUNSET");
+            }
+            parsed
+        });
+
+        Self::new(sunset)
+    }
+
+    pub fn new(sunset: Option<DateTime<Utc>>) -> Self {
+        Self {
+            sunset: sunset.and_then(|at| HeaderValue::from_str(&http_date(at)).ok()),
+        }
+    }
+}
+
+fn parse_sunset(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+                .map(|at| at.and_utc())
+        })
+}
+
+/// Format a timestamp as an HTTP-date (RFC 9110)
+fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Successor of a deprecated v1 path, if it has one. Routes below a
+/// superseded one, like `/api/v1/consume/:serviceId/stream`, have none.
+fn successor_path(path: &str) -> Option<String> {
+    SUCCESSOR_ROUTES.iter().find_map(|(v1, v2)| {
+        path.strip_prefix(v1)
+            .filter(|rest| !rest.contains('/'))
+            .map(|rest| format!("{}{}", v2, rest))
+    })
+}
+
+/// Deprecation middleware - announces v1 sunset on routes with a v2 successor
+pub async fn deprecation_middleware(
+    State(deprecation): State<ApiDeprecation>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let successor = successor_path(request.uri().path());
+    let mut response = next.run(request).await;
+
+    if let Some(successor) = successor {
+        let headers = response.headers_mut();
+        headers.insert("Deprecation", HeaderValue::from_static("true"));
+
+        if let Some(sunset) = &deprecation.sunset {
+            headers.insert("Sunset", sunset.clone());
+        }
+
+        if let Ok(link) =
+            HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor))
+        {
+            headers.insert("Link", link);
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_successor_and_sunset_headers() {
+        let id = "5f0c6e3e-8d7a-4b5e-9a43-2f3d1c0b9e11";
+        assert_eq!(
+            successor_path(&format!("/api/v1/consume/{}", id)),
+            Some(format!("/api/v2/consume/{}", id))
+        );
+        assert_eq!(successor_path("/api/v1/keys"), None);
+        assert_eq!(
+            successor_path(&format!("/api/v1/consume/{}/stream", id)),
+            None
+        );
+
+        let sunset = parse_sunset("2027-03-31").unwrap();
+        assert_eq!(http_date(sunset), "Wed, 31 Mar 2027 00:00:00 GMT");
+    }
+}
@@ -6,10 +6,11 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use prometheus::{
-    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
 };
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::error;
 
 lazy_static::lazy_static! {
@@ -52,6 +53,165 @@ lazy_static::lazy_static! {
         &["service_id", "tier"]
     )
     .expect("Failed to create QUOTA_EXCEEDED_TOTAL metric");
+
+    static ref CONCURRENCY_LIMITS_EXCEEDED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "concurrency_limits_exceeded_total",
+            "Requests rejected for exceeding the consumer's in-flight limit"
+        ),
+        &["service_id", "tier"]
+    )
+    .expect("Failed to create CONCURRENCY_LIMITS_EXCEEDED_TOTAL metric");
+
+    static ref RESPONSE_CACHE_LOOKUPS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("response_cache_lookups_total", "Response cache lookups by result"),
+        &["service_id", "result"]
+    )
+    .expect("Failed to create RESPONSE_CACHE_LOOKUPS_TOTAL metric");
+
+    static ref RATE_LIMIT_QUEUE_DEPTH: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("rate_limit_queue_depth", "Rate-limited requests waiting per tier"),
+        &["tier"]
+    )
+    .expect("Failed to create RATE_LIMIT_QUEUE_DEPTH metric");
+
+    static ref ANALYTICS_EVENTS_DROPPED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("analytics_events_dropped_total", "Total analytics events dropped on buffer overflow"),
+        &["event_type", "reason"]
+    )
+    .expect("Failed to create ANALYTICS_EVENTS_DROPPED_TOTAL metric");
+
+    static ref ANALYTICS_EVENTS_SPILLED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("analytics_events_spilled_total", "Total analytics events spilled to disk on buffer overflow"),
+        &["event_type"]
+    )
+    .expect("Failed to create ANALYTICS_EVENTS_SPILLED_TOTAL metric");
+
+    static ref ANALYTICS_EVENTS_REPLAYED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("analytics_events_replayed_total", "Total spilled analytics events delivered from disk"),
+        &["event_type"]
+    )
+    .expect("Failed to create ANALYTICS_EVENTS_REPLAYED_TOTAL metric");
+
+    // Kafka delivery outcomes: delivered, retried, spooled, replayed
+    static ref ANALYTICS_DELIVERIES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("analytics_deliveries_total", "Analytics events by Kafka delivery outcome"),
+        &["outcome"]
+    )
+    .expect("Failed to create ANALYTICS_DELIVERIES_TOTAL metric");
+
+    // Analytics events mirrored as they are emitted; no consumer or
+    // free-form labels, so series stay bounded
+    static ref ANALYTICS_EVENTS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("analytics_events_total", "Analytics events emitted, by type and priority"),
+        &["event_type", "priority", "service_id", "severity"]
+    )
+    .expect("Failed to create ANALYTICS_EVENTS_TOTAL metric");
+
+    static ref MARKETPLACE_OVERHEAD_SECONDS: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "marketplace_overhead_seconds",
+            "Latency added by the marketplace on top of the provider call"
+        )
+        .buckets(vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25]),
+        &["service_id"]
+    )
+    .expect("Failed to create MARKETPLACE_OVERHEAD_SECONDS metric");
+
+    pub static ref HTTP_REQUESTS_IN_FLIGHT: IntGauge = IntGauge::new(
+        "http_requests_in_flight",
+        "HTTP requests currently being served"
+    )
+    .expect("Failed to create HTTP_REQUESTS_IN_FLIGHT metric");
+
+    pub static ref UPSTREAM_REQUESTS_IN_FLIGHT: IntGauge = IntGauge::new(
+        "upstream_requests_in_flight",
+        "Requests currently waiting on an LLM provider"
+    )
+    .expect("Failed to create UPSTREAM_REQUESTS_IN_FLIGHT metric");
+
+    static ref ANALYTICS_QUEUE_DEPTH: IntGauge = IntGauge::new(
+        "analytics_queue_depth",
+        "Analytics events buffered awaiting flush"
+    )
+    .expect("Failed to create ANALYTICS_QUEUE_DEPTH metric");
+
+    static ref ADMISSION_DECISIONS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("admission_decisions_total", "Consumption admission decisions by tier"),
+        &["tier", "outcome"]
+    )
+    .expect("Failed to create ADMISSION_DECISIONS_TOTAL metric");
+
+    static ref PARAMETER_POLICY_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "parameter_policy_total",
+            "Request parameters clamped or rejected by tier parameter policies"
+        ),
+        &["tier", "parameter", "outcome"]
+    )
+    .expect("Failed to create PARAMETER_POLICY_TOTAL metric");
+
+    static ref CANARY_PROBES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("canary_probes_total", "Total synthetic canary probes by outcome"),
+        &["service_id", "outcome"]
+    )
+    .expect("Failed to create CANARY_PROBES_TOTAL metric");
+
+    static ref PLUGIN_INVOCATIONS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("plugin_invocations_total", "Total Wasm plugin invocations by outcome"),
+        &["plugin", "outcome"]
+    )
+    .expect("Failed to create PLUGIN_INVOCATIONS_TOTAL metric");
+
+    static ref PII_DETECTIONS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("pii_detections_total", "Total PII detections by the built-in filter"),
+        &["service_id", "category", "action"]
+    )
+    .expect("Failed to create PII_DETECTIONS_TOTAL metric");
+
+    static ref CONTENT_SCANS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("content_scans_total", "Total LLM-Shield content scans by verdict"),
+        &["service_id", "content", "verdict"]
+    )
+    .expect("Failed to create CONTENT_SCANS_TOTAL metric");
+
+    // Redis keyspace audit
+    static ref REDIS_KEYS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("redis_keys", "Redis keys per key pattern at the last audit"),
+        &["pattern"]
+    )
+    .expect("Failed to create REDIS_KEYS metric");
+
+    static ref REDIS_KEY_MEMORY_BYTES: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("redis_key_memory_bytes", "Estimated Redis memory per key pattern"),
+        &["pattern"]
+    )
+    .expect("Failed to create REDIS_KEY_MEMORY_BYTES metric");
+
+    static ref REDIS_KEYS_WITHOUT_TTL: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("redis_keys_without_ttl", "Sampled Redis keys per key pattern with no TTL"),
+        &["pattern"]
+    )
+    .expect("Failed to create REDIS_KEYS_WITHOUT_TTL metric");
+
+    static ref PROVIDER_CREDENTIAL_ACCESS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "provider_credential_access_total",
+            "Provider credential changes and decryptions by action"
+        ),
+        &["action"]
+    )
+    .expect("Failed to create PROVIDER_CREDENTIAL_ACCESS metric");
+
+    static ref RESPONSE_STAGE_DURATION_SECONDS: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "response_stage_duration_seconds",
+            "Time spent in each response pipeline stage"
+        )
+        .buckets(vec![0.00001, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.25]),
+        &["stage"]
+    )
+    .expect("Failed to create RESPONSE_STAGE_DURATION_SECONDS metric");
 }
 
 /// Initialize Prometheus registry with metrics
@@ -82,9 +242,118 @@ pub fn init_metrics() -> Registry {
         .register(Box::new(QUOTA_EXCEEDED_TOTAL.clone()))
         .expect("Failed to register QUOTA_EXCEEDED_TOTAL");
 
+    registry
+        .register(Box::new(CONCURRENCY_LIMITS_EXCEEDED_TOTAL.clone()))
+        .expect("Failed to register CONCURRENCY_LIMITS_EXCEEDED_TOTAL");
+
+    registry
+        .register(Box::new(RESPONSE_CACHE_LOOKUPS_TOTAL.clone()))
+        .expect("Failed to register RESPONSE_CACHE_LOOKUPS_TOTAL");
+
+    registry
+        .register(Box::new(RATE_LIMIT_QUEUE_DEPTH.clone()))
+        .expect("Failed to register RATE_LIMIT_QUEUE_DEPTH");
+
+    registry
+        .register(Box::new(ANALYTICS_EVENTS_DROPPED_TOTAL.clone()))
+        .expect("Failed to register ANALYTICS_EVENTS_DROPPED_TOTAL");
+
+    registry
+        .register(Box::new(ANALYTICS_EVENTS_SPILLED_TOTAL.clone()))
+        .expect("Failed to register ANALYTICS_EVENTS_SPILLED_TOTAL");
+
+    registry
+        .register(Box::new(ANALYTICS_EVENTS_REPLAYED_TOTAL.clone()))
+        .expect("Failed to register ANALYTICS_EVENTS_REPLAYED_TOTAL");
+
+    registry
+        .register(Box::new(ANALYTICS_DELIVERIES_TOTAL.clone()))
+        .expect("Failed to register ANALYTICS_DELIVERIES_TOTAL");
+
+    registry
+        .register(Box::new(ANALYTICS_EVENTS_TOTAL.clone()))
+        .expect("Failed to register ANALYTICS_EVENTS_TOTAL");
+
+    registry
+        .register(Box::new(MARKETPLACE_OVERHEAD_SECONDS.clone()))
+        .expect("Failed to register MARKETPLACE_OVERHEAD_SECONDS");
+
+    registry
+        .register(Box::new(HTTP_REQUESTS_IN_FLIGHT.clone()))
+        .expect("Failed to register HTTP_REQUESTS_IN_FLIGHT");
+
+    registry
+        .register(Box::new(UPSTREAM_REQUESTS_IN_FLIGHT.clone()))
+        .expect("Failed to register UPSTREAM_REQUESTS_IN_FLIGHT");
+
+    registry
+        .register(Box::new(ANALYTICS_QUEUE_DEPTH.clone()))
+        .expect("Failed to register ANALYTICS_QUEUE_DEPTH");
+
+    registry
+        .register(Box::new(ADMISSION_DECISIONS_TOTAL.clone()))
+        .expect("Failed to register ADMISSION_DECISIONS_TOTAL");
+
+    registry
+        .register(Box::new(PARAMETER_POLICY_TOTAL.clone()))
+        .expect("Failed to register PARAMETER_POLICY_TOTAL");
+
+    registry
+        .register(Box::new(CANARY_PROBES_TOTAL.clone()))
+        .expect("Failed to register CANARY_PROBES_TOTAL");
+
+    registry
+        .register(Box::new(PLUGIN_INVOCATIONS_TOTAL.clone()))
+        .expect("Failed to register PLUGIN_INVOCATIONS_TOTAL");
+
+    registry
+        .register(Box::new(PII_DETECTIONS_TOTAL.clone()))
+        .expect("Failed to register PII_DETECTIONS_TOTAL");
+
+    registry
+        .register(Box::new(CONTENT_SCANS_TOTAL.clone()))
+        .expect("Failed to register CONTENT_SCANS_TOTAL");
+
+    registry
+        .register(Box::new(REDIS_KEYS.clone()))
+        .expect("Failed to register REDIS_KEYS");
+
+    registry
+        .register(Box::new(REDIS_KEY_MEMORY_BYTES.clone()))
+        .expect("Failed to register REDIS_KEY_MEMORY_BYTES");
+
+    registry
+        .register(Box::new(REDIS_KEYS_WITHOUT_TTL.clone()))
+        .expect("Failed to register REDIS_KEYS_WITHOUT_TTL");
+
+    registry
+        .register(Box::new(PROVIDER_CREDENTIAL_ACCESS.clone()))
+        .expect("Failed to register PROVIDER_CREDENTIAL_ACCESS");
+
+    registry
+        .register(Box::new(RESPONSE_STAGE_DURATION_SECONDS.clone()))
+        .expect("Failed to register RESPONSE_STAGE_DURATION_SECONDS");
+
     registry
 }
 
+/// Tracks a request for the in-flight gauge until dropped, so requests
+/// cancelled by a disconnect or timeout are counted out too
+struct InFlightGuard;
+
+impl InFlightGuard {
+    fn new() -> Self {
+        HTTP_REQUESTS_IN_FLIGHT.inc();
+        Self
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        HTTP_REQUESTS_IN_FLIGHT.dec();
+    }
+}
+
 /// Metrics middleware - records HTTP metrics
 pub async fn metrics_middleware(
     request: Request,
@@ -94,7 +363,9 @@ pub async fn metrics_middleware(
     let method = request.method().to_string();
     let path = request.uri().path().to_string();
 
+    let in_flight = InFlightGuard::new();
     let response = next.run(request).await;
+    drop(in_flight);
 
     let duration = start.elapsed().as_secs_f64();
     let status = response.status().as_u16().to_string();
@@ -162,4 +433,129 @@ pub mod record {
             .with_label_values(&[&service_id.to_string(), tier])
             .inc();
     }
+
+    pub fn concurrency_limit_exceeded(service_id: Uuid, tier: &str) {
+        CONCURRENCY_LIMITS_EXCEEDED_TOTAL
+            .with_label_values(&[&service_id.to_string(), tier])
+            .inc();
+    }
+
+    pub fn response_cache_lookup(service_id: Uuid, hit: bool) {
+        RESPONSE_CACHE_LOOKUPS_TOTAL
+            .with_label_values(&[&service_id.to_string(), if hit { "hit" } else { "miss" }])
+            .inc();
+    }
+
+    pub fn rate_limit_queue_depth(tier: &str, depth: usize) {
+        RATE_LIMIT_QUEUE_DEPTH
+            .with_label_values(&[tier])
+            .set(depth as i64);
+    }
+
+    pub fn analytics_event_dropped(event_type: &str, reason: &str) {
+        ANALYTICS_EVENTS_DROPPED_TOTAL
+            .with_label_values(&[event_type, reason])
+            .inc();
+    }
+
+    pub fn analytics_event_spilled(event_type: &str) {
+        ANALYTICS_EVENTS_SPILLED_TOTAL
+            .with_label_values(&[event_type])
+            .inc();
+    }
+
+    pub fn analytics_event_replayed(event_type: &str) {
+        ANALYTICS_EVENTS_REPLAYED_TOTAL
+            .with_label_values(&[event_type])
+            .inc();
+    }
+
+    #[cfg(feature = "kafka")]
+    pub fn analytics_delivery(outcome: &str, count: usize) {
+        ANALYTICS_DELIVERIES_TOTAL
+            .with_label_values(&[outcome])
+            .inc_by(count as u64);
+    }
+
+    /// `service_id` is empty for events not tied to a service
+    pub fn analytics_event(
+        event_type: &str,
+        priority: &str,
+        service_id: Option<Uuid>,
+        severity: &str,
+    ) {
+        let service_id = service_id.map(|id| id.to_string()).unwrap_or_default();
+        ANALYTICS_EVENTS_TOTAL
+            .with_label_values(&[event_type, priority, &service_id, severity])
+            .inc();
+    }
+
+    pub fn marketplace_overhead(service_id: Uuid, overhead_ms: u64) {
+        MARKETPLACE_OVERHEAD_SECONDS
+            .with_label_values(&[&service_id.to_string()])
+            .observe(overhead_ms as f64 / 1000.0);
+    }
+
+    pub fn admission(tier: &str, admitted: bool) {
+        let outcome = if admitted { "admitted" } else { "rejected" };
+        ADMISSION_DECISIONS_TOTAL
+            .with_label_values(&[tier, outcome])
+            .inc();
+    }
+
+    pub fn parameter_policy(tier: &str, parameter: &str, outcome: &str) {
+        PARAMETER_POLICY_TOTAL
+            .with_label_values(&[tier, parameter, outcome])
+            .inc();
+    }
+
+    pub fn analytics_queue_depth(depth: usize) {
+        ANALYTICS_QUEUE_DEPTH.set(depth as i64);
+    }
+
+    pub fn canary_probe(service_id: Uuid, outcome: &str) {
+        CANARY_PROBES_TOTAL
+            .with_label_values(&[&service_id.to_string(), outcome])
+            .inc();
+    }
+
+    pub fn plugin_invocation(plugin: &str, outcome: &str) {
+        PLUGIN_INVOCATIONS_TOTAL
+            .with_label_values(&[plugin, outcome])
+            .inc();
+    }
+
+    pub fn pii_detections(service_id: Uuid, category: &str, action: &str, count: usize) {
+        PII_DETECTIONS_TOTAL
+            .with_label_values(&[&service_id.to_string(), category, action])
+            .inc_by(count as u64);
+    }
+
+    pub fn content_scan(service_id: Uuid, content: &str, verdict: &str) {
+        CONTENT_SCANS_TOTAL
+            .with_label_values(&[&service_id.to_string(), content, verdict])
+            .inc();
+    }
+
+    pub fn redis_keys(pattern: &str, keys: u64, memory_bytes: u64, without_ttl: u64) {
+        REDIS_KEYS.with_label_values(&[pattern]).set(keys as i64);
+        REDIS_KEY_MEMORY_BYTES
+            .with_label_values(&[pattern])
+            .set(memory_bytes as i64);
+        REDIS_KEYS_WITHOUT_TTL
+            .with_label_values(&[pattern])
+            .set(without_ttl as i64);
+    }
+
+    pub fn provider_credential_access(action: &str) {
+        PROVIDER_CREDENTIAL_ACCESS
+            .with_label_values(&[action])
+            .inc();
+    }
+
+    pub fn response_stage(stage: &str, elapsed: Duration) {
+        RESPONSE_STAGE_DURATION_SECONDS
+            .with_label_values(&[stage])
+            .observe(elapsed.as_secs_f64());
+    }
 }
@@ -1,7 +1,9 @@
 pub mod auth;
+pub mod deprecation;
 pub mod metrics;
 pub mod tracing;
 
-pub use auth::auth_middleware;
+pub use auth::{admin_auth_middleware, auth_middleware};
+pub use deprecation::{deprecation_middleware, ApiDeprecation};
 pub use metrics::{init_metrics, metrics_handler, metrics_middleware};
 pub use tracing::init_tracing;
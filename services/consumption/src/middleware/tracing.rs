@@ -7,11 +7,14 @@ use opentelemetry::{
     },
     KeyValue,
 };
+use llm_infra::logging::{LogFilterHandle, RateLimitLayer};
 use opentelemetry_jaeger::new_agent_pipeline;
 use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Registry};
 
 /// Initialize OpenTelemetry tracing with Jaeger
-pub fn init_tracing() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// Returns the handle the admin API uses to change the filter at runtime.
+pub fn init_tracing() -> Result<LogFilterHandle, Box<dyn std::error::Error>> {
     // Set up TraceContext propagator
     global::set_text_map_propagator(TraceContextPropagator::new());
 
@@ -45,17 +48,19 @@ pub fn init_tracing() -> Result<(), Box<dyn std::error::Error>> {
     let filter_layer = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new("info"))
         .unwrap();
+    let (filter_layer, log_filter) = LogFilterHandle::reloadable(filter_layer);
 
-    // Combine layers
+    // Combine layers; only console output is rate limited, Jaeger still
+    // receives every event
     let subscriber = Registry::default()
         .with(filter_layer)
-        .with(fmt_layer)
+        .with(RateLimitLayer::from_env(fmt_layer))
         .with(telemetry);
 
     // Set global subscriber
     tracing::subscriber::set_global_default(subscriber)?;
 
-    Ok(())
+    Ok(log_filter)
 }
 
 /// Shutdown tracing gracefully
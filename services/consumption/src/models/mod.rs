@@ -5,7 +5,7 @@ use uuid::Uuid;
 use validator::Validate;
 
 /// Service tier for rate limiting
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum ServiceTier {
     Basic,
@@ -14,7 +14,48 @@ pub enum ServiceTier {
 }
 
 impl ServiceTier {
-    /// Get rate limit for tier (requests per second)
+    pub const ALL: [ServiceTier; 3] = [ServiceTier::Basic, ServiceTier::Premium, ServiceTier::Enterprise];
+
+    /// Parse a lowercase tier name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "basic" => Some(ServiceTier::Basic),
+            "premium" => Some(ServiceTier::Premium),
+            "enterprise" => Some(ServiceTier::Enterprise),
+            _ => None,
+        }
+    }
+
+    /// Compiled-in limits, used until the tier catalog overrides them
+    pub fn default_limits(&self) -> TierLimits {
+        TierLimits {
+            rate_limit: self.rate_limit(),
+            burst_capacity: self.burst_capacity(),
+            quota_limit: self.quota_limit(),
+            overage: OveragePolicy::Block,
+            parameters: Some(self.default_parameters()),
+            max_concurrency: Some(self.max_concurrency()),
+        }
+    }
+
+    /// Compiled-in generation parameter policy
+    pub fn default_parameters(&self) -> ParameterPolicy {
+        let (default_max_tokens, max_tokens, max_temperature, max_batch_size) = match self {
+            ServiceTier::Basic => (256, 1024, 1.0, 1),
+            ServiceTier::Premium => (512, 4096, 1.5, 4),
+            ServiceTier::Enterprise => (1024, 32_768, 2.0, 16),
+        };
+        ParameterPolicy {
+            default_max_tokens,
+            max_tokens,
+            min_temperature: 0.0,
+            max_temperature,
+            max_batch_size,
+            enforcement: ParameterEnforcement::Reject,
+        }
+    }
+
+    /// Default rate limit for tier (requests per second)
     pub fn rate_limit(&self) -> u64 {
         match self {
             ServiceTier::Basic => 10,
@@ -23,7 +64,7 @@ impl ServiceTier {
         }
     }
 
-    /// Get burst capacity for tier
+    /// Default burst capacity for tier
     pub fn burst_capacity(&self) -> u32 {
         match self {
             ServiceTier::Basic => 20,
@@ -32,7 +73,25 @@ impl ServiceTier {
         }
     }
 
-    /// Get quota limit (tokens per month)
+    /// Default most requests a consumer may have in flight on a service
+    pub fn max_concurrency(&self) -> u32 {
+        match self {
+            ServiceTier::Basic => 4,
+            ServiceTier::Premium => 32,
+            ServiceTier::Enterprise => 256,
+        }
+    }
+
+    /// Lowercase tier name used in storage and metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceTier::Basic => "basic",
+            ServiceTier::Premium => "premium",
+            ServiceTier::Enterprise => "enterprise",
+        }
+    }
+
+    /// Default quota limit (tokens per month)
     pub fn quota_limit(&self) -> i64 {
         match self {
             ServiceTier::Basic => 100_000,
@@ -42,6 +101,159 @@ impl ServiceTier {
     }
 }
 
+/// What happens once a consumer has used its monthly quota
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OveragePolicy {
+    /// Reject requests until the quota resets
+    #[default]
+    Block,
+    /// Keep serving; usage past the quota is billed as overage
+    Allow,
+}
+
+/// What happens to a request whose parameters exceed its tier's policy
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterEnforcement {
+    /// Reject the request with `422`
+    #[default]
+    Reject,
+    /// Lower (or raise) the parameter to the nearest allowed value
+    Clamp,
+}
+
+/// Generation parameters a tier may request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParameterPolicy {
+    /// `max_tokens` used when the request doesn't set one
+    pub default_max_tokens: u32,
+    pub max_tokens: u32,
+    pub min_temperature: f32,
+    pub max_temperature: f32,
+    /// Completions per request (`n`)
+    pub max_batch_size: u32,
+    #[serde(default)]
+    pub enforcement: ParameterEnforcement,
+}
+
+impl ParameterPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_tokens == 0 || self.max_batch_size == 0 {
+            return Err("max_tokens and max_batch_size must be positive".to_string());
+        }
+        if self.default_max_tokens == 0 || self.default_max_tokens > self.max_tokens {
+            return Err("default_max_tokens must be between 1 and max_tokens".to_string());
+        }
+        if !(self.min_temperature >= 0.0 && self.min_temperature <= self.max_temperature) {
+            return Err("min_temperature must be between 0 and max_temperature".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Limits of a tier, as configured in the tier catalog
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TierLimits {
+    /// Requests per second
+    pub rate_limit: u64,
+    pub burst_capacity: u32,
+    /// Tokens per month
+    pub quota_limit: i64,
+    #[serde(default)]
+    pub overage: OveragePolicy,
+    /// Generation parameter policy; the tier's compiled-in policy when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<ParameterPolicy>,
+    /// Most requests a consumer may have in flight on a service; the tier's
+    /// compiled-in limit when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<u32>,
+}
+
+impl TierLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rate_limit == 0 {
+            return Err("rate_limit must be positive".to_string());
+        }
+        if self.burst_capacity == 0 {
+            return Err("burst_capacity must be positive".to_string());
+        }
+        if self.quota_limit < 0 {
+            return Err("quota_limit must not be negative".to_string());
+        }
+        if self.max_concurrency == Some(0) {
+            return Err("max_concurrency must be positive".to_string());
+        }
+        if let Some(parameters) = &self.parameters {
+            parameters.validate()?;
+        }
+        Ok(())
+    }
+
+    /// These limits at `percent` of their size, for a consumer whose limits
+    /// are still ramping up. Rate limit and burst never drop below 1.
+    pub fn ramped(mut self, percent: u32) -> Self {
+        let percent = percent.min(100);
+        self.rate_limit = (self.rate_limit * percent as u64 / 100).max(1);
+        self.burst_capacity = (self.burst_capacity as u64 * percent as u64 / 100).max(1) as u32;
+        self.quota_limit = (self.quota_limit as i128 * percent as i128 / 100) as i64;
+        self
+    }
+}
+
+/// Permission an API key grants on the consumer API
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ApiKeyScope {
+    /// Consume services
+    #[serde(rename = "consume")]
+    Consume,
+    /// Read quota, usage, SLA reports, analytics and incidents
+    #[serde(rename = "read:usage")]
+    ReadUsage,
+    /// Read statements and invoices
+    #[serde(rename = "read:billing")]
+    ReadBilling,
+    #[serde(rename = "manage:keys")]
+    ManageKeys,
+    #[serde(rename = "manage:webhooks")]
+    ManageWebhooks,
+    /// Acknowledge and resolve incidents
+    #[serde(rename = "manage:incidents")]
+    ManageIncidents,
+    /// Export and erase personal data
+    #[serde(rename = "manage:privacy")]
+    ManagePrivacy,
+}
+
+impl ApiKeyScope {
+    pub const ALL: [ApiKeyScope; 7] = [
+        ApiKeyScope::Consume,
+        ApiKeyScope::ReadUsage,
+        ApiKeyScope::ReadBilling,
+        ApiKeyScope::ManageKeys,
+        ApiKeyScope::ManageWebhooks,
+        ApiKeyScope::ManageIncidents,
+        ApiKeyScope::ManagePrivacy,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::Consume => "consume",
+            ApiKeyScope::ReadUsage => "read:usage",
+            ApiKeyScope::ReadBilling => "read:billing",
+            ApiKeyScope::ManageKeys => "manage:keys",
+            ApiKeyScope::ManageWebhooks => "manage:webhooks",
+            ApiKeyScope::ManageIncidents => "manage:incidents",
+            ApiKeyScope::ManagePrivacy => "manage:privacy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == name)
+    }
+}
+
 /// API key model
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ApiKey {
@@ -54,6 +266,8 @@ pub struct ApiKey {
     pub expires_at: Option<DateTime<Utc>>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub metadata: sqlx::types::Json<serde_json::Value>,
+    /// Scopes the key is limited to; every scope when unset
+    pub scopes: Option<Vec<String>>,
 }
 
 impl ApiKey {
@@ -71,6 +285,20 @@ impl ApiKey {
         true
     }
 
+    pub fn has_scope(&self, scope: ApiKeyScope) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.iter().any(|s| s == scope.as_str()))
+    }
+
+    /// Scopes the key grants
+    pub fn get_scopes(&self) -> Vec<ApiKeyScope> {
+        ApiKeyScope::ALL
+            .into_iter()
+            .filter(|scope| self.has_scope(*scope))
+            .collect()
+    }
+
     pub fn get_tier(&self) -> ServiceTier {
         match self.tier.to_lowercase().as_str() {
             "basic" => ServiceTier::Basic,
@@ -81,6 +309,45 @@ impl ApiKey {
     }
 }
 
+/// Credential a consumer API request was authenticated with: an API key or
+/// a bearer token from the configured identity provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Caller {
+    pub consumer_id: Uuid,
+    /// Scopes the credential grants; every scope when unset
+    pub scopes: Option<Vec<ApiKeyScope>>,
+    /// Tier from a token's claims. API keys carry their tier per service, so
+    /// the consumer's key for the service decides when unset.
+    pub tier: Option<ServiceTier>,
+}
+
+impl Caller {
+    pub fn has_scope(&self, scope: ApiKeyScope) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.contains(&scope))
+    }
+
+    /// Names of `scopes` the caller doesn't have
+    pub fn missing_scopes(&self, scopes: &[ApiKeyScope]) -> Vec<&'static str> {
+        scopes
+            .iter()
+            .filter(|scope| !self.has_scope(**scope))
+            .map(|scope| scope.as_str())
+            .collect()
+    }
+}
+
+impl From<&ApiKey> for Caller {
+    fn from(key: &ApiKey) -> Self {
+        Self {
+            consumer_id: key.consumer_id,
+            scopes: key.scopes.is_some().then(|| key.get_scopes()),
+            tier: None,
+        }
+    }
+}
+
 /// Service information
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Service {
@@ -96,7 +363,9 @@ pub struct Service {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricingModel {
-    pub model: String, // per-token, per-request, subscription
+    pub model: String, // per-token, tiered, volume, per-request, subscription
+    /// A single rate, or the brackets of tiered and volume pricing in
+    /// ascending `up_to` order
     pub rates: Vec<PricingRate>,
 }
 
@@ -105,6 +374,37 @@ pub struct PricingRate {
     pub tier: String,
     pub rate: f64,
     pub unit: String, // token, request, month
+    /// Tokens per request up to which this bracket applies; open on the last
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub up_to: Option<u64>,
+    /// Per-token rate of prompt tokens, instead of `rate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_rate: Option<f64>,
+    /// Per-token rate of completion tokens, instead of `rate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_rate: Option<f64>,
+}
+
+impl PricingModel {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.model != "tiered" && self.model != "volume" {
+            return Ok(());
+        }
+        let Some((last, brackets)) = self.rates.split_last() else {
+            return Err(format!("{} pricing needs at least one bracket", self.model));
+        };
+        if last.up_to.is_some() {
+            return Err("the last bracket must leave up_to open".to_string());
+        }
+        let mut lower = 0;
+        for bracket in brackets {
+            match bracket.up_to {
+                Some(up_to) if up_to > lower => lower = up_to,
+                _ => return Err("bracket up_to must be set and ascending".to_string()),
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +412,44 @@ pub struct SlaConfig {
     pub availability: f64,
     pub max_latency_ms: u64,
     pub timeout_ms: u64,
+    /// Maximum acceptable marketplace overhead (p95) on top of the provider call
+    #[serde(default)]
+    pub max_overhead_ms: Option<u64>,
+}
+
+/// Per-request latency breakdown
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RequestTimings {
+    /// Lookup, rate limit and quota checks before routing
+    pub admission_ms: u64,
+    /// Time spent waiting on the provider, including retries
+    pub provider_ms: u64,
+    /// Cost calculation and usage/quota bookkeeping after routing
+    pub post_processing_ms: u64,
+    /// Response pipeline stages, included in `post_processing_ms`
+    #[serde(default)]
+    pub response_stages: ResponseStageTimings,
+}
+
+/// Time spent in each response pipeline stage, in microseconds; `None` for
+/// stages that didn't run
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ResponseStageTimings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shield_scan_us: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profanity_mask_us: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_validation_us: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_us: Option<u64>,
+}
+
+impl RequestTimings {
+    /// Latency added by the marketplace itself
+    pub fn overhead_ms(&self) -> u64 {
+        self.admission_ms + self.post_processing_ms
+    }
 }
 
 /// Consumption request
@@ -126,6 +464,10 @@ pub struct ConsumeRequest {
     #[serde(default = "default_temperature")]
     pub temperature: f32,
 
+    /// Completions to generate in one call (batch size)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+
     #[serde(default)]
     pub metadata: serde_json::Value,
 }
@@ -144,6 +486,84 @@ pub struct ConsumeResponse {
     pub latency_ms: u64,
 }
 
+/// Consumption request (v2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumeRequestV2 {
+    pub prompt: String,
+
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    #[serde(default = "default_temperature")]
+    pub temperature: f32,
+
+    /// Completions to generate in one call (batch size)
+    #[serde(default)]
+    pub n: Option<u32>,
+
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+
+    /// Deliver the response as server-sent events
+    #[serde(default)]
+    pub stream: bool,
+}
+
+impl From<ConsumeRequestV2> for ConsumeRequest {
+    fn from(request: ConsumeRequestV2) -> Self {
+        Self {
+            prompt: request.prompt,
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            n: request.n,
+            metadata: request.metadata,
+        }
+    }
+}
+
+/// Consumption response (v2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumeResponseV2 {
+    pub request_id: Uuid,
+    pub response: serde_json::Value,
+    pub usage: UsageInfo,
+    pub cost: CostInfo,
+    pub latency_ms: u64,
+    pub timings: RequestTimings,
+}
+
+/// Async consumption job, as returned when it's queued and polled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumptionJobStatus {
+    pub job_id: Uuid,
+    pub service_id: Uuid,
+    pub status: String, // queued, running, succeeded, failed
+    /// Runs so far, including retries after transient failures
+    pub attempts: i32,
+    /// The consume response, once the job succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ConsumeResponseV2>,
+    /// Why the job failed, with the code the synchronous API would return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiErrorDetail>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Structured error envelope, rendered by `AppError`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiErrorBody {
+    pub error: ApiErrorDetail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiErrorDetail {
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<u64>,
+}
+
 /// Usage information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsageInfo {
@@ -173,6 +593,8 @@ pub struct UsageRecord {
     pub cost: sqlx::types::Json<CostInfo>,
     pub status: String,
     pub error: Option<sqlx::types::Json<serde_json::Value>>,
+    /// Request context, e.g. the calling client under `client`
+    pub metadata: Option<sqlx::types::Json<serde_json::Value>>,
 }
 
 /// Quota status
@@ -182,10 +604,115 @@ pub struct QuotaStatus {
     pub consumer_id: Uuid,
     pub tier: ServiceTier,
     pub used_tokens: i64,
+    /// Tier quota plus `granted_tokens`
     pub total_tokens: i64,
+    /// Overage granted by an admin for this month
+    pub granted_tokens: i64,
     pub remaining_tokens: i64,
     pub reset_at: DateTime<Utc>,
     pub exceeded: bool,
+    pub overage: OveragePolicy,
+    /// Warm-up of a new consumer's limits, while it lasts; `total_tokens`
+    /// already reflects it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rampup: Option<RampupStatus>,
+}
+
+/// Where a new consumer is in their organization's limit ramp-up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RampupStatus {
+    /// Percent of the tier's limits in force now
+    pub percent: u32,
+    pub start_percent: u32,
+    pub ramp_days: u32,
+    /// Creation of the consumer's first API key
+    pub started_at: DateTime<Utc>,
+    /// When the full tier limits apply
+    pub ends_at: DateTime<Utc>,
+}
+
+impl QuotaStatus {
+    /// Whether a request may proceed: within quota, or the tier allows overage
+    pub fn admits(&self) -> bool {
+        !self.exceeded || self.overage == OveragePolicy::Allow
+    }
+}
+
+/// Window size of a quota history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaGranularity {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl QuotaGranularity {
+    /// Postgres `date_trunc` field name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaGranularity::Hour => "hour",
+            QuotaGranularity::Day => "day",
+            QuotaGranularity::Week => "week",
+            QuotaGranularity::Month => "month",
+        }
+    }
+}
+
+/// Token consumption within one history window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaHistoryPoint {
+    pub window_start: DateTime<Utc>,
+    pub tokens: i64,
+    pub requests: i64,
+    /// Tokens used in the quota month up to the end of this window
+    pub used_tokens: i64,
+    pub remaining_tokens: i64,
+}
+
+/// Historical quota consumption for a service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaHistory {
+    pub service_id: Uuid,
+    pub consumer_id: Uuid,
+    pub tier: ServiceTier,
+    pub granularity: QuotaGranularity,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub total_tokens: i64,
+    pub points: Vec<QuotaHistoryPoint>,
+}
+
+/// How a service's rate limit is enforced
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitAlgorithm {
+    /// Refills `rate_limit` tokens per second up to `burst_capacity`
+    #[default]
+    TokenBucket,
+    /// At most `rate_limit * 60` requests in any 60 second window; the burst
+    /// capacity doesn't apply
+    SlidingWindow,
+}
+
+impl RateLimitAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitAlgorithm::TokenBucket => "token_bucket",
+            RateLimitAlgorithm::SlidingWindow => "sliding_window",
+        }
+    }
+
+    /// Parse a stored algorithm name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "token_bucket" => Some(RateLimitAlgorithm::TokenBucket),
+            "sliding_window" => Some(RateLimitAlgorithm::SlidingWindow),
+            _ => None,
+        }
+    }
 }
 
 /// Rate limit status
@@ -193,9 +720,14 @@ pub struct QuotaStatus {
 pub struct RateLimitStatus {
     pub exceeded: bool,
     pub retry_after_seconds: Option<u64>,
+    /// Requests per second for the token bucket, per window for the sliding
+    /// window
     pub limit: u64,
     pub remaining: u32,
     pub reset_at: DateTime<Utc>,
+    /// Algorithm the limit was enforced with
+    #[serde(default)]
+    pub algorithm: RateLimitAlgorithm,
 }
 
 /// Create API key request
@@ -208,6 +740,10 @@ pub struct CreateApiKeyRequest {
 
     #[serde(default)]
     pub expires_in_days: Option<i64>,
+
+    /// Scopes to limit the key to; every scope when unset
+    #[serde(default)]
+    pub scopes: Option<Vec<ApiKeyScope>>,
 }
 
 /// API key response (includes plaintext key once)
@@ -217,10 +753,29 @@ pub struct ApiKeyResponse {
     pub key: String, // Only returned on creation
     pub service_id: Uuid,
     pub tier: ServiceTier,
+    pub scopes: Vec<ApiKeyScope>,
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
 }
 
+/// Replacement of a rotated API key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotatedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKeyResponse,
+    pub previous_key_id: Uuid,
+    /// When the rotated key stops working
+    pub previous_key_expires_at: DateTime<Utc>,
+}
+
+/// Rotate API key request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RotateApiKeyRequest {
+    /// How long the old key keeps working; the service's default when unset
+    #[serde(default)]
+    pub grace_period_secs: Option<u64>,
+}
+
 /// Usage statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsageStats {
@@ -235,6 +790,28 @@ pub struct UsageStats {
     pub error_rate: f64,
 }
 
+/// Consumer overview for operators
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ConsumerSummary {
+    pub consumer_id: Uuid,
+    pub active_keys: i64,
+    pub total_keys: i64,
+    pub last_key_created_at: DateTime<Utc>,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub suspension_reason: Option<String>,
+}
+
+/// Requests from one SDK version by one consumer
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SdkVersionUsage {
+    pub consumer_id: Uuid,
+    pub sdk: String,
+    pub sdk_version: String,
+    pub requests: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
 /// SLA violation record
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct SLAViolation {
@@ -247,6 +824,21 @@ pub struct SLAViolation {
     pub severity: String,
 }
 
+/// Incident grouping related SLA violations
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Incident {
+    pub id: Uuid,
+    pub service_id: Uuid,
+    pub metric: String,
+    pub severity: String,
+    pub status: String, // open, acknowledged, resolved
+    pub violation_count: i64,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
 /// SLA status for a service
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SLAStatus {
@@ -259,6 +851,8 @@ pub struct SLAStatus {
     pub error_rate: f64,
     pub error_rate_threshold: f64,
     pub error_rate_compliant: bool,
+    /// Failures attributed to the marketplace rather than the provider
+    pub marketplace_error_rate: f64,
     pub uptime_percentage: f64,
     pub uptime_threshold: f64,
     pub uptime_compliant: bool,
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::models::{ConsumerSummary, SLAViolation, SdkVersionUsage};
+
+/// Operator-facing queries and actions that span consumers and services
+#[derive(Clone)]
+pub struct AdminService {
+    db: Arc<PgPool>,
+}
+
+impl AdminService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    /// List consumers known from issued API keys
+    pub async fn list_consumers(&self, limit: i64) -> Result<Vec<ConsumerSummary>> {
+        let consumers = sqlx::query_as::<_, ConsumerSummary>(
+            r#"
+            SELECT
+                k.consumer_id,
+                COUNT(*) FILTER (WHERE k.revoked_at IS NULL) AS active_keys,
+                COUNT(*) AS total_keys,
+                MAX(k.created_at) AS last_key_created_at,
+                s.suspended_at,
+                s.reason AS suspension_reason
+            FROM api_keys k
+            LEFT JOIN consumer_suspensions s ON s.consumer_id = k.consumer_id
+            GROUP BY k.consumer_id, s.suspended_at, s.reason
+            ORDER BY last_key_created_at DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list consumers")?;
+
+        Ok(consumers)
+    }
+
+    /// Suspend a consumer; returns false if already suspended
+    pub async fn suspend_consumer(&self, consumer_id: Uuid, reason: Option<&str>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO consumer_suspensions (consumer_id, reason)
+            VALUES ($1, $2)
+            ON CONFLICT (consumer_id) DO NOTHING
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(reason)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to suspend consumer")?;
+
+        info!(consumer_id = %consumer_id, reason = ?reason, "Consumer suspended");
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Lift a suspension; returns false if the consumer was not suspended
+    pub async fn unsuspend_consumer(&self, consumer_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM consumer_suspensions WHERE consumer_id = $1")
+            .bind(consumer_id)
+            .execute(self.db.as_ref())
+            .await
+            .context("Failed to unsuspend consumer")?;
+
+        info!(consumer_id = %consumer_id, "Consumer unsuspended");
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Recent SLA violations across all services, newest first
+    pub async fn recent_violations(
+        &self,
+        since: Option<DateTime<Utc>>,
+        service_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<SLAViolation>> {
+        let violations = sqlx::query_as::<_, SLAViolation>(
+            r#"
+            SELECT id, service_id, metric, threshold, actual, timestamp, severity
+            FROM sla_violations
+            WHERE ($1::timestamptz IS NULL OR timestamp > $1)
+                AND ($2::uuid IS NULL OR service_id = $2)
+            ORDER BY timestamp DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(since)
+        .bind(service_id)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list SLA violations")?;
+
+        Ok(violations)
+    }
+
+    /// Requests per consumer and client SDK version since `since`, from the
+    /// client recorded in usage metadata. Requests without SDK headers or a
+    /// parseable `User-Agent` are grouped under `unknown`.
+    pub async fn sdk_versions(
+        &self,
+        since: DateTime<Utc>,
+        consumer_id: Option<Uuid>,
+        sdk: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<SdkVersionUsage>> {
+        let usage = sqlx::query_as::<_, SdkVersionUsage>(
+            r#"
+            SELECT
+                consumer_id,
+                COALESCE(metadata->'client'->>'sdk', 'unknown') AS sdk,
+                COALESCE(metadata->'client'->>'sdk_version', 'unknown') AS sdk_version,
+                COUNT(*) AS requests,
+                MIN(timestamp) AS first_seen,
+                MAX(timestamp) AS last_seen
+            FROM usage_records_all
+            WHERE timestamp > $1
+                AND ($2::uuid IS NULL OR consumer_id = $2)
+                AND ($3::text IS NULL OR metadata->'client'->>'sdk' = $3)
+            GROUP BY 1, 2, 3
+            ORDER BY consumer_id, requests DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(since)
+        .bind(consumer_id)
+        .bind(sdk)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to aggregate SDK versions")?;
+
+        Ok(usage)
+    }
+}
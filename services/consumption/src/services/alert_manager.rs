@@ -0,0 +1,354 @@
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::models::{Incident, SLAViolation};
+
+/// Default quiet period after which an incident is auto-resolved and
+/// further violations open a new incident
+const DEFAULT_INCIDENT_WINDOW_MINUTES: i64 = 30;
+
+/// Alert manager grouping SLA violations into incidents
+/// Alerts fire on incident state changes rather than on every violation
+#[derive(Clone)]
+pub struct AlertManager {
+    db: Arc<PgPool>,
+    window: Duration,
+    webhook: Option<AlertWebhook>,
+}
+
+/// Outbound webhook receiving alert notifications
+#[derive(Clone)]
+pub struct AlertWebhook {
+    client: Arc<Client>,
+    url: String,
+}
+
+impl AlertWebhook {
+    pub fn new(url: String) -> Self {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .expect("Failed to create HTTP client for alert webhooks");
+
+        Self {
+            client: Arc::new(client),
+            url,
+        }
+    }
+
+    /// Deliver a notification in the background
+    pub fn notify(&self, payload: serde_json::Value) {
+        let webhook = self.clone();
+
+        tokio::spawn(async move {
+            webhook.deliver(&payload).await;
+        });
+    }
+
+    /// Deliver a notification and wait for the response; `true` once the
+    /// receiver accepted it
+    pub async fn deliver(&self, payload: &serde_json::Value) -> bool {
+        match self.client.post(&self.url).json(payload).send().await {
+            Ok(response) if !response.status().is_success() => {
+                warn!(status = %response.status(), "Alert webhook rejected notification");
+                false
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to deliver alert webhook");
+                false
+            }
+            Ok(_) => true,
+        }
+    }
+}
+
+/// Incident lifecycle transitions reported to the webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncidentTransition {
+    Opened,
+    Escalated,
+    Acknowledged,
+    Resolved,
+}
+
+impl IncidentTransition {
+    fn event_name(&self) -> &'static str {
+        match self {
+            IncidentTransition::Opened => "incident.opened",
+            IncidentTransition::Escalated => "incident.escalated",
+            IncidentTransition::Acknowledged => "incident.acknowledged",
+            IncidentTransition::Resolved => "incident.resolved",
+        }
+    }
+}
+
+impl AlertManager {
+    pub fn new(db: PgPool) -> Self {
+        let window_minutes = std::env::var("INCIDENT_WINDOW_MINUTES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_INCIDENT_WINDOW_MINUTES);
+
+        Self {
+            db: Arc::new(db),
+            window: Duration::minutes(window_minutes),
+            webhook: None,
+        }
+    }
+
+    /// Post incident state changes to the given webhook URL
+    pub fn with_webhook(mut self, url: String) -> Self {
+        self.webhook = Some(AlertWebhook::new(url));
+        self
+    }
+
+    /// Group a violation into an active incident, opening one if needed
+    pub async fn handle_violation(&self, violation: &SLAViolation) -> Result<Incident> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin incident transaction")?;
+
+        let active = sqlx::query_as::<_, Incident>(
+            r#"
+            SELECT id, service_id, metric, severity, status, violation_count,
+                   first_seen_at, last_seen_at, acknowledged_at, resolved_at
+            FROM incidents
+            WHERE service_id = $1 AND metric = $2 AND status <> 'resolved'
+            FOR UPDATE
+            "#,
+        )
+        .bind(violation.service_id)
+        .bind(&violation.metric)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to look up active incident")?;
+
+        let (incident, transition) = match active {
+            Some(incident) if violation.timestamp - incident.last_seen_at < self.window => {
+                let escalated =
+                    violation.severity == "critical" && incident.severity != "critical";
+
+                let updated = sqlx::query_as::<_, Incident>(
+                    r#"
+                    UPDATE incidents
+                    SET violation_count = violation_count + 1,
+                        last_seen_at = $2,
+                        severity = CASE WHEN $3 = 'critical' THEN 'critical' ELSE severity END
+                    WHERE id = $1
+                    RETURNING id, service_id, metric, severity, status, violation_count,
+                              first_seen_at, last_seen_at, acknowledged_at, resolved_at
+                    "#,
+                )
+                .bind(incident.id)
+                .bind(violation.timestamp)
+                .bind(&violation.severity)
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to update incident")?;
+
+                (updated, escalated.then_some(IncidentTransition::Escalated))
+            }
+            stale => {
+                // A stale incident is closed before a new one is opened
+                if let Some(stale) = stale {
+                    sqlx::query(
+                        "UPDATE incidents SET status = 'resolved', resolved_at = NOW() WHERE id = $1",
+                    )
+                    .bind(stale.id)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to resolve stale incident")?;
+                }
+
+                let opened = sqlx::query_as::<_, Incident>(
+                    r#"
+                    INSERT INTO incidents (
+                        id, service_id, metric, severity, status, violation_count,
+                        first_seen_at, last_seen_at
+                    )
+                    VALUES ($1, $2, $3, $4, 'open', 1, $5, $5)
+                    RETURNING id, service_id, metric, severity, status, violation_count,
+                              first_seen_at, last_seen_at, acknowledged_at, resolved_at
+                    "#,
+                )
+                .bind(Uuid::new_v4())
+                .bind(violation.service_id)
+                .bind(&violation.metric)
+                .bind(&violation.severity)
+                .bind(violation.timestamp)
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to open incident")?;
+
+                (opened, Some(IncidentTransition::Opened))
+            }
+        };
+
+        tx.commit()
+            .await
+            .context("Failed to commit incident transaction")?;
+
+        match transition {
+            Some(transition) => self.notify(&incident, transition),
+            None => debug!(
+                incident_id = %incident.id,
+                violation_count = incident.violation_count,
+                "Violation grouped into existing incident"
+            ),
+        }
+
+        Ok(incident)
+    }
+
+    /// Acknowledge an open incident
+    pub async fn acknowledge(&self, incident_id: Uuid) -> Result<Option<Incident>> {
+        let incident = sqlx::query_as::<_, Incident>(
+            r#"
+            UPDATE incidents
+            SET status = 'acknowledged', acknowledged_at = NOW()
+            WHERE id = $1 AND status = 'open'
+            RETURNING id, service_id, metric, severity, status, violation_count,
+                      first_seen_at, last_seen_at, acknowledged_at, resolved_at
+            "#,
+        )
+        .bind(incident_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to acknowledge incident")?;
+
+        if let Some(incident) = &incident {
+            self.notify(incident, IncidentTransition::Acknowledged);
+        }
+
+        Ok(incident)
+    }
+
+    /// Resolve an open or acknowledged incident
+    pub async fn resolve(&self, incident_id: Uuid) -> Result<Option<Incident>> {
+        let incident = sqlx::query_as::<_, Incident>(
+            r#"
+            UPDATE incidents
+            SET status = 'resolved', resolved_at = NOW()
+            WHERE id = $1 AND status <> 'resolved'
+            RETURNING id, service_id, metric, severity, status, violation_count,
+                      first_seen_at, last_seen_at, acknowledged_at, resolved_at
+            "#,
+        )
+        .bind(incident_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to resolve incident")?;
+
+        if let Some(incident) = &incident {
+            self.notify(incident, IncidentTransition::Resolved);
+        }
+
+        Ok(incident)
+    }
+
+    /// Background job: resolve incidents with no violations within the window
+    pub async fn resolve_stale_incidents(&self) -> Result<usize> {
+        let cutoff = Utc::now() - self.window;
+
+        let resolved = sqlx::query_as::<_, Incident>(
+            r#"
+            UPDATE incidents
+            SET status = 'resolved', resolved_at = NOW()
+            WHERE status <> 'resolved' AND last_seen_at < $1
+            RETURNING id, service_id, metric, severity, status, violation_count,
+                      first_seen_at, last_seen_at, acknowledged_at, resolved_at
+            "#,
+        )
+        .bind(cutoff)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to resolve stale incidents")?;
+
+        for incident in &resolved {
+            self.notify(incident, IncidentTransition::Resolved);
+        }
+
+        Ok(resolved.len())
+    }
+
+    /// List incidents, newest first
+    pub async fn list_incidents(
+        &self,
+        service_id: Option<Uuid>,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Incident>> {
+        let incidents = sqlx::query_as::<_, Incident>(
+            r#"
+            SELECT id, service_id, metric, severity, status, violation_count,
+                   first_seen_at, last_seen_at, acknowledged_at, resolved_at
+            FROM incidents
+            WHERE ($1::uuid IS NULL OR service_id = $1)
+                AND ($2::text IS NULL OR status = $2)
+            ORDER BY last_seen_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(service_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list incidents")?;
+
+        Ok(incidents)
+    }
+
+    /// Log and deliver an incident state change
+    fn notify(&self, incident: &Incident, transition: IncidentTransition) {
+        if incident.severity == "critical"
+            && matches!(
+                transition,
+                IncidentTransition::Opened | IncidentTransition::Escalated
+            )
+        {
+            // In production, integrate with PagerDuty, Opsgenie, or similar
+            error!(
+                incident_id = %incident.id,
+                service_id = %incident.service_id,
+                metric = %incident.metric,
+                violation_count = incident.violation_count,
+                "CRITICAL SLA INCIDENT - Alert triggered"
+            );
+        } else {
+            info!(
+                incident_id = %incident.id,
+                service_id = %incident.service_id,
+                transition = transition.event_name(),
+                "Incident state changed"
+            );
+        }
+
+        if let Some(webhook) = &self.webhook {
+            webhook.notify(serde_json::json!({
+                "event": transition.event_name(),
+                "incident": incident,
+                // Traces the incident to the build that raised it
+                "build": llm_infra::build_info!(),
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_event_names() {
+        assert_eq!(IncidentTransition::Opened.event_name(), "incident.opened");
+        assert_eq!(IncidentTransition::Resolved.event_name(), "incident.resolved");
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use super::analytics_spool::{EventSpool, DEFAULT_SPOOL_MAX_BYTES};
+use super::analytics_streamer::AnalyticsEvent;
+use crate::middleware::metrics::record;
+
+/// Longest delay between retries of a batch
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Kafka delivery settings, read from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaConfig {
+    /// Comma-separated bootstrap servers
+    pub brokers: String,
+    pub topic: String,
+    /// Retries of a batch's failed events before they are spooled
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles with each attempt
+    pub retry_base_delay: Duration,
+    /// How long the producer tries to deliver each event
+    pub delivery_timeout: Duration,
+    /// Events Kafka didn't accept are appended here and resent every minute
+    pub spool_path: PathBuf,
+    pub spool_max_bytes: u64,
+}
+
+impl KafkaConfig {
+    /// Read `KAFKA_BROKERS`, `KAFKA_TOPIC`, `KAFKA_MAX_RETRIES`,
+    /// `KAFKA_RETRY_BASE_DELAY_MS`, `KAFKA_DELIVERY_TIMEOUT_MS`,
+    /// `ANALYTICS_SPOOL_PATH` and `ANALYTICS_SPOOL_MAX_BYTES`; `None` when no
+    /// brokers are configured
+    pub fn from_env() -> Option<Self> {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+
+        let brokers = std::env::var("KAFKA_BROKERS")
+            .ok()
+            .filter(|brokers| !brokers.trim().is_empty())?;
+
+        Some(Self {
+            brokers,
+            topic: std::env::var("KAFKA_TOPIC")
+                .unwrap_or_else(|_| "marketplace.consumption.events".to_string()),
+            max_retries: var("KAFKA_MAX_RETRIES").unwrap_or(3),
+            retry_base_delay: Duration::from_millis(
+                var("KAFKA_RETRY_BASE_DELAY_MS").unwrap_or(200),
+            ),
+            delivery_timeout: Duration::from_millis(
+                var("KAFKA_DELIVERY_TIMEOUT_MS").unwrap_or(5000),
+            ),
+            spool_path: std::env::var("ANALYTICS_SPOOL_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("/var/lib/consumption/analytics-spool.jsonl")),
+            spool_max_bytes: var("ANALYTICS_SPOOL_MAX_BYTES").unwrap_or(DEFAULT_SPOOL_MAX_BYTES),
+        })
+    }
+
+    /// Delay before retry number `attempt` (starting at 1)
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(20);
+        self.retry_base_delay
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Publishes analytics events to Kafka, keyed by service id so each
+/// service's events stay ordered within a partition. Events still failing
+/// after the retries are spooled to disk and resent later, so they are
+/// delivered at least once rather than lost.
+#[derive(Clone)]
+pub struct KafkaSink {
+    producer: FutureProducer,
+    config: Arc<KafkaConfig>,
+    spool: EventSpool,
+    /// Keeps replays from resending the same spooled events concurrently
+    replay_lock: Arc<Mutex<()>>,
+}
+
+impl KafkaSink {
+    pub fn new(config: KafkaConfig) -> Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set(
+                "message.timeout.ms",
+                config.delivery_timeout.as_millis().to_string(),
+            )
+            .set("enable.idempotence", "true")
+            .create()
+            .context("Failed to create Kafka producer")?;
+
+        info!(
+            brokers = %config.brokers,
+            topic = %config.topic,
+            "Kafka analytics producer created"
+        );
+
+        Ok(Self {
+            producer,
+            spool: EventSpool::new(config.spool_path.clone(), config.spool_max_bytes),
+            config: Arc::new(config),
+            replay_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Deliver a batch, retrying failed events with backoff; events still
+    /// failing are spooled. Fails only when they couldn't be spooled either.
+    pub async fn deliver(&self, events: &[AnalyticsEvent]) -> Result<()> {
+        let mut pending: Vec<&AnalyticsEvent> = events.iter().collect();
+        let mut attempt = 0;
+
+        loop {
+            let sent = pending.len();
+            pending = self.send_all(&pending).await;
+            record::analytics_delivery("delivered", sent - pending.len());
+
+            if pending.is_empty() {
+                return Ok(());
+            }
+            if attempt >= self.config.max_retries {
+                break;
+            }
+
+            attempt += 1;
+            let delay = self.config.retry_delay(attempt);
+            warn!(
+                failed = pending.len(),
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "Retrying analytics events Kafka didn't accept"
+            );
+            record::analytics_delivery("retried", pending.len());
+            tokio::time::sleep(delay).await;
+        }
+
+        self.spool(&pending)
+    }
+
+    /// Resend spooled events; events that fail again go back to the spool
+    pub async fn replay_spool(&self) -> Result<usize> {
+        let _guard = self.replay_lock.lock().await;
+
+        let events = self.spool.take()?;
+        if events.is_empty() {
+            return Ok(0);
+        }
+
+        let pending: Vec<&AnalyticsEvent> = events.iter().collect();
+        let failed = self.send_all(&pending).await;
+        if !failed.is_empty() {
+            self.spool.append(&failed)?;
+        }
+        self.spool.finish_replay()?;
+
+        let delivered = events.len() - failed.len();
+        record::analytics_delivery("replayed", delivered);
+        info!(
+            delivered,
+            still_spooled = failed.len(),
+            "Replayed spooled analytics events"
+        );
+
+        Ok(delivered)
+    }
+
+    /// Send events concurrently; returns the ones Kafka didn't accept
+    async fn send_all<'a>(&self, events: &[&'a AnalyticsEvent]) -> Vec<&'a AnalyticsEvent> {
+        let sends = events.iter().map(|event| async move {
+            let payload = match serde_json::to_string(event) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    error!(error = %e, event_type = event.event_type(), "Failed to serialize analytics event");
+                    record::analytics_event_dropped(event.event_type(), "unserializable");
+                    return None;
+                }
+            };
+            let key = event.service_id().map(|id| id.to_string()).unwrap_or_default();
+            let message = FutureRecord::to(&self.config.topic)
+                .payload(&payload)
+                .key(&key);
+
+            // The timeout bounds waiting for room in the producer queue;
+            // delivery itself is bounded by message.timeout.ms
+            match self
+                .producer
+                .send(message, Timeout::After(self.config.delivery_timeout))
+                .await
+            {
+                Ok(_) => None,
+                Err((e, _)) => {
+                    debug!(error = %e, event_type = event.event_type(), "Kafka delivery failed");
+                    Some(*event)
+                }
+            }
+        });
+
+        futures::future::join_all(sends)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn spool(&self, events: &[&AnalyticsEvent]) -> Result<()> {
+        if let Err(e) = self.spool.append(events) {
+            for event in events {
+                record::analytics_event_dropped(event.event_type(), "undeliverable");
+            }
+            return Err(e);
+        }
+
+        warn!(
+            count = events.len(),
+            path = ?self.spool.path(),
+            "Spooled analytics events Kafka didn't accept"
+        );
+        record::analytics_delivery("spooled", events.len());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        let config = KafkaConfig {
+            brokers: "localhost:9092".to_string(),
+            topic: "events".to_string(),
+            max_retries: 3,
+            retry_base_delay: Duration::from_millis(200),
+            delivery_timeout: Duration::from_secs(5),
+            spool_path: PathBuf::from("/tmp/spool.jsonl"),
+            spool_max_bytes: DEFAULT_SPOOL_MAX_BYTES,
+        };
+
+        assert_eq!(config.retry_delay(1), Duration::from_millis(200));
+        assert_eq!(config.retry_delay(3), Duration::from_millis(800));
+        assert_eq!(config.retry_delay(10), MAX_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use tracing::debug;
+use uuid::Uuid;
+
+use super::analytics_streamer::AnalyticsEvent;
+
+/// Maximum number of events returned by a single replay page
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+/// Durable analytics event log backed by PostgreSQL
+/// Every flushed batch is appended here so downstream consumers can
+/// replay events they missed on the Analytics Hub / Kafka path
+#[derive(Clone)]
+pub struct AnalyticsOutbox {
+    db: Arc<PgPool>,
+}
+
+/// Outbox row as stored in the database
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OutboxEvent {
+    pub id: i64,
+    pub event_type: String,
+    pub service_id: Option<Uuid>,
+    pub consumer_id: Option<Uuid>,
+    pub payload: sqlx::types::Json<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Page of replayed events with the cursor for the next request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxPage {
+    pub events: Vec<OutboxEvent>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl AnalyticsOutbox {
+    pub fn new(db: PgPool) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    /// Append a batch of events to the outbox in a single transaction
+    pub async fn append(&self, events: &[AnalyticsEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin outbox transaction")?;
+
+        for event in events {
+            let payload =
+                serde_json::to_value(event).context("Failed to serialize analytics event")?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO analytics_outbox (event_type, service_id, consumer_id, payload)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(event.event_type())
+            .bind(event.service_id())
+            .bind(event.consumer_id())
+            .bind(sqlx::types::Json(payload))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to append analytics event to outbox")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit outbox transaction")?;
+
+        debug!(count = events.len(), "Analytics events appended to outbox");
+
+        Ok(())
+    }
+
+    /// List events for a consumer after the outbox id of a cursor (see
+    /// `parse_cursor`), oldest first
+    pub async fn list_events(
+        &self,
+        consumer_id: Uuid,
+        after_id: Option<i64>,
+        event_types: &[String],
+        limit: i64,
+    ) -> Result<OutboxPage> {
+        let after_id = after_id.unwrap_or(0);
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+
+        // Fetch one extra row to know whether another page exists
+        let mut events = sqlx::query_as::<_, OutboxEvent>(
+            r#"
+            SELECT id, event_type, service_id, consumer_id, payload, created_at
+            FROM analytics_outbox
+            WHERE consumer_id = $1
+                AND id > $2
+                AND (cardinality($3::text[]) = 0 OR event_type = ANY($3))
+            ORDER BY id ASC
+            LIMIT $4
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(after_id)
+        .bind(event_types)
+        .bind(limit + 1)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to read analytics outbox")?;
+
+        let has_more = events.len() as i64 > limit;
+        events.truncate(limit as usize);
+
+        let next_cursor = events.last().map(|event| event.id.to_string());
+
+        Ok(OutboxPage {
+            events,
+            next_cursor,
+            has_more,
+        })
+    }
+
+    /// Outbox id of a cursor; cursors are the id rendered as a decimal string
+    pub fn parse_cursor(cursor: &str) -> Result<i64> {
+        let id = cursor
+            .parse::<i64>()
+            .with_context(|| format!("Invalid cursor: {}", cursor))?;
+
+        if id < 0 {
+            anyhow::bail!("Invalid cursor: {}", cursor);
+        }
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cursor() {
+        assert_eq!(AnalyticsOutbox::parse_cursor("42").unwrap(), 42);
+        assert!(AnalyticsOutbox::parse_cursor("-1").is_err());
+        assert!(AnalyticsOutbox::parse_cursor("abc").is_err());
+    }
+}
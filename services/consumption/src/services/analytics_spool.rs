@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+use super::analytics_streamer::AnalyticsEvent;
+
+/// Size limit of a spool when none is configured
+pub const DEFAULT_SPOOL_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Appending would take the spool past its size limit
+#[derive(Debug, thiserror::Error)]
+#[error("Analytics spool {0:?} is full")]
+pub struct SpoolFull(pub PathBuf);
+
+/// Size-bounded append-only file of analytics events awaiting delivery, one
+/// JSON line per event.
+///
+/// `take` moves the file aside before reading it and `finish_replay`
+/// removes it once the events were delivered, so a replay interrupted by a
+/// restart is read again: events are delivered at least once.
+#[derive(Clone)]
+pub struct EventSpool {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Keeps appends from racing the move in `take`
+    lock: Arc<Mutex<()>>,
+}
+
+impl EventSpool {
+    pub fn new(path: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            path,
+            max_bytes,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn replay_path(&self) -> PathBuf {
+        self.path.with_extension("replaying")
+    }
+
+    /// Append events; fails with `SpoolFull` rather than exceed the size
+    /// limit
+    pub fn append(&self, events: &[&AnalyticsEvent]) -> Result<()> {
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(
+                &serde_json::to_string(event).context("Failed to serialize analytics event")?,
+            );
+            lines.push('\n');
+        }
+
+        let _guard = self.lock.lock().unwrap();
+
+        let size = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to stat analytics spool {:?}", self.path))
+            }
+        };
+        if size + lines.len() as u64 > self.max_bytes {
+            return Err(SpoolFull(self.path.clone()).into());
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open analytics spool {:?}", self.path))?;
+        file.write_all(lines.as_bytes())
+            .context("Failed to write to analytics spool")?;
+
+        Ok(())
+    }
+
+    /// Move the spool aside for replay and read it back; a replay left over
+    /// from an earlier run is read instead. Call `finish_replay` once the
+    /// events are delivered.
+    pub fn take(&self) -> Result<Vec<AnalyticsEvent>> {
+        let replaying = self.replay_path();
+
+        {
+            let _guard = self.lock.lock().unwrap();
+            if !replaying.exists() {
+                match std::fs::rename(&self.path, &replaying) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => {
+                        return Err(e).with_context(|| {
+                            format!("Failed to move analytics spool {:?}", self.path)
+                        })
+                    }
+                }
+            }
+        }
+
+        let contents = std::fs::read_to_string(&replaying)
+            .with_context(|| format!("Failed to read analytics spool {:?}", replaying))?;
+
+        Ok(contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    warn!(error = %e, "Skipping unreadable spooled analytics event");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Forget the events returned by `take`
+    pub fn finish_replay(&self) -> Result<()> {
+        let replaying = self.replay_path();
+        std::fs::remove_file(&replaying)
+            .with_context(|| format!("Failed to remove replayed spool {:?}", replaying))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn event() -> AnalyticsEvent {
+        AnalyticsEvent::SLAViolation {
+            service_id: Uuid::new_v4(),
+            timestamp: Utc::now().to_rfc3339(),
+            metric: "latency_p99".to_string(),
+            threshold: 500.0,
+            actual: 750.0,
+            severity: "high".to_string(),
+        }
+    }
+
+    fn spool(max_bytes: u64) -> EventSpool {
+        let path = std::env::temp_dir().join(format!("analytics-spool-{}.jsonl", Uuid::new_v4()));
+        EventSpool::new(path, max_bytes)
+    }
+
+    #[test]
+    fn test_spool_round_trip() {
+        let spool = spool(DEFAULT_SPOOL_MAX_BYTES);
+        let (first, second) = (event(), event());
+
+        assert!(spool.take().unwrap().is_empty());
+
+        spool.append(&[&first, &second]).unwrap();
+        let spooled = spool.take().unwrap();
+        assert_eq!(spooled.len(), 2);
+        assert_eq!(spooled[0].service_id(), first.service_id());
+
+        // A replay that didn't finish is picked up again before new events
+        spool.append(&[&event()]).unwrap();
+        assert_eq!(spool.take().unwrap().len(), 2);
+
+        spool.finish_replay().unwrap();
+        assert_eq!(spool.take().unwrap().len(), 1);
+        spool.finish_replay().unwrap();
+    }
+
+    #[test]
+    fn test_spool_is_bounded() {
+        let event = event();
+        let line_bytes = serde_json::to_string(&event).unwrap().len() as u64 + 1;
+        let spool = spool(line_bytes * 2);
+
+        spool.append(&[&event, &event]).unwrap();
+        let error = spool.append(&[&event]).unwrap_err();
+        assert!(error.downcast_ref::<SpoolFull>().is_some());
+
+        assert_eq!(spool.take().unwrap().len(), 2);
+        spool.finish_replay().unwrap();
+    }
+}
@@ -1,18 +1,269 @@
-use anyhow::{Context, Result};
-use chrono::Utc;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+#[cfg(feature = "kafka")]
+use super::analytics_kafka::{KafkaConfig, KafkaSink};
+use super::analytics_outbox::AnalyticsOutbox;
+use super::analytics_spool::{EventSpool, SpoolFull, DEFAULT_SPOOL_MAX_BYTES};
+use crate::middleware::metrics::record;
 use crate::models::{CostInfo, UsageInfo};
 
 /// Analytics Hub integration for real-time metrics streaming
-/// Uses async channel with batching for high throughput
+/// Uses a bounded priority buffer with batching for high throughput
 #[derive(Clone)]
 pub struct AnalyticsStreamer {
-    sender: mpsc::Sender<AnalyticsEvent>,
+    buffer: Arc<Mutex<EventBuffer>>,
+    /// Signalled when events are pushed, wakes the background worker
+    events_available: Arc<Notify>,
+    /// Signalled when the worker drains events, wakes blocked senders
+    space_available: Arc<Notify>,
+    overflow_strategy: OverflowStrategy,
+    outbox: Option<AnalyticsOutbox>,
+    sink: AnalyticsSink,
+    /// Spill file of the `SpillToDisk` strategy
+    spill: Option<EventSpool>,
+    dropped_events: Arc<AtomicU64>,
+    spilled_events: Arc<AtomicU64>,
+    replayed_events: Arc<AtomicU64>,
+    /// Lowest priority mirrored into `analytics_events_total`
+    metrics_priority: EventPriority,
+}
+
+/// What to do when the event buffer is full
+///
+/// Regardless of strategy, a full buffer first evicts the oldest event of a
+/// strictly lower priority than the incoming one, so quota and violation
+/// events are never dropped while routine consumption events are queued.
+/// With `SpillToDisk`, evicted events are spilled rather than dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverflowStrategy {
+    /// Evict the oldest queued event of the same priority
+    DropOldest,
+    /// Discard the incoming event
+    DropNewest,
+    /// Wait up to the deadline for space, then discard the incoming event
+    BlockWithDeadline(Duration),
+    /// Append the incoming event as a JSON line to a spill file, bounded by
+    /// `ANALYTICS_SPILL_MAX_BYTES`; spilled events are delivered on startup
+    /// and every minute
+    SpillToDisk(PathBuf),
+}
+
+impl OverflowStrategy {
+    /// Parse strategy from `ANALYTICS_OVERFLOW_STRATEGY`
+    /// (`drop-oldest`, `drop-newest`, `block`, `spill`)
+    pub fn from_env() -> Self {
+        let strategy = std::env::var("ANALYTICS_OVERFLOW_STRATEGY")
+            .unwrap_or_else(|_| "drop-oldest".to_string());
+
+        match strategy.to_lowercase().as_str() {
+            "drop-newest" => OverflowStrategy::DropNewest,
+            "block" | "block-with-deadline" => {
+                let deadline_ms = std::env::var("ANALYTICS_BLOCK_DEADLINE_MS")
+                    .ok()
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .unwrap_or(50);
+                OverflowStrategy::BlockWithDeadline(Duration::from_millis(deadline_ms))
+            }
+            "spill" | "spill-to-disk" => {
+                let path = std::env::var("ANALYTICS_SPILL_PATH")
+                    .unwrap_or_else(|_| "/var/lib/consumption/analytics-spill.jsonl".to_string());
+                OverflowStrategy::SpillToDisk(PathBuf::from(path))
+            }
+            _ => OverflowStrategy::DropOldest,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            OverflowStrategy::DropOldest => "drop_oldest",
+            OverflowStrategy::DropNewest => "drop_newest",
+            OverflowStrategy::BlockWithDeadline(_) => "deadline_exceeded",
+            OverflowStrategy::SpillToDisk(_) => "spill_failed",
+        }
+    }
+}
+
+/// Where flushed batches are delivered
+#[derive(Clone)]
+pub enum AnalyticsSink {
+    /// Events are only logged at debug level, for development
+    Log,
+    /// Kafka (`kafka` feature), with retries and a disk spool
+    #[cfg(feature = "kafka")]
+    Kafka(KafkaSink),
+}
+
+impl AnalyticsSink {
+    /// Kafka when `KAFKA_BROKERS` is set, logging otherwise. Fails when
+    /// brokers are configured but the service was built without the
+    /// `kafka` feature, rather than dropping every event.
+    pub fn from_env() -> Result<Self> {
+        #[cfg(feature = "kafka")]
+        if let Some(config) = KafkaConfig::from_env() {
+            return Ok(AnalyticsSink::Kafka(KafkaSink::new(config)?));
+        }
+
+        #[cfg(not(feature = "kafka"))]
+        if std::env::var("KAFKA_BROKERS").is_ok_and(|brokers| !brokers.trim().is_empty()) {
+            anyhow::bail!(
+                "KAFKA_BROKERS is set but the service was built without the kafka feature"
+            );
+        }
+
+        Ok(AnalyticsSink::Log)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            AnalyticsSink::Log => "log",
+            #[cfg(feature = "kafka")]
+            AnalyticsSink::Kafka(_) => "kafka",
+        }
+    }
+
+    async fn deliver(&self, events: &[AnalyticsEvent]) -> Result<()> {
+        match self {
+            AnalyticsSink::Log => {
+                for event in events {
+                    debug!(event = ?event, "Analytics event");
+                }
+                Ok(())
+            }
+            #[cfg(feature = "kafka")]
+            AnalyticsSink::Kafka(sink) => sink.deliver(events).await,
+        }
+    }
+
+    /// Resend events spooled after failed deliveries
+    async fn replay_spool(&self) {
+        #[cfg(feature = "kafka")]
+        if let AnalyticsSink::Kafka(sink) = self {
+            if let Err(e) = sink.replay_spool().await {
+                error!(error = %e, "Failed to replay spooled analytics events");
+            }
+        }
+    }
+}
+
+/// Delivery priority of an analytics event; higher variants win under pressure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventPriority {
+    /// Key lifecycle bookkeeping
+    Low = 0,
+    /// Routine consumption events
+    Normal = 1,
+    /// Quota, rate limit, policy and SLA violations
+    Critical = 2,
+}
+
+impl EventPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventPriority::Low => "low",
+            EventPriority::Normal => "normal",
+            EventPriority::Critical => "critical",
+        }
+    }
+
+    /// Parse `ANALYTICS_METRICS_PRIORITY` (`critical`, `normal`, `low`);
+    /// only violation events are mirrored into Prometheus by default
+    pub fn metrics_threshold_from_env() -> Self {
+        match std::env::var("ANALYTICS_METRICS_PRIORITY")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "low" => EventPriority::Low,
+            "normal" => EventPriority::Normal,
+            _ => EventPriority::Critical,
+        }
+    }
+}
+
+const PRIORITY_LEVELS: usize = 3;
+
+/// Bounded buffer holding one FIFO queue per priority
+struct EventBuffer {
+    queues: [VecDeque<AnalyticsEvent>; PRIORITY_LEVELS],
+    len: usize,
+    capacity: usize,
+}
+
+/// Outcome of pushing into a full or non-full buffer
+enum PushOutcome {
+    Queued,
+    /// Queued after evicting the returned event
+    Evicted(AnalyticsEvent),
+    /// Buffer is full and nothing could be evicted for this event
+    Full(AnalyticsEvent),
+}
+
+impl EventBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            queues: Default::default(),
+            len: 0,
+            capacity,
+        }
+    }
+
+    /// Push an event, preempting lower-priority events when full.
+    /// When `evict_same_priority` is set, the oldest event of the same
+    /// priority is evicted as a last resort.
+    fn push(&mut self, event: AnalyticsEvent, evict_same_priority: bool) -> PushOutcome {
+        let priority = event.priority() as usize;
+
+        if self.len < self.capacity {
+            self.queues[priority].push_back(event);
+            self.len += 1;
+            return PushOutcome::Queued;
+        }
+
+        let victim_queue = (0..priority)
+            .find(|p| !self.queues[*p].is_empty())
+            .or_else(|| {
+                (evict_same_priority && !self.queues[priority].is_empty()).then_some(priority)
+            });
+
+        match victim_queue.and_then(|p| self.queues[p].pop_front()) {
+            Some(evicted) => {
+                self.queues[priority].push_back(event);
+                PushOutcome::Evicted(evicted)
+            }
+            None => PushOutcome::Full(event),
+        }
+    }
+
+    /// Drain up to `max` events, highest priority first
+    fn drain(&mut self, max: usize) -> Vec<AnalyticsEvent> {
+        let mut batch = Vec::with_capacity(max.min(self.len));
+
+        for queue in self.queues.iter_mut().rev() {
+            while batch.len() < max {
+                match queue.pop_front() {
+                    Some(event) => batch.push(event),
+                    None => break,
+                }
+            }
+        }
+
+        self.len -= batch.len();
+        batch
+    }
+
+    fn is_full(&self) -> bool {
+        self.len >= self.capacity
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +317,26 @@ pub enum AnalyticsEvent {
         severity: String,
         message: String,
     },
+    /// Built-in PII filter detections; category counts only, never the matches
+    #[serde(rename = "pii_detected")]
+    PiiDetected {
+        service_id: Uuid,
+        consumer_id: Uuid,
+        timestamp: String,
+        action: String,
+        categories: BTreeMap<String, usize>,
+    },
+    /// Prompt or response blocked by LLM-Shield; filter ids only, never
+    /// the content
+    #[serde(rename = "content_blocked")]
+    ContentBlocked {
+        service_id: Uuid,
+        consumer_id: Uuid,
+        timestamp: String,
+        /// `prompt` or `response`
+        content: String,
+        filters: Vec<String>,
+    },
     #[serde(rename = "api_key_created")]
     ApiKeyCreated {
         consumer_id: Uuid,
@@ -80,37 +351,313 @@ pub enum AnalyticsEvent {
         timestamp: String,
         reason: String,
     },
+    /// A key was replaced; the old one works until `old_key_expires_at`
+    #[serde(rename = "api_key_rotated")]
+    ApiKeyRotated {
+        consumer_id: Uuid,
+        service_id: Uuid,
+        timestamp: String,
+        old_key_id: Uuid,
+        new_key_id: Uuid,
+        old_key_expires_at: String,
+    },
+}
+
+impl AnalyticsEvent {
+    /// Stable event type name, matching the serialized `event_type` tag
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            AnalyticsEvent::ConsumptionRequest { .. } => "consumption_request",
+            AnalyticsEvent::RateLimitExceeded { .. } => "rate_limit_exceeded",
+            AnalyticsEvent::QuotaExceeded { .. } => "quota_exceeded",
+            AnalyticsEvent::SLAViolation { .. } => "sla_violation",
+            AnalyticsEvent::PolicyViolation { .. } => "policy_violation",
+            AnalyticsEvent::PiiDetected { .. } => "pii_detected",
+            AnalyticsEvent::ContentBlocked { .. } => "content_blocked",
+            AnalyticsEvent::ApiKeyCreated { .. } => "api_key_created",
+            AnalyticsEvent::ApiKeyRevoked { .. } => "api_key_revoked",
+            AnalyticsEvent::ApiKeyRotated { .. } => "api_key_rotated",
+        }
+    }
+
+    /// Service the event relates to, if any
+    pub fn service_id(&self) -> Option<Uuid> {
+        match self {
+            AnalyticsEvent::ConsumptionRequest { service_id, .. }
+            | AnalyticsEvent::RateLimitExceeded { service_id, .. }
+            | AnalyticsEvent::QuotaExceeded { service_id, .. }
+            | AnalyticsEvent::SLAViolation { service_id, .. }
+            | AnalyticsEvent::PolicyViolation { service_id, .. }
+            | AnalyticsEvent::PiiDetected { service_id, .. }
+            | AnalyticsEvent::ContentBlocked { service_id, .. }
+            | AnalyticsEvent::ApiKeyCreated { service_id, .. }
+            | AnalyticsEvent::ApiKeyRevoked { service_id, .. }
+            | AnalyticsEvent::ApiKeyRotated { service_id, .. } => Some(*service_id),
+        }
+    }
+
+    /// Consumer the event relates to; service-wide events have none
+    pub fn consumer_id(&self) -> Option<Uuid> {
+        match self {
+            AnalyticsEvent::ConsumptionRequest { consumer_id, .. }
+            | AnalyticsEvent::RateLimitExceeded { consumer_id, .. }
+            | AnalyticsEvent::QuotaExceeded { consumer_id, .. }
+            | AnalyticsEvent::PolicyViolation { consumer_id, .. }
+            | AnalyticsEvent::PiiDetected { consumer_id, .. }
+            | AnalyticsEvent::ContentBlocked { consumer_id, .. }
+            | AnalyticsEvent::ApiKeyCreated { consumer_id, .. }
+            | AnalyticsEvent::ApiKeyRevoked { consumer_id, .. }
+            | AnalyticsEvent::ApiKeyRotated { consumer_id, .. } => Some(*consumer_id),
+            AnalyticsEvent::SLAViolation { .. } => None,
+        }
+    }
+
+    /// Severity label for metrics: free-form severities from the policy
+    /// engine and SLA monitor are folded into a fixed set to bound series
+    pub fn severity_label(&self) -> &'static str {
+        let severity = match self {
+            AnalyticsEvent::SLAViolation { severity, .. }
+            | AnalyticsEvent::PolicyViolation { severity, .. } => severity,
+            _ => return "none",
+        };
+
+        match severity.to_lowercase().as_str() {
+            "info" => "info",
+            "low" => "low",
+            "warning" => "warning",
+            "medium" => "medium",
+            "high" => "high",
+            "critical" => "critical",
+            _ => "other",
+        }
+    }
+
+    /// Delivery priority used when the buffer is under pressure
+    pub fn priority(&self) -> EventPriority {
+        match self {
+            AnalyticsEvent::RateLimitExceeded { .. }
+            | AnalyticsEvent::QuotaExceeded { .. }
+            | AnalyticsEvent::SLAViolation { .. }
+            | AnalyticsEvent::PolicyViolation { .. }
+            | AnalyticsEvent::ContentBlocked { .. } => EventPriority::Critical,
+            AnalyticsEvent::ConsumptionRequest { .. } | AnalyticsEvent::PiiDetected { .. } => {
+                EventPriority::Normal
+            }
+            AnalyticsEvent::ApiKeyCreated { .. }
+            | AnalyticsEvent::ApiKeyRevoked { .. }
+            | AnalyticsEvent::ApiKeyRotated { .. } => EventPriority::Low,
+        }
+    }
 }
 
 impl AnalyticsStreamer {
     /// Create new analytics streamer with background worker
     pub fn new(buffer_size: usize) -> Self {
-        let (sender, receiver) = mpsc::channel(buffer_size);
+        Self::with_overflow_strategy(buffer_size, OverflowStrategy::DropOldest)
+    }
+
+    /// Create analytics streamer with an explicit overflow strategy
+    pub fn with_overflow_strategy(buffer_size: usize, overflow_strategy: OverflowStrategy) -> Self {
+        Self::build(buffer_size, overflow_strategy, None, AnalyticsSink::Log)
+    }
+
+    /// Persist every flushed batch to the durable outbox before delivering
+    /// it to `sink`
+    pub fn with_outbox(
+        buffer_size: usize,
+        overflow_strategy: OverflowStrategy,
+        outbox: AnalyticsOutbox,
+        sink: AnalyticsSink,
+    ) -> Self {
+        Self::build(buffer_size, overflow_strategy, Some(outbox), sink)
+    }
+
+    fn build(
+        buffer_size: usize,
+        overflow_strategy: OverflowStrategy,
+        outbox: Option<AnalyticsOutbox>,
+        sink: AnalyticsSink,
+    ) -> Self {
+        let spill = match &overflow_strategy {
+            OverflowStrategy::SpillToDisk(path) => {
+                let max_bytes = std::env::var("ANALYTICS_SPILL_MAX_BYTES")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_SPOOL_MAX_BYTES);
+                Some(EventSpool::new(path.clone(), max_bytes))
+            }
+            _ => None,
+        };
+
+        let streamer = Self {
+            buffer: Arc::new(Mutex::new(EventBuffer::new(buffer_size))),
+            events_available: Arc::new(Notify::new()),
+            space_available: Arc::new(Notify::new()),
+            overflow_strategy,
+            outbox,
+            sink,
+            spill,
+            dropped_events: Arc::new(AtomicU64::new(0)),
+            spilled_events: Arc::new(AtomicU64::new(0)),
+            replayed_events: Arc::new(AtomicU64::new(0)),
+            metrics_priority: EventPriority::metrics_threshold_from_env(),
+        };
 
         // Spawn background worker to process events
+        let worker = streamer.clone();
         tokio::spawn(async move {
-            Self::process_events(receiver).await;
+            worker.process_events().await;
         });
 
-        Self { sender }
+        streamer
     }
 
-    /// Send event to analytics hub (non-blocking)
+    /// Send event to analytics hub (non-blocking unless the
+    /// `BlockWithDeadline` strategy is configured)
     pub async fn send(&self, event: AnalyticsEvent) -> Result<()> {
-        // Non-blocking send - if buffer is full, log warning and drop event
-        if let Err(e) = self.sender.try_send(event.clone()) {
-            error!(
-                error = %e,
-                event_type = ?event,
-                "Failed to send analytics event - buffer full"
+        let evict_same_priority = self.overflow_strategy == OverflowStrategy::DropOldest;
+        let priority = event.priority();
+
+        // Mirror into Prometheus before buffering, so alerts don't depend
+        // on delivery to the Analytics Hub or on the event surviving overflow
+        if priority >= self.metrics_priority {
+            record::analytics_event(
+                event.event_type(),
+                priority.as_str(),
+                event.service_id(),
+                event.severity_label(),
             );
-            // Don't fail the request if analytics fails
-            return Ok(());
         }
 
+        let outcome = self.buffer.lock().unwrap().push(event, evict_same_priority);
+
+        match outcome {
+            PushOutcome::Queued => {}
+            PushOutcome::Evicted(evicted) => {
+                let reason = if evicted.priority() < priority {
+                    "preempted"
+                } else {
+                    "drop_oldest"
+                };
+                self.discard(&evicted, reason);
+            }
+            PushOutcome::Full(event) => self.handle_overflow(event).await,
+        }
+
+        self.events_available.notify_one();
+
+        // Don't fail the request if analytics fails
         Ok(())
     }
 
+    /// Apply the configured overflow strategy to an event that didn't fit
+    async fn handle_overflow(&self, event: AnalyticsEvent) {
+        match &self.overflow_strategy {
+            OverflowStrategy::DropOldest | OverflowStrategy::DropNewest => {
+                self.record_dropped(&event, "drop_newest");
+            }
+            OverflowStrategy::BlockWithDeadline(deadline) => {
+                let deadline = tokio::time::Instant::now() + *deadline;
+                let mut pending = event;
+
+                loop {
+                    let notified = self.space_available.notified();
+
+                    match self.buffer.lock().unwrap().push(pending, false) {
+                        PushOutcome::Queued => return,
+                        PushOutcome::Evicted(evicted) => {
+                            self.record_dropped(&evicted, "preempted");
+                            return;
+                        }
+                        PushOutcome::Full(event) => pending = event,
+                    }
+
+                    if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                        self.record_dropped(&pending, self.overflow_strategy.label());
+                        return;
+                    }
+                }
+            }
+            OverflowStrategy::SpillToDisk(_) => {
+                self.discard(&event, self.overflow_strategy.label());
+            }
+        }
+    }
+
+    /// Spill an event that lost its place in the buffer, or drop it when
+    /// the strategy doesn't spill
+    fn discard(&self, event: &AnalyticsEvent, reason: &str) {
+        let Some(spill) = &self.spill else {
+            self.record_dropped(event, reason);
+            return;
+        };
+
+        match spill.append(&[event]) {
+            Ok(()) => {
+                self.spilled_events.fetch_add(1, Ordering::Relaxed);
+                record::analytics_event_spilled(event.event_type());
+            }
+            Err(e) if e.is::<SpoolFull>() => self.record_dropped(event, "spill_full"),
+            Err(e) => {
+                error!(error = %e, path = ?spill.path(), "Failed to spill analytics event");
+                self.record_dropped(event, "spill_failed");
+            }
+        }
+    }
+
+    /// Deliver events spilled on overflow, oldest first. They stay on disk
+    /// until delivered, so a restart mid-replay delivers them again.
+    async fn replay_spill(&self) {
+        let Some(spill) = &self.spill else {
+            return;
+        };
+
+        let events = match spill.take() {
+            Ok(events) => events,
+            Err(e) => {
+                error!(error = %e, path = ?spill.path(), "Failed to read spilled analytics events");
+                return;
+            }
+        };
+        if events.is_empty() {
+            return;
+        }
+
+        // Keep the spill for the next replay unless every chunk was delivered;
+        // chunks delivered before a failure are sent again then
+        for chunk in events.chunks(100) {
+            if let Err(e) = self.flush_batch(&mut chunk.to_vec()).await {
+                error!(
+                    error = %e,
+                    count = events.len(),
+                    "Failed to replay spilled analytics events"
+                );
+                return;
+            }
+        }
+        if let Err(e) = spill.finish_replay() {
+            error!(error = %e, "Failed to remove replayed analytics spill");
+        }
+
+        for event in &events {
+            record::analytics_event_replayed(event.event_type());
+        }
+        self.replayed_events
+            .fetch_add(events.len() as u64, Ordering::Relaxed);
+        info!(count = events.len(), "Replayed spilled analytics events");
+    }
+
+    fn record_dropped(&self, event: &AnalyticsEvent, reason: &str) {
+        self.dropped_events.fetch_add(1, Ordering::Relaxed);
+        record::analytics_event_dropped(event.event_type(), reason);
+
+        warn!(
+            event_type = event.event_type(),
+            reason = reason,
+            "Dropped analytics event - buffer full"
+        );
+    }
+
     /// Record consumption request
     pub async fn record_consumption(
         &self,
@@ -221,123 +768,179 @@ impl AnalyticsStreamer {
         self.send(event).await
     }
 
+    /// Record built-in PII filter detections
+    pub async fn record_pii_detected(
+        &self,
+        service_id: Uuid,
+        consumer_id: Uuid,
+        action: String,
+        categories: BTreeMap<String, usize>,
+    ) -> Result<()> {
+        let event = AnalyticsEvent::PiiDetected {
+            service_id,
+            consumer_id,
+            timestamp: Utc::now().to_rfc3339(),
+            action,
+            categories,
+        };
+
+        self.send(event).await
+    }
+
+    /// Record a prompt or response blocked by LLM-Shield
+    pub async fn record_content_blocked(
+        &self,
+        service_id: Uuid,
+        consumer_id: Uuid,
+        content: &str,
+        filters: Vec<String>,
+    ) -> Result<()> {
+        let event = AnalyticsEvent::ContentBlocked {
+            service_id,
+            consumer_id,
+            timestamp: Utc::now().to_rfc3339(),
+            content: content.to_string(),
+            filters,
+        };
+
+        self.send(event).await
+    }
+
+    /// Record an API key rotation
+    pub async fn record_api_key_rotated(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        old_key_id: Uuid,
+        new_key_id: Uuid,
+        old_key_expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let event = AnalyticsEvent::ApiKeyRotated {
+            consumer_id,
+            service_id,
+            timestamp: Utc::now().to_rfc3339(),
+            old_key_id,
+            new_key_id,
+            old_key_expires_at: old_key_expires_at.to_rfc3339(),
+        };
+
+        self.send(event).await
+    }
+
     /// Background worker to batch and send events to Analytics Hub
-    async fn process_events(mut receiver: mpsc::Receiver<AnalyticsEvent>) {
-        info!("Analytics streamer worker started");
+    async fn process_events(&self) {
+        info!(sink = self.sink.name(), "Analytics streamer worker started");
 
-        let mut batch: Vec<AnalyticsEvent> = Vec::with_capacity(100);
         let batch_interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
         tokio::pin!(batch_interval);
+        let spool_interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        tokio::pin!(spool_interval);
 
         loop {
             tokio::select! {
-                // Receive events
-                Some(event) = receiver.recv() => {
-                    batch.push(event);
-
-                    // Flush batch if it reaches max size
-                    if batch.len() >= 100 {
-                        Self::flush_batch(&mut batch).await;
+                // Wake on new events, flush once a full batch is queued
+                _ = self.events_available.notified() => {
+                    let ready = self.buffer.lock().unwrap().len >= 100;
+                    if ready {
+                        self.drain_and_flush().await;
                     }
                 }
                 // Flush batch periodically
                 _ = batch_interval.tick() => {
-                    if !batch.is_empty() {
-                        Self::flush_batch(&mut batch).await;
-                    }
+                    record::analytics_queue_depth(self.buffer.lock().unwrap().len);
+                    self.drain_and_flush().await;
                 }
-                // Channel closed
-                else => {
-                    info!("Analytics channel closed, flushing remaining events");
-                    if !batch.is_empty() {
-                        Self::flush_batch(&mut batch).await;
-                    }
-                    break;
+                // Deliver spilled events and resend events that failed
+                // delivery earlier; the first tick replays on startup
+                _ = spool_interval.tick() => {
+                    self.replay_spill().await;
+                    self.sink.replay_spool().await;
                 }
             }
         }
+    }
 
-        info!("Analytics streamer worker stopped");
+    /// Flush every queued event now, e.g. on shutdown; returns how many
+    /// events were flushed
+    pub async fn flush(&self) -> usize {
+        self.drain_and_flush().await
     }
 
-    /// Flush batch of events to Analytics Hub
-    async fn flush_batch(batch: &mut Vec<AnalyticsEvent>) {
-        let count = batch.len();
-        debug!(count = count, "Flushing analytics batch");
+    /// Drain queued events in batches of 100 and flush them
+    async fn drain_and_flush(&self) -> usize {
+        let mut flushed = 0;
+        loop {
+            let mut batch = self.buffer.lock().unwrap().drain(100);
+            if batch.is_empty() {
+                break;
+            }
 
-        // In production, send to Kafka or Analytics Hub API
-        if let Err(e) = Self::send_to_analytics_hub(batch).await {
-            error!(
-                error = %e,
-                count = count,
-                "Failed to send analytics batch"
-            );
-        } else {
-            debug!(count = count, "Analytics batch sent successfully");
+            let count = batch.len();
+            flushed += count;
+            self.space_available.notify_waiters();
+            if let Err(e) = self.flush_batch(&mut batch).await {
+                error!(
+                    error = %e,
+                    count = count,
+                    "Failed to send analytics batch"
+                );
+            }
         }
-
-        batch.clear();
+        flushed
     }
 
-    /// Send batch to Analytics Hub
-    /// In production, this would use Kafka producer or HTTP API
-    async fn send_to_analytics_hub(events: &[AnalyticsEvent]) -> Result<()> {
-        // TODO: Implement actual Kafka producer or HTTP client
-        // For now, log events
-
-        let analytics_hub_url = std::env::var("ANALYTICS_HUB_URL")
-            .unwrap_or_else(|_| "http://localhost:9092".to_string());
-
-        let kafka_topic = std::env::var("KAFKA_TOPIC")
-            .unwrap_or_else(|_| "marketplace.consumption.events".to_string());
+    /// Flush batch of events to Analytics Hub; fails when the sink couldn't
+    /// deliver or keep them
+    async fn flush_batch(&self, batch: &mut Vec<AnalyticsEvent>) -> Result<()> {
+        let count = batch.len();
+        debug!(count = count, "Flushing analytics batch");
 
-        debug!(
-            url = %analytics_hub_url,
-            topic = %kafka_topic,
-            count = events.len(),
-            "Would send events to Analytics Hub"
-        );
+        // Durable log first, so replay covers events the hub never receives
+        if let Some(outbox) = &self.outbox {
+            if let Err(e) = outbox.append(batch).await {
+                error!(
+                    error = %e,
+                    count = count,
+                    "Failed to append analytics batch to outbox"
+                );
+            }
+        }
 
-        // Kafka integration would be:
-        // ```rust
-        // use rdkafka::producer::{FutureProducer, FutureRecord};
-        //
-        // let producer: FutureProducer = ClientConfig::new()
-        //     .set("bootstrap.servers", &analytics_hub_url)
-        //     .create()?;
-        //
-        // for event in events {
-        //     let payload = serde_json::to_string(event)?;
-        //     producer.send(
-        //         FutureRecord::to(&kafka_topic)
-        //             .payload(&payload)
-        //             .key(&event.service_id.to_string()),
-        //         Duration::from_secs(0)
-        //     ).await?;
-        // }
-        // ```
-
-        // For development, just log
-        for event in events {
-            debug!(event = ?event, "Analytics event");
+        let delivered = self.sink.deliver(batch).await;
+        if delivered.is_ok() {
+            debug!(count = count, "Analytics batch sent successfully");
         }
 
-        Ok(())
+        batch.clear();
+        delivered
     }
 
-    /// Get channel capacity and current length (for monitoring)
+    /// Get buffer capacity, current length and overflow counters (for monitoring)
     pub fn metrics(&self) -> ChannelMetrics {
+        let buffer = self.buffer.lock().unwrap();
+
         ChannelMetrics {
-            capacity: self.sender.capacity(),
-            current_length: self.sender.max_capacity() - self.sender.capacity(),
+            capacity: buffer.capacity - buffer.len,
+            current_length: buffer.len,
+            dropped_events: self.dropped_events.load(Ordering::Relaxed),
+            spilled_events: self.spilled_events.load(Ordering::Relaxed),
+            replayed_events: self.replayed_events.load(Ordering::Relaxed),
         }
     }
+
+    /// Whether the buffer is currently at capacity
+    pub fn is_saturated(&self) -> bool {
+        self.buffer.lock().unwrap().is_full()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ChannelMetrics {
     pub capacity: usize,
     pub current_length: usize,
+    pub dropped_events: u64,
+    pub spilled_events: u64,
+    pub replayed_events: u64,
 }
 
 #[cfg(test)]
@@ -380,4 +983,99 @@ mod tests {
         // Allow background worker to process
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
+
+    fn quota_event() -> AnalyticsEvent {
+        AnalyticsEvent::QuotaExceeded {
+            service_id: Uuid::new_v4(),
+            consumer_id: Uuid::new_v4(),
+            timestamp: Utc::now().to_rfc3339(),
+            tier: "basic".to_string(),
+            used_tokens: 100,
+            total_tokens: 100,
+        }
+    }
+
+    fn consumption_event() -> AnalyticsEvent {
+        AnalyticsEvent::ConsumptionRequest {
+            request_id: Uuid::new_v4(),
+            service_id: Uuid::new_v4(),
+            consumer_id: Uuid::new_v4(),
+            timestamp: Utc::now().to_rfc3339(),
+            latency_ms: 10,
+            usage: UsageInfo {
+                prompt_tokens: 1,
+                completion_tokens: 1,
+                total_tokens: 2,
+            },
+            cost: CostInfo {
+                amount: 0.0,
+                currency: "USD".to_string(),
+                breakdown: serde_json::json!({}),
+            },
+            status: "success".to_string(),
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_critical_event_preempts_routine_event() {
+        let mut buffer = EventBuffer::new(1);
+        assert!(matches!(buffer.push(consumption_event(), false), PushOutcome::Queued));
+
+        match buffer.push(quota_event(), false) {
+            PushOutcome::Evicted(evicted) => assert_eq!(evicted.event_type(), "consumption_request"),
+            _ => panic!("expected routine event to be evicted"),
+        }
+
+        // A routine event can never displace a critical one
+        assert!(matches!(buffer.push(consumption_event(), true), PushOutcome::Full(_)));
+    }
+
+    #[test]
+    fn test_severity_label_is_bounded() {
+        let violation = |severity: &str| AnalyticsEvent::PolicyViolation {
+            service_id: Uuid::new_v4(),
+            consumer_id: Uuid::new_v4(),
+            timestamp: Utc::now().to_rfc3339(),
+            policy_id: "p1".to_string(),
+            policy_name: "no-secrets".to_string(),
+            severity: severity.to_string(),
+            message: "blocked".to_string(),
+        };
+
+        assert_eq!(violation("HIGH").severity_label(), "high");
+        assert_eq!(violation("sev-1 page someone").severity_label(), "other");
+        assert_eq!(quota_event().severity_label(), "none");
+    }
+
+    #[test]
+    fn test_drain_orders_by_priority() {
+        let mut buffer = EventBuffer::new(10);
+        buffer.push(consumption_event(), false);
+        buffer.push(quota_event(), false);
+
+        let batch = buffer.drain(10);
+        assert_eq!(batch[0].event_type(), "quota_exceeded");
+        assert_eq!(batch[1].event_type(), "consumption_request");
+        assert_eq!(buffer.len, 0);
+    }
+
+    #[tokio::test]
+    async fn test_spilled_events_are_replayed() {
+        let path = std::env::temp_dir().join(format!("analytics-spill-{}.jsonl", Uuid::new_v4()));
+        let streamer = AnalyticsStreamer::with_overflow_strategy(
+            1,
+            OverflowStrategy::SpillToDisk(path.clone()),
+        );
+
+        streamer.send(quota_event()).await.unwrap();
+        // Doesn't fit and can't preempt the quota event
+        streamer.send(consumption_event()).await.unwrap();
+        assert_eq!(streamer.metrics().spilled_events, 1);
+        assert_eq!(streamer.metrics().dropped_events, 0);
+
+        streamer.replay_spill().await;
+        assert_eq!(streamer.metrics().replayed_events, 1);
+        assert!(!path.exists());
+    }
 }
@@ -10,17 +10,37 @@ use std::sync::Arc;
 use tracing::{debug, warn};
 use uuid::Uuid;
 
-use crate::models::{ApiKey, ApiKeyResponse, CreateApiKeyRequest};
+use crate::models::{
+    ApiKey, ApiKeyResponse, ApiKeyScope, Caller, CreateApiKeyRequest, RotatedApiKey,
+};
+use crate::utils::AppError;
+
+/// Longest a rotated key may stay valid next to its replacement
+pub const MAX_ROTATION_GRACE_SECS: u64 = 7 * 24 * 3600;
 
 /// API key manager for generation, validation, and revocation
 #[derive(Clone)]
 pub struct ApiKeyManager {
     db: Arc<PgPool>,
+    rotation_grace: Duration,
 }
 
 impl ApiKeyManager {
     pub fn new(db: PgPool) -> Self {
-        Self { db: Arc::new(db) }
+        Self {
+            db: Arc::new(db),
+            rotation_grace: Duration::hours(24),
+        }
+    }
+
+    /// How long rotated keys stay valid when consumers don't say
+    pub fn with_rotation_grace(mut self, grace: Duration) -> Self {
+        self.rotation_grace = grace;
+        self
+    }
+
+    pub fn rotation_grace(&self) -> Duration {
+        self.rotation_grace
     }
 
     /// Generate a new API key
@@ -41,18 +61,26 @@ impl ApiKeyManager {
             .map(|days| Utc::now() + Duration::days(days));
 
         let service_id = Uuid::parse_str(&request.service_id)
-            .context("Invalid service ID")?;
+            .map_err(|_| AppError::InvalidRequest("Invalid service ID".to_string()))?;
 
         let id = Uuid::new_v4();
 
+        // Unrestricted keys store no scopes, so they also get scopes added later
+        let scopes = request.scopes.map(|requested| {
+            ApiKeyScope::ALL
+                .into_iter()
+                .filter(|scope| requested.contains(scope))
+                .collect::<Vec<_>>()
+        });
+
         // Insert into database
         sqlx::query(
             r#"
             INSERT INTO api_keys (
                 id, key_hash, consumer_id, service_id, tier,
-                created_at, expires_at, metadata
+                created_at, expires_at, metadata, scopes
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             "#,
         )
         .bind(id)
@@ -63,6 +91,12 @@ impl ApiKeyManager {
         .bind(Utc::now())
         .bind(expires_at)
         .bind(sqlx::types::Json(serde_json::json!({})))
+        .bind(scopes.as_ref().map(|scopes| {
+            scopes
+                .iter()
+                .map(|scope| scope.as_str().to_string())
+                .collect::<Vec<_>>()
+        }))
         .execute(self.db.as_ref())
         .await
         .context("Failed to create API key")?;
@@ -72,6 +106,7 @@ impl ApiKeyManager {
             consumer_id = %consumer_id,
             service_id = %service_id,
             tier = ?request.tier,
+            scopes = ?scopes,
             "API key created"
         );
 
@@ -80,6 +115,7 @@ impl ApiKeyManager {
             key: api_key,
             service_id,
             tier: request.tier,
+            scopes: scopes.unwrap_or_else(|| ApiKeyScope::ALL.to_vec()),
             created_at: Utc::now(),
             expires_at,
         })
@@ -94,7 +130,7 @@ impl ApiKeyManager {
         let api_key_record = sqlx::query_as::<_, ApiKey>(
             r#"
             SELECT id, key_hash, consumer_id, service_id, tier,
-                   created_at, expires_at, revoked_at, metadata
+                   created_at, expires_at, revoked_at, metadata, scopes
             FROM api_keys
             WHERE key_hash = $1
             "#,
@@ -109,9 +145,25 @@ impl ApiKeyManager {
             anyhow::bail!("API key is expired or revoked");
         }
 
+        if self.is_suspended(api_key_record.consumer_id).await? {
+            anyhow::bail!("Consumer is suspended");
+        }
+
         Ok(api_key_record)
     }
 
+    /// Whether the consumer is suspended; suspended consumers can't
+    /// authenticate with any credential
+    pub async fn is_suspended(&self, consumer_id: Uuid) -> Result<bool> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM consumer_suspensions WHERE consumer_id = $1)",
+        )
+        .bind(consumer_id)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to check consumer suspension")
+    }
+
     /// Revoke an API key
     pub async fn revoke_key(&self, key_id: Uuid, consumer_id: Uuid) -> Result<()> {
         let result = sqlx::query(
@@ -128,7 +180,9 @@ impl ApiKeyManager {
         .context("Failed to revoke API key")?;
 
         if result.rows_affected() == 0 {
-            anyhow::bail!("API key not found or already revoked");
+            return Err(
+                AppError::NotFound("API key not found or already revoked".to_string()).into(),
+            );
         }
 
         debug!(
@@ -140,12 +194,105 @@ impl ApiKeyManager {
         Ok(())
     }
 
+    /// Rotate an API key: issue a replacement with the same service, tier and
+    /// scopes, and keep the old key valid for `grace` so clients can switch
+    /// over without downtime. A zero grace revokes the old key right away.
+    ///
+    /// When rotating on behalf of a consumer, `caller` is the key making the
+    /// request: only that consumer's keys can be rotated, and only keys
+    /// without scopes the caller lacks.
+    pub async fn rotate_key(
+        &self,
+        key_id: Uuid,
+        caller: Option<&Caller>,
+        grace: Duration,
+    ) -> Result<RotatedApiKey> {
+        let existing = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, key_hash, consumer_id, service_id, tier,
+                   created_at, expires_at, revoked_at, metadata, scopes
+            FROM api_keys
+            WHERE id = $1 AND revoked_at IS NULL
+                AND ($2::UUID IS NULL OR consumer_id = $2)
+            "#,
+        )
+        .bind(key_id)
+        .bind(caller.map(|caller| caller.consumer_id))
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load API key")?
+        .ok_or_else(|| AppError::NotFound("API key not found or already revoked".to_string()))?;
+
+        if let Some(caller) = caller {
+            let missing = caller.missing_scopes(&existing.get_scopes());
+            if !missing.is_empty() {
+                return Err(AppError::Authorization(format!(
+                    "API key can't rotate a key with scopes it is missing: {}",
+                    missing.join(", ")
+                ))
+                .into());
+            }
+        }
+
+        let replacement = self
+            .create_api_key(
+                existing.consumer_id,
+                CreateApiKeyRequest {
+                    service_id: existing.service_id.to_string(),
+                    tier: existing.get_tier(),
+                    // Keep the remaining lifetime of the old key
+                    expires_in_days: existing
+                        .expires_at
+                        .map(|at| (at - Utc::now()).num_days().max(1)),
+                    scopes: existing.scopes.is_some().then(|| existing.get_scopes()),
+                },
+            )
+            .await?;
+
+        let previous_key_expires_at = if grace > Duration::zero() {
+            // Expiring the old key, rather than revoking it, keeps it working
+            // everywhere until then. Its owner is rotating it, so it gets no
+            // expiry notice.
+            sqlx::query_scalar(
+                r#"
+                UPDATE api_keys
+                SET expires_at = LEAST(COALESCE(expires_at, $2), $2),
+                    expiry_notified_at = COALESCE(expiry_notified_at, NOW())
+                WHERE id = $1
+                RETURNING expires_at
+                "#,
+            )
+            .bind(existing.id)
+            .bind(Utc::now() + grace)
+            .fetch_one(self.db.as_ref())
+            .await
+            .context("Failed to schedule expiry of rotated API key")?
+        } else {
+            self.revoke_key(existing.id, existing.consumer_id).await?;
+            Utc::now()
+        };
+
+        debug!(
+            old_key_id = %existing.id,
+            new_key_id = %replacement.id,
+            consumer_id = %existing.consumer_id,
+            old_key_expires_at = %previous_key_expires_at,
+            "API key rotated"
+        );
+
+        Ok(RotatedApiKey {
+            key: replacement,
+            previous_key_id: existing.id,
+            previous_key_expires_at,
+        })
+    }
+
     /// List all API keys for a consumer
     pub async fn list_keys(&self, consumer_id: Uuid) -> Result<Vec<ApiKey>> {
         let keys = sqlx::query_as::<_, ApiKey>(
             r#"
             SELECT id, key_hash, consumer_id, service_id, tier,
-                   created_at, expires_at, revoked_at, metadata
+                   created_at, expires_at, revoked_at, metadata, scopes
             FROM api_keys
             WHERE consumer_id = $1
             ORDER BY created_at DESC
@@ -198,9 +345,7 @@ mod tests {
 
     #[test]
     fn test_generate_key() {
-        let manager = ApiKeyManager {
-            db: Arc::new(PgPool::connect_lazy("postgres://localhost").unwrap()),
-        };
+        let manager = ApiKeyManager::new(PgPool::connect_lazy("postgres://localhost").unwrap());
 
         let key1 = manager.generate_key();
         let key2 = manager.generate_key();
@@ -213,9 +358,7 @@ mod tests {
 
     #[test]
     fn test_hash_key() {
-        let manager = ApiKeyManager {
-            db: Arc::new(PgPool::connect_lazy("postgres://localhost").unwrap()),
-        };
+        let manager = ApiKeyManager::new(PgPool::connect_lazy("postgres://localhost").unwrap());
 
         let key = "test_key_12345";
         let hash1 = manager.hash_key(key).unwrap();
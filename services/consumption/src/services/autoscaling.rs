@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::analytics_streamer::AnalyticsStreamer;
+use super::request_router::UPSTREAM_POOL_SIZE;
+use crate::middleware::metrics::{record, HTTP_REQUESTS_IN_FLIGHT, UPSTREAM_REQUESTS_IN_FLIGHT};
+use crate::models::ServiceTier;
+
+/// Seconds of admission history used to compute per-tier rates
+const RATE_WINDOW_SECS: usize = 10;
+
+/// Load signals for KEDA/HPA scaling of the consumption deployment
+#[derive(Clone)]
+pub struct AutoscalingSignals {
+    analytics: AnalyticsStreamer,
+    admissions: Arc<Mutex<HashMap<&'static str, AdmissionWindow>>>,
+    started: Instant,
+}
+
+/// Point-in-time view of the scaling signals
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoscalingSnapshot {
+    pub in_flight_requests: i64,
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    pub upstream_in_flight: i64,
+    /// Upstream in-flight requests relative to the connection pool size
+    pub upstream_saturation: f64,
+    pub admission_rates: HashMap<String, TierAdmissionRate>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Admission decisions per second over the rate window
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TierAdmissionRate {
+    pub admitted_per_sec: f64,
+    pub rejected_per_sec: f64,
+}
+
+/// Ring of one-second buckets of (admitted, rejected) counts
+#[derive(Default)]
+struct AdmissionWindow {
+    buckets: [(u64, u64, u64); RATE_WINDOW_SECS],
+}
+
+impl AdmissionWindow {
+    fn bucket(&mut self, second: u64) -> &mut (u64, u64, u64) {
+        let bucket = &mut self.buckets[second as usize % RATE_WINDOW_SECS];
+        if bucket.0 != second {
+            *bucket = (second, 0, 0);
+        }
+        bucket
+    }
+
+    fn record(&mut self, second: u64, admitted: bool) {
+        let bucket = self.bucket(second);
+        if admitted {
+            bucket.1 += 1;
+        } else {
+            bucket.2 += 1;
+        }
+    }
+
+    fn rate(&self, now_second: u64) -> TierAdmissionRate {
+        let oldest = now_second.saturating_sub(RATE_WINDOW_SECS as u64 - 1);
+        let (admitted, rejected) = self
+            .buckets
+            .iter()
+            .filter(|(second, _, _)| *second >= oldest && *second <= now_second)
+            .fold((0, 0), |(a, r), (_, admitted, rejected)| {
+                (a + admitted, r + rejected)
+            });
+
+        TierAdmissionRate {
+            admitted_per_sec: admitted as f64 / RATE_WINDOW_SECS as f64,
+            rejected_per_sec: rejected as f64 / RATE_WINDOW_SECS as f64,
+        }
+    }
+}
+
+impl AutoscalingSignals {
+    pub fn new(analytics: AnalyticsStreamer) -> Self {
+        Self {
+            analytics,
+            admissions: Arc::new(Mutex::new(HashMap::new())),
+            started: Instant::now(),
+        }
+    }
+
+    /// Record an admission decision for the consumption pipeline
+    pub fn record_admission(&self, tier: &ServiceTier, admitted: bool) {
+        record::admission(tier.as_str(), admitted);
+
+        let second = self.started.elapsed().as_secs();
+        self.admissions
+            .lock()
+            .unwrap()
+            .entry(tier.as_str())
+            .or_default()
+            .record(second, admitted);
+    }
+
+    /// Current scaling signals
+    pub fn snapshot(&self) -> AutoscalingSnapshot {
+        let queue = self.analytics.metrics();
+        record::analytics_queue_depth(queue.current_length);
+
+        let upstream_in_flight = UPSTREAM_REQUESTS_IN_FLIGHT.get();
+        let second = self.started.elapsed().as_secs();
+
+        let admission_rates = self
+            .admissions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(tier, window)| (tier.to_string(), window.rate(second)))
+            .collect();
+
+        AutoscalingSnapshot {
+            in_flight_requests: HTTP_REQUESTS_IN_FLIGHT.get(),
+            queue_depth: queue.current_length,
+            queue_capacity: queue.capacity,
+            upstream_in_flight,
+            upstream_saturation: upstream_in_flight as f64 / UPSTREAM_POOL_SIZE as f64,
+            admission_rates,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admission_window_expires_old_buckets() {
+        let mut window = AdmissionWindow::default();
+
+        for _ in 0..20 {
+            window.record(1, true);
+        }
+        window.record(2, false);
+
+        let rate = window.rate(2);
+        assert_eq!(rate.admitted_per_sec, 2.0);
+        assert_eq!(rate.rejected_per_sec, 0.1);
+
+        // Buckets older than the window no longer count
+        let rate = window.rate(1 + RATE_WINDOW_SECS as u64);
+        assert_eq!(rate.admitted_per_sec, 0.0);
+        assert_eq!(rate.rejected_per_sec, 0.1);
+    }
+}
@@ -0,0 +1,322 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::sla_reports::month_bounds;
+use super::statements::{Statement, StatementGenerator};
+
+/// Item of usage not priced in brackets (per-token, per-request, or records
+/// from before a service moved to tiered pricing)
+const USAGE_ITEM: &str = "usage";
+
+/// Charge for one pricing bracket, or for unbracketed usage, of a service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceItem {
+    /// Bracket tier name, or `usage`
+    pub description: String,
+    pub tokens: i64,
+    pub amount: f64,
+}
+
+/// Charges of one service within an invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub service_id: Uuid,
+    pub service_name: String,
+    /// Billed (successful) requests
+    pub requests: i64,
+    pub items: Vec<InvoiceItem>,
+    pub amount: f64,
+    pub sla_credit: f64,
+}
+
+/// A consumer's invoice for one month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    /// `INV-YYYYMM-<consumer prefix>`, stable for a consumer and month
+    pub invoice_number: String,
+    pub consumer_id: Uuid,
+    /// Invoice month, `YYYY-MM`
+    pub month: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// The month is still in progress; figures will change
+    pub provisional: bool,
+    pub lines: Vec<InvoiceLine>,
+    pub subtotal: f64,
+    pub sla_credits: f64,
+    /// Flat placeholder rate until per-jurisdiction tax is calculated
+    pub tax_rate: f64,
+    pub tax: f64,
+    pub total: f64,
+    pub currency: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+impl Invoice {
+    /// Build an invoice from the month's statement and the bracket charges
+    /// of each service, keyed by service id
+    fn new(
+        statement: Statement,
+        mut brackets: HashMap<Uuid, Vec<InvoiceItem>>,
+        tax_rate: f64,
+    ) -> Self {
+        let lines: Vec<InvoiceLine> = statement
+            .lines
+            .into_iter()
+            .map(|line| {
+                let mut items = brackets.remove(&line.service_id).unwrap_or_default();
+                let bracketed_tokens: i64 = items.iter().map(|item| item.tokens).sum();
+                let bracketed_amount: f64 = items.iter().map(|item| item.amount).sum();
+                // Whatever the brackets don't account for was priced flat
+                if line.tokens > bracketed_tokens || line.cost - bracketed_amount > 1e-9 {
+                    items.push(InvoiceItem {
+                        description: USAGE_ITEM.to_string(),
+                        tokens: (line.tokens - bracketed_tokens).max(0),
+                        amount: (line.cost - bracketed_amount).max(0.0),
+                    });
+                }
+
+                InvoiceLine {
+                    service_id: line.service_id,
+                    service_name: line.service_name,
+                    requests: line.successful_requests,
+                    items,
+                    amount: line.cost,
+                    sla_credit: line.sla_credit,
+                }
+            })
+            .collect();
+
+        let taxable = statement.total_due;
+        let tax = taxable * tax_rate;
+        let consumer = statement.consumer_id.simple().to_string();
+
+        Self {
+            invoice_number: format!(
+                "INV-{}-{}",
+                statement.month.replace('-', ""),
+                consumer[..8].to_uppercase()
+            ),
+            consumer_id: statement.consumer_id,
+            month: statement.month,
+            period_start: statement.period_start,
+            period_end: statement.period_end,
+            provisional: statement.provisional,
+            lines,
+            subtotal: statement.subtotal,
+            sla_credits: statement.sla_credits,
+            tax_rate,
+            tax,
+            total: taxable + tax,
+            currency: statement.currency,
+            issued_at: statement.generated_at,
+        }
+    }
+
+    /// One row per item, SLA credit and tax, for import into accounting
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "invoice_number,month,service_id,service_name,item,tokens,amount,currency\n",
+        );
+        let mut row =
+            |service_id: String, service_name: &str, item: &str, tokens: String, amount: f64| {
+                let _ = writeln!(
+                    csv,
+                    "{},{},{},\"{}\",\"{}\",{},{:.6},{}",
+                    self.invoice_number,
+                    self.month,
+                    service_id,
+                    service_name.replace('"', "\"\""),
+                    item.replace('"', "\"\""),
+                    tokens,
+                    amount,
+                    self.currency
+                );
+            };
+
+        for line in &self.lines {
+            for item in &line.items {
+                row(
+                    line.service_id.to_string(),
+                    &line.service_name,
+                    &item.description,
+                    item.tokens.to_string(),
+                    item.amount,
+                );
+            }
+            if line.sla_credit > 0.0 {
+                row(
+                    line.service_id.to_string(),
+                    &line.service_name,
+                    "sla_credit",
+                    String::new(),
+                    -line.sla_credit,
+                );
+            }
+        }
+        row(String::new(), "", "tax", String::new(), self.tax);
+        csv
+    }
+}
+
+/// Monthly consumer invoices for finance, built on statements with usage
+/// itemized by pricing bracket
+#[derive(Clone)]
+pub struct InvoiceGenerator {
+    db: Arc<PgPool>,
+    statements: StatementGenerator,
+    tax_rate: f64,
+}
+
+impl InvoiceGenerator {
+    pub fn new(db: PgPool, statements: StatementGenerator) -> Self {
+        Self {
+            db: Arc::new(db),
+            statements,
+            tax_rate: 0.0,
+        }
+    }
+
+    /// Apply a flat tax rate (e.g. `0.2` for 20%) to amounts due
+    pub fn with_tax_rate(mut self, tax_rate: f64) -> Self {
+        self.tax_rate = tax_rate;
+        self
+    }
+
+    /// Invoice for a month, starting on its first day. Totals match the
+    /// month's statement before tax.
+    pub async fn get_invoice(&self, consumer_id: Uuid, month: NaiveDate) -> Result<Invoice> {
+        let statement = self.statements.get_statement(consumer_id, month).await?;
+        let brackets = self.bracket_charges(consumer_id, month).await?;
+
+        Ok(Invoice::new(statement, brackets, self.tax_rate))
+    }
+
+    /// Tokens and amounts per service and pricing bracket, summed from the
+    /// cost breakdowns of the month's billed usage records
+    async fn bracket_charges(
+        &self,
+        consumer_id: Uuid,
+        month: NaiveDate,
+    ) -> Result<HashMap<Uuid, Vec<InvoiceItem>>> {
+        let (period_start, period_end) = month_bounds(month);
+        let until = period_end.min(Utc::now());
+
+        let rows = sqlx::query_as::<_, (Uuid, String, i64, f64)>(
+            r#"
+            SELECT
+                u.service_id,
+                b->>'tier',
+                COALESCE(SUM((b->>'prompt_tokens')::BIGINT
+                    + (b->>'completion_tokens')::BIGINT), 0)::BIGINT,
+                COALESCE(SUM((b->>'amount')::DOUBLE PRECISION), 0.0)
+            FROM usage_records_all u
+            CROSS JOIN LATERAL jsonb_array_elements(
+                CASE jsonb_typeof(u.cost->'breakdown'->'brackets')
+                    WHEN 'array' THEN u.cost->'breakdown'->'brackets'
+                    ELSE '[]'::JSONB
+                END
+            ) b
+            WHERE u.consumer_id = $1
+                AND u.timestamp >= $2
+                AND u.timestamp < $3
+                AND u.status = 'success'
+            GROUP BY u.service_id, b->>'tier'
+            ORDER BY u.service_id, MIN((b->>'up_to')::BIGINT) NULLS LAST
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(period_start)
+        .bind(until)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to get invoice pricing brackets")?;
+
+        let mut brackets: HashMap<Uuid, Vec<InvoiceItem>> = HashMap::new();
+        for (service_id, tier, tokens, amount) in rows {
+            brackets.entry(service_id).or_default().push(InvoiceItem {
+                description: tier,
+                tokens,
+                amount,
+            });
+        }
+        Ok(brackets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::statements::StatementLine;
+
+    #[test]
+    fn test_invoice_items_and_tax() {
+        let tiered = Uuid::new_v4();
+        let line =
+            |service_id: Uuid, name: &str, tokens: i64, cost: f64, sla_credit: f64| StatementLine {
+                service_id,
+                service_name: name.to_string(),
+                requests: 10,
+                successful_requests: 9,
+                tokens,
+                cost,
+                sla_credit,
+            };
+        let statement: Statement = serde_json::from_value(serde_json::json!({
+            "consumer_id": "6f1c2a3b-0000-4000-8000-000000000000",
+            "month": "2025-11",
+            "period_start": "2025-11-01T00:00:00Z",
+            "period_end": "2025-12-01T00:00:00Z",
+            "provisional": false,
+            "lines": [
+                line(tiered, "gpt", 12000, 0.94, 0.04),
+                line(Uuid::new_v4(), "embed", 500, 0.05, 0.0),
+            ],
+            "total_requests": 20,
+            "total_tokens": 12500,
+            "subtotal": 0.99,
+            "sla_credits": 0.04,
+            "total_due": 0.95,
+            "currency": "USD",
+            "events": [],
+            "generated_at": "2025-12-01T02:00:00Z",
+        }))
+        .unwrap();
+
+        let item = |description: &str, tokens: i64, amount: f64| InvoiceItem {
+            description: description.to_string(),
+            tokens,
+            amount,
+        };
+        let brackets = HashMap::from([(
+            tiered,
+            vec![item("first-1k", 1000, 0.1), item("next-9k", 9000, 0.72)],
+        )]);
+
+        let invoice = Invoice::new(statement, brackets, 0.2);
+        assert_eq!(invoice.invoice_number, "INV-202511-6F1C2A3B");
+        assert!((invoice.tax - 0.19).abs() < 1e-9);
+        assert!((invoice.total - 1.14).abs() < 1e-9);
+
+        // Tokens the brackets don't cover are billed as flat usage
+        let gpt = &invoice.lines[0].items;
+        assert_eq!(gpt.len(), 3);
+        assert_eq!(gpt[2].description, USAGE_ITEM);
+        assert_eq!(gpt[2].tokens, 2000);
+        assert!((gpt[2].amount - 0.12).abs() < 1e-9);
+        assert_eq!(invoice.lines[1].items.len(), 1);
+
+        let csv = invoice.to_csv();
+        // Header, four items, one SLA credit and tax
+        assert_eq!(csv.lines().count(), 7);
+        assert!(csv.contains("\"gpt\",\"next-9k\",9000,0.720000,USD"));
+        assert!(csv.contains("\"gpt\",\"sla_credit\",,-0.040000,USD"));
+        assert!(csv.ends_with(",\"\",\"tax\",,0.190000,USD\n"));
+    }
+}
@@ -0,0 +1,364 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::info;
+use uuid::Uuid;
+
+/// How long a consumer's billing anchor is cached
+const ANCHOR_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// One quota period: usage counts from `start` until the reset at `end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QuotaPeriod {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl QuotaPeriod {
+    /// YYYY-MM of the period start, the `month` of `quota_usage` and
+    /// `quota_events`. Periods start at most once a month, so labels of
+    /// consecutive periods never collide.
+    pub fn label(&self) -> String {
+        format!("{}-{:02}", self.start.year(), self.start.month())
+    }
+}
+
+/// Day of the month an organization's quotas reset. Days past the end of a
+/// month reset on its last day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BillingAnchor {
+    pub anchor_day: u32,
+    /// Period bridging the old and new anchor after a change; it keeps the
+    /// start (and label) of the period in progress when the anchor changed
+    pub transition: Option<QuotaPeriod>,
+}
+
+impl Default for BillingAnchor {
+    /// Calendar months, for organizations without an anchor
+    fn default() -> Self {
+        Self {
+            anchor_day: 1,
+            transition: None,
+        }
+    }
+}
+
+/// Midnight UTC of the anchor day in a month, clamped to the month's last day
+fn anchor_date(year: i32, month: u32, anchor_day: u32) -> DateTime<Utc> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).expect("valid month");
+    let last_day = first
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .expect("valid month end");
+
+    first
+        .with_day(anchor_day.clamp(1, last_day))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .expect("valid anchor date")
+        .and_utc()
+}
+
+/// `(year, month)` shifted by `delta` months
+fn shift_month(year: i32, month: u32, delta: i32) -> (i32, u32) {
+    let index = year * 12 + month as i32 - 1 + delta;
+    (index.div_euclid(12), index.rem_euclid(12) as u32 + 1)
+}
+
+impl BillingAnchor {
+    /// Quota period containing `at`
+    pub fn period(&self, at: DateTime<Utc>) -> QuotaPeriod {
+        if let Some(transition) = self.transition {
+            if transition.start <= at && at < transition.end {
+                return transition;
+            }
+        }
+
+        let this_month = anchor_date(at.year(), at.month(), self.anchor_day);
+        if at >= this_month {
+            let (year, month) = shift_month(at.year(), at.month(), 1);
+            QuotaPeriod {
+                start: this_month,
+                end: anchor_date(year, month, self.anchor_day),
+            }
+        } else {
+            let (year, month) = shift_month(at.year(), at.month(), -1);
+            QuotaPeriod {
+                start: anchor_date(year, month, self.anchor_day),
+                end: this_month,
+            }
+        }
+    }
+
+    /// Anchor after moving to `anchor_day` at `at`. The period in progress
+    /// runs on to the first new anchor date in a later month than it started,
+    /// so usage already counted stays in its period and the next period gets
+    /// a fresh label.
+    pub fn change(&self, anchor_day: u32, at: DateTime<Utc>) -> Self {
+        let current = self.period(at);
+        let (mut year, mut month) = shift_month(current.start.year(), current.start.month(), 1);
+        let mut end = anchor_date(year, month, anchor_day);
+        while end <= at {
+            (year, month) = shift_month(year, month, 1);
+            end = anchor_date(year, month, anchor_day);
+        }
+
+        Self {
+            anchor_day,
+            transition: Some(QuotaPeriod {
+                start: current.start,
+                end,
+            }),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct AnchorRow {
+    anchor_day: i16,
+    transition_start: Option<DateTime<Utc>>,
+    transition_end: Option<DateTime<Utc>>,
+}
+
+impl From<AnchorRow> for BillingAnchor {
+    fn from(row: AnchorRow) -> Self {
+        Self {
+            anchor_day: row.anchor_day as u32,
+            transition: row
+                .transition_start
+                .zip(row.transition_end)
+                .map(|(start, end)| QuotaPeriod { start, end }),
+        }
+    }
+}
+
+/// Per-organization billing anchors; consumers belong to an organization
+/// through their API key metadata
+#[derive(Clone)]
+pub struct BillingAnchors {
+    db: Arc<PgPool>,
+    cache: Arc<RwLock<HashMap<Uuid, (Instant, BillingAnchor)>>>,
+}
+
+impl BillingAnchors {
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self {
+            db,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Anchor of a consumer's organization, calendar months without one
+    pub async fn for_consumer(&self, consumer_id: Uuid) -> Result<BillingAnchor> {
+        if let Some((loaded_at, anchor)) = self.cache.read().unwrap().get(&consumer_id) {
+            if loaded_at.elapsed() < ANCHOR_CACHE_TTL {
+                return Ok(*anchor);
+            }
+        }
+
+        let anchor = sqlx::query_as::<_, AnchorRow>(
+            r#"
+            SELECT b.anchor_day, b.transition_start, b.transition_end
+            FROM billing_anchors b
+            JOIN api_keys k ON k.metadata->>'organization_id' = b.organization_id::text
+            WHERE k.consumer_id = $1
+            LIMIT 1
+            "#,
+        )
+        .bind(consumer_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to look up billing anchor")?
+        .map(BillingAnchor::from)
+        .unwrap_or_default();
+
+        self.cache
+            .write()
+            .unwrap()
+            .insert(consumer_id, (Instant::now(), anchor));
+
+        Ok(anchor)
+    }
+
+    pub async fn get(&self, organization_id: Uuid) -> Result<Option<BillingAnchor>> {
+        let anchor = sqlx::query_as::<_, AnchorRow>(
+            "SELECT anchor_day, transition_start, transition_end FROM billing_anchors WHERE organization_id = $1",
+        )
+        .bind(organization_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to get billing anchor")?;
+
+        Ok(anchor.map(BillingAnchor::from))
+    }
+
+    /// Move an organization's quota resets to `anchor_day`. Returns the new
+    /// anchor and the organization's consumers, whose counters must now
+    /// expire at the end of the transition period.
+    pub async fn set(
+        &self,
+        organization_id: Uuid,
+        anchor_day: u32,
+    ) -> Result<(BillingAnchor, Vec<Uuid>)> {
+        anyhow::ensure!(
+            (1..=31).contains(&anchor_day),
+            "Anchor day must be between 1 and 31"
+        );
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+
+        let current = sqlx::query_as::<_, AnchorRow>(
+            r#"
+            SELECT anchor_day, transition_start, transition_end
+            FROM billing_anchors
+            WHERE organization_id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to lock billing anchor")?
+        .map(BillingAnchor::from)
+        .unwrap_or_default();
+
+        let now = Utc::now();
+        let anchor = if current.anchor_day == anchor_day {
+            current
+        } else {
+            current.change(anchor_day, now)
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO billing_anchors (organization_id, anchor_day, transition_start, transition_end, updated_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (organization_id) DO UPDATE SET
+                anchor_day = EXCLUDED.anchor_day,
+                transition_start = EXCLUDED.transition_start,
+                transition_end = EXCLUDED.transition_end,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(organization_id)
+        .bind(anchor.anchor_day as i16)
+        .bind(anchor.transition.map(|period| period.start))
+        .bind(anchor.transition.map(|period| period.end))
+        .execute(&mut *tx)
+        .await
+        .context("Failed to store billing anchor")?;
+
+        let members: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT DISTINCT consumer_id FROM api_keys WHERE metadata->>'organization_id' = $1::text",
+        )
+        .bind(organization_id)
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to list organization consumers")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit billing anchor")?;
+
+        let mut cache = self.cache.write().unwrap();
+        for consumer_id in &members {
+            cache.remove(consumer_id);
+        }
+        drop(cache);
+
+        info!(
+            organization_id = %organization_id,
+            anchor_day = anchor_day,
+            previous_anchor_day = current.anchor_day,
+            reset_at = %anchor.period(now).end,
+            consumers = members.len(),
+            "Billing anchor changed"
+        );
+
+        Ok((anchor, members))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, month, day, 12, 0, 0).unwrap()
+    }
+
+    fn midnight(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_periods_follow_anchor_day() {
+        let calendar = BillingAnchor::default().period(at(12, 20));
+        assert_eq!(calendar.start, midnight(2026, 12, 1));
+        assert_eq!(calendar.end, midnight(2027, 1, 1));
+        assert_eq!(calendar.label(), "2026-12");
+
+        let anchor = BillingAnchor {
+            anchor_day: 15,
+            transition: None,
+        };
+        let before = anchor.period(at(1, 10));
+        assert_eq!(before.start, midnight(2025, 12, 15));
+        assert_eq!(before.end, midnight(2026, 1, 15));
+        assert_eq!(before.label(), "2025-12");
+        assert_eq!(
+            anchor.period(midnight(2026, 1, 15)).start,
+            midnight(2026, 1, 15)
+        );
+
+        // The 31st resets on the last day of shorter months
+        let anchor = BillingAnchor {
+            anchor_day: 31,
+            transition: None,
+        };
+        assert_eq!(anchor.period(at(2, 10)).end, midnight(2026, 2, 28));
+        let february = anchor.period(at(3, 5));
+        assert_eq!(february.start, midnight(2026, 2, 28));
+        assert_eq!(february.end, midnight(2026, 3, 31));
+    }
+
+    #[test]
+    fn test_anchor_change_bridges_period_in_progress() {
+        // Calendar month to the 15th on Oct 5: October runs until Nov 15
+        let changed = BillingAnchor::default().change(15, at(10, 5));
+        let transition = changed.period(at(10, 20));
+        assert_eq!(transition.start, midnight(2026, 10, 1));
+        assert_eq!(transition.end, midnight(2026, 11, 15));
+        assert_eq!(transition.label(), "2026-10");
+        let next = changed.period(at(11, 20));
+        assert_eq!(next.start, midnight(2026, 11, 15));
+        assert_eq!(next.label(), "2026-11");
+
+        // The 15th to calendar months on Oct 20: the period ends Nov 1
+        let anchor = BillingAnchor {
+            anchor_day: 15,
+            transition: None,
+        };
+        let transition = anchor.change(1, at(10, 20)).period(at(10, 20));
+        assert_eq!(transition.start, midnight(2026, 10, 15));
+        assert_eq!(transition.end, midnight(2026, 11, 1));
+
+        // A new anchor already passed in the following month moves one later
+        let anchor = BillingAnchor {
+            anchor_day: 28,
+            transition: None,
+        };
+        let transition = anchor.change(15, at(11, 20)).period(at(11, 20));
+        assert_eq!(transition.start, midnight(2026, 10, 28));
+        assert_eq!(transition.end, midnight(2026, 12, 15));
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::rate_limiter::RateLimiter;
+use super::request_router::RequestRouter;
+use super::service_health::ServiceHealth;
+use super::sla_monitor::SLAMonitor;
+use super::usage_meter::UsageMeter;
+use crate::middleware::metrics::record;
+use crate::models::{ConsumeRequest, Service, ServiceTier, UsageInfo};
+
+/// Well-known consumer id used for canary traffic when none is configured
+const DEFAULT_CANARY_CONSUMER_ID: Uuid = Uuid::from_u128(0x00000000_0000_4000_8000_00000000ca4a);
+
+/// Known-safe prompt sent by the canary
+const CANARY_PROMPT: &str = "Reply with the single word OK.";
+
+/// Consumer id the canary issues requests under (`CANARY_CONSUMER_ID`)
+pub fn canary_consumer_id() -> Uuid {
+    std::env::var("CANARY_CONSUMER_ID")
+        .ok()
+        .and_then(|v| Uuid::parse_str(&v).ok())
+        .unwrap_or(DEFAULT_CANARY_CONSUMER_ID)
+}
+
+/// Where a failed canary probe broke down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanaryOutcome {
+    Success,
+    /// The upstream LLM provider failed or returned an error
+    ProviderFailure,
+    /// The marketplace itself (database, Redis, admission) failed
+    MarketplaceFailure,
+}
+
+impl CanaryOutcome {
+    /// Usage record status; only `error` counts against the provider's SLA
+    pub fn as_status(&self) -> &'static str {
+        match self {
+            CanaryOutcome::Success => "success",
+            CanaryOutcome::ProviderFailure => "error",
+            CanaryOutcome::MarketplaceFailure => "marketplace_error",
+        }
+    }
+}
+
+/// Result of a single canary probe
+#[derive(Debug, Clone)]
+pub struct CanaryProbe {
+    pub service_id: Uuid,
+    pub outcome: CanaryOutcome,
+    /// End-to-end latency including any injected delay
+    pub total_latency_ms: u64,
+    /// Latency attributable to the provider
+    pub provider_latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Synthetic canary issuing known-safe requests through the consumption pipeline
+#[derive(Clone)]
+pub struct SyntheticCanary {
+    db: Arc<PgPool>,
+    rate_limiter: RateLimiter,
+    request_router: RequestRouter,
+    usage_meter: UsageMeter,
+    sla_monitor: SLAMonitor,
+    service_health: Option<ServiceHealth>,
+    consumer_id: Uuid,
+    injected_latency: Duration,
+}
+
+impl SyntheticCanary {
+    pub fn new(
+        db: PgPool,
+        rate_limiter: RateLimiter,
+        request_router: RequestRouter,
+        usage_meter: UsageMeter,
+        sla_monitor: SLAMonitor,
+    ) -> Self {
+        let consumer_id = canary_consumer_id();
+
+        // Artificial marketplace-side delay, used to exercise latency alerting
+        let injected_latency = std::env::var("CANARY_INJECTED_LATENCY_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::ZERO);
+
+        Self {
+            db: Arc::new(db),
+            rate_limiter,
+            request_router,
+            usage_meter,
+            sla_monitor,
+            service_health: None,
+            consumer_id,
+            injected_latency,
+        }
+    }
+
+    /// Count probe outcomes towards service health, so an unavailable
+    /// service recovers even without consumer traffic
+    pub fn with_service_health(mut self, service_health: ServiceHealth) -> Self {
+        self.service_health = Some(service_health);
+        self
+    }
+
+    pub fn consumer_id(&self) -> Uuid {
+        self.consumer_id
+    }
+
+    /// Background job: probe every active service once
+    pub async fn probe_all_services(&self) -> Result<Vec<CanaryProbe>> {
+        let service_ids: Vec<Uuid> =
+            sqlx::query_scalar("SELECT id FROM services WHERE status = 'active'")
+                .fetch_all(self.db.as_ref())
+                .await
+                .context("Failed to get active services")?;
+
+        let mut probes = Vec::with_capacity(service_ids.len());
+        for service_id in service_ids {
+            probes.push(self.probe_service(service_id).await);
+        }
+
+        let failures = probes
+            .iter()
+            .filter(|p| p.outcome != CanaryOutcome::Success)
+            .count();
+
+        info!(
+            services = probes.len(),
+            failures = failures,
+            "Canary probes completed"
+        );
+
+        Ok(probes)
+    }
+
+    /// Probe a single service and record the result as an availability sample
+    pub async fn probe_service(&self, service_id: Uuid) -> CanaryProbe {
+        let start = Instant::now();
+        let request_id = Uuid::new_v4();
+
+        let mut probe = CanaryProbe {
+            service_id,
+            outcome: CanaryOutcome::Success,
+            total_latency_ms: 0,
+            provider_latency_ms: 0,
+            error: None,
+        };
+
+        // Marketplace layer: service lookup and admission
+        let service = match self.admit(service_id).await {
+            Ok(service) => service,
+            Err(e) => {
+                warn!(service_id = %service_id, error = %e, "Canary failed in marketplace layer");
+                probe.outcome = CanaryOutcome::MarketplaceFailure;
+                probe.error = Some(e.to_string());
+                probe.total_latency_ms = start.elapsed().as_millis() as u64;
+                self.record(&probe, request_id, None).await;
+                return probe;
+            }
+        };
+
+        if !self.injected_latency.is_zero() {
+            tokio::time::sleep(self.injected_latency).await;
+        }
+
+        // Provider layer
+        let request = ConsumeRequest {
+            prompt: CANARY_PROMPT.to_string(),
+            max_tokens: Some(5),
+            temperature: 0.0,
+            n: None,
+            metadata: serde_json::json!({ "canary": true }),
+        };
+
+        let provider_start = Instant::now();
+        let usage = match self
+            .request_router
+            .route_request(&service, &request, request_id, self.consumer_id)
+            .await
+        {
+            Ok((_, usage, latency_ms)) => {
+                probe.provider_latency_ms = latency_ms;
+                Some(usage)
+            }
+            Err(e) => {
+                warn!(service_id = %service_id, error = %e, "Canary failed at provider");
+                probe.outcome = CanaryOutcome::ProviderFailure;
+                probe.provider_latency_ms = provider_start.elapsed().as_millis() as u64;
+                probe.error = Some(e.to_string());
+                None
+            }
+        };
+        probe.total_latency_ms = start.elapsed().as_millis() as u64;
+
+        self.record(&probe, request_id, usage).await;
+
+        if let Some(service_health) = &self.service_health {
+            match &probe.error {
+                None => service_health.record_success(service_id).await,
+                Some(e) => service_health.record_failure(service_id, e).await,
+            }
+        }
+
+        // Only provider-attributable samples feed SLA violation checks
+        if let Err(e) = self
+            .sla_monitor
+            .check_sla_violation(&service, probe.provider_latency_ms, probe.outcome.as_status())
+            .await
+        {
+            error!(service_id = %service_id, error = %e, "Failed to check canary SLA");
+        }
+
+        probe
+    }
+
+    /// Run the marketplace-side steps of the pipeline
+    async fn admit(&self, service_id: Uuid) -> Result<Service> {
+        let service = sqlx::query_as::<_, Service>(
+            r#"
+            SELECT id, name, version, endpoint, status, pricing, sla, created_at
+            FROM services
+            WHERE id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to load service")?;
+
+        let status = self
+            .rate_limiter
+            .check_rate_limit(self.consumer_id, service_id, &ServiceTier::Enterprise)
+            .await
+            .context("Rate limit check failed")?;
+
+        if status.exceeded {
+            anyhow::bail!("Canary consumer rate limited");
+        }
+
+        Ok(service)
+    }
+
+    async fn record(&self, probe: &CanaryProbe, request_id: Uuid, usage: Option<UsageInfo>) {
+        record::canary_probe(probe.service_id, probe.outcome.as_status());
+
+        let usage = usage.unwrap_or(UsageInfo {
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            total_tokens: 0,
+        });
+
+        if let Err(e) = self
+            .usage_meter
+            .record_usage(
+                request_id,
+                probe.service_id,
+                self.consumer_id,
+                usage,
+                probe.provider_latency_ms as i32,
+                probe.outcome.as_status().to_string(),
+                probe
+                    .error
+                    .as_ref()
+                    .map(|e| serde_json::json!({ "message": e, "canary": true })),
+                None,
+                None,
+            )
+            .await
+        {
+            error!(service_id = %probe.service_id, error = %e, "Failed to record canary sample");
+        }
+
+        debug!(
+            service_id = %probe.service_id,
+            outcome = probe.outcome.as_status(),
+            total_latency_ms = probe.total_latency_ms,
+            provider_latency_ms = probe.provider_latency_ms,
+            "Canary sample recorded"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marketplace_failures_do_not_count_as_provider_errors() {
+        assert_eq!(CanaryOutcome::ProviderFailure.as_status(), "error");
+        assert_ne!(CanaryOutcome::MarketplaceFailure.as_status(), "error");
+    }
+}
@@ -0,0 +1,133 @@
+use axum::http::{header, HeaderMap};
+use serde::{Deserialize, Serialize};
+
+/// Longest header value kept in usage metadata
+const MAX_VALUE_LEN: usize = 128;
+
+/// Client that issued a request, from the SDK headers and `User-Agent`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientInfo {
+    /// SDK language, or the product name of a non-SDK client
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdk: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdk_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    /// Fingerprint a request. `X-SDK-Language` / `X-SDK-Version` win; clients
+    /// that don't send them are identified by the leading `product/version`
+    /// token of their `User-Agent`.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let value = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(truncate)
+        };
+
+        let user_agent = value(header::USER_AGENT.as_str());
+        let (ua_product, ua_version) = user_agent
+            .as_deref()
+            .and_then(parse_user_agent)
+            .map(|(product, version)| (Some(truncate(product)), Some(truncate(version))))
+            .unwrap_or_default();
+
+        let sdk = value("x-sdk-language");
+        let sdk_version = value("x-sdk-version");
+
+        // Only borrow the User-Agent version when it describes the same client
+        let sdk_version = match (&sdk, sdk_version) {
+            (_, Some(version)) => Some(version),
+            (None, None) => ua_version,
+            (Some(_), None) => None,
+        };
+
+        Self {
+            sdk: sdk.or(ua_product),
+            sdk_version,
+            user_agent,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sdk.is_none() && self.sdk_version.is_none() && self.user_agent.is_none()
+    }
+
+    /// Usage record metadata carrying this client, or `None` if nothing was
+    /// sent
+    pub fn to_metadata(&self) -> Option<serde_json::Value> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(serde_json::json!({ "client": self }))
+    }
+}
+
+/// Split the first `User-Agent` token into product and version, e.g.
+/// `@llm-marketplace/sdk/1.0.0` -> (`@llm-marketplace/sdk`, `1.0.0`)
+fn parse_user_agent(user_agent: &str) -> Option<(&str, &str)> {
+    let token = user_agent.split_whitespace().next()?;
+    let (product, version) = token.rsplit_once('/')?;
+    if product.is_empty() || version.is_empty() {
+        return None;
+    }
+    Some((product, version))
+}
+
+fn truncate(value: &str) -> String {
+    match value.char_indices().nth(MAX_VALUE_LEN) {
+        Some((end, _)) => value[..end].to_string(),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_sdk_headers_take_precedence() {
+        let info = ClientInfo::from_headers(&headers(&[
+            ("user-agent", "@llm-marketplace/sdk/1.0.0"),
+            ("x-sdk-language", "javascript"),
+            ("x-sdk-version", "1.0.0"),
+        ]));
+
+        assert_eq!(info.sdk.as_deref(), Some("javascript"));
+        assert_eq!(info.sdk_version.as_deref(), Some("1.0.0"));
+        assert_eq!(info.user_agent.as_deref(), Some("@llm-marketplace/sdk/1.0.0"));
+    }
+
+    #[test]
+    fn test_falls_back_to_user_agent() {
+        let info = ClientInfo::from_headers(&headers(&[(
+            "user-agent",
+            "python-requests/2.31.0 CPython/3.12",
+        )]));
+        assert_eq!(info.sdk.as_deref(), Some("python-requests"));
+        assert_eq!(info.sdk_version.as_deref(), Some("2.31.0"));
+
+        // A language header without a version doesn't borrow another product's version
+        let info = ClientInfo::from_headers(&headers(&[
+            ("user-agent", "curl/8.4.0"),
+            ("x-sdk-language", "go"),
+        ]));
+        assert_eq!(info.sdk.as_deref(), Some("go"));
+        assert_eq!(info.sdk_version, None);
+
+        assert!(ClientInfo::from_headers(&HeaderMap::new()).to_metadata().is_none());
+    }
+}
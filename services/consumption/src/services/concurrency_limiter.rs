@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use redis::{aio::ConnectionManager, AsyncCommands, Script};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// How long a permit holds its slot if it's never released, e.g. when the
+/// replica holding it dies; longer than the longest streamed request
+const PERMIT_LEASE_MS: i64 = 15 * 60 * 1000;
+
+/// How often a queued request retries for a free slot
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Redis-backed semaphore capping how many requests a consumer has in
+/// flight on a service, across all replicas.
+///
+/// Each in-flight request is a member of a sorted set scored by its lease
+/// expiry. Requests over the limit wait up to the queue timeout for a slot,
+/// then are rejected.
+#[derive(Clone)]
+pub struct ConcurrencyLimiter {
+    redis: Arc<ConnectionManager>,
+    queue_timeout: Duration,
+}
+
+/// A slot held by one in-flight request, released when dropped
+pub struct ConcurrencyPermit {
+    redis: Arc<ConnectionManager>,
+    key: String,
+    member: String,
+}
+
+impl ConcurrencyLimiter {
+    /// Limiter rejecting requests over the limit right away
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self {
+            redis: Arc::new(redis),
+            queue_timeout: Duration::ZERO,
+        }
+    }
+
+    /// Let requests over the limit wait up to `timeout` for a slot
+    pub fn with_queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = timeout;
+        self
+    }
+
+    fn key(consumer_id: Uuid, service_id: Uuid) -> String {
+        format!("concurrency:{}:{}", consumer_id, service_id)
+    }
+
+    /// Take one of the consumer's `limit` slots on the service, waiting up
+    /// to the queue timeout for one to free up. `None` if none did.
+    pub async fn acquire(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        limit: u32,
+    ) -> Result<Option<ConcurrencyPermit>> {
+        let key = Self::key(consumer_id, service_id);
+        let member = Uuid::new_v4().to_string();
+        let started = Instant::now();
+
+        loop {
+            if self.try_acquire(&key, &member, limit).await? {
+                debug!(
+                    consumer_id = %consumer_id,
+                    service_id = %service_id,
+                    waited_ms = started.elapsed().as_millis() as u64,
+                    "Concurrency permit acquired"
+                );
+                return Ok(Some(ConcurrencyPermit {
+                    redis: self.redis.clone(),
+                    key,
+                    member,
+                }));
+            }
+
+            let Some(remaining) = self.queue_timeout.checked_sub(started.elapsed()) else {
+                return Ok(None);
+            };
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            tokio::time::sleep(QUEUE_POLL_INTERVAL.min(remaining)).await;
+        }
+    }
+
+    async fn try_acquire(&self, key: &str, member: &str, limit: u32) -> Result<bool> {
+        let script = Script::new(
+            r"
+            local key = KEYS[1]
+            local limit = tonumber(ARGV[1])
+            local now = tonumber(ARGV[2])
+            local lease = tonumber(ARGV[3])
+            local member = ARGV[4]
+
+            -- Free slots whose holder never released them
+            redis.call('ZREMRANGEBYSCORE', key, '-inf', now)
+
+            if redis.call('ZCARD', key) >= limit then
+                return 0
+            end
+
+            redis.call('ZADD', key, now + lease, member)
+            redis.call('PEXPIRE', key, lease)
+            return 1
+            ",
+        );
+
+        let mut conn = self.redis.as_ref().clone();
+        let acquired: i64 = script
+            .key(key)
+            .arg(limit)
+            .arg(Utc::now().timestamp_millis())
+            .arg(PERMIT_LEASE_MS)
+            .arg(member)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to execute concurrency limit script")?;
+
+        Ok(acquired == 1)
+    }
+
+    /// Requests a consumer has in flight on a service
+    pub async fn in_flight(&self, consumer_id: Uuid, service_id: Uuid) -> Result<u64> {
+        let mut conn = self.redis.as_ref().clone();
+        conn.zcount(
+            Self::key(consumer_id, service_id),
+            Utc::now().timestamp_millis(),
+            "+inf",
+        )
+        .await
+        .context("Failed to count in-flight requests")
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let mut conn = self.redis.as_ref().clone();
+        let key = std::mem::take(&mut self.key);
+        let member = std::mem::take(&mut self.member);
+        runtime.spawn(async move {
+            if let Err(e) = conn.zrem::<_, _, ()>(&key, &member).await {
+                // The slot frees up once its lease runs out
+                warn!(error = %e, key = %key, "Failed to release concurrency permit");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrency_limiter() {
+        // This test requires Redis to be running
+        // Skip in CI environments
+        if std::env::var("CI").is_ok() {
+            return;
+        }
+
+        let redis = redis::Client::open("redis://localhost:6379")
+            .unwrap()
+            .get_tokio_connection_manager()
+            .await
+            .unwrap();
+
+        let limiter = ConcurrencyLimiter::new(redis);
+        let consumer_id = Uuid::new_v4();
+        let service_id = Uuid::new_v4();
+
+        let first = limiter.acquire(consumer_id, service_id, 2).await.unwrap();
+        let second = limiter.acquire(consumer_id, service_id, 2).await.unwrap();
+        assert!(first.is_some() && second.is_some());
+        assert!(limiter
+            .acquire(consumer_id, service_id, 2)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(limiter.in_flight(consumer_id, service_id).await.unwrap(), 2);
+
+        // A queued request gets the slot released while it waits
+        let queued = limiter.clone().with_queue_timeout(Duration::from_secs(2));
+        drop(first);
+        assert!(queued
+            .acquire(consumer_id, service_id, 2)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
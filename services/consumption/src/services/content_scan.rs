@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::middleware::metrics::record;
+use crate::services::shield_client::{
+    ContentScanResponse, ContentType, FilterAction, ShieldClient,
+};
+
+/// How long a service's scan config is cached before being reloaded
+const CONFIG_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Per-service LLM-Shield content scanning. Responses are scanned by the
+/// response pipeline's `shield_scan` stage; both honor `fail_closed`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentScanConfig {
+    /// Scan prompts before routing
+    #[serde(default)]
+    pub scan_prompts: bool,
+    /// Reject requests while Shield can't be reached, instead of letting
+    /// content through unscanned. Always on when the deployment's Shield
+    /// failure policy is closed.
+    #[serde(default)]
+    pub fail_closed: bool,
+}
+
+/// What to do with scanned text, from Shield's `FilterAction`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    /// Allowed, possibly logged by Shield
+    Passed,
+    /// Allowed, but matched a filter set to warn
+    Warned {
+        filters: Vec<String>,
+    },
+    /// Allowed with the matches replaced by `[REDACTED]`
+    Redacted {
+        text: String,
+        filters: Vec<String>,
+    },
+    Blocked {
+        filters: Vec<String>,
+    },
+    /// Shield couldn't be reached and the service fails closed
+    Unavailable,
+}
+
+impl ScanVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanVerdict::Passed => "passed",
+            ScanVerdict::Warned { .. } => "warned",
+            ScanVerdict::Redacted { .. } => "redacted",
+            ScanVerdict::Blocked { .. } => "blocked",
+            ScanVerdict::Unavailable => "unavailable",
+        }
+    }
+}
+
+/// Apply a Shield scan result to the scanned text
+pub fn verdict(text: &str, scan: &ContentScanResponse) -> ScanVerdict {
+    let filters: Vec<String> = scan.matches.iter().map(|m| m.filter_id.clone()).collect();
+
+    if !scan.allowed || scan.action == FilterAction::Block {
+        return ScanVerdict::Blocked { filters };
+    }
+
+    match scan.action {
+        FilterAction::Redact => {
+            let mut redacted = text.to_string();
+            for matched in scan
+                .matches
+                .iter()
+                .filter_map(|m| m.matched_content.as_deref())
+                .filter(|matched| !matched.is_empty())
+            {
+                redacted = redacted.replace(matched, "[REDACTED]");
+            }
+            ScanVerdict::Redacted {
+                text: redacted,
+                filters,
+            }
+        }
+        FilterAction::Warn => ScanVerdict::Warned { filters },
+        _ => ScanVerdict::Passed,
+    }
+}
+
+/// Scans prompts and responses with LLM-Shield according to each service's
+/// configuration
+#[derive(Clone)]
+pub struct ContentScanner {
+    db: Arc<PgPool>,
+    shield: ShieldClient,
+    configs: Arc<RwLock<HashMap<Uuid, (Instant, ContentScanConfig)>>>,
+}
+
+impl ContentScanner {
+    pub fn new(db: PgPool, shield: ShieldClient) -> Self {
+        Self {
+            db: Arc::new(db),
+            shield,
+            configs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Scan a prompt; `None` when the service doesn't scan prompts
+    pub async fn scan_prompt(
+        &self,
+        service_id: Uuid,
+        consumer_id: Uuid,
+        prompt: &str,
+    ) -> Result<Option<ScanVerdict>> {
+        let config = self.cached_config(service_id).await?;
+        if !config.scan_prompts {
+            return Ok(None);
+        }
+
+        let content_type = ContentType::Prompt;
+        let verdict = self
+            .scan(service_id, consumer_id, content_type, prompt, &config)
+            .await;
+        Ok(Some(verdict))
+    }
+
+    /// Scan generated text, for the response pipeline's `shield_scan` stage
+    pub async fn scan_response(
+        &self,
+        service_id: Uuid,
+        consumer_id: Uuid,
+        text: &str,
+    ) -> Result<ScanVerdict> {
+        let config = self.cached_config(service_id).await?;
+        let content_type = ContentType::Response;
+        Ok(self
+            .scan(service_id, consumer_id, content_type, text, &config)
+            .await)
+    }
+
+    async fn scan(
+        &self,
+        service_id: Uuid,
+        consumer_id: Uuid,
+        content_type: ContentType,
+        text: &str,
+        config: &ContentScanConfig,
+    ) -> ScanVerdict {
+        let content = match content_type {
+            ContentType::Prompt => "prompt",
+            _ => "response",
+        };
+
+        let verdict = match self
+            .shield
+            .scan_content(text, content_type, service_id, consumer_id)
+            .await
+        {
+            Ok(scan) => verdict(text, &scan),
+            Err(e) if config.fail_closed || !self.shield.failure_policy().allows() => {
+                warn!(error = %e, service_id = %service_id, content, "Shield scan failed, failing closed");
+                ScanVerdict::Unavailable
+            }
+            Err(e) => {
+                warn!(error = %e, service_id = %service_id, content, "Shield scan failed, failing open");
+                ScanVerdict::Passed
+            }
+        };
+
+        if let ScanVerdict::Warned { filters } = &verdict {
+            warn!(
+                service_id = %service_id,
+                consumer_id = %consumer_id,
+                content,
+                filters = ?filters,
+                "Content matched Shield warning filters"
+            );
+        }
+        record::content_scan(service_id, content, verdict.as_str());
+
+        verdict
+    }
+
+    /// Scan configuration for a service (nothing scanned, failing open, when
+    /// never configured)
+    pub async fn get_config(&self, service_id: Uuid) -> Result<ContentScanConfig> {
+        let config: Option<sqlx::types::Json<ContentScanConfig>> = sqlx::query_scalar(
+            r#"
+            SELECT config
+            FROM content_scans
+            WHERE service_id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load content scan config")?;
+
+        Ok(config.map(|config| config.0).unwrap_or_default())
+    }
+
+    /// Store a service's scan configuration; takes effect on all replicas
+    /// within the cache TTL
+    pub async fn set_config(&self, service_id: Uuid, config: &ContentScanConfig) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO content_scans (service_id, config, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (service_id)
+            DO UPDATE SET config = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(service_id)
+        .bind(sqlx::types::Json(config))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store content scan config")?;
+
+        self.configs.write().unwrap().remove(&service_id);
+
+        info!(service_id = %service_id, ?config, "Content scanning updated");
+
+        Ok(())
+    }
+
+    async fn cached_config(&self, service_id: Uuid) -> Result<ContentScanConfig> {
+        if let Some((loaded_at, config)) = self.configs.read().unwrap().get(&service_id) {
+            if loaded_at.elapsed() < CONFIG_CACHE_TTL {
+                return Ok(config.clone());
+            }
+        }
+
+        let config = self.get_config(service_id).await?;
+        self.configs
+            .write()
+            .unwrap()
+            .insert(service_id, (Instant::now(), config.clone()));
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(allowed: bool, action: &str, matched: Option<&str>) -> ContentScanResponse {
+        serde_json::from_value(serde_json::json!({
+            "allowed": allowed,
+            "action": action,
+            "matches": [{
+                "filter_id": "secrets",
+                "filter_type": "custom_regex",
+                "severity": "high",
+                "matched_content": matched,
+                "message": "API key",
+            }],
+            "risk_score": 0.9,
+            "processing_time_ms": 3,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verdict_honors_filter_action() {
+        let text = "my key is sk-123, keep it safe";
+        let filters = vec!["secrets".to_string()];
+
+        assert_eq!(
+            verdict(text, &scan(true, "redact", Some("sk-123"))),
+            ScanVerdict::Redacted {
+                text: "my key is [REDACTED], keep it safe".to_string(),
+                filters: filters.clone(),
+            }
+        );
+        assert_eq!(
+            verdict(text, &scan(true, "warn", None)),
+            ScanVerdict::Warned {
+                filters: filters.clone()
+            }
+        );
+        assert_eq!(
+            verdict(text, &scan(true, "block", None)),
+            ScanVerdict::Blocked {
+                filters: filters.clone()
+            }
+        );
+        // Disallowed content is blocked whatever the action says
+        assert_eq!(
+            verdict(text, &scan(false, "log", None)),
+            ScanVerdict::Blocked { filters }
+        );
+        assert_eq!(verdict(text, &scan(true, "log", None)), ScanVerdict::Passed);
+    }
+}
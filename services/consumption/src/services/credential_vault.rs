@@ -0,0 +1,550 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::middleware::metrics::record;
+
+/// Actor recorded for decryptions made while routing requests
+const ROUTER_ACTOR: &str = "request_router";
+
+/// Credential metadata; never includes the secret
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CredentialMetadata {
+    pub service_id: Uuid,
+    pub header_name: String,
+    pub prefix: String,
+    pub key_id: String,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub rotated_at: DateTime<Utc>,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+}
+
+/// A provider credential as submitted by an operator
+#[derive(Debug, Clone, Deserialize)]
+pub struct StoreCredentialRequest {
+    pub secret: String,
+    #[serde(default = "default_header_name")]
+    pub header_name: String,
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+}
+
+fn default_header_name() -> String {
+    "Authorization".to_string()
+}
+
+fn default_prefix() -> String {
+    "Bearer ".to_string()
+}
+
+/// One entry of a service's credential audit trail
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CredentialAuditEntry {
+    pub id: i64,
+    pub service_id: Uuid,
+    pub action: String,
+    pub actor: String,
+    pub version: Option<i32>,
+    pub request_id: Option<Uuid>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Header injected into upstream requests
+#[derive(Clone)]
+pub struct AuthHeader {
+    pub name: HeaderName,
+    pub value: HeaderValue,
+}
+
+impl std::fmt::Debug for AuthHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthHeader")
+            .field("name", &self.name)
+            .field("value", &"[REDACTED]")
+            .finish()
+    }
+}
+
+/// Encrypts provider secrets with the master key, binding each ciphertext to
+/// its service id so rows can't be swapped between services
+struct MasterKey {
+    cipher: Aes256Gcm,
+    key_id: String,
+}
+
+impl MasterKey {
+    /// Parse a 32-byte key given as 64 hex characters
+    fn from_hex(hex_key: &str) -> Result<Self> {
+        let bytes = hex::decode(hex_key.trim()).context("PROVIDER_CREDENTIAL_KEY is not hex")?;
+        if bytes.len() != 32 {
+            bail!("PROVIDER_CREDENTIAL_KEY must be 32 bytes (64 hex characters)");
+        }
+
+        Ok(Self {
+            cipher: Aes256Gcm::new_from_slice(&bytes).context("Invalid credential key")?,
+            key_id: hex::encode(&Sha256::digest(&bytes)[..4]),
+        })
+    }
+
+    fn encrypt(&self, service_id: Uuid, secret: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: secret.as_bytes(),
+                    aad: service_id.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt provider credential"))?;
+
+        Ok((ciphertext, nonce.to_vec()))
+    }
+
+    fn decrypt(&self, service_id: Uuid, ciphertext: &[u8], nonce: &[u8]) -> Result<String> {
+        if nonce.len() != 12 {
+            bail!("Stored credential nonce is malformed");
+        }
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: service_id.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to decrypt provider credential"))?;
+
+        String::from_utf8(plaintext).context("Provider credential is not valid UTF-8")
+    }
+}
+
+/// Build the injected header, rejecting names and values reqwest would refuse
+fn auth_header(header_name: &str, prefix: &str, secret: &str) -> Result<AuthHeader> {
+    let name = HeaderName::from_bytes(header_name.as_bytes())
+        .with_context(|| format!("Invalid header name '{}'", header_name))?;
+    let mut value = HeaderValue::from_str(&format!("{}{}", prefix, secret)).map_err(|_| {
+        anyhow!("Invalid header value: secret or prefix contains invalid characters")
+    })?;
+    value.set_sensitive(true);
+
+    Ok(AuthHeader { name, value })
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredCredential {
+    header_name: String,
+    prefix: String,
+    ciphertext: Vec<u8>,
+    nonce: Vec<u8>,
+    key_id: String,
+    version: i32,
+}
+
+/// Encrypted storage of upstream provider credentials, one per service.
+///
+/// Secrets are encrypted with AES-256-GCM under `PROVIDER_CREDENTIAL_KEY`
+/// and only decrypted to build the auth header for upstream requests. Every
+/// change and every decryption is written to `provider_credential_audit`;
+/// a decryption that can't be audited fails. Decrypted headers are cached
+/// for `PROVIDER_CREDENTIAL_CACHE_SECS` (default 60, 0 disables caching so
+/// each request decrypts and audits), so a rotation reaches other replicas
+/// within that window.
+#[derive(Clone)]
+pub struct CredentialVault {
+    db: Arc<PgPool>,
+    key: Option<Arc<MasterKey>>,
+    cache_ttl: Duration,
+    cache: Arc<RwLock<HashMap<Uuid, (Instant, Option<AuthHeader>)>>>,
+}
+
+impl CredentialVault {
+    pub fn new(db: PgPool) -> Result<Self> {
+        let key = std::env::var("PROVIDER_CREDENTIAL_KEY")
+            .ok()
+            .filter(|key| !key.is_empty())
+            .map(|key| MasterKey::from_hex(&key).map(Arc::new))
+            .transpose()?;
+        if key.is_none() {
+            warn!("PROVIDER_CREDENTIAL_KEY not set; provider credentials are disabled");
+        }
+
+        let cache_ttl = std::env::var("PROVIDER_CREDENTIAL_CACHE_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(60));
+
+        Ok(Self {
+            db: Arc::new(db),
+            key,
+            cache_ttl,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    fn key(&self) -> Result<&MasterKey> {
+        self.key
+            .as_deref()
+            .context("Provider credential key not configured")
+    }
+
+    /// Credential metadata for a service, if one is stored
+    pub async fn metadata(&self, service_id: Uuid) -> Result<Option<CredentialMetadata>> {
+        sqlx::query_as(
+            r#"
+            SELECT service_id, header_name, prefix, key_id, version,
+                   created_at, rotated_at, last_accessed_at
+            FROM provider_credentials
+            WHERE service_id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load provider credential")
+    }
+
+    /// Store a service's credential, replacing any existing one
+    pub async fn store(
+        &self,
+        service_id: Uuid,
+        request: &StoreCredentialRequest,
+        actor: &str,
+    ) -> Result<CredentialMetadata> {
+        let key = self.key()?;
+        if request.secret.is_empty() {
+            bail!("Invalid credential: secret is empty");
+        }
+        auth_header(&request.header_name, &request.prefix, &request.secret)?;
+        let (ciphertext, nonce) = key.encrypt(service_id, &request.secret)?;
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        let metadata: CredentialMetadata = sqlx::query_as(
+            r#"
+            INSERT INTO provider_credentials
+                (service_id, header_name, prefix, ciphertext, nonce, key_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (service_id) DO UPDATE
+            SET header_name = $2, prefix = $3, ciphertext = $4, nonce = $5, key_id = $6,
+                version = provider_credentials.version + 1, rotated_at = NOW()
+            RETURNING service_id, header_name, prefix, key_id, version,
+                      created_at, rotated_at, last_accessed_at
+            "#,
+        )
+        .bind(service_id)
+        .bind(&request.header_name)
+        .bind(&request.prefix)
+        .bind(&ciphertext)
+        .bind(&nonce)
+        .bind(&key.key_id)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to store provider credential")?;
+
+        let action = if metadata.version > 1 {
+            "rotated"
+        } else {
+            "stored"
+        };
+        self.commit_change(tx, &metadata, action, actor).await?;
+
+        Ok(metadata)
+    }
+
+    /// Replace the secret of an existing credential, keeping its header name
+    /// and prefix; `None` when the service has no credential
+    pub async fn rotate(
+        &self,
+        service_id: Uuid,
+        secret: &str,
+        actor: &str,
+    ) -> Result<Option<CredentialMetadata>> {
+        let key = self.key()?;
+        if secret.is_empty() {
+            bail!("Invalid credential: secret is empty");
+        }
+        auth_header("authorization", "", secret)?;
+        let (ciphertext, nonce) = key.encrypt(service_id, secret)?;
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        let metadata: Option<CredentialMetadata> = sqlx::query_as(
+            r#"
+            UPDATE provider_credentials
+            SET ciphertext = $2, nonce = $3, key_id = $4, version = version + 1, rotated_at = NOW()
+            WHERE service_id = $1
+            RETURNING service_id, header_name, prefix, key_id, version,
+                      created_at, rotated_at, last_accessed_at
+            "#,
+        )
+        .bind(service_id)
+        .bind(&ciphertext)
+        .bind(&nonce)
+        .bind(&key.key_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to rotate provider credential")?;
+
+        let Some(metadata) = metadata else {
+            return Ok(None);
+        };
+        self.commit_change(tx, &metadata, "rotated", actor).await?;
+
+        Ok(Some(metadata))
+    }
+
+    /// Audit a credential change in its transaction, then commit and drop
+    /// the cached header
+    async fn commit_change(
+        &self,
+        mut tx: sqlx::Transaction<'_, sqlx::Postgres>,
+        metadata: &CredentialMetadata,
+        action: &str,
+        actor: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO provider_credential_audit (service_id, action, actor, version)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(metadata.service_id)
+        .bind(action)
+        .bind(actor)
+        .bind(metadata.version)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to audit provider credential change")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit provider credential")?;
+        self.cache.write().unwrap().remove(&metadata.service_id);
+        record::provider_credential_access(action);
+
+        info!(
+            service_id = %metadata.service_id,
+            version = metadata.version,
+            actor = actor,
+            "Provider credential {}",
+            action
+        );
+
+        Ok(())
+    }
+
+    /// Delete a service's credential; returns false when there was none
+    pub async fn delete(&self, service_id: Uuid, actor: &str) -> Result<bool> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        let version: Option<i32> = sqlx::query_scalar(
+            "DELETE FROM provider_credentials WHERE service_id = $1 RETURNING version",
+        )
+        .bind(service_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to delete provider credential")?;
+
+        if version.is_some() {
+            sqlx::query(
+                r#"
+                INSERT INTO provider_credential_audit (service_id, action, actor, version)
+                VALUES ($1, 'deleted', $2, $3)
+                "#,
+            )
+            .bind(service_id)
+            .bind(actor)
+            .bind(version)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to audit provider credential deletion")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit provider credential")?;
+        self.cache.write().unwrap().remove(&service_id);
+
+        if version.is_some() {
+            record::provider_credential_access("deleted");
+            info!(service_id = %service_id, actor = actor, "Provider credential deleted");
+        }
+
+        Ok(version.is_some())
+    }
+
+    /// Audit trail for a service, newest first
+    pub async fn audit_log(
+        &self,
+        service_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<CredentialAuditEntry>> {
+        sqlx::query_as(
+            r#"
+            SELECT id, service_id, action, actor, version, request_id, occurred_at
+            FROM provider_credential_audit
+            WHERE service_id = $1
+            ORDER BY occurred_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(service_id)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load provider credential audit log")
+    }
+
+    /// Header to add to an upstream request for the service, if it has a
+    /// credential
+    pub async fn auth_header(
+        &self,
+        service_id: Uuid,
+        request_id: Uuid,
+    ) -> Result<Option<AuthHeader>> {
+        if let Some((loaded_at, header)) = self.cache.read().unwrap().get(&service_id) {
+            if loaded_at.elapsed() < self.cache_ttl {
+                return Ok(header.clone());
+            }
+        }
+
+        let stored: Option<StoredCredential> = sqlx::query_as(
+            r#"
+            SELECT header_name, prefix, ciphertext, nonce, key_id, version
+            FROM provider_credentials
+            WHERE service_id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load provider credential")?;
+
+        let header = match stored {
+            Some(stored) => Some(self.decrypt(service_id, request_id, stored).await?),
+            None => None,
+        };
+
+        if !self.cache_ttl.is_zero() {
+            self.cache
+                .write()
+                .unwrap()
+                .insert(service_id, (Instant::now(), header.clone()));
+        }
+
+        Ok(header)
+    }
+
+    /// Decrypt a stored credential, auditing the attempt before the secret
+    /// is released
+    async fn decrypt(
+        &self,
+        service_id: Uuid,
+        request_id: Uuid,
+        stored: StoredCredential,
+    ) -> Result<AuthHeader> {
+        let decrypted = self.key().and_then(|key| {
+            if stored.key_id != key.key_id {
+                bail!(
+                    "Provider credential was encrypted with key {}, current key is {}",
+                    stored.key_id,
+                    key.key_id
+                );
+            }
+            let secret = key.decrypt(service_id, &stored.ciphertext, &stored.nonce)?;
+            auth_header(&stored.header_name, &stored.prefix, &secret)
+        });
+        let action = if decrypted.is_ok() {
+            "decrypted"
+        } else {
+            "decrypt_failed"
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO provider_credential_audit (service_id, action, actor, version, request_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(service_id)
+        .bind(action)
+        .bind(ROUTER_ACTOR)
+        .bind(stored.version)
+        .bind(request_id)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to audit provider credential access")?;
+
+        if decrypted.is_ok() {
+            sqlx::query(
+                "UPDATE provider_credentials SET last_accessed_at = NOW() WHERE service_id = $1",
+            )
+            .bind(service_id)
+            .execute(self.db.as_ref())
+            .await
+            .context("Failed to update provider credential access time")?;
+        }
+        record::provider_credential_access(action);
+
+        decrypted
+            .with_context(|| format!("Provider credential for service {} unusable", service_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ciphertext_is_bound_to_service() {
+        let key = MasterKey::from_hex(&"11".repeat(32)).unwrap();
+        let service_id = Uuid::new_v4();
+
+        let (ciphertext, nonce) = key.encrypt(service_id, "sk-provider-secret").unwrap();
+        assert!(!ciphertext.windows(6).any(|w| w == b"sk-pro"));
+        assert_eq!(
+            key.decrypt(service_id, &ciphertext, &nonce).unwrap(),
+            "sk-provider-secret"
+        );
+        assert!(key.decrypt(Uuid::new_v4(), &ciphertext, &nonce).is_err());
+
+        let other = MasterKey::from_hex(&"22".repeat(32)).unwrap();
+        assert_ne!(key.key_id, other.key_id);
+        assert!(other.decrypt(service_id, &ciphertext, &nonce).is_err());
+        assert!(MasterKey::from_hex("abcd").is_err());
+
+        let header = auth_header("Authorization", "Bearer ", "sk-provider-secret").unwrap();
+        assert_eq!(header.value.to_str().unwrap(), "Bearer sk-provider-secret");
+        assert!(header.value.is_sensitive());
+        assert!(!format!("{:?}", header).contains("sk-provider"));
+        assert!(auth_header("Bad Header", "", "secret").is_err());
+        assert!(auth_header("x-api-key", "", "line\nbreak").is_err());
+    }
+}
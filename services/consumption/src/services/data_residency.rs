@@ -0,0 +1,354 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Schema holding data of organizations without a residency pin
+const DEFAULT_SCHEMA: &str = "public";
+
+/// Region to schema mapping used when `DATA_RESIDENCY_REGIONS` is unset
+const DEFAULT_REGIONS: &str = "eu=residency_eu,us=residency_us";
+
+/// How long a consumer's storage location is cached
+const LOCATION_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Tables whose rows follow an organization's residency pin, with the
+/// column identifying the consumer
+const PINNED_TABLES: &[(&str, &str)] =
+    &[("usage_records", "consumer_id"), ("audit_logs", "actor_id")];
+
+/// Where a consumer's usage records and audit logs are stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StorageLocation {
+    /// Pinned region, `None` for the default location
+    pub region: Option<String>,
+    pub schema: String,
+}
+
+impl StorageLocation {
+    fn default_location() -> Self {
+        Self {
+            region: None,
+            schema: DEFAULT_SCHEMA.to_string(),
+        }
+    }
+
+    /// Schema-qualified table name in this location
+    pub fn table(&self, name: &str) -> String {
+        format!("{}.{}", self.schema, name)
+    }
+}
+
+/// Residency pin for an organization
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ResidencyPin {
+    pub organization_id: Uuid,
+    pub region: String,
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// Routes storage of usage and audit data to an organization's pinned region
+#[derive(Clone)]
+pub struct DataResidency {
+    db: Arc<PgPool>,
+    regions: Arc<HashMap<String, String>>,
+    locations: Arc<RwLock<HashMap<Uuid, (Instant, StorageLocation)>>>,
+}
+
+/// Parse `region=schema` pairs, ignoring entries with unsafe schema names
+fn parse_regions(spec: &str) -> HashMap<String, String> {
+    spec.split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(region, schema)| (region.trim().to_lowercase(), schema.trim().to_string()))
+        .filter(|(region, schema)| {
+            let valid = !region.is_empty()
+                && !schema.is_empty()
+                && schema
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                warn!(region = %region, schema = %schema, "Ignoring invalid residency region");
+            }
+            valid
+        })
+        .collect()
+}
+
+impl DataResidency {
+    pub fn new(db: PgPool) -> Self {
+        let spec =
+            std::env::var("DATA_RESIDENCY_REGIONS").unwrap_or_else(|_| DEFAULT_REGIONS.to_string());
+
+        Self {
+            db: Arc::new(db),
+            regions: Arc::new(parse_regions(&spec)),
+            locations: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Configured regions
+    pub fn regions(&self) -> Vec<String> {
+        let mut regions: Vec<String> = self.regions.keys().cloned().collect();
+        regions.sort();
+        regions
+    }
+
+    /// Every schema usage records and audit logs are stored in, starting
+    /// with the default one
+    pub fn schemas(&self) -> Vec<String> {
+        let mut schemas: Vec<String> = self
+            .regions
+            .values()
+            .filter(|schema| schema.as_str() != DEFAULT_SCHEMA)
+            .cloned()
+            .collect();
+        schemas.sort();
+        schemas.dedup();
+        schemas.insert(0, DEFAULT_SCHEMA.to_string());
+        schemas
+    }
+
+    fn location_for_region(&self, region: &str) -> Result<StorageLocation> {
+        let schema = self
+            .regions
+            .get(region)
+            .with_context(|| format!("Residency region {} is not configured", region))?;
+
+        Ok(StorageLocation {
+            region: Some(region.to_string()),
+            schema: schema.clone(),
+        })
+    }
+
+    /// Storage location for a consumer's data.
+    ///
+    /// Fails rather than falling back to the default location when the
+    /// consumer's organization is pinned to a region this instance doesn't serve.
+    pub async fn location_for(&self, consumer_id: Uuid) -> Result<StorageLocation> {
+        if let Some((loaded_at, location)) = self.locations.read().unwrap().get(&consumer_id) {
+            if loaded_at.elapsed() < LOCATION_CACHE_TTL {
+                return Ok(location.clone());
+            }
+        }
+
+        let region: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT p.region
+            FROM data_residency_pins p
+            JOIN api_keys k ON k.metadata->>'organization_id' = p.organization_id::text
+            WHERE k.consumer_id = $1
+            LIMIT 1
+            "#,
+        )
+        .bind(consumer_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to look up residency pin")?;
+
+        let location = match region {
+            Some(region) => self.location_for_region(&region)?,
+            None => StorageLocation::default_location(),
+        };
+
+        self.locations
+            .write()
+            .unwrap()
+            .insert(consumer_id, (Instant::now(), location.clone()));
+
+        Ok(location)
+    }
+
+    pub async fn get_pin(&self, organization_id: Uuid) -> Result<Option<ResidencyPin>> {
+        let pin = sqlx::query_as::<_, ResidencyPin>(
+            "SELECT organization_id, region, pinned_at FROM data_residency_pins WHERE organization_id = $1",
+        )
+        .bind(organization_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to get residency pin")?;
+
+        Ok(pin)
+    }
+
+    /// Pin an organization to a region and move its existing data there
+    pub async fn pin_organization(
+        &self,
+        organization_id: Uuid,
+        region: &str,
+    ) -> Result<ResidencyPin> {
+        let region = region.to_lowercase();
+        let target = self.location_for_region(&region)?;
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin residency transaction")?;
+
+        let pin = sqlx::query_as::<_, ResidencyPin>(
+            r#"
+            INSERT INTO data_residency_pins (organization_id, region, pinned_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (organization_id) DO UPDATE SET
+                region = EXCLUDED.region,
+                pinned_at = NOW()
+            RETURNING organization_id, region, pinned_at
+            "#,
+        )
+        .bind(organization_id)
+        .bind(&region)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to store residency pin")?;
+
+        let members = self.members(&mut tx, organization_id).await?;
+        let moved = self.move_into(&mut tx, &members, &target).await?;
+
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO {} (event_type, actor_id, actor_type, resource_id, resource_type, action, details)
+            VALUES ('data_residency', $1, 'admin', $1, 'organization', 'pin', $2)
+            "#,
+            target.table("audit_logs")
+        ))
+        .bind(organization_id)
+        .bind(serde_json::json!({ "region": region, "rows_moved": moved }))
+        .execute(&mut *tx)
+        .await
+        .context("Failed to audit residency pin")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit residency pin")?;
+
+        {
+            let mut locations = self.locations.write().unwrap();
+            for member in &members {
+                locations.remove(member);
+            }
+        }
+
+        info!(
+            organization_id = %organization_id,
+            region = %region,
+            members = members.len(),
+            rows_moved = moved,
+            "Organization pinned to region"
+        );
+
+        Ok(pin)
+    }
+
+    /// Background job: move rows of pinned organizations that were written
+    /// outside their region (e.g. by a replica with a stale location cache)
+    pub async fn enforce_residency(&self) -> Result<u64> {
+        let pins = sqlx::query_as::<_, ResidencyPin>(
+            "SELECT organization_id, region, pinned_at FROM data_residency_pins",
+        )
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list residency pins")?;
+
+        let mut moved = 0;
+        for pin in pins {
+            let target = match self.location_for_region(&pin.region) {
+                Ok(target) => target,
+                Err(e) => {
+                    warn!(organization_id = %pin.organization_id, error = %e, "Skipping residency pin");
+                    continue;
+                }
+            };
+
+            let mut tx = self
+                .db
+                .begin()
+                .await
+                .context("Failed to begin residency transaction")?;
+            let members = self.members(&mut tx, pin.organization_id).await?;
+            moved += self.move_into(&mut tx, &members, &target).await?;
+            tx.commit()
+                .await
+                .context("Failed to commit residency enforcement")?;
+        }
+
+        if moved > 0 {
+            warn!(
+                rows_moved = moved,
+                "Moved misplaced rows into their residency region"
+            );
+        }
+
+        Ok(moved)
+    }
+
+    async fn members(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        organization_id: Uuid,
+    ) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar(
+            "SELECT DISTINCT consumer_id FROM api_keys WHERE metadata->>'organization_id' = $1::text",
+        )
+        .bind(organization_id)
+        .fetch_all(&mut **tx)
+        .await
+        .context("Failed to list organization members")
+    }
+
+    /// Move members' rows from every other location into `target`
+    async fn move_into(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        members: &[Uuid],
+        target: &StorageLocation,
+    ) -> Result<u64> {
+        if members.is_empty() {
+            return Ok(0);
+        }
+
+        let sources = std::iter::once(DEFAULT_SCHEMA)
+            .chain(self.regions.values().map(String::as_str))
+            .filter(|schema| *schema != target.schema);
+
+        let mut moved = 0;
+        for source in sources {
+            for (table, column) in PINNED_TABLES {
+                let result = sqlx::query(&format!(
+                    r#"
+                    WITH moved AS (
+                        DELETE FROM {source}.{table} WHERE {column} = ANY($1) RETURNING *
+                    )
+                    INSERT INTO {target} SELECT * FROM moved
+                    "#,
+                    target = target.table(table),
+                ))
+                .bind(members)
+                .execute(&mut **tx)
+                .await
+                .with_context(|| format!("Failed to move {} from {}", table, source))?;
+
+                moved += result.rows_affected();
+            }
+        }
+
+        Ok(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_regions_rejects_unsafe_schemas() {
+        let regions = parse_regions("EU=residency_eu, us=residency_us,bad=x;drop table,empty=");
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions.get("eu").map(String::as_str), Some("residency_eu"));
+        assert_eq!(regions.get("us").map(String::as_str), Some("residency_us"));
+    }
+}
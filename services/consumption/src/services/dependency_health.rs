@@ -0,0 +1,185 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::{PolicyEngineClient, ShieldClient};
+
+/// Longest a dependency may take to answer before it counts as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeStatus {
+    Up,
+    /// A non-critical dependency is down; requests are still served
+    Degraded,
+    /// A critical dependency is down
+    Down,
+}
+
+/// Result of checking one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCheck {
+    pub name: &'static str,
+    /// The service can't serve requests while a critical dependency is down
+    pub critical: bool,
+    pub up: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Liveness or readiness probe response
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: ProbeStatus,
+    pub checks: Vec<DependencyCheck>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<DependencyCheck>) -> Self {
+        let down = |critical: bool| {
+            checks
+                .iter()
+                .any(|check| check.critical == critical && !check.up)
+        };
+        let status = if down(true) {
+            ProbeStatus::Down
+        } else if down(false) {
+            ProbeStatus::Degraded
+        } else {
+            ProbeStatus::Up
+        };
+
+        Self {
+            status,
+            checks,
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+/// Checks the dependencies of the service for the Kubernetes readiness
+/// probe. Postgres and Redis are critical. LLM-Policy-Engine and LLM-Shield
+/// only degrade the service whatever their failure policy: that policy
+/// decides in the request path, so requests not needing them keep being
+/// served.
+#[derive(Clone)]
+pub struct DependencyHealth {
+    db: PgPool,
+    /// Connected afresh by each check, so the probe needs no live Redis to
+    /// be built and reports the connection state as it is now
+    redis: redis::Client,
+    policy_engine: PolicyEngineClient,
+    shield: ShieldClient,
+}
+
+impl DependencyHealth {
+    pub fn new(
+        db: PgPool,
+        redis: redis::Client,
+        policy_engine: PolicyEngineClient,
+        shield: ShieldClient,
+    ) -> Self {
+        Self {
+            db,
+            redis,
+            policy_engine,
+            shield,
+        }
+    }
+
+    /// Check every dependency concurrently
+    pub async fn readiness(&self) -> HealthReport {
+        let (postgres, redis, policy_engine, shield) = tokio::join!(
+            check("postgres", true, async {
+                sqlx::query("SELECT 1").execute(&self.db).await?;
+                Ok(())
+            }),
+            check("redis", true, async {
+                let mut conn = self.redis.get_multiplexed_tokio_connection().await?;
+                let _: String = redis::cmd("PING").query_async(&mut conn).await?;
+                Ok(())
+            }),
+            check("policy_engine", false, self.policy_engine.ping()),
+            check("shield", false, self.shield.ping()),
+        );
+
+        HealthReport::new(vec![postgres, redis, policy_engine, shield])
+    }
+}
+
+async fn check(
+    name: &'static str,
+    critical: bool,
+    probe: impl Future<Output = Result<()>>,
+) -> DependencyCheck {
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, probe)
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("No answer within {:?}", CHECK_TIMEOUT)));
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    if let Err(e) = &result {
+        warn!(dependency = name, critical, error = %e, "Dependency check failed");
+    }
+
+    DependencyCheck {
+        name,
+        critical,
+        up: result.is_ok(),
+        latency_ms,
+        error: result.err().map(|e| format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &'static str, critical: bool, up: bool) -> DependencyCheck {
+        DependencyCheck {
+            name,
+            critical,
+            up,
+            latency_ms: 1,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_report_status() {
+        assert_eq!(HealthReport::new(vec![]).status, ProbeStatus::Up);
+        assert_eq!(
+            HealthReport::new(vec![
+                dependency("postgres", true, true),
+                dependency("shield", false, false),
+            ])
+            .status,
+            ProbeStatus::Degraded
+        );
+        assert_eq!(
+            HealthReport::new(vec![
+                dependency("redis", true, false),
+                dependency("shield", false, false),
+            ])
+            .status,
+            ProbeStatus::Down
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_errors() {
+        let failed = check("shield", false, async { Err(anyhow!("refused")) }).await;
+        assert!(!failed.up);
+        assert_eq!(failed.error.as_deref(), Some("refused"));
+
+        let up = check("postgres", true, async { Ok(()) }).await;
+        assert!(up.up && up.error.is_none());
+    }
+}
@@ -0,0 +1,392 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::service_health::{HealthState, HealthStatus, HealthThresholds};
+use crate::models::Service;
+
+/// How long a service's endpoints are cached before they're read again
+const POOL_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Most endpoints a service may register
+pub const MAX_ENDPOINTS: usize = 32;
+
+/// Weight of the most recent latency in an endpoint's moving average
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// How a service's endpoints share its traffic
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalancingStrategy {
+    /// Each endpoint gets a share of requests proportional to its weight
+    #[default]
+    WeightedRoundRobin,
+    /// Requests go to the endpoint with the lowest recent latency
+    LeastLatency,
+}
+
+/// One replica of a service
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WeightedEndpoint {
+    pub url: String,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Endpoints a service's traffic is balanced across; without any, requests
+/// go to `services.endpoint`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EndpointPool {
+    #[serde(default)]
+    pub strategy: BalancingStrategy,
+    #[serde(default)]
+    pub endpoints: Vec<WeightedEndpoint>,
+}
+
+impl EndpointPool {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.endpoints.len() > MAX_ENDPOINTS {
+            return Err(format!("At most {} endpoints are allowed", MAX_ENDPOINTS));
+        }
+        let mut urls = HashSet::new();
+        for endpoint in &self.endpoints {
+            if !(endpoint.url.starts_with("http://") || endpoint.url.starts_with("https://")) {
+                return Err(format!("Endpoint {} is not an HTTP(S) URL", endpoint.url));
+            }
+            if !(1..=1000).contains(&endpoint.weight) {
+                return Err(format!(
+                    "Weight of {} must be between 1 and 1000",
+                    endpoint.url
+                ));
+            }
+            if !urls.insert(endpoint.url.as_str()) {
+                return Err(format!("Endpoint {} is listed twice", endpoint.url));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A replica's view of one endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointStatus {
+    pub url: String,
+    pub weight: u32,
+    pub status: HealthStatus,
+    pub consecutive_failures: u32,
+    /// Moving average of successful requests' latency
+    pub latency_ms: Option<f64>,
+}
+
+#[derive(Default)]
+struct EndpointState {
+    health: HealthState,
+    /// Smooth weighted round-robin counter
+    current_weight: i64,
+    latency_ms: Option<f64>,
+}
+
+struct CachedPool {
+    loaded_at: Instant,
+    /// `services.endpoint`, to tell requests a routing rule redirected
+    registered: String,
+    pool: EndpointPool,
+}
+
+/// Spreads requests over a service's endpoints and fails over between them.
+///
+/// Every endpoint has its own circuit, using the service health thresholds:
+/// an endpoint that keeps failing stops getting requests until trial
+/// requests go through again. Circuits and latencies are tracked per
+/// replica, in memory.
+#[derive(Clone)]
+pub struct EndpointBalancer {
+    db: Arc<PgPool>,
+    thresholds: HealthThresholds,
+    pools: Arc<RwLock<HashMap<Uuid, CachedPool>>>,
+    endpoints: Arc<Mutex<HashMap<(Uuid, String), EndpointState>>>,
+}
+
+impl EndpointBalancer {
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db: Arc::new(db),
+            thresholds: HealthThresholds::from_env(),
+            pools: Arc::new(RwLock::new(HashMap::new())),
+            endpoints: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// A service's endpoints and registered endpoint, cached for a short TTL
+    async fn load(&self, service_id: Uuid) -> Result<(String, EndpointPool)> {
+        if let Some(cached) = self.pools.read().unwrap().get(&service_id) {
+            if cached.loaded_at.elapsed() < POOL_CACHE_TTL {
+                return Ok((cached.registered.clone(), cached.pool.clone()));
+            }
+        }
+
+        let row: Option<(String, sqlx::types::Json<EndpointPool>)> =
+            sqlx::query_as("SELECT endpoint, endpoints FROM services WHERE id = $1")
+                .bind(service_id)
+                .fetch_optional(self.db.as_ref())
+                .await
+                .context("Failed to load service endpoints")?;
+
+        let (registered, pool) = row
+            .map(|(registered, pool)| (registered, pool.0))
+            .unwrap_or_default();
+        self.pools.write().unwrap().insert(
+            service_id,
+            CachedPool {
+                loaded_at: Instant::now(),
+                registered: registered.clone(),
+                pool: pool.clone(),
+            },
+        );
+
+        Ok((registered, pool))
+    }
+
+    /// Endpoints to try for a request, in order: the one the strategy picks,
+    /// then the other healthy ones to fail over to. Empty when every
+    /// endpoint's circuit is open. A service without endpoints, or one whose
+    /// endpoint a routing rule replaced, only gets `service.endpoint`.
+    pub async fn candidates(&self, service: &Service) -> Result<Vec<String>> {
+        let (registered, pool) = self.load(service.id).await?;
+        if pool.endpoints.is_empty() || service.endpoint != registered {
+            return Ok(vec![service.endpoint.clone()]);
+        }
+        Ok(self.order(service.id, &pool, Utc::now()))
+    }
+
+    fn order(&self, service_id: Uuid, pool: &EndpointPool, now: DateTime<Utc>) -> Vec<String> {
+        let mut states = self.endpoints.lock().unwrap();
+        let mut available: Vec<(&WeightedEndpoint, Option<f64>)> = Vec::new();
+        for endpoint in &pool.endpoints {
+            let state = states
+                .entry((service_id, endpoint.url.clone()))
+                .or_default();
+            if state.health.admits(&self.thresholds, now) {
+                available.push((endpoint, state.latency_ms));
+            }
+        }
+        if available.is_empty() {
+            return Vec::new();
+        }
+
+        match pool.strategy {
+            BalancingStrategy::WeightedRoundRobin => {
+                // Smooth weighted round-robin: every endpoint gains its weight,
+                // the one with the most is picked and pays back the total
+                let total: i64 = available.iter().map(|(e, _)| i64::from(e.weight)).sum();
+                let mut picked = 0;
+                let mut best = i64::MIN;
+                for (i, (endpoint, _)) in available.iter().enumerate() {
+                    let state = states
+                        .get_mut(&(service_id, endpoint.url.clone()))
+                        .expect("state inserted above");
+                    state.current_weight += i64::from(endpoint.weight);
+                    if state.current_weight > best {
+                        best = state.current_weight;
+                        picked = i;
+                    }
+                }
+                if let Some(state) = states.get_mut(&(service_id, available[picked].0.url.clone()))
+                {
+                    state.current_weight -= total;
+                }
+
+                let first = available.remove(picked);
+                available.sort_by(|a, b| b.0.weight.cmp(&a.0.weight));
+                available.insert(0, first);
+            }
+            BalancingStrategy::LeastLatency => {
+                // Endpoints without a latency yet go first, so they get one
+                available.sort_by(|a, b| {
+                    a.1.unwrap_or(0.0)
+                        .total_cmp(&b.1.unwrap_or(0.0))
+                        .then(b.0.weight.cmp(&a.0.weight))
+                });
+            }
+        }
+
+        available
+            .into_iter()
+            .map(|(endpoint, _)| endpoint.url.clone())
+            .collect()
+    }
+
+    /// Count the outcome of a request to one of a service's endpoints
+    pub fn record(&self, service_id: Uuid, url: &str, outcome: Result<u64, &str>) {
+        let mut states = self.endpoints.lock().unwrap();
+        let state = states.entry((service_id, url.to_string())).or_default();
+
+        if let Ok(latency_ms) = outcome {
+            let latency_ms = latency_ms as f64;
+            state.latency_ms = Some(state.latency_ms.map_or(latency_ms, |average| {
+                average + LATENCY_SMOOTHING * (latency_ms - average)
+            }));
+        }
+
+        if let Some(status) = state
+            .health
+            .record(outcome.is_ok(), &self.thresholds, Utc::now())
+        {
+            match outcome {
+                Ok(_) => info!(
+                    service_id = %service_id,
+                    endpoint = %url,
+                    status = status.as_str(),
+                    "Service endpoint recovering"
+                ),
+                Err(error) => warn!(
+                    service_id = %service_id,
+                    endpoint = %url,
+                    status = status.as_str(),
+                    error = %error,
+                    "Service endpoint failing"
+                ),
+            }
+        }
+    }
+
+    /// A service's endpoints with this replica's view of their health
+    pub async fn statuses(&self, service_id: Uuid) -> Result<(EndpointPool, Vec<EndpointStatus>)> {
+        let (_, pool) = self.load(service_id).await?;
+        let states = self.endpoints.lock().unwrap();
+        let statuses = pool
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let state = states.get(&(service_id, endpoint.url.clone()));
+                EndpointStatus {
+                    url: endpoint.url.clone(),
+                    weight: endpoint.weight,
+                    status: state.map_or(HealthStatus::Active, |s| s.health.status),
+                    consecutive_failures: state.map_or(0, |s| s.health.consecutive_failures),
+                    latency_ms: state.and_then(|s| s.latency_ms),
+                }
+            })
+            .collect();
+        Ok((pool, statuses))
+    }
+
+    /// Replace a service's endpoints; other replicas pick them up within the
+    /// cache TTL
+    pub async fn set_pool(&self, service_id: Uuid, pool: EndpointPool) -> Result<()> {
+        pool.validate().map_err(anyhow::Error::msg)?;
+
+        let result = sqlx::query("UPDATE services SET endpoints = $2 WHERE id = $1")
+            .bind(service_id)
+            .bind(sqlx::types::Json(&pool))
+            .execute(self.db.as_ref())
+            .await
+            .context("Failed to update service endpoints")?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Service {} not found", service_id);
+        }
+
+        self.pools.write().unwrap().remove(&service_id);
+
+        info!(
+            service_id = %service_id,
+            endpoints = pool.endpoints.len(),
+            strategy = ?pool.strategy,
+            "Service endpoints updated"
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(strategy: BalancingStrategy, weights: &[u32]) -> EndpointPool {
+        EndpointPool {
+            strategy,
+            endpoints: weights
+                .iter()
+                .enumerate()
+                .map(|(i, weight)| WeightedEndpoint {
+                    url: format!("http://replica-{}", i),
+                    weight: *weight,
+                })
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weighted_round_robin_and_failover() {
+        let balancer = EndpointBalancer::new(PgPool::connect_lazy("postgres://localhost").unwrap());
+        let service_id = Uuid::new_v4();
+        let pool = pool(BalancingStrategy::WeightedRoundRobin, &[3, 1]);
+
+        let picks: Vec<String> = (0..8)
+            .map(|_| balancer.order(service_id, &pool, Utc::now())[0].clone())
+            .collect();
+        assert_eq!(
+            picks
+                .iter()
+                .filter(|url| *url == "http://replica-0")
+                .count(),
+            6
+        );
+        // Every order lists the other endpoint to fail over to
+        assert_eq!(balancer.order(service_id, &pool, Utc::now()).len(), 2);
+
+        // An endpoint whose circuit opened gets no more requests
+        for _ in 0..balancer.thresholds.unavailable_after {
+            balancer.record(service_id, "http://replica-0", Err("connection refused"));
+        }
+        assert_eq!(
+            balancer.order(service_id, &pool, Utc::now()),
+            vec!["http://replica-1".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_least_latency() {
+        let balancer = EndpointBalancer::new(PgPool::connect_lazy("postgres://localhost").unwrap());
+        let service_id = Uuid::new_v4();
+        let pool = pool(BalancingStrategy::LeastLatency, &[1, 1, 1]);
+
+        balancer.record(service_id, "http://replica-0", Ok(300));
+        balancer.record(service_id, "http://replica-1", Ok(40));
+        balancer.record(service_id, "http://replica-2", Ok(120));
+        assert_eq!(
+            balancer.order(service_id, &pool, Utc::now()),
+            vec![
+                "http://replica-1".to_string(),
+                "http://replica-2".to_string(),
+                "http://replica-0".to_string()
+            ]
+        );
+
+        assert!(pool.validate().is_ok());
+        assert!(EndpointPool {
+            strategy: BalancingStrategy::LeastLatency,
+            endpoints: vec![
+                WeightedEndpoint {
+                    url: "http://replica-0".to_string(),
+                    weight: 1
+                };
+                2
+            ],
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,573 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Longest delay between retries of a failed job
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// How long an idle worker waits before polling for work again
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Job queue settings, read from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobQueueConfig {
+    /// Jobs run concurrently per replica
+    pub concurrency: usize,
+    /// How long a claimed job is hidden from other workers without a
+    /// heartbeat; after that it is picked up again (e.g. after a crash)
+    pub visibility_timeout: Duration,
+    /// Attempts before a job is moved to the dead-letter table
+    pub max_attempts: i32,
+    /// Delay before the first retry; doubles with each attempt
+    pub retry_base_delay: Duration,
+    /// Finished jobs are deleted after this long
+    pub retention: Duration,
+}
+
+impl Default for JobQueueConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            visibility_timeout: Duration::from_secs(60),
+            max_attempts: 5,
+            retry_base_delay: Duration::from_secs(5),
+            retention: Duration::from_secs(7 * 24 * 3600),
+        }
+    }
+}
+
+impl JobQueueConfig {
+    /// Read `JOB_WORKER_CONCURRENCY`, `JOB_VISIBILITY_TIMEOUT_SECS`,
+    /// `JOB_MAX_ATTEMPTS`, `JOB_RETRY_BASE_DELAY_SECS` and
+    /// `JOB_RETENTION_DAYS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+
+        let defaults = Self::default();
+        Self {
+            concurrency: var("JOB_WORKER_CONCURRENCY").unwrap_or(defaults.concurrency),
+            visibility_timeout: var("JOB_VISIBILITY_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.visibility_timeout),
+            max_attempts: var::<i32>("JOB_MAX_ATTEMPTS")
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.max_attempts),
+            retry_base_delay: var("JOB_RETRY_BASE_DELAY_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.retry_base_delay),
+            retention: var::<u64>("JOB_RETENTION_DAYS")
+                .map(|days| Duration::from_secs(days * 24 * 3600))
+                .unwrap_or(defaults.retention),
+        }
+    }
+
+    /// Delay before retrying a job that has failed `attempts` times
+    fn retry_delay(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+        self.retry_base_delay
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// A queued unit of work
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: sqlx::types::Json<Value>,
+    pub status: String, // queued, running, succeeded, failed
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub result: Option<sqlx::types::Json<Value>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A job that exhausted its attempts
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub kind: String,
+    pub payload: sqlx::types::Json<Value>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Runs jobs of one kind; the returned value is stored as the job result
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    async fn run(&self, job: &Job) -> Result<Value>;
+}
+
+/// Durable job queue on Postgres. Workers claim jobs with
+/// `SELECT ... FOR UPDATE SKIP LOCKED` and hold a lease that is renewed
+/// while the job runs, so queued and in-flight jobs survive restarts.
+#[derive(Clone)]
+pub struct JobQueue {
+    db: Arc<PgPool>,
+    config: JobQueueConfig,
+    /// Identifies this replica's workers in `locked_by`
+    replica: Uuid,
+    /// Set on shutdown; workers stop claiming jobs
+    stopping: Arc<AtomicBool>,
+}
+
+const JOB_COLUMNS: &str = "id, kind, payload, status, attempts, max_attempts, run_at, \
+                           last_error, result, created_at, updated_at, completed_at";
+
+impl JobQueue {
+    pub fn new(db: PgPool, config: JobQueueConfig) -> Self {
+        Self {
+            db: Arc::new(db),
+            config,
+            replica: Uuid::new_v4(),
+            stopping: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn config(&self) -> &JobQueueConfig {
+        &self.config
+    }
+
+    /// Queue a job to run as soon as a worker is free
+    pub async fn enqueue(&self, kind: &str, payload: Value) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+
+        sqlx::query(
+            r#"
+            INSERT INTO jobs (id, kind, payload, max_attempts)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(id)
+        .bind(kind)
+        .bind(sqlx::types::Json(payload))
+        .bind(self.config.max_attempts)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to enqueue job")?;
+
+        debug!(job_id = %id, kind = kind, "Job enqueued");
+
+        Ok(id)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Option<Job>> {
+        let job =
+            sqlx::query_as::<_, Job>(&format!("SELECT {} FROM jobs WHERE id = $1", JOB_COLUMNS))
+                .bind(id)
+                .fetch_optional(self.db.as_ref())
+                .await
+                .context("Failed to get job")?;
+
+        Ok(job)
+    }
+
+    /// Claim the next runnable job of the given kinds. A running job whose
+    /// lease has expired is claimable again; its interrupted run counts as
+    /// an attempt.
+    async fn claim(&self, kinds: &[String], worker_id: &str) -> Result<Option<Job>> {
+        let job = sqlx::query_as::<_, Job>(&format!(
+            r#"
+            UPDATE jobs SET
+                status = 'running',
+                attempts = attempts + 1,
+                locked_by = $2,
+                locked_until = NOW() + make_interval(secs => $3),
+                updated_at = NOW()
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE kind = ANY($1)
+                    AND ((status = 'queued' AND run_at <= NOW())
+                        OR (status = 'running' AND locked_until < NOW()))
+                ORDER BY run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(kinds)
+        .bind(worker_id)
+        .bind(self.config.visibility_timeout.as_secs_f64())
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to claim job")?;
+
+        Ok(job)
+    }
+
+    /// Extend the lease on a running job
+    async fn heartbeat(&self, job_id: Uuid, worker_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs SET locked_until = NOW() + make_interval(secs => $3)
+            WHERE id = $1 AND locked_by = $2 AND status = 'running'
+            "#,
+        )
+        .bind(job_id)
+        .bind(worker_id)
+        .bind(self.config.visibility_timeout.as_secs_f64())
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to extend job lease")?;
+
+        Ok(())
+    }
+
+    async fn complete(&self, job_id: Uuid, worker_id: &str, result: Value) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs SET
+                status = 'succeeded',
+                result = $3,
+                last_error = NULL,
+                locked_by = NULL,
+                locked_until = NULL,
+                completed_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1 AND locked_by = $2
+            "#,
+        )
+        .bind(job_id)
+        .bind(worker_id)
+        .bind(sqlx::types::Json(result))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to complete job")?;
+
+        Ok(())
+    }
+
+    /// Schedule a retry, or dead-letter the job once attempts are exhausted
+    async fn fail(&self, job: &Job, worker_id: &str, error: &str) -> Result<()> {
+        if job.attempts < job.max_attempts {
+            let retry_at = Utc::now()
+                + chrono::Duration::from_std(self.config.retry_delay(job.attempts))
+                    .unwrap_or_else(|_| chrono::Duration::hours(1));
+
+            sqlx::query(
+                r#"
+                UPDATE jobs SET
+                    status = 'queued',
+                    run_at = $3,
+                    last_error = $4,
+                    locked_by = NULL,
+                    locked_until = NULL,
+                    updated_at = NOW()
+                WHERE id = $1 AND locked_by = $2
+                "#,
+            )
+            .bind(job.id)
+            .bind(worker_id)
+            .bind(retry_at)
+            .bind(error)
+            .execute(self.db.as_ref())
+            .await
+            .context("Failed to schedule job retry")?;
+
+            warn!(job_id = %job.id, kind = %job.kind, attempts = job.attempts, error = error, "Job failed, retrying");
+            return Ok(());
+        }
+
+        self.dead_letter(job.id, error).await?;
+        error!(job_id = %job.id, kind = %job.kind, attempts = job.attempts, error = error, "Job dead-lettered");
+
+        Ok(())
+    }
+
+    async fn dead_letter(&self, job_id: Uuid, error: &str) -> Result<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin dead-letter transaction")?;
+
+        sqlx::query(
+            r#"
+            UPDATE jobs SET
+                status = 'failed',
+                last_error = $2,
+                locked_by = NULL,
+                locked_until = NULL,
+                completed_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(error)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to mark job failed")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO job_dead_letters (job_id, kind, payload, attempts, last_error)
+            SELECT id, kind, payload, attempts, last_error FROM jobs WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to insert dead letter")?;
+
+        tx.commit().await.context("Failed to commit dead letter")?;
+
+        Ok(())
+    }
+
+    /// Dead-letter jobs whose final attempt was interrupted (lease expired
+    /// with no attempts left), and delete finished jobs past retention
+    pub async fn sweep(&self) -> Result<usize> {
+        let expired: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT id FROM jobs
+            WHERE status = 'running'
+                AND locked_until < NOW()
+                AND attempts >= max_attempts
+            "#,
+        )
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to find expired jobs")?;
+
+        for job_id in &expired {
+            self.dead_letter(*job_id, "Lease expired on final attempt")
+                .await?;
+        }
+
+        let deleted = sqlx::query(
+            r#"
+            DELETE FROM jobs
+            WHERE status IN ('succeeded', 'failed')
+                AND completed_at < NOW() - make_interval(secs => $1)
+            "#,
+        )
+        .bind(self.config.retention.as_secs_f64())
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to delete finished jobs")?;
+
+        debug!(
+            dead_lettered = expired.len(),
+            deleted = deleted.rows_affected(),
+            "Job queue swept"
+        );
+
+        Ok(expired.len())
+    }
+
+    /// Stop this replica's workers and put their running jobs straight back
+    /// on the queue instead of waiting for the leases to expire. The
+    /// interrupted run doesn't count as an attempt. Returns how many jobs
+    /// were interrupted.
+    pub async fn release_leases(&self) -> Result<u64> {
+        self.stopping.store(true, Ordering::SeqCst);
+
+        let released = sqlx::query(
+            r#"
+            UPDATE jobs SET
+                status = 'queued',
+                attempts = GREATEST(attempts - 1, 0),
+                run_at = NOW(),
+                locked_by = NULL,
+                locked_until = NULL,
+                updated_at = NOW()
+            WHERE status = 'running' AND locked_by LIKE $1
+            "#,
+        )
+        .bind(format!("{}/%", self.replica))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to release job leases")?;
+
+        Ok(released.rows_affected())
+    }
+
+    /// Most recent dead letters
+    pub async fn dead_letters(&self, limit: i64) -> Result<Vec<DeadLetter>> {
+        let dead_letters = sqlx::query_as::<_, DeadLetter>(
+            r#"
+            SELECT id, job_id, kind, payload, attempts, last_error, failed_at
+            FROM job_dead_letters
+            ORDER BY failed_at DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list dead letters")?;
+
+        Ok(dead_letters)
+    }
+
+    /// Put a dead-lettered job back on the queue with fresh attempts;
+    /// `false` if the dead letter doesn't exist
+    pub async fn requeue_dead_letter(&self, dead_letter_id: Uuid) -> Result<bool> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin requeue transaction")?;
+
+        let job_id: Option<Uuid> =
+            sqlx::query_scalar("DELETE FROM job_dead_letters WHERE id = $1 RETURNING job_id")
+                .bind(dead_letter_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .context("Failed to remove dead letter")?;
+
+        let Some(job_id) = job_id else {
+            return Ok(false);
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE jobs SET
+                status = 'queued',
+                attempts = 0,
+                run_at = NOW(),
+                completed_at = NULL,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to requeue job")?;
+
+        tx.commit().await.context("Failed to commit requeue")?;
+
+        info!(job_id = %job_id, "Dead-lettered job requeued");
+
+        Ok(true)
+    }
+
+    /// Start `concurrency` workers running jobs of the registered kinds
+    pub fn spawn_workers(
+        &self,
+        handlers: HashMap<String, Arc<dyn JobHandler>>,
+    ) -> Vec<JoinHandle<()>> {
+        self.spawn_pool("default", self.config.concurrency, handlers)
+    }
+
+    /// Start a separate pool of workers for the registered kinds, so slow
+    /// jobs can't hold up the kinds run by other pools
+    pub fn spawn_pool(
+        &self,
+        pool: &str,
+        concurrency: usize,
+        handlers: HashMap<String, Arc<dyn JobHandler>>,
+    ) -> Vec<JoinHandle<()>> {
+        let handlers = Arc::new(handlers);
+        let kinds: Arc<Vec<String>> = Arc::new(handlers.keys().cloned().collect());
+
+        info!(
+            pool = pool,
+            concurrency = concurrency,
+            kinds = ?kinds,
+            "Starting job workers"
+        );
+
+        (0..concurrency)
+            .map(|n| {
+                let queue = self.clone();
+                let handlers = handlers.clone();
+                let kinds = kinds.clone();
+                let worker_id = format!("{}/{}-{}", queue.replica, pool, n);
+
+                tokio::spawn(async move {
+                    while !queue.stopping.load(Ordering::SeqCst) {
+                        match queue.claim(&kinds, &worker_id).await {
+                            Ok(Some(job)) => queue.run_job(&job, &handlers, &worker_id).await,
+                            Ok(None) => tokio::time::sleep(IDLE_POLL_INTERVAL).await,
+                            Err(e) => {
+                                error!(error = %e, "Job worker failed to claim job");
+                                tokio::time::sleep(IDLE_POLL_INTERVAL * 10).await;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+
+    async fn run_job(
+        &self,
+        job: &Job,
+        handlers: &HashMap<String, Arc<dyn JobHandler>>,
+        worker_id: &str,
+    ) {
+        let Some(handler) = handlers.get(&job.kind) else {
+            return;
+        };
+
+        debug!(job_id = %job.id, kind = %job.kind, attempt = job.attempts, "Running job");
+
+        // Renew the lease at a third of the visibility timeout while running
+        let run = handler.run(job);
+        tokio::pin!(run);
+        let mut heartbeat = tokio::time::interval(self.config.visibility_timeout / 3);
+        heartbeat.tick().await;
+
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut run => break outcome,
+                _ = heartbeat.tick() => {
+                    if let Err(e) = self.heartbeat(job.id, worker_id).await {
+                        warn!(job_id = %job.id, error = %e, "Failed to renew job lease");
+                    }
+                }
+            }
+        };
+
+        let recorded = match outcome {
+            Ok(result) => self.complete(job.id, worker_id, result).await,
+            Err(e) => self.fail(job, worker_id, &format!("{:#}", e)).await,
+        };
+
+        if let Err(e) = recorded {
+            error!(job_id = %job.id, error = %e, "Failed to record job outcome");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        let config = JobQueueConfig {
+            retry_base_delay: Duration::from_secs(5),
+            ..JobQueueConfig::default()
+        };
+
+        assert_eq!(config.retry_delay(1), Duration::from_secs(5));
+        assert_eq!(config.retry_delay(2), Duration::from_secs(10));
+        assert_eq!(config.retry_delay(4), Duration::from_secs(40));
+        assert_eq!(config.retry_delay(30), MAX_RETRY_DELAY);
+    }
+}
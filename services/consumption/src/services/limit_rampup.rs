@@ -0,0 +1,309 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::models::{RampupStatus, TierLimits};
+
+/// How long a consumer's ramp-up is cached
+const RAMPUP_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Longest ramp an organization may set
+pub const MAX_RAMP_DAYS: u32 = 365;
+
+/// Warm-up of an organization's new consumers: their rate limits, burst and
+/// quota start at `start_percent` of the tier's and grow linearly to the
+/// full limits over `ramp_days`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimitRampup {
+    pub start_percent: u32,
+    pub ramp_days: u32,
+}
+
+impl LimitRampup {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=100).contains(&self.start_percent) {
+            return Err("start_percent must be between 1 and 100".to_string());
+        }
+        if !(1..=MAX_RAMP_DAYS).contains(&self.ramp_days) {
+            return Err(format!("ramp_days must be between 1 and {}", MAX_RAMP_DAYS));
+        }
+        Ok(())
+    }
+
+    /// Ramp-up of a consumer who started at `started_at`, or `None` once
+    /// their full limits apply
+    pub fn status(&self, started_at: DateTime<Utc>, at: DateTime<Utc>) -> Option<RampupStatus> {
+        let ends_at = started_at + Duration::days(self.ramp_days as i64);
+        if at >= ends_at {
+            return None;
+        }
+
+        let elapsed = (at - started_at).num_seconds().max(0);
+        let total = (ends_at - started_at).num_seconds();
+        let growth = (100 - self.start_percent) as i64 * elapsed / total;
+
+        Some(RampupStatus {
+            percent: self.start_percent + growth as u32,
+            start_percent: self.start_percent,
+            ramp_days: self.ramp_days,
+            started_at,
+            ends_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+struct RampupRow {
+    start_percent: i16,
+    ramp_days: i32,
+    /// First API key of the consumer; `None` outside a consumer lookup
+    started_at: Option<DateTime<Utc>>,
+}
+
+impl From<&RampupRow> for LimitRampup {
+    fn from(row: &RampupRow) -> Self {
+        Self {
+            start_percent: row.start_percent as u32,
+            ramp_days: row.ramp_days as u32,
+        }
+    }
+}
+
+/// Per-organization limit ramp-ups; consumers belong to an organization
+/// through their API key metadata
+#[derive(Clone)]
+pub struct LimitRampups {
+    db: Arc<PgPool>,
+    cache: Arc<RwLock<HashMap<Uuid, (Instant, Option<(LimitRampup, DateTime<Utc>)>)>>>,
+}
+
+impl LimitRampups {
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self {
+            db,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Ramp-up a consumer is in now, `None` without a ramp-up policy or once
+    /// it has run its course
+    pub async fn for_consumer(&self, consumer_id: Uuid) -> Result<Option<RampupStatus>> {
+        let cached = self
+            .cache
+            .read()
+            .unwrap()
+            .get(&consumer_id)
+            .filter(|(loaded_at, _)| loaded_at.elapsed() < RAMPUP_CACHE_TTL)
+            .map(|(_, rampup)| *rampup);
+
+        let rampup = match cached {
+            Some(rampup) => rampup,
+            None => {
+                let rampup = sqlx::query_as::<_, RampupRow>(
+                    r#"
+                    SELECT r.start_percent, r.ramp_days,
+                           (SELECT MIN(created_at) FROM api_keys WHERE consumer_id = $1) AS started_at
+                    FROM limit_rampups r
+                    JOIN api_keys k ON k.metadata->>'organization_id' = r.organization_id::text
+                    WHERE k.consumer_id = $1
+                    LIMIT 1
+                    "#,
+                )
+                .bind(consumer_id)
+                .fetch_optional(self.db.as_ref())
+                .await
+                .context("Failed to look up limit ramp-up")?
+                .and_then(|row| Some((LimitRampup::from(&row), row.started_at?)));
+
+                self.cache
+                    .write()
+                    .unwrap()
+                    .insert(consumer_id, (Instant::now(), rampup));
+                rampup
+            }
+        };
+
+        Ok(rampup.and_then(|(rampup, started_at)| rampup.status(started_at, Utc::now())))
+    }
+
+    /// A consumer's tier limits, reduced while they ramp up
+    pub async fn ramp(
+        &self,
+        consumer_id: Uuid,
+        limits: TierLimits,
+    ) -> Result<(TierLimits, Option<RampupStatus>)> {
+        let rampup = self.for_consumer(consumer_id).await?;
+        let limits = match rampup {
+            Some(rampup) => limits.ramped(rampup.percent),
+            None => limits,
+        };
+        Ok((limits, rampup))
+    }
+
+    pub async fn get(&self, organization_id: Uuid) -> Result<Option<LimitRampup>> {
+        let row = sqlx::query_as::<_, RampupRow>(
+            "SELECT start_percent, ramp_days, NULL::timestamptz AS started_at FROM limit_rampups WHERE organization_id = $1",
+        )
+        .bind(organization_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to get limit ramp-up")?;
+
+        Ok(row.as_ref().map(LimitRampup::from))
+    }
+
+    /// Ramp up the limits of an organization's consumers. Consumers whose
+    /// first API key is older than the ramp keep their full limits.
+    pub async fn set(&self, organization_id: Uuid, rampup: LimitRampup) -> Result<()> {
+        rampup.validate().map_err(anyhow::Error::msg)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO limit_rampups (organization_id, start_percent, ramp_days, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (organization_id) DO UPDATE SET
+                start_percent = EXCLUDED.start_percent,
+                ramp_days = EXCLUDED.ramp_days,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(organization_id)
+        .bind(rampup.start_percent as i16)
+        .bind(rampup.ramp_days as i32)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store limit ramp-up")?;
+
+        let consumers = self.forget_members(organization_id).await?;
+        info!(
+            organization_id = %organization_id,
+            start_percent = rampup.start_percent,
+            ramp_days = rampup.ramp_days,
+            consumers = consumers,
+            "Limit ramp-up set"
+        );
+        Ok(())
+    }
+
+    /// Give an organization's consumers their full limits. Returns whether
+    /// it had a ramp-up.
+    pub async fn remove(&self, organization_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM limit_rampups WHERE organization_id = $1")
+            .bind(organization_id)
+            .execute(self.db.as_ref())
+            .await
+            .context("Failed to delete limit ramp-up")?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let consumers = self.forget_members(organization_id).await?;
+        info!(
+            organization_id = %organization_id,
+            consumers = consumers,
+            "Limit ramp-up removed"
+        );
+        Ok(true)
+    }
+
+    /// Drop the cached ramp-ups of an organization's consumers, returning
+    /// how many it has
+    async fn forget_members(&self, organization_id: Uuid) -> Result<usize> {
+        let members: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT DISTINCT consumer_id FROM api_keys WHERE metadata->>'organization_id' = $1::text",
+        )
+        .bind(organization_id)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list organization consumers")?;
+
+        let mut cache = self.cache.write().unwrap();
+        for consumer_id in &members {
+            cache.remove(consumer_id);
+        }
+        Ok(members.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_rampup_grows_to_full_limits() {
+        let rampup = LimitRampup {
+            start_percent: 20,
+            ramp_days: 10,
+        };
+        let started_at = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+
+        let first = rampup.status(started_at, started_at).unwrap();
+        assert_eq!(first.percent, 20);
+        assert_eq!(first.ends_at, started_at + Duration::days(10));
+        assert_eq!(
+            rampup
+                .status(started_at, started_at + Duration::days(5))
+                .unwrap()
+                .percent,
+            60
+        );
+        assert_eq!(
+            rampup
+                .status(
+                    started_at,
+                    started_at + Duration::days(10) - Duration::seconds(1)
+                )
+                .unwrap()
+                .percent,
+            99
+        );
+        assert!(rampup
+            .status(started_at, started_at + Duration::days(10))
+            .is_none());
+
+        assert!(rampup.validate().is_ok());
+        assert!(LimitRampup {
+            start_percent: 0,
+            ramp_days: 10
+        }
+        .validate()
+        .is_err());
+        assert!(LimitRampup {
+            start_percent: 20,
+            ramp_days: 0
+        }
+        .validate()
+        .is_err());
+        assert!(LimitRampup {
+            start_percent: 20,
+            ramp_days: 400
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_ramped_limits() {
+        let limits = TierLimits {
+            rate_limit: 100,
+            burst_capacity: 3,
+            quota_limit: 10_000_000,
+            overage: Default::default(),
+            parameters: None,
+            max_concurrency: None,
+        };
+
+        let ramped = limits.clone().ramped(10);
+        assert_eq!(ramped.rate_limit, 10);
+        assert_eq!(ramped.burst_capacity, 1);
+        assert_eq!(ramped.quota_limit, 1_000_000);
+        assert_eq!(limits.clone().ramped(100), limits);
+    }
+}
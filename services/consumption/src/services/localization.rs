@@ -0,0 +1,724 @@
+//! Localization of consumer-facing reports
+//!
+//! SLA reports and statements are rendered in their consumer's locale: the
+//! organization's locale when one is set, `en-US` otherwise. A [`Locale`]
+//! has number and date conventions and a table of translated strings;
+//! strings missing from a table fall back to English.
+//!
+//! Locales are built in for `en-US`, `en-GB`, `de-DE`, `fr-FR`, `es-ES` and
+//! `pt-BR`. `LOCALES_DIR` adds or replaces locales with `<tag>.json` files:
+//!
+//! ```json
+//! {
+//!   "decimal_separator": ",",
+//!   "group_separator": ".",
+//!   "date_format": "%d.%m.%Y",
+//!   "datetime_format": "%d.%m.%Y %H:%M",
+//!   "strings": {"sla.title": "SLA-Bericht: {service} ({month})"}
+//! }
+//! ```
+//!
+//! Only rendered documents are localized; JSON responses and CSV exports
+//! keep machine-readable numbers and dates.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Locale of organizations without one
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// How long a consumer's locale is cached
+const LOCALE_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// English strings, the fallback of every locale. `{name}` placeholders
+/// are filled in by the renderer.
+const EN: &[(&str, &str)] = &[
+    ("report.service_consumer", "Service {service} · Consumer {consumer}"),
+    ("report.consumer", "Consumer {consumer}"),
+    ("report.period", "Period {start} to {end} (UTC) · Generated {generated}"),
+    ("report.provisional", "Provisional: the reporting period is still in progress and these figures will change."),
+    ("sla.title", "SLA Report: {service} ({month})"),
+    ("sla.compliance_summary", "Compliance summary"),
+    ("sla.objective", "Objective"),
+    ("sla.target", "Target"),
+    ("sla.actual", "Actual"),
+    ("sla.status", "Status"),
+    ("sla.uptime", "Uptime"),
+    ("sla.latency_p95", "Latency (p95)"),
+    ("sla.error_rate", "Error rate"),
+    ("sla.met", "Met"),
+    ("sla.missed", "Missed"),
+    ("sla.overall", "Overall: {status} over {requests} requests."),
+    ("sla.sla_met", "SLA met"),
+    ("sla.sla_missed", "SLA missed"),
+    ("sla.latency", "Latency"),
+    ("sla.violations", "Violations"),
+    ("sla.metric", "Metric"),
+    ("sla.severity", "Severity"),
+    ("sla.count", "Count"),
+    ("sla.worst", "Worst"),
+    ("sla.first", "First"),
+    ("sla.last", "Last"),
+    ("sla.no_violations", "No SLA violations were recorded in this period."),
+    ("sla.service_credits", "Service credits"),
+    ("sla.billed", "Billed"),
+    ("sla.credit", "Credit"),
+    ("sla.credit_amount", "Credit amount"),
+    ("sla.credit_schedule", "Credits apply when uptime falls below the target: 10% at or above 99%, 25% at or above 95%, 50% below 95%."),
+    ("statement.title", "Statement {month}"),
+    ("statement.usage", "Usage by service"),
+    ("statement.service", "Service"),
+    ("statement.requests", "Requests"),
+    ("statement.successful", "Successful"),
+    ("statement.tokens", "Tokens"),
+    ("statement.cost", "Cost"),
+    ("statement.sla_credit", "SLA credit"),
+    ("statement.no_usage", "No usage was recorded in this period."),
+    ("statement.total", "Total"),
+    ("statement.subtotal", "Subtotal"),
+    ("statement.sla_credits", "SLA credits"),
+    ("statement.total_due", "Total due"),
+    ("statement.events", "Notable events"),
+    ("statement.date", "Date"),
+    ("statement.event", "Event"),
+    ("statement.description", "Description"),
+    ("event.incident", "Incident"),
+    ("event.key_revoked", "API key revoked"),
+    ("event.suspended", "Account suspended"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("report.service_consumer", "Dienst {service} · Kunde {consumer}"),
+    ("report.consumer", "Kunde {consumer}"),
+    ("report.period", "Zeitraum {start} bis {end} (UTC) · Erstellt {generated}"),
+    ("report.provisional", "Vorläufig: Der Berichtszeitraum läuft noch, die Zahlen können sich ändern."),
+    ("sla.title", "SLA-Bericht: {service} ({month})"),
+    ("sla.compliance_summary", "Übersicht der Einhaltung"),
+    ("sla.objective", "Ziel"),
+    ("sla.target", "Vorgabe"),
+    ("sla.actual", "Ist"),
+    ("sla.status", "Status"),
+    ("sla.uptime", "Verfügbarkeit"),
+    ("sla.latency_p95", "Latenz (p95)"),
+    ("sla.error_rate", "Fehlerrate"),
+    ("sla.met", "Erfüllt"),
+    ("sla.missed", "Verfehlt"),
+    ("sla.overall", "Gesamt: {status} bei {requests} Anfragen."),
+    ("sla.sla_met", "SLA erfüllt"),
+    ("sla.sla_missed", "SLA verfehlt"),
+    ("sla.latency", "Latenz"),
+    ("sla.violations", "Verstöße"),
+    ("sla.metric", "Metrik"),
+    ("sla.severity", "Schweregrad"),
+    ("sla.count", "Anzahl"),
+    ("sla.worst", "Schlechtester Wert"),
+    ("sla.first", "Erster"),
+    ("sla.last", "Letzter"),
+    ("sla.no_violations", "In diesem Zeitraum wurden keine SLA-Verstöße erfasst."),
+    ("sla.service_credits", "Gutschriften"),
+    ("sla.billed", "Abgerechnet"),
+    ("sla.credit", "Gutschrift"),
+    ("sla.credit_amount", "Gutschriftbetrag"),
+    ("sla.credit_schedule", "Gutschriften gelten, wenn die Verfügbarkeit unter der Vorgabe liegt: 10 % ab 99 %, 25 % ab 95 %, 50 % unter 95 %."),
+    ("statement.title", "Abrechnung {month}"),
+    ("statement.usage", "Nutzung nach Dienst"),
+    ("statement.service", "Dienst"),
+    ("statement.requests", "Anfragen"),
+    ("statement.successful", "Erfolgreich"),
+    ("statement.tokens", "Tokens"),
+    ("statement.cost", "Kosten"),
+    ("statement.sla_credit", "SLA-Gutschrift"),
+    ("statement.no_usage", "In diesem Zeitraum wurde keine Nutzung erfasst."),
+    ("statement.total", "Summe"),
+    ("statement.subtotal", "Zwischensumme"),
+    ("statement.sla_credits", "SLA-Gutschriften"),
+    ("statement.total_due", "Fälliger Betrag"),
+    ("statement.events", "Wichtige Ereignisse"),
+    ("statement.date", "Datum"),
+    ("statement.event", "Ereignis"),
+    ("statement.description", "Beschreibung"),
+    ("event.incident", "Störung"),
+    ("event.key_revoked", "API-Schlüssel widerrufen"),
+    ("event.suspended", "Konto gesperrt"),
+];
+
+const FR: &[(&str, &str)] = &[
+    ("report.service_consumer", "Service {service} · Client {consumer}"),
+    ("report.consumer", "Client {consumer}"),
+    ("report.period", "Période du {start} au {end} (UTC) · Généré le {generated}"),
+    ("report.provisional", "Provisoire : la période est en cours et ces chiffres vont évoluer."),
+    ("sla.title", "Rapport SLA : {service} ({month})"),
+    ("sla.compliance_summary", "Synthèse de conformité"),
+    ("sla.objective", "Objectif"),
+    ("sla.target", "Cible"),
+    ("sla.actual", "Réel"),
+    ("sla.status", "Statut"),
+    ("sla.uptime", "Disponibilité"),
+    ("sla.latency_p95", "Latence (p95)"),
+    ("sla.error_rate", "Taux d'erreur"),
+    ("sla.met", "Atteint"),
+    ("sla.missed", "Manqué"),
+    ("sla.overall", "Global : {status} sur {requests} requêtes."),
+    ("sla.sla_met", "SLA respecté"),
+    ("sla.sla_missed", "SLA non respecté"),
+    ("sla.latency", "Latence"),
+    ("sla.violations", "Violations"),
+    ("sla.metric", "Métrique"),
+    ("sla.severity", "Gravité"),
+    ("sla.count", "Nombre"),
+    ("sla.worst", "Pire valeur"),
+    ("sla.first", "Première"),
+    ("sla.last", "Dernière"),
+    ("sla.no_violations", "Aucune violation du SLA n'a été enregistrée sur cette période."),
+    ("sla.service_credits", "Crédits de service"),
+    ("sla.billed", "Facturé"),
+    ("sla.credit", "Crédit"),
+    ("sla.credit_amount", "Montant du crédit"),
+    ("sla.credit_schedule", "Des crédits s'appliquent lorsque la disponibilité est inférieure à la cible : 10 % à partir de 99 %, 25 % à partir de 95 %, 50 % en dessous de 95 %."),
+    ("statement.title", "Relevé {month}"),
+    ("statement.usage", "Utilisation par service"),
+    ("statement.service", "Service"),
+    ("statement.requests", "Requêtes"),
+    ("statement.successful", "Réussies"),
+    ("statement.tokens", "Jetons"),
+    ("statement.cost", "Coût"),
+    ("statement.sla_credit", "Crédit SLA"),
+    ("statement.no_usage", "Aucune utilisation n'a été enregistrée sur cette période."),
+    ("statement.total", "Total"),
+    ("statement.subtotal", "Sous-total"),
+    ("statement.sla_credits", "Crédits SLA"),
+    ("statement.total_due", "Montant dû"),
+    ("statement.events", "Événements notables"),
+    ("statement.date", "Date"),
+    ("statement.event", "Événement"),
+    ("statement.description", "Description"),
+    ("event.incident", "Incident"),
+    ("event.key_revoked", "Clé API révoquée"),
+    ("event.suspended", "Compte suspendu"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("report.service_consumer", "Servicio {service} · Cliente {consumer}"),
+    ("report.consumer", "Cliente {consumer}"),
+    ("report.period", "Periodo del {start} al {end} (UTC) · Generado el {generated}"),
+    ("report.provisional", "Provisional: el periodo sigue en curso y estas cifras cambiarán."),
+    ("sla.title", "Informe de SLA: {service} ({month})"),
+    ("sla.compliance_summary", "Resumen de cumplimiento"),
+    ("sla.objective", "Objetivo"),
+    ("sla.target", "Meta"),
+    ("sla.actual", "Real"),
+    ("sla.status", "Estado"),
+    ("sla.uptime", "Disponibilidad"),
+    ("sla.latency_p95", "Latencia (p95)"),
+    ("sla.error_rate", "Tasa de errores"),
+    ("sla.met", "Cumplido"),
+    ("sla.missed", "Incumplido"),
+    ("sla.overall", "Total: {status} en {requests} solicitudes."),
+    ("sla.sla_met", "SLA cumplido"),
+    ("sla.sla_missed", "SLA incumplido"),
+    ("sla.latency", "Latencia"),
+    ("sla.violations", "Infracciones"),
+    ("sla.metric", "Métrica"),
+    ("sla.severity", "Gravedad"),
+    ("sla.count", "Cantidad"),
+    ("sla.worst", "Peor valor"),
+    ("sla.first", "Primera"),
+    ("sla.last", "Última"),
+    ("sla.no_violations", "No se registraron infracciones del SLA en este periodo."),
+    ("sla.service_credits", "Créditos de servicio"),
+    ("sla.billed", "Facturado"),
+    ("sla.credit", "Crédito"),
+    ("sla.credit_amount", "Importe del crédito"),
+    ("sla.credit_schedule", "Se aplican créditos cuando la disponibilidad queda por debajo de la meta: 10 % desde el 99 %, 25 % desde el 95 %, 50 % por debajo del 95 %."),
+    ("statement.title", "Extracto {month}"),
+    ("statement.usage", "Uso por servicio"),
+    ("statement.service", "Servicio"),
+    ("statement.requests", "Solicitudes"),
+    ("statement.successful", "Correctas"),
+    ("statement.tokens", "Tokens"),
+    ("statement.cost", "Coste"),
+    ("statement.sla_credit", "Crédito SLA"),
+    ("statement.no_usage", "No se registró uso en este periodo."),
+    ("statement.total", "Total"),
+    ("statement.subtotal", "Subtotal"),
+    ("statement.sla_credits", "Créditos SLA"),
+    ("statement.total_due", "Importe a pagar"),
+    ("statement.events", "Eventos destacados"),
+    ("statement.date", "Fecha"),
+    ("statement.event", "Evento"),
+    ("statement.description", "Descripción"),
+    ("event.incident", "Incidente"),
+    ("event.key_revoked", "Clave de API revocada"),
+    ("event.suspended", "Cuenta suspendida"),
+];
+
+const PT_BR: &[(&str, &str)] = &[
+    ("report.service_consumer", "Serviço {service} · Cliente {consumer}"),
+    ("report.consumer", "Cliente {consumer}"),
+    ("report.period", "Período de {start} a {end} (UTC) · Gerado em {generated}"),
+    ("report.provisional", "Provisório: o período ainda está em andamento e estes valores vão mudar."),
+    ("sla.title", "Relatório de SLA: {service} ({month})"),
+    ("sla.compliance_summary", "Resumo de conformidade"),
+    ("sla.objective", "Objetivo"),
+    ("sla.target", "Meta"),
+    ("sla.actual", "Real"),
+    ("sla.status", "Situação"),
+    ("sla.uptime", "Disponibilidade"),
+    ("sla.latency_p95", "Latência (p95)"),
+    ("sla.error_rate", "Taxa de erros"),
+    ("sla.met", "Atingido"),
+    ("sla.missed", "Não atingido"),
+    ("sla.overall", "Geral: {status} em {requests} requisições."),
+    ("sla.sla_met", "SLA cumprido"),
+    ("sla.sla_missed", "SLA descumprido"),
+    ("sla.latency", "Latência"),
+    ("sla.violations", "Violações"),
+    ("sla.metric", "Métrica"),
+    ("sla.severity", "Gravidade"),
+    ("sla.count", "Quantidade"),
+    ("sla.worst", "Pior valor"),
+    ("sla.first", "Primeira"),
+    ("sla.last", "Última"),
+    ("sla.no_violations", "Nenhuma violação de SLA foi registrada neste período."),
+    ("sla.service_credits", "Créditos de serviço"),
+    ("sla.billed", "Faturado"),
+    ("sla.credit", "Crédito"),
+    ("sla.credit_amount", "Valor do crédito"),
+    ("sla.credit_schedule", "Créditos se aplicam quando a disponibilidade fica abaixo da meta: 10% a partir de 99%, 25% a partir de 95%, 50% abaixo de 95%."),
+    ("statement.title", "Extrato {month}"),
+    ("statement.usage", "Uso por serviço"),
+    ("statement.service", "Serviço"),
+    ("statement.requests", "Requisições"),
+    ("statement.successful", "Bem-sucedidas"),
+    ("statement.tokens", "Tokens"),
+    ("statement.cost", "Custo"),
+    ("statement.sla_credit", "Crédito de SLA"),
+    ("statement.no_usage", "Nenhum uso foi registrado neste período."),
+    ("statement.total", "Total"),
+    ("statement.subtotal", "Subtotal"),
+    ("statement.sla_credits", "Créditos de SLA"),
+    ("statement.total_due", "Total a pagar"),
+    ("statement.events", "Eventos relevantes"),
+    ("statement.date", "Data"),
+    ("statement.event", "Evento"),
+    ("statement.description", "Descrição"),
+    ("event.incident", "Incidente"),
+    ("event.key_revoked", "Chave de API revogada"),
+    ("event.suspended", "Conta suspensa"),
+];
+
+/// Number, date and text conventions of a locale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Locale {
+    /// BCP 47 tag, e.g. `de-DE`; taken from the file name for locale files
+    #[serde(default)]
+    pub tag: String,
+    pub decimal_separator: char,
+    /// Thousands separator; `None` doesn't group digits
+    pub group_separator: Option<char>,
+    /// `strftime` pattern of dates
+    pub date_format: String,
+    /// `strftime` pattern of dates with a time
+    pub datetime_format: String,
+    /// Translated strings by key
+    #[serde(default)]
+    pub strings: HashMap<String, String>,
+}
+
+impl Locale {
+    fn builtin(
+        tag: &str,
+        decimal_separator: char,
+        group_separator: char,
+        date_format: &str,
+        strings: &[(&str, &str)],
+    ) -> Self {
+        Self {
+            tag: tag.to_string(),
+            decimal_separator,
+            group_separator: Some(group_separator),
+            date_format: date_format.to_string(),
+            datetime_format: format!("{} %H:%M", date_format),
+            strings: strings
+                .iter()
+                .map(|(key, text)| (key.to_string(), text.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Translated string for `key`, else the English one, else the key
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| EN.iter().find(|(k, _)| *k == key).map(|(_, text)| *text))
+            .unwrap_or(key)
+    }
+
+    /// A number with `decimals` digits after the separator
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value);
+        let (sign, digits) = match formatted.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", formatted.as_str()),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let mut number = format!("{}{}", sign, self.group(integer));
+        if !fraction.is_empty() {
+            number.push(self.decimal_separator);
+            number.push_str(fraction);
+        }
+        number
+    }
+
+    pub fn integer(&self, value: i64) -> String {
+        let digits = value.unsigned_abs().to_string();
+        let sign = if value < 0 { "-" } else { "" };
+        format!("{}{}", sign, self.group(&digits))
+    }
+
+    /// A percentage, e.g. `99.500%`
+    pub fn percent(&self, value: f64, decimals: usize) -> String {
+        format!("{}%", self.number(value, decimals))
+    }
+
+    /// An amount with two decimals and its currency code
+    pub fn money(&self, amount: f64, currency: &str) -> String {
+        format!("{} {}", self.number(amount, 2), currency)
+    }
+
+    pub fn date(&self, at: DateTime<Utc>) -> String {
+        at.format(&self.date_format).to_string()
+    }
+
+    pub fn datetime(&self, at: DateTime<Utc>) -> String {
+        at.format(&self.datetime_format).to_string()
+    }
+
+    /// Digits with the group separator every three from the right
+    fn group(&self, digits: &str) -> String {
+        let Some(separator) = self.group_separator else {
+            return digits.to_string();
+        };
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+/// `template` with each `{name}` replaced by its value in `args`
+pub fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// Locales reports can be rendered in, by lowercased tag
+#[derive(Debug, Clone)]
+pub struct Locales {
+    locales: Arc<HashMap<String, Arc<Locale>>>,
+}
+
+impl Locales {
+    /// The compiled-in locales
+    pub fn builtin() -> Self {
+        let locales = [
+            Locale::builtin("en-US", '.', ',', "%m/%d/%Y", &[]),
+            Locale::builtin("en-GB", '.', ',', "%d/%m/%Y", &[]),
+            Locale::builtin("de-DE", ',', '.', "%d.%m.%Y", DE),
+            Locale::builtin("fr-FR", ',', '\u{202f}', "%d/%m/%Y", FR),
+            Locale::builtin("es-ES", ',', '.', "%d/%m/%Y", ES),
+            Locale::builtin("pt-BR", ',', '.', "%d/%m/%Y", PT_BR),
+        ];
+
+        Self {
+            locales: Arc::new(
+                locales
+                    .into_iter()
+                    .map(|locale| (locale.tag.to_lowercase(), Arc::new(locale)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Built-in locales plus those in `LOCALES_DIR`, when set
+    pub fn from_env() -> Result<Self> {
+        let locales = Self::builtin();
+        match std::env::var("LOCALES_DIR") {
+            Ok(dir) => locales.with_dir(Path::new(&dir)),
+            Err(_) => Ok(locales),
+        }
+    }
+
+    /// Add or replace locales with the `<tag>.json` files in `dir`
+    pub fn with_dir(self, dir: &Path) -> Result<Self> {
+        let mut locales = (*self.locales).clone();
+        let entries = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read locale directory {:?}", dir))?;
+
+        for entry in entries {
+            let path = entry.context("Failed to read locale directory")?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(tag) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read locale file {:?}", path))?;
+            let mut locale: Locale = serde_json::from_str(&text)
+                .with_context(|| format!("Invalid locale file {:?}", path))?;
+            locale.tag = tag.to_string();
+
+            info!(locale = %tag, strings = locale.strings.len(), "Loaded locale");
+            locales.insert(tag.to_lowercase(), Arc::new(locale));
+        }
+
+        Ok(Self {
+            locales: Arc::new(locales),
+        })
+    }
+
+    /// A locale by tag, ignoring case
+    pub fn get(&self, tag: &str) -> Option<Arc<Locale>> {
+        self.locales.get(&tag.to_lowercase()).cloned()
+    }
+
+    pub fn default_locale(&self) -> Arc<Locale> {
+        self.get(DEFAULT_LOCALE)
+            .expect("the default locale is built in")
+    }
+
+    /// Tags of every locale, sorted
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .locales
+            .values()
+            .map(|locale| locale.tag.clone())
+            .collect();
+        tags.sort();
+        tags
+    }
+}
+
+/// Locale chosen for an organization
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct OrganizationLocale {
+    pub organization_id: Uuid,
+    pub locale: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Per-organization report locales; consumers belong to an organization
+/// through their API key metadata
+#[derive(Clone)]
+pub struct ReportLocales {
+    db: Arc<PgPool>,
+    locales: Locales,
+    cache: Arc<RwLock<HashMap<Uuid, (Instant, Arc<Locale>)>>>,
+}
+
+impl ReportLocales {
+    pub fn new(db: PgPool, locales: Locales) -> Self {
+        Self {
+            db: Arc::new(db),
+            locales,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn locales(&self) -> &Locales {
+        &self.locales
+    }
+
+    /// Locale of a consumer's organization, the default without one
+    pub async fn for_consumer(&self, consumer_id: Uuid) -> Result<Arc<Locale>> {
+        if let Some((loaded_at, locale)) = self.cache.read().unwrap().get(&consumer_id) {
+            if loaded_at.elapsed() < LOCALE_CACHE_TTL {
+                return Ok(locale.clone());
+            }
+        }
+
+        let tag: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT l.locale
+            FROM organization_locales l
+            JOIN api_keys k ON k.metadata->>'organization_id' = l.organization_id::text
+            WHERE k.consumer_id = $1
+            LIMIT 1
+            "#,
+        )
+        .bind(consumer_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to look up organization locale")?;
+
+        let locale = match tag {
+            Some(tag) => self.locales.get(&tag).unwrap_or_else(|| {
+                warn!(locale = %tag, "Organization locale is not loaded, using the default");
+                self.locales.default_locale()
+            }),
+            None => self.locales.default_locale(),
+        };
+
+        self.cache
+            .write()
+            .unwrap()
+            .insert(consumer_id, (Instant::now(), locale.clone()));
+
+        Ok(locale)
+    }
+
+    pub async fn get(&self, organization_id: Uuid) -> Result<Option<OrganizationLocale>> {
+        let locale = sqlx::query_as::<_, OrganizationLocale>(
+            "SELECT organization_id, locale, updated_at FROM organization_locales WHERE organization_id = $1",
+        )
+        .bind(organization_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to get organization locale")?;
+
+        Ok(locale)
+    }
+
+    /// Render an organization's reports and statements in a locale
+    pub async fn set(&self, organization_id: Uuid, tag: &str) -> Result<OrganizationLocale> {
+        let Some(locale) = self.locales.get(tag) else {
+            bail!("Locale {} is not available", tag);
+        };
+
+        let stored = sqlx::query_as::<_, OrganizationLocale>(
+            r#"
+            INSERT INTO organization_locales (organization_id, locale, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (organization_id) DO UPDATE SET
+                locale = EXCLUDED.locale,
+                updated_at = NOW()
+            RETURNING organization_id, locale, updated_at
+            "#,
+        )
+        .bind(organization_id)
+        .bind(&locale.tag)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to store organization locale")?;
+
+        let members: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT DISTINCT consumer_id FROM api_keys WHERE metadata->>'organization_id' = $1::text",
+        )
+        .bind(organization_id)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list organization consumers")?;
+
+        let mut cache = self.cache.write().unwrap();
+        for consumer_id in &members {
+            cache.remove(consumer_id);
+        }
+        drop(cache);
+
+        info!(
+            organization_id = %organization_id,
+            locale = %locale.tag,
+            consumers = members.len(),
+            "Organization locale set"
+        );
+
+        Ok(stored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_locale_formats() {
+        let locales = Locales::builtin();
+        let us = locales.default_locale();
+        let de = locales.get("de-de").unwrap();
+        let at = Utc.with_ymd_and_hms(2025, 11, 3, 14, 5, 0).unwrap();
+
+        assert_eq!(us.number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(de.number(1234567.891, 2), "1.234.567,89");
+        assert_eq!(de.number(-0.5, 3), "-0,500");
+        assert_eq!(us.number(999.0, 0), "999");
+        assert_eq!(de.integer(-1000), "-1.000");
+        assert_eq!(de.money(250.0, "EUR"), "250,00 EUR");
+        assert_eq!(de.percent(99.5, 1), "99,5%");
+        assert_eq!(us.date(at), "11/03/2025");
+        assert_eq!(de.datetime(at), "03.11.2025 14:05");
+
+        assert_eq!(de.text("sla.uptime"), "Verfügbarkeit");
+        assert_eq!(
+            fill(
+                de.text("sla.overall"),
+                &[("status", "SLA erfüllt"), ("requests", "1.000")]
+            ),
+            "Gesamt: SLA erfüllt bei 1.000 Anfragen."
+        );
+        assert_eq!(us.text("sla.uptime"), "Uptime");
+        assert_eq!(us.text("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_builtin_tables_translate_every_string() {
+        for table in [DE, FR, ES, PT_BR] {
+            for (key, _) in EN {
+                assert!(
+                    table.iter().any(|(k, _)| k == key),
+                    "missing translation of {}",
+                    key
+                );
+            }
+            assert_eq!(table.len(), EN.len());
+        }
+    }
+
+    #[test]
+    fn test_locale_files_extend_builtins() {
+        let dir = std::env::temp_dir().join(format!("locales-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("nl-NL.json"),
+            r#"{
+                "decimal_separator": ",",
+                "group_separator": ".",
+                "date_format": "%d-%m-%Y",
+                "datetime_format": "%d-%m-%Y %H:%M",
+                "strings": {"sla.uptime": "Beschikbaarheid"}
+            }"#,
+        )
+        .unwrap();
+
+        let locales = Locales::builtin().with_dir(&dir).unwrap();
+        let nl = locales.get("nl-nl").unwrap();
+        assert_eq!(nl.tag, "nl-NL");
+        assert_eq!(nl.text("sla.uptime"), "Beschikbaarheid");
+        // Untranslated strings fall back to English
+        assert_eq!(nl.text("sla.latency"), "Latency");
+        assert!(locales.tags().contains(&"de-DE".to_string()));
+
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        assert!(Locales::builtin().with_dir(&dir).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
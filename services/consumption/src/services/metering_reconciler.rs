@@ -0,0 +1,289 @@
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::canary::canary_consumer_id;
+use super::quota_manager::QuotaManager;
+
+/// Default relative drift tolerated between metering sources (0.1%)
+const DEFAULT_TOLERANCE: f64 = 0.001;
+
+/// Cross-checks daily token totals between usage records, quota increments,
+/// analytics events and invoices to catch metering drift
+#[derive(Clone)]
+pub struct MeteringReconciler {
+    db: Arc<PgPool>,
+    quota_manager: QuotaManager,
+    tolerance: f64,
+}
+
+/// Reconciliation outcome for one service on one day
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReconciliationResult {
+    pub day: NaiveDate,
+    pub service_id: Uuid,
+    pub usage_tokens: i64,
+    pub quota_tokens: i64,
+    pub analytics_tokens: i64,
+    /// `None` until the billing system reports invoiced usage for the day
+    pub invoiced_tokens: Option<i64>,
+    pub max_drift: f64,
+    pub status: String, // ok, discrepancy
+}
+
+/// Invoiced token total reported by billing
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvoicedUsage {
+    pub service_id: Uuid,
+    pub tokens: i64,
+}
+
+/// Largest relative difference of any source from the usage records total
+fn max_drift(usage_tokens: i64, others: &[i64]) -> f64 {
+    others
+        .iter()
+        .map(|&other| {
+            let diff = (other - usage_tokens).unsigned_abs() as f64;
+            if usage_tokens == 0 {
+                if other == 0 { 0.0 } else { 1.0 }
+            } else {
+                diff / usage_tokens as f64
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+impl MeteringReconciler {
+    pub fn new(db: PgPool, quota_manager: QuotaManager) -> Self {
+        let tolerance = std::env::var("METERING_TOLERANCE")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(DEFAULT_TOLERANCE);
+
+        Self {
+            db: Arc::new(db),
+            quota_manager,
+            tolerance,
+        }
+    }
+
+    /// Reconcile every service with activity on `day` and store the results
+    pub async fn reconcile_day(&self, day: NaiveDate) -> Result<Vec<ReconciliationResult>> {
+        // Canary traffic is recorded as usage but never charged to a quota
+        let usage = self
+            .sum_by_service(
+                r#"
+                SELECT service_id, COALESCE(SUM((usage->>'total_tokens')::BIGINT), 0)::BIGINT
+                FROM usage_records_all
+                WHERE timestamp >= $1::date AND timestamp < $1::date + 1
+                    AND status = 'success'
+                    AND consumer_id <> $2
+                GROUP BY service_id
+                "#,
+                day,
+            )
+            .await
+            .context("Failed to sum usage records")?;
+
+        let analytics = self
+            .sum_by_service(
+                r#"
+                SELECT service_id, COALESCE(SUM((payload->'usage'->>'total_tokens')::BIGINT), 0)::BIGINT
+                FROM analytics_outbox
+                WHERE event_type = 'consumption_request'
+                    AND created_at >= $1::date AND created_at < $1::date + 1
+                    AND consumer_id IS DISTINCT FROM $2
+                GROUP BY service_id
+                "#,
+                day,
+            )
+            .await
+            .context("Failed to sum analytics events")?;
+
+        let invoiced: HashMap<Uuid, i64> = sqlx::query_as::<_, (Uuid, i64)>(
+            "SELECT service_id, tokens FROM invoiced_usage WHERE day = $1",
+        )
+        .bind(day)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load invoiced usage")?
+        .into_iter()
+        .collect();
+
+        let quota = self.quota_manager.daily_increments(day).await?;
+
+        let service_ids: BTreeSet<Uuid> = usage
+            .keys()
+            .chain(analytics.keys())
+            .chain(quota.keys())
+            .chain(invoiced.keys())
+            .copied()
+            .collect();
+
+        let mut results = Vec::with_capacity(service_ids.len());
+
+        for service_id in service_ids {
+            let usage_tokens = usage.get(&service_id).copied().unwrap_or(0);
+            let quota_tokens = quota.get(&service_id).copied().unwrap_or(0);
+            let analytics_tokens = analytics.get(&service_id).copied().unwrap_or(0);
+            let invoiced_tokens = invoiced.get(&service_id).copied();
+
+            let mut others = vec![quota_tokens, analytics_tokens];
+            others.extend(invoiced_tokens);
+            let drift = max_drift(usage_tokens, &others);
+
+            let status = if drift > self.tolerance {
+                warn!(
+                    day = %day,
+                    service_id = %service_id,
+                    usage_tokens = usage_tokens,
+                    quota_tokens = quota_tokens,
+                    analytics_tokens = analytics_tokens,
+                    invoiced_tokens = ?invoiced_tokens,
+                    drift = drift,
+                    "Metering discrepancy detected"
+                );
+                "discrepancy"
+            } else {
+                "ok"
+            };
+
+            let result = sqlx::query_as::<_, ReconciliationResult>(
+                r#"
+                INSERT INTO metering_reconciliations (
+                    day, service_id, usage_tokens, quota_tokens, analytics_tokens,
+                    invoiced_tokens, max_drift, status, checked_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+                ON CONFLICT (day, service_id) DO UPDATE SET
+                    usage_tokens = EXCLUDED.usage_tokens,
+                    quota_tokens = EXCLUDED.quota_tokens,
+                    analytics_tokens = EXCLUDED.analytics_tokens,
+                    invoiced_tokens = EXCLUDED.invoiced_tokens,
+                    max_drift = EXCLUDED.max_drift,
+                    status = EXCLUDED.status,
+                    checked_at = NOW()
+                RETURNING day, service_id, usage_tokens, quota_tokens, analytics_tokens,
+                          invoiced_tokens, max_drift, status
+                "#,
+            )
+            .bind(day)
+            .bind(service_id)
+            .bind(usage_tokens)
+            .bind(quota_tokens)
+            .bind(analytics_tokens)
+            .bind(invoiced_tokens)
+            .bind(drift)
+            .bind(status)
+            .fetch_one(self.db.as_ref())
+            .await
+            .context("Failed to store reconciliation result")?;
+
+            results.push(result);
+        }
+
+        info!(
+            day = %day,
+            services = results.len(),
+            discrepancies = results.iter().filter(|r| r.status == "discrepancy").count(),
+            "Metering reconciliation completed"
+        );
+
+        Ok(results)
+    }
+
+    /// Background job: reconcile the previous (complete) day
+    pub async fn reconcile_yesterday(&self) -> Result<Vec<ReconciliationResult>> {
+        let yesterday = Utc::now().date_naive().pred_opt().context("Invalid date")?;
+        self.reconcile_day(yesterday).await
+    }
+
+    /// Stored reconciliation results, newest first
+    pub async fn list_results(
+        &self,
+        day: Option<NaiveDate>,
+        discrepancies_only: bool,
+        limit: i64,
+    ) -> Result<Vec<ReconciliationResult>> {
+        let results = sqlx::query_as::<_, ReconciliationResult>(
+            r#"
+            SELECT day, service_id, usage_tokens, quota_tokens, analytics_tokens,
+                   invoiced_tokens, max_drift, status
+            FROM metering_reconciliations
+            WHERE ($1::date IS NULL OR day = $1)
+                AND (NOT $2 OR status = 'discrepancy')
+            ORDER BY day DESC, max_drift DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(day)
+        .bind(discrepancies_only)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list reconciliation results")?;
+
+        Ok(results)
+    }
+
+    /// Store invoiced token totals reported by billing for a day
+    pub async fn record_invoiced_usage(&self, day: NaiveDate, entries: &[InvoicedUsage]) -> Result<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin invoiced usage transaction")?;
+
+        for entry in entries {
+            sqlx::query(
+                r#"
+                INSERT INTO invoiced_usage (day, service_id, tokens)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (day, service_id) DO UPDATE SET
+                    tokens = EXCLUDED.tokens,
+                    recorded_at = NOW()
+                "#,
+            )
+            .bind(day)
+            .bind(entry.service_id)
+            .bind(entry.tokens)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to record invoiced usage")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit invoiced usage")?;
+
+        Ok(())
+    }
+
+    async fn sum_by_service(&self, query: &str, day: NaiveDate) -> Result<HashMap<Uuid, i64>> {
+        let rows = sqlx::query_as::<_, (Uuid, i64)>(query)
+            .bind(day)
+            .bind(canary_consumer_id())
+            .fetch_all(self.db.as_ref())
+            .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_drift() {
+        assert_eq!(max_drift(1000, &[1000, 1000]), 0.0);
+        assert_eq!(max_drift(1000, &[999, 1010]), 0.01);
+        assert_eq!(max_drift(0, &[0]), 0.0);
+        assert_eq!(max_drift(0, &[5]), 1.0);
+    }
+}
@@ -1,25 +1,148 @@
+pub mod admin;
+pub mod alert_manager;
+#[cfg(feature = "kafka")]
+pub mod analytics_kafka;
+pub mod analytics_outbox;
+pub mod analytics_spool;
 pub mod analytics_streamer;
 pub mod api_key_manager;
+pub mod autoscaling;
+pub mod billing;
+pub mod billing_anchor;
+pub mod canary;
+pub mod client_telemetry;
+pub mod concurrency_limiter;
+pub mod content_scan;
+pub mod credential_vault;
+pub mod data_residency;
+pub mod dependency_health;
+pub mod endpoint_balancer;
+pub mod job_queue;
+pub mod limit_rampup;
+pub mod localization;
+pub mod metering_reconciler;
+pub mod mtls;
+pub mod oidc;
+pub mod parameter_policy;
+pub mod pii_filter;
+pub mod plugin_runtime;
 pub mod policy_client;
+pub mod pricing_experiments;
+pub mod privacy;
+pub mod quota_ledger;
 pub mod quota_manager;
+pub mod rate_limit_overrides;
+pub mod rate_limit_queue;
+pub mod rate_limit_simulator;
 pub mod rate_limiter;
+pub mod redis_audit;
 pub mod request_router;
+pub mod response_cache;
+pub mod response_pipeline;
+pub mod routing_rules;
+pub mod service_health;
+pub mod shutdown;
 pub mod sla_monitor;
+pub mod sla_reports;
+pub mod startup;
+pub mod statements;
+pub mod tier_catalog;
+pub mod upstream_recorder;
+pub mod usage_ingest;
+pub mod usage_insights;
 pub mod usage_meter;
+pub mod usage_partitions;
+pub mod usage_rollups;
+pub mod webhook_dispatcher;
 
 // Phase 2B: Runtime consumption adapters for upstream LLM-Dev-Ops services
 pub mod policy_engine_client;
 pub mod registry_client;
 pub mod shield_client;
 
-pub use analytics_streamer::{AnalyticsEvent, AnalyticsStreamer};
+#[cfg(test)]
+mod contract_tests;
+
+pub use admin::AdminService;
+pub use alert_manager::{AlertManager, AlertWebhook};
+pub use analytics_outbox::{AnalyticsOutbox, OutboxEvent, OutboxPage};
+pub use analytics_streamer::{
+    AnalyticsEvent, AnalyticsSink, AnalyticsStreamer, EventPriority, OverflowStrategy,
+};
 pub use api_key_manager::ApiKeyManager;
+pub use autoscaling::{AutoscalingSignals, AutoscalingSnapshot, TierAdmissionRate};
+pub use billing::{Invoice, InvoiceGenerator, InvoiceItem, InvoiceLine};
+pub use billing_anchor::{BillingAnchor, BillingAnchors, QuotaPeriod};
+pub use canary::{CanaryOutcome, CanaryProbe, SyntheticCanary};
+pub use client_telemetry::ClientInfo;
+pub use concurrency_limiter::{ConcurrencyLimiter, ConcurrencyPermit};
+pub use content_scan::{ContentScanConfig, ContentScanner, ScanVerdict};
+pub use credential_vault::{
+    AuthHeader, CredentialAuditEntry, CredentialMetadata, CredentialVault, StoreCredentialRequest,
+};
+pub use data_residency::{DataResidency, ResidencyPin, StorageLocation};
+pub use dependency_health::{DependencyCheck, DependencyHealth, HealthReport, ProbeStatus};
+pub use endpoint_balancer::{
+    BalancingStrategy, EndpointBalancer, EndpointPool, EndpointStatus, WeightedEndpoint,
+};
+pub use job_queue::{DeadLetter, Job, JobHandler, JobQueue, JobQueueConfig};
+pub use limit_rampup::{LimitRampup, LimitRampups, MAX_RAMP_DAYS};
+pub use localization::{Locale, Locales, OrganizationLocale, ReportLocales, DEFAULT_LOCALE};
+pub use metering_reconciler::{InvoicedUsage, MeteringReconciler, ReconciliationResult};
+pub use mtls::{ClientCertAuth, ClientCertificate, MtlsConfig, MtlsMode, TlsServer};
+pub use oidc::{OidcConfig, OidcVerifier};
+pub use parameter_policy::{apply_parameter_policy, ParameterViolation};
+pub use pii_filter::{redact_pii, PiiCategory, PiiFilter, PiiFilterConfig, PiiMode, PiiScan};
+pub use plugin_runtime::{
+    PluginCapability, PluginKind, PluginPin, PluginRuntime, PolicyDecision, RequestPluginOutcome,
+};
 pub use policy_client::{PolicyClient, PolicyValidationResponse, PolicyViolation};
-pub use quota_manager::QuotaManager;
+pub use pricing_experiments::{
+    CreateExperimentRequest, PriceAssignment, PriceBook, PricingExperiment, PricingExperiments,
+    VariantResult,
+};
+pub use privacy::{ErasureReport, PrivacyExport, PrivacyService, Signed};
+pub use quota_ledger::{
+    NewQuotaEvent, QuotaBalance, QuotaEvent, QuotaEventKind, QuotaLedger, QuotaLedgerEntry,
+};
+pub use quota_manager::{
+    history_window_count, QuotaCounters, QuotaManager, QuotaPreload, RedisCounter,
+    MAX_HISTORY_WINDOWS,
+};
+pub use rate_limit_overrides::{RateLimitOverride, RateLimitOverrides};
+pub use rate_limit_queue::{RateLimitQueue, RateLimitQueueConfig};
+pub use rate_limit_simulator::{
+    ConsumerThrottle, RateLimitSimulator, SimulationOutcome, SimulationReport, SimulationRequest,
+};
 pub use rate_limiter::RateLimiter;
-pub use request_router::RequestRouter;
-pub use sla_monitor::SLAMonitor;
-pub use usage_meter::UsageMeter;
+pub use redis_audit::{PatternAudit, RedisAudit, RedisAuditReport, TtlDistribution};
+pub use request_router::{RequestRouter, UpstreamStream};
+pub use response_cache::{CachedResponse, ResponseCache, ResponseCacheConfig};
+pub use response_pipeline::{
+    ResponseContext, ResponsePipeline, ResponsePipelineConfig, ResponsePipelineOutcome,
+};
+pub use routing_rules::{
+    RoutingAction, RoutingContext, RoutingDecision, RoutingPriority, RoutingRule, RoutingRulesEngine,
+};
+pub use service_health::{HealthStatus, HealthThresholds, ServiceHealth, ServiceHealthRecord};
+pub use shutdown::{ShutdownPhase, ShutdownReport};
+pub use sla_monitor::{SLAMonitor, ViolationDeduplicator};
+pub use sla_reports::{SlaReport, SlaReportGenerator, ViolationSummary};
+pub use startup::{SharedStartupReport, StartupPhase, StartupReport};
+pub use statements::{Statement, StatementEvent, StatementGenerator, StatementLine};
+pub use tier_catalog::TierCatalog;
+pub use upstream_recorder::UpstreamRecorder;
+pub use usage_ingest::{IngestReport, UsageBatch, UsageIngestor};
+pub use usage_insights::{
+    PeakWindow, RateLimitProfileSuggestion, UsageHeatmap, UsageInsights, UsageInsightsAnalyzer,
+    USAGE_INSIGHTS_JOB,
+};
+pub use usage_meter::{UsageMeter, UsageRecordFilter, UsageRecordPage};
+pub use usage_partitions::{UsagePartitionConfig, UsagePartitions};
+pub use usage_rollups::{UsageRollups, UsageTotals};
+pub use webhook_dispatcher::{
+    CreatedWebhook, Webhook, WebhookDispatcher, WebhookEvent, WebhookRequest, WEBHOOK_DELIVERY_JOB,
+};
 
 // Phase 2B: Export upstream service consumers
 pub use policy_engine_client::{
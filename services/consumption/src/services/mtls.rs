@@ -0,0 +1,403 @@
+use anyhow::{bail, Context, Result};
+use axum::{
+    http::{HeaderMap, Request as HttpRequest},
+    Router,
+};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+};
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Header a TLS-terminating proxy forwards the client certificate in
+/// (Envoy's format: `By=...;Hash=...;Subject="CN=...,O=..."`)
+pub const FORWARDED_CLIENT_CERT_HEADER: &str = "x-forwarded-client-cert";
+
+/// Where client certificates are verified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MtlsMode {
+    /// The service terminates TLS itself and verifies client certificates
+    /// against the client CA
+    Terminate {
+        cert: PathBuf,
+        key: PathBuf,
+        client_ca: PathBuf,
+    },
+    /// A proxy in front of the service verifies them and forwards the
+    /// certificate in `X-Forwarded-Client-Cert`
+    Forwarded,
+}
+
+/// Client certificate authentication settings, read from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtlsConfig {
+    pub mode: MtlsMode,
+    /// Subject attribute holding the consumer id
+    pub consumer_attribute: String,
+}
+
+impl MtlsConfig {
+    /// Read `MTLS_MODE` (`off`, `terminate` or `forwarded`) and
+    /// `MTLS_CONSUMER_ATTRIBUTE`, plus `TLS_CERT_PATH`, `TLS_KEY_PATH` and
+    /// `MTLS_CLIENT_CA_PATH` when terminating. `None` when off.
+    pub fn from_env() -> Result<Option<Self>> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let path = |name: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .with_context(|| format!("{} is required with MTLS_MODE=terminate", name))
+        };
+
+        let mode = match var("MTLS_MODE").as_deref() {
+            None | Some("off") => return Ok(None),
+            Some("terminate") => MtlsMode::Terminate {
+                cert: path("TLS_CERT_PATH")?,
+                key: path("TLS_KEY_PATH")?,
+                client_ca: path("MTLS_CLIENT_CA_PATH")?,
+            },
+            Some("forwarded") => MtlsMode::Forwarded,
+            Some(other) => bail!(
+                "Invalid MTLS_MODE: {} (expected off, terminate or forwarded)",
+                other
+            ),
+        };
+
+        Ok(Some(Self {
+            mode,
+            consumer_attribute: var("MTLS_CONSUMER_ATTRIBUTE").unwrap_or_else(|| "CN".to_string()),
+        }))
+    }
+}
+
+/// Verified client certificate of a TLS connection the service terminated,
+/// inserted into the extensions of every request on it
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+    /// Subject distinguished name, e.g. `O=Acme, CN=<consumer id>`
+    pub subject: String,
+}
+
+/// Identifies consumers by their client certificate, for customers who
+/// can't use API keys on internal networks.
+///
+/// The consumer id is read from an attribute of the certificate subject
+/// (`CN` by default), verified by the service itself or by the proxy that
+/// forwards it. Requests carrying an `Authorization` header are still
+/// authenticated with it.
+#[derive(Clone)]
+pub struct ClientCertAuth {
+    forwarded: bool,
+    consumer_attribute: Arc<str>,
+}
+
+impl ClientCertAuth {
+    pub fn new(config: &MtlsConfig) -> Self {
+        let forwarded = config.mode == MtlsMode::Forwarded;
+        info!(
+            forwarded,
+            attribute = %config.consumer_attribute,
+            "Accepting client certificates"
+        );
+        Self {
+            forwarded,
+            consumer_attribute: config.consumer_attribute.as_str().into(),
+        }
+    }
+
+    /// Consumer id from the request's client certificate; `None` without
+    /// one
+    pub fn consumer_id<B>(&self, request: &HttpRequest<B>) -> Option<Result<Uuid>> {
+        let subject = if self.forwarded {
+            forwarded_subject(request.headers())?
+        } else {
+            request
+                .extensions()
+                .get::<ClientCertificate>()?
+                .subject
+                .clone()
+        };
+
+        Some(
+            subject_attribute(&subject, &self.consumer_attribute)
+                .and_then(|id| Uuid::parse_str(&id).ok())
+                .with_context(|| {
+                    format!(
+                        "Certificate subject attribute {} is not a consumer id: {}",
+                        self.consumer_attribute, subject
+                    )
+                }),
+        )
+    }
+}
+
+/// Serves the app over TLS, asking clients for a certificate. Clients
+/// without one can still authenticate with an API key.
+#[derive(Clone)]
+pub struct TlsServer {
+    acceptor: TlsAcceptor,
+}
+
+impl TlsServer {
+    pub fn new(cert: &Path, key: &Path, client_ca: &Path) -> Result<Self> {
+        let provider = Arc::new(ring::default_provider());
+
+        let mut roots = RootCertStore::empty();
+        for ca in load_certs(client_ca)? {
+            roots.add(ca).context("Invalid client CA certificate")?;
+        }
+        let verifier =
+            WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .allow_unauthenticated()
+                .build()
+                .context("Invalid client CA")?;
+
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .context("Unsupported TLS versions")?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(load_certs(cert)?, load_key(key)?)
+            .context("Invalid TLS certificate or key")?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    /// Accept connections until `shutdown` completes, then wait for open
+    /// connections to finish their requests
+    pub async fn serve(
+        self,
+        listener: TcpListener,
+        app: Router,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> std::io::Result<()> {
+        let graceful = GracefulShutdown::new();
+        tokio::pin!(shutdown);
+
+        loop {
+            let (stream, peer) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!(error = %e, "Failed to accept connection");
+                        continue;
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+
+            let acceptor = self.acceptor.clone();
+            let app = app.clone();
+            let watcher = graceful.watcher();
+            tokio::spawn(async move {
+                let stream = match acceptor.accept(stream).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        debug!(error = %e, peer = %peer, "TLS handshake failed");
+                        return;
+                    }
+                };
+                let certificate = stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|certs| certs.first())
+                    .and_then(client_certificate);
+
+                let service =
+                    hyper::service::service_fn(move |mut request: HttpRequest<Incoming>| {
+                        if let Some(certificate) = &certificate {
+                            request.extensions_mut().insert(certificate.clone());
+                        }
+                        app.clone().oneshot(request)
+                    });
+
+                let builder = Builder::new(TokioExecutor::new());
+                let connection =
+                    builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+                if let Err(e) = watcher.watch(connection).await {
+                    debug!(error = %e, peer = %peer, "Connection closed with error");
+                }
+            });
+        }
+
+        graceful.shutdown().await;
+        Ok(())
+    }
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("Invalid PEM in {}", path.display()))?;
+    if certs.is_empty() {
+        bail!("No certificates in {}", path.display());
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .with_context(|| format!("Invalid PEM in {}", path.display()))?
+        .with_context(|| format!("No private key in {}", path.display()))
+}
+
+fn client_certificate(cert: &CertificateDer<'_>) -> Option<ClientCertificate> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert.as_ref()).ok()?;
+    Some(ClientCertificate {
+        subject: cert.subject().to_string(),
+    })
+}
+
+/// Split on `separator` outside double quotes and backslash escapes
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Subject of the client certificate in `X-Forwarded-Client-Cert`. Proxies
+/// that append to the header put the certificate they verified last.
+fn forwarded_subject(headers: &HeaderMap) -> Option<String> {
+    let header = headers.get(FORWARDED_CLIENT_CERT_HEADER)?.to_str().ok()?;
+    let element = split_unquoted(header, ',').pop()?;
+
+    split_unquoted(element, ';').into_iter().find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case("subject") {
+            return None;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        // Only quotes are escaped for the header; DN escapes stay
+        Some(value.replace("\\\"", "\""))
+    })
+}
+
+/// Value of an attribute (e.g. `CN`) of a distinguished name
+fn subject_attribute(subject: &str, attribute: &str) -> Option<String> {
+    split_unquoted(subject, ',').into_iter().find_map(|rdn| {
+        let (key, value) = rdn.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(attribute)
+            .then(|| unescape(value.trim()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forwarded_subject() {
+        let consumer_id = Uuid::new_v4();
+        let header = format!(
+            "By=spiffe://proxy;Hash=abc;Subject=\"OU=Platform,O=Acme\\, Inc.,CN={}\";URI=",
+            consumer_id
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(FORWARDED_CLIENT_CERT_HEADER, header.parse().unwrap());
+
+        let subject = forwarded_subject(&headers).unwrap();
+        assert_eq!(
+            subject_attribute(&subject, "cn"),
+            Some(consumer_id.to_string())
+        );
+        assert_eq!(
+            subject_attribute(&subject, "O"),
+            Some("Acme, Inc.".to_string())
+        );
+        assert_eq!(subject_attribute(&subject, "UID"), None);
+
+        // The last certificate is the one the nearest proxy verified
+        headers.insert(
+            FORWARDED_CLIENT_CERT_HEADER,
+            "Subject=\"CN=first\",Hash=abc;Subject=\"CN=last\""
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(forwarded_subject(&headers).unwrap(), "CN=last");
+
+        assert_eq!(forwarded_subject(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_consumer_id() {
+        let auth = ClientCertAuth::new(&MtlsConfig {
+            mode: MtlsMode::Forwarded,
+            consumer_attribute: "CN".to_string(),
+        });
+        let consumer_id = Uuid::new_v4();
+
+        let request = HttpRequest::builder()
+            .header(
+                FORWARDED_CLIENT_CERT_HEADER,
+                format!("Subject=\"O=Acme, CN={}\"", consumer_id),
+            )
+            .body(())
+            .unwrap();
+        assert_eq!(auth.consumer_id(&request).unwrap().unwrap(), consumer_id);
+
+        let request = HttpRequest::builder()
+            .header(FORWARDED_CLIENT_CERT_HEADER, "Subject=\"CN=build-agent\"")
+            .body(())
+            .unwrap();
+        assert!(auth.consumer_id(&request).unwrap().is_err());
+
+        // Certificates forwarded by a proxy are ignored when terminating
+        let auth = ClientCertAuth {
+            forwarded: false,
+            ..auth
+        };
+        assert!(auth.consumer_id(&request).is_none());
+    }
+}
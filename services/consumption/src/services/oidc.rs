@@ -0,0 +1,340 @@
+use anyhow::{bail, Context, Result};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::models::{ApiKeyScope, Caller, ServiceTier};
+
+/// Soonest the key set is fetched again for a token signed by an unknown key
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+/// Asymmetric algorithms accepted; the key set only holds public keys
+const ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Identity provider settings, read from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcConfig {
+    /// Expected `iss` of tokens
+    pub issuer: String,
+    /// Expected `aud`; not checked when unset
+    pub audience: Option<String>,
+    /// Key set location; discovered from the issuer when unset
+    pub jwks_url: Option<String>,
+    /// Claim holding the consumer id
+    pub consumer_claim: String,
+    /// Claim holding the tier
+    pub tier_claim: String,
+    /// How long the key set is cached
+    pub jwks_cache_ttl: Duration,
+}
+
+impl OidcConfig {
+    /// Read `OIDC_ISSUER`, `OIDC_AUDIENCE`, `OIDC_JWKS_URL`,
+    /// `OIDC_CONSUMER_CLAIM`, `OIDC_TIER_CLAIM` and `OIDC_JWKS_CACHE_SECS`.
+    /// Bearer tokens are only accepted when `OIDC_ISSUER` is set.
+    pub fn from_env() -> Option<Self> {
+        let issuer = std::env::var("OIDC_ISSUER")
+            .ok()
+            .filter(|v| !v.is_empty())?;
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        Some(Self {
+            issuer,
+            audience: var("OIDC_AUDIENCE"),
+            jwks_url: var("OIDC_JWKS_URL"),
+            consumer_claim: var("OIDC_CONSUMER_CLAIM").unwrap_or_else(|| "sub".to_string()),
+            tier_claim: var("OIDC_TIER_CLAIM").unwrap_or_else(|| "tier".to_string()),
+            jwks_cache_ttl: var("OIDC_JWKS_CACHE_SECS")
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(Duration::from_secs(3600)),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+struct CachedJwks {
+    fetched_at: Instant,
+    keys: JwkSet,
+}
+
+/// Verifies bearer tokens issued by the configured OIDC identity provider,
+/// as an alternative to API keys.
+///
+/// Tokens must be signed by a key from the issuer's key set, which is cached
+/// and fetched again when it expires or a token names a key it doesn't hold
+/// (at most once a minute), so the provider can rotate signing keys.
+#[derive(Clone)]
+pub struct OidcVerifier {
+    config: Arc<OidcConfig>,
+    http: reqwest::Client,
+    jwks: Arc<RwLock<Option<CachedJwks>>>,
+    refresh: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl OidcVerifier {
+    pub fn new(config: OidcConfig) -> Self {
+        info!(issuer = %config.issuer, "Accepting bearer tokens from identity provider");
+        Self {
+            config: Arc::new(config),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+            jwks: Arc::new(RwLock::new(None)),
+            refresh: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Whether a bearer credential is a JWT rather than an API key
+    pub fn is_token(credential: &str) -> bool {
+        !credential.starts_with("llm_mk_") && credential.split('.').count() == 3
+    }
+
+    /// Verify a token's signature, issuer, audience and expiry, and map its
+    /// claims to the caller
+    pub async fn verify(&self, token: &str) -> Result<Caller> {
+        let header = decode_header(token).context("Malformed token header")?;
+        if !ALGORITHMS.contains(&header.alg) {
+            bail!("Token algorithm {:?} is not accepted", header.alg);
+        }
+
+        let key = self.decoding_key(header.kid.as_deref()).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        match &self.config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = decode::<Value>(token, &key, &validation)
+            .context("Invalid token")?
+            .claims;
+
+        caller_from_claims(&self.config, &claims)
+    }
+
+    async fn decoding_key(&self, kid: Option<&str>) -> Result<DecodingKey> {
+        if let Some(key) = self.cached_key(kid) {
+            return key;
+        }
+
+        let _refresh = self.refresh.lock().await;
+        // Another request may have refreshed the key set meanwhile
+        if let Some(key) = self.cached_key(kid) {
+            return key;
+        }
+
+        let keys = self.fetch_jwks().await?;
+        let key = find_key(&keys, kid);
+        *self.jwks.write().unwrap() = Some(CachedJwks {
+            fetched_at: Instant::now(),
+            keys,
+        });
+        key
+    }
+
+    /// The key from the cached key set. `None` when the key set must be
+    /// fetched: it expired, or it doesn't have the key and is old enough to
+    /// be refreshed.
+    fn cached_key(&self, kid: Option<&str>) -> Option<Result<DecodingKey>> {
+        let jwks = self.jwks.read().unwrap();
+        let cached = jwks.as_ref()?;
+        let age = cached.fetched_at.elapsed();
+        if age >= self.config.jwks_cache_ttl {
+            return None;
+        }
+
+        match find_key(&cached.keys, kid) {
+            Ok(key) => Some(Ok(key)),
+            Err(_) if age >= JWKS_MIN_REFRESH => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    async fn fetch_jwks(&self) -> Result<JwkSet> {
+        let jwks_url = match &self.config.jwks_url {
+            Some(url) => url.clone(),
+            None => {
+                let discovery_url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer.trim_end_matches('/')
+                );
+                self.http
+                    .get(&discovery_url)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .context("Failed to fetch OIDC discovery document")?
+                    .json::<Discovery>()
+                    .await
+                    .context("Invalid OIDC discovery document")?
+                    .jwks_uri
+            }
+        };
+
+        let keys = self
+            .http
+            .get(&jwks_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Failed to fetch JWKS")?
+            .json::<JwkSet>()
+            .await
+            .context("Invalid JWKS")?;
+
+        info!(keys = keys.keys.len(), "Fetched identity provider keys");
+        Ok(keys)
+    }
+}
+
+fn find_key(keys: &JwkSet, kid: Option<&str>) -> Result<DecodingKey> {
+    let jwk = match kid {
+        Some(kid) => keys.find(kid),
+        // Tokens without a key id are only accepted from single-key sets
+        None if keys.keys.len() == 1 => keys.keys.first(),
+        None => None,
+    }
+    .with_context(|| format!("No signing key {:?} in JWKS", kid))?;
+
+    DecodingKey::from_jwk(jwk).context("Unusable signing key")
+}
+
+/// Map verified claims to the caller: the consumer id and tier claims, and
+/// the `scope` (space-separated) or `scp` claim. A token is limited to the
+/// marketplace scopes it lists, and has every scope without either claim.
+fn caller_from_claims(config: &OidcConfig, claims: &Value) -> Result<Caller> {
+    let consumer_id = claims
+        .get(&config.consumer_claim)
+        .and_then(Value::as_str)
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .with_context(|| {
+            format!(
+                "Token claim '{}' is not a consumer id",
+                config.consumer_claim
+            )
+        })?;
+
+    let tier = match claims.get(&config.tier_claim) {
+        None | Some(Value::Null) => None,
+        Some(tier) => Some(
+            tier.as_str()
+                .and_then(|tier| ServiceTier::from_name(&tier.to_lowercase()))
+                .with_context(|| format!("Token claim '{}' is not a tier", config.tier_claim))?,
+        ),
+    };
+
+    let granted: Option<Vec<&str>> = match (claims.get("scope"), claims.get("scp")) {
+        (Some(Value::String(scope)), _) => Some(scope.split_whitespace().collect()),
+        (_, Some(Value::Array(scp))) => Some(scp.iter().filter_map(Value::as_str).collect()),
+        (_, Some(Value::String(scp))) => Some(scp.split_whitespace().collect()),
+        _ => None,
+    };
+    let scopes = granted.map(|granted| {
+        ApiKeyScope::ALL
+            .into_iter()
+            .filter(|scope| granted.contains(&scope.as_str()))
+            .collect::<Vec<_>>()
+    });
+
+    if scopes.as_ref().is_some_and(|scopes| scopes.is_empty()) {
+        warn!(consumer_id = %consumer_id, "Token grants no marketplace scopes");
+    }
+
+    Ok(Caller {
+        consumer_id,
+        scopes,
+        tier,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> OidcConfig {
+        OidcConfig {
+            issuer: "https://idp.example.com".to_string(),
+            audience: None,
+            jwks_url: None,
+            consumer_claim: "consumer_id".to_string(),
+            tier_claim: "tier".to_string(),
+            jwks_cache_ttl: Duration::from_secs(3600),
+        }
+    }
+
+    #[test]
+    fn test_is_token() {
+        assert!(OidcVerifier::is_token("eyJhbGciOi.eyJzdWIiOi.c2lnbmF0dXJl"));
+        assert!(!OidcVerifier::is_token("llm_mk_abcdef"));
+        assert!(!OidcVerifier::is_token("not-a-token"));
+    }
+
+    #[test]
+    fn test_caller_from_claims() {
+        let consumer_id = Uuid::new_v4();
+
+        let caller = caller_from_claims(
+            &config(),
+            &serde_json::json!({
+                "sub": "user@example.com",
+                "consumer_id": consumer_id.to_string(),
+                "tier": "Enterprise",
+                "scope": "openid consume read:usage",
+            }),
+        )
+        .unwrap();
+        assert_eq!(caller.consumer_id, consumer_id);
+        assert_eq!(caller.tier, Some(ServiceTier::Enterprise));
+        assert_eq!(
+            caller.scopes,
+            Some(vec![ApiKeyScope::Consume, ApiKeyScope::ReadUsage])
+        );
+
+        let caller = caller_from_claims(
+            &config(),
+            &serde_json::json!({
+                "consumer_id": consumer_id.to_string(),
+                "scp": ["manage:keys"],
+            }),
+        )
+        .unwrap();
+        assert_eq!(caller.tier, None);
+        assert_eq!(caller.scopes, Some(vec![ApiKeyScope::ManageKeys]));
+
+        // Without scope claims a token has every scope
+        let caller = caller_from_claims(
+            &config(),
+            &serde_json::json!({"consumer_id": consumer_id.to_string()}),
+        )
+        .unwrap();
+        assert!(caller.has_scope(ApiKeyScope::ManagePrivacy));
+
+        assert!(caller_from_claims(&config(), &serde_json::json!({"sub": "user"})).is_err());
+        assert!(caller_from_claims(
+            &config(),
+            &serde_json::json!({"consumer_id": consumer_id.to_string(), "tier": "gold"}),
+        )
+        .is_err());
+    }
+}
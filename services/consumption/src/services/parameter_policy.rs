@@ -0,0 +1,126 @@
+use crate::models::{ConsumeRequest, ParameterEnforcement, ParameterPolicy};
+
+/// A request parameter outside its tier's policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterViolation {
+    pub parameter: &'static str,
+    pub message: String,
+}
+
+/// Apply a tier's parameter policy to a request at admission
+///
+/// Fills in the tier's default `max_tokens`, then either rejects the first
+/// parameter out of range or clamps every one, depending on the policy's
+/// enforcement. Returns the clamped parameters.
+pub fn apply_parameter_policy(
+    policy: &ParameterPolicy,
+    tier: &str,
+    request: &mut ConsumeRequest,
+) -> Result<Vec<&'static str>, ParameterViolation> {
+    let clamp = policy.enforcement == ParameterEnforcement::Clamp;
+    let mut clamped = Vec::new();
+    let mut check = |parameter: &'static str, allowed: bool, message: String| {
+        if allowed {
+            Ok(false)
+        } else if clamp {
+            clamped.push(parameter);
+            Ok(true)
+        } else {
+            Err(ParameterViolation { parameter, message })
+        }
+    };
+
+    let max_tokens = *request
+        .max_tokens
+        .get_or_insert(policy.default_max_tokens.min(policy.max_tokens));
+    if check(
+        "max_tokens",
+        max_tokens <= policy.max_tokens,
+        format!(
+            "max_tokens {} exceeds the {} tier limit of {}",
+            max_tokens, tier, policy.max_tokens
+        ),
+    )? {
+        request.max_tokens = Some(policy.max_tokens);
+    }
+
+    let temperature = request.temperature;
+    if check(
+        "temperature",
+        (policy.min_temperature..=policy.max_temperature).contains(&temperature),
+        format!(
+            "temperature {} is outside the {} tier range of {} to {}",
+            temperature, tier, policy.min_temperature, policy.max_temperature
+        ),
+    )? {
+        // NaN clamps to the bottom of the range
+        request.temperature = if temperature > policy.max_temperature {
+            policy.max_temperature
+        } else {
+            policy.min_temperature
+        };
+    }
+
+    if let Some(n) = request.n {
+        if check(
+            "n",
+            n <= policy.max_batch_size,
+            format!(
+                "n {} exceeds the {} tier batch size limit of {}",
+                n, tier, policy.max_batch_size
+            ),
+        )? {
+            request.n = Some(policy.max_batch_size);
+        }
+    }
+
+    Ok(clamped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ServiceTier;
+
+    fn request(max_tokens: Option<u32>, temperature: f32, n: Option<u32>) -> ConsumeRequest {
+        ConsumeRequest {
+            prompt: "hello".to_string(),
+            max_tokens,
+            temperature,
+            n,
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_basic_tier_rejects_or_clamps_enterprise_scale_requests() {
+        let mut policy = ServiceTier::Basic.default_parameters();
+
+        let mut defaulted = request(None, 0.7, None);
+        assert_eq!(
+            apply_parameter_policy(&policy, "basic", &mut defaulted),
+            Ok(vec![])
+        );
+        assert_eq!(defaulted.max_tokens, Some(256));
+
+        let mut huge = request(Some(32_000), 1.8, Some(8));
+        let violation = apply_parameter_policy(&policy, "basic", &mut huge).unwrap_err();
+        assert_eq!(violation.parameter, "max_tokens");
+        assert_eq!(
+            violation.message,
+            "max_tokens 32000 exceeds the basic tier limit of 1024"
+        );
+        assert_eq!(huge.max_tokens, Some(32_000));
+
+        policy.enforcement = ParameterEnforcement::Clamp;
+        let clamped = apply_parameter_policy(&policy, "basic", &mut huge).unwrap();
+        assert_eq!(clamped, vec!["max_tokens", "temperature", "n"]);
+        assert_eq!(huge.max_tokens, Some(1024));
+        assert_eq!(huge.temperature, 1.0);
+        assert_eq!(huge.n, Some(1));
+
+        let mut nan = request(Some(10), f32::NAN, None);
+        apply_parameter_policy(&policy, "basic", &mut nan).unwrap();
+        assert_eq!(nan.temperature, 0.0);
+    }
+}
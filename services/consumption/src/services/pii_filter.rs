@@ -0,0 +1,367 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::info;
+use uuid::Uuid;
+
+/// How long a service's filter config is cached before being reloaded
+const CONFIG_CACHE_TTL: Duration = Duration::from_secs(10);
+
+lazy_static::lazy_static! {
+    static ref EMAIL: Regex =
+        Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9](?:[a-z0-9-]*[a-z0-9])?(?:\.[a-z0-9](?:[a-z0-9-]*[a-z0-9])?)*\.[a-z]{2,}\b")
+            .expect("valid email pattern");
+
+    static ref SSN: Regex =
+        Regex::new(r"\b(\d{3})[- ]?(\d{2})[- ]?(\d{4})\b").expect("valid SSN pattern");
+
+    static ref CREDIT_CARD: Regex =
+        Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("valid credit card pattern");
+}
+
+/// Kind of personal data the built-in detector recognizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiCategory {
+    Email,
+    Ssn,
+    CreditCard,
+}
+
+impl PiiCategory {
+    pub const ALL: [PiiCategory; 3] = [PiiCategory::Email, PiiCategory::Ssn, PiiCategory::CreditCard];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PiiCategory::Email => "email",
+            PiiCategory::Ssn => "ssn",
+            PiiCategory::CreditCard => "credit_card",
+        }
+    }
+
+    fn placeholder(&self) -> &'static str {
+        match self {
+            PiiCategory::Email => "[REDACTED_EMAIL]",
+            PiiCategory::Ssn => "[REDACTED_SSN]",
+            PiiCategory::CreditCard => "[REDACTED_CREDIT_CARD]",
+        }
+    }
+}
+
+/// What happens to a prompt containing PII
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PiiMode {
+    /// No scanning
+    #[default]
+    Off,
+    /// Replace detections with a placeholder before routing
+    Redact,
+    /// Reject the request
+    Block,
+}
+
+impl PiiMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PiiMode::Off => "off",
+            PiiMode::Redact => "redact",
+            PiiMode::Block => "block",
+        }
+    }
+}
+
+/// Per-service PII filter configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PiiFilterConfig {
+    #[serde(default)]
+    pub mode: PiiMode,
+    /// Categories to scan for; all when omitted
+    #[serde(default = "all_categories")]
+    pub categories: Vec<PiiCategory>,
+}
+
+fn all_categories() -> Vec<PiiCategory> {
+    PiiCategory::ALL.to_vec()
+}
+
+impl Default for PiiFilterConfig {
+    fn default() -> Self {
+        Self {
+            mode: PiiMode::Off,
+            categories: all_categories(),
+        }
+    }
+}
+
+/// Result of scanning a prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiScan {
+    pub mode: PiiMode,
+    /// Detections per category; never the matched text itself
+    pub counts: BTreeMap<PiiCategory, usize>,
+    /// Prompt with detections replaced, in redact mode
+    pub redacted: Option<String>,
+}
+
+impl PiiScan {
+    pub fn detected(&self) -> bool {
+        !self.counts.is_empty()
+    }
+
+    pub fn blocked(&self) -> bool {
+        self.mode == PiiMode::Block && self.detected()
+    }
+}
+
+/// Built-in PII pre-filter for deployments without LLM-Shield: regex
+/// detection with checksum validation, applied per service configuration
+#[derive(Clone)]
+pub struct PiiFilter {
+    db: Arc<PgPool>,
+    configs: Arc<RwLock<HashMap<Uuid, (Instant, PiiFilterConfig)>>>,
+}
+
+impl PiiFilter {
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db: Arc::new(db),
+            configs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Scan a prompt according to the service's configuration; `None` when
+    /// the filter is off for the service
+    pub async fn scan(&self, service_id: Uuid, prompt: &str) -> Result<Option<PiiScan>> {
+        let config = self.cached_config(service_id).await?;
+        if config.mode == PiiMode::Off {
+            return Ok(None);
+        }
+
+        Ok(Some(scan_text(prompt, &config)))
+    }
+
+    /// Filter configuration for a service (off when never configured)
+    pub async fn get_config(&self, service_id: Uuid) -> Result<PiiFilterConfig> {
+        let config: Option<sqlx::types::Json<PiiFilterConfig>> = sqlx::query_scalar(
+            r#"
+            SELECT config
+            FROM pii_filters
+            WHERE service_id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load PII filter config")?;
+
+        Ok(config.map(|config| config.0).unwrap_or_default())
+    }
+
+    /// Store a service's filter configuration; takes effect on all replicas
+    /// within the cache TTL
+    pub async fn set_config(&self, service_id: Uuid, config: &PiiFilterConfig) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO pii_filters (service_id, config, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (service_id)
+            DO UPDATE SET config = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(service_id)
+        .bind(sqlx::types::Json(config))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store PII filter config")?;
+
+        self.configs.write().unwrap().remove(&service_id);
+
+        info!(service_id = %service_id, mode = config.mode.as_str(), "PII filter updated");
+
+        Ok(())
+    }
+
+    async fn cached_config(&self, service_id: Uuid) -> Result<PiiFilterConfig> {
+        if let Some((loaded_at, config)) = self.configs.read().unwrap().get(&service_id) {
+            if loaded_at.elapsed() < CONFIG_CACHE_TTL {
+                return Ok(config.clone());
+            }
+        }
+
+        let config = self.get_config(service_id).await?;
+        self.configs
+            .write()
+            .unwrap()
+            .insert(service_id, (Instant::now(), config.clone()));
+
+        Ok(config)
+    }
+}
+
+/// Find PII of the given categories, as non-overlapping byte ranges in order.
+/// Card numbers are matched first so their digit groups aren't taken for SSNs.
+fn detect(text: &str, categories: &[PiiCategory]) -> Vec<(PiiCategory, Range<usize>)> {
+    let mut found: Vec<(PiiCategory, Range<usize>)> = Vec::new();
+
+    let precedence = [PiiCategory::CreditCard, PiiCategory::Ssn, PiiCategory::Email];
+    for category in precedence.iter().filter(|c| categories.contains(c)) {
+        let matches: Vec<Range<usize>> = match category {
+            PiiCategory::Email => EMAIL.find_iter(text).map(|m| m.range()).collect(),
+            PiiCategory::Ssn => SSN
+                .captures_iter(text)
+                .filter(|c| valid_ssn(&c[1], &c[2], &c[3]))
+                .filter_map(|c| c.get(0).map(|m| m.range()))
+                .collect(),
+            PiiCategory::CreditCard => CREDIT_CARD
+                .find_iter(text)
+                .filter(|m| luhn_valid(m.as_str()))
+                .map(|m| m.range())
+                .collect(),
+        };
+
+        for range in matches {
+            let overlaps = found
+                .iter()
+                .any(|(_, other)| range.start < other.end && other.start < range.end);
+            if !overlaps {
+                found.push((*category, range));
+            }
+        }
+    }
+
+    found.sort_by_key(|(_, range)| range.start);
+    found
+}
+
+fn scan_text(text: &str, config: &PiiFilterConfig) -> PiiScan {
+    let found = detect(text, &config.categories);
+
+    let mut counts = BTreeMap::new();
+    for (category, _) in &found {
+        *counts.entry(*category).or_insert(0) += 1;
+    }
+
+    let redacted = (config.mode == PiiMode::Redact && !found.is_empty()).then(|| {
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for (category, range) in &found {
+            redacted.push_str(&text[last..range.start]);
+            redacted.push_str(category.placeholder());
+            last = range.end;
+        }
+        redacted.push_str(&text[last..]);
+        redacted
+    });
+
+    PiiScan {
+        mode: config.mode,
+        counts,
+        redacted,
+    }
+}
+
+/// Text with every detected category replaced by its placeholder,
+/// regardless of any service configuration
+pub fn redact_pii(text: &str) -> String {
+    let config = PiiFilterConfig {
+        mode: PiiMode::Redact,
+        categories: all_categories(),
+    };
+    scan_text(text, &config)
+        .redacted
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// SSA rules: no 000, 666 or 9xx area, no 00 group, no 0000 serial
+fn valid_ssn(area: &str, group: &str, serial: &str) -> bool {
+    area != "000" && area != "666" && !area.starts_with('9') && group != "00" && serial != "0000"
+}
+
+/// Luhn checksum over the digits of a candidate card number
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+
+    sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(mode: PiiMode) -> PiiFilterConfig {
+        PiiFilterConfig {
+            mode,
+            categories: all_categories(),
+        }
+    }
+
+    #[test]
+    fn test_redacts_validated_matches_only() {
+        let prompt = "Mail jane.doe@example.com, SSN 123-45-6789, card 4111 1111 1111 1111. \
+                      Order 4111 1111 1111 1112 and SSN 000-12-3456 are not PII.";
+
+        let scan = scan_text(prompt, &config(PiiMode::Redact));
+
+        assert_eq!(scan.counts.get(&PiiCategory::Email), Some(&1));
+        assert_eq!(scan.counts.get(&PiiCategory::Ssn), Some(&1));
+        assert_eq!(scan.counts.get(&PiiCategory::CreditCard), Some(&1));
+        assert_eq!(
+            scan.redacted.as_deref(),
+            Some(
+                "Mail [REDACTED_EMAIL], SSN [REDACTED_SSN], card [REDACTED_CREDIT_CARD]. \
+                 Order 4111 1111 1111 1112 and SSN 000-12-3456 are not PII."
+            )
+        );
+        assert!(!scan.blocked());
+    }
+
+    #[test]
+    fn test_block_mode_and_category_selection() {
+        let prompt = "Reach me at ops@example.org";
+
+        let scan = scan_text(prompt, &config(PiiMode::Block));
+        assert!(scan.blocked());
+        assert_eq!(scan.redacted, None);
+
+        let cards_only = PiiFilterConfig {
+            mode: PiiMode::Block,
+            categories: vec![PiiCategory::CreditCard],
+        };
+        assert!(!scan_text(prompt, &cards_only).detected());
+    }
+
+    #[test]
+    fn test_luhn() {
+        assert!(luhn_valid("4111-1111-1111-1111"));
+        assert!(luhn_valid("378282246310005"));
+        assert!(!luhn_valid("1234567812345678"));
+        assert!(!luhn_valid("4111"));
+    }
+}
@@ -0,0 +1,596 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::middleware::metrics::record;
+
+/// Default instruction budget per plugin invocation
+const DEFAULT_FUEL: u64 = 10_000_000;
+
+/// Default linear memory limit per plugin instance
+const DEFAULT_MEMORY_MB: u64 = 16;
+
+/// How long a service's plugin pins are cached before re-reading them
+const PIN_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Host module name for plugin imports
+const HOST_MODULE: &str = "marketplace";
+
+/// What a plugin does, and the export it must provide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    /// Rewrites the consume request before it is routed
+    RequestTransform,
+    /// Rewrites the provider response before it is returned
+    ResponseTransform,
+    /// Allows or denies the consume request
+    Policy,
+}
+
+impl PluginKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluginKind::RequestTransform => "request_transform",
+            PluginKind::ResponseTransform => "response_transform",
+            PluginKind::Policy => "policy",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "request_transform" => Some(PluginKind::RequestTransform),
+            "response_transform" => Some(PluginKind::ResponseTransform),
+            "policy" => Some(PluginKind::Policy),
+            _ => None,
+        }
+    }
+
+    fn export_name(&self) -> &'static str {
+        match self {
+            PluginKind::RequestTransform => "transform_request",
+            PluginKind::ResponseTransform => "transform_response",
+            PluginKind::Policy => "evaluate",
+        }
+    }
+}
+
+/// Host functions a plugin may import; anything not granted fails to link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginCapability {
+    /// `marketplace.log(ptr, len)` - write a UTF-8 message to the service log
+    Log,
+    /// `marketplace.now_ms() -> i64` - wall clock in milliseconds
+    Clock,
+}
+
+/// Exact plugin version pinned in a service's metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginPin {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub capabilities: Vec<PluginCapability>,
+    /// Instruction budget; capped at the runtime maximum
+    #[serde(default)]
+    pub fuel: Option<u64>,
+    /// Memory limit; capped at the runtime maximum
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+}
+
+/// Decision returned by a policy plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    pub allow: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Result of running a service's request-phase plugins
+#[derive(Debug)]
+pub enum RequestPluginOutcome {
+    /// Request (possibly transformed) may proceed
+    Allowed(Value),
+    /// A policy plugin rejected the request
+    Denied { plugin: String, reason: String },
+}
+
+/// Resource limits applied to a single invocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PluginLimits {
+    fuel: u64,
+    memory_bytes: usize,
+}
+
+/// Per-invocation store data
+struct PluginState {
+    plugin: String,
+    limits: StoreLimits,
+}
+
+/// Embedded WebAssembly host for per-service transform and policy plugins.
+///
+/// Plugins export `memory`, `alloc(len) -> ptr` and one entry point per kind
+/// taking `(ptr, len)` of a JSON document and returning `(ptr << 32) | len`
+/// of the JSON result. Each invocation gets a fresh instance with fuel and
+/// memory limits and only the host functions its pin grants.
+#[derive(Clone)]
+pub struct PluginRuntime {
+    db: Arc<PgPool>,
+    engine: Engine,
+    modules: Arc<RwLock<HashMap<(String, String), (PluginKind, Module)>>>,
+    pins: Arc<RwLock<HashMap<Uuid, (Instant, Vec<PluginPin>)>>>,
+    max_limits: PluginLimits,
+}
+
+impl PluginRuntime {
+    pub fn new(db: PgPool) -> Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).context("Failed to create Wasm engine")?;
+
+        let fuel = std::env::var("PLUGIN_MAX_FUEL")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_FUEL);
+        let memory_mb = std::env::var("PLUGIN_MAX_MEMORY_MB")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_MEMORY_MB);
+
+        Ok(Self {
+            db: Arc::new(db),
+            engine,
+            modules: Arc::new(RwLock::new(HashMap::new())),
+            pins: Arc::new(RwLock::new(HashMap::new())),
+            max_limits: PluginLimits {
+                fuel,
+                memory_bytes: megabytes(memory_mb),
+            },
+        })
+    }
+
+    /// Validate and store a new plugin version; published versions are immutable
+    pub async fn publish_plugin(
+        &self,
+        name: &str,
+        version: &str,
+        kind: PluginKind,
+        wasm: &[u8],
+    ) -> Result<()> {
+        let module = self.compile(kind, wasm)?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO plugins (name, version, kind, module)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (name, version) DO NOTHING
+            "#,
+        )
+        .bind(name)
+        .bind(version)
+        .bind(kind.as_str())
+        .bind(wasm)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store plugin")?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Plugin {}@{} is already published", name, version);
+        }
+
+        self.modules
+            .write()
+            .unwrap()
+            .insert((name.to_string(), version.to_string()), (kind, module));
+
+        info!(plugin = %name, version = %version, kind = kind.as_str(), "Plugin published");
+
+        Ok(())
+    }
+
+    /// Replace the plugins pinned for a service; every pin must be published
+    pub async fn set_service_plugins(&self, service_id: Uuid, pins: Vec<PluginPin>) -> Result<()> {
+        for pin in &pins {
+            self.load_module(&pin.name, &pin.version).await?;
+        }
+
+        let result = sqlx::query("UPDATE services SET plugins = $2 WHERE id = $1")
+            .bind(service_id)
+            .bind(sqlx::types::Json(&pins))
+            .execute(self.db.as_ref())
+            .await
+            .context("Failed to update service plugins")?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Service {} not found", service_id);
+        }
+
+        self.pins.write().unwrap().remove(&service_id);
+
+        info!(service_id = %service_id, plugins = pins.len(), "Service plugins updated");
+
+        Ok(())
+    }
+
+    /// Plugins pinned for a service, cached for a short TTL
+    pub async fn service_plugins(&self, service_id: Uuid) -> Result<Vec<PluginPin>> {
+        if let Some((loaded_at, pins)) = self.pins.read().unwrap().get(&service_id) {
+            if loaded_at.elapsed() < PIN_CACHE_TTL {
+                return Ok(pins.clone());
+            }
+        }
+
+        let pins: Option<sqlx::types::Json<Vec<PluginPin>>> =
+            sqlx::query_scalar("SELECT plugins FROM services WHERE id = $1")
+                .bind(service_id)
+                .fetch_optional(self.db.as_ref())
+                .await
+                .context("Failed to load service plugins")?;
+
+        let pins = pins.map(|p| p.0).unwrap_or_default();
+        self.pins
+            .write()
+            .unwrap()
+            .insert(service_id, (Instant::now(), pins.clone()));
+
+        Ok(pins)
+    }
+
+    /// Run request transforms and policies, in pin order
+    pub async fn run_request_plugins(
+        &self,
+        service_id: Uuid,
+        mut request: Value,
+    ) -> Result<RequestPluginOutcome> {
+        for pin in self.service_plugins(service_id).await? {
+            let (kind, module) = self.load_module(&pin.name, &pin.version).await?;
+
+            match kind {
+                PluginKind::RequestTransform => {
+                    request = self.invoke(&pin, kind, module, &request).await?;
+                }
+                PluginKind::Policy => {
+                    let decision: PolicyDecision =
+                        serde_json::from_value(self.invoke(&pin, kind, module, &request).await?)
+                            .with_context(|| {
+                                format!("Plugin {} returned an invalid decision", pin.name)
+                            })?;
+
+                    if !decision.allow {
+                        record::plugin_invocation(&pin.name, "denied");
+                        return Ok(RequestPluginOutcome::Denied {
+                            plugin: pin.name,
+                            reason: decision
+                                .reason
+                                .unwrap_or_else(|| "Denied by policy".to_string()),
+                        });
+                    }
+                }
+                PluginKind::ResponseTransform => {}
+            }
+        }
+
+        Ok(RequestPluginOutcome::Allowed(request))
+    }
+
+    /// Run response transforms, in pin order
+    pub async fn run_response_plugins(
+        &self,
+        service_id: Uuid,
+        mut response: Value,
+    ) -> Result<Value> {
+        for pin in self.service_plugins(service_id).await? {
+            let (kind, module) = self.load_module(&pin.name, &pin.version).await?;
+
+            if kind == PluginKind::ResponseTransform {
+                response = self.invoke(&pin, kind, module, &response).await?;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Whether the service has response transforms pinned
+    pub async fn transforms_responses(&self, service_id: Uuid) -> Result<bool> {
+        for pin in self.service_plugins(service_id).await? {
+            let (kind, _) = self.load_module(&pin.name, &pin.version).await?;
+            if kind == PluginKind::ResponseTransform {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn compile(&self, kind: PluginKind, wasm: &[u8]) -> Result<Module> {
+        let module = Module::new(&self.engine, wasm).context("Invalid Wasm module")?;
+
+        for export in ["memory", "alloc", kind.export_name()] {
+            if module.get_export(export).is_none() {
+                anyhow::bail!("Plugin is missing required export `{}`", export);
+            }
+        }
+
+        Ok(module)
+    }
+
+    async fn load_module(&self, name: &str, version: &str) -> Result<(PluginKind, Module)> {
+        let key = (name.to_string(), version.to_string());
+        if let Some(cached) = self.modules.read().unwrap().get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let (kind, wasm): (String, Vec<u8>) =
+            sqlx::query_as("SELECT kind, module FROM plugins WHERE name = $1 AND version = $2")
+                .bind(name)
+                .bind(version)
+                .fetch_optional(self.db.as_ref())
+                .await
+                .context("Failed to load plugin")?
+                .with_context(|| format!("Plugin {}@{} is not published", name, version))?;
+
+        let kind =
+            PluginKind::parse(&kind).with_context(|| format!("Unknown plugin kind {}", kind))?;
+        let module = self.compile(kind, &wasm)?;
+
+        self.modules
+            .write()
+            .unwrap()
+            .insert(key, (kind, module.clone()));
+
+        Ok((kind, module))
+    }
+
+    fn limits_for(&self, pin: &PluginPin) -> PluginLimits {
+        PluginLimits {
+            fuel: pin
+                .fuel
+                .unwrap_or(self.max_limits.fuel)
+                .min(self.max_limits.fuel),
+            memory_bytes: pin
+                .memory_mb
+                .map(megabytes)
+                .unwrap_or(self.max_limits.memory_bytes)
+                .min(self.max_limits.memory_bytes),
+        }
+    }
+
+    async fn invoke(
+        &self,
+        pin: &PluginPin,
+        kind: PluginKind,
+        module: Module,
+        input: &Value,
+    ) -> Result<Value> {
+        let engine = self.engine.clone();
+        let limits = self.limits_for(pin);
+        let capabilities = pin.capabilities.clone();
+        let plugin = format!("{}@{}", pin.name, pin.version);
+        let input = serde_json::to_vec(input)?;
+
+        // Wasm execution is synchronous and bounded by fuel
+        let result = tokio::task::spawn_blocking({
+            let plugin = plugin.clone();
+            move || {
+                invoke_blocking(
+                    &engine,
+                    &module,
+                    kind,
+                    &plugin,
+                    &capabilities,
+                    limits,
+                    &input,
+                )
+            }
+        })
+        .await
+        .context("Plugin task panicked")?;
+
+        match result {
+            Ok(output) => {
+                record::plugin_invocation(&pin.name, "ok");
+                serde_json::from_slice(&output)
+                    .with_context(|| format!("Plugin {} returned invalid JSON", plugin))
+            }
+            Err(e) => {
+                record::plugin_invocation(&pin.name, "error");
+                warn!(plugin = %plugin, error = %e, "Plugin invocation failed");
+                Err(e.context(format!("Plugin {} failed", plugin)))
+            }
+        }
+    }
+}
+
+/// Bytes in `mb` megabytes, saturating rather than overflowing on limits
+/// too large to address
+fn megabytes(mb: u64) -> usize {
+    usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX)
+}
+
+fn invoke_blocking(
+    engine: &Engine,
+    module: &Module,
+    kind: PluginKind,
+    plugin: &str,
+    capabilities: &[PluginCapability],
+    limits: PluginLimits,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let mut store = Store::new(
+        engine,
+        PluginState {
+            plugin: plugin.to_string(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(limits.memory_bytes)
+                .instances(1)
+                .memories(1)
+                .tables(1)
+                .build(),
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(limits.fuel)?;
+
+    let linker = linker_for(engine, capabilities)?;
+    let instance = linker
+        .instantiate(&mut store, module)
+        .context("Plugin failed to instantiate (missing capability?)")?;
+
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .context("Plugin does not export memory")?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+    let entry = instance.get_typed_func::<(i32, i32), i64>(&mut store, kind.export_name())?;
+
+    let len = i32::try_from(input.len()).context("Plugin input too large")?;
+    let ptr = alloc.call(&mut store, len)?;
+    memory.write(&mut store, ptr as usize, input)?;
+
+    let packed = entry.call(&mut store, (ptr, len))?;
+    let (out_ptr, out_len) = ((packed >> 32) as u32 as usize, packed as u32 as usize);
+    if out_len > limits.memory_bytes {
+        anyhow::bail!("Plugin output exceeds memory limit");
+    }
+
+    let mut output = vec![0; out_len];
+    memory.read(&store, out_ptr, &mut output)?;
+
+    debug!(
+        plugin = %plugin,
+        fuel_used = limits.fuel.saturating_sub(store.get_fuel().unwrap_or(0)),
+        "Plugin invocation completed"
+    );
+
+    Ok(output)
+}
+
+/// Linker exposing only the host functions granted to a plugin
+fn linker_for(engine: &Engine, capabilities: &[PluginCapability]) -> Result<Linker<PluginState>> {
+    let mut linker = Linker::new(engine);
+
+    for capability in capabilities {
+        match capability {
+            PluginCapability::Log => {
+                linker.func_wrap(
+                    HOST_MODULE,
+                    "log",
+                    |mut caller: Caller<'_, PluginState>, ptr: i32, len: i32| -> Result<()> {
+                        let memory = caller
+                            .get_export("memory")
+                            .and_then(|export| export.into_memory())
+                            .context("Plugin does not export memory")?;
+
+                        let mut message = vec![0; len.clamp(0, 4096) as usize];
+                        memory.read(&caller, ptr as usize, &mut message)?;
+
+                        info!(
+                            plugin = %caller.data().plugin,
+                            message = %String::from_utf8_lossy(&message),
+                            "Plugin log"
+                        );
+                        Ok(())
+                    },
+                )?;
+            }
+            PluginCapability::Clock => {
+                linker.func_wrap(HOST_MODULE, "now_ms", || {
+                    chrono::Utc::now().timestamp_millis()
+                })?;
+            }
+        }
+    }
+
+    Ok(linker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECHO_POLICY: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (data (i32.const 0) "{\22allow\22:false,\22reason\22:\22blocked\22}")
+          (func (export "evaluate") (param i32 i32) (result i64) (i64.const 34)))
+    "#;
+
+    const SPIN_POLICY: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 0))
+          (func (export "evaluate") (param i32 i32) (result i64)
+            (loop $spin (br $spin))
+            (i64.const 0)))
+    "#;
+
+    const LOGGING_POLICY: &str = r#"
+        (module
+          (import "marketplace" "log" (func $log (param i32 i32)))
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 0))
+          (func (export "evaluate") (param i32 i32) (result i64) (i64.const 0)))
+    "#;
+
+    fn engine() -> Engine {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        Engine::new(&config).unwrap()
+    }
+
+    fn limits() -> PluginLimits {
+        PluginLimits {
+            fuel: 100_000,
+            memory_bytes: 1024 * 1024,
+        }
+    }
+
+    fn run(engine: &Engine, wat: &str, capabilities: &[PluginCapability]) -> Result<Vec<u8>> {
+        let module = Module::new(engine, wat).unwrap();
+        invoke_blocking(
+            engine,
+            &module,
+            PluginKind::Policy,
+            "test@1.0.0",
+            capabilities,
+            limits(),
+            b"{}",
+        )
+    }
+
+    #[test]
+    fn test_policy_plugin_output() {
+        let output = run(&engine(), ECHO_POLICY, &[]).unwrap();
+        let decision: PolicyDecision = serde_json::from_slice(&output).unwrap();
+        assert!(!decision.allow);
+        assert_eq!(decision.reason.as_deref(), Some("blocked"));
+    }
+
+    #[test]
+    fn test_fuel_limit_stops_runaway_plugin() {
+        assert!(run(&engine(), SPIN_POLICY, &[]).is_err());
+    }
+
+    #[test]
+    fn test_megabytes_saturate() {
+        assert_eq!(megabytes(64), 64 * 1024 * 1024);
+        assert_eq!(megabytes(u64::MAX), usize::MAX);
+    }
+
+    #[test]
+    fn test_ungranted_capability_fails_to_link() {
+        let engine = engine();
+        assert!(run(&engine, LOGGING_POLICY, &[]).is_err());
+        assert!(run(&engine, LOGGING_POLICY, &[PluginCapability::Log]).is_ok());
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use llm_infra::config::FailurePolicy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -14,6 +15,7 @@ use crate::models::{ConsumeRequest, Service};
 pub struct PolicyClient {
     client: Arc<Client>,
     policy_engine_url: String,
+    failure_policy: FailurePolicy,
 }
 
 #[derive(Debug, Serialize)]
@@ -67,6 +69,31 @@ impl PolicyClient {
         Self {
             client: Arc::new(client),
             policy_engine_url,
+            failure_policy: FailurePolicy::Open,
+        }
+    }
+
+    /// Allow or deny requests while the Policy Engine is unavailable
+    /// (allowed by default)
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Validation result while the Policy Engine can't be reached
+    fn unavailable_response(&self) -> PolicyValidationResponse {
+        let (allowed, mode) = if self.failure_policy.allows() {
+            (true, "fail-open")
+        } else {
+            (false, "fail-closed")
+        };
+        warn!(mode, "Policy Engine unavailable");
+
+        PolicyValidationResponse {
+            allowed,
+            reason: Some(format!("Policy Engine unavailable - {}", mode)),
+            violations: vec![],
+            metadata: serde_json::json!({"failover": true}),
         }
     }
 
@@ -103,13 +130,19 @@ impl PolicyClient {
             "Validating consumption with Policy Engine"
         );
 
-        let response = self
+        let response = match self
             .client
             .post(&format!("{}/api/v1/validate/consumption", self.policy_engine_url))
             .json(&validation_request)
             .send()
             .await
-            .context("Failed to send request to Policy Engine")?;
+        {
+            Ok(response) => response,
+            Err(e) => {
+                error!(error = %e, "Failed to send request to Policy Engine");
+                return Ok(self.unavailable_response());
+            }
+        };
 
         let status = response.status();
         let latency = start.elapsed();
@@ -120,16 +153,7 @@ impl PolicyClient {
                 latency_ms = latency.as_millis(),
                 "Policy Engine returned error"
             );
-
-            // Fail-open in case of Policy Engine unavailability
-            // In production, configure fail-closed for stricter security
-            warn!("Policy Engine unavailable, failing open");
-            return Ok(PolicyValidationResponse {
-                allowed: true,
-                reason: Some("Policy Engine unavailable - fail-open".to_string()),
-                violations: vec![],
-                metadata: serde_json::json!({"failover": true}),
-            });
+            return Ok(self.unavailable_response());
         }
 
         let validation_response: PolicyValidationResponse = response
@@ -172,13 +196,19 @@ impl PolicyClient {
             ])
             .send()
             .await
-            .context("Failed to check access")?;
-
-        if !response.status().is_success() {
-            // Fail-open for access checks
-            warn!("Policy Engine access check failed, failing open");
-            return Ok(true);
-        }
+            .and_then(|response| response.error_for_status());
+
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    allowed = self.failure_policy.allows(),
+                    "Access check failed, applying failure policy"
+                );
+                return Ok(self.failure_policy.allows());
+            }
+        };
 
         #[derive(Deserialize)]
         struct AccessResponse {
@@ -210,12 +240,19 @@ impl PolicyClient {
             }))
             .send()
             .await
-            .context("Failed to check data residency")?;
-
-        if !response.status().is_success() {
-            warn!("Data residency check failed, failing open");
-            return Ok(true);
-        }
+            .and_then(|response| response.error_for_status());
+
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    allowed = self.failure_policy.allows(),
+                    "Data residency check failed, applying failure policy"
+                );
+                return Ok(self.failure_policy.allows());
+            }
+        };
 
         #[derive(Deserialize)]
         struct ResidencyResponse {
@@ -323,4 +360,18 @@ mod tests {
 
         assert_eq!(violation.severity, "high");
     }
+
+    #[tokio::test]
+    async fn test_unavailable_response_follows_failure_policy() {
+        let client = PolicyClient::new("http://localhost:8080".to_string());
+        assert!(client.unavailable_response().allowed);
+
+        let client = client.with_failure_policy(FailurePolicy::Closed);
+        let response = client.unavailable_response();
+        assert!(!response.allowed);
+        assert_eq!(
+            response.reason.as_deref(),
+            Some("Policy Engine unavailable - fail-closed")
+        );
+    }
 }
@@ -11,6 +11,7 @@
 //! and compliance rule retrieval.
 
 use anyhow::{Context, Result};
+use llm_infra::config::FailurePolicy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -24,6 +25,7 @@ use uuid::Uuid;
 pub struct PolicyEngineClient {
     client: Arc<Client>,
     policy_engine_url: String,
+    failure_policy: FailurePolicy,
 }
 
 /// Policy bundle consumed from LLM-Policy-Engine
@@ -236,9 +238,17 @@ impl PolicyEngineClient {
         Self {
             client: Arc::new(client),
             policy_engine_url,
+            failure_policy: FailurePolicy::Open,
         }
     }
 
+    /// Fail lookups instead of returning empty results while LLM-Policy-Engine is
+    /// unavailable (open by default)
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
     /// Fetch policy bundles for a service
     pub async fn get_policy_bundles(&self, service_id: Uuid) -> Result<Vec<PolicyBundle>> {
         let start = std::time::Instant::now();
@@ -263,6 +273,12 @@ impl PolicyEngineClient {
                 latency_ms = latency.as_millis(),
                 "Failed to fetch policy bundles"
             );
+            if !self.failure_policy.allows() {
+                anyhow::bail!(
+                    "Failed to fetch policy bundles with status: {}",
+                    response.status()
+                );
+            }
             return Ok(vec![]);
         }
 
@@ -317,6 +333,12 @@ impl PolicyEngineClient {
                 latency_ms = latency.as_millis(),
                 "Failed to fetch enforcement metadata"
             );
+            if !self.failure_policy.allows() {
+                anyhow::bail!(
+                    "Failed to fetch enforcement metadata with status: {}",
+                    response.status()
+                );
+            }
             return Ok(None);
         }
 
@@ -358,6 +380,12 @@ impl PolicyEngineClient {
                 latency_ms = latency.as_millis(),
                 "Failed to fetch compliance rules"
             );
+            if !self.failure_policy.allows() {
+                anyhow::bail!(
+                    "Failed to fetch compliance rules with status: {}",
+                    response.status()
+                );
+            }
             return Ok(vec![]);
         }
 
@@ -409,6 +437,12 @@ impl PolicyEngineClient {
                 latency_ms = latency.as_millis(),
                 "Failed to fetch compliance status"
             );
+            if !self.failure_policy.allows() {
+                anyhow::bail!(
+                    "Failed to fetch compliance status with status: {}",
+                    response.status()
+                );
+            }
             return Ok(None);
         }
 
@@ -494,6 +528,17 @@ impl PolicyEngineClient {
             None => Ok(true), // No compliance requirements configured
         }
     }
+
+    /// Check that LLM-Policy-Engine is reachable and healthy
+    pub async fn ping(&self) -> Result<()> {
+        self.client
+            .get(format!("{}/health", self.policy_engine_url))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("LLM-Policy-Engine health check failed")?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
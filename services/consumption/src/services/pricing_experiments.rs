@@ -0,0 +1,524 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::models::PricingModel;
+
+/// How long a service's running experiment is cached before being reloaded
+const EXPERIMENT_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Buckets consumers are hashed into; price book weights are in these units
+pub const BUCKETS: u32 = 10_000;
+
+/// Variant of consumers left on the service's default pricing
+pub const CONTROL_VARIANT: &str = "control";
+
+/// Alternative pricing served to a share of a service's consumers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceBook {
+    pub variant: String,
+    /// Share of consumers, in basis points
+    pub weight: u32,
+    pub pricing: PricingModel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PricingExperiment {
+    pub id: Uuid,
+    pub service_id: Uuid,
+    pub name: String,
+    pub status: String, // running, stopped
+    pub books: sqlx::types::Json<Vec<PriceBook>>,
+    pub started_at: DateTime<Utc>,
+    pub stopped_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateExperimentRequest {
+    pub name: String,
+    pub books: Vec<PriceBook>,
+    /// Consumers pinned to a variant up front
+    #[serde(default)]
+    pub cohorts: HashMap<Uuid, String>,
+}
+
+/// Price book a consumer is served under a running experiment
+#[derive(Debug, Clone)]
+pub struct PriceAssignment {
+    pub experiment_id: Uuid,
+    pub experiment: String,
+    pub variant: String,
+    /// `None` for the control variant
+    pub pricing: Option<PricingModel>,
+}
+
+impl PriceAssignment {
+    /// Annotation stored in usage record metadata under `experiment`
+    pub fn annotation(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.experiment_id,
+            "name": self.experiment,
+            "variant": self.variant,
+        })
+    }
+}
+
+/// Usage and revenue of one variant since the experiment started
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VariantResult {
+    pub variant: String,
+    pub consumers: i64,
+    pub requests: i64,
+    pub tokens: i64,
+    pub revenue: f64,
+}
+
+/// Running experiment with its cohort pins, as cached for admission
+struct ActiveExperiment {
+    experiment: PricingExperiment,
+    cohorts: HashMap<Uuid, String>,
+}
+
+/// Serves A/B price books: each service may run one experiment that prices
+/// buckets of its consumers from alternative books. Bucketing hashes the
+/// experiment and consumer ids, so a consumer keeps its variant across
+/// requests and replicas.
+#[derive(Clone)]
+pub struct PricingExperiments {
+    db: Arc<PgPool>,
+    running: Arc<RwLock<HashMap<Uuid, (Instant, Option<Arc<ActiveExperiment>>)>>>,
+}
+
+impl PricingExperiments {
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db: Arc::new(db),
+            running: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Price book for a consumer of a service, or `None` if the service runs
+    /// no experiment
+    pub async fn assign(
+        &self,
+        service_id: Uuid,
+        consumer_id: Uuid,
+    ) -> Result<Option<PriceAssignment>> {
+        let Some(active) = self.running_experiment(service_id).await? else {
+            return Ok(None);
+        };
+        let experiment = &active.experiment;
+
+        let book = match active.cohorts.get(&consumer_id) {
+            Some(variant) => experiment
+                .books
+                .iter()
+                .find(|book| &book.variant == variant),
+            None => pick_book(&experiment.books, bucket(experiment.id, consumer_id)),
+        };
+
+        Ok(Some(PriceAssignment {
+            experiment_id: experiment.id,
+            experiment: experiment.name.clone(),
+            variant: book
+                .map_or(CONTROL_VARIANT, |book| &book.variant)
+                .to_string(),
+            pricing: book.map(|book| book.pricing.clone()),
+        }))
+    }
+
+    /// Experiments of a service, newest first
+    pub async fn list(&self, service_id: Uuid) -> Result<Vec<PricingExperiment>> {
+        let experiments = sqlx::query_as::<_, PricingExperiment>(
+            r#"
+            SELECT id, service_id, name, status, books, started_at, stopped_at
+            FROM pricing_experiments
+            WHERE service_id = $1
+            ORDER BY started_at DESC
+            "#,
+        )
+        .bind(service_id)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list pricing experiments")?;
+
+        Ok(experiments)
+    }
+
+    /// Start an experiment; fails if the service already runs one
+    pub async fn create(
+        &self,
+        service_id: Uuid,
+        request: &CreateExperimentRequest,
+    ) -> Result<PricingExperiment> {
+        validate(request)?;
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin pricing experiment transaction")?;
+
+        let experiment = sqlx::query_as::<_, PricingExperiment>(
+            r#"
+            INSERT INTO pricing_experiments (service_id, name, books)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (service_id) WHERE status = 'running' DO NOTHING
+            RETURNING id, service_id, name, status, books, started_at, stopped_at
+            "#,
+        )
+        .bind(service_id)
+        .bind(&request.name)
+        .bind(sqlx::types::Json(&request.books))
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to store pricing experiment")?;
+
+        let Some(experiment) = experiment else {
+            bail!(
+                "Service {} already has a running pricing experiment",
+                service_id
+            );
+        };
+
+        for (consumer_id, variant) in &request.cohorts {
+            sqlx::query(
+                r#"
+                INSERT INTO pricing_experiment_cohorts (experiment_id, consumer_id, variant)
+                VALUES ($1, $2, $3)
+                "#,
+            )
+            .bind(experiment.id)
+            .bind(consumer_id)
+            .bind(variant)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store pricing experiment cohort")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit pricing experiment")?;
+
+        self.running.write().unwrap().remove(&service_id);
+
+        info!(
+            service_id = %service_id,
+            experiment_id = %experiment.id,
+            name = %experiment.name,
+            books = request.books.len(),
+            "Pricing experiment started"
+        );
+
+        Ok(experiment)
+    }
+
+    /// Stop a running experiment; its consumers return to default pricing.
+    /// Returns false if it was not running.
+    pub async fn stop(&self, experiment_id: Uuid) -> Result<bool> {
+        let service_id: Option<Uuid> = sqlx::query_scalar(
+            r#"
+            UPDATE pricing_experiments
+            SET status = 'stopped', stopped_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            RETURNING service_id
+            "#,
+        )
+        .bind(experiment_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to stop pricing experiment")?;
+
+        let Some(service_id) = service_id else {
+            return Ok(false);
+        };
+        self.running.write().unwrap().remove(&service_id);
+
+        info!(experiment_id = %experiment_id, "Pricing experiment stopped");
+
+        Ok(true)
+    }
+
+    /// Pin a consumer to a variant of a running experiment
+    pub async fn pin_consumer(
+        &self,
+        experiment_id: Uuid,
+        consumer_id: Uuid,
+        variant: &str,
+    ) -> Result<()> {
+        let experiment = sqlx::query_as::<_, PricingExperiment>(
+            r#"
+            SELECT id, service_id, name, status, books, started_at, stopped_at
+            FROM pricing_experiments
+            WHERE id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(experiment_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load pricing experiment")?
+        .with_context(|| format!("Running pricing experiment {} not found", experiment_id))?;
+
+        if variant != CONTROL_VARIANT
+            && !experiment.books.iter().any(|book| book.variant == variant)
+        {
+            bail!("Invalid experiment: unknown variant '{}'", variant);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO pricing_experiment_cohorts (experiment_id, consumer_id, variant)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (experiment_id, consumer_id)
+            DO UPDATE SET variant = $3, assigned_at = NOW()
+            "#,
+        )
+        .bind(experiment_id)
+        .bind(consumer_id)
+        .bind(variant)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to pin consumer to pricing variant")?;
+
+        self.running.write().unwrap().remove(&experiment.service_id);
+
+        info!(
+            experiment_id = %experiment_id,
+            consumer_id = %consumer_id,
+            variant = variant,
+            "Consumer pinned to pricing variant"
+        );
+
+        Ok(())
+    }
+
+    /// Per-variant usage and revenue from the annotations on usage records
+    pub async fn results(&self, experiment_id: Uuid) -> Result<Vec<VariantResult>> {
+        let results = sqlx::query_as::<_, VariantResult>(
+            r#"
+            SELECT
+                u.metadata->'experiment'->>'variant' AS variant,
+                COUNT(DISTINCT u.consumer_id) AS consumers,
+                COUNT(*) AS requests,
+                COALESCE(SUM((u.usage->>'total_tokens')::BIGINT), 0)::BIGINT AS tokens,
+                COALESCE(SUM((u.cost->>'amount')::DOUBLE PRECISION), 0) AS revenue
+            FROM usage_records_all u
+            JOIN pricing_experiments e ON e.id = $1
+            WHERE u.service_id = e.service_id
+                AND u.timestamp >= e.started_at
+                AND u.metadata->'experiment'->>'id' = $1::text
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )
+        .bind(experiment_id)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to aggregate pricing experiment results")?;
+
+        Ok(results)
+    }
+
+    /// Running experiment of a service, reloaded after the cache TTL
+    async fn running_experiment(&self, service_id: Uuid) -> Result<Option<Arc<ActiveExperiment>>> {
+        if let Some((loaded_at, active)) = self.running.read().unwrap().get(&service_id) {
+            if loaded_at.elapsed() < EXPERIMENT_CACHE_TTL {
+                return Ok(active.clone());
+            }
+        }
+
+        let experiment = sqlx::query_as::<_, PricingExperiment>(
+            r#"
+            SELECT id, service_id, name, status, books, started_at, stopped_at
+            FROM pricing_experiments
+            WHERE service_id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load running pricing experiment")?;
+
+        let active = match experiment {
+            Some(experiment) => {
+                let cohorts: Vec<(Uuid, String)> = sqlx::query_as(
+                    r#"
+                    SELECT consumer_id, variant
+                    FROM pricing_experiment_cohorts
+                    WHERE experiment_id = $1
+                    "#,
+                )
+                .bind(experiment.id)
+                .fetch_all(self.db.as_ref())
+                .await
+                .context("Failed to load pricing experiment cohorts")?;
+
+                Some(Arc::new(ActiveExperiment {
+                    experiment,
+                    cohorts: cohorts.into_iter().collect(),
+                }))
+            }
+            None => None,
+        };
+
+        self.running
+            .write()
+            .unwrap()
+            .insert(service_id, (Instant::now(), active.clone()));
+
+        Ok(active)
+    }
+}
+
+fn validate(request: &CreateExperimentRequest) -> Result<()> {
+    if request.name.trim().is_empty() {
+        bail!("Invalid experiment: name is required");
+    }
+    if request.books.is_empty() {
+        bail!("Invalid experiment: at least one price book is required");
+    }
+
+    let mut variants = HashSet::new();
+    for book in &request.books {
+        if book.variant == CONTROL_VARIANT {
+            bail!(
+                "Invalid experiment: '{}' is reserved for default pricing",
+                CONTROL_VARIANT
+            );
+        }
+        if !variants.insert(book.variant.as_str()) {
+            bail!("Invalid experiment: duplicate variant '{}'", book.variant);
+        }
+        if book.weight == 0 {
+            bail!(
+                "Invalid experiment: variant '{}' has zero weight",
+                book.variant
+            );
+        }
+        if book.pricing.rates.is_empty() && book.pricing.model != "subscription" {
+            bail!(
+                "Invalid experiment: variant '{}' has no rates",
+                book.variant
+            );
+        }
+        if let Err(e) = book.pricing.validate() {
+            bail!("Invalid experiment: variant '{}': {}", book.variant, e);
+        }
+    }
+
+    let total: u32 = request.books.iter().map(|book| book.weight).sum();
+    if total > BUCKETS {
+        bail!(
+            "Invalid experiment: weights sum to {} (max {})",
+            total,
+            BUCKETS
+        );
+    }
+
+    for variant in request.cohorts.values() {
+        if variant != CONTROL_VARIANT && !variants.contains(variant.as_str()) {
+            bail!(
+                "Invalid experiment: cohort assigned to unknown variant '{}'",
+                variant
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Stable bucket of a consumer within an experiment. Hashing the experiment
+/// id too reshuffles consumers between experiments, so the same consumers
+/// aren't always the ones paying a test price.
+fn bucket(experiment_id: Uuid, consumer_id: Uuid) -> u32 {
+    let digest = Sha256::new()
+        .chain_update(experiment_id.as_bytes())
+        .chain_update(consumer_id.as_bytes())
+        .finalize();
+    let prefix = u64::from_be_bytes(digest[..8].try_into().unwrap());
+    (prefix % BUCKETS as u64) as u32
+}
+
+/// Book whose weight range covers the bucket; buckets past the last book
+/// stay on default pricing
+fn pick_book(books: &[PriceBook], bucket: u32) -> Option<&PriceBook> {
+    let mut upper = 0;
+    for book in books {
+        upper += book.weight;
+        if bucket < upper {
+            return Some(book);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PricingRate;
+
+    fn book(variant: &str, weight: u32) -> PriceBook {
+        PriceBook {
+            variant: variant.to_string(),
+            weight,
+            pricing: PricingModel {
+                model: "per-token".to_string(),
+                rates: vec![PricingRate {
+                    tier: "basic".to_string(),
+                    rate: 0.00001,
+                    unit: "token".to_string(),
+                    up_to: None,
+                    prompt_rate: None,
+                    completion_rate: None,
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn test_bucketing_is_deterministic_and_weighted() {
+        let experiment_id = Uuid::new_v4();
+        let books = vec![book("discount", 2000), book("premium", 3000)];
+
+        let mut counts: HashMap<&str, u32> = HashMap::new();
+        for _ in 0..20_000 {
+            let consumer_id = Uuid::new_v4();
+            let b = bucket(experiment_id, consumer_id);
+            assert_eq!(b, bucket(experiment_id, consumer_id));
+
+            let variant = pick_book(&books, b).map_or(CONTROL_VARIANT, |book| &book.variant);
+            *counts.entry(variant).or_default() += 1;
+        }
+
+        // 20% / 30% / 50% within a couple of points
+        assert!((3600..4400).contains(&counts["discount"]));
+        assert!((5400..6600).contains(&counts["premium"]));
+        assert!((9200..10_800).contains(&counts[CONTROL_VARIANT]));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_books() {
+        let request = |books: Vec<PriceBook>| CreateExperimentRequest {
+            name: "q3-pricing".to_string(),
+            books,
+            cohorts: HashMap::new(),
+        };
+
+        assert!(validate(&request(vec![book("a", 6000), book("b", 5000)])).is_err());
+        assert!(validate(&request(vec![book("a", 100), book("a", 100)])).is_err());
+        assert!(validate(&request(vec![book(CONTROL_VARIANT, 100)])).is_err());
+
+        assert!(validate(&request(vec![book("a", 4000), book("b", 6000)])).is_ok());
+
+        let mut pinned = request(vec![book("a", 5000)]);
+        pinned.cohorts.insert(Uuid::new_v4(), "b".to_string());
+        assert!(validate(&pinned).is_err());
+    }
+}
@@ -0,0 +1,492 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use super::data_residency::DataResidency;
+use super::usage_partitions::ARCHIVE_PREFIX;
+
+/// Signature algorithm used for export and erasure reports
+const SIGNATURE_ALGORITHM: &str = "HMAC-SHA256";
+
+/// Matches jobs of the consumer bound to `$1`: async consume requests carry
+/// the authenticated caller, with their prompt and result, in the job
+const CONSUMER_JOBS: &str = "payload->'caller'->>'consumer_id' = $1::text";
+
+/// Machine-readable copy of everything stored about a consumer
+#[derive(Debug, Clone, Serialize)]
+pub struct PrivacyExport {
+    pub request_id: Uuid,
+    pub consumer_id: Uuid,
+    pub generated_at: DateTime<Utc>,
+    /// Region holding the consumer's usage records and audit logs
+    pub storage_region: Option<String>,
+    pub api_keys: Vec<Value>,
+    pub usage_records: Vec<Value>,
+    pub audit_logs: Vec<Value>,
+    pub analytics_events: Vec<Value>,
+    pub quota_usage: Vec<Value>,
+    pub statements: Vec<Value>,
+    pub rate_limit_overrides: Vec<Value>,
+    pub pricing_experiment_cohorts: Vec<Value>,
+    pub jobs: Vec<Value>,
+    pub job_dead_letters: Vec<Value>,
+}
+
+/// Proof that a consumer's personal data was erased
+#[derive(Debug, Clone, Serialize)]
+pub struct ErasureReport {
+    pub request_id: Uuid,
+    /// SHA-256 of the consumer id, so the report identifies the subject
+    /// without retaining the identifier itself
+    pub subject_hash: String,
+    pub method: &'static str,
+    /// Rows deleted or anonymized, per table
+    pub rows: BTreeMap<String, u64>,
+    pub redis_keys_deleted: usize,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Report with a detached signature over its JSON serialization
+#[derive(Debug, Clone, Serialize)]
+pub struct Signed<T> {
+    pub report: T,
+    pub algorithm: &'static str,
+    pub signature: String,
+}
+
+/// GDPR data subject export and erasure across all consumption stores
+#[derive(Clone)]
+pub struct PrivacyService {
+    db: Arc<PgPool>,
+    redis: Arc<ConnectionManager>,
+    residency: DataResidency,
+    signing_key: Option<Arc<Vec<u8>>>,
+}
+
+fn subject_hash(consumer_id: Uuid) -> String {
+    hex::encode(Sha256::digest(consumer_id.as_bytes()))
+}
+
+fn sign<T: Serialize>(key: &[u8], report: T) -> Result<Signed<T>> {
+    let payload = serde_json::to_vec(&report).context("Failed to serialize report")?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).context("Invalid signing key")?;
+    mac.update(&payload);
+
+    Ok(Signed {
+        report,
+        algorithm: SIGNATURE_ALGORITHM,
+        signature: hex::encode(mac.finalize().into_bytes()),
+    })
+}
+
+impl PrivacyService {
+    pub fn new(db: PgPool, redis: ConnectionManager, residency: DataResidency) -> Self {
+        let signing_key = std::env::var("PRIVACY_SIGNING_KEY")
+            .ok()
+            .filter(|key| !key.is_empty())
+            .map(|key| Arc::new(key.into_bytes()));
+
+        Self {
+            db: Arc::new(db),
+            redis: Arc::new(redis),
+            residency,
+            signing_key,
+        }
+    }
+
+    fn signing_key(&self) -> Result<&[u8]> {
+        self.signing_key
+            .as_deref()
+            .map(Vec::as_slice)
+            .context("Privacy signing key not configured")
+    }
+
+    /// Collect a signed export of the consumer's personal data
+    pub async fn export(&self, consumer_id: Uuid) -> Result<Signed<PrivacyExport>> {
+        let key = self.signing_key()?;
+        let location = self.residency.location_for(consumer_id).await?;
+        let db = self.db.as_ref();
+
+        let api_keys = sqlx::query_scalar(
+            "SELECT to_jsonb(k) - 'key_hash' FROM api_keys k WHERE consumer_id = $1 ORDER BY created_at",
+        )
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export API keys")?;
+
+        let usage_records = sqlx::query_scalar(&format!(
+            "SELECT to_jsonb(u) FROM {} u WHERE consumer_id = $1 ORDER BY timestamp",
+            location.table("usage_records")
+        ))
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export usage records")?;
+
+        let audit_logs = sqlx::query_scalar(&format!(
+            "SELECT to_jsonb(a) FROM {} a WHERE actor_id = $1 OR resource_id = $1 ORDER BY timestamp",
+            location.table("audit_logs")
+        ))
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export audit logs")?;
+
+        let analytics_events = sqlx::query_scalar(
+            "SELECT to_jsonb(o) FROM analytics_outbox o WHERE consumer_id = $1 ORDER BY id",
+        )
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export analytics events")?;
+
+        let quota_usage = sqlx::query_scalar(
+            "SELECT to_jsonb(q) FROM quota_usage q WHERE consumer_id = $1 ORDER BY month",
+        )
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export quota usage")?;
+
+        let statements = sqlx::query_scalar(
+            "SELECT to_jsonb(s) FROM statements s WHERE consumer_id = $1 ORDER BY month",
+        )
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export statements")?;
+
+        let rate_limit_overrides = sqlx::query_scalar(
+            "SELECT to_jsonb(r) FROM rate_limit_overrides r WHERE consumer_id = $1 ORDER BY service_id",
+        )
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export rate limit overrides")?;
+
+        let pricing_experiment_cohorts = sqlx::query_scalar(
+            "SELECT to_jsonb(c) FROM pricing_experiment_cohorts c WHERE consumer_id = $1 ORDER BY assigned_at",
+        )
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export pricing experiment cohorts")?;
+
+        let jobs = sqlx::query_scalar(&format!(
+            "SELECT to_jsonb(j) FROM jobs j WHERE {} ORDER BY created_at",
+            CONSUMER_JOBS
+        ))
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export jobs")?;
+
+        let job_dead_letters = sqlx::query_scalar(&format!(
+            "SELECT to_jsonb(d) FROM job_dead_letters d WHERE {} ORDER BY failed_at",
+            CONSUMER_JOBS
+        ))
+        .bind(consumer_id)
+        .fetch_all(db)
+        .await
+        .context("Failed to export job dead letters")?;
+
+        let export = PrivacyExport {
+            request_id: Uuid::new_v4(),
+            consumer_id,
+            generated_at: Utc::now(),
+            storage_region: location.region,
+            api_keys,
+            usage_records,
+            audit_logs,
+            analytics_events,
+            quota_usage,
+            statements,
+            rate_limit_overrides,
+            pricing_experiment_cohorts,
+            jobs,
+            job_dead_letters,
+        };
+
+        let signed = sign(key, export)?;
+        self.record_request(signed.report.request_id, "export", consumer_id, &signed.signature)
+            .await?;
+
+        info!(request_id = %signed.report.request_id, "Privacy export generated");
+
+        Ok(signed)
+    }
+
+    /// Erase the consumer's personal data.
+    ///
+    /// API keys, suspensions, webhooks, rate limit overrides, pricing
+    /// experiment pins and queued or finished async requests (with their
+    /// dead letters) are deleted. Usage (including archived usage partitions), quota, statement,
+    /// analytics and audit rows are kept for billing and SLA aggregates but
+    /// re-attributed to a random tombstone id with free-form fields
+    /// stripped, so they can no longer be linked to the consumer.
+    pub async fn erase(&self, consumer_id: Uuid) -> Result<Signed<ErasureReport>> {
+        let key = self.signing_key()?;
+        let location = self.residency.location_for(consumer_id).await?;
+        let tombstone = Uuid::new_v4();
+
+        // Redis first, so quota persistence cannot re-create rows afterwards
+        let redis_keys_deleted = self.delete_redis_keys(consumer_id).await?;
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin erasure transaction")?;
+        let mut rows = BTreeMap::new();
+
+        let statements = [
+            ("api_keys", "DELETE FROM api_keys WHERE consumer_id = $1".to_string()),
+            (
+                "consumer_suspensions",
+                "DELETE FROM consumer_suspensions WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "consumer_webhooks",
+                "DELETE FROM consumer_webhooks WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "rate_limit_overrides",
+                "DELETE FROM rate_limit_overrides WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "pricing_experiment_cohorts",
+                "DELETE FROM pricing_experiment_cohorts WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "job_dead_letters",
+                format!("DELETE FROM job_dead_letters WHERE {}", CONSUMER_JOBS),
+            ),
+            ("jobs", format!("DELETE FROM jobs WHERE {}", CONSUMER_JOBS)),
+            (
+                "usage_records",
+                format!(
+                    "UPDATE {} SET consumer_id = $2, error = NULL, metadata = NULL WHERE consumer_id = $1",
+                    location.table("usage_records")
+                ),
+            ),
+            (
+                "audit_logs",
+                format!(
+                    r#"
+                    UPDATE {} SET
+                        actor_id = CASE WHEN actor_id = $1 THEN $2 ELSE actor_id END,
+                        resource_id = CASE WHEN resource_id = $1 THEN $2 ELSE resource_id END,
+                        details = '{{"anonymized": true}}'::jsonb,
+                        ip_address = NULL,
+                        user_agent = NULL
+                    WHERE actor_id = $1 OR resource_id = $1
+                    "#,
+                    location.table("audit_logs")
+                ),
+            ),
+            (
+                "analytics_outbox",
+                r#"
+                UPDATE analytics_outbox SET
+                    consumer_id = $2,
+                    payload = payload - 'consumer_id' - 'metadata' - 'error'
+                WHERE consumer_id = $1
+                "#
+                .to_string(),
+            ),
+            (
+                "quota_usage",
+                "UPDATE quota_usage SET consumer_id = $2 WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "sla_reports",
+                r#"
+                UPDATE sla_reports SET
+                    consumer_id = $2,
+                    report = jsonb_set(report, '{consumer_id}', to_jsonb($2::text))
+                WHERE consumer_id = $1
+                "#
+                .to_string(),
+            ),
+            (
+                "statements",
+                r#"
+                UPDATE statements SET
+                    consumer_id = $2,
+                    statement = jsonb_set(statement, '{consumer_id}', to_jsonb($2::text))
+                WHERE consumer_id = $1
+                "#
+                .to_string(),
+            ),
+            (
+                "quota_usage_rollups",
+                "UPDATE quota_usage_rollups SET consumer_id = $2 WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "usage_rollups",
+                "UPDATE usage_rollups SET consumer_id = $2 WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "quota_events",
+                "UPDATE quota_events SET consumer_id = $2 WHERE consumer_id = $1".to_string(),
+            ),
+            (
+                "ingested_usage",
+                "UPDATE ingested_usage SET consumer_id = $2 WHERE consumer_id = $1".to_string(),
+            ),
+        ];
+
+        for (table, statement) in statements {
+            let result = sqlx::query(&statement)
+                .bind(consumer_id)
+                .bind(tombstone)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to erase {}", table))?;
+            rows.insert(table.to_string(), result.rows_affected());
+        }
+
+        // Archived usage partitions are out of usage_records but still hold
+        // the consumer's rows
+        let archives: Vec<String> = sqlx::query_scalar(
+            "SELECT tablename::text FROM pg_tables WHERE schemaname = $1 AND starts_with(tablename, $2)",
+        )
+        .bind(&location.schema)
+        .bind(ARCHIVE_PREFIX)
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to list archived usage records")?;
+        let mut archived_rows = 0;
+        for archive in archives {
+            let result = sqlx::query(&format!(
+                "UPDATE {}.{} SET consumer_id = $2, error = NULL, metadata = NULL WHERE consumer_id = $1",
+                location.schema, archive
+            ))
+            .bind(consumer_id)
+            .bind(tombstone)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to erase {}", archive))?;
+            archived_rows += result.rows_affected();
+        }
+        rows.insert("usage_records_archived".to_string(), archived_rows);
+
+        let report = ErasureReport {
+            request_id: Uuid::new_v4(),
+            subject_hash: subject_hash(consumer_id),
+            method: "anonymization",
+            rows,
+            redis_keys_deleted,
+            completed_at: Utc::now(),
+        };
+        let signed = sign(key, report)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO privacy_requests (id, kind, subject_hash, signature, report)
+            VALUES ($1, 'erasure', $2, $3, $4)
+            "#,
+        )
+        .bind(signed.report.request_id)
+        .bind(&signed.report.subject_hash)
+        .bind(&signed.signature)
+        .bind(sqlx::types::Json(&signed.report))
+        .execute(&mut *tx)
+        .await
+        .context("Failed to record erasure")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit erasure")?;
+
+        info!(
+            request_id = %signed.report.request_id,
+            subject_hash = %signed.report.subject_hash,
+            "Consumer data erased"
+        );
+
+        Ok(signed)
+    }
+
+    async fn delete_redis_keys(&self, consumer_id: Uuid) -> Result<usize> {
+        let mut conn = self.redis.as_ref().clone();
+        let mut deleted = 0;
+
+        for pattern in [
+            format!("ratelimit:{}:*", consumer_id),
+            format!("concurrency:{}:*", consumer_id),
+            format!("quota:{}:*", consumer_id),
+        ] {
+            let keys: Vec<String> = conn
+                .keys(&pattern)
+                .await
+                .context("Failed to scan consumer keys")?;
+
+            if !keys.is_empty() {
+                let _: () = conn
+                    .del(&keys)
+                    .await
+                    .context("Failed to delete consumer keys")?;
+                deleted += keys.len();
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    async fn record_request(&self, id: Uuid, kind: &str, consumer_id: Uuid, signature: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO privacy_requests (id, kind, subject_hash, signature)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(id)
+        .bind(kind)
+        .bind(subject_hash(consumer_id))
+        .bind(signature)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to record privacy request")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_covers_report() {
+        let report = |rows: u64| ErasureReport {
+            request_id: Uuid::nil(),
+            subject_hash: subject_hash(Uuid::nil()),
+            method: "anonymization",
+            rows: BTreeMap::from([("usage_records".to_string(), rows)]),
+            redis_keys_deleted: 0,
+            completed_at: DateTime::<Utc>::UNIX_EPOCH,
+        };
+
+        let a = sign(b"key", report(3)).unwrap();
+        let b = sign(b"key", report(3)).unwrap();
+        let c = sign(b"key", report(4)).unwrap();
+        let d = sign(b"other", report(3)).unwrap();
+
+        assert_eq!(a.signature, b.signature);
+        assert_ne!(a.signature, c.signature);
+        assert_ne!(a.signature, d.signature);
+        assert_eq!(a.report.subject_hash.len(), 64);
+    }
+}
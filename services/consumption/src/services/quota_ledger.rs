@@ -0,0 +1,316 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// What changed a quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaEventKind {
+    /// Tokens used by a successful request
+    Consumption,
+    /// Usage cleared by an admin
+    Reset,
+    /// Usage corrected by an admin, e.g. refunding a failed batch
+    Adjustment,
+    /// Extra allowance on top of the tier quota for the month
+    OverageGrant,
+}
+
+impl QuotaEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaEventKind::Consumption => "consumption",
+            QuotaEventKind::Reset => "reset",
+            QuotaEventKind::Adjustment => "adjustment",
+            QuotaEventKind::OverageGrant => "overage_grant",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "consumption" => Some(QuotaEventKind::Consumption),
+            "reset" => Some(QuotaEventKind::Reset),
+            "adjustment" => Some(QuotaEventKind::Adjustment),
+            "overage_grant" => Some(QuotaEventKind::OverageGrant),
+            _ => None,
+        }
+    }
+}
+
+/// One row of the quota ledger
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaEvent {
+    pub id: i64,
+    pub consumer_id: Uuid,
+    pub service_id: Uuid,
+    pub month: String,
+    pub kind: QuotaEventKind,
+    pub used_delta: i64,
+    pub granted_delta: i64,
+    pub request_id: Option<Uuid>,
+    pub actor: Option<String>,
+    pub reason: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct QuotaEventRow {
+    id: i64,
+    consumer_id: Uuid,
+    service_id: Uuid,
+    month: String,
+    kind: String,
+    used_delta: i64,
+    granted_delta: i64,
+    request_id: Option<Uuid>,
+    actor: Option<String>,
+    reason: Option<String>,
+    occurred_at: DateTime<Utc>,
+}
+
+impl TryFrom<QuotaEventRow> for QuotaEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(row: QuotaEventRow) -> Result<Self> {
+        let kind = QuotaEventKind::parse(&row.kind)
+            .with_context(|| format!("Unknown quota event kind {}", row.kind))?;
+
+        Ok(QuotaEvent {
+            id: row.id,
+            consumer_id: row.consumer_id,
+            service_id: row.service_id,
+            month: row.month,
+            kind,
+            used_delta: row.used_delta,
+            granted_delta: row.granted_delta,
+            request_id: row.request_id,
+            actor: row.actor,
+            reason: row.reason,
+            occurred_at: row.occurred_at,
+        })
+    }
+}
+
+/// Used tokens and extra allowance of a consumer/service pair for a month
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct QuotaBalance {
+    pub used_tokens: i64,
+    pub granted_tokens: i64,
+}
+
+impl QuotaBalance {
+    /// Apply one event. A reset clears usage regardless of its recorded
+    /// delta, so consumption racing the reset cannot leave usage behind.
+    pub fn apply(&mut self, event: &QuotaEvent) {
+        match event.kind {
+            QuotaEventKind::Reset => self.used_tokens = 0,
+            _ => self.used_tokens += event.used_delta,
+        }
+        self.granted_tokens += event.granted_delta;
+    }
+}
+
+/// A ledger event with the balance right after it
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaLedgerEntry {
+    #[serde(flatten)]
+    pub event: QuotaEvent,
+    pub balance: QuotaBalance,
+}
+
+/// Fold events, oldest first, into running balances
+pub fn replay(events: Vec<QuotaEvent>) -> Vec<QuotaLedgerEntry> {
+    let mut balance = QuotaBalance::default();
+    events
+        .into_iter()
+        .map(|event| {
+            balance.apply(&event);
+            QuotaLedgerEntry { event, balance }
+        })
+        .collect()
+}
+
+/// A quota event about to be appended
+#[derive(Debug, Clone)]
+pub struct NewQuotaEvent {
+    pub consumer_id: Uuid,
+    pub service_id: Uuid,
+    pub month: String,
+    pub kind: QuotaEventKind,
+    pub used_delta: i64,
+    pub granted_delta: i64,
+    pub request_id: Option<Uuid>,
+    pub actor: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// Append-only Postgres ledger of quota events
+#[derive(Clone)]
+pub struct QuotaLedger {
+    db: Arc<PgPool>,
+}
+
+impl QuotaLedger {
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self { db }
+    }
+
+    /// Append an event. Consumption is recorded once per request, so a
+    /// retried usage update is a no-op.
+    pub async fn append(&self, event: NewQuotaEvent) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO quota_events
+                (consumer_id, service_id, month, kind, used_delta, granted_delta,
+                 request_id, actor, reason)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (request_id) WHERE kind = 'consumption' DO NOTHING
+            "#,
+        )
+        .bind(event.consumer_id)
+        .bind(event.service_id)
+        .bind(&event.month)
+        .bind(event.kind.as_str())
+        .bind(event.used_delta)
+        .bind(event.granted_delta)
+        .bind(event.request_id)
+        .bind(&event.actor)
+        .bind(&event.reason)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to append quota event")?;
+
+        Ok(())
+    }
+
+    /// Every event of a consumer/service pair in a month, oldest first
+    pub async fn events(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        month: &str,
+    ) -> Result<Vec<QuotaEvent>> {
+        let rows: Vec<QuotaEventRow> = sqlx::query_as(
+            r#"
+            SELECT id, consumer_id, service_id, month, kind, used_delta, granted_delta,
+                   request_id, actor, reason, occurred_at
+            FROM quota_events
+            WHERE consumer_id = $1 AND service_id = $2 AND month = $3
+            ORDER BY id
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(month)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load quota events")?;
+
+        rows.into_iter().map(QuotaEvent::try_from).collect()
+    }
+
+    /// Extra allowance granted to one pair for a month
+    pub async fn granted_tokens(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        month: &str,
+    ) -> Result<i64> {
+        sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(SUM(granted_delta), 0)::BIGINT
+            FROM quota_events
+            WHERE consumer_id = $1 AND service_id = $2 AND month = $3
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(month)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to load granted tokens")
+    }
+
+    /// Balances of every pair with events in a month. Same fold as
+    /// [`QuotaBalance::apply`]: usage counts from the last reset on.
+    pub async fn balances(&self, month: &str) -> Result<Vec<(Uuid, Uuid, QuotaBalance)>> {
+        let rows = sqlx::query_as::<_, (Uuid, Uuid, i64, i64)>(
+            r#"
+            SELECT consumer_id, service_id,
+                   COALESCE(SUM(used_delta) FILTER (WHERE id > COALESCE(last_reset, 0)), 0)::BIGINT,
+                   COALESCE(SUM(granted_delta), 0)::BIGINT
+            FROM (
+                SELECT *, MAX(id) FILTER (WHERE kind = 'reset')
+                    OVER (PARTITION BY consumer_id, service_id) AS last_reset
+                FROM quota_events
+                WHERE month = $1
+            ) events
+            GROUP BY consumer_id, service_id
+            "#,
+        )
+        .bind(month)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to rebuild quota balances")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(consumer_id, service_id, used_tokens, granted_tokens)| {
+                (
+                    consumer_id,
+                    service_id,
+                    QuotaBalance {
+                        used_tokens,
+                        granted_tokens,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i64, kind: QuotaEventKind, used_delta: i64, granted_delta: i64) -> QuotaEvent {
+        QuotaEvent {
+            id,
+            consumer_id: Uuid::nil(),
+            service_id: Uuid::nil(),
+            month: "2026-10".to_string(),
+            kind,
+            used_delta,
+            granted_delta,
+            request_id: None,
+            actor: None,
+            reason: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_replay_resets_usage_but_keeps_grants() {
+        let entries = replay(vec![
+            event(1, QuotaEventKind::Consumption, 700, 0),
+            event(2, QuotaEventKind::OverageGrant, 0, 500),
+            event(3, QuotaEventKind::Adjustment, -200, 0),
+            // Recorded delta is stale: 50 tokens landed between read and reset
+            event(4, QuotaEventKind::Reset, -450, 0),
+            event(5, QuotaEventKind::Consumption, 30, 0),
+        ]);
+
+        let used: Vec<i64> = entries.iter().map(|e| e.balance.used_tokens).collect();
+        assert_eq!(used, vec![700, 700, 500, 0, 30]);
+        assert_eq!(
+            entries.last().unwrap().balance,
+            QuotaBalance {
+                used_tokens: 30,
+                granted_tokens: 500,
+            }
+        );
+    }
+}
@@ -1,28 +1,166 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Datelike, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Timelike, Utc};
 use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::Serialize;
 use sqlx::PgPool;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, warn};
 use uuid::Uuid;
 
-use crate::models::{QuotaStatus, ServiceTier, UsageInfo};
+use crate::models::{QuotaGranularity, QuotaHistoryPoint, QuotaStatus, ServiceTier, UsageInfo};
+use crate::services::{
+    quota_ledger, BillingAnchor, BillingAnchors, LimitRampups, NewQuotaEvent, QuotaEventKind,
+    QuotaLedger, QuotaLedgerEntry, QuotaPeriod, TierCatalog,
+};
+
+/// Days a daily quota ledger is kept for reconciliation
+const LEDGER_RETENTION_DAYS: i64 = 40;
+
+/// Most windows a single history request may span
+pub const MAX_HISTORY_WINDOWS: usize = 1000;
+
+/// Months before the current one whose quota periods may still be in
+/// progress, since a period bridging a billing anchor change keeps the
+/// start of the period it extends
+const PERIOD_LOOKBACK_MONTHS: u32 = 3;
+
+/// A quota counter as stored in Redis
+#[derive(Debug, Clone, Serialize)]
+pub struct RedisCounter {
+    pub key: String,
+    /// `None` when the key doesn't exist
+    pub value: Option<i64>,
+    /// Seconds until the key expires; `None` without a key or an expiry
+    pub ttl_secs: Option<i64>,
+}
+
+/// Raw Redis quota counters of a consumer/service pair
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaCounters {
+    pub consumer_id: Uuid,
+    pub service_id: Uuid,
+    pub period: QuotaPeriod,
+    /// Tokens used this quota period
+    pub used: RedisCounter,
+    /// Tokens granted on top of the tier quota this period
+    pub granted: RedisCounter,
+}
+
+/// How persisted quota usage gets back into Redis after a restart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaPreload {
+    /// Load every quota of the month before serving traffic
+    Eager,
+    /// Load each consumer/service quota on its first check
+    Lazy,
+}
+
+impl QuotaPreload {
+    pub fn from_env() -> Self {
+        match std::env::var("QUOTA_PRELOAD")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "lazy" => QuotaPreload::Lazy,
+            _ => QuotaPreload::Eager,
+        }
+    }
+}
+
+/// Quotas already hydrated from the database in lazy mode, by quota
+/// period label; cleared every calendar month
+#[derive(Default)]
+struct Hydrated {
+    month: String,
+    keys: HashSet<(Uuid, Uuid, String)>,
+}
 
 /// Quota manager for tracking and enforcing usage limits
 #[derive(Clone)]
 pub struct QuotaManager {
     redis: Arc<ConnectionManager>,
     db: Arc<PgPool>,
+    tiers: TierCatalog,
+    ledger: QuotaLedger,
+    anchors: BillingAnchors,
+    rampups: LimitRampups,
+    preload: QuotaPreload,
+    hydrated: Arc<Mutex<Hydrated>>,
 }
 
 impl QuotaManager {
-    pub fn new(redis: ConnectionManager, db: PgPool) -> Self {
+    pub fn new(redis: ConnectionManager, db: PgPool, tiers: TierCatalog) -> Self {
+        let db = Arc::new(db);
         Self {
             redis: Arc::new(redis),
-            db: Arc::new(db),
+            ledger: QuotaLedger::new(db.clone()),
+            anchors: BillingAnchors::new(db.clone()),
+            rampups: LimitRampups::new(db.clone()),
+            db,
+            tiers,
+            preload: QuotaPreload::Eager,
+            hydrated: Arc::new(Mutex::new(Hydrated::default())),
         }
     }
 
+    pub fn with_preload(mut self, preload: QuotaPreload) -> Self {
+        self.preload = preload;
+        self
+    }
+
+    pub fn preload(&self) -> QuotaPreload {
+        self.preload
+    }
+
+    pub fn billing_anchors(&self) -> &BillingAnchors {
+        &self.anchors
+    }
+
+    /// Limit ramp-ups of new consumers, shared with the rate limiter
+    pub fn limit_rampups(&self) -> &LimitRampups {
+        &self.rampups
+    }
+
+    /// Quota period in progress for a consumer, from their organization's
+    /// billing anchor
+    pub async fn current_period(&self, consumer_id: Uuid) -> Result<QuotaPeriod> {
+        Ok(self
+            .anchors
+            .for_consumer(consumer_id)
+            .await?
+            .period(Utc::now()))
+    }
+
+    /// Move an organization's quota resets to `anchor_day`. Counters of the
+    /// period in progress are kept and now expire when it ends.
+    pub async fn set_billing_anchor(
+        &self,
+        organization_id: Uuid,
+        anchor_day: u32,
+    ) -> Result<BillingAnchor> {
+        let (anchor, members) = self.anchors.set(organization_id, anchor_day).await?;
+        let period = anchor.period(Utc::now());
+        let mut conn = self.redis.as_ref().clone();
+
+        for consumer_id in members {
+            let keys: Vec<String> = conn
+                .keys(format!("quota:{}:*", consumer_id))
+                .await
+                .context("Failed to scan consumer quotas")?;
+            for key in keys {
+                let _: () = conn
+                    .expire(&key, seconds_until(period.end))
+                    .await
+                    .context("Failed to set expiry")?;
+            }
+        }
+
+        Ok(anchor)
+    }
+
     /// Check if quota is available
     pub async fn check_quota(
         &self,
@@ -31,21 +169,33 @@ impl QuotaManager {
         tier: &ServiceTier,
     ) -> Result<QuotaStatus> {
         let key = self.quota_key(consumer_id, service_id);
+        let grant_key = self.grant_key(consumer_id, service_id);
+        let period = self.current_period(consumer_id).await?;
         let mut conn = self.redis.as_ref().clone();
 
-        // Get current usage from Redis cache
-        let used_tokens: Option<i64> = conn
-            .get(&key)
+        // Get current usage and granted overage from Redis cache
+        let (used_tokens, granted_tokens): (Option<i64>, Option<i64>) = redis::cmd("MGET")
+            .arg(&key)
+            .arg(&grant_key)
+            .query_async(&mut conn)
             .await
             .context("Failed to get quota from Redis")?;
 
-        let used_tokens = used_tokens.unwrap_or(0);
-        let total_tokens = tier.quota_limit();
+        let (used_tokens, granted_tokens) = match used_tokens {
+            Some(used) => (used, granted_tokens.unwrap_or(0)),
+            None if self.needs_hydration(consumer_id, service_id, &period) => {
+                self.hydrate(consumer_id, service_id, &period).await?
+            }
+            None => (0, granted_tokens.unwrap_or(0)),
+        };
+        let (limits, rampup) = self
+            .rampups
+            .ramp(consumer_id, self.tiers.limits(tier))
+            .await?;
+        let total_tokens = limits.quota_limit + granted_tokens;
         let remaining_tokens = total_tokens - used_tokens;
         let exceeded = remaining_tokens <= 0;
 
-        let reset_at = self.get_quota_reset_time();
-
         debug!(
             consumer_id = %consumer_id,
             service_id = %service_id,
@@ -61,42 +211,56 @@ impl QuotaManager {
             tier: tier.clone(),
             used_tokens,
             total_tokens,
+            granted_tokens,
             remaining_tokens,
-            reset_at,
+            reset_at: period.end,
             exceeded,
+            overage: limits.overage,
+            rampup,
         })
     }
 
-    /// Update quota after consumption
+    /// Update quota after consumption. Returns the tokens used in the
+    /// period, including this request's.
     pub async fn update_quota(
         &self,
         consumer_id: Uuid,
         service_id: Uuid,
+        request_id: Uuid,
         usage: &UsageInfo,
-    ) -> Result<()> {
+    ) -> Result<i64> {
         let key = self.quota_key(consumer_id, service_id);
+        let period = self.current_period(consumer_id).await?;
         let mut conn = self.redis.as_ref().clone();
 
         let tokens_used = usage.total_tokens as i64;
 
         // Increment usage in Redis
-        conn.incr(&key, tokens_used)
-            .await
-            .context("Failed to increment quota")?;
+        let used_tokens = self.increment(&key, tokens_used, &period).await?;
 
-        // Set expiry to end of month if not set
-        let ttl: i64 = conn
-            .ttl(&key)
+        // Daily per-service ledger of increments for metering reconciliation
+        let ledger_key = Self::ledger_key(Utc::now().date_naive());
+        conn.hincr(&ledger_key, service_id.to_string(), tokens_used)
             .await
-            .context("Failed to get TTL")?;
+            .context("Failed to update quota ledger")?;
+        conn.expire(&ledger_key, Duration::days(LEDGER_RETENTION_DAYS).num_seconds())
+            .await
+            .context("Failed to set ledger expiry")?;
 
-        if ttl == -1 {
-            let reset_time = self.get_quota_reset_time();
-            let seconds_until_reset = (reset_time - Utc::now()).num_seconds();
-            conn.expire(&key, seconds_until_reset as usize)
-                .await
-                .context("Failed to set expiry")?;
-        }
+        // Append-only record backing rebuilds and billing disputes
+        self.ledger
+            .append(NewQuotaEvent {
+                consumer_id,
+                service_id,
+                month: period.label(),
+                kind: QuotaEventKind::Consumption,
+                used_delta: tokens_used,
+                granted_delta: 0,
+                request_id: Some(request_id),
+                actor: None,
+                reason: None,
+            })
+            .await?;
 
         debug!(
             consumer_id = %consumer_id,
@@ -105,7 +269,28 @@ impl QuotaManager {
             "Quota updated"
         );
 
-        Ok(())
+        Ok(used_tokens)
+    }
+
+    /// Tokens added to quotas on a given day, per service
+    pub async fn daily_increments(&self, day: NaiveDate) -> Result<HashMap<Uuid, i64>> {
+        let mut conn = self.redis.as_ref().clone();
+
+        let ledger: HashMap<String, i64> = conn
+            .hgetall(Self::ledger_key(day))
+            .await
+            .context("Failed to read quota ledger")?;
+
+        Ok(ledger
+            .into_iter()
+            .filter_map(|(service_id, tokens)| {
+                Uuid::parse_str(&service_id).ok().map(|id| (id, tokens))
+            })
+            .collect())
+    }
+
+    fn ledger_key(day: NaiveDate) -> String {
+        format!("metering:quota:{}", day.format("%Y-%m-%d"))
     }
 
     /// Reset quota (admin function)
@@ -113,23 +298,213 @@ impl QuotaManager {
         &self,
         consumer_id: Uuid,
         service_id: Uuid,
+        actor: &str,
     ) -> Result<()> {
         let key = self.quota_key(consumer_id, service_id);
+        let period = self.current_period(consumer_id).await?;
         let mut conn = self.redis.as_ref().clone();
 
-        conn.del(&key)
+        let cleared: Option<i64> = redis::cmd("GETDEL")
+            .arg(&key)
+            .query_async(&mut conn)
             .await
             .context("Failed to reset quota")?;
 
+        self.ledger
+            .append(NewQuotaEvent {
+                consumer_id,
+                service_id,
+                month: period.label(),
+                kind: QuotaEventKind::Reset,
+                used_delta: -cleared.unwrap_or(0),
+                granted_delta: 0,
+                request_id: None,
+                actor: Some(actor.to_string()),
+                reason: None,
+            })
+            .await?;
+
         debug!(
             consumer_id = %consumer_id,
             service_id = %service_id,
+            cleared_tokens = cleared.unwrap_or(0),
             "Quota reset"
         );
 
         Ok(())
     }
 
+    /// Raw Redis counters of a consumer/service pair (admin function). A
+    /// missing usage counter is hydrated from Postgres on the next check.
+    pub async fn counters(&self, consumer_id: Uuid, service_id: Uuid) -> Result<QuotaCounters> {
+        let used = self.counter(self.quota_key(consumer_id, service_id));
+        let granted = self.counter(self.grant_key(consumer_id, service_id));
+
+        Ok(QuotaCounters {
+            consumer_id,
+            service_id,
+            period: self.current_period(consumer_id).await?,
+            used: used.await?,
+            granted: granted.await?,
+        })
+    }
+
+    async fn counter(&self, key: String) -> Result<RedisCounter> {
+        let mut conn = self.redis.as_ref().clone();
+        let (value, ttl): (Option<i64>, i64) = redis::pipe()
+            .get(&key)
+            .ttl(&key)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read quota counter")?;
+
+        Ok(RedisCounter {
+            key,
+            value,
+            ttl_secs: (ttl >= 0).then_some(ttl),
+        })
+    }
+
+    /// Correct used tokens by `tokens` (admin function); negative refunds
+    /// usage. Returns the new usage.
+    pub async fn adjust_quota(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        tokens: i64,
+        actor: &str,
+        reason: &str,
+    ) -> Result<i64> {
+        let period = self.current_period(consumer_id).await?;
+        let used_tokens = self
+            .increment(&self.quota_key(consumer_id, service_id), tokens, &period)
+            .await?;
+
+        self.ledger
+            .append(NewQuotaEvent {
+                consumer_id,
+                service_id,
+                month: period.label(),
+                kind: QuotaEventKind::Adjustment,
+                used_delta: tokens,
+                granted_delta: 0,
+                request_id: None,
+                actor: Some(actor.to_string()),
+                reason: Some(reason.to_string()),
+            })
+            .await?;
+
+        debug!(
+            consumer_id = %consumer_id,
+            service_id = %service_id,
+            tokens = tokens,
+            "Quota adjusted"
+        );
+
+        Ok(used_tokens)
+    }
+
+    /// Grant `tokens` on top of the tier quota until the next reset (admin
+    /// function). Returns the total granted this quota period.
+    pub async fn grant_overage(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        tokens: i64,
+        actor: &str,
+        reason: &str,
+    ) -> Result<i64> {
+        let period = self.current_period(consumer_id).await?;
+        let granted_tokens = self
+            .increment(&self.grant_key(consumer_id, service_id), tokens, &period)
+            .await?;
+
+        self.ledger
+            .append(NewQuotaEvent {
+                consumer_id,
+                service_id,
+                month: period.label(),
+                kind: QuotaEventKind::OverageGrant,
+                used_delta: 0,
+                granted_delta: tokens,
+                request_id: None,
+                actor: Some(actor.to_string()),
+                reason: Some(reason.to_string()),
+            })
+            .await?;
+
+        debug!(
+            consumer_id = %consumer_id,
+            service_id = %service_id,
+            tokens = tokens,
+            "Quota overage granted"
+        );
+
+        Ok(granted_tokens)
+    }
+
+    /// Ledger of a consumer/service pair for a month (default: the current
+    /// quota period's label) with running balances. Consumption is replayed
+    /// into the balances either way but only listed with
+    /// `include_consumption`.
+    pub async fn ledger_entries(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        month: Option<&str>,
+        include_consumption: bool,
+    ) -> Result<Vec<QuotaLedgerEntry>> {
+        let month = match month {
+            Some(month) => month.to_string(),
+            None => self.current_period(consumer_id).await?.label(),
+        };
+        let events = self.ledger.events(consumer_id, service_id, &month).await?;
+
+        Ok(quota_ledger::replay(events)
+            .into_iter()
+            .filter(|entry| include_consumption || entry.event.kind != QuotaEventKind::Consumption)
+            .collect())
+    }
+
+    /// Restore quota counters missing from Redis by replaying the ledger of
+    /// each consumer's current quota period (background job). Counters still
+    /// in Redis are left alone and only logged when they drift from the
+    /// ledger, since usage recorded before the ledger existed is not in it.
+    pub async fn rebuild_quotas(&self) -> Result<usize> {
+        let balances = self.current_balances().await?;
+        let mut restored = 0;
+
+        for (consumer_id, service_id, period, balance) in balances {
+            for (key, tokens) in [
+                (self.quota_key(consumer_id, service_id), balance.used_tokens),
+                (
+                    self.grant_key(consumer_id, service_id),
+                    balance.granted_tokens,
+                ),
+            ] {
+                if tokens <= 0 {
+                    continue;
+                }
+                match self.set_if_missing(&key, tokens, &period).await? {
+                    None => restored += 1,
+                    Some(current) if current != tokens => {
+                        warn!(
+                            key = %key,
+                            ledger = tokens,
+                            redis = current,
+                            "Quota counter drifts from ledger"
+                        );
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        debug!(restored = restored, "Quotas rebuilt from ledger");
+
+        Ok(restored)
+    }
+
     /// Persist quota data from Redis to PostgreSQL (background job)
     pub async fn persist_quotas(&self) -> Result<()> {
         let mut conn = self.redis.as_ref().clone();
@@ -149,6 +524,8 @@ impl QuotaManager {
 
             // Parse key to extract consumer_id and service_id
             if let Some((consumer_id, service_id)) = self.parse_quota_key(&key) {
+                let period = self.current_period(consumer_id).await?;
+
                 // Insert or update quota record in database
                 sqlx::query(
                     r#"
@@ -160,7 +537,7 @@ impl QuotaManager {
                 )
                 .bind(consumer_id)
                 .bind(service_id)
-                .bind(self.current_month())
+                .bind(period.label())
                 .bind(used_tokens)
                 .execute(self.db.as_ref())
                 .await
@@ -179,38 +556,272 @@ impl QuotaManager {
             r#"
             SELECT consumer_id, service_id, month, used_tokens
             FROM quota_usage
-            WHERE month = $1
+            WHERE month >= $1
             "#
         )
-        .bind(self.current_month())
+        .bind(lookback_months(Utc::now()).pop().unwrap_or_default())
         .fetch_all(self.db.as_ref())
         .await
         .context("Failed to load quotas from database")?;
 
         let mut conn = self.redis.as_ref().clone();
+        let mut loaded = 0;
+
+        for (consumer_id, service_id, month, used_tokens) in records {
+            // Earlier months may hold the current period of consumers with
+            // a billing anchor; only load each consumer's own period
+            let period = self.current_period(consumer_id).await?;
+            if month != period.label() {
+                continue;
+            }
 
-        for (consumer_id, service_id, _, used_tokens) in records {
             let key = self.quota_key(consumer_id, service_id);
             conn.set(&key, used_tokens)
                 .await
                 .context("Failed to set quota in Redis")?;
-
-            let reset_time = self.get_quota_reset_time();
-            let seconds_until_reset = (reset_time - Utc::now()).num_seconds();
-            conn.expire(&key, seconds_until_reset as usize)
+            conn.expire(&key, seconds_until(period.end))
                 .await
                 .context("Failed to set expiry")?;
+            loaded += 1;
         }
 
-        debug!(quotas_loaded = records.len(), "Quotas loaded from database");
+        // Overage grants only live in the ledger
+        for (consumer_id, service_id, period, balance) in self.current_balances().await? {
+            if balance.granted_tokens > 0 {
+                let key = self.grant_key(consumer_id, service_id);
+                conn.set(&key, balance.granted_tokens)
+                    .await
+                    .context("Failed to set overage grant in Redis")?;
+                conn.expire(&key, seconds_until(period.end))
+                    .await
+                    .context("Failed to set expiry")?;
+            }
+        }
+
+        debug!(quotas_loaded = loaded, "Quotas loaded from database");
 
         Ok(())
     }
 
+    /// Whether a missing Redis quota should be looked up in the database
+    fn needs_hydration(&self, consumer_id: Uuid, service_id: Uuid, period: &QuotaPeriod) -> bool {
+        if self.preload != QuotaPreload::Lazy {
+            return false;
+        }
+
+        let now = Utc::now();
+        let month = format!("{}-{:02}", now.year(), now.month());
+        let mut hydrated = self.hydrated.lock().unwrap();
+        if hydrated.month != month {
+            hydrated.month = month;
+            hydrated.keys.clear();
+        }
+        !hydrated
+            .keys
+            .contains(&(consumer_id, service_id, period.label()))
+    }
+
+    /// Load one persisted quota and its overage grants into Redis on first
+    /// access (lazy preload)
+    async fn hydrate(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        period: &QuotaPeriod,
+    ) -> Result<(i64, i64)> {
+        let persisted: Option<i64> = sqlx::query_scalar(
+            r#"
+            SELECT used_tokens
+            FROM quota_usage
+            WHERE consumer_id = $1 AND service_id = $2 AND month = $3
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(period.label())
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load quota from database")?;
+
+        let mut used_tokens = 0;
+        if let Some(persisted) = persisted.filter(|used| *used > 0) {
+            let key = self.quota_key(consumer_id, service_id);
+            used_tokens = self
+                .set_if_missing(&key, persisted, period)
+                .await?
+                .unwrap_or(persisted);
+        }
+
+        let mut granted_tokens = self
+            .ledger
+            .granted_tokens(consumer_id, service_id, &period.label())
+            .await?;
+        if granted_tokens > 0 {
+            let key = self.grant_key(consumer_id, service_id);
+            granted_tokens = self
+                .set_if_missing(&key, granted_tokens, period)
+                .await?
+                .unwrap_or(granted_tokens);
+        }
+
+        self.hydrated
+            .lock()
+            .unwrap()
+            .keys
+            .insert((consumer_id, service_id, period.label()));
+
+        debug!(
+            consumer_id = %consumer_id,
+            service_id = %service_id,
+            used_tokens = used_tokens,
+            granted_tokens = granted_tokens,
+            "Quota hydrated from database"
+        );
+
+        Ok((used_tokens, granted_tokens))
+    }
+
+    /// Add `delta` to a counter of `period`, expiring it at the period's
+    /// reset. Returns the new value.
+    async fn increment(&self, key: &str, delta: i64, period: &QuotaPeriod) -> Result<i64> {
+        let mut conn = self.redis.as_ref().clone();
+
+        let value: i64 = conn
+            .incr(key, delta)
+            .await
+            .context("Failed to increment quota")?;
+
+        // Set expiry to the reset if not set
+        let ttl: i64 = conn
+            .ttl(key)
+            .await
+            .context("Failed to get TTL")?;
+
+        if ttl == -1 {
+            conn.expire(key, seconds_until(period.end))
+                .await
+                .context("Failed to set expiry")?;
+        }
+
+        Ok(value)
+    }
+
+    /// `SET NX` a counter of `period`, so usage recorded meanwhile by
+    /// another replica is kept. Returns the value already in Redis, if any.
+    async fn set_if_missing(
+        &self,
+        key: &str,
+        value: i64,
+        period: &QuotaPeriod,
+    ) -> Result<Option<i64>> {
+        let mut conn = self.redis.as_ref().clone();
+
+        let set: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(seconds_until(period.end))
+            .query_async(&mut conn)
+            .await
+            .context("Failed to set quota in Redis")?;
+
+        match set {
+            Some(_) => Ok(None),
+            None => Ok(Some(
+                conn.get::<_, Option<i64>>(key)
+                    .await
+                    .context("Failed to get quota from Redis")?
+                    .unwrap_or(0),
+            )),
+        }
+    }
+
+    /// Roll up successful usage since `since` into hourly quota rollups
+    /// (background job). Hours are recomputed in full, so re-running over
+    /// the same range is safe.
+    pub async fn rollup_usage(&self, since: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO quota_usage_rollups (consumer_id, service_id, hour, tokens, requests, updated_at)
+            SELECT consumer_id, service_id,
+                   date_trunc('hour', timestamp AT TIME ZONE 'UTC') AT TIME ZONE 'UTC',
+                   COALESCE(SUM((usage->>'total_tokens')::BIGINT), 0)::BIGINT,
+                   COUNT(*),
+                   NOW()
+            FROM usage_records_all
+            WHERE timestamp >= $1 AND status = 'success'
+            GROUP BY 1, 2, 3
+            ON CONFLICT (consumer_id, service_id, hour)
+            DO UPDATE SET tokens = EXCLUDED.tokens, requests = EXCLUDED.requests, updated_at = NOW()
+            "#,
+        )
+        .bind(window_start(QuotaGranularity::Hour, since))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to roll up quota usage")?;
+
+        debug!(rows = result.rows_affected(), "Quota usage rolled up");
+
+        Ok(result.rows_affected())
+    }
+
+    /// Quota consumption per window between `from` and `to`, read from the
+    /// hourly rollups. Windows without usage are included with zero tokens.
+    pub async fn usage_history(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        tier: &ServiceTier,
+        granularity: QuotaGranularity,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<QuotaHistoryPoint>> {
+        // Start at the beginning of the quota period so the running total
+        // includes usage before `from`
+        let anchor = self.anchors.for_consumer(consumer_id).await?;
+        let period_start = anchor.period(from).start;
+
+        let rows = sqlx::query_as::<_, (DateTime<Utc>, i64, i64)>(
+            r#"
+            SELECT date_trunc($3, hour AT TIME ZONE 'UTC') AT TIME ZONE 'UTC',
+                   SUM(tokens)::BIGINT,
+                   SUM(requests)::BIGINT
+            FROM quota_usage_rollups
+            WHERE consumer_id = $1 AND service_id = $2
+                AND hour >= $4 AND hour < $5
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(granularity.as_str())
+        .bind(period_start.min(window_start(granularity, from)))
+        .bind(to)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load quota history")?;
+
+        let first = window_start(granularity, from);
+        let quota_limit = self.tiers.limits(tier).quota_limit;
+        let points = build_history(granularity, &anchor, period_start, to, quota_limit, &rows);
+        Ok(points
+            .into_iter()
+            .filter(|point| point.window_start >= first)
+            .collect())
+    }
+
     fn quota_key(&self, consumer_id: Uuid, service_id: Uuid) -> String {
         format!("quota:{}:{}", consumer_id, service_id)
     }
 
+    /// Overage granted this quota period; four segments, so `persist_quotas`
+    /// skips it while erasure and the Redis audit still match `quota:*`
+    fn grant_key(&self, consumer_id: Uuid, service_id: Uuid) -> String {
+        format!("quota:{}:{}:granted", consumer_id, service_id)
+    }
+
     fn parse_quota_key(&self, key: &str) -> Option<(Uuid, Uuid)> {
         let parts: Vec<&str> = key.split(':').collect();
         if parts.len() == 3 {
@@ -222,34 +833,141 @@ impl QuotaManager {
         }
     }
 
-    fn get_quota_reset_time(&self) -> DateTime<Utc> {
-        let now = Utc::now();
-        let year = now.year();
-        let month = now.month();
-
-        // First day of next month
-        if month == 12 {
-            Utc.with_ymd_and_hms(year + 1, 1, 1, 0, 0, 0).unwrap()
-        } else {
-            Utc.with_ymd_and_hms(year, month + 1, 1, 0, 0, 0).unwrap()
+    /// Ledger balances of every pair, each from its consumer's current
+    /// quota period
+    async fn current_balances(
+        &self,
+    ) -> Result<Vec<(Uuid, Uuid, QuotaPeriod, quota_ledger::QuotaBalance)>> {
+        let mut current = Vec::new();
+        for month in lookback_months(Utc::now()) {
+            for (consumer_id, service_id, balance) in self.ledger.balances(&month).await? {
+                let period = self.current_period(consumer_id).await?;
+                if period.label() == month {
+                    current.push((consumer_id, service_id, period, balance));
+                }
+            }
         }
+        Ok(current)
     }
+}
 
-    fn current_month(&self) -> String {
-        let now = Utc::now();
-        format!("{}-{:02}", now.year(), now.month())
+/// Seconds until `end`, at least one so a counter never loses its expiry
+fn seconds_until(end: DateTime<Utc>) -> i64 {
+    (end - Utc::now()).num_seconds().max(1)
+}
+
+/// Labels of the current and `PERIOD_LOOKBACK_MONTHS` previous months,
+/// newest first
+fn lookback_months(now: DateTime<Utc>) -> Vec<String> {
+    let this_month = window_start(QuotaGranularity::Month, now);
+    (0..=PERIOD_LOOKBACK_MONTHS)
+        .map(|back| {
+            let month = this_month - Months::new(back);
+            format!("{}-{:02}", month.year(), month.month())
+        })
+        .collect()
+}
+
+/// Start of the window containing `at`
+fn window_start(granularity: QuotaGranularity, at: DateTime<Utc>) -> DateTime<Utc> {
+    let date = at.date_naive();
+    let start = match granularity {
+        QuotaGranularity::Hour => date.and_hms_opt(at.hour(), 0, 0),
+        QuotaGranularity::Day => date.and_hms_opt(0, 0, 0),
+        QuotaGranularity::Week => (date - Duration::days(date.weekday().num_days_from_monday() as i64))
+            .and_hms_opt(0, 0, 0),
+        QuotaGranularity::Month => date.with_day(1).and_then(|d| d.and_hms_opt(0, 0, 0)),
+    };
+    start.expect("valid window start").and_utc()
+}
+
+/// Start of the window following the one starting at `start`
+fn next_window(granularity: QuotaGranularity, start: DateTime<Utc>) -> DateTime<Utc> {
+    match granularity {
+        QuotaGranularity::Hour => start + Duration::hours(1),
+        QuotaGranularity::Day => start + Duration::days(1),
+        QuotaGranularity::Week => start + Duration::weeks(1),
+        QuotaGranularity::Month => start + Months::new(1),
+    }
+}
+
+/// Number of windows between `from` and `to`
+pub fn history_window_count(
+    granularity: QuotaGranularity,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> usize {
+    let mut count = 0;
+    let mut start = window_start(granularity, from);
+    while start < to && count <= MAX_HISTORY_WINDOWS {
+        count += 1;
+        start = next_window(granularity, start);
     }
+    count
+}
+
+/// Fill every window between `from` and `to` from per-window `(start,
+/// tokens, requests)` rows, keeping a running total that resets when a new
+/// quota period of `anchor` starts. Windows count towards the period they
+/// start in.
+fn build_history(
+    granularity: QuotaGranularity,
+    anchor: &BillingAnchor,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    quota_limit: i64,
+    rows: &[(DateTime<Utc>, i64, i64)],
+) -> Vec<QuotaHistoryPoint> {
+    let usage: HashMap<DateTime<Utc>, (i64, i64)> = rows
+        .iter()
+        .map(|(start, tokens, requests)| (*start, (*tokens, *requests)))
+        .collect();
+
+    let mut points = Vec::new();
+    let mut used_tokens = 0;
+    let mut period = None;
+    let mut start = window_start(granularity, from);
+
+    while start < to {
+        let window_period = anchor.period(start).start;
+        if period != Some(window_period) {
+            period = Some(window_period);
+            used_tokens = 0;
+        }
+
+        let (tokens, requests) = usage.get(&start).copied().unwrap_or((0, 0));
+        used_tokens += tokens;
+
+        points.push(QuotaHistoryPoint {
+            window_start: start,
+            tokens,
+            requests,
+            used_tokens,
+            remaining_tokens: (quota_limit - used_tokens).max(0),
+        });
+
+        start = next_window(granularity, start);
+    }
+
+    points
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_quota_key_parsing() {
         let manager = QuotaManager {
             redis: Arc::new(redis::Client::open("redis://localhost").unwrap().get_tokio_connection_manager()),
             db: Arc::new(PgPool::connect_lazy("postgres://localhost").unwrap()),
+            tiers: TierCatalog::new(PgPool::connect_lazy("postgres://localhost").unwrap()),
+            ledger: QuotaLedger::new(Arc::new(PgPool::connect_lazy("postgres://localhost").unwrap())),
+            anchors: BillingAnchors::new(Arc::new(PgPool::connect_lazy("postgres://localhost").unwrap())),
+            rampups: LimitRampups::new(Arc::new(PgPool::connect_lazy("postgres://localhost").unwrap())),
+            preload: QuotaPreload::Eager,
+            hydrated: Arc::new(Mutex::new(Hydrated::default())),
         };
 
         let consumer_id = Uuid::new_v4();
@@ -259,5 +977,59 @@ mod tests {
         let (parsed_consumer, parsed_service) = manager.parse_quota_key(&key).unwrap();
         assert_eq!(consumer_id, parsed_consumer);
         assert_eq!(service_id, parsed_service);
+        assert!(manager
+            .parse_quota_key(&manager.grant_key(consumer_id, service_id))
+            .is_none());
+    }
+
+    #[test]
+    fn test_history_running_total_resets_each_month() {
+        let at = |month, day| Utc.with_ymd_and_hms(2025, month, day, 0, 0, 0).unwrap();
+        let rows = vec![(at(10, 30), 100, 2), (at(11, 1), 40, 1), (at(11, 3), 10, 1)];
+
+        let points = build_history(
+            QuotaGranularity::Day,
+            &BillingAnchor::default(),
+            at(10, 30),
+            at(11, 4),
+            1_000,
+            &rows,
+        );
+
+        let used: Vec<i64> = points.iter().map(|p| p.used_tokens).collect();
+        assert_eq!(used, vec![100, 100, 40, 40, 50]);
+        assert_eq!(points[3].tokens, 0);
+        assert_eq!(points[4].remaining_tokens, 950);
+
+        // Anchored on the 31st, the total resets on Oct 31 and keeps
+        // running into November
+        let anchor = BillingAnchor {
+            anchor_day: 31,
+            transition: None,
+        };
+        let points = build_history(
+            QuotaGranularity::Day,
+            &anchor,
+            at(10, 30),
+            at(11, 4),
+            1_000,
+            &rows,
+        );
+        let used: Vec<i64> = points.iter().map(|p| p.used_tokens).collect();
+        assert_eq!(used, vec![100, 0, 40, 40, 50]);
+    }
+
+    #[test]
+    fn test_history_windows() {
+        let from = Utc.with_ymd_and_hms(2025, 11, 5, 13, 30, 0).unwrap();
+        assert_eq!(
+            window_start(QuotaGranularity::Week, from),
+            Utc.with_ymd_and_hms(2025, 11, 3, 0, 0, 0).unwrap()
+        );
+        assert_eq!(history_window_count(QuotaGranularity::Hour, from, from + Duration::hours(2)), 3);
+        assert_eq!(
+            history_window_count(QuotaGranularity::Hour, from, from + Duration::days(365)),
+            MAX_HISTORY_WINDOWS + 1
+        );
     }
 }
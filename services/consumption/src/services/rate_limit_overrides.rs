@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::models::TierLimits;
+
+/// How long an override, or the lack of one, is cached in Redis
+const OVERRIDE_CACHE_TTL_SECS: u64 = 300;
+
+/// Cached value of a consumer/service pair without an override
+const NO_OVERRIDE: &str = "none";
+
+/// Negotiated rate limit of one consumer on one service, replacing their
+/// tier's rate limit and burst capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitOverride {
+    /// Requests per second
+    pub rate_limit: u64,
+    pub burst_capacity: u32,
+}
+
+impl RateLimitOverride {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rate_limit == 0 {
+            return Err("rate_limit must be positive".to_string());
+        }
+        if self.burst_capacity == 0 {
+            return Err("burst_capacity must be positive".to_string());
+        }
+        if i64::try_from(self.rate_limit).is_err() {
+            return Err("rate_limit is too large".to_string());
+        }
+        if i32::try_from(self.burst_capacity).is_err() {
+            return Err("burst_capacity is too large".to_string());
+        }
+        Ok(())
+    }
+
+    /// Tier limits with this override's rate limit and burst capacity
+    pub fn apply(&self, limits: TierLimits) -> TierLimits {
+        TierLimits {
+            rate_limit: self.rate_limit,
+            burst_capacity: self.burst_capacity,
+            ..limits
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct OverrideRow {
+    rate_limit: i64,
+    burst_capacity: i32,
+}
+
+impl From<OverrideRow> for RateLimitOverride {
+    fn from(row: OverrideRow) -> Self {
+        Self {
+            rate_limit: row.rate_limit as u64,
+            burst_capacity: row.burst_capacity as u32,
+        }
+    }
+}
+
+/// Per-consumer rate limit overrides, stored in Postgres and cached in
+/// Redis so every replica sees a change as soon as it's made
+#[derive(Clone)]
+pub struct RateLimitOverrides {
+    db: Arc<PgPool>,
+    redis: Arc<ConnectionManager>,
+}
+
+impl RateLimitOverrides {
+    pub fn new(db: PgPool, redis: ConnectionManager) -> Self {
+        Self {
+            db: Arc::new(db),
+            redis: Arc::new(redis),
+        }
+    }
+
+    fn cache_key(consumer_id: Uuid, service_id: Uuid) -> String {
+        format!("ratelimit_override:{}:{}", consumer_id, service_id)
+    }
+
+    /// Override of a consumer on a service, from Redis when cached
+    pub async fn get(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+    ) -> Result<Option<RateLimitOverride>> {
+        let key = Self::cache_key(consumer_id, service_id);
+        let mut conn = self.redis.as_ref().clone();
+
+        let cached: Option<String> = conn
+            .get(&key)
+            .await
+            .context("Failed to get cached rate limit override")?;
+        match cached.as_deref() {
+            Some(NO_OVERRIDE) => return Ok(None),
+            Some(cached) => {
+                if let Ok(cached) = serde_json::from_str(cached) {
+                    return Ok(Some(cached));
+                }
+            }
+            None => {}
+        }
+
+        let stored = self.load(consumer_id, service_id).await?;
+        self.cache(&key, stored).await?;
+        Ok(stored)
+    }
+
+    async fn load(&self, consumer_id: Uuid, service_id: Uuid) -> Result<Option<RateLimitOverride>> {
+        let row = sqlx::query_as::<_, OverrideRow>(
+            r#"
+            SELECT rate_limit, burst_capacity
+            FROM rate_limit_overrides
+            WHERE consumer_id = $1 AND service_id = $2
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to get rate limit override")?;
+
+        Ok(row.map(RateLimitOverride::from))
+    }
+
+    async fn cache(&self, key: &str, stored: Option<RateLimitOverride>) -> Result<()> {
+        let value = match stored {
+            Some(stored) => serde_json::to_string(&stored)?,
+            None => NO_OVERRIDE.to_string(),
+        };
+        let mut conn = self.redis.as_ref().clone();
+        conn.set_ex::<_, _, ()>(key, value, OVERRIDE_CACHE_TTL_SECS)
+            .await
+            .context("Failed to cache rate limit override")
+    }
+
+    /// Give a consumer negotiated limits on a service
+    pub async fn set(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        limits: RateLimitOverride,
+    ) -> Result<()> {
+        limits.validate().map_err(anyhow::Error::msg)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO rate_limit_overrides (consumer_id, service_id, rate_limit, burst_capacity, updated_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (consumer_id, service_id) DO UPDATE SET
+                rate_limit = EXCLUDED.rate_limit,
+                burst_capacity = EXCLUDED.burst_capacity,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(limits.rate_limit as i64)
+        .bind(limits.burst_capacity as i32)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store rate limit override")?;
+
+        self.cache(&Self::cache_key(consumer_id, service_id), Some(limits))
+            .await?;
+        info!(
+            consumer_id = %consumer_id,
+            service_id = %service_id,
+            rate_limit = limits.rate_limit,
+            burst_capacity = limits.burst_capacity,
+            "Rate limit override set"
+        );
+        Ok(())
+    }
+
+    /// Put a consumer back on their tier's limits. Returns whether they had
+    /// an override.
+    pub async fn remove(&self, consumer_id: Uuid, service_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM rate_limit_overrides WHERE consumer_id = $1 AND service_id = $2",
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to delete rate limit override")?;
+
+        self.cache(&Self::cache_key(consumer_id, service_id), None)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        info!(
+            consumer_id = %consumer_id,
+            service_id = %service_id,
+            "Rate limit override removed"
+        );
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_replaces_tier_rate_limit() {
+        let limits = TierLimits {
+            rate_limit: 10,
+            burst_capacity: 20,
+            quota_limit: 100_000,
+            overage: Default::default(),
+            parameters: None,
+            max_concurrency: None,
+        };
+        let negotiated = RateLimitOverride {
+            rate_limit: 250,
+            burst_capacity: 400,
+        };
+
+        let applied = negotiated.apply(limits.clone());
+        assert_eq!(applied.rate_limit, 250);
+        assert_eq!(applied.burst_capacity, 400);
+        assert_eq!(applied.quota_limit, limits.quota_limit);
+
+        assert!(negotiated.validate().is_ok());
+        assert!(RateLimitOverride {
+            rate_limit: 0,
+            burst_capacity: 400
+        }
+        .validate()
+        .is_err());
+        assert!(RateLimitOverride {
+            rate_limit: u64::MAX,
+            burst_capacity: 400
+        }
+        .validate()
+        .is_err());
+    }
+}
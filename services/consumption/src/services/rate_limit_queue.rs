@@ -0,0 +1,286 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::middleware::metrics::record;
+use crate::models::{RateLimitStatus, ServiceTier};
+use crate::services::RateLimiter;
+
+/// How often queued requests are checked against their rate limit again
+const QUEUE_TICK: Duration = Duration::from_millis(50);
+
+/// Most queued requests rechecked per tick, highest tier first
+const DEQUEUE_BATCH: usize = 256;
+
+/// Tiers in dequeue order
+const PRIORITY: [ServiceTier; 3] = [
+    ServiceTier::Enterprise,
+    ServiceTier::Premium,
+    ServiceTier::Basic,
+];
+
+/// Rate limit queue settings, read from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitQueueConfig {
+    /// Hold rate-limited requests instead of rejecting them right away
+    pub enabled: bool,
+    /// Requests waiting per tier; rate-limited requests past it are rejected
+    pub capacity: usize,
+    /// Longest a request waits for its rate limit before it's rejected
+    pub max_wait: Duration,
+}
+
+impl Default for RateLimitQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: 1000,
+            max_wait: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RateLimitQueueConfig {
+    /// Read `RATE_LIMIT_QUEUE`, `RATE_LIMIT_QUEUE_CAPACITY` and
+    /// `RATE_LIMIT_QUEUE_MAX_WAIT_MS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+
+        let defaults = Self::default();
+        Self {
+            enabled: var("RATE_LIMIT_QUEUE").unwrap_or(defaults.enabled),
+            capacity: var("RATE_LIMIT_QUEUE_CAPACITY").unwrap_or(defaults.capacity),
+            max_wait: var("RATE_LIMIT_QUEUE_MAX_WAIT_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.max_wait),
+        }
+    }
+}
+
+/// A rate-limited request waiting for its rate limit to admit it
+struct Waiter {
+    consumer_id: Uuid,
+    service_id: Uuid,
+    tier: ServiceTier,
+    deadline: Instant,
+    admitted: oneshot::Sender<RateLimitStatus>,
+}
+
+/// Holds rate-limited requests in a bounded queue per tier and admits them
+/// as their rate limit allows, instead of rejecting them with `429` at once.
+///
+/// One worker per replica rechecks queued requests every tick, Enterprise
+/// first, then Premium, then Basic, so higher tiers get through first when
+/// many requests are waiting. A request is rejected when its tier's queue is
+/// full, or when it's still limited after the max wait.
+#[derive(Clone)]
+pub struct RateLimitQueue {
+    limiter: RateLimiter,
+    config: RateLimitQueueConfig,
+    queues: Arc<Mutex<[VecDeque<Waiter>; 3]>>,
+    queued: Arc<Notify>,
+}
+
+impl RateLimitQueue {
+    pub fn new(limiter: RateLimiter, config: RateLimitQueueConfig) -> Self {
+        Self {
+            limiter,
+            config,
+            queues: Arc::new(Mutex::new(Default::default())),
+            queued: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn priority(tier: &ServiceTier) -> usize {
+        PRIORITY
+            .iter()
+            .position(|t| t == tier)
+            .unwrap_or(PRIORITY.len() - 1)
+    }
+
+    /// Wait for a rate-limited request to be admitted. Returns the status it
+    /// was admitted with, or `status` unchanged if queueing is disabled, its
+    /// tier's queue is full or it wasn't admitted within the max wait.
+    pub async fn wait(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        tier: &ServiceTier,
+        status: RateLimitStatus,
+    ) -> RateLimitStatus {
+        if !self.config.enabled || !status.exceeded {
+            return status;
+        }
+        // Tokens won't be back in time anyway
+        if status
+            .retry_after_seconds
+            .is_some_and(|secs| Duration::from_secs(secs) > self.config.max_wait)
+        {
+            return status;
+        }
+
+        let deadline = Instant::now() + self.config.max_wait;
+        let (admitted, rx) = oneshot::channel();
+        {
+            let mut queues = self.queues.lock().unwrap();
+            let queue = &mut queues[Self::priority(tier)];
+            if queue.len() >= self.config.capacity {
+                return status;
+            }
+            queue.push_back(Waiter {
+                consumer_id,
+                service_id,
+                tier: tier.clone(),
+                deadline,
+                admitted,
+            });
+        }
+        self.queued.notify_one();
+
+        match tokio::time::timeout_at(deadline, rx).await {
+            Ok(Ok(admitted)) => admitted,
+            _ => status,
+        }
+    }
+
+    /// Start the worker admitting queued requests
+    pub fn spawn_worker(&self) -> JoinHandle<()> {
+        info!(
+            capacity = self.config.capacity,
+            max_wait_ms = self.config.max_wait.as_millis() as u64,
+            "Starting rate limit queue"
+        );
+
+        let queue = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let batch = take_ready(
+                    &mut queue.queues.lock().unwrap(),
+                    Instant::now(),
+                    DEQUEUE_BATCH,
+                );
+                if batch.is_empty() {
+                    // Sleep until something is queued, rechecking now and
+                    // then for waiters that timed out
+                    let _ = tokio::time::timeout(QUEUE_TICK * 20, queue.queued.notified()).await;
+                    continue;
+                }
+
+                let mut limited = Vec::new();
+                for waiter in batch {
+                    match queue
+                        .limiter
+                        .check_rate_limit(waiter.consumer_id, waiter.service_id, &waiter.tier)
+                        .await
+                    {
+                        Ok(status) if !status.exceeded => {
+                            let _ = waiter.admitted.send(status);
+                        }
+                        Ok(_) => limited.push(waiter),
+                        Err(e) => {
+                            warn!(error = %e, "Rate limit check of queued request failed");
+                            limited.push(waiter);
+                        }
+                    }
+                }
+
+                // Still limited: back to the front of their queue, in order
+                {
+                    let mut queues = queue.queues.lock().unwrap();
+                    for waiter in limited.into_iter().rev() {
+                        queues[Self::priority(&waiter.tier)].push_front(waiter);
+                    }
+                    for (tier, waiting) in PRIORITY.iter().zip(queues.iter()) {
+                        record::rate_limit_queue_depth(tier.as_str(), waiting.len());
+                    }
+                }
+                tokio::time::sleep(QUEUE_TICK).await;
+            }
+        })
+    }
+}
+
+/// Drop waiters that gave up, then take up to `batch` from the front of the
+/// queues in priority order
+fn take_ready(queues: &mut [VecDeque<Waiter>; 3], now: Instant, batch: usize) -> Vec<Waiter> {
+    let mut ready = Vec::new();
+    for queue in queues.iter_mut() {
+        queue.retain(|waiter| waiter.deadline > now && !waiter.admitted.is_closed());
+        while ready.len() < batch {
+            match queue.pop_front() {
+                Some(waiter) => ready.push(waiter),
+                None => break,
+            }
+        }
+    }
+    ready
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waiter(
+        tier: ServiceTier,
+        deadline: Instant,
+    ) -> (Waiter, oneshot::Receiver<RateLimitStatus>) {
+        let (admitted, rx) = oneshot::channel();
+        let waiter = Waiter {
+            consumer_id: Uuid::new_v4(),
+            service_id: Uuid::new_v4(),
+            tier,
+            deadline,
+            admitted,
+        };
+        (waiter, rx)
+    }
+
+    #[tokio::test]
+    async fn test_take_ready_prefers_higher_tiers() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(5);
+        let mut queues: [VecDeque<Waiter>; 3] = Default::default();
+        let mut receivers = Vec::new();
+
+        for tier in [
+            ServiceTier::Basic,
+            ServiceTier::Premium,
+            ServiceTier::Enterprise,
+            ServiceTier::Basic,
+        ] {
+            let (waiter, rx) = waiter(tier.clone(), later);
+            queues[RateLimitQueue::priority(&tier)].push_back(waiter);
+            receivers.push(rx);
+        }
+        // Timed out, and abandoned by its request
+        let (expired, _rx) = waiter(ServiceTier::Enterprise, now);
+        queues[0].push_front(expired);
+        let (abandoned, rx) = waiter(ServiceTier::Premium, later);
+        queues[1].push_front(abandoned);
+        drop(rx);
+
+        let ready = take_ready(&mut queues, now, 3);
+        let tiers: Vec<_> = ready.iter().map(|w| w.tier.clone()).collect();
+        assert_eq!(
+            tiers,
+            vec![
+                ServiceTier::Enterprise,
+                ServiceTier::Premium,
+                ServiceTier::Basic
+            ]
+        );
+        assert_eq!(queues[2].len(), 1);
+        assert!(queues[0].is_empty() && queues[1].is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::models::ServiceTier;
+use crate::services::TierCatalog;
+
+/// Longest traffic window a simulation may replay
+const MAX_WINDOW_DAYS: i64 = 31;
+
+/// Most historical requests a single simulation loads
+pub const MAX_SIMULATED_REQUESTS: i64 = 5_000_000;
+
+/// Consumers listed in a report, most throttled first
+const TOP_THROTTLED: usize = 20;
+
+/// Hypothetical limits to replay historical traffic against
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimulationRequest {
+    /// Tier whose consumers' traffic is replayed
+    pub tier: ServiceTier,
+    /// Requests per second
+    pub rate_limit: u64,
+    pub burst_capacity: u32,
+    /// Restrict the replay to one service
+    #[serde(default)]
+    pub service_id: Option<Uuid>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// Throttling under one limit configuration
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct SimulationOutcome {
+    pub rate_limit: u64,
+    pub burst_capacity: u32,
+    pub throttled: u64,
+    pub throttled_rate: f64,
+    pub consumers_throttled: u64,
+}
+
+/// Throttling of one consumer on one service under the proposed limits
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerThrottle {
+    pub consumer_id: Uuid,
+    pub service_id: Uuid,
+    pub requests: u64,
+    pub throttled: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    pub tier: ServiceTier,
+    pub service_id: Option<Uuid>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub requests: u64,
+    pub consumers: u64,
+    /// Limits currently configured for the tier, for comparison
+    pub current: SimulationOutcome,
+    pub proposed: SimulationOutcome,
+    pub top_throttled: Vec<ConsumerThrottle>,
+}
+
+/// Replays historical traffic against the token bucket offline, so operators
+/// can see how many requests a limit change would have throttled.
+///
+/// Traffic comes from usage records, which only hold requests that were
+/// admitted at the time; demand that was already throttled is not replayed.
+#[derive(Clone)]
+pub struct RateLimitSimulator {
+    db: Arc<PgPool>,
+    tiers: TierCatalog,
+}
+
+impl RateLimitSimulator {
+    pub fn new(db: PgPool, tiers: TierCatalog) -> Self {
+        Self {
+            db: Arc::new(db),
+            tiers,
+        }
+    }
+
+    pub async fn simulate(&self, request: &SimulationRequest) -> Result<SimulationReport> {
+        if request.rate_limit == 0 || request.burst_capacity == 0 {
+            bail!("Invalid simulation: rate_limit and burst_capacity must be positive");
+        }
+        if request.to <= request.from {
+            bail!("Invalid simulation: to must be after from");
+        }
+        if request.to - request.from > Duration::days(MAX_WINDOW_DAYS) {
+            bail!("Invalid simulation: window exceeds {} days", MAX_WINDOW_DAYS);
+        }
+
+        // Ordered by consumer and service so each bucket's requests are contiguous
+        let rows: Vec<(Uuid, Uuid, DateTime<Utc>)> = sqlx::query_as(
+            r#"
+            SELECT u.consumer_id, u.service_id, u.timestamp
+            FROM usage_records_all u
+            WHERE u.timestamp >= $1 AND u.timestamp < $2
+                AND ($3::uuid IS NULL OR u.service_id = $3)
+                AND EXISTS (
+                    SELECT 1 FROM api_keys k
+                    WHERE k.consumer_id = u.consumer_id
+                        AND k.service_id = u.service_id
+                        AND k.tier = $4
+                )
+            ORDER BY u.consumer_id, u.service_id, u.timestamp
+            LIMIT $5
+            "#,
+        )
+        .bind(request.from)
+        .bind(request.to)
+        .bind(request.service_id)
+        .bind(request.tier.as_str())
+        .bind(MAX_SIMULATED_REQUESTS + 1)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load historical traffic")?;
+
+        if rows.len() as i64 > MAX_SIMULATED_REQUESTS {
+            bail!(
+                "Invalid simulation: window holds more than {} requests; narrow it or pick a service",
+                MAX_SIMULATED_REQUESTS
+            );
+        }
+
+        let current = self.tiers.limits(&request.tier);
+        let mut report = SimulationReport {
+            tier: request.tier.clone(),
+            service_id: request.service_id,
+            from: request.from,
+            to: request.to,
+            requests: rows.len() as u64,
+            consumers: 0,
+            current: SimulationOutcome {
+                rate_limit: current.rate_limit,
+                burst_capacity: current.burst_capacity,
+                ..Default::default()
+            },
+            proposed: SimulationOutcome {
+                rate_limit: request.rate_limit,
+                burst_capacity: request.burst_capacity,
+                ..Default::default()
+            },
+            top_throttled: Vec::new(),
+        };
+
+        let mut throttles = Vec::new();
+        for bucket in rows.chunk_by(|a, b| (a.0, a.1) == (b.0, b.1)) {
+            let (consumer_id, service_id, _) = bucket[0];
+            let seconds: Vec<i64> = bucket.iter().map(|(_, _, at)| at.timestamp()).collect();
+
+            let throttled = replay(&seconds, request.rate_limit, request.burst_capacity);
+            let throttled_now = replay(&seconds, current.rate_limit, current.burst_capacity);
+
+            report.consumers += 1;
+            record(&mut report.proposed, throttled);
+            record(&mut report.current, throttled_now);
+
+            if throttled > 0 {
+                throttles.push(ConsumerThrottle {
+                    consumer_id,
+                    service_id,
+                    requests: seconds.len() as u64,
+                    throttled,
+                });
+            }
+        }
+
+        for outcome in [&mut report.proposed, &mut report.current] {
+            if report.requests > 0 {
+                outcome.throttled_rate = outcome.throttled as f64 / report.requests as f64;
+            }
+        }
+
+        throttles.sort_by(|a, b| b.throttled.cmp(&a.throttled));
+        throttles.truncate(TOP_THROTTLED);
+        report.top_throttled = throttles;
+
+        info!(
+            tier = request.tier.as_str(),
+            requests = report.requests,
+            throttled = report.proposed.throttled,
+            "Rate limit simulation completed"
+        );
+
+        Ok(report)
+    }
+}
+
+fn record(outcome: &mut SimulationOutcome, throttled: u64) {
+    outcome.throttled += throttled;
+    if throttled > 0 {
+        outcome.consumers_throttled += 1;
+    }
+}
+
+/// Run one bucket's request times (unix seconds, ascending) through the same
+/// token bucket as `RateLimiter`, returning how many would be throttled
+fn replay(seconds: &[i64], rate: u64, capacity: u32) -> u64 {
+    let capacity = capacity as f64;
+    let mut tokens = capacity;
+    let mut last_update = seconds.first().copied().unwrap_or_default();
+    let mut throttled = 0;
+
+    for &now in seconds {
+        let delta = (now - last_update).max(0) as f64;
+        tokens = capacity.min(tokens + delta * rate as f64);
+        last_update = now;
+
+        if tokens >= 1.0 {
+            tokens -= 1.0;
+        } else {
+            throttled += 1;
+        }
+    }
+
+    throttled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_matches_token_bucket() {
+        // 30 requests in one second against burst 20: 10 throttled
+        assert_eq!(replay(&[100; 30], 10, 20), 10);
+
+        // A second later the bucket has refilled 10 tokens
+        let mut seconds = vec![100; 25];
+        seconds.extend([101; 12]);
+        assert_eq!(replay(&seconds, 10, 20), 5 + 2);
+
+        // Steady traffic under the rate is never throttled
+        let steady: Vec<i64> = (0..600).map(|i| i / 5).collect();
+        assert_eq!(replay(&steady, 10, 20), 0);
+    }
+}
@@ -1,36 +1,206 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
 use redis::{aio::ConnectionManager, AsyncCommands, Script};
-use std::sync::Arc;
-use tracing::{debug, warn};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::models::{RateLimitStatus, ServiceTier};
+use crate::models::{RateLimitAlgorithm, RateLimitStatus, ServiceTier, TierLimits};
+use crate::services::{LimitRampups, RateLimitOverrides, TierCatalog};
 
-/// Redis-backed distributed rate limiter using token bucket algorithm
+/// How long a service's algorithm is cached before it's read again
+const ALGORITHM_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Length of the sliding window, in milliseconds
+const WINDOW_MS: i64 = 60_000;
+
+/// Redis-backed distributed rate limiter. Services use a token bucket
+/// unless configured for a sliding window.
 #[derive(Clone)]
 pub struct RateLimiter {
     redis: Arc<ConnectionManager>,
+    tiers: TierCatalog,
+    rampups: Option<LimitRampups>,
+    overrides: Option<RateLimitOverrides>,
+    db: Option<Arc<PgPool>>,
+    algorithms: Arc<RwLock<HashMap<Uuid, (Instant, RateLimitAlgorithm)>>>,
 }
 
 impl RateLimiter {
-    pub fn new(redis: ConnectionManager) -> Self {
+    pub fn new(redis: ConnectionManager, tiers: TierCatalog) -> Self {
         Self {
             redis: Arc::new(redis),
+            tiers,
+            rampups: None,
+            overrides: None,
+            db: None,
+            algorithms: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    /// Check rate limit using token bucket algorithm
-    /// Returns Ok(RateLimitStatus) if allowed, Err if exceeded
+    fn bucket_key(consumer_id: Uuid, service_id: Uuid) -> String {
+        format!("ratelimit:{}:{}", consumer_id, service_id)
+    }
+
+    fn window_key(consumer_id: Uuid, service_id: Uuid) -> String {
+        format!("ratelimit:{}:{}:window", consumer_id, service_id)
+    }
+
+    /// Scale the limits of consumers whose organization ramps up new ones
+    pub fn with_rampups(mut self, rampups: LimitRampups) -> Self {
+        self.rampups = Some(rampups);
+        self
+    }
+
+    /// Give consumers with a negotiated rate limit their override instead
+    /// of the tier's limits
+    pub fn with_overrides(mut self, overrides: RateLimitOverrides) -> Self {
+        self.overrides = Some(overrides);
+        self
+    }
+
+    /// Read each service's algorithm from the `services` table; without it
+    /// every service uses the token bucket
+    pub fn with_algorithms(mut self, db: PgPool) -> Self {
+        self.db = Some(Arc::new(db));
+        self
+    }
+
+    /// Algorithm a service's rate limit is enforced with, cached for a short
+    /// TTL
+    pub async fn algorithm(&self, service_id: Uuid) -> Result<RateLimitAlgorithm> {
+        let Some(db) = &self.db else {
+            return Ok(RateLimitAlgorithm::default());
+        };
+        if let Some((loaded_at, algorithm)) = self.algorithms.read().unwrap().get(&service_id) {
+            if loaded_at.elapsed() < ALGORITHM_CACHE_TTL {
+                return Ok(*algorithm);
+            }
+        }
+
+        let name: Option<String> =
+            sqlx::query_scalar("SELECT rate_limit_algorithm FROM services WHERE id = $1")
+                .bind(service_id)
+                .fetch_optional(db.as_ref())
+                .await
+                .context("Failed to load rate limit algorithm")?;
+
+        let algorithm = match name.as_deref().map(RateLimitAlgorithm::from_name) {
+            Some(Some(algorithm)) => algorithm,
+            Some(None) => {
+                warn!(service_id = %service_id, algorithm = ?name, "Unknown rate limit algorithm");
+                RateLimitAlgorithm::default()
+            }
+            None => RateLimitAlgorithm::default(),
+        };
+        self.algorithms
+            .write()
+            .unwrap()
+            .insert(service_id, (Instant::now(), algorithm));
+
+        Ok(algorithm)
+    }
+
+    /// Switch a service's algorithm; other replicas pick it up within the
+    /// cache TTL
+    pub async fn set_algorithm(
+        &self,
+        service_id: Uuid,
+        algorithm: RateLimitAlgorithm,
+    ) -> Result<()> {
+        let Some(db) = &self.db else {
+            anyhow::bail!("Rate limit algorithms aren't configurable");
+        };
+
+        let result = sqlx::query("UPDATE services SET rate_limit_algorithm = $2 WHERE id = $1")
+            .bind(service_id)
+            .bind(algorithm.as_str())
+            .execute(db.as_ref())
+            .await
+            .context("Failed to update rate limit algorithm")?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Service {} not found", service_id);
+        }
+
+        self.algorithms
+            .write()
+            .unwrap()
+            .insert(service_id, (Instant::now(), algorithm));
+
+        info!(
+            service_id = %service_id,
+            algorithm = algorithm.as_str(),
+            "Rate limit algorithm updated"
+        );
+
+        Ok(())
+    }
+
+    /// Limits of a consumer on a service: their override if they have one,
+    /// else the tier's limits, reduced while they ramp up. Overrides are
+    /// negotiated limits and aren't ramped.
+    async fn limits(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        tier: &ServiceTier,
+    ) -> Result<TierLimits> {
+        let limits = self.tiers.limits(tier);
+        if let Some(overrides) = &self.overrides {
+            if let Some(negotiated) = overrides.get(consumer_id, service_id).await? {
+                return Ok(negotiated.apply(limits));
+            }
+        }
+        match &self.rampups {
+            Some(rampups) => Ok(rampups.ramp(consumer_id, limits).await?.0),
+            None => Ok(limits),
+        }
+    }
+
+    /// Check rate limit with the service's algorithm, counting one request
+    /// if allowed
     pub async fn check_rate_limit(
         &self,
         consumer_id: Uuid,
         service_id: Uuid,
         tier: &ServiceTier,
     ) -> Result<RateLimitStatus> {
-        let key = format!("ratelimit:{}:{}", consumer_id, service_id);
-        let rate = tier.rate_limit();
-        let capacity = tier.burst_capacity();
+        let limits = self.limits(consumer_id, service_id, tier).await?;
+        let status = match self.algorithm(service_id).await? {
+            RateLimitAlgorithm::TokenBucket => {
+                self.take_token(consumer_id, service_id, &limits).await?
+            }
+            RateLimitAlgorithm::SlidingWindow => {
+                self.count_in_window(consumer_id, service_id, &limits, 1)
+                    .await?
+            }
+        };
+
+        debug!(
+            consumer_id = %consumer_id,
+            service_id = %service_id,
+            algorithm = status.algorithm.as_str(),
+            allowed = !status.exceeded,
+            remaining = status.remaining,
+            "Rate limit check"
+        );
+
+        Ok(status)
+    }
+
+    async fn take_token(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        limits: &TierLimits,
+    ) -> Result<RateLimitStatus> {
+        let key = Self::bucket_key(consumer_id, service_id);
+        let rate = limits.rate_limit;
+        let capacity = limits.burst_capacity;
 
         // Token bucket algorithm implemented in Lua for atomicity
         let script = Script::new(
@@ -91,20 +261,93 @@ impl RateLimiter {
 
         let reset_at = Utc::now() + Duration::seconds(60);
 
-        debug!(
-            consumer_id = %consumer_id,
-            service_id = %service_id,
-            allowed = allowed,
-            remaining = remaining,
-            "Rate limit check"
-        );
-
         Ok(RateLimitStatus {
             exceeded: !allowed,
             retry_after_seconds: if allowed { None } else { Some(retry_after) },
             limit: rate,
             remaining,
             reset_at,
+            algorithm: RateLimitAlgorithm::TokenBucket,
+        })
+    }
+
+    /// Sliding window log: every admitted request is a member of a sorted
+    /// set scored by its time, and a request is admitted while fewer than
+    /// `rate_limit * 60` members are younger than the window. `requested` of
+    /// 0 only reads the window.
+    async fn count_in_window(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        limits: &TierLimits,
+        requested: u32,
+    ) -> Result<RateLimitStatus> {
+        let key = Self::window_key(consumer_id, service_id);
+        let limit = limits.rate_limit * (WINDOW_MS / 1000) as u64;
+
+        let script = Script::new(
+            r"
+            local key = KEYS[1]
+            local limit = tonumber(ARGV[1])
+            local window = tonumber(ARGV[2])
+            local now = tonumber(ARGV[3])
+            local requested = tonumber(ARGV[4])
+            local member = ARGV[5]
+
+            -- Forget requests that left the window
+            redis.call('ZREMRANGEBYSCORE', key, '-inf', now - window)
+            local count = redis.call('ZCARD', key)
+
+            local allowed = 0
+            if count < limit then
+                allowed = 1
+                if requested > 0 then
+                    redis.call('ZADD', key, now, member)
+                    redis.call('PEXPIRE', key, window)
+                    count = count + 1
+                end
+            end
+
+            -- Time until the oldest request leaves the window
+            local reset_after = 0
+            local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+            if oldest[2] then
+                reset_after = tonumber(oldest[2]) + window - now
+            end
+
+            return {allowed, limit - count, reset_after}
+            ",
+        );
+
+        let now = Utc::now();
+        let mut conn = self.redis.as_ref().clone();
+
+        let result: Vec<i64> = script
+            .key(&key)
+            .arg(limit)
+            .arg(WINDOW_MS)
+            .arg(now.timestamp_millis())
+            .arg(requested)
+            .arg(Uuid::new_v4().to_string())
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to execute sliding window script")?;
+
+        let allowed = result[0] == 1;
+        let remaining = result[1].max(0) as u32;
+        let reset_after_ms = result[2].max(0);
+
+        Ok(RateLimitStatus {
+            exceeded: !allowed,
+            retry_after_seconds: if allowed {
+                None
+            } else {
+                Some(((reset_after_ms + 999) / 1000).max(1) as u64)
+            },
+            limit,
+            remaining,
+            reset_at: now + Duration::milliseconds(reset_after_ms),
+            algorithm: RateLimitAlgorithm::SlidingWindow,
         })
     }
 
@@ -114,10 +357,13 @@ impl RateLimiter {
         consumer_id: Uuid,
         service_id: Uuid,
     ) -> Result<()> {
-        let key = format!("ratelimit:{}:{}", consumer_id, service_id);
+        let keys = [
+            Self::bucket_key(consumer_id, service_id),
+            Self::window_key(consumer_id, service_id),
+        ];
         let mut conn = self.redis.as_ref().clone();
 
-        conn.del(&key)
+        conn.del(&keys[..])
             .await
             .context("Failed to reset rate limit")?;
 
@@ -137,7 +383,14 @@ impl RateLimiter {
         service_id: Uuid,
         tier: &ServiceTier,
     ) -> Result<RateLimitStatus> {
-        let key = format!("ratelimit:{}:{}", consumer_id, service_id);
+        let limits = self.limits(consumer_id, service_id, tier).await?;
+        if self.algorithm(service_id).await? == RateLimitAlgorithm::SlidingWindow {
+            return self
+                .count_in_window(consumer_id, service_id, &limits, 0)
+                .await;
+        }
+
+        let key = Self::bucket_key(consumer_id, service_id);
         let mut conn = self.redis.as_ref().clone();
 
         let bucket: Vec<Option<String>> = conn
@@ -148,16 +401,17 @@ impl RateLimiter {
         let tokens = bucket[0]
             .as_ref()
             .and_then(|s| s.parse::<f64>().ok())
-            .unwrap_or(tier.burst_capacity() as f64);
+            .unwrap_or(limits.burst_capacity as f64);
 
         let reset_at = Utc::now() + Duration::seconds(60);
 
         Ok(RateLimitStatus {
             exceeded: tokens < 1.0,
             retry_after_seconds: None,
-            limit: tier.rate_limit(),
+            limit: limits.rate_limit,
             remaining: tokens as u32,
             reset_at,
+            algorithm: RateLimitAlgorithm::TokenBucket,
         })
     }
 }
@@ -180,7 +434,8 @@ mod tests {
             .await
             .unwrap();
 
-        let limiter = RateLimiter::new(redis);
+        let db = sqlx::PgPool::connect_lazy("postgres://localhost").unwrap();
+        let limiter = RateLimiter::new(redis, TierCatalog::new(db));
         let consumer_id = Uuid::new_v4();
         let service_id = Uuid::new_v4();
         let tier = ServiceTier::Basic;
@@ -198,4 +453,74 @@ mod tests {
             .await
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn test_sliding_window() {
+        // This test requires Redis to be running
+        // Skip in CI environments
+        if std::env::var("CI").is_ok() {
+            return;
+        }
+
+        let redis = redis::Client::open("redis://localhost:6379")
+            .unwrap()
+            .get_tokio_connection_manager()
+            .await
+            .unwrap();
+
+        let db = sqlx::PgPool::connect_lazy("postgres://localhost").unwrap();
+        let limiter = RateLimiter::new(redis, TierCatalog::new(db));
+        let consumer_id = Uuid::new_v4();
+        let service_id = Uuid::new_v4();
+        let limits = TierLimits {
+            rate_limit: 1,
+            ..ServiceTier::Basic.default_limits()
+        };
+
+        // 60 requests a minute, regardless of the burst capacity
+        for _ in 0..60 {
+            let status = limiter
+                .count_in_window(consumer_id, service_id, &limits, 1)
+                .await
+                .unwrap();
+            assert!(!status.exceeded);
+        }
+        let status = limiter
+            .count_in_window(consumer_id, service_id, &limits, 1)
+            .await
+            .unwrap();
+        assert!(status.exceeded);
+        assert_eq!(status.limit, 60);
+        assert_eq!(status.remaining, 0);
+        assert_eq!(status.algorithm, RateLimitAlgorithm::SlidingWindow);
+        assert!(status.retry_after_seconds.unwrap() <= 60);
+
+        limiter
+            .reset_rate_limit(consumer_id, service_id)
+            .await
+            .unwrap();
+        let status = limiter
+            .count_in_window(consumer_id, service_id, &limits, 0)
+            .await
+            .unwrap();
+        assert_eq!(status.remaining, 60);
+    }
+
+    #[test]
+    fn test_algorithm_names() {
+        for algorithm in [
+            RateLimitAlgorithm::TokenBucket,
+            RateLimitAlgorithm::SlidingWindow,
+        ] {
+            assert_eq!(
+                RateLimitAlgorithm::from_name(algorithm.as_str()),
+                Some(algorithm)
+            );
+            assert_eq!(
+                serde_json::to_value(algorithm).unwrap(),
+                serde_json::json!(algorithm.as_str())
+            );
+        }
+        assert_eq!(RateLimitAlgorithm::from_name("leaky_bucket"), None);
+    }
 }
@@ -0,0 +1,377 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+use crate::middleware::metrics::record;
+
+/// Most keys counted per pattern; larger families are reported as truncated
+const MAX_SCANNED_KEYS: u64 = 1_000_000;
+
+/// Keys per pattern whose TTL and memory are sampled
+const SAMPLE_SIZE: usize = 500;
+
+/// Growth between audits that triggers a warning, as a ratio of key counts
+const DEFAULT_GROWTH_WARN_RATIO: f64 = 1.5;
+
+/// Families smaller than this are not checked for growth
+const GROWTH_MIN_KEYS: u64 = 1000;
+
+/// Redis key family written by the service, with the longest TTL it sets
+#[derive(Debug, Clone, Copy)]
+pub struct KeyPattern {
+    pub name: &'static str,
+    pub pattern: &'static str,
+    pub max_ttl_secs: i64,
+}
+
+/// Every family the service writes; each must expire on its own
+pub const KEY_PATTERNS: [KeyPattern; 5] = [
+    // Token buckets, expired after an hour idle by the rate limit script, and
+    // sliding windows, expired a minute after their last request
+    KeyPattern {
+        name: "ratelimit",
+        pattern: "ratelimit:*",
+        max_ttl_secs: 3600,
+    },
+    // In-flight request slots, expired with their 15 minute lease
+    KeyPattern {
+        name: "concurrency",
+        pattern: "concurrency:*",
+        max_ttl_secs: 900,
+    },
+    // Quota counters, expiring at the next reset; a period bridging a
+    // billing anchor change can end up to two months out
+    KeyPattern {
+        name: "quota",
+        pattern: "quota:*",
+        max_ttl_secs: 62 * 86_400,
+    },
+    // Cached provider responses, expiring after their service's cache TTL
+    KeyPattern {
+        name: "response_cache",
+        pattern: "response_cache:*",
+        max_ttl_secs: 86_400,
+    },
+    // Daily per-service metering ledgers (LEDGER_RETENTION_DAYS)
+    KeyPattern {
+        name: "metering_ledger",
+        pattern: "metering:quota:*",
+        max_ttl_secs: 40 * 86_400,
+    },
+];
+
+/// Sampled keys by remaining TTL
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TtlDistribution {
+    pub no_ttl: u64,
+    pub under_1m: u64,
+    pub under_1h: u64,
+    pub under_1d: u64,
+    pub under_7d: u64,
+    pub over_7d: u64,
+}
+
+impl TtlDistribution {
+    /// Count one key by its `TTL` reply (-1 = no expiry, -2 = gone)
+    fn record(&mut self, ttl: i64) {
+        match ttl {
+            -2 => {}
+            t if t < 0 => self.no_ttl += 1,
+            t if t < 60 => self.under_1m += 1,
+            t if t < 3600 => self.under_1h += 1,
+            t if t < 86_400 => self.under_1d += 1,
+            t if t < 7 * 86_400 => self.under_7d += 1,
+            _ => self.over_7d += 1,
+        }
+    }
+}
+
+/// Audit of one key family
+#[derive(Debug, Clone, Serialize)]
+pub struct PatternAudit {
+    pub name: &'static str,
+    pub pattern: &'static str,
+    pub keys: u64,
+    /// Counting stopped at the scan limit; `keys` is a lower bound
+    pub truncated: bool,
+    pub sampled: u64,
+    pub sampled_memory_bytes: u64,
+    /// Sampled average memory times key count
+    pub estimated_memory_bytes: u64,
+    pub ttl: TtlDistribution,
+    /// Sampled keys whose TTL exceeds what the service ever sets
+    pub ttl_over_max: u64,
+    /// Key count relative to the previous audit
+    pub growth_ratio: Option<f64>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RedisAuditReport {
+    pub audited_at: DateTime<Utc>,
+    pub total_keys: u64,
+    pub used_memory_bytes: Option<u64>,
+    /// Keys matching none of the known patterns
+    pub unaccounted_keys: Option<u64>,
+    pub patterns: Vec<PatternAudit>,
+}
+
+impl RedisAuditReport {
+    pub fn warnings(&self) -> usize {
+        self.patterns.iter().map(|p| p.warnings.len()).sum()
+    }
+}
+
+/// Samples the service's Redis keyspace per key family and warns on signs of
+/// a TTL bug or leak: keys that never expire, TTLs longer than the service
+/// sets, and families growing quickly between audits
+#[derive(Clone)]
+pub struct RedisAudit {
+    redis: Arc<ConnectionManager>,
+    growth_warn_ratio: f64,
+    last: Arc<RwLock<Option<RedisAuditReport>>>,
+}
+
+impl RedisAudit {
+    pub fn new(redis: ConnectionManager) -> Self {
+        let growth_warn_ratio = std::env::var("REDIS_AUDIT_GROWTH_WARN_RATIO")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|ratio: &f64| *ratio > 1.0)
+            .unwrap_or(DEFAULT_GROWTH_WARN_RATIO);
+
+        Self {
+            redis: Arc::new(redis),
+            growth_warn_ratio,
+            last: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Most recent audit, if one has run
+    pub fn last_report(&self) -> Option<RedisAuditReport> {
+        self.last.read().unwrap().clone()
+    }
+
+    /// Audit every key family, publish gauges and keep the report
+    pub async fn run(&self) -> Result<RedisAuditReport> {
+        let mut conn = self.redis.as_ref().clone();
+        let previous = self.last_report();
+
+        let total_keys: u64 = redis::cmd("DBSIZE")
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read Redis DBSIZE")?;
+        let info: String = redis::cmd("INFO")
+            .arg("memory")
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read Redis memory info")?;
+
+        let mut patterns = Vec::with_capacity(KEY_PATTERNS.len());
+        for pattern in KEY_PATTERNS {
+            let previous_keys = previous
+                .as_ref()
+                .and_then(|r| r.patterns.iter().find(|p| p.name == pattern.name))
+                .map(|p| p.keys);
+
+            let mut audit = self.audit_pattern(&mut conn, pattern).await?;
+            audit.growth_ratio = previous_keys
+                .filter(|keys| *keys > 0)
+                .map(|keys| audit.keys as f64 / keys as f64);
+            audit.warnings = assess(&audit, pattern, self.growth_warn_ratio);
+
+            for warning in &audit.warnings {
+                warn!(pattern = pattern.pattern, "Redis audit: {}", warning);
+            }
+            record::redis_keys(
+                pattern.name,
+                audit.keys,
+                audit.estimated_memory_bytes,
+                audit.ttl.no_ttl,
+            );
+
+            patterns.push(audit);
+        }
+
+        let accounted: u64 = patterns.iter().map(|p| p.keys).sum();
+        let truncated = patterns.iter().any(|p| p.truncated);
+
+        let report = RedisAuditReport {
+            audited_at: Utc::now(),
+            total_keys,
+            used_memory_bytes: parse_used_memory(&info),
+            unaccounted_keys: (!truncated).then(|| total_keys.saturating_sub(accounted)),
+            patterns,
+        };
+
+        info!(
+            total_keys = report.total_keys,
+            used_memory_bytes = ?report.used_memory_bytes,
+            warnings = report.warnings(),
+            "Redis audit completed"
+        );
+
+        *self.last.write().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Count a family with SCAN and sample TTL and memory of its first keys
+    async fn audit_pattern(
+        &self,
+        conn: &mut ConnectionManager,
+        pattern: KeyPattern,
+    ) -> Result<PatternAudit> {
+        let mut cursor: u64 = 0;
+        let mut keys = 0;
+        let mut sample: Vec<String> = Vec::with_capacity(SAMPLE_SIZE);
+
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(pattern.pattern)
+                .arg("COUNT")
+                .arg(1000)
+                .query_async(conn)
+                .await
+                .with_context(|| format!("Failed to scan {}", pattern.pattern))?;
+
+            keys += batch.len() as u64;
+            let room = SAMPLE_SIZE - sample.len();
+            sample.extend(batch.into_iter().take(room));
+
+            cursor = next;
+            if cursor == 0 || keys >= MAX_SCANNED_KEYS {
+                break;
+            }
+        }
+
+        let mut pipe = redis::pipe();
+        for key in &sample {
+            pipe.cmd("TTL").arg(key);
+            pipe.cmd("MEMORY").arg("USAGE").arg(key);
+        }
+        let replies: Vec<Option<i64>> = if sample.is_empty() {
+            Vec::new()
+        } else {
+            pipe.query_async(conn)
+                .await
+                .with_context(|| format!("Failed to sample {}", pattern.pattern))?
+        };
+
+        let mut ttl = TtlDistribution::default();
+        let mut ttl_over_max = 0;
+        let mut sampled_memory_bytes = 0;
+        for reply in replies.chunks(2) {
+            let key_ttl = reply[0].unwrap_or(-2);
+            ttl.record(key_ttl);
+            if key_ttl > pattern.max_ttl_secs {
+                ttl_over_max += 1;
+            }
+            sampled_memory_bytes += reply.get(1).copied().flatten().unwrap_or(0).max(0) as u64;
+        }
+
+        let sampled = sample.len() as u64;
+        let estimated_memory_bytes = sampled_memory_bytes
+            .checked_div(sampled)
+            .map_or(0, |average| average * keys);
+
+        Ok(PatternAudit {
+            name: pattern.name,
+            pattern: pattern.pattern,
+            keys,
+            truncated: cursor != 0,
+            sampled,
+            sampled_memory_bytes,
+            estimated_memory_bytes,
+            ttl,
+            ttl_over_max,
+            growth_ratio: None,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+/// Leak and TTL-bug warnings for one family
+fn assess(audit: &PatternAudit, pattern: KeyPattern, growth_warn_ratio: f64) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if audit.ttl.no_ttl > 0 {
+        warnings.push(format!(
+            "{} of {} sampled keys have no TTL and will never expire",
+            audit.ttl.no_ttl, audit.sampled
+        ));
+    }
+    if audit.ttl_over_max > 0 {
+        warnings.push(format!(
+            "{} of {} sampled keys expire later than the service's {}s maximum",
+            audit.ttl_over_max, audit.sampled, pattern.max_ttl_secs
+        ));
+    }
+    if let Some(ratio) = audit.growth_ratio {
+        if ratio >= growth_warn_ratio && audit.keys >= GROWTH_MIN_KEYS {
+            warnings.push(format!(
+                "key count grew {:.1}x since the last audit (now {})",
+                ratio, audit.keys
+            ));
+        }
+    }
+    if audit.truncated {
+        warnings.push(format!(
+            "more than {} keys; count is a lower bound",
+            MAX_SCANNED_KEYS
+        ));
+    }
+
+    warnings
+}
+
+fn parse_used_memory(info: &str) -> Option<u64> {
+    info.lines()
+        .find_map(|line| line.strip_prefix("used_memory:"))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_flags_missing_ttl_and_growth() {
+        let mut ttl = TtlDistribution::default();
+        for reply in [-1, -1, 30, 1800, 2_000_000, -2] {
+            ttl.record(reply);
+        }
+        assert_eq!(ttl.no_ttl, 2);
+        assert_eq!(ttl.under_1m, 1);
+        assert_eq!(ttl.under_1h, 1);
+        assert_eq!(ttl.over_7d, 1);
+
+        let audit = PatternAudit {
+            name: "ratelimit",
+            pattern: "ratelimit:*",
+            keys: 5000,
+            truncated: false,
+            sampled: 5,
+            sampled_memory_bytes: 500,
+            estimated_memory_bytes: 500_000,
+            ttl,
+            ttl_over_max: 1,
+            growth_ratio: Some(2.0),
+            warnings: Vec::new(),
+        };
+
+        let warnings = assess(&audit, KEY_PATTERNS[0], DEFAULT_GROWTH_WARN_RATIO);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("no TTL"));
+        assert!(warnings[2].contains("grew 2.0x"));
+
+        assert_eq!(
+            parse_used_memory("# Memory\r\nused_memory:1048576\r\n"),
+            Some(1_048_576)
+        );
+    }
+}
@@ -1,24 +1,58 @@
 use anyhow::{Context, Result};
-use reqwest::{Client, StatusCode};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use hyper::body::Bytes;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, RequestBuilder, StatusCode};
 use serde_json::Value;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
+use std::task::{Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
 use tracing::{debug, error, warn};
 use uuid::Uuid;
 
+use crate::middleware::metrics::UPSTREAM_REQUESTS_IN_FLIGHT;
 use crate::models::{ConsumeRequest, Service, UsageInfo};
+use crate::services::{CredentialVault, EndpointBalancer, UpstreamRecorder};
+
+/// Maximum idle connections kept per provider host
+pub const UPSTREAM_POOL_SIZE: usize = 100;
+
+/// Longest a streamed response may run; the service's SLA timeout only
+/// applies until the provider starts responding
+pub const STREAM_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Tracks a provider request for the in-flight gauge until dropped
+struct UpstreamGuard;
+
+impl UpstreamGuard {
+    fn new() -> Self {
+        UPSTREAM_REQUESTS_IN_FLIGHT.inc();
+        Self
+    }
+}
+
+impl Drop for UpstreamGuard {
+    fn drop(&mut self) {
+        UPSTREAM_REQUESTS_IN_FLIGHT.dec();
+    }
+}
 
 /// Request router for proxying requests to LLM services
 #[derive(Clone)]
 pub struct RequestRouter {
     client: Arc<Client>,
+    credentials: Option<CredentialVault>,
+    recorder: Option<UpstreamRecorder>,
+    balancer: Option<EndpointBalancer>,
 }
 
 impl RequestRouter {
     pub fn new() -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
-            .pool_max_idle_per_host(100)
+            .pool_max_idle_per_host(UPSTREAM_POOL_SIZE)
             .pool_idle_timeout(Duration::from_secs(90))
             .tcp_keepalive(Duration::from_secs(60))
             .build()
@@ -26,9 +60,43 @@ impl RequestRouter {
 
         Self {
             client: Arc::new(client),
+            credentials: None,
+            recorder: None,
+            balancer: None,
         }
     }
 
+    /// Inject each service's stored provider credential into its requests
+    pub fn with_credentials(mut self, vault: CredentialVault) -> Self {
+        self.credentials = Some(vault);
+        self
+    }
+
+    /// Record sanitized upstream calls as fixtures; non-production only
+    pub fn with_recorder(mut self, recorder: UpstreamRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Spread requests over services' registered endpoints, failing over
+    /// between them
+    pub fn with_balancer(mut self, balancer: EndpointBalancer) -> Self {
+        self.balancer = Some(balancer);
+        self
+    }
+
+    /// Endpoints to send a request for the service to, in order of preference
+    async fn endpoints(&self, service: &Service) -> Result<Vec<String>> {
+        let Some(balancer) = &self.balancer else {
+            return Ok(vec![service.endpoint.clone()]);
+        };
+        let endpoints = balancer.candidates(service).await?;
+        if endpoints.is_empty() {
+            anyhow::bail!("No endpoint of service {} is available", service.id);
+        }
+        Ok(endpoints)
+    }
+
     /// Route a request to the LLM service
     pub async fn route_request(
         &self,
@@ -37,7 +105,21 @@ impl RequestRouter {
         request_id: Uuid,
         consumer_id: Uuid,
     ) -> Result<(Value, UsageInfo, u64)> {
-        let start = std::time::Instant::now();
+        let timeout = Duration::from_millis(service.sla.0.timeout_ms);
+        self.send_request(service, request, request_id, consumer_id, timeout)
+            .await
+    }
+
+    async fn send_request(
+        &self,
+        service: &Service,
+        request: &ConsumeRequest,
+        request_id: Uuid,
+        consumer_id: Uuid,
+        timeout: Duration,
+    ) -> Result<(Value, UsageInfo, u64)> {
+        let _upstream = UpstreamGuard::new();
+        let start = Instant::now();
 
         debug!(
             service_id = %service.id,
@@ -46,23 +128,13 @@ impl RequestRouter {
             "Routing request to LLM service"
         );
 
-        // Build request payload
-        let payload = serde_json::json!({
-            "prompt": request.prompt,
-            "max_tokens": request.max_tokens,
-            "temperature": request.temperature,
-            "metadata": request.metadata,
-        });
+        let payload = Self::payload(request);
+        let upstream = self
+            .upstream_request(service, &payload, request_id, consumer_id, timeout)
+            .await?;
 
         // Make request with retries
-        let response = self
-            .client
-            .post(&service.endpoint)
-            .header("X-Request-ID", request_id.to_string())
-            .header("X-Consumer-ID", consumer_id.to_string())
-            .header("Content-Type", "application/json")
-            .timeout(Duration::from_millis(service.sla.0.timeout_ms))
-            .json(&payload)
+        let response = upstream
             .send()
             .await
             .context("Failed to send request to LLM service")?;
@@ -83,6 +155,20 @@ impl RequestRouter {
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
 
+            if let Some(recorder) = &self.recorder {
+                let recorded = serde_json::from_str(&error_body)
+                    .unwrap_or_else(|_| Value::String(error_body.clone()));
+                recorder
+                    .record(
+                        service.id,
+                        &service.endpoint,
+                        &payload,
+                        status.as_u16(),
+                        &recorded,
+                    )
+                    .await;
+            }
+
             anyhow::bail!("LLM service error: {} - {}", status, error_body);
         }
 
@@ -91,6 +177,18 @@ impl RequestRouter {
             .await
             .context("Failed to parse LLM service response")?;
 
+        if let Some(recorder) = &self.recorder {
+            recorder
+                .record(
+                    service.id,
+                    &service.endpoint,
+                    &payload,
+                    status.as_u16(),
+                    &body,
+                )
+                .await;
+        }
+
         // Extract usage information
         let usage = self.extract_usage(&body)?;
 
@@ -105,13 +203,163 @@ impl RequestRouter {
         Ok((body, usage, latency_ms))
     }
 
+    /// Route a request to the LLM service, streaming its response back
+    ///
+    /// The provider is asked to stream (`"stream": true`) and its chunked or
+    /// SSE response is passed through as it arrives, counting usage from the
+    /// events on the way; see [`StreamUsage`]. The service's SLA timeout
+    /// applies until the response starts, [`STREAM_TIMEOUT`] to the whole
+    /// stream. Streams aren't retried or recorded as fixtures.
+    pub async fn route_request_streaming(
+        &self,
+        service: &Service,
+        request: &ConsumeRequest,
+        request_id: Uuid,
+        consumer_id: Uuid,
+    ) -> Result<UpstreamStream> {
+        let upstream_guard = UpstreamGuard::new();
+        let start = Instant::now();
+
+        debug!(
+            service_id = %service.id,
+            request_id = %request_id,
+            endpoint = %service.endpoint,
+            "Streaming request from LLM service"
+        );
+
+        // Streams go to the balancer's pick; they aren't retried elsewhere
+        let endpoint = self.endpoints(service).await?.swap_remove(0);
+        let routed;
+        let service = if endpoint == service.endpoint {
+            service
+        } else {
+            routed = Service {
+                endpoint,
+                ..service.clone()
+            };
+            &routed
+        };
+
+        let mut payload = Self::payload(request);
+        payload["stream"] = true.into();
+        let upstream = self
+            .upstream_request(service, &payload, request_id, consumer_id, STREAM_TIMEOUT)
+            .await?;
+
+        let first_byte_timeout = Duration::from_millis(service.sla.0.timeout_ms);
+        let response = tokio::time::timeout(first_byte_timeout, upstream.send())
+            .await
+            .context("LLM service did not start responding in time")?
+            .context("Failed to send request to LLM service")?;
+
+        let status = response.status();
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        if !status.is_success() {
+            error!(
+                service_id = %service.id,
+                request_id = %request_id,
+                status = %status,
+                "LLM service returned error"
+            );
+
+            let error_body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            anyhow::bail!("LLM service error: {} - {}", status, error_body);
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("text/event-stream")
+            .to_string();
+        let (done, usage) = oneshot::channel();
+
+        Ok(UpstreamStream {
+            content_type,
+            latency_ms,
+            body: MeteredStream {
+                chunks: response.bytes_stream().boxed(),
+                meter: Some((StreamUsage::default(), done)),
+                start,
+                _upstream: upstream_guard,
+            },
+            usage,
+        })
+    }
+
+    /// Request payload sent to providers
+    fn payload(request: &ConsumeRequest) -> Value {
+        let mut payload = serde_json::json!({
+            "prompt": request.prompt,
+            "max_tokens": request.max_tokens,
+            "temperature": request.temperature,
+            "metadata": request.metadata,
+        });
+        if let Some(n) = request.n {
+            payload["n"] = n.into();
+        }
+        payload
+    }
+
+    /// A provider call with the service's credential injected
+    async fn upstream_request(
+        &self,
+        service: &Service,
+        payload: &Value,
+        request_id: Uuid,
+        consumer_id: Uuid,
+        timeout: Duration,
+    ) -> Result<RequestBuilder> {
+        let mut upstream = self
+            .client
+            .post(&service.endpoint)
+            .header("X-Request-ID", request_id.to_string())
+            .header("X-Consumer-ID", consumer_id.to_string())
+            .header("Content-Type", "application/json")
+            .timeout(timeout)
+            .json(payload);
+
+        // Never fall back to an unauthenticated call when a credential exists
+        // but can't be used
+        if let Some(vault) = &self.credentials {
+            if let Some(auth) = vault.auth_header(service.id, request_id).await? {
+                upstream = upstream.header(auth.name, auth.value);
+            }
+        }
+
+        Ok(upstream)
+    }
+
     /// Route request with circuit breaker pattern
+    ///
+    /// Failed attempts move on to the service's next endpoint, if it has
+    /// several; a single endpoint is retried with exponential backoff.
     pub async fn route_with_circuit_breaker(
         &self,
         service: &Service,
         request: &ConsumeRequest,
         request_id: Uuid,
         consumer_id: Uuid,
+    ) -> Result<(Value, UsageInfo, u64)> {
+        let timeout = Duration::from_millis(service.sla.0.timeout_ms);
+        self.route_with_timeout(service, request, request_id, consumer_id, timeout)
+            .await
+    }
+
+    /// Like [`route_with_circuit_breaker`](Self::route_with_circuit_breaker),
+    /// but each provider call may take up to `timeout` instead of the
+    /// service's SLA timeout. The SLA is still measured against latency.
+    pub async fn route_with_timeout(
+        &self,
+        service: &Service,
+        request: &ConsumeRequest,
+        request_id: Uuid,
+        consumer_id: Uuid,
+        timeout: Duration,
     ) -> Result<(Value, UsageInfo, u64)> {
         // Implement circuit breaker logic
         // For now, just call the basic route_request
@@ -119,24 +367,47 @@ impl RequestRouter {
 
         const MAX_RETRIES: u32 = 3;
         let mut last_error = None;
+        let endpoints = self.endpoints(service).await?;
 
         for attempt in 1..=MAX_RETRIES {
-            match self
-                .route_request(service, request, request_id, consumer_id)
-                .await
-            {
+            let endpoint = &endpoints[(attempt as usize - 1) % endpoints.len()];
+            let routed;
+            let target = if *endpoint == service.endpoint {
+                service
+            } else {
+                routed = Service {
+                    endpoint: endpoint.clone(),
+                    ..service.clone()
+                };
+                &routed
+            };
+
+            let result = self
+                .send_request(target, request, request_id, consumer_id, timeout)
+                .await;
+            if let Some(balancer) = &self.balancer {
+                match &result {
+                    Ok((_, _, latency_ms)) => {
+                        balancer.record(service.id, endpoint, Ok(*latency_ms))
+                    }
+                    Err(e) => balancer.record(service.id, endpoint, Err(&e.to_string())),
+                }
+            }
+
+            match result {
                 Ok(result) => return Ok(result),
                 Err(e) => {
                     warn!(
                         service_id = %service.id,
                         request_id = %request_id,
+                        endpoint = %endpoint,
                         attempt = attempt,
                         error = %e,
                         "Request failed, retrying"
                     );
                     last_error = Some(e);
 
-                    if attempt < MAX_RETRIES {
+                    if attempt < MAX_RETRIES && endpoints.len() == 1 {
                         // Exponential backoff
                         let delay = Duration::from_millis(100 * 2_u64.pow(attempt - 1));
                         tokio::time::sleep(delay).await;
@@ -152,42 +423,165 @@ impl RequestRouter {
     fn extract_usage(&self, response: &Value) -> Result<UsageInfo> {
         // Standard OpenAI-like response format
         if let Some(usage) = response.get("usage") {
-            let prompt_tokens = usage
-                .get("prompt_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0) as u32;
-
-            let completion_tokens = usage
-                .get("completion_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0) as u32;
-
-            let total_tokens = usage
-                .get("total_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or((prompt_tokens + completion_tokens) as u64) as u32;
-
-            return Ok(UsageInfo {
-                prompt_tokens,
-                completion_tokens,
-                total_tokens,
-            });
+            return Ok(usage_info(usage));
         }
 
         // Fallback: estimate based on response
         warn!("No usage information in response, estimating");
 
-        let response_text = response.to_string();
-        let estimated_tokens = (response_text.len() / 4) as u32; // Rough estimate
+        Ok(estimated_usage(response.to_string().len()))
+    }
+}
+
+/// Usage from an OpenAI-like `usage` object
+fn usage_info(usage: &Value) -> UsageInfo {
+    let prompt_tokens = usage
+        .get("prompt_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    let completion_tokens = usage
+        .get("completion_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    let total_tokens = usage
+        .get("total_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or((prompt_tokens + completion_tokens) as u64) as u32;
+
+    UsageInfo {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens,
+    }
+}
+
+/// Completion tokens of a response without usage, from its length
+fn estimated_usage(response_len: usize) -> UsageInfo {
+    let estimated_tokens = (response_len / 4) as u32; // Rough estimate
+
+    UsageInfo {
+        prompt_tokens: 0,
+        completion_tokens: estimated_tokens,
+        total_tokens: estimated_tokens,
+    }
+}
+
+/// Token usage of a streamed response, counted chunk by chunk
+///
+/// Reads SSE `data:` lines and NDJSON lines as JSON events. The last event
+/// with a `usage` object wins, which is where OpenAI-like providers report
+/// usage when streaming. Without one, usage is estimated from the length of
+/// the events, like a buffered response without usage.
+#[derive(Debug, Default)]
+pub struct StreamUsage {
+    /// Start of a line whose end hasn't arrived yet
+    partial: Vec<u8>,
+    reported: Option<UsageInfo>,
+    events_len: usize,
+}
 
-        Ok(UsageInfo {
-            prompt_tokens: 0,
-            completion_tokens: estimated_tokens,
-            total_tokens: estimated_tokens,
+impl StreamUsage {
+    /// Count the events of the next chunk
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.partial.extend_from_slice(chunk);
+        while let Some(end) = self.partial.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            self.read_line(&line);
+        }
+    }
+
+    fn read_line(&mut self, line: &[u8]) {
+        let Ok(line) = std::str::from_utf8(line) else {
+            return;
+        };
+        let line = line.trim();
+        let data = match line.strip_prefix("data:") {
+            Some(data) => data.trim_start(),
+            None if line.starts_with('{') => line,
+            // Other SSE fields, comments and keep-alives
+            None => return,
+        };
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+
+        match event.get("usage").filter(|usage| usage.is_object()) {
+            Some(usage) => self.reported = Some(usage_info(usage)),
+            None => self.events_len += data.len(),
+        }
+    }
+
+    /// Usage of the whole stream, once it ended
+    pub fn finish(mut self) -> UsageInfo {
+        let rest = std::mem::take(&mut self.partial);
+        self.read_line(&rest);
+
+        self.reported.unwrap_or_else(|| {
+            warn!("No usage information in stream, estimating");
+            estimated_usage(self.events_len)
         })
     }
 }
 
+/// A provider's response, streamed
+pub struct UpstreamStream {
+    /// Content type of the provider's response, e.g. `text/event-stream`
+    pub content_type: String,
+    /// Time until the provider started responding
+    pub latency_ms: u64,
+    pub body: MeteredStream,
+    /// Usage and total duration in milliseconds, sent once the body ended or
+    /// was dropped
+    pub usage: oneshot::Receiver<(UsageInfo, u64)>,
+}
+
+/// Response chunks passed through as they arrive, counting usage
+///
+/// When the provider finishes, fails or the client goes away, the usage so
+/// far is sent on [`UpstreamStream::usage`], so interrupted streams are
+/// still metered.
+pub struct MeteredStream {
+    chunks: BoxStream<'static, reqwest::Result<Bytes>>,
+    meter: Option<(StreamUsage, oneshot::Sender<(UsageInfo, u64)>)>,
+    start: Instant,
+    _upstream: UpstreamGuard,
+}
+
+impl MeteredStream {
+    fn finish(&mut self) {
+        if let Some((usage, done)) = self.meter.take() {
+            let elapsed_ms = self.start.elapsed().as_millis() as u64;
+            let _ = done.send((usage.finish(), elapsed_ms));
+        }
+    }
+}
+
+impl Stream for MeteredStream {
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.chunks.poll_next_unpin(cx);
+        match &polled {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if let Some((usage, _)) = &mut self.meter {
+                    usage.feed(chunk);
+                }
+            }
+            Poll::Ready(_) => self.finish(),
+            Poll::Pending => {}
+        }
+        polled
+    }
+}
+
+impl Drop for MeteredStream {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
 impl Default for RequestRouter {
     fn default() -> Self {
         Self::new()
@@ -228,4 +622,23 @@ mod tests {
         let usage = router.extract_usage(&response).unwrap();
         assert!(usage.total_tokens > 0);
     }
+
+    #[test]
+    fn test_stream_usage() {
+        let mut usage = StreamUsage::default();
+        usage.feed(b"data: {\"choices\":[{\"text\":\"Hel\"}]}\n\n: keep-alive\n");
+        usage.feed(b"data: {\"choices\":[{\"text\":\"lo\"}],\"usage\":null}\n\ndata: {\"cho");
+        usage.feed(b"ices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2}}\n\n");
+        usage.feed(b"data: [DONE]\n\n");
+
+        let usage = usage.finish();
+        assert_eq!(usage.prompt_tokens, 5);
+        assert_eq!(usage.completion_tokens, 2);
+        assert_eq!(usage.total_tokens, 7);
+
+        // Without reported usage, the last line needs no trailing newline
+        let mut usage = StreamUsage::default();
+        usage.feed(b"{\"text\":\"Hello world, streamed\"}\n{\"text\":\"!\"}");
+        assert_eq!(usage.finish().total_tokens, (32 + 12) / 4);
+    }
 }
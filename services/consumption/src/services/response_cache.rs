@@ -0,0 +1,279 @@
+use anyhow::{Context, Result};
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::models::{ConsumeRequest, UsageInfo};
+
+const CONFIG_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Longest a response may be cached
+pub const MAX_CACHE_TTL_SECS: u64 = 86_400;
+
+/// Responses larger than this aren't cached
+const MAX_CACHED_BYTES: usize = 512 * 1024;
+
+/// Per-service response cache settings; off unless configured
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// How long a response is served from the cache
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+fn default_ttl_secs() -> u64 {
+    300
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_ttl_secs(),
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_CACHE_TTL_SECS).contains(&self.ttl_secs) {
+            return Err(format!(
+                "ttl_secs must be between 1 and {}",
+                MAX_CACHE_TTL_SECS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A provider response stored for identical requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub response: Value,
+    /// Usage of the request that was routed; hits are served without any
+    pub usage: UsageInfo,
+}
+
+#[derive(Clone)]
+struct CachedConfig {
+    loaded_at: Instant,
+    config: ResponseCacheConfig,
+}
+
+/// Opt-in exact-match cache of provider responses in Redis.
+///
+/// A request is identical to a cached one when its service, prompt,
+/// parameters and metadata all match, after admission has applied the
+/// parameter policy, PII filter and request plugins. Cached entries are
+/// shared by every consumer of the service. Settings live in Postgres and
+/// are cached per replica like the response pipeline's.
+#[derive(Clone)]
+pub struct ResponseCache {
+    db: Arc<PgPool>,
+    redis: Arc<ConnectionManager>,
+    configs: Arc<RwLock<HashMap<Uuid, CachedConfig>>>,
+}
+
+impl ResponseCache {
+    pub fn new(db: PgPool, redis: ConnectionManager) -> Self {
+        Self {
+            db: Arc::new(db),
+            redis: Arc::new(redis),
+            configs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Response cache settings of a service (off when never configured)
+    pub async fn get_config(&self, service_id: Uuid) -> Result<ResponseCacheConfig> {
+        let config: Option<sqlx::types::Json<ResponseCacheConfig>> = sqlx::query_scalar(
+            r#"
+            SELECT config
+            FROM response_caches
+            WHERE service_id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load response cache config")?;
+
+        Ok(config.map(|config| config.0).unwrap_or_default())
+    }
+
+    /// Store a service's cache settings. Cached responses are dropped, so
+    /// none outlive a shorter TTL or a disabled cache.
+    pub async fn set_config(&self, service_id: Uuid, config: &ResponseCacheConfig) -> Result<()> {
+        config.validate().map_err(anyhow::Error::msg)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO response_caches (service_id, config, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (service_id)
+            DO UPDATE SET config = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(service_id)
+        .bind(sqlx::types::Json(config))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store response cache config")?;
+
+        self.configs.write().unwrap().remove(&service_id);
+        self.purge(service_id).await?;
+
+        info!(service_id = %service_id, ?config, "Response cache updated");
+
+        Ok(())
+    }
+
+    async fn config(&self, service_id: Uuid) -> Result<ResponseCacheConfig> {
+        if let Some(cached) = self.configs.read().unwrap().get(&service_id) {
+            if cached.loaded_at.elapsed() < CONFIG_CACHE_TTL {
+                return Ok(cached.config.clone());
+            }
+        }
+
+        let config = self.get_config(service_id).await?;
+        self.configs.write().unwrap().insert(
+            service_id,
+            CachedConfig {
+                loaded_at: Instant::now(),
+                config: config.clone(),
+            },
+        );
+
+        Ok(config)
+    }
+
+    /// Whether responses of the service are cached
+    pub async fn is_enabled(&self, service_id: Uuid) -> Result<bool> {
+        Ok(self.config(service_id).await?.enabled)
+    }
+
+    fn key(service_id: Uuid, request: &ConsumeRequest) -> String {
+        format!("response_cache:{}:{}", service_id, request_hash(request))
+    }
+
+    /// The cached response to an identical request, if there is one
+    pub async fn get(
+        &self,
+        service_id: Uuid,
+        request: &ConsumeRequest,
+    ) -> Result<Option<CachedResponse>> {
+        let mut conn = self.redis.as_ref().clone();
+        let cached: Option<String> = conn
+            .get(Self::key(service_id, request))
+            .await
+            .context("Failed to get cached response")?;
+
+        Ok(cached.and_then(|cached| serde_json::from_str(&cached).ok()))
+    }
+
+    /// Cache a successful provider response for the service's TTL
+    pub async fn put(
+        &self,
+        service_id: Uuid,
+        request: &ConsumeRequest,
+        cached: &CachedResponse,
+    ) -> Result<()> {
+        let config = self.config(service_id).await?;
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let value = serde_json::to_string(cached)?;
+        if value.len() > MAX_CACHED_BYTES {
+            warn!(
+                service_id = %service_id,
+                bytes = value.len(),
+                "Response too large to cache"
+            );
+            return Ok(());
+        }
+
+        let mut conn = self.redis.as_ref().clone();
+        conn.set_ex::<_, _, ()>(Self::key(service_id, request), value, config.ttl_secs)
+            .await
+            .context("Failed to cache response")
+    }
+
+    /// Drop every cached response of a service. Returns how many there were.
+    pub async fn purge(&self, service_id: Uuid) -> Result<usize> {
+        let mut conn = self.redis.as_ref().clone();
+        let keys: Vec<String> = conn
+            .keys(format!("response_cache:{}:*", service_id))
+            .await
+            .context("Failed to scan cached responses")?;
+
+        if !keys.is_empty() {
+            let _: () = conn
+                .del(&keys)
+                .await
+                .context("Failed to delete cached responses")?;
+        }
+
+        info!(service_id = %service_id, purged = keys.len(), "Response cache purged");
+        Ok(keys.len())
+    }
+}
+
+/// Hash of everything sent to the provider for a request
+fn request_hash(request: &ConsumeRequest) -> String {
+    // serde_json maps are sorted, so equal metadata always serializes alike
+    let canonical = serde_json::to_vec(request).unwrap_or_default();
+    hex::encode(Sha256::digest(canonical))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(prompt: &str, temperature: f32) -> ConsumeRequest {
+        serde_json::from_value(serde_json::json!({
+            "prompt": prompt,
+            "max_tokens": 256,
+            "temperature": temperature,
+            "metadata": {"model": "gpt-4", "user": "a"},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_request_hash() {
+        let id = Uuid::nil();
+        assert_eq!(
+            ResponseCache::key(id, &request("Hello", 0.0)),
+            ResponseCache::key(id, &request("Hello", 0.0))
+        );
+        assert_ne!(
+            ResponseCache::key(id, &request("Hello", 0.0)),
+            ResponseCache::key(id, &request("Hello!", 0.0))
+        );
+        assert_ne!(
+            ResponseCache::key(id, &request("Hello", 0.0)),
+            ResponseCache::key(id, &request("Hello", 0.7))
+        );
+        assert_ne!(
+            ResponseCache::key(id, &request("Hello", 0.0)),
+            ResponseCache::key(Uuid::new_v4(), &request("Hello", 0.0))
+        );
+
+        assert!(ResponseCacheConfig::default().validate().is_ok());
+        assert!(ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 0
+        }
+        .validate()
+        .is_err());
+    }
+}
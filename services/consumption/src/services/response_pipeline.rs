@@ -0,0 +1,446 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::middleware::metrics::record;
+use crate::models::ResponseStageTimings;
+use crate::services::content_scan::{ContentScanner, ScanVerdict};
+
+/// How long a service's pipeline config is cached before being reloaded
+const CONFIG_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Words masked by the profanity stage, matched whole and case-insensitively
+const PROFANITY: &[&str] = &[
+    "asshole",
+    "bastard",
+    "bitch",
+    "bullshit",
+    "crap",
+    "cunt",
+    "damn",
+    "dick",
+    "fuck",
+    "fucked",
+    "fucker",
+    "fucking",
+    "motherfucker",
+    "piss",
+    "shit",
+    "shitty",
+];
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_PROFANITY: Regex =
+        profanity_pattern(PROFANITY.iter().copied()).expect("valid profanity pattern");
+
+    static ref CODE_FENCE: Regex =
+        Regex::new(r"(?s)^\s*```[a-zA-Z]*\s*\n(.*?)\n?\s*```\s*$").expect("valid code fence pattern");
+}
+
+fn profanity_pattern<'a>(words: impl Iterator<Item = &'a str>) -> Result<Regex> {
+    let alternatives: Vec<String> = words.map(regex::escape).collect();
+    RegexBuilder::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+        .case_insensitive(true)
+        .build()
+        .context("Invalid profanity word list")
+}
+
+/// Per-service response post-processing; every stage is off by default
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponsePipelineConfig {
+    /// Scan generated text with LLM-Shield, blocking or redacting matches
+    #[serde(default)]
+    pub shield_scan: bool,
+    /// Mask profanity, keeping each word's first letter
+    #[serde(default)]
+    pub profanity_mask: bool,
+    /// Words masked in addition to the built-in list
+    #[serde(default)]
+    pub extra_profanity: Vec<String>,
+    /// Require valid JSON when the request asked for JSON output
+    #[serde(default)]
+    pub json_validation: bool,
+    /// Add a `marketplace` object with model, request id and version
+    #[serde(default)]
+    pub inject_metadata: bool,
+}
+
+impl ResponsePipelineConfig {
+    pub fn is_empty(&self) -> bool {
+        !self.shield_scan && !self.profanity_mask && !self.json_validation && !self.inject_metadata
+    }
+}
+
+/// Request details the stages need
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseContext<'a> {
+    pub service_id: Uuid,
+    pub consumer_id: Uuid,
+    pub request_id: Uuid,
+    pub model: &'a str,
+    /// The request asked for JSON output (`metadata.response_format`)
+    pub json_mode: bool,
+}
+
+/// Whether request metadata asks for JSON output: `"response_format": "json"`
+/// or OpenAI-style `{"type": "json_object"}`
+pub fn wants_json(metadata: &Value) -> bool {
+    match metadata.get("response_format") {
+        Some(Value::String(format)) => format == "json" || format == "json_object",
+        Some(format) => format
+            .get("type")
+            .and_then(Value::as_str)
+            .is_some_and(|kind| kind.starts_with("json")),
+        None => false,
+    }
+}
+
+/// Result of running a service's response pipeline
+#[derive(Debug)]
+pub enum ResponsePipelineOutcome {
+    /// Response (possibly rewritten) may be returned
+    Passed(Value),
+    /// LLM-Shield blocked the generated text
+    Blocked {
+        reason: String,
+        filters: Vec<String>,
+    },
+    /// LLM-Shield couldn't be reached and the service fails closed
+    ScanUnavailable,
+    /// JSON output was requested but the provider's text isn't JSON
+    InvalidJson { reason: String },
+}
+
+/// Generated text of a provider response: `choices[].text`,
+/// `choices[].message.content`, or a top-level `text`/`output`/`content`
+fn response_texts(response: &mut Value) -> Vec<&mut String> {
+    if response.get("choices").is_some_and(Value::is_array) {
+        return response["choices"]
+            .as_array_mut()
+            .into_iter()
+            .flatten()
+            .filter_map(|choice| {
+                let choice = choice.as_object_mut()?;
+                if choice.get("text").is_some_and(Value::is_string) {
+                    return choice.get_mut("text");
+                }
+                choice.get_mut("message")?.get_mut("content")
+            })
+            .filter_map(|text| match text {
+                Value::String(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+    }
+
+    let Some(object) = response.as_object_mut() else {
+        return Vec::new();
+    };
+    object
+        .iter_mut()
+        .filter(|(key, _)| matches!(key.as_str(), "text" | "output" | "content"))
+        .filter_map(|(_, value)| match value {
+            Value::String(text) => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replace all but the first letter of each match with `*`
+fn mask(text: &str, pattern: &Regex) -> Option<String> {
+    if !pattern.is_match(text) {
+        return None;
+    }
+
+    Some(
+        pattern
+            .replace_all(text, |caps: &regex::Captures| {
+                let mut chars = caps[0].chars();
+                let first = chars.next().map(String::from).unwrap_or_default();
+                format!("{}{}", first, "*".repeat(chars.count()))
+            })
+            .into_owned(),
+    )
+}
+
+/// Parse generated text as JSON, unwrapping a Markdown code fence; returns
+/// the normalized text
+fn validate_json(text: &str) -> std::result::Result<String, String> {
+    let body = CODE_FENCE
+        .captures(text)
+        .and_then(|caps| caps.get(1))
+        .map_or(text, |m| m.as_str())
+        .trim();
+
+    serde_json::from_str::<Value>(body)
+        .map(|_| body.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Elapsed time in microseconds, recorded for the stage's histogram
+fn finish_stage(stage: &'static str, started: Instant) -> Option<u64> {
+    let elapsed = started.elapsed();
+    record::response_stage(stage, elapsed);
+    Some(elapsed.as_micros() as u64)
+}
+
+#[derive(Clone)]
+struct CachedConfig {
+    loaded_at: Instant,
+    config: ResponsePipelineConfig,
+    /// Built-in list plus the service's extra words
+    profanity: Option<Regex>,
+}
+
+/// Configurable per-service post-processing of provider responses: Shield
+/// response scan, profanity masking, JSON-mode validation and metadata
+/// injection, in that order
+#[derive(Clone)]
+pub struct ResponsePipeline {
+    db: Arc<PgPool>,
+    scanner: ContentScanner,
+    configs: Arc<RwLock<HashMap<Uuid, CachedConfig>>>,
+}
+
+impl ResponsePipeline {
+    pub fn new(db: PgPool, scanner: ContentScanner) -> Self {
+        Self {
+            db: Arc::new(db),
+            scanner,
+            configs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Pipeline configuration for a service (all stages off when never
+    /// configured)
+    pub async fn get_config(&self, service_id: Uuid) -> Result<ResponsePipelineConfig> {
+        let config: Option<sqlx::types::Json<ResponsePipelineConfig>> = sqlx::query_scalar(
+            r#"
+            SELECT config
+            FROM response_pipelines
+            WHERE service_id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load response pipeline config")?;
+
+        Ok(config.map(|config| config.0).unwrap_or_default())
+    }
+
+    /// Store a service's pipeline configuration; takes effect on all
+    /// replicas within the cache TTL
+    pub async fn set_config(
+        &self,
+        service_id: Uuid,
+        config: &ResponsePipelineConfig,
+    ) -> Result<()> {
+        if config
+            .extra_profanity
+            .iter()
+            .any(|word| word.trim().is_empty())
+        {
+            anyhow::bail!("Invalid pipeline config: extra_profanity contains an empty word");
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO response_pipelines (service_id, config, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (service_id)
+            DO UPDATE SET config = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(service_id)
+        .bind(sqlx::types::Json(config))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store response pipeline config")?;
+
+        self.configs.write().unwrap().remove(&service_id);
+
+        info!(service_id = %service_id, ?config, "Response pipeline updated");
+
+        Ok(())
+    }
+
+    async fn cached_config(&self, service_id: Uuid) -> Result<CachedConfig> {
+        if let Some(cached) = self.configs.read().unwrap().get(&service_id) {
+            if cached.loaded_at.elapsed() < CONFIG_CACHE_TTL {
+                return Ok(cached.clone());
+            }
+        }
+
+        let config = self.get_config(service_id).await?;
+        let profanity = match (config.profanity_mask, config.extra_profanity.is_empty()) {
+            (false, _) => None,
+            (true, true) => Some(DEFAULT_PROFANITY.clone()),
+            (true, false) => {
+                Some(profanity_pattern(PROFANITY.iter().copied().chain(
+                    config.extra_profanity.iter().map(|word| word.trim()),
+                ))?)
+            }
+        };
+        let cached = CachedConfig {
+            loaded_at: Instant::now(),
+            config,
+            profanity,
+        };
+        self.configs
+            .write()
+            .unwrap()
+            .insert(service_id, cached.clone());
+
+        Ok(cached)
+    }
+
+    /// Whether any stage is enabled for the service
+    pub async fn is_enabled(&self, service_id: Uuid) -> Result<bool> {
+        Ok(!self.cached_config(service_id).await?.config.is_empty())
+    }
+
+    /// Run the service's enabled stages over a provider response
+    pub async fn process(
+        &self,
+        context: ResponseContext<'_>,
+        mut response: Value,
+    ) -> Result<(ResponsePipelineOutcome, ResponseStageTimings)> {
+        let cached = self.cached_config(context.service_id).await?;
+        let config = &cached.config;
+        let mut timings = ResponseStageTimings::default();
+        if config.is_empty() {
+            return Ok((ResponsePipelineOutcome::Passed(response), timings));
+        }
+
+        if config.shield_scan {
+            let started = Instant::now();
+            let blocked = self.shield_scan(&context, &mut response).await?;
+            timings.shield_scan_us = finish_stage("shield_scan", started);
+            if let Some(outcome) = blocked {
+                return Ok((outcome, timings));
+            }
+        }
+
+        if let Some(pattern) = &cached.profanity {
+            let started = Instant::now();
+            for text in response_texts(&mut response) {
+                if let Some(masked) = mask(text, pattern) {
+                    *text = masked;
+                }
+            }
+            timings.profanity_mask_us = finish_stage("profanity_mask", started);
+        }
+
+        if config.json_validation && context.json_mode {
+            let started = Instant::now();
+            let mut invalid = None;
+            for text in response_texts(&mut response) {
+                match validate_json(text) {
+                    Ok(normalized) => *text = normalized,
+                    Err(reason) => {
+                        invalid = Some(reason);
+                        break;
+                    }
+                }
+            }
+            timings.json_validation_us = finish_stage("json_validation", started);
+            if let Some(reason) = invalid {
+                return Ok((ResponsePipelineOutcome::InvalidJson { reason }, timings));
+            }
+        }
+
+        if config.inject_metadata {
+            let started = Instant::now();
+            if let Some(object) = response.as_object_mut() {
+                object.insert(
+                    "marketplace".to_string(),
+                    serde_json::json!({
+                        "model": context.model,
+                        "request_id": context.request_id,
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }),
+                );
+            }
+            timings.metadata_us = finish_stage("inject_metadata", started);
+        }
+
+        Ok((ResponsePipelineOutcome::Passed(response), timings))
+    }
+
+    /// Scan each generated text, redacting matches Shield asks to redact;
+    /// returns the outcome when a text is blocked, or Shield is unreachable
+    /// and the service fails closed
+    async fn shield_scan(
+        &self,
+        context: &ResponseContext<'_>,
+        response: &mut Value,
+    ) -> Result<Option<ResponsePipelineOutcome>> {
+        for text in response_texts(response) {
+            let verdict = self
+                .scanner
+                .scan_response(context.service_id, context.consumer_id, text)
+                .await?;
+
+            match verdict {
+                ScanVerdict::Blocked { filters } => {
+                    let reason = format!("blocked by {}", filters.join(", "));
+                    return Ok(Some(ResponsePipelineOutcome::Blocked { reason, filters }));
+                }
+                ScanVerdict::Unavailable => {
+                    return Ok(Some(ResponsePipelineOutcome::ScanUnavailable));
+                }
+                ScanVerdict::Redacted { text: redacted, .. } => *text = redacted,
+                ScanVerdict::Passed | ScanVerdict::Warned { .. } => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_stages() {
+        let mut response = serde_json::json!({
+            "choices": [
+                {"text": "Well, damn. That SHIT is classic."},
+                {"message": {"role": "assistant", "content": "```json\n{\"ok\": true}\n```"}}
+            ],
+            "usage": {"total_tokens": 12}
+        });
+
+        let texts = response_texts(&mut response);
+        assert_eq!(texts.len(), 2);
+
+        let masked = mask("Well, damn. That SHIT is classic.", &DEFAULT_PROFANITY).unwrap();
+        assert_eq!(masked, "Well, d***. That S*** is classic.");
+        assert!(mask("Scunthorpe dickens", &DEFAULT_PROFANITY).is_none());
+
+        assert_eq!(
+            validate_json("```json\n{\"ok\": true}\n```").unwrap(),
+            "{\"ok\": true}"
+        );
+        assert_eq!(validate_json(" [1, 2] ").unwrap(), "[1, 2]");
+        assert!(validate_json("Sure! Here is your JSON: {").is_err());
+
+        assert!(wants_json(
+            &serde_json::json!({"response_format": {"type": "json_object"}})
+        ));
+        assert!(wants_json(&serde_json::json!({"response_format": "json"})));
+        assert!(!wants_json(
+            &serde_json::json!({"response_format": {"type": "text"}})
+        ));
+        assert!(!wants_json(&Value::Null));
+    }
+}
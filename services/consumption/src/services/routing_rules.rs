@@ -0,0 +1,380 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Timelike, Utc};
+use rhai::packages::{Package, StandardPackage};
+use rhai::{Engine, Scope, AST};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// How long compiled rules are cached before being reloaded from the database
+const RULE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Upper bound on interpreter operations per rule evaluation
+const MAX_OPERATIONS: u64 = 10_000;
+
+/// What a matching rule does to the request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoutingAction {
+    /// Send the request to a different endpoint
+    Route { endpoint: String },
+    /// Forward a scheduling priority to the provider
+    Priority { priority: RoutingPriority },
+    /// Force the provider's sandbox mode
+    Sandbox,
+    /// Reject the request at admission
+    Reject { reason: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoutingPriority {
+    Low,
+    Normal,
+    High,
+}
+
+/// Routing rule as stored and edited through the admin API
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RoutingRule {
+    pub name: String,
+    pub condition: String,
+    pub action: sqlx::types::Json<RoutingAction>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Request attributes exposed to rule expressions
+#[derive(Debug, Clone)]
+pub struct RoutingContext {
+    pub tier: String,
+    pub prompt_chars: i64,
+    pub max_tokens: i64,
+    pub model: String,
+    /// UTC hour of day, 0-23
+    pub hour: i64,
+    /// ISO weekday, 1 (Monday) - 7 (Sunday)
+    pub weekday: i64,
+}
+
+impl RoutingContext {
+    pub fn new(tier: &str, prompt: &str, max_tokens: Option<u32>, model: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            tier: tier.to_string(),
+            prompt_chars: prompt.chars().count() as i64,
+            max_tokens: max_tokens.map(i64::from).unwrap_or(0),
+            model: model.to_string(),
+            hour: now.hour() as i64,
+            weekday: now.weekday().number_from_monday() as i64,
+        }
+    }
+
+    fn scope(&self) -> Scope<'static> {
+        let mut scope = Scope::new();
+        scope.push_constant("tier", self.tier.clone());
+        scope.push_constant("prompt_chars", self.prompt_chars);
+        scope.push_constant("max_tokens", self.max_tokens);
+        scope.push_constant("model", self.model.clone());
+        scope.push_constant("hour", self.hour);
+        scope.push_constant("weekday", self.weekday);
+        scope
+    }
+}
+
+/// Combined effect of all matching rules
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RoutingDecision {
+    pub endpoint: Option<String>,
+    pub priority: Option<RoutingPriority>,
+    pub sandbox: bool,
+    pub reject: Option<String>,
+    /// Names of the rules that matched, in evaluation order
+    pub matched: Vec<String>,
+}
+
+struct CompiledRule {
+    name: String,
+    condition: AST,
+    action: RoutingAction,
+}
+
+/// Evaluates per-service routing rules (Rhai expressions) at admission
+#[derive(Clone)]
+pub struct RoutingRulesEngine {
+    db: Arc<PgPool>,
+    engine: Arc<Engine>,
+    rules: Arc<RwLock<HashMap<Uuid, (Instant, Arc<Vec<CompiledRule>>)>>>,
+}
+
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new_raw();
+    engine.register_global_module(StandardPackage::new().as_shared_module());
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_expr_depths(32, 32);
+    engine.set_max_string_size(4096);
+    engine
+}
+
+impl RoutingRulesEngine {
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db: Arc::new(db),
+            engine: Arc::new(sandboxed_engine()),
+            rules: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Evaluate a service's rules against a request
+    pub async fn evaluate(
+        &self,
+        service_id: Uuid,
+        context: &RoutingContext,
+    ) -> Result<RoutingDecision> {
+        let rules = self.compiled_rules(service_id).await?;
+        Ok(evaluate_rules(&self.engine, &rules, context))
+    }
+
+    /// Rules configured for a service, in evaluation order
+    pub async fn list_rules(&self, service_id: Uuid) -> Result<Vec<RoutingRule>> {
+        let rules = sqlx::query_as::<_, RoutingRule>(
+            r#"
+            SELECT name, condition, action, enabled
+            FROM routing_rules
+            WHERE service_id = $1
+            ORDER BY position
+            "#,
+        )
+        .bind(service_id)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list routing rules")?;
+
+        Ok(rules)
+    }
+
+    /// Replace a service's rules; every condition must compile
+    pub async fn replace_rules(&self, service_id: Uuid, rules: &[RoutingRule]) -> Result<()> {
+        for rule in rules {
+            self.compile(rule)?;
+        }
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin routing rules transaction")?;
+
+        sqlx::query("DELETE FROM routing_rules WHERE service_id = $1")
+            .bind(service_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear routing rules")?;
+
+        for (position, rule) in rules.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO routing_rules (service_id, position, name, condition, action, enabled)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(service_id)
+            .bind(position as i32)
+            .bind(&rule.name)
+            .bind(&rule.condition)
+            .bind(&rule.action)
+            .bind(rule.enabled)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store routing rule")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit routing rules")?;
+
+        self.rules.write().unwrap().remove(&service_id);
+
+        info!(service_id = %service_id, rules = rules.len(), "Routing rules updated");
+
+        Ok(())
+    }
+
+    fn compile(&self, rule: &RoutingRule) -> Result<CompiledRule> {
+        let condition = self
+            .engine
+            .compile_expression(&rule.condition)
+            .map_err(|e| anyhow::anyhow!("Rule '{}' does not compile: {}", rule.name, e))?;
+
+        Ok(CompiledRule {
+            name: rule.name.clone(),
+            condition,
+            action: rule.action.0.clone(),
+        })
+    }
+
+    /// Compiled enabled rules for a service, reloaded after the cache TTL
+    async fn compiled_rules(&self, service_id: Uuid) -> Result<Arc<Vec<CompiledRule>>> {
+        if let Some((loaded_at, rules)) = self.rules.read().unwrap().get(&service_id) {
+            if loaded_at.elapsed() < RULE_CACHE_TTL {
+                return Ok(rules.clone());
+            }
+        }
+
+        let compiled: Vec<CompiledRule> = self
+            .list_rules(service_id)
+            .await?
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| match self.compile(rule) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    warn!(service_id = %service_id, error = %e, "Skipping invalid routing rule");
+                    None
+                }
+            })
+            .collect();
+
+        let compiled = Arc::new(compiled);
+        self.rules
+            .write()
+            .unwrap()
+            .insert(service_id, (Instant::now(), compiled.clone()));
+
+        Ok(compiled)
+    }
+}
+
+/// Apply rules in order: the first reject stops evaluation, the first
+/// matching route/priority wins, and any matching sandbox rule applies
+fn evaluate_rules(
+    engine: &Engine,
+    rules: &[CompiledRule],
+    context: &RoutingContext,
+) -> RoutingDecision {
+    let mut decision = RoutingDecision::default();
+
+    for rule in rules {
+        let mut scope = context.scope();
+        let matched = match engine.eval_ast_with_scope::<bool>(&mut scope, &rule.condition) {
+            Ok(matched) => matched,
+            Err(e) => {
+                warn!(rule = %rule.name, error = %e, "Routing rule evaluation failed");
+                continue;
+            }
+        };
+
+        if !matched {
+            continue;
+        }
+
+        debug!(rule = %rule.name, "Routing rule matched");
+        decision.matched.push(rule.name.clone());
+
+        match &rule.action {
+            RoutingAction::Reject { reason } => {
+                decision.reject = Some(reason.clone());
+                break;
+            }
+            RoutingAction::Route { endpoint } => {
+                decision.endpoint.get_or_insert_with(|| endpoint.clone());
+            }
+            RoutingAction::Priority { priority } => {
+                decision.priority.get_or_insert(*priority);
+            }
+            RoutingAction::Sandbox => decision.sandbox = true,
+        }
+    }
+
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, condition: &str, action: RoutingAction) -> CompiledRule {
+        CompiledRule {
+            name: name.to_string(),
+            condition: sandboxed_engine().compile_expression(condition).unwrap(),
+            action,
+        }
+    }
+
+    fn context(tier: &str, prompt_chars: i64, hour: i64) -> RoutingContext {
+        RoutingContext {
+            tier: tier.to_string(),
+            prompt_chars,
+            max_tokens: 256,
+            model: "gpt-small".to_string(),
+            hour,
+            weekday: 3,
+        }
+    }
+
+    #[test]
+    fn test_rules_combine_in_order() {
+        let engine = sandboxed_engine();
+        let rules = vec![
+            rule(
+                "large-prompts-to-batch",
+                "prompt_chars > 10000",
+                RoutingAction::Route {
+                    endpoint: "http://batch".to_string(),
+                },
+            ),
+            rule(
+                "enterprise-first",
+                r#"tier == "enterprise""#,
+                RoutingAction::Priority {
+                    priority: RoutingPriority::High,
+                },
+            ),
+            rule("night-sandbox", "hour < 6", RoutingAction::Sandbox),
+            rule("broken", "tier > 3", RoutingAction::Sandbox),
+        ];
+
+        let decision = evaluate_rules(&engine, &rules, &context("enterprise", 20_000, 12));
+        assert_eq!(decision.endpoint.as_deref(), Some("http://batch"));
+        assert_eq!(decision.priority, Some(RoutingPriority::High));
+        assert!(!decision.sandbox);
+        assert_eq!(
+            decision.matched,
+            vec!["large-prompts-to-batch", "enterprise-first"]
+        );
+
+        let decision = evaluate_rules(&engine, &rules, &context("basic", 100, 3));
+        assert_eq!(decision.endpoint, None);
+        assert!(decision.sandbox);
+    }
+
+    #[test]
+    fn test_reject_stops_evaluation() {
+        let engine = sandboxed_engine();
+        let rules = vec![
+            rule(
+                "basic-no-large",
+                r#"tier == "basic" && prompt_chars > 1000"#,
+                RoutingAction::Reject {
+                    reason: "Prompt too large for tier".to_string(),
+                },
+            ),
+            rule("always-sandbox", "true", RoutingAction::Sandbox),
+        ];
+
+        let decision = evaluate_rules(&engine, &rules, &context("basic", 5000, 12));
+        assert_eq!(
+            decision.reject.as_deref(),
+            Some("Prompt too large for tier")
+        );
+        assert!(!decision.sandbox);
+    }
+}
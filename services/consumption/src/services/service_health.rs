@@ -0,0 +1,458 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::alert_manager::AlertWebhook;
+
+/// Health of a service's upstream endpoint, worst last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Active,
+    Degraded,
+    Unavailable,
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Active => "active",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Unavailable => "unavailable",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(HealthStatus::Active),
+            "degraded" => Some(HealthStatus::Degraded),
+            "unavailable" => Some(HealthStatus::Unavailable),
+            _ => None,
+        }
+    }
+
+    /// One step towards active
+    fn recovered(self) -> Self {
+        match self {
+            HealthStatus::Unavailable => HealthStatus::Degraded,
+            _ => HealthStatus::Active,
+        }
+    }
+}
+
+/// Failure and recovery thresholds; recovery needs a run of successes per
+/// step, so a flapping endpoint doesn't bounce between statuses
+#[derive(Debug, Clone)]
+pub struct HealthThresholds {
+    /// Consecutive failures after which a service is degraded
+    pub degraded_after: u32,
+    /// Consecutive failures after which a service is unavailable
+    pub unavailable_after: u32,
+    /// Consecutive successes to move one step back towards active
+    pub recover_after: u32,
+    /// How long an unavailable service rejects requests before letting
+    /// trial requests through again
+    pub retry_after: Duration,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            degraded_after: 3,
+            unavailable_after: 10,
+            recover_after: 3,
+            retry_after: Duration::seconds(30),
+        }
+    }
+}
+
+impl HealthThresholds {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+
+        Self {
+            degraded_after: var("SERVICE_HEALTH_DEGRADED_FAILURES", defaults.degraded_after),
+            unavailable_after: var(
+                "SERVICE_HEALTH_UNAVAILABLE_FAILURES",
+                defaults.unavailable_after,
+            ),
+            recover_after: var("SERVICE_HEALTH_RECOVERY_SUCCESSES", defaults.recover_after),
+            retry_after: Duration::seconds(i64::from(var(
+                "SERVICE_HEALTH_RETRY_SECS",
+                defaults.retry_after.num_seconds() as u32,
+            ))),
+        }
+    }
+}
+
+/// A replica's view of one service's health
+#[derive(Debug, Clone)]
+pub struct HealthState {
+    pub status: HealthStatus,
+    pub consecutive_failures: u32,
+    pub consecutive_successes: u32,
+    pub last_failure_at: Option<DateTime<Utc>>,
+}
+
+impl Default for HealthState {
+    fn default() -> Self {
+        Self {
+            status: HealthStatus::Active,
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            last_failure_at: None,
+        }
+    }
+}
+
+impl HealthState {
+    /// Count an upstream outcome, returning the new status if it changed
+    pub fn record(
+        &mut self,
+        success: bool,
+        thresholds: &HealthThresholds,
+        now: DateTime<Utc>,
+    ) -> Option<HealthStatus> {
+        let previous = self.status;
+
+        if success {
+            self.consecutive_failures = 0;
+            self.consecutive_successes += 1;
+            if self.status != HealthStatus::Active
+                && self.consecutive_successes >= thresholds.recover_after
+            {
+                self.status = self.status.recovered();
+                self.consecutive_successes = 0;
+            }
+        } else {
+            self.consecutive_successes = 0;
+            self.consecutive_failures += 1;
+            self.last_failure_at = Some(now);
+            let target = if self.consecutive_failures >= thresholds.unavailable_after {
+                HealthStatus::Unavailable
+            } else if self.consecutive_failures >= thresholds.degraded_after {
+                HealthStatus::Degraded
+            } else {
+                HealthStatus::Active
+            };
+            self.status = self.status.max(target);
+        }
+
+        (self.status != previous).then_some(self.status)
+    }
+
+    /// Whether requests may reach the upstream; an unavailable service only
+    /// lets trial requests through once `retry_after` has passed since its
+    /// last failure
+    pub fn admits(&self, thresholds: &HealthThresholds, now: DateTime<Utc>) -> bool {
+        if self.status != HealthStatus::Unavailable {
+            return true;
+        }
+        self.last_failure_at
+            .is_none_or(|failed_at| now - failed_at >= thresholds.retry_after)
+    }
+}
+
+/// Stored health of a service
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ServiceHealthRecord {
+    pub service_id: Uuid,
+    pub status: String,
+    pub consecutive_failures: i32,
+    pub last_error: Option<String>,
+    pub changed_at: DateTime<Utc>,
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    state: HealthState,
+    webhook_url: Option<String>,
+}
+
+/// Upstream health registry moving services between active, degraded and
+/// unavailable on consecutive upstream and canary failures. Admission
+/// checks never touch the database.
+#[derive(Clone)]
+pub struct ServiceHealth {
+    db: Arc<PgPool>,
+    thresholds: HealthThresholds,
+    services: Arc<RwLock<HashMap<Uuid, Entry>>>,
+    webhook: Option<AlertWebhook>,
+}
+
+impl ServiceHealth {
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db: Arc::new(db),
+            thresholds: HealthThresholds::from_env(),
+            services: Arc::new(RwLock::new(HashMap::new())),
+            webhook: None,
+        }
+    }
+
+    /// Also post every status change to the given webhook URL, in addition
+    /// to the provider's own
+    pub fn with_webhook(mut self, url: String) -> Self {
+        self.webhook = Some(AlertWebhook::new(url));
+        self
+    }
+
+    /// Whether a request may be routed to the service now
+    pub fn admits(&self, service_id: Uuid) -> bool {
+        self.services
+            .read()
+            .unwrap()
+            .get(&service_id)
+            .is_none_or(|entry| entry.state.admits(&self.thresholds, Utc::now()))
+    }
+
+    pub async fn record_success(&self, service_id: Uuid) {
+        self.record(service_id, None).await;
+    }
+
+    pub async fn record_failure(&self, service_id: Uuid, error: &str) {
+        self.record(service_id, Some(error)).await;
+    }
+
+    async fn record(&self, service_id: Uuid, error: Option<&str>) {
+        let (previous, changed, failures, webhook_url) = {
+            let mut services = self.services.write().unwrap();
+            let entry = services.entry(service_id).or_default();
+            let previous = entry.state.status;
+            let changed = entry
+                .state
+                .record(error.is_none(), &self.thresholds, Utc::now());
+            (
+                previous,
+                changed,
+                entry.state.consecutive_failures,
+                entry.webhook_url.clone(),
+            )
+        };
+
+        let Some(status) = changed else {
+            return;
+        };
+
+        if status > previous {
+            warn!(
+                service_id = %service_id,
+                from = previous.as_str(),
+                to = status.as_str(),
+                consecutive_failures = failures,
+                error = error.unwrap_or_default(),
+                "Service health degraded"
+            );
+        } else {
+            info!(
+                service_id = %service_id,
+                from = previous.as_str(),
+                to = status.as_str(),
+                "Service health recovered"
+            );
+        }
+
+        if let Err(e) = self.persist(service_id, status, failures, error).await {
+            warn!(service_id = %service_id, error = %e, "Failed to store service health");
+        }
+
+        let payload = serde_json::json!({
+            "event": "service.status_changed",
+            "service_id": service_id,
+            "from": previous.as_str(),
+            "to": status.as_str(),
+            "consecutive_failures": failures,
+            "last_error": error,
+            "timestamp": Utc::now().to_rfc3339(),
+        });
+        if let Some(url) = webhook_url {
+            AlertWebhook::new(url).notify(payload.clone());
+        }
+        if let Some(webhook) = &self.webhook {
+            webhook.notify(payload);
+        }
+    }
+
+    async fn persist(
+        &self,
+        service_id: Uuid,
+        status: HealthStatus,
+        failures: u32,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO service_health (service_id, status, consecutive_failures, last_error, changed_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (service_id) DO UPDATE SET
+                status = EXCLUDED.status,
+                consecutive_failures = EXCLUDED.consecutive_failures,
+                last_error = COALESCE(EXCLUDED.last_error, service_health.last_error),
+                changed_at = NOW()
+            "#,
+        )
+        .bind(service_id)
+        .bind(status.as_str())
+        .bind(failures as i32)
+        .bind(error)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store service health")?;
+
+        Ok(())
+    }
+
+    /// Adopt statuses and webhooks stored by any replica; returns how many
+    /// services changed status on this replica
+    pub async fn reload(&self) -> Result<usize> {
+        let rows = self.list().await?;
+
+        let mut services = self.services.write().unwrap();
+        let mut changed = 0;
+        for row in rows {
+            let Some(status) = HealthStatus::parse(&row.status) else {
+                continue;
+            };
+            let entry = services.entry(row.service_id).or_default();
+            entry.webhook_url = row.webhook_url;
+            if entry.state.status != status {
+                entry.state = HealthState {
+                    status,
+                    consecutive_failures: row.consecutive_failures.max(0) as u32,
+                    consecutive_successes: 0,
+                    last_failure_at: Some(row.changed_at),
+                };
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Stored health of every service that ever changed status or has a
+    /// webhook
+    pub async fn list(&self) -> Result<Vec<ServiceHealthRecord>> {
+        sqlx::query_as(
+            r#"
+            SELECT service_id, status, consecutive_failures, last_error, changed_at, webhook_url
+            FROM service_health
+            ORDER BY service_id
+            "#,
+        )
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load service health")
+    }
+
+    /// Set or clear the provider endpoint notified of the service's status
+    /// changes
+    pub async fn set_webhook(
+        &self,
+        service_id: Uuid,
+        webhook_url: Option<String>,
+    ) -> Result<ServiceHealthRecord> {
+        let record = sqlx::query_as(
+            r#"
+            INSERT INTO service_health (service_id, webhook_url)
+            VALUES ($1, $2)
+            ON CONFLICT (service_id) DO UPDATE SET webhook_url = EXCLUDED.webhook_url
+            RETURNING service_id, status, consecutive_failures, last_error, changed_at, webhook_url
+            "#,
+        )
+        .bind(service_id)
+        .bind(&webhook_url)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to store service health webhook")?;
+
+        self.services
+            .write()
+            .unwrap()
+            .entry(service_id)
+            .or_default()
+            .webhook_url = webhook_url;
+
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thresholds() -> HealthThresholds {
+        HealthThresholds {
+            degraded_after: 2,
+            unavailable_after: 4,
+            recover_after: 2,
+            retry_after: Duration::seconds(30),
+        }
+    }
+
+    #[test]
+    fn test_failures_degrade_then_disable() {
+        let t = thresholds();
+        let now = Utc::now();
+        let mut state = HealthState::default();
+
+        assert_eq!(state.record(false, &t, now), None);
+        assert_eq!(state.record(false, &t, now), Some(HealthStatus::Degraded));
+        assert_eq!(state.record(false, &t, now), None);
+        assert_eq!(
+            state.record(false, &t, now),
+            Some(HealthStatus::Unavailable)
+        );
+
+        assert!(!state.admits(&t, now + Duration::seconds(29)));
+        assert!(state.admits(&t, now + Duration::seconds(30)));
+    }
+
+    #[test]
+    fn test_recovery_needs_a_run_of_successes_per_step() {
+        let t = thresholds();
+        let now = Utc::now();
+        let mut state = HealthState::default();
+        for _ in 0..4 {
+            state.record(false, &t, now);
+        }
+
+        // A success between failures resets the run
+        assert_eq!(state.record(true, &t, now), None);
+        assert_eq!(state.record(false, &t, now), None);
+        assert_eq!(state.status, HealthStatus::Unavailable);
+
+        assert_eq!(state.record(true, &t, now), None);
+        assert_eq!(state.record(true, &t, now), Some(HealthStatus::Degraded));
+        assert_eq!(state.record(true, &t, now), None);
+        assert_eq!(state.record(true, &t, now), Some(HealthStatus::Active));
+
+        // Back to counting failures from zero
+        assert_eq!(state.record(false, &t, now), None);
+        assert_eq!(state.status, HealthStatus::Active);
+    }
+
+    #[test]
+    fn test_status_names_round_trip() {
+        for status in [
+            HealthStatus::Active,
+            HealthStatus::Degraded,
+            HealthStatus::Unavailable,
+        ] {
+            assert_eq!(HealthStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(HealthStatus::parse("retired"), None);
+    }
+}
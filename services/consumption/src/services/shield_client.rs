@@ -7,6 +7,7 @@
 //! Phase 2B: Runtime consumption integration only - no schema modifications.
 
 use anyhow::{Context, Result};
+use llm_infra::config::FailurePolicy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -20,6 +21,7 @@ use uuid::Uuid;
 pub struct ShieldClient {
     client: Arc<Client>,
     shield_url: String,
+    failure_policy: FailurePolicy,
 }
 
 /// Filter pack consumed from LLM-Shield
@@ -183,7 +185,7 @@ pub struct ScanContext {
 }
 
 /// Content scan response
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentScanResponse {
     pub allowed: bool,
     pub action: FilterAction,
@@ -193,7 +195,7 @@ pub struct ContentScanResponse {
 }
 
 /// Filter match details
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterMatch {
     pub filter_id: String,
     pub filter_type: FilterType,
@@ -223,9 +225,22 @@ impl ShieldClient {
         Self {
             client: Arc::new(client),
             shield_url,
+            failure_policy: FailurePolicy::Open,
         }
     }
 
+    /// Fail lookups instead of returning empty results, and deny content that
+    /// can't be scanned, while LLM-Shield is unavailable (open by default)
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Failure policy for requests validated by LLM-Shield
+    pub fn failure_policy(&self) -> FailurePolicy {
+        self.failure_policy
+    }
+
     /// Fetch all active filter packs for a service
     pub async fn get_filter_packs(&self, service_id: Uuid) -> Result<Vec<FilterPack>> {
         let start = std::time::Instant::now();
@@ -250,6 +265,12 @@ impl ShieldClient {
                 latency_ms = latency.as_millis(),
                 "Failed to fetch filter packs"
             );
+            if !self.failure_policy.allows() {
+                anyhow::bail!(
+                    "Failed to fetch filter packs with status: {}",
+                    response.status()
+                );
+            }
             return Ok(vec![]);
         }
 
@@ -292,6 +313,12 @@ impl ShieldClient {
                 latency_ms = latency.as_millis(),
                 "Failed to fetch safety modules"
             );
+            if !self.failure_policy.allows() {
+                anyhow::bail!(
+                    "Failed to fetch safety modules with status: {}",
+                    response.status()
+                );
+            }
             return Ok(vec![]);
         }
 
@@ -346,6 +373,12 @@ impl ShieldClient {
                 latency_ms = latency.as_millis(),
                 "Failed to fetch shielding metadata"
             );
+            if !self.failure_policy.allows() {
+                anyhow::bail!(
+                    "Failed to fetch shielding metadata with status: {}",
+                    response.status()
+                );
+            }
             return Ok(None);
         }
 
@@ -400,22 +433,14 @@ impl ShieldClient {
 
         let latency = start.elapsed();
 
+        // Callers decide whether to fail open
         if !response.status().is_success() {
             error!(
                 status = %response.status(),
                 latency_ms = latency.as_millis(),
                 "Shield scan failed"
             );
-
-            // Fail-open: allow content if shield is unavailable
-            warn!("Shield unavailable, failing open");
-            return Ok(ContentScanResponse {
-                allowed: true,
-                action: FilterAction::Allow,
-                matches: vec![],
-                risk_score: 0.0,
-                processing_time_ms: latency.as_millis() as u64,
-            });
+            anyhow::bail!("Shield scan returned {}", response.status());
         }
 
         let scan_response: ContentScanResponse = response
@@ -453,6 +478,17 @@ impl ShieldClient {
             None => Ok(false),
         }
     }
+
+    /// Check that LLM-Shield is reachable and healthy
+    pub async fn ping(&self) -> Result<()> {
+        self.client
+            .get(format!("{}/health", self.shield_url))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("LLM-Shield health check failed")?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::future::Future;
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+use super::alert_manager::AlertWebhook;
+
+/// Wait for SIGTERM or Ctrl-C; returns the signal name
+pub async fn signal() -> &'static str {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!(error = %e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => "SIGINT",
+        _ = terminate => "SIGTERM",
+    }
+}
+
+/// One step of graceful shutdown
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownPhase {
+    pub name: &'static str,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// What graceful shutdown drained and what it lost, emitted once at exit so
+/// rollout-induced data loss shows up in logs and alerts
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownReport {
+    pub signal: &'static str,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Requests in flight when the signal arrived
+    pub requests_in_flight: i64,
+    pub requests_drained: i64,
+    /// Requests still running when the drain timeout expired
+    pub requests_abandoned: i64,
+    pub analytics_events_flushed: usize,
+    /// Events left in the buffer after the final flush
+    pub analytics_events_unflushed: usize,
+    /// Events dropped on buffer overflow over the process lifetime
+    pub analytics_events_dropped: u64,
+    /// Running jobs handed back to the queue for other replicas
+    pub jobs_interrupted: u64,
+    pub phases: Vec<ShutdownPhase>,
+    #[serde(skip)]
+    started: Instant,
+}
+
+impl ShutdownReport {
+    pub fn begin(signal: &'static str, requests_in_flight: i64) -> Self {
+        info!(
+            signal = signal,
+            requests_in_flight = requests_in_flight,
+            "Shutting down"
+        );
+
+        Self {
+            signal,
+            started_at: Utc::now(),
+            duration_ms: 0,
+            requests_in_flight,
+            requests_drained: 0,
+            requests_abandoned: 0,
+            analytics_events_flushed: 0,
+            analytics_events_unflushed: 0,
+            analytics_events_dropped: 0,
+            jobs_interrupted: 0,
+            phases: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Run and time one phase; a failure is recorded and shutdown continues
+    pub async fn phase<T>(
+        &mut self,
+        name: &'static str,
+        work: impl Future<Output = Result<T>>,
+    ) -> Option<T> {
+        let begin = Instant::now();
+        let outcome = work.await;
+
+        let error = outcome.as_ref().err().map(|e| format!("{:#}", e));
+        if let Some(error) = &error {
+            warn!(phase = name, error = %error, "Shutdown phase failed");
+        }
+
+        self.phases.push(ShutdownPhase {
+            name,
+            duration_ms: begin.elapsed().as_millis() as u64,
+            error,
+        });
+
+        outcome.ok()
+    }
+
+    /// Log the report and deliver it to the shutdown webhook, if configured
+    pub async fn finish(mut self, webhook: Option<&AlertWebhook>) {
+        self.duration_ms = self.started.elapsed().as_millis() as u64;
+
+        info!(
+            signal = self.signal,
+            duration_ms = self.duration_ms,
+            requests_drained = self.requests_drained,
+            requests_abandoned = self.requests_abandoned,
+            analytics_events_flushed = self.analytics_events_flushed,
+            analytics_events_unflushed = self.analytics_events_unflushed,
+            jobs_interrupted = self.jobs_interrupted,
+            report = %serde_json::to_string(&self).unwrap_or_default(),
+            "Shutdown report"
+        );
+
+        if let Some(webhook) = webhook {
+            let payload = serde_json::json!({
+                "event": "shutdown",
+                "host": std::env::var("HOSTNAME").ok(),
+                "report": &self,
+            });
+            webhook.deliver(&payload).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_phases_record_failures_and_continue() {
+        let mut report = ShutdownReport::begin("SIGTERM", 3);
+
+        let flushed = report.phase("flush_analytics", async { Ok(42) }).await;
+        let released: Option<u64> = report
+            .phase("release_jobs", async {
+                Err(anyhow::anyhow!("database unavailable"))
+            })
+            .await;
+
+        assert_eq!(flushed, Some(42));
+        assert_eq!(released, None);
+        assert_eq!(report.phases.len(), 2);
+        assert_eq!(report.phases[0].error, None);
+        assert_eq!(
+            report.phases[1].error.as_deref(),
+            Some("database unavailable")
+        );
+    }
+}
@@ -1,22 +1,161 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, error, warn};
 use uuid::Uuid;
 
+use super::alert_manager::{AlertManager, AlertWebhook};
+use super::analytics_streamer::AnalyticsStreamer;
+use super::usage_rollups::UsageRollups;
+use super::webhook_dispatcher::WebhookDispatcher;
+use crate::middleware::metrics::record;
 use crate::models::{Service, SLAStatus, SLAViolation};
 
+/// Default window during which identical violations are emitted only once
+const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(300);
+
+/// Overhead samples kept per service for the rolling p95
+const OVERHEAD_WINDOW_SIZE: usize = 200;
+
+/// Overhead p95 is evaluated once per this many new samples
+const OVERHEAD_EVAL_INTERVAL: usize = 50;
+
 /// SLA monitoring service for tracking service level agreements
 /// Monitors latency, availability, and error rates against SLA thresholds
 #[derive(Clone)]
 pub struct SLAMonitor {
     db: Arc<PgPool>,
+    rollups: UsageRollups,
+    analytics: AnalyticsStreamer,
+    dedup: Arc<ViolationDeduplicator>,
+    overhead: Arc<OverheadTracker>,
+    alert_manager: AlertManager,
+    alert_webhook: Option<AlertWebhook>,
+    consumer_webhooks: Option<WebhookDispatcher>,
+}
+
+/// Suppresses repeated identical violations (same service, metric and
+/// severity) within a time window so downstream alerting sees one event
+pub struct ViolationDeduplicator {
+    window: Duration,
+    last_emitted: Mutex<HashMap<(Uuid, String, String), Instant>>,
+}
+
+impl ViolationDeduplicator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last_emitted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns true if the violation should be emitted, recording the emission
+    pub fn should_emit(&self, violation: &SLAViolation) -> bool {
+        self.should_emit_at(violation, Instant::now())
+    }
+
+    fn should_emit_at(&self, violation: &SLAViolation, now: Instant) -> bool {
+        let key = (
+            violation.service_id,
+            violation.metric.clone(),
+            violation.severity.clone(),
+        );
+        let mut last_emitted = self.last_emitted.lock().unwrap();
+
+        if let Some(previous) = last_emitted.get(&key) {
+            if now.duration_since(*previous) < self.window {
+                return false;
+            }
+        }
+
+        // Keep the map bounded by discarding expired entries
+        let window = self.window;
+        last_emitted.retain(|_, emitted| now.duration_since(*emitted) < window);
+        last_emitted.insert(key, now);
+
+        true
+    }
+}
+
+/// Rolling per-service window of marketplace overhead samples
+pub struct OverheadTracker {
+    windows: Mutex<HashMap<Uuid, OverheadWindow>>,
+}
+
+#[derive(Default)]
+struct OverheadWindow {
+    samples: VecDeque<u64>,
+    since_eval: usize,
+}
+
+impl OverheadTracker {
+    pub fn new() -> Self {
+        Self {
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a sample; returns the rolling p95 when an evaluation is due
+    pub fn record(&self, service_id: Uuid, overhead_ms: u64) -> Option<u64> {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(service_id).or_default();
+
+        if window.samples.len() == OVERHEAD_WINDOW_SIZE {
+            window.samples.pop_front();
+        }
+        window.samples.push_back(overhead_ms);
+        window.since_eval += 1;
+
+        if window.since_eval < OVERHEAD_EVAL_INTERVAL {
+            return None;
+        }
+        window.since_eval = 0;
+
+        let mut sorted: Vec<u64> = window.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(sorted[(sorted.len() * 95) / 100])
+    }
+}
+
+impl Default for OverheadTracker {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SLAMonitor {
-    pub fn new(db: PgPool) -> Self {
-        Self { db: Arc::new(db) }
+    pub fn new(db: PgPool, analytics: AnalyticsStreamer, alert_manager: AlertManager) -> Self {
+        let window = std::env::var("SLA_VIOLATION_DEDUP_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_DEDUP_WINDOW);
+
+        Self {
+            rollups: UsageRollups::new(db.clone()),
+            db: Arc::new(db),
+            analytics,
+            dedup: Arc::new(ViolationDeduplicator::new(window)),
+            overhead: Arc::new(OverheadTracker::new()),
+            alert_manager,
+            alert_webhook: None,
+            consumer_webhooks: None,
+        }
+    }
+
+    /// Post violation notifications to the given webhook URL
+    pub fn with_alert_webhook(mut self, url: String) -> Self {
+        self.alert_webhook = Some(AlertWebhook::new(url));
+        self
+    }
+
+    /// Also notify consumers' webhooks of violations on services they use
+    pub fn with_consumer_webhooks(mut self, webhooks: WebhookDispatcher) -> Self {
+        self.consumer_webhooks = Some(webhooks);
+        self
     }
 
     /// Check if a request violates SLA thresholds
@@ -73,6 +212,53 @@ impl SLAMonitor {
         Ok(None)
     }
 
+    /// Track marketplace overhead for a request and flag a violation when
+    /// the rolling p95 exceeds the service's declared budget
+    pub async fn record_overhead(
+        &self,
+        service: &Service,
+        overhead_ms: u64,
+    ) -> Result<Option<SLAViolation>> {
+        record::marketplace_overhead(service.id, overhead_ms);
+
+        let Some(budget_ms) = service.sla.0.max_overhead_ms else {
+            return Ok(None);
+        };
+
+        let Some(p95) = self.overhead.record(service.id, overhead_ms) else {
+            return Ok(None);
+        };
+
+        if p95 <= budget_ms {
+            return Ok(None);
+        }
+
+        warn!(
+            service_id = %service.id,
+            overhead_p95_ms = p95,
+            budget_ms = budget_ms,
+            "Marketplace overhead budget exceeded"
+        );
+
+        let violation = SLAViolation {
+            id: Uuid::new_v4(),
+            service_id: service.id,
+            metric: "overhead".to_string(),
+            threshold: budget_ms as f64,
+            actual: p95 as f64,
+            timestamp: Utc::now(),
+            severity: if p95 > budget_ms * 2 {
+                "critical".to_string()
+            } else {
+                "warning".to_string()
+            },
+        };
+
+        self.record_violation(&violation).await?;
+
+        Ok(Some(violation))
+    }
+
     /// Check error rate SLA for a service over the last 5 minutes
     async fn check_error_rate_sla(&self, service_id: Uuid) -> Result<()> {
         let five_minutes_ago = Utc::now() - chrono::Duration::minutes(5);
@@ -82,9 +268,10 @@ impl SLAMonitor {
             SELECT
                 COUNT(*) as total_requests,
                 COUNT(*) FILTER (WHERE status = 'error') as error_count
-            FROM usage_records
+            FROM usage_records_all
             WHERE service_id = $1
                 AND timestamp >= $2
+                AND status <> 'marketplace_error'
             "#,
         )
         .bind(service_id)
@@ -160,32 +347,51 @@ impl SLAMonitor {
             "SLA violation recorded"
         );
 
-        // Trigger alert for critical violations
-        if violation.severity == "critical" {
-            self.trigger_alert(violation).await?;
+        // Every violation counts towards its incident, even when deduplicated
+        if let Err(e) = self.alert_manager.handle_violation(violation).await {
+            error!(error = %e, "Failed to group SLA violation into incident");
         }
 
+        self.emit_violation(violation).await?;
+
         Ok(())
     }
 
-    /// Trigger alert for SLA violation
-    async fn trigger_alert(&self, violation: &SLAViolation) -> Result<()> {
-        // In production, integrate with PagerDuty, Opsgenie, or similar
-        error!(
-            violation_id = %violation.id,
-            service_id = %violation.service_id,
-            metric = %violation.metric,
-            threshold = violation.threshold,
-            actual = violation.actual,
-            severity = %violation.severity,
-            "CRITICAL SLA VIOLATION - Alert triggered"
-        );
+    /// Fan a recorded violation out to analytics and the alert webhook,
+    /// suppressing duplicates within the dedup window
+    async fn emit_violation(&self, violation: &SLAViolation) -> Result<()> {
+        if !self.dedup.should_emit(violation) {
+            debug!(
+                service_id = %violation.service_id,
+                metric = %violation.metric,
+                severity = %violation.severity,
+                "Duplicate SLA violation suppressed"
+            );
+            return Ok(());
+        }
+
+        self.analytics
+            .record_sla_violation(
+                violation.service_id,
+                violation.metric.clone(),
+                violation.threshold,
+                violation.actual,
+                violation.severity.clone(),
+            )
+            .await?;
+
+        if let Some(webhook) = &self.alert_webhook {
+            webhook.notify(serde_json::json!({
+                "event": "sla_violation",
+                "violation": violation,
+            }));
+        }
 
-        // TODO: Send to alerting system
-        // - PagerDuty API
-        // - Opsgenie API
-        // - Slack webhook
-        // - Email notification
+        if let Some(webhooks) = &self.consumer_webhooks {
+            if let Err(e) = webhooks.sla_violation(violation).await {
+                error!(error = %e, "Failed to queue SLA violation webhooks");
+            }
+        }
 
         Ok(())
     }
@@ -212,37 +418,42 @@ impl SLAMonitor {
         .await
         .context("Failed to get service")?;
 
-        // Calculate actual metrics
-        let stats = sqlx::query_as::<_, (i64, f64, i64)>(
-            r#"
-            SELECT
-                COUNT(*) as total_requests,
-                COALESCE(AVG(duration_ms), 0.0) as avg_latency_ms,
-                COUNT(*) FILTER (WHERE status = 'error') as error_count
-            FROM usage_records
-            WHERE service_id = $1
-                AND timestamp >= $2
-                AND timestamp <= $3
-            "#,
-        )
-        .bind(service_id)
-        .bind(period_start)
-        .bind(period_end)
-        .fetch_one(self.db.as_ref())
-        .await
-        .context("Failed to get SLA statistics")?;
+        // Calculate actual metrics, mostly from usage rollups. Marketplace-layer
+        // failures (e.g. from the synthetic canary) are tracked separately and
+        // never count against the provider's error rate or uptime.
+        let totals = self
+            .rollups
+            .totals(
+                "usage_records_all",
+                None,
+                service_id,
+                period_start,
+                period_end,
+            )
+            .await
+            .context("Failed to get SLA statistics")?;
 
-        let (total_requests, avg_latency_ms, error_count) = stats;
+        let total_requests = totals.requests;
+        let avg_latency_ms = totals.provider_avg_latency_ms();
+        let (error_count, marketplace_error_count) =
+            (totals.error_count, totals.marketplace_error_count);
+        let provider_requests = totals.provider_requests();
 
-        let error_rate = if total_requests > 0 {
-            (error_count as f64) / (total_requests as f64)
+        let error_rate = if provider_requests > 0 {
+            (error_count as f64) / (provider_requests as f64)
+        } else {
+            0.0
+        };
+
+        let marketplace_error_rate = if total_requests > 0 {
+            (marketplace_error_count as f64) / (total_requests as f64)
         } else {
             0.0
         };
 
         // Calculate uptime
-        let uptime = if total_requests > 0 {
-            ((total_requests - error_count) as f64) / (total_requests as f64) * 100.0
+        let uptime = if provider_requests > 0 {
+            ((provider_requests - error_count) as f64) / (provider_requests as f64) * 100.0
         } else {
             100.0
         };
@@ -279,6 +490,7 @@ impl SLAMonitor {
             error_rate,
             error_rate_threshold: 0.001,
             error_rate_compliant,
+            marketplace_error_rate,
             uptime_percentage: uptime,
             uptime_threshold: sla.availability,
             uptime_compliant,
@@ -361,4 +573,60 @@ mod tests {
 
         assert_eq!(violation.severity, "critical");
     }
+
+    fn violation(metric: &str, severity: &str, service_id: Uuid) -> SLAViolation {
+        SLAViolation {
+            id: Uuid::new_v4(),
+            service_id,
+            metric: metric.to_string(),
+            threshold: 100.0,
+            actual: 250.0,
+            timestamp: Utc::now(),
+            severity: severity.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_deduplicator_suppresses_within_window() {
+        let dedup = ViolationDeduplicator::new(Duration::from_secs(60));
+        let service_id = Uuid::new_v4();
+        let now = Instant::now();
+
+        assert!(dedup.should_emit_at(&violation("latency", "critical", service_id), now));
+        assert!(!dedup.should_emit_at(
+            &violation("latency", "critical", service_id),
+            now + Duration::from_secs(30)
+        ));
+
+        // Different severity or metric is a distinct violation
+        assert!(dedup.should_emit_at(&violation("latency", "warning", service_id), now));
+        assert!(dedup.should_emit_at(&violation("error_rate", "critical", service_id), now));
+
+        // Emitted again once the window has elapsed
+        assert!(dedup.should_emit_at(
+            &violation("latency", "critical", service_id),
+            now + Duration::from_secs(61)
+        ));
+    }
+
+    #[test]
+    fn test_overhead_tracker_evaluates_rolling_p95() {
+        let tracker = OverheadTracker::new();
+        let service_id = Uuid::new_v4();
+
+        for _ in 0..OVERHEAD_EVAL_INTERVAL - 1 {
+            assert_eq!(tracker.record(service_id, 2), None);
+        }
+        assert_eq!(tracker.record(service_id, 2), Some(2));
+
+        // A slow tail shows up in the next evaluation
+        for _ in 0..OVERHEAD_EVAL_INTERVAL - 10 {
+            tracker.record(service_id, 2);
+        }
+        let mut p95 = None;
+        for _ in 0..10 {
+            p95 = tracker.record(service_id, 40);
+        }
+        assert_eq!(p95, Some(40));
+    }
 }
@@ -0,0 +1,635 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::fmt::Write;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::models::Service;
+use crate::services::localization::{fill, Locale};
+
+/// Error rate above which a month is not compliant
+const ERROR_RATE_THRESHOLD: f64 = 0.001;
+
+/// Service credit, as a percentage of the month's bill, for uptime below
+/// the SLA target: the first row whose floor the uptime reaches applies
+const CREDIT_SCHEDULE: [(f64, f64); 3] = [(99.0, 10.0), (95.0, 25.0), (0.0, 50.0)];
+
+/// Violations of one metric and severity within the report month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViolationSummary {
+    pub metric: String,
+    pub severity: String,
+    pub count: i64,
+    /// Largest measured value among the violations
+    pub worst: f64,
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
+}
+
+/// Monthly SLA compliance report for one consumer of a service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaReport {
+    pub service_id: Uuid,
+    pub service_name: String,
+    pub consumer_id: Uuid,
+    /// Report month, `YYYY-MM`
+    pub month: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// The month is still in progress; figures will change
+    pub provisional: bool,
+    pub total_requests: i64,
+    pub uptime_percentage: f64,
+    pub uptime_target: f64,
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub latency_p99_ms: f64,
+    pub latency_target_ms: f64,
+    pub error_rate: f64,
+    pub error_rate_threshold: f64,
+    pub violations: Vec<ViolationSummary>,
+    pub compliant: bool,
+    pub credit_percent: f64,
+    /// What the consumer was billed for the service in the month
+    pub billed_amount: f64,
+    pub credit_amount: f64,
+    pub currency: String,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Monthly per-service SLA compliance reports for enterprise consumers
+#[derive(Clone)]
+pub struct SlaReportGenerator {
+    db: Arc<PgPool>,
+}
+
+impl SlaReportGenerator {
+    pub fn new(db: PgPool) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    /// Report for a month, starting on its first day. Reports for completed
+    /// months are generated once and stored; the current month is always
+    /// generated fresh and marked provisional.
+    pub async fn get_report(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        month: NaiveDate,
+    ) -> Result<SlaReport> {
+        let (period_start, period_end) = month_bounds(month);
+        let now = Utc::now();
+        if period_start > now {
+            bail!("Report month {} has not started", month.format("%Y-%m"));
+        }
+
+        if period_end > now {
+            return self.generate(consumer_id, service_id, month).await;
+        }
+
+        let stored: Option<sqlx::types::Json<SlaReport>> = sqlx::query_scalar(
+            r#"
+            SELECT report
+            FROM sla_reports
+            WHERE consumer_id = $1 AND service_id = $2 AND month = $3
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(month)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load SLA report")?;
+
+        if let Some(report) = stored {
+            return Ok(report.0);
+        }
+
+        let report = self.generate(consumer_id, service_id, month).await?;
+        self.store(&report, month).await?;
+        Ok(report)
+    }
+
+    /// Compute a report from usage records and recorded violations
+    pub async fn generate(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        month: NaiveDate,
+    ) -> Result<SlaReport> {
+        let (period_start, period_end) = month_bounds(month);
+        let now = Utc::now();
+        let provisional = period_end > now;
+        let until = period_end.min(now);
+
+        let service = sqlx::query_as::<_, Service>(
+            r#"
+            SELECT id, name, version, endpoint, status, pricing, sla, created_at
+            FROM services
+            WHERE id = $1
+            "#,
+        )
+        .bind(service_id)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to get service")?;
+
+        // Service-wide figures; marketplace-layer failures never count
+        // against the provider, as in live SLA status
+        let (total_requests, error_count, p50, p95, p99) =
+            sqlx::query_as::<_, (i64, i64, Option<f64>, Option<f64>, Option<f64>)>(
+                r#"
+                SELECT
+                    COUNT(*),
+                    COUNT(*) FILTER (WHERE status = 'error'),
+                    percentile_cont(0.50) WITHIN GROUP (ORDER BY duration_ms),
+                    percentile_cont(0.95) WITHIN GROUP (ORDER BY duration_ms),
+                    percentile_cont(0.99) WITHIN GROUP (ORDER BY duration_ms)
+                FROM usage_records_all
+                WHERE service_id = $1
+                    AND timestamp >= $2
+                    AND timestamp < $3
+                    AND status <> 'marketplace_error'
+                "#,
+            )
+            .bind(service_id)
+            .bind(period_start)
+            .bind(until)
+            .fetch_one(self.db.as_ref())
+            .await
+            .context("Failed to get SLA statistics")?;
+
+        let violations = sqlx::query_as::<_, (String, String, i64, f64, DateTime<Utc>, DateTime<Utc>)>(
+            r#"
+            SELECT metric, severity, COUNT(*), MAX(actual), MIN(timestamp), MAX(timestamp)
+            FROM sla_violations
+            WHERE service_id = $1
+                AND timestamp >= $2
+                AND timestamp < $3
+            GROUP BY metric, severity
+            ORDER BY metric, severity
+            "#,
+        )
+        .bind(service_id)
+        .bind(period_start)
+        .bind(until)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to get SLA violations")?
+        .into_iter()
+        .map(|(metric, severity, count, worst, first_at, last_at)| ViolationSummary {
+            metric,
+            severity,
+            count,
+            worst,
+            first_at,
+            last_at,
+        })
+        .collect();
+
+        let (billed_amount, currency) = sqlx::query_as::<_, (f64, Option<String>)>(
+            r#"
+            SELECT
+                COALESCE(SUM((cost->>'amount')::DOUBLE PRECISION), 0.0),
+                MAX(cost->>'currency')
+            FROM usage_records_all
+            WHERE consumer_id = $1
+                AND service_id = $2
+                AND timestamp >= $3
+                AND timestamp < $4
+                AND status = 'success'
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(period_start)
+        .bind(until)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to get billed amount")?;
+
+        let sla = &service.sla.0;
+        let error_rate = if total_requests > 0 {
+            error_count as f64 / total_requests as f64
+        } else {
+            0.0
+        };
+        let uptime_percentage = if total_requests > 0 {
+            (total_requests - error_count) as f64 / total_requests as f64 * 100.0
+        } else {
+            100.0
+        };
+        let latency_p95_ms = p95.unwrap_or(0.0);
+
+        let compliant = uptime_percentage >= sla.availability
+            && latency_p95_ms <= sla.max_latency_ms as f64
+            && error_rate <= ERROR_RATE_THRESHOLD;
+        let credit_percent = credit_percent(uptime_percentage, sla.availability);
+
+        Ok(SlaReport {
+            service_id,
+            service_name: service.name,
+            consumer_id,
+            month: month.format("%Y-%m").to_string(),
+            period_start,
+            period_end,
+            provisional,
+            total_requests,
+            uptime_percentage,
+            uptime_target: sla.availability,
+            latency_p50_ms: p50.unwrap_or(0.0),
+            latency_p95_ms,
+            latency_p99_ms: p99.unwrap_or(0.0),
+            latency_target_ms: sla.max_latency_ms as f64,
+            error_rate,
+            error_rate_threshold: ERROR_RATE_THRESHOLD,
+            violations,
+            compliant,
+            credit_percent,
+            billed_amount,
+            credit_amount: billed_amount * credit_percent / 100.0,
+            currency: currency.unwrap_or_else(|| "USD".to_string()),
+            generated_at: now,
+        })
+    }
+
+    async fn store(&self, report: &SlaReport, month: NaiveDate) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sla_reports (consumer_id, service_id, month, report, generated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (consumer_id, service_id, month) DO NOTHING
+            "#,
+        )
+        .bind(report.consumer_id)
+        .bind(report.service_id)
+        .bind(month)
+        .bind(sqlx::types::Json(report))
+        .bind(report.generated_at)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store SLA report")?;
+
+        Ok(())
+    }
+
+    /// Generate and store last month's reports for every enterprise
+    /// consumer with usage (background job)
+    pub async fn generate_previous_month(&self) -> Result<usize> {
+        let today = Utc::now().date_naive();
+        let month = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+            .and_then(|first| first.checked_sub_months(Months::new(1)))
+            .context("Invalid report month")?;
+        let (period_start, period_end) = month_bounds(month);
+
+        let pending = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT DISTINCT u.consumer_id, u.service_id
+            FROM usage_records_all u
+            JOIN api_keys k ON k.consumer_id = u.consumer_id AND k.service_id = u.service_id
+            WHERE u.timestamp >= $1 AND u.timestamp < $2
+                AND k.tier = 'enterprise'
+                AND k.revoked_at IS NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM sla_reports r
+                    WHERE r.consumer_id = u.consumer_id
+                        AND r.service_id = u.service_id
+                        AND r.month = $3
+                )
+            "#,
+        )
+        .bind(period_start)
+        .bind(period_end)
+        .bind(month)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to find consumers needing SLA reports")?;
+
+        let mut generated = 0;
+        for (consumer_id, service_id) in pending {
+            match self.generate(consumer_id, service_id, month).await {
+                Ok(report) => {
+                    self.store(&report, month).await?;
+                    generated += 1;
+                }
+                Err(e) => error!(
+                    consumer_id = %consumer_id,
+                    service_id = %service_id,
+                    error = %e,
+                    "Failed to generate SLA report"
+                ),
+            }
+        }
+
+        if generated > 0 {
+            info!(month = %month.format("%Y-%m"), reports = generated, "SLA reports generated");
+        } else {
+            debug!(month = %month.format("%Y-%m"), "No SLA reports to generate");
+        }
+
+        Ok(generated)
+    }
+}
+
+/// Start (inclusive) and end (exclusive) of the month starting on `month`
+pub(crate) fn month_bounds(month: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = month.and_hms_opt(0, 0, 0).expect("valid midnight").and_utc();
+    (start, start + Months::new(1))
+}
+
+/// Credit owed for a month's uptime against the SLA target
+fn credit_percent(uptime_percentage: f64, target: f64) -> f64 {
+    if uptime_percentage >= target {
+        return 0.0;
+    }
+    CREDIT_SCHEDULE
+        .iter()
+        .find(|(floor, _)| uptime_percentage >= *floor)
+        .map(|(_, credit)| *credit)
+        .unwrap_or(0.0)
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A translated string as HTML, with `args` (already HTML) filled in
+pub(crate) fn html_text(locale: &Locale, key: &str, args: &[(&str, &str)]) -> String {
+    fill(&escape_html(locale.text(key)), args)
+}
+
+fn status_cell(locale: &Locale, compliant: bool) -> String {
+    if compliant {
+        format!(
+            r#"<td class="ok">{}</td>"#,
+            html_text(locale, "sla.met", &[])
+        )
+    } else {
+        format!(
+            r#"<td class="missed">{}</td>"#,
+            html_text(locale, "sla.missed", &[])
+        )
+    }
+}
+
+/// Stylesheet of printable reports and statements
+pub(crate) const REPORT_STYLE: &str = r#"<style>
+  body { font-family: Georgia, "Times New Roman", serif; margin: 2.5rem auto; max-width: 48rem; color: #111; }
+  h1 { font-size: 1.5rem; margin-bottom: .2rem; }
+  h2 { font-size: 1.1rem; margin-top: 2rem; border-bottom: 1px solid #999; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { border: 1px solid #bbb; padding: .35rem .6rem; text-align: left; font-size: .9rem; }
+  th { background: #f0f0f0; }
+  td.num { text-align: right; font-variant-numeric: tabular-nums; }
+  .ok { color: #1b5e20; }
+  .missed { color: #b71c1c; font-weight: bold; }
+  .meta, .notice { color: #555; font-size: .85rem; }
+  .notice { border: 1px solid #e0a800; padding: .5rem; color: #6d4c00; }
+  @media print {
+    body { margin: 0; max-width: none; }
+    @page { size: A4; margin: 2cm; }
+    h2, table { page-break-inside: avoid; }
+    th { -webkit-print-color-adjust: exact; print-color-adjust: exact; }
+  }
+</style>"#;
+
+impl SlaReport {
+    /// Printable HTML compliance document in `locale`
+    pub fn to_html(&self, locale: &Locale) -> String {
+        let service_name = escape_html(&self.service_name);
+        let title = html_text(
+            locale,
+            "sla.title",
+            &[("service", &service_name), ("month", &self.month)],
+        );
+        let service_id = self.service_id.to_string();
+        let consumer_id = self.consumer_id.to_string();
+        let start = locale.date(self.period_start);
+        let end = locale.date(self.period_end);
+        let generated = locale.datetime(self.generated_at);
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+{style}
+</head>
+<body>
+<h1>{title}</h1>
+<p class="meta">{service_consumer}<br>
+{period}</p>
+"#,
+            lang = escape_html(&locale.tag),
+            title = title,
+            style = REPORT_STYLE,
+            service_consumer = html_text(
+                locale,
+                "report.service_consumer",
+                &[("service", &service_id), ("consumer", &consumer_id)],
+            ),
+            period = html_text(
+                locale,
+                "report.period",
+                &[("start", &start), ("end", &end), ("generated", &generated)],
+            ),
+        );
+
+        if self.provisional {
+            let _ = writeln!(
+                html,
+                r#"<p class="notice">{}</p>"#,
+                html_text(locale, "report.provisional", &[])
+            );
+        }
+
+        let (class, key) = if self.compliant {
+            ("ok", "sla.sla_met")
+        } else {
+            ("missed", "sla.sla_missed")
+        };
+        let overall = format!(
+            r#"<strong class="{}">{}</strong>"#,
+            class,
+            html_text(locale, key, &[])
+        );
+        let requests = locale.integer(self.total_requests);
+        let ms = |value: f64, decimals: usize| format!("{} ms", locale.number(value, decimals));
+        let _ = write!(
+            html,
+            r#"<h2>{compliance_summary}</h2>
+<table>
+<tr><th>{objective}</th><th>{target}</th><th>{actual}</th><th>{status}</th></tr>
+<tr><td>{uptime_label}</td><td class="num">{uptime_target}</td><td class="num">{uptime}</td>{uptime_status}</tr>
+<tr><td>{latency_label}</td><td class="num">&le; {latency_target}</td><td class="num">{p95}</td>{latency_status}</tr>
+<tr><td>{error_label}</td><td class="num">&le; {error_target}</td><td class="num">{error_rate}</td>{error_status}</tr>
+</table>
+<p>{overall}</p>
+
+<h2>{latency}</h2>
+<table>
+<tr><th>p50</th><th>p95</th><th>p99</th></tr>
+<tr><td class="num">{p50}</td><td class="num">{p95}</td><td class="num">{p99}</td></tr>
+</table>
+
+<h2>{violations}</h2>
+"#,
+            compliance_summary = html_text(locale, "sla.compliance_summary", &[]),
+            objective = html_text(locale, "sla.objective", &[]),
+            target = html_text(locale, "sla.target", &[]),
+            actual = html_text(locale, "sla.actual", &[]),
+            status = html_text(locale, "sla.status", &[]),
+            uptime_label = html_text(locale, "sla.uptime", &[]),
+            uptime_target = locale.percent(self.uptime_target, 3),
+            uptime = locale.percent(self.uptime_percentage, 3),
+            uptime_status = status_cell(locale, self.uptime_percentage >= self.uptime_target),
+            latency_label = html_text(locale, "sla.latency_p95", &[]),
+            latency_target = ms(self.latency_target_ms, 0),
+            p95 = ms(self.latency_p95_ms, 1),
+            latency_status = status_cell(locale, self.latency_p95_ms <= self.latency_target_ms),
+            error_label = html_text(locale, "sla.error_rate", &[]),
+            error_target = locale.percent(self.error_rate_threshold * 100.0, 3),
+            error_rate = locale.percent(self.error_rate * 100.0, 3),
+            error_status = status_cell(locale, self.error_rate <= self.error_rate_threshold),
+            overall = html_text(
+                locale,
+                "sla.overall",
+                &[("status", &overall), ("requests", &requests)],
+            ),
+            latency = html_text(locale, "sla.latency", &[]),
+            p50 = ms(self.latency_p50_ms, 1),
+            p99 = ms(self.latency_p99_ms, 1),
+            violations = html_text(locale, "sla.violations", &[]),
+        );
+
+        if self.violations.is_empty() {
+            let _ = writeln!(
+                html,
+                "<p>{}</p>",
+                html_text(locale, "sla.no_violations", &[])
+            );
+        } else {
+            let _ = writeln!(
+                html,
+                "<table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+                html_text(locale, "sla.metric", &[]),
+                html_text(locale, "sla.severity", &[]),
+                html_text(locale, "sla.count", &[]),
+                html_text(locale, "sla.worst", &[]),
+                html_text(locale, "sla.first", &[]),
+                html_text(locale, "sla.last", &[]),
+            );
+            for violation in &self.violations {
+                let _ = writeln!(
+                    html,
+                    r#"<tr><td>{}</td><td>{}</td><td class="num">{}</td><td class="num">{}</td><td>{}</td><td>{}</td></tr>"#,
+                    escape_html(&violation.metric),
+                    escape_html(&violation.severity),
+                    locale.integer(violation.count),
+                    locale.number(violation.worst, 3),
+                    locale.datetime(violation.first_at),
+                    locale.datetime(violation.last_at),
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        let currency = escape_html(&self.currency);
+        let _ = write!(
+            html,
+            r#"
+<h2>{service_credits}</h2>
+<table>
+<tr><th>{billed_label}</th><th>{credit_label}</th><th>{amount_label}</th></tr>
+<tr><td class="num">{billed}</td><td class="num">{percent}</td><td class="num">{amount}</td></tr>
+</table>
+<p class="meta">{schedule}</p>
+</body>
+</html>
+"#,
+            service_credits = html_text(locale, "sla.service_credits", &[]),
+            billed_label = html_text(locale, "sla.billed", &[]),
+            credit_label = html_text(locale, "sla.credit", &[]),
+            amount_label = html_text(locale, "sla.credit_amount", &[]),
+            billed = locale.money(self.billed_amount, &currency),
+            percent = locale.percent(self.credit_percent, 0),
+            amount = locale.money(self.credit_amount, &currency),
+            schedule = html_text(locale, "sla.credit_schedule", &[]),
+        );
+
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::localization::Locales;
+
+    #[test]
+    fn test_credit_percent() {
+        assert_eq!(credit_percent(99.95, 99.9), 0.0);
+        assert_eq!(credit_percent(99.5, 99.9), 10.0);
+        assert_eq!(credit_percent(97.0, 99.9), 25.0);
+        assert_eq!(credit_percent(80.0, 99.9), 50.0);
+    }
+
+    #[test]
+    fn test_render_html_escapes_service_name() {
+        let month = NaiveDate::from_ymd_opt(2025, 11, 1).unwrap();
+        let (period_start, period_end) = month_bounds(month);
+        assert_eq!(period_end.month(), 12);
+
+        let report = SlaReport {
+            service_id: Uuid::new_v4(),
+            service_name: "<script>gpt</script>".to_string(),
+            consumer_id: Uuid::new_v4(),
+            month: "2025-11".to_string(),
+            period_start,
+            period_end,
+            provisional: false,
+            total_requests: 1000,
+            uptime_percentage: 99.5,
+            uptime_target: 99.9,
+            latency_p50_ms: 120.0,
+            latency_p95_ms: 480.0,
+            latency_p99_ms: 900.0,
+            latency_target_ms: 1000.0,
+            error_rate: 0.005,
+            error_rate_threshold: ERROR_RATE_THRESHOLD,
+            violations: Vec::new(),
+            compliant: false,
+            credit_percent: 10.0,
+            billed_amount: 250.0,
+            credit_amount: 25.0,
+            currency: "USD".to_string(),
+            generated_at: Utc::now(),
+        };
+
+        let locales = Locales::builtin();
+        let html = report.to_html(&locales.default_locale());
+        assert!(html.contains("&lt;script&gt;gpt&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("25.00 USD"));
+        assert!(html.contains("99.500%"));
+        assert!(html.contains("@media print"));
+
+        let html = report.to_html(&locales.get("de-DE").unwrap());
+        assert!(html.contains(r#"<html lang="de-DE">"#));
+        assert!(html.contains("SLA-Bericht: &lt;script&gt;gpt&lt;/script&gt; (2025-11)"));
+        assert!(html.contains("25,00 USD"));
+        assert!(html.contains("99,500%"));
+        assert!(html.contains("01.11.2025"));
+        assert!(html.contains(r#"<strong class="missed">SLA verfehlt</strong> bei 1.000 Anfragen"#));
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use tracing::info;
+
+use super::quota_manager::QuotaPreload;
+
+/// One timed step of process startup
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupPhase {
+    pub name: &'static str,
+    pub duration_ms: u64,
+}
+
+/// Where cold-start time went, from process start until the server accepts
+/// connections
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupReport {
+    pub started_at: DateTime<Utc>,
+    pub ready_at: Option<DateTime<Utc>>,
+    pub total_ms: u64,
+    pub quota_preload: QuotaPreload,
+    pub phases: Vec<StartupPhase>,
+    #[serde(skip)]
+    started: Instant,
+    #[serde(skip)]
+    last_mark: Instant,
+}
+
+/// Startup report, filled in once the server is listening
+pub type SharedStartupReport = Arc<OnceLock<StartupReport>>;
+
+impl StartupReport {
+    pub fn begin() -> Self {
+        let now = Instant::now();
+        Self {
+            started_at: Utc::now(),
+            ready_at: None,
+            total_ms: 0,
+            quota_preload: QuotaPreload::Eager,
+            phases: Vec::new(),
+            started: now,
+            last_mark: now,
+        }
+    }
+
+    /// Close a phase covering everything since the previous mark
+    pub fn mark(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push(StartupPhase {
+            name,
+            duration_ms: now.duration_since(self.last_mark).as_millis() as u64,
+        });
+        self.last_mark = now;
+    }
+
+    /// Stop the clock and log the breakdown
+    pub fn finish(mut self) -> Self {
+        self.total_ms = self.started.elapsed().as_millis() as u64;
+        self.ready_at = Some(Utc::now());
+
+        info!(
+            total_ms = self.total_ms,
+            quota_preload = ?self.quota_preload,
+            phases = %serde_json::to_string(&self.phases).unwrap_or_default(),
+            "Startup complete"
+        );
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks_cover_time_since_previous_phase() {
+        let mut report = StartupReport::begin();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        report.mark("db_pool");
+        report.mark("redis");
+        let report = report.finish();
+
+        let names: Vec<_> = report.phases.iter().map(|p| p.name).collect();
+        assert_eq!(names, ["db_pool", "redis"]);
+        assert!(report.phases[0].duration_ms >= 20);
+        assert!(report.phases[1].duration_ms < 20);
+        assert!(report.total_ms >= report.phases[0].duration_ms);
+        assert!(report.ready_at.is_some());
+    }
+}
@@ -0,0 +1,656 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::fmt::Write;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use super::alert_manager::AlertWebhook;
+use super::localization::Locale;
+use super::sla_reports::{escape_html, html_text, month_bounds, SlaReportGenerator, REPORT_STYLE};
+
+/// Usage and cost of one service within a statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementLine {
+    pub service_id: Uuid,
+    pub service_name: String,
+    pub requests: i64,
+    pub successful_requests: i64,
+    pub tokens: i64,
+    pub cost: f64,
+    /// SLA credit owed for the service (enterprise tier only)
+    pub sla_credit: f64,
+}
+
+/// Something the consumer should know about from the statement month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementEvent {
+    /// `incident`, `key_revoked` or `suspended`
+    pub kind: String,
+    pub occurred_at: DateTime<Utc>,
+    pub service_id: Option<Uuid>,
+    pub description: String,
+}
+
+/// Monthly usage, cost and SLA credits of one consumer across services
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    pub consumer_id: Uuid,
+    /// Statement month, `YYYY-MM`
+    pub month: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// The month is still in progress; figures will change
+    pub provisional: bool,
+    pub lines: Vec<StatementLine>,
+    pub total_requests: i64,
+    pub total_tokens: i64,
+    pub subtotal: f64,
+    pub sla_credits: f64,
+    pub total_due: f64,
+    pub currency: String,
+    pub events: Vec<StatementEvent>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl Statement {
+    fn new(
+        consumer_id: Uuid,
+        month: NaiveDate,
+        lines: Vec<StatementLine>,
+        events: Vec<StatementEvent>,
+        currency: String,
+    ) -> Self {
+        let (period_start, period_end) = month_bounds(month);
+        let now = Utc::now();
+        let subtotal: f64 = lines.iter().map(|l| l.cost).sum();
+        let sla_credits = lines
+            .iter()
+            .map(|l| l.sla_credit)
+            .sum::<f64>()
+            .min(subtotal);
+
+        Self {
+            consumer_id,
+            month: month.format("%Y-%m").to_string(),
+            period_start,
+            period_end,
+            provisional: period_end > now,
+            total_requests: lines.iter().map(|l| l.requests).sum(),
+            total_tokens: lines.iter().map(|l| l.tokens).sum(),
+            subtotal,
+            sla_credits,
+            total_due: subtotal - sla_credits,
+            currency,
+            lines,
+            events,
+            generated_at: now,
+        }
+    }
+
+    /// Per-service lines as CSV, for export into billing or spreadsheets
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "month,service_id,service_name,requests,successful_requests,tokens,cost,sla_credit,currency\n",
+        );
+        for line in &self.lines {
+            let _ = writeln!(
+                csv,
+                "{},{},\"{}\",{},{},{},{:.6},{:.6},{}",
+                self.month,
+                line.service_id,
+                line.service_name.replace('"', "\"\""),
+                line.requests,
+                line.successful_requests,
+                line.tokens,
+                line.cost,
+                line.sla_credit,
+                self.currency
+            );
+        }
+        csv
+    }
+
+    /// Printable HTML statement in `locale`
+    pub fn to_html(&self, locale: &Locale) -> String {
+        let month = escape_html(&self.month);
+        let title = html_text(locale, "statement.title", &[("month", &month)]);
+        let consumer_id = self.consumer_id.to_string();
+        let start = locale.date(self.period_start);
+        let end = locale.date(self.period_end);
+        let generated = locale.datetime(self.generated_at);
+        let currency = escape_html(&self.currency);
+        let money = |amount: f64| locale.money(amount, &currency);
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+{style}
+</head>
+<body>
+<h1>{title}</h1>
+<p class="meta">{consumer}<br>
+{period}</p>
+"#,
+            lang = escape_html(&locale.tag),
+            title = title,
+            style = REPORT_STYLE,
+            consumer = html_text(locale, "report.consumer", &[("consumer", &consumer_id)]),
+            period = html_text(
+                locale,
+                "report.period",
+                &[("start", &start), ("end", &end), ("generated", &generated)],
+            ),
+        );
+
+        if self.provisional {
+            let _ = writeln!(
+                html,
+                r#"<p class="notice">{}</p>"#,
+                html_text(locale, "report.provisional", &[])
+            );
+        }
+
+        let _ = writeln!(
+            html,
+            "<h2>{}</h2>",
+            html_text(locale, "statement.usage", &[])
+        );
+        if self.lines.is_empty() {
+            let _ = writeln!(
+                html,
+                "<p>{}</p>",
+                html_text(locale, "statement.no_usage", &[])
+            );
+        } else {
+            let _ = writeln!(
+                html,
+                "<table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+                html_text(locale, "statement.service", &[]),
+                html_text(locale, "statement.requests", &[]),
+                html_text(locale, "statement.successful", &[]),
+                html_text(locale, "statement.tokens", &[]),
+                html_text(locale, "statement.cost", &[]),
+                html_text(locale, "statement.sla_credit", &[]),
+            );
+            for line in &self.lines {
+                let _ = writeln!(
+                    html,
+                    r#"<tr><td>{}</td><td class="num">{}</td><td class="num">{}</td><td class="num">{}</td><td class="num">{}</td><td class="num">{}</td></tr>"#,
+                    escape_html(&line.service_name),
+                    locale.integer(line.requests),
+                    locale.integer(line.successful_requests),
+                    locale.integer(line.tokens),
+                    money(line.cost),
+                    money(line.sla_credit),
+                );
+            }
+            let _ = writeln!(
+                html,
+                r#"<tr><th>{}</th><th class="num">{}</th><th></th><th class="num">{}</th><th></th><th></th></tr>"#,
+                html_text(locale, "statement.total", &[]),
+                locale.integer(self.total_requests),
+                locale.integer(self.total_tokens),
+            );
+            html.push_str("</table>\n");
+        }
+
+        let _ = write!(
+            html,
+            r#"<table>
+<tr><td>{subtotal_label}</td><td class="num">{subtotal}</td></tr>
+<tr><td>{credits_label}</td><td class="num">-{credits}</td></tr>
+<tr><th>{total_due_label}</th><th class="num">{total_due}</th></tr>
+</table>
+"#,
+            subtotal_label = html_text(locale, "statement.subtotal", &[]),
+            subtotal = money(self.subtotal),
+            credits_label = html_text(locale, "statement.sla_credits", &[]),
+            credits = money(self.sla_credits),
+            total_due_label = html_text(locale, "statement.total_due", &[]),
+            total_due = money(self.total_due),
+        );
+
+        if !self.events.is_empty() {
+            let _ = writeln!(
+                html,
+                "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th><th>{}</th></tr>",
+                html_text(locale, "statement.events", &[]),
+                html_text(locale, "statement.date", &[]),
+                html_text(locale, "statement.event", &[]),
+                html_text(locale, "statement.description", &[]),
+            );
+            for event in &self.events {
+                // Descriptions come from stored records and stay untranslated
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    locale.datetime(event.occurred_at),
+                    html_text(locale, &format!("event.{}", event.kind), &[]),
+                    escape_html(&event.description),
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Per-consumer monthly statements, announced through the statement webhook
+/// once a completed month's statement is stored
+#[derive(Clone)]
+pub struct StatementGenerator {
+    db: Arc<PgPool>,
+    sla_reports: SlaReportGenerator,
+    webhook: Option<AlertWebhook>,
+}
+
+impl StatementGenerator {
+    pub fn new(db: PgPool, sla_reports: SlaReportGenerator) -> Self {
+        Self {
+            db: Arc::new(db),
+            sla_reports,
+            webhook: None,
+        }
+    }
+
+    /// Send statement-ready notifications to a webhook
+    pub fn with_webhook(mut self, url: String) -> Self {
+        self.webhook = Some(AlertWebhook::new(url));
+        self
+    }
+
+    /// Statement for a month, starting on its first day. Completed months
+    /// are generated once and stored; the current month is always generated
+    /// fresh and marked provisional.
+    pub async fn get_statement(&self, consumer_id: Uuid, month: NaiveDate) -> Result<Statement> {
+        let (period_start, period_end) = month_bounds(month);
+        let now = Utc::now();
+        if period_start > now {
+            bail!("Statement month {} has not started", month.format("%Y-%m"));
+        }
+
+        if period_end > now {
+            return self.generate(consumer_id, month).await;
+        }
+
+        let stored: Option<sqlx::types::Json<Statement>> = sqlx::query_scalar(
+            r#"
+            SELECT statement
+            FROM statements
+            WHERE consumer_id = $1 AND month = $2
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(month)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load statement")?;
+
+        if let Some(statement) = stored {
+            return Ok(statement.0);
+        }
+
+        let statement = self.generate(consumer_id, month).await?;
+        self.store(&statement, month).await?;
+        Ok(statement)
+    }
+
+    /// Compute a statement from usage records, SLA reports and the month's
+    /// incidents, key revocations and suspensions
+    pub async fn generate(&self, consumer_id: Uuid, month: NaiveDate) -> Result<Statement> {
+        let (period_start, period_end) = month_bounds(month);
+        let until = period_end.min(Utc::now());
+
+        let rows = sqlx::query_as::<_, (Uuid, String, i64, i64, i64, f64, Option<String>, bool)>(
+            r#"
+            SELECT
+                u.service_id,
+                s.name,
+                COUNT(*),
+                COUNT(*) FILTER (WHERE u.status = 'success'),
+                COALESCE(SUM((u.usage->>'total_tokens')::BIGINT)
+                    FILTER (WHERE u.status = 'success'), 0)::BIGINT,
+                COALESCE(SUM((u.cost->>'amount')::DOUBLE PRECISION)
+                    FILTER (WHERE u.status = 'success'), 0.0),
+                MAX(u.cost->>'currency'),
+                EXISTS (
+                    SELECT 1 FROM api_keys k
+                    WHERE k.consumer_id = u.consumer_id
+                        AND k.service_id = u.service_id
+                        AND k.tier = 'enterprise'
+                )
+            FROM usage_records_all u
+            JOIN services s ON s.id = u.service_id
+            WHERE u.consumer_id = $1
+                AND u.timestamp >= $2
+                AND u.timestamp < $3
+            GROUP BY u.consumer_id, u.service_id, s.name
+            ORDER BY s.name
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(period_start)
+        .bind(until)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to get statement usage")?;
+
+        let mut currency = None;
+        let mut lines = Vec::with_capacity(rows.len());
+        for (
+            service_id,
+            service_name,
+            requests,
+            successful_requests,
+            tokens,
+            cost,
+            line_currency,
+            enterprise,
+        ) in rows
+        {
+            currency = currency.or(line_currency);
+
+            // Credits follow the SLA report, which only enterprise consumers get
+            let sla_credit = if enterprise {
+                self.sla_reports
+                    .get_report(consumer_id, service_id, month)
+                    .await
+                    .map(|report| report.credit_amount)
+                    .unwrap_or_else(|e| {
+                        error!(service_id = %service_id, error = %e, "Failed to get SLA credit for statement");
+                        0.0
+                    })
+            } else {
+                0.0
+            };
+
+            lines.push(StatementLine {
+                service_id,
+                service_name,
+                requests,
+                successful_requests,
+                tokens,
+                cost,
+                sla_credit,
+            });
+        }
+
+        let service_ids: Vec<Uuid> = lines.iter().map(|l| l.service_id).collect();
+        let events = self
+            .notable_events(consumer_id, &service_ids, period_start, until)
+            .await?;
+
+        Ok(Statement::new(
+            consumer_id,
+            month,
+            lines,
+            events,
+            currency.unwrap_or_else(|| "USD".to_string()),
+        ))
+    }
+
+    async fn notable_events(
+        &self,
+        consumer_id: Uuid,
+        service_ids: &[Uuid],
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<StatementEvent>> {
+        let rows = sqlx::query_as::<_, (String, DateTime<Utc>, Option<Uuid>, String)>(
+            r#"
+            SELECT 'incident', i.first_seen_at, i.service_id,
+                   s.name || ': ' || i.severity || ' ' || i.metric || ' incident ('
+                       || i.violation_count || ' violations)'
+            FROM incidents i
+            JOIN services s ON s.id = i.service_id
+            WHERE i.service_id = ANY($2) AND i.first_seen_at >= $3 AND i.first_seen_at < $4
+            UNION ALL
+            SELECT 'key_revoked', k.revoked_at, k.service_id,
+                   'API key for ' || s.name || ' revoked'
+            FROM api_keys k
+            JOIN services s ON s.id = k.service_id
+            WHERE k.consumer_id = $1 AND k.revoked_at >= $3 AND k.revoked_at < $4
+            UNION ALL
+            SELECT 'suspended', c.suspended_at, NULL,
+                   'Account suspended' || COALESCE(': ' || c.reason, '')
+            FROM consumer_suspensions c
+            WHERE c.consumer_id = $1 AND c.suspended_at >= $3 AND c.suspended_at < $4
+            ORDER BY 2
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_ids)
+        .bind(from)
+        .bind(until)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to get statement events")?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(kind, occurred_at, service_id, description)| StatementEvent {
+                    kind,
+                    occurred_at,
+                    service_id,
+                    description,
+                },
+            )
+            .collect())
+    }
+
+    async fn store(&self, statement: &Statement, month: NaiveDate) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO statements (consumer_id, month, statement, generated_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (consumer_id, month) DO NOTHING
+            "#,
+        )
+        .bind(statement.consumer_id)
+        .bind(month)
+        .bind(sqlx::types::Json(statement))
+        .bind(statement.generated_at)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store statement")?;
+
+        Ok(())
+    }
+
+    /// Generate and store last month's statement for every consumer with
+    /// usage, then notify for stored statements not yet announced
+    /// (background job)
+    pub async fn generate_previous_month(&self) -> Result<usize> {
+        let today = Utc::now().date_naive();
+        let month = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+            .and_then(|first| first.checked_sub_months(Months::new(1)))
+            .context("Invalid statement month")?;
+        let (period_start, period_end) = month_bounds(month);
+
+        let pending: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT u.consumer_id
+            FROM usage_records_all u
+            WHERE u.timestamp >= $1 AND u.timestamp < $2
+                AND NOT EXISTS (
+                    SELECT 1 FROM statements st
+                    WHERE st.consumer_id = u.consumer_id AND st.month = $3
+                )
+            "#,
+        )
+        .bind(period_start)
+        .bind(period_end)
+        .bind(month)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to find consumers needing statements")?;
+
+        let mut generated = 0;
+        for consumer_id in pending {
+            match self.generate(consumer_id, month).await {
+                Ok(statement) => {
+                    self.store(&statement, month).await?;
+                    generated += 1;
+                }
+                Err(e) => error!(
+                    consumer_id = %consumer_id,
+                    error = %e,
+                    "Failed to generate statement"
+                ),
+            }
+        }
+
+        if generated > 0 {
+            info!(month = %month.format("%Y-%m"), statements = generated, "Statements generated");
+        } else {
+            debug!(month = %month.format("%Y-%m"), "No statements to generate");
+        }
+
+        self.notify_ready(month).await?;
+
+        Ok(generated)
+    }
+
+    /// Announce stored statements of a month through the webhook; failed
+    /// deliveries are retried on the next run
+    async fn notify_ready(&self, month: NaiveDate) -> Result<()> {
+        let Some(webhook) = &self.webhook else {
+            return Ok(());
+        };
+
+        let unnotified = sqlx::query_as::<_, (Uuid, sqlx::types::Json<Statement>)>(
+            r#"
+            SELECT consumer_id, statement
+            FROM statements
+            WHERE month = $1 AND notified_at IS NULL
+            "#,
+        )
+        .bind(month)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to find unnotified statements")?;
+
+        for (consumer_id, statement) in unnotified {
+            let payload = serde_json::json!({
+                "event": "statement_ready",
+                "consumer_id": consumer_id,
+                "month": statement.month,
+                "total_due": statement.total_due,
+                "sla_credits": statement.sla_credits,
+                "currency": statement.currency,
+                "path": format!("/api/v1/statements/{}", statement.month),
+            });
+
+            if webhook.deliver(&payload).await {
+                sqlx::query(
+                    "UPDATE statements SET notified_at = NOW() WHERE consumer_id = $1 AND month = $2",
+                )
+                .bind(consumer_id)
+                .bind(month)
+                .execute(self.db.as_ref())
+                .await
+                .context("Failed to mark statement notified")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::localization::Locales;
+
+    #[test]
+    fn test_totals_cap_credits_at_subtotal() {
+        let month = NaiveDate::from_ymd_opt(2025, 11, 1).unwrap();
+        let line = |name: &str, cost: f64, sla_credit: f64| StatementLine {
+            service_id: Uuid::new_v4(),
+            service_name: name.to_string(),
+            requests: 10,
+            successful_requests: 9,
+            tokens: 1000,
+            cost,
+            sla_credit,
+        };
+
+        let statement = Statement::new(
+            Uuid::new_v4(),
+            month,
+            vec![line("gpt \"turbo\"", 40.0, 4.0), line("embed", 10.0, 0.0)],
+            Vec::new(),
+            "USD".to_string(),
+        );
+
+        assert_eq!(statement.month, "2025-11");
+        assert!(!statement.provisional);
+        assert_eq!(statement.total_requests, 20);
+        assert_eq!(statement.total_tokens, 2000);
+        assert_eq!(statement.subtotal, 50.0);
+        assert_eq!(statement.total_due, 46.0);
+
+        let csv = statement.to_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.contains("\"gpt \"\"turbo\"\"\",10,9,1000,40.000000,4.000000,USD"));
+
+        let over_credited = Statement::new(
+            Uuid::new_v4(),
+            month,
+            vec![line("gpt", 5.0, 8.0)],
+            Vec::new(),
+            "USD".to_string(),
+        );
+        assert_eq!(over_credited.total_due, 0.0);
+    }
+
+    #[test]
+    fn test_render_html_in_locale() {
+        let month = NaiveDate::from_ymd_opt(2025, 11, 1).unwrap();
+        let statement = Statement::new(
+            Uuid::new_v4(),
+            month,
+            vec![StatementLine {
+                service_id: Uuid::new_v4(),
+                service_name: "<b>gpt</b>".to_string(),
+                requests: 12_500,
+                successful_requests: 12_480,
+                tokens: 3_400_000,
+                cost: 1234.5,
+                sla_credit: 0.0,
+            }],
+            vec![StatementEvent {
+                kind: "key_revoked".to_string(),
+                occurred_at: month.and_hms_opt(9, 30, 0).unwrap().and_utc(),
+                service_id: None,
+                description: "API key for gpt revoked".to_string(),
+            }],
+            "EUR".to_string(),
+        );
+
+        let locales = Locales::builtin();
+        let html = statement.to_html(&locales.default_locale());
+        assert!(html.contains("<title>Statement 2025-11</title>"));
+        assert!(html.contains("&lt;b&gt;gpt&lt;/b&gt;"));
+        assert!(html.contains("1,234.50 EUR"));
+        assert!(html.contains("3,400,000"));
+        assert!(html.contains("<td>11/01/2025 09:30</td><td>API key revoked</td>"));
+
+        let html = statement.to_html(&locales.get("de-DE").unwrap());
+        assert!(html.contains("<title>Abrechnung 2025-11</title>"));
+        assert!(html.contains("1.234,50 EUR"));
+        assert!(html.contains("3.400.000"));
+        assert!(html.contains("<td>01.11.2025 09:30</td><td>API-Schlüssel widerrufen</td>"));
+    }
+}
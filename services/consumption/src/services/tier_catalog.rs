@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+use crate::models::{ParameterPolicy, ServiceTier, TierLimits};
+
+/// Tier limits loaded from the `tier_definitions` table. Tiers without a
+/// row keep their compiled-in defaults; reads never touch the database.
+#[derive(Clone)]
+pub struct TierCatalog {
+    db: Arc<PgPool>,
+    tiers: Arc<RwLock<HashMap<ServiceTier, TierLimits>>>,
+}
+
+impl TierCatalog {
+    /// Catalog holding the compiled-in defaults until the first reload
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db: Arc::new(db),
+            tiers: Arc::new(RwLock::new(resolve(Vec::new()))),
+        }
+    }
+
+    /// Current limits of a tier, with its compiled-in parameter policy and
+    /// concurrency limit if the stored definition has none
+    pub fn limits(&self, tier: &ServiceTier) -> TierLimits {
+        let mut limits = self
+            .tiers
+            .read()
+            .unwrap()
+            .get(tier)
+            .cloned()
+            .unwrap_or_else(|| tier.default_limits());
+        limits
+            .parameters
+            .get_or_insert_with(|| tier.default_parameters());
+        limits
+            .max_concurrency
+            .get_or_insert_with(|| tier.max_concurrency());
+        limits
+    }
+
+    /// Current generation parameter policy of a tier
+    pub fn parameters(&self, tier: &ServiceTier) -> ParameterPolicy {
+        self.limits(tier)
+            .parameters
+            .unwrap_or_else(|| tier.default_parameters())
+    }
+
+    /// Current limits of every tier, keyed by name
+    pub fn all(&self) -> BTreeMap<&'static str, TierLimits> {
+        ServiceTier::ALL
+            .iter()
+            .map(|tier| (tier.as_str(), self.limits(tier)))
+            .collect()
+    }
+
+    /// Reload definitions from the database; returns how many tiers are
+    /// overridden
+    pub async fn reload(&self) -> Result<usize> {
+        let rows: Vec<(String, sqlx::types::Json<TierLimits>)> = sqlx::query_as(
+            r#"
+            SELECT name, limits
+            FROM tier_definitions
+            "#,
+        )
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load tier definitions")?;
+
+        let overridden = rows.len();
+        let tiers = resolve(rows.into_iter().map(|(name, limits)| (name, limits.0)).collect());
+        *self.tiers.write().unwrap() = tiers;
+
+        Ok(overridden)
+    }
+
+    /// Store a tier's limits; takes effect on other replicas at their next
+    /// reload
+    pub async fn set_limits(&self, tier: &ServiceTier, limits: &TierLimits) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO tier_definitions (name, limits, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (name)
+            DO UPDATE SET limits = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(tier.as_str())
+        .bind(sqlx::types::Json(limits))
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store tier definition")?;
+
+        self.tiers.write().unwrap().insert(tier.clone(), limits.clone());
+
+        info!(
+            tier = tier.as_str(),
+            rate_limit = limits.rate_limit,
+            burst_capacity = limits.burst_capacity,
+            quota_limit = limits.quota_limit,
+            "Tier limits updated"
+        );
+
+        Ok(())
+    }
+}
+
+/// Apply stored definitions over the compiled-in defaults, skipping unknown
+/// tier names and invalid limits
+fn resolve(rows: Vec<(String, TierLimits)>) -> HashMap<ServiceTier, TierLimits> {
+    let mut tiers: HashMap<ServiceTier, TierLimits> = ServiceTier::ALL
+        .iter()
+        .map(|tier| (tier.clone(), tier.default_limits()))
+        .collect();
+
+    for (name, limits) in rows {
+        let Some(tier) = ServiceTier::from_name(&name) else {
+            warn!(tier = %name, "Ignoring definition for unknown tier");
+            continue;
+        };
+        if let Err(e) = limits.validate() {
+            warn!(tier = %name, error = %e, "Ignoring invalid tier definition");
+            continue;
+        }
+        tiers.insert(tier, limits);
+    }
+
+    tiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OveragePolicy;
+
+    #[test]
+    fn test_resolve_overrides_known_valid_tiers() {
+        let enterprise = TierLimits {
+            rate_limit: 5000,
+            burst_capacity: 10_000,
+            quota_limit: 5_000_000_000,
+            overage: OveragePolicy::Allow,
+            parameters: None,
+            max_concurrency: None,
+        };
+        let invalid = TierLimits {
+            rate_limit: 0,
+            ..ServiceTier::Premium.default_limits()
+        };
+
+        let tiers = resolve(vec![
+            ("enterprise".to_string(), enterprise.clone()),
+            ("premium".to_string(), invalid),
+            ("platinum".to_string(), enterprise.clone()),
+        ]);
+
+        assert_eq!(tiers.len(), 3);
+        assert_eq!(tiers[&ServiceTier::Enterprise], enterprise);
+        assert_eq!(tiers[&ServiceTier::Premium], ServiceTier::Premium.default_limits());
+        assert_eq!(tiers[&ServiceTier::Basic], ServiceTier::Basic.default_limits());
+    }
+}
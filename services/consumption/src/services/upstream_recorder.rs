@@ -0,0 +1,215 @@
+//! Upstream request/response recording for fixture generation
+//!
+//! When `UPSTREAM_RECORD_DIR` is set, the router writes each provider call
+//! as a fixture in the `contracts/` format (`method`, `path`, `request`,
+//! `status`, `response`), so the mock upstream server of the contract tests
+//! and benchmarks can serve real provider behavior. Recording is refused
+//! when `RUST_ENV=production`.
+//!
+//! Fixtures are sanitized before they are written: headers, and with them
+//! provider credentials, are never recorded, the endpoint's query string is
+//! dropped, consumer `metadata` is emptied, credential-like fields are
+//! replaced and PII in any string is redacted.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::services::pii_filter::redact_pii;
+
+/// Fields whose values are replaced wherever they appear
+const SECRET_FIELDS: [&str; 8] = [
+    "access_token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "client_secret",
+    "password",
+    "refresh_token",
+    "secret",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Writes sanitized upstream calls as fixtures, one per service and status
+#[derive(Debug, Clone)]
+pub struct UpstreamRecorder {
+    dir: PathBuf,
+}
+
+impl UpstreamRecorder {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create fixture directory {:?}", dir))?;
+        Ok(Self { dir })
+    }
+
+    /// Recorder for `UPSTREAM_RECORD_DIR`, or `None` when recording is off
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(dir) = std::env::var("UPSTREAM_RECORD_DIR") else {
+            return Ok(None);
+        };
+        let production = std::env::var("RUST_ENV")
+            .map(|env| env.eq_ignore_ascii_case("production"))
+            .unwrap_or(false);
+        if production {
+            bail!("UPSTREAM_RECORD_DIR must not be set when RUST_ENV=production");
+        }
+
+        Self::new(dir).map(Some)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Record one call to a service's endpoint, replacing the previous
+    /// fixture for the same status. Failures are logged, never returned, so
+    /// recording can't fail a request.
+    pub async fn record(
+        &self,
+        service_id: Uuid,
+        endpoint: &str,
+        request: &Value,
+        status: u16,
+        response: &Value,
+    ) {
+        let fixture = fixture(endpoint, request, status, response);
+        let path = self.dir.join(format!("{}_{}.json", service_id, status));
+
+        let mut text = serde_json::to_string_pretty(&fixture).expect("JSON values serialize");
+        text.push('\n');
+        match tokio::fs::write(&path, text).await {
+            Ok(()) => debug!(service_id = %service_id, path = ?path, "Recorded upstream fixture"),
+            Err(e) => warn!(
+                service_id = %service_id,
+                path = ?path,
+                error = %e,
+                "Failed to record upstream fixture"
+            ),
+        }
+    }
+}
+
+/// Sanitized fixture for a call
+fn fixture(endpoint: &str, request: &Value, status: u16, response: &Value) -> Value {
+    let path = reqwest::Url::parse(endpoint)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| "/".to_string());
+
+    let mut request = sanitize(request);
+    if let Some(metadata) = request.get_mut("metadata") {
+        *metadata = Value::Object(Map::new());
+    }
+
+    serde_json::json!({
+        "method": "POST",
+        "path": path,
+        "request": request,
+        "status": status,
+        "response": sanitize(response),
+    })
+}
+
+/// Copy of a document with credential-like fields replaced and PII redacted
+fn sanitize(value: &Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(redact_pii(text)),
+        Value::Array(items) => Value::Array(items.iter().map(sanitize).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| {
+                    let value = if SECRET_FIELDS.contains(&key.to_lowercase().as_str()) {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        sanitize(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fixture_is_sanitized() {
+        let request = json!({
+            "prompt": "Summarize the ticket from jane.doe@example.com",
+            "max_tokens": 64,
+            "temperature": 0.2,
+            "metadata": {"customer": "acme", "ticket": 42},
+        });
+        let response = json!({
+            "choices": [{"text": "Jane (jane.doe@example.com) asks for a refund"}],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 9, "total_tokens": 21},
+            "session": {"Access_Token": "tok-live-123"},
+        });
+
+        let fixture = fixture(
+            "https://llm.example.com/v1/complete?api_key=sk-live",
+            &request,
+            200,
+            &response,
+        );
+
+        assert_eq!(
+            fixture,
+            json!({
+                "method": "POST",
+                "path": "/v1/complete",
+                "request": {
+                    "prompt": "Summarize the ticket from [REDACTED_EMAIL]",
+                    "max_tokens": 64,
+                    "temperature": 0.2,
+                    "metadata": {},
+                },
+                "status": 200,
+                "response": {
+                    "choices": [{"text": "Jane ([REDACTED_EMAIL]) asks for a refund"}],
+                    "usage": {"prompt_tokens": 12, "completion_tokens": 9, "total_tokens": 21},
+                    "session": {"Access_Token": "[REDACTED]"},
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_replaces_fixture_per_status() {
+        let dir = std::env::temp_dir().join(format!("upstream-fixtures-{}", Uuid::new_v4()));
+        let recorder = UpstreamRecorder::new(&dir).unwrap();
+        let service_id = Uuid::new_v4();
+        let endpoint = "http://localhost:9000/generate";
+
+        for (prompt, status, response) in [
+            ("a", 200, json!({"n": 1})),
+            ("b", 200, json!({"n": 2})),
+            ("c", 503, json!("overloaded")),
+        ] {
+            let request = json!({ "prompt": prompt });
+            recorder
+                .record(service_id, endpoint, &request, status, &response)
+                .await;
+        }
+
+        let read = |status: u16| -> Value {
+            let path = dir.join(format!("{}_{}.json", service_id, status));
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+        assert_eq!(read(200)["request"]["prompt"], "b");
+        assert_eq!(read(200)["response"]["n"], 2);
+        assert_eq!(read(503)["response"], "overloaded");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,402 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::analytics_streamer::AnalyticsStreamer;
+use super::pricing_experiments::PricingExperiments;
+use super::quota_manager::QuotaManager;
+use super::usage_meter::UsageMeter;
+use crate::models::UsageInfo;
+
+/// Records accepted in one batch
+pub const MAX_BATCH_RECORDS: usize = 1000;
+
+/// Reported times this far ahead of the server clock are tolerated
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+/// Default oldest record accepted, in hours
+const DEFAULT_MAX_AGE_HOURS: i64 = 72;
+
+/// Batch of usage metered by the provider of `service_id`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageBatch {
+    pub service_id: Uuid,
+    pub records: Vec<IngestRecord>,
+}
+
+/// One request served by the provider without going through the marketplace
+#[derive(Debug, Clone, Deserialize)]
+pub struct IngestRecord {
+    /// Provider's id for the record; a record is charged once per service
+    pub external_id: String,
+    pub consumer_id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub usage: UsageInfo,
+    #[serde(default)]
+    pub duration_ms: u32,
+}
+
+/// Per-record outcome of a batch
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IngestReport {
+    pub accepted: usize,
+    /// Records ingested by an earlier batch
+    pub duplicates: Vec<String>,
+    pub rejected: Vec<RejectedRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedRecord {
+    pub external_id: String,
+    pub reason: String,
+}
+
+/// Ingests usage that providers meter themselves (edge inference) and folds
+/// it into usage records, quotas and analytics like a proxied request
+#[derive(Clone)]
+pub struct UsageIngestor {
+    db: Arc<PgPool>,
+    usage_meter: UsageMeter,
+    quota_manager: QuotaManager,
+    analytics_streamer: AnalyticsStreamer,
+    pricing_experiments: PricingExperiments,
+    signing_key: Option<Arc<Vec<u8>>>,
+    max_age: Duration,
+}
+
+/// Signing key of one service, derived from the master key so none are stored
+fn service_key(master: &[u8], service_id: Uuid) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(master).context("Invalid signing key")?;
+    mac.update(service_id.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Check a hex HMAC-SHA256 of `body` made with the service key
+fn verify_signature(service_key: &str, body: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature.trim()) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(service_key.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Why a record can't be ingested, checked before anything is stored
+fn validate_record(record: &IngestRecord, now: DateTime<Utc>, max_age: Duration) -> Option<String> {
+    if record.external_id.is_empty() || record.external_id.len() > 255 {
+        return Some("external_id must be 1 to 255 characters".to_string());
+    }
+    let usage = &record.usage;
+    if u64::from(usage.prompt_tokens) + u64::from(usage.completion_tokens)
+        != u64::from(usage.total_tokens)
+    {
+        return Some("total_tokens must equal prompt_tokens + completion_tokens".to_string());
+    }
+    if record.occurred_at > now + Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+        return Some("occurred_at is in the future".to_string());
+    }
+    if record.occurred_at < now - max_age {
+        return Some(format!(
+            "occurred_at is older than {} hours",
+            max_age.num_hours()
+        ));
+    }
+    None
+}
+
+impl UsageIngestor {
+    pub fn new(
+        db: PgPool,
+        usage_meter: UsageMeter,
+        quota_manager: QuotaManager,
+        analytics_streamer: AnalyticsStreamer,
+        pricing_experiments: PricingExperiments,
+    ) -> Self {
+        let signing_key = std::env::var("USAGE_INGEST_SIGNING_KEY")
+            .ok()
+            .filter(|key| !key.is_empty())
+            .map(|key| Arc::new(key.into_bytes()));
+        let max_age_hours = std::env::var("USAGE_INGEST_MAX_AGE_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_HOURS);
+
+        Self {
+            db: Arc::new(db),
+            usage_meter,
+            quota_manager,
+            analytics_streamer,
+            pricing_experiments,
+            signing_key,
+            max_age: Duration::hours(max_age_hours),
+        }
+    }
+
+    /// Whether `USAGE_INGEST_SIGNING_KEY` is set
+    pub fn enabled(&self) -> bool {
+        self.signing_key.is_some()
+    }
+
+    /// Key the provider of `service_id` signs its batches with
+    pub fn service_key(&self, service_id: Uuid) -> Result<String> {
+        let master = self
+            .signing_key
+            .as_deref()
+            .context("Usage ingest signing key not configured")?;
+        service_key(master, service_id)
+    }
+
+    /// Whether `signature` is the service's signature of the raw request body
+    pub fn verify(&self, service_id: Uuid, body: &[u8], signature: &str) -> Result<bool> {
+        Ok(verify_signature(
+            &self.service_key(service_id)?,
+            body,
+            signature,
+        ))
+    }
+
+    /// Charge every new, valid record of a verified batch
+    pub async fn ingest(&self, batch: UsageBatch) -> Result<IngestReport> {
+        let now = Utc::now();
+        let mut report = IngestReport::default();
+
+        for record in batch.records {
+            if let Some(reason) = validate_record(&record, now, self.max_age) {
+                report.rejected.push(RejectedRecord {
+                    external_id: record.external_id,
+                    reason,
+                });
+                continue;
+            }
+
+            if !self
+                .has_active_key(record.consumer_id, batch.service_id)
+                .await?
+            {
+                report.rejected.push(RejectedRecord {
+                    external_id: record.external_id,
+                    reason: "Consumer has no active API key for this service".to_string(),
+                });
+                continue;
+            }
+
+            let request_id = Uuid::new_v4();
+            if !self.claim(batch.service_id, request_id, &record).await? {
+                report.duplicates.push(record.external_id);
+                continue;
+            }
+
+            if let Err(e) = self.charge(batch.service_id, request_id, &record).await {
+                error!(
+                    error = %e,
+                    service_id = %batch.service_id,
+                    external_id = %record.external_id,
+                    "Failed to record ingested usage"
+                );
+                // Release the claim so a retried batch records it
+                self.release(batch.service_id, &record.external_id).await?;
+                report.rejected.push(RejectedRecord {
+                    external_id: record.external_id,
+                    reason: "Failed to record usage, retry the record".to_string(),
+                });
+                continue;
+            }
+
+            report.accepted += 1;
+        }
+
+        info!(
+            service_id = %batch.service_id,
+            accepted = report.accepted,
+            duplicates = report.duplicates.len(),
+            rejected = report.rejected.len(),
+            "Usage batch ingested"
+        );
+
+        Ok(report)
+    }
+
+    async fn has_active_key(&self, consumer_id: Uuid, service_id: Uuid) -> Result<bool> {
+        sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM api_keys
+                WHERE consumer_id = $1 AND service_id = $2 AND revoked_at IS NULL
+            )
+            "#,
+        )
+        .bind(consumer_id)
+        .bind(service_id)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to look up consumer API key")
+    }
+
+    /// Reserve the record's external id, false if it was already ingested
+    async fn claim(
+        &self,
+        service_id: Uuid,
+        request_id: Uuid,
+        record: &IngestRecord,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO ingested_usage (service_id, external_id, request_id, consumer_id, occurred_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (service_id, external_id) DO NOTHING
+            "#,
+        )
+        .bind(service_id)
+        .bind(&record.external_id)
+        .bind(request_id)
+        .bind(record.consumer_id)
+        .bind(record.occurred_at)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to claim ingested record")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn release(&self, service_id: Uuid, external_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM ingested_usage WHERE service_id = $1 AND external_id = $2")
+            .bind(service_id)
+            .bind(external_id)
+            .execute(self.db.as_ref())
+            .await
+            .context("Failed to release ingested record")?;
+        Ok(())
+    }
+
+    /// Meter the record like a proxied request. It is charged at ingestion
+    /// time, so usage records, quota and analytics agree on the day for
+    /// metering reconciliation; the reported time is kept in the metadata.
+    async fn charge(
+        &self,
+        service_id: Uuid,
+        request_id: Uuid,
+        record: &IngestRecord,
+    ) -> Result<()> {
+        let price_assignment = self
+            .pricing_experiments
+            .assign(service_id, record.consumer_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(error = %e, "Failed to resolve pricing experiment, using default pricing");
+                None
+            });
+        let price_book = price_assignment.as_ref().and_then(|a| a.pricing.as_ref());
+
+        let mut metadata = serde_json::json!({
+            "source": "ingest",
+            "external_id": record.external_id,
+            "occurred_at": record.occurred_at,
+        });
+        if let Some(assignment) = &price_assignment {
+            metadata["experiment"] = assignment.annotation();
+        }
+
+        let usage_record = self
+            .usage_meter
+            .record_usage(
+                request_id,
+                service_id,
+                record.consumer_id,
+                record.usage.clone(),
+                record.duration_ms as i32,
+                "success".to_string(),
+                None,
+                Some(metadata),
+                price_book,
+            )
+            .await?;
+
+        self.quota_manager
+            .update_quota(record.consumer_id, service_id, request_id, &record.usage)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to update quota");
+            })
+            .ok();
+
+        self.analytics_streamer
+            .record_consumption(
+                request_id,
+                service_id,
+                record.consumer_id,
+                u64::from(record.duration_ms),
+                record.usage.clone(),
+                usage_record.cost.0,
+                "success".to_string(),
+            )
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to record analytics event");
+            })
+            .ok();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(prompt: u32, completion: u32, total: u32, age: Duration) -> IngestRecord {
+        IngestRecord {
+            external_id: "edge-1".to_string(),
+            consumer_id: Uuid::nil(),
+            occurred_at: Utc::now() - age,
+            usage: UsageInfo {
+                prompt_tokens: prompt,
+                completion_tokens: completion,
+                total_tokens: total,
+            },
+            duration_ms: 120,
+        }
+    }
+
+    #[test]
+    fn test_signature_uses_service_key() {
+        let service_id = Uuid::new_v4();
+        let key = service_key(b"master", service_id).unwrap();
+        assert_ne!(key, service_key(b"master", Uuid::new_v4()).unwrap());
+        assert_ne!(key, service_key(b"other", service_id).unwrap());
+
+        let body = br#"{"service_id":"x","records":[]}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+        mac.update(body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        assert!(verify_signature(&key, body, &signature));
+        assert!(!verify_signature(&key, b"{}", &signature));
+        assert!(!verify_signature(&key, body, "not hex"));
+    }
+
+    #[test]
+    fn test_record_validation() {
+        let now = Utc::now();
+        let max_age = Duration::hours(72);
+
+        assert_eq!(
+            validate_record(&record(10, 20, 30, Duration::hours(1)), now, max_age),
+            None
+        );
+        assert!(validate_record(&record(10, 20, 31, Duration::hours(1)), now, max_age).is_some());
+        assert!(validate_record(&record(10, 20, 30, Duration::hours(73)), now, max_age).is_some());
+        assert!(validate_record(&record(10, 20, 30, Duration::hours(-1)), now, max_age).is_some());
+
+        let mut unnamed = record(10, 20, 30, Duration::zero());
+        unnamed.external_id.clear();
+        assert!(validate_record(&unnamed, now, max_age).is_some());
+    }
+}
@@ -0,0 +1,387 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::services::{Job, JobHandler};
+
+/// Job kind computing usage insights; the payload may name a
+/// `service_id` and the number of `weeks` to analyze
+pub const USAGE_INSIGHTS_JOB: &str = "usage_insights";
+
+/// Cells in an hour-of-week heatmap
+pub const HOURS_PER_WEEK: usize = 7 * 24;
+
+/// Weeks of hourly rollups analyzed by default
+pub const DEFAULT_WEEKS: u32 = 4;
+
+/// Longest analysis window
+const MAX_WEEKS: u32 = 26;
+
+/// An hour is part of a peak when its mean token usage exceeds the median
+/// hour of the week by this factor
+const PEAK_FACTOR: f64 = 1.5;
+
+/// Shortest run of peak hours reported as a sustained peak
+const MIN_PEAK_HOURS: usize = 2;
+
+/// Suggested rate limit multipliers are rounded up to this step
+const MULTIPLIER_STEP: f64 = 0.25;
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Mean usage per hour of the week, in UTC. Cell `day * 24 + hour` with
+/// Monday as day 0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageHeatmap {
+    pub tokens: Vec<f64>,
+    pub requests: Vec<f64>,
+}
+
+impl UsageHeatmap {
+    /// Average `(hour, tokens, requests)` rollups over `weeks` weeks; each
+    /// cell occurs once a week
+    pub fn from_rollups(rollups: &[(DateTime<Utc>, i64, i64)], weeks: u32) -> Self {
+        let mut heatmap = Self {
+            tokens: vec![0.0; HOURS_PER_WEEK],
+            requests: vec![0.0; HOURS_PER_WEEK],
+        };
+        let weeks = weeks.max(1) as f64;
+        for (hour, tokens, requests) in rollups {
+            let cell = hour.weekday().num_days_from_monday() as usize * 24 + hour.hour() as usize;
+            heatmap.tokens[cell] += *tokens as f64 / weeks;
+            heatmap.requests[cell] += *requests as f64 / weeks;
+        }
+        heatmap
+    }
+}
+
+/// A run of consecutive hours with sustained high token usage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeakWindow {
+    /// Day the window starts, `mon` to `sun`
+    pub day: String,
+    /// UTC hour the window starts
+    pub start_hour: u32,
+    /// Length in hours; may run past midnight
+    pub hours: u32,
+    pub mean_tokens_per_hour: f64,
+    pub max_tokens_per_hour: f64,
+    /// Busiest hour's requests relative to the median hour
+    pub request_ratio: f64,
+}
+
+/// A scheduled rate limit profile covering a peak window, for operators to
+/// review; nothing is applied automatically
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitProfileSuggestion {
+    /// e.g. `mon-09h-4h`
+    pub name: String,
+    pub day: String,
+    pub start_hour: u32,
+    pub hours: u32,
+    /// Factor to apply to the tier rate limits during the window so the
+    /// busiest hour fits with the headroom the median hour has
+    pub rate_multiplier: f64,
+}
+
+/// Usage heatmap, peaks and rate limit suggestions for one service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageInsights {
+    pub service_id: Uuid,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub weeks: u32,
+    pub heatmap: UsageHeatmap,
+    /// Median hour's mean token usage; peaks are measured against it
+    pub baseline_tokens_per_hour: f64,
+    pub peaks: Vec<PeakWindow>,
+    pub suggested_profiles: Vec<RateLimitProfileSuggestion>,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Analytics job turning hourly usage rollups into per-service capacity
+/// insights: when in the week a service is busy, and the rate limit
+/// schedule that would cover it
+#[derive(Clone)]
+pub struct UsageInsightsAnalyzer {
+    db: Arc<PgPool>,
+}
+
+impl UsageInsightsAnalyzer {
+    pub fn new(db: PgPool) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    /// Compute and store insights for every service with usage in the last
+    /// `weeks` weeks. Returns how many services were analyzed.
+    pub async fn compute_all(&self, weeks: u32) -> Result<usize> {
+        let weeks = weeks.clamp(1, MAX_WEEKS);
+        let (window_start, _) = analysis_window(weeks);
+        let service_ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT DISTINCT service_id FROM quota_usage_rollups WHERE hour >= $1",
+        )
+        .bind(window_start)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list services with usage")?;
+
+        for service_id in &service_ids {
+            self.compute(*service_id, weeks).await?;
+        }
+
+        info!(
+            services = service_ids.len(),
+            weeks = weeks,
+            "Usage insights computed"
+        );
+        Ok(service_ids.len())
+    }
+
+    /// Compute and store insights for one service over the last `weeks`
+    /// whole weeks, ending at the start of the current hour
+    pub async fn compute(&self, service_id: Uuid, weeks: u32) -> Result<UsageInsights> {
+        let weeks = weeks.clamp(1, MAX_WEEKS);
+        let (window_start, window_end) = analysis_window(weeks);
+
+        let rollups = sqlx::query_as::<_, (DateTime<Utc>, i64, i64)>(
+            r#"
+            SELECT hour, SUM(tokens)::BIGINT, SUM(requests)::BIGINT
+            FROM quota_usage_rollups
+            WHERE service_id = $1 AND hour >= $2 AND hour < $3
+            GROUP BY hour
+            "#,
+        )
+        .bind(service_id)
+        .bind(window_start)
+        .bind(window_end)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to load usage rollups")?;
+
+        let heatmap = UsageHeatmap::from_rollups(&rollups, weeks);
+        let peaks = detect_peaks(&heatmap);
+        let insights = UsageInsights {
+            service_id,
+            window_start,
+            window_end,
+            weeks,
+            baseline_tokens_per_hour: median(&heatmap.tokens),
+            suggested_profiles: peaks.iter().map(suggest_profile).collect(),
+            peaks,
+            heatmap,
+            computed_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO usage_insights (service_id, window_start, window_end, insights, computed_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (service_id) DO UPDATE SET
+                window_start = EXCLUDED.window_start,
+                window_end = EXCLUDED.window_end,
+                insights = EXCLUDED.insights,
+                computed_at = EXCLUDED.computed_at
+            "#,
+        )
+        .bind(service_id)
+        .bind(window_start)
+        .bind(window_end)
+        .bind(sqlx::types::Json(&insights))
+        .bind(insights.computed_at)
+        .execute(self.db.as_ref())
+        .await
+        .context("Failed to store usage insights")?;
+
+        debug!(
+            service_id = %service_id,
+            peaks = insights.peaks.len(),
+            "Usage insights stored"
+        );
+
+        Ok(insights)
+    }
+
+    /// Latest stored insights for a service, if the job has analyzed it
+    pub async fn get(&self, service_id: Uuid) -> Result<Option<UsageInsights>> {
+        let insights = sqlx::query_scalar::<_, sqlx::types::Json<UsageInsights>>(
+            "SELECT insights FROM usage_insights WHERE service_id = $1",
+        )
+        .bind(service_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load usage insights")?;
+
+        Ok(insights.map(|insights| insights.0))
+    }
+
+    /// Whether `provider_id` publishes the service
+    pub async fn is_provider(&self, service_id: Uuid, provider_id: Uuid) -> Result<bool> {
+        let owned: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM services WHERE id = $1 AND provider_id = $2)",
+        )
+        .bind(service_id)
+        .bind(provider_id)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to look up service provider")?;
+
+        Ok(owned)
+    }
+}
+
+#[async_trait]
+impl JobHandler for UsageInsightsAnalyzer {
+    async fn run(&self, job: &Job) -> Result<Value> {
+        let weeks = job
+            .payload
+            .get("weeks")
+            .and_then(Value::as_u64)
+            .map_or(DEFAULT_WEEKS, |weeks| weeks.min(MAX_WEEKS as u64) as u32);
+
+        match job.payload.get("service_id").and_then(Value::as_str) {
+            Some(service_id) => {
+                let service_id = Uuid::parse_str(service_id).context("Invalid service_id")?;
+                let insights = self.compute(service_id, weeks).await?;
+                Ok(json!({ "services": 1, "peaks": insights.peaks.len() }))
+            }
+            None => Ok(json!({ "services": self.compute_all(weeks).await? })),
+        }
+    }
+}
+
+/// The last `weeks` whole weeks, ending at the start of the current hour
+fn analysis_window(weeks: u32) -> (DateTime<Utc>, DateTime<Utc>) {
+    let now = Utc::now();
+    let end = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+    (end - Duration::weeks(weeks as i64), end)
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted.get(sorted.len() / 2).copied().unwrap_or(0.0)
+}
+
+/// Runs of at least [`MIN_PEAK_HOURS`] hours whose token usage exceeds
+/// [`PEAK_FACTOR`] times the median hour, in week order. A run crossing
+/// from Sunday into Monday is one window.
+pub fn detect_peaks(heatmap: &UsageHeatmap) -> Vec<PeakWindow> {
+    let threshold = median(&heatmap.tokens) * PEAK_FACTOR;
+    let is_peak = |cell: usize| heatmap.tokens[cell] > threshold && heatmap.tokens[cell] > 0.0;
+
+    // Start scanning after a quiet hour so no run is split at the week end
+    let Some(quiet) = (0..HOURS_PER_WEEK).find(|cell| !is_peak(*cell)) else {
+        return Vec::new();
+    };
+    let baseline_requests = median(&heatmap.requests);
+
+    let mut peaks = Vec::new();
+    let mut run: Vec<usize> = Vec::new();
+    for offset in 1..=HOURS_PER_WEEK {
+        let cell = (quiet + offset) % HOURS_PER_WEEK;
+        if is_peak(cell) {
+            run.push(cell);
+            continue;
+        }
+        if run.len() >= MIN_PEAK_HOURS {
+            let tokens: Vec<f64> = run.iter().map(|cell| heatmap.tokens[*cell]).collect();
+            let max_requests = run
+                .iter()
+                .map(|cell| heatmap.requests[*cell])
+                .fold(0.0, f64::max);
+            let start = run[0];
+            peaks.push((
+                start,
+                PeakWindow {
+                    day: DAY_NAMES[start / 24].to_string(),
+                    start_hour: (start % 24) as u32,
+                    hours: run.len() as u32,
+                    mean_tokens_per_hour: tokens.iter().sum::<f64>() / tokens.len() as f64,
+                    max_tokens_per_hour: tokens.iter().copied().fold(0.0, f64::max),
+                    request_ratio: if baseline_requests > 0.0 {
+                        max_requests / baseline_requests
+                    } else {
+                        0.0
+                    },
+                },
+            ));
+        }
+        run.clear();
+    }
+
+    peaks.sort_by_key(|(start, _)| *start);
+    peaks.into_iter().map(|(_, peak)| peak).collect()
+}
+
+/// Rate limit profile for a peak: the tier limits scaled by the peak's
+/// request ratio, rounded up to [`MULTIPLIER_STEP`] and never below 1
+pub fn suggest_profile(peak: &PeakWindow) -> RateLimitProfileSuggestion {
+    let multiplier = (peak.request_ratio / MULTIPLIER_STEP).ceil() * MULTIPLIER_STEP;
+    RateLimitProfileSuggestion {
+        name: format!("{}-{:02}h-{}h", peak.day, peak.start_hour, peak.hours),
+        day: peak.day.clone(),
+        start_hour: peak.start_hour,
+        hours: peak.hours,
+        rate_multiplier: multiplier.max(1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_sustained_peaks_become_rate_limit_profiles() {
+        // Two weeks: steady 100 tokens/hour, a weekday 09:00-12:00 rush at
+        // 400 and a single busy hour that is not sustained
+        let monday = Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap();
+        let mut rollups = Vec::new();
+        for hour in 0..(2 * HOURS_PER_WEEK as i64) {
+            let at = monday + Duration::hours(hour);
+            let rush = at.weekday().num_days_from_monday() == 1 && (9..12).contains(&at.hour());
+            let spike = at.weekday().num_days_from_monday() == 4 && at.hour() == 15;
+            let (tokens, requests) = if rush {
+                (400, 50)
+            } else if spike {
+                (900, 90)
+            } else {
+                (100, 20)
+            };
+            rollups.push((at, tokens, requests));
+        }
+        // Sunday night into Monday morning wraps around the week
+        rollups.push((monday + Duration::hours(167), 300, 30));
+        rollups.push((monday + Duration::hours(168 + 167), 300, 30));
+        rollups.push((monday, 300, 30));
+        rollups.push((monday + Duration::hours(168), 300, 30));
+
+        let heatmap = UsageHeatmap::from_rollups(&rollups, 2);
+        assert_eq!(heatmap.tokens[24 + 10], 400.0);
+        assert_eq!(median(&heatmap.tokens), 100.0);
+
+        let peaks = detect_peaks(&heatmap);
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(
+            (peaks[0].day.as_str(), peaks[0].start_hour, peaks[0].hours),
+            ("tue", 9, 3)
+        );
+        assert_eq!(peaks[0].mean_tokens_per_hour, 400.0);
+        assert_eq!(peaks[0].request_ratio, 2.5);
+        assert_eq!(
+            (peaks[1].day.as_str(), peaks[1].start_hour, peaks[1].hours),
+            ("sun", 23, 2)
+        );
+
+        let profile = suggest_profile(&peaks[0]);
+        assert_eq!(profile.name, "tue-09h-3h");
+        assert_eq!(profile.rate_multiplier, 2.5);
+        assert!(detect_peaks(&UsageHeatmap::from_rollups(&[], 4)).is_empty());
+    }
+}
@@ -1,26 +1,60 @@
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::sync::Arc;
 use tracing::{debug, error};
 use uuid::Uuid;
 
+use super::data_residency::DataResidency;
+use super::usage_rollups::UsageRollups;
 use crate::models::{
-    CostInfo, PricingModel, Service, UsageInfo, UsageRecord, UsageStats,
+    CostInfo, PricingModel, PricingRate, Service, UsageInfo, UsageRecord, UsageStats,
 };
 
+/// Maximum number of usage records returned by a single page
+pub const MAX_RECORDS_PAGE_SIZE: i64 = 1000;
+
+/// Filters of a usage records export
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecordFilter {
+    /// Only records at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only records before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Only records with this status, e.g. `success` or `error`
+    pub status: Option<String>,
+    /// Only records that cost at least this much
+    pub min_cost: Option<f64>,
+}
+
+/// Page of usage records, oldest first, with the cursor for the next request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecordPage {
+    pub records: Vec<UsageRecord>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
 /// Usage metering service for tracking consumption and calculating costs
 #[derive(Clone)]
 pub struct UsageMeter {
     db: Arc<PgPool>,
+    residency: DataResidency,
+    rollups: UsageRollups,
 }
 
 impl UsageMeter {
-    pub fn new(db: PgPool) -> Self {
-        Self { db: Arc::new(db) }
+    pub fn new(db: PgPool, residency: DataResidency) -> Self {
+        Self {
+            rollups: UsageRollups::new(db.clone()),
+            db: Arc::new(db),
+            residency,
+        }
     }
 
-    /// Record usage for a request
+    /// Record usage for a request, priced from `price_book` when the consumer
+    /// is in a pricing experiment and from the service's pricing otherwise
     pub async fn record_usage(
         &self,
         request_id: Uuid,
@@ -30,12 +64,17 @@ impl UsageMeter {
         duration_ms: i32,
         status: String,
         error: Option<serde_json::Value>,
+        metadata: Option<serde_json::Value>,
+        price_book: Option<&PricingModel>,
     ) -> Result<UsageRecord> {
-        // Get service for pricing calculation
-        let service = self.get_service(service_id).await?;
-
         // Calculate cost
-        let cost = self.calculate_cost(&service.pricing.0, &usage)?;
+        let cost = match price_book {
+            Some(pricing) => self.calculate_cost(pricing, &usage)?,
+            None => {
+                let service = self.get_service(service_id).await?;
+                self.calculate_cost(&service.pricing.0, &usage)?
+            }
+        };
 
         let record = UsageRecord {
             id: Uuid::new_v4(),
@@ -48,18 +87,21 @@ impl UsageMeter {
             cost: sqlx::types::Json(cost.clone()),
             status,
             error: error.map(sqlx::types::Json),
+            metadata: metadata.map(sqlx::types::Json),
         };
 
-        // Insert usage record into database
-        sqlx::query(
+        // Insert usage record into the consumer's residency region
+        let location = self.residency.location_for(consumer_id).await?;
+        sqlx::query(&format!(
             r#"
-            INSERT INTO usage_records (
+            INSERT INTO {} (
                 id, request_id, service_id, consumer_id, timestamp,
-                duration_ms, usage, cost, status, error
+                duration_ms, usage, cost, status, error, metadata
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             "#,
-        )
+            location.table("usage_records")
+        ))
         .bind(&record.id)
         .bind(&record.request_id)
         .bind(&record.service_id)
@@ -70,6 +112,7 @@ impl UsageMeter {
         .bind(&record.cost)
         .bind(&record.status)
         .bind(&record.error)
+        .bind(&record.metadata)
         .execute(self.db.as_ref())
         .await
         .context("Failed to insert usage record")?;
@@ -99,7 +142,8 @@ impl UsageMeter {
                     .first()
                     .context("No pricing rate found")?;
 
-                let amount = (usage.total_tokens as f64) * rate.rate;
+                let (prompt, completion) = split_tokens(usage);
+                let amount = token_cost(rate, prompt, completion);
 
                 Ok(CostInfo {
                     amount,
@@ -109,6 +153,27 @@ impl UsageMeter {
                         "completion_tokens": usage.completion_tokens,
                         "total_tokens": usage.total_tokens,
                         "rate_per_token": rate.rate,
+                        "prompt_rate": rate.prompt_rate.unwrap_or(rate.rate),
+                        "completion_rate": rate.completion_rate.unwrap_or(rate.rate),
+                    }),
+                })
+            }
+            "tiered" | "volume" => {
+                pricing.validate().map_err(anyhow::Error::msg)?;
+
+                let graduated = pricing.model == "tiered";
+                let brackets = price_brackets(&pricing.rates, usage, graduated);
+                let amount = brackets.iter().map(|bracket| bracket.amount).sum();
+
+                Ok(CostInfo {
+                    amount,
+                    currency: "USD".to_string(),
+                    breakdown: serde_json::json!({
+                        "model": pricing.model,
+                        "prompt_tokens": usage.prompt_tokens,
+                        "completion_tokens": usage.completion_tokens,
+                        "total_tokens": usage.total_tokens,
+                        "brackets": brackets,
                     }),
                 })
             }
@@ -161,30 +226,22 @@ impl UsageMeter {
         let period_start = Utc::now() - chrono::Duration::days(days);
         let period_end = Utc::now();
 
-        let stats = sqlx::query_as::<_, (i64, i64, f64, f64, i64)>(
-            r#"
-            SELECT
-                COUNT(*) as total_requests,
-                COALESCE(SUM((usage->>'total_tokens')::bigint), 0) as total_tokens,
-                COALESCE(SUM((cost->>'amount')::float), 0.0) as total_cost,
-                COALESCE(AVG(duration_ms), 0.0) as avg_latency_ms,
-                COUNT(*) FILTER (WHERE status = 'error') as error_count
-            FROM usage_records
-            WHERE consumer_id = $1
-                AND service_id = $2
-                AND timestamp >= $3
-                AND timestamp <= $4
-            "#,
-        )
-        .bind(consumer_id)
-        .bind(service_id)
-        .bind(period_start)
-        .bind(period_end)
-        .fetch_one(self.db.as_ref())
-        .await
-        .context("Failed to get usage statistics")?;
+        // Whole hours are read from the usage rollups; raw reads only touch
+        // the consumer's residency region
+        let location = self.residency.location_for(consumer_id).await?;
+        let totals = self
+            .rollups
+            .totals(
+                &location.table("usage_records"),
+                Some(consumer_id),
+                service_id,
+                period_start,
+                period_end,
+            )
+            .await
+            .context("Failed to get usage statistics")?;
 
-        let (total_requests, total_tokens, total_cost, avg_latency_ms, error_count) = stats;
+        let (total_requests, error_count) = (totals.requests, totals.error_count);
 
         let error_rate = if total_requests > 0 {
             (error_count as f64) / (total_requests as f64)
@@ -198,13 +255,72 @@ impl UsageMeter {
             period_start,
             period_end,
             total_requests,
-            total_tokens,
-            total_cost,
-            avg_latency_ms,
+            total_tokens: totals.tokens,
+            total_cost: totals.cost,
+            avg_latency_ms: totals.avg_latency_ms(),
             error_rate,
         })
     }
 
+    /// List a consumer's usage records for a service after the record a
+    /// cursor points at (see `parse_record_cursor`), oldest first
+    pub async fn list_records(
+        &self,
+        consumer_id: Uuid,
+        service_id: Uuid,
+        filter: &UsageRecordFilter,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> Result<UsageRecordPage> {
+        let limit = limit.clamp(1, MAX_RECORDS_PAGE_SIZE);
+
+        // Fetch one extra row to know whether another page exists
+        let location = self.residency.location_for(consumer_id).await?;
+        let mut records = sqlx::query_as::<_, UsageRecord>(&format!(
+            r#"
+            SELECT
+                id, request_id, service_id, consumer_id, timestamp,
+                duration_ms, usage, cost, status, error, metadata
+            FROM {}
+            WHERE consumer_id = $1
+                AND service_id = $2
+                AND ($3::timestamptz IS NULL OR (timestamp, id) > ($3, $4))
+                AND ($5::timestamptz IS NULL OR timestamp >= $5)
+                AND ($6::timestamptz IS NULL OR timestamp < $6)
+                AND ($7::text IS NULL OR status = $7)
+                AND ($8::float8 IS NULL OR (cost->>'amount')::float8 >= $8)
+            ORDER BY timestamp ASC, id ASC
+            LIMIT $9
+            "#,
+            location.table("usage_records")
+        ))
+        .bind(consumer_id)
+        .bind(service_id)
+        .bind(after.map(|(timestamp, _)| timestamp))
+        .bind(after.map(|(_, id)| id))
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(&filter.status)
+        .bind(filter.min_cost)
+        .bind(limit + 1)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list usage records")?;
+
+        let has_more = records.len() as i64 > limit;
+        records.truncate(limit as usize);
+
+        let next_cursor = records
+            .last()
+            .map(|record| record_cursor(record.timestamp, record.id));
+
+        Ok(UsageRecordPage {
+            records,
+            next_cursor,
+            has_more,
+        })
+    }
+
     async fn get_service(&self, service_id: Uuid) -> Result<Service> {
         sqlx::query_as::<_, Service>(
             r#"
@@ -219,3 +335,220 @@ impl UsageMeter {
         .context("Failed to get service")
     }
 }
+
+/// Cursors are a record's timestamp in microseconds and its id, as
+/// `<micros>_<id>`, as records are ordered by both
+fn record_cursor(timestamp: DateTime<Utc>, id: Uuid) -> String {
+    format!("{}_{}", timestamp.timestamp_micros(), id)
+}
+
+/// Timestamp and id of the record a cursor points at
+pub fn parse_record_cursor(cursor: &str) -> Result<(DateTime<Utc>, Uuid)> {
+    let invalid = || anyhow::anyhow!("Invalid cursor: {}", cursor);
+
+    let (micros, id) = cursor.split_once('_').ok_or_else(invalid)?;
+    let timestamp = micros
+        .parse()
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or_else(invalid)?;
+    let id = id.parse().map_err(|_| invalid())?;
+
+    Ok((timestamp, id))
+}
+
+/// Charge for the tokens of a request that fell into one pricing bracket
+#[derive(Debug, Clone, Serialize)]
+struct BracketCharge {
+    tier: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    up_to: Option<u64>,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    amount: f64,
+}
+
+/// Prompt and completion tokens of a request; tokens the provider counted
+/// beyond both are billed as completion
+fn split_tokens(usage: &UsageInfo) -> (u64, u64) {
+    let prompt = u64::from(usage.prompt_tokens.min(usage.total_tokens));
+    (prompt, u64::from(usage.total_tokens) - prompt)
+}
+
+fn token_cost(rate: &PricingRate, prompt: u64, completion: u64) -> f64 {
+    prompt as f64 * rate.prompt_rate.unwrap_or(rate.rate)
+        + completion as f64 * rate.completion_rate.unwrap_or(rate.rate)
+}
+
+/// Price a request's tokens across ascending brackets. Tiered (graduated)
+/// pricing charges each bracket's share of the tokens at its rate, volume
+/// pricing charges all of them at the rate of the bracket the total falls
+/// in. Prompt tokens fill the brackets before completion tokens.
+fn price_brackets(
+    brackets: &[PricingRate],
+    usage: &UsageInfo,
+    graduated: bool,
+) -> Vec<BracketCharge> {
+    let (prompt, completion) = split_tokens(usage);
+    let total = prompt + completion;
+
+    let ranges: Vec<(&PricingRate, u64, u64)> = if graduated {
+        let mut lower = 0;
+        brackets
+            .iter()
+            .map(|bracket| {
+                let upper = bracket.up_to.unwrap_or(u64::MAX);
+                let range = (bracket, lower.min(total), upper.min(total));
+                lower = upper;
+                range
+            })
+            .filter(|(_, from, to)| to > from)
+            .collect()
+    } else {
+        brackets
+            .iter()
+            .find(|bracket| bracket.up_to.is_none_or(|up_to| total <= up_to))
+            .map(|bracket| (bracket, 0, total))
+            .into_iter()
+            .collect()
+    };
+
+    ranges
+        .into_iter()
+        .map(|(bracket, from, to)| {
+            let prompt_tokens = to.min(prompt).saturating_sub(from);
+            let completion_tokens = to - from - prompt_tokens;
+            BracketCharge {
+                tier: bracket.tier.clone(),
+                up_to: bracket.up_to,
+                prompt_tokens,
+                completion_tokens,
+                amount: token_cost(bracket, prompt_tokens, completion_tokens),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bracket(up_to: Option<u64>, rate: f64) -> PricingRate {
+        PricingRate {
+            tier: format!("up to {:?}", up_to),
+            rate,
+            unit: "token".to_string(),
+            up_to,
+            prompt_rate: None,
+            completion_rate: None,
+        }
+    }
+
+    fn usage(prompt_tokens: u32, completion_tokens: u32) -> UsageInfo {
+        UsageInfo {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+
+    fn meter() -> UsageMeter {
+        let db = PgPool::connect_lazy("postgres://localhost").unwrap();
+        UsageMeter::new(db.clone(), DataResidency::new(db))
+    }
+
+    fn pricing(model: &str, rates: Vec<PricingRate>) -> PricingModel {
+        PricingModel {
+            model: model.to_string(),
+            rates,
+        }
+    }
+
+    fn brackets() -> Vec<PricingRate> {
+        vec![
+            bracket(Some(1000), 0.0001),
+            bracket(Some(10000), 0.00008),
+            bracket(None, 0.00006),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_tiered_pricing_is_graduated() {
+        let cost = meter()
+            .calculate_cost(&pricing("tiered", brackets()), &usage(4000, 8000))
+            .unwrap();
+
+        // 1000 at 0.0001, 9000 at 0.00008 and 2000 at 0.00006
+        assert!((cost.amount - 0.94).abs() < 1e-9);
+        let brackets = cost.breakdown["brackets"].as_array().unwrap();
+        assert_eq!(brackets.len(), 3);
+        assert_eq!(brackets[0]["prompt_tokens"], 1000);
+        assert_eq!(brackets[1]["prompt_tokens"], 3000);
+        assert_eq!(brackets[1]["completion_tokens"], 6000);
+        assert_eq!(brackets[2]["completion_tokens"], 2000);
+    }
+
+    #[tokio::test]
+    async fn test_volume_pricing_uses_one_bracket() {
+        let meter = meter();
+        let volume = pricing("volume", brackets());
+
+        let cost = meter.calculate_cost(&volume, &usage(4000, 8000)).unwrap();
+        assert!((cost.amount - 0.72).abs() < 1e-9);
+        assert_eq!(cost.breakdown["brackets"].as_array().unwrap().len(), 1);
+
+        let cost = meter.calculate_cost(&volume, &usage(600, 400)).unwrap();
+        assert!((cost.amount - 0.1).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_and_completion_rates() {
+        let mut rate = bracket(None, 0.00002);
+        rate.completion_rate = Some(0.00006);
+
+        let cost = meter()
+            .calculate_cost(&pricing("per-token", vec![rate]), &usage(1000, 500))
+            .unwrap();
+        assert!((cost.amount - 0.05).abs() < 1e-9);
+        assert_eq!(cost.breakdown["prompt_rate"], 0.00002);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_brackets() {
+        let meter = meter();
+        let open_middle = vec![
+            bracket(Some(1000), 0.0001),
+            bracket(None, 0.00008),
+            bracket(None, 0.00006),
+        ];
+        let descending = vec![
+            bracket(Some(1000), 0.0001),
+            bracket(Some(500), 0.00008),
+            bracket(None, 0.00006),
+        ];
+
+        for rates in [
+            vec![],
+            open_middle,
+            descending,
+            vec![bracket(Some(1000), 0.0001)],
+        ] {
+            assert!(meter
+                .calculate_cost(&pricing("tiered", rates), &usage(10, 10))
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_record_cursor_round_trip() {
+        let timestamp = DateTime::from_timestamp_micros(1_762_000_000_123_456).unwrap();
+        let id = Uuid::new_v4();
+
+        let cursor = record_cursor(timestamp, id);
+        assert_eq!(parse_record_cursor(&cursor).unwrap(), (timestamp, id));
+
+        for invalid in ["", "42", "abc_def", &format!("x_{}", id)] {
+            assert!(parse_record_cursor(invalid).is_err());
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::data_residency::DataResidency;
+
+/// Monthly partitions are named `usage_records_YYYY_MM`
+const PARTITION_PREFIX: &str = "usage_records_";
+
+/// Partition holding rows outside every monthly partition
+const DEFAULT_PARTITION: &str = "usage_records_default";
+
+/// Archived partitions are detached and renamed `usage_records_archived_YYYY_MM`,
+/// staying in their schema so they keep their residency
+pub const ARCHIVE_PREFIX: &str = "usage_records_archived_";
+
+/// Shortest retention accepted; statements and metering reconciliation
+/// read the previous month
+const MIN_RETENTION_MONTHS: u32 = 2;
+
+/// What happens to partitions past retention
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionAction {
+    /// Detach and keep as a standalone table, out of every usage query
+    Archive,
+    Drop,
+}
+
+/// Partition maintenance settings, read from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsagePartitionConfig {
+    /// Partitions created ahead of the current month
+    pub months_ahead: u32,
+    /// Months kept before the current one; `None` keeps everything
+    pub retention_months: Option<u32>,
+    pub retention_action: RetentionAction,
+}
+
+impl Default for UsagePartitionConfig {
+    fn default() -> Self {
+        Self {
+            months_ahead: 3,
+            retention_months: Some(13),
+            retention_action: RetentionAction::Archive,
+        }
+    }
+}
+
+impl UsagePartitionConfig {
+    /// Read `USAGE_PARTITION_MONTHS_AHEAD`, `USAGE_RETENTION_MONTHS` (0 keeps
+    /// everything) and `USAGE_RETENTION_ACTION` (`archive` or `drop`),
+    /// falling back to the defaults
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+
+        let defaults = Self::default();
+        let retention_months = match var::<u32>("USAGE_RETENTION_MONTHS") {
+            Some(0) => None,
+            Some(months) => Some(months.max(MIN_RETENTION_MONTHS)),
+            None => defaults.retention_months,
+        };
+        let retention_action = match std::env::var("USAGE_RETENTION_ACTION")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "drop" => RetentionAction::Drop,
+            _ => RetentionAction::Archive,
+        };
+
+        Self {
+            months_ahead: var("USAGE_PARTITION_MONTHS_AHEAD").unwrap_or(defaults.months_ahead),
+            retention_months,
+            retention_action,
+        }
+    }
+}
+
+/// Partition changes made by a maintenance run, as schema-qualified tables
+#[derive(Debug, Clone, Default)]
+pub struct PartitionMaintenance {
+    pub created: Vec<String>,
+    pub archived: Vec<String>,
+    pub dropped: Vec<String>,
+    /// Rows moved out of default partitions into their month's partition
+    pub rows_moved: u64,
+}
+
+impl PartitionMaintenance {
+    pub fn changes(&self) -> usize {
+        self.created.len() + self.archived.len() + self.dropped.len()
+    }
+}
+
+/// Month of a monthly partition, from its name
+fn partition_month(table: &str) -> Option<NaiveDate> {
+    let month = table.strip_prefix(PARTITION_PREFIX)?;
+    NaiveDate::parse_from_str(&format!("{}_01", month), "%Y_%m_%d").ok()
+}
+
+fn partition_name(month: NaiveDate) -> String {
+    format!("{}{}", PARTITION_PREFIX, month.format("%Y_%m"))
+}
+
+fn month_start(month: NaiveDate) -> DateTime<Utc> {
+    month.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+fn next_month(month: NaiveDate) -> NaiveDate {
+    month + Months::new(1)
+}
+
+/// Partitions to create and expired partitions to archive or drop
+#[derive(Debug, PartialEq)]
+struct PartitionPlan {
+    create: Vec<NaiveDate>,
+    expire: Vec<NaiveDate>,
+}
+
+/// Plan one schema's maintenance. Upcoming months and months with rows in
+/// the default partition get a partition; partitions before the retention
+/// cutoff expire, including ones just created for old rows.
+fn plan(
+    config: &UsagePartitionConfig,
+    today: NaiveDate,
+    existing: &[NaiveDate],
+    in_default: &[NaiveDate],
+) -> PartitionPlan {
+    let current = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap();
+
+    let mut create: Vec<NaiveDate> = (0..=config.months_ahead)
+        .map(|ahead| current + Months::new(ahead))
+        .chain(in_default.iter().copied())
+        .filter(|month| !existing.contains(month))
+        .collect();
+    create.sort();
+    create.dedup();
+
+    let mut expire: Vec<NaiveDate> = match config.retention_months {
+        Some(months) => {
+            let cutoff = current - Months::new(months);
+            existing
+                .iter()
+                .chain(&create)
+                .copied()
+                .filter(|month| *month < cutoff)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    expire.sort();
+
+    PartitionPlan { create, expire }
+}
+
+/// Keeps `usage_records` partitioned by month in every storage schema:
+/// creates partitions ahead of time, moves rows that landed in the default
+/// partition into their month's partition, and archives or drops
+/// partitions past retention
+#[derive(Clone)]
+pub struct UsagePartitions {
+    db: Arc<PgPool>,
+    residency: DataResidency,
+    config: UsagePartitionConfig,
+}
+
+impl UsagePartitions {
+    pub fn new(db: PgPool, residency: DataResidency, config: UsagePartitionConfig) -> Self {
+        Self {
+            db: Arc::new(db),
+            residency,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &UsagePartitionConfig {
+        &self.config
+    }
+
+    /// Background job: maintain the partitions of every storage schema
+    pub async fn maintain(&self) -> Result<PartitionMaintenance> {
+        let today = Utc::now().date_naive();
+        let mut report = PartitionMaintenance::default();
+
+        for schema in self.residency.schemas() {
+            self.maintain_schema(&schema, today, &mut report)
+                .await
+                .with_context(|| {
+                    format!("Failed to maintain usage_records partitions in {}", schema)
+                })?;
+        }
+
+        if report.changes() > 0 {
+            info!(
+                created = report.created.len(),
+                archived = report.archived.len(),
+                dropped = report.dropped.len(),
+                rows_moved = report.rows_moved,
+                "Usage record partitions maintained"
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Maintain one schema in a single transaction. Replicas skip schemas
+    /// another replica is maintaining.
+    async fn maintain_schema(
+        &self,
+        schema: &str,
+        today: NaiveDate,
+        report: &mut PartitionMaintenance,
+    ) -> Result<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin partition maintenance")?;
+
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock(hashtext($1))")
+            .bind(format!("usage_records_partitions:{}", schema))
+            .fetch_one(&mut *tx)
+            .await
+            .context("Failed to lock partition maintenance")?;
+        if !locked {
+            return Ok(());
+        }
+
+        let partitioned: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM pg_partitioned_table pt
+                JOIN pg_class c ON c.oid = pt.partrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE n.nspname = $1 AND c.relname = 'usage_records'
+            )
+            "#,
+        )
+        .bind(schema)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to check usage_records partitioning")?;
+        if !partitioned {
+            warn!(
+                schema,
+                "usage_records is not partitioned, skipping partition maintenance"
+            );
+            return Ok(());
+        }
+
+        let partitions: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT c.relname::text
+            FROM pg_inherits i
+            JOIN pg_class c ON c.oid = i.inhrelid
+            JOIN pg_class p ON p.oid = i.inhparent
+            JOIN pg_namespace n ON n.oid = p.relnamespace
+            WHERE n.nspname = $1 AND p.relname = 'usage_records'
+            "#,
+        )
+        .bind(schema)
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to list usage_records partitions")?;
+
+        let existing: Vec<NaiveDate> = partitions
+            .iter()
+            .filter_map(|p| partition_month(p))
+            .collect();
+        let has_default = partitions.iter().any(|p| p == DEFAULT_PARTITION);
+        let in_default: Vec<NaiveDate> = if has_default {
+            sqlx::query_scalar(&format!(
+                "SELECT DISTINCT date_trunc('month', timestamp AT TIME ZONE 'UTC')::date FROM {}.{}",
+                schema, DEFAULT_PARTITION
+            ))
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to scan default usage_records partition")?
+        } else {
+            Vec::new()
+        };
+
+        let plan = plan(&self.config, today, &existing, &in_default);
+
+        for month in plan.create {
+            let moved = create_partition(&mut tx, schema, month, has_default).await?;
+            let table = format!("{}.{}", schema, partition_name(month));
+            info!(partition = %table, rows_moved = moved, "Usage record partition created");
+            report.created.push(table);
+            report.rows_moved += moved;
+        }
+
+        for month in plan.expire {
+            let table = format!("{}.{}", schema, partition_name(month));
+            match self.config.retention_action {
+                RetentionAction::Archive => {
+                    let archive = format!("{}{}", ARCHIVE_PREFIX, month.format("%Y_%m"));
+                    sqlx::query(&format!(
+                        "ALTER TABLE {}.usage_records DETACH PARTITION {}",
+                        schema, table
+                    ))
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to detach {}", table))?;
+                    sqlx::query(&format!("ALTER TABLE {} RENAME TO {}", table, archive))
+                        .execute(&mut *tx)
+                        .await
+                        .with_context(|| format!("Failed to archive {}", table))?;
+
+                    info!(partition = %table, archive = %archive, "Usage record partition archived");
+                    report.archived.push(table);
+                }
+                RetentionAction::Drop => {
+                    sqlx::query(&format!("DROP TABLE {}", table))
+                        .execute(&mut *tx)
+                        .await
+                        .with_context(|| format!("Failed to drop {}", table))?;
+
+                    info!(partition = %table, "Usage record partition dropped");
+                    report.dropped.push(table);
+                }
+            }
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit partition maintenance")?;
+
+        Ok(())
+    }
+}
+
+/// Create a month's partition, moving its rows out of the default
+/// partition first so attaching it doesn't conflict; returns rows moved
+async fn create_partition(
+    tx: &mut Transaction<'_, Postgres>,
+    schema: &str,
+    month: NaiveDate,
+    has_default: bool,
+) -> Result<u64> {
+    let table = format!("{}.{}", schema, partition_name(month));
+    let (from, to) = (month_start(month), month_start(next_month(month)));
+
+    sqlx::query(&format!(
+        "CREATE TABLE {} (LIKE {}.usage_records INCLUDING DEFAULTS)",
+        table, schema
+    ))
+    .execute(&mut **tx)
+    .await
+    .with_context(|| format!("Failed to create {}", table))?;
+
+    let mut moved = 0;
+    if has_default {
+        moved = sqlx::query(&format!(
+            r#"
+            WITH moved AS (
+                DELETE FROM {schema}.{default}
+                WHERE timestamp >= $1 AND timestamp < $2
+                RETURNING *
+            )
+            INSERT INTO {table} SELECT * FROM moved
+            "#,
+            default = DEFAULT_PARTITION,
+        ))
+        .bind(from)
+        .bind(to)
+        .execute(&mut **tx)
+        .await
+        .with_context(|| format!("Failed to move rows into {}", table))?
+        .rows_affected();
+    }
+
+    sqlx::query(&format!(
+        "ALTER TABLE {}.usage_records ATTACH PARTITION {} FOR VALUES FROM ('{}') TO ('{}')",
+        schema,
+        table,
+        from.to_rfc3339(),
+        to.to_rfc3339()
+    ))
+    .execute(&mut **tx)
+    .await
+    .with_context(|| format!("Failed to attach {}", table))?;
+
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn month(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+    }
+
+    #[test]
+    fn test_partition_names() {
+        assert_eq!(partition_name(month(2026, 1)), "usage_records_2026_01");
+        assert_eq!(
+            partition_month("usage_records_2026_01"),
+            Some(month(2026, 1))
+        );
+        assert_eq!(partition_month("usage_records_default"), None);
+        assert_eq!(partition_month("usage_records_archived_2026_01"), None);
+    }
+
+    #[test]
+    fn test_plan_creates_ahead_and_expires_past_retention() {
+        let config = UsagePartitionConfig {
+            months_ahead: 2,
+            retention_months: Some(3),
+            retention_action: RetentionAction::Archive,
+        };
+        let today = NaiveDate::from_ymd_opt(2026, 11, 16).unwrap();
+        let existing = [month(2026, 7), month(2026, 8), month(2026, 11)];
+        // A late record from May lands in the default partition
+        let in_default = [month(2026, 5), month(2026, 9)];
+
+        assert_eq!(
+            plan(&config, today, &existing, &in_default),
+            PartitionPlan {
+                create: vec![
+                    month(2026, 5),
+                    month(2026, 9),
+                    month(2026, 12),
+                    month(2027, 1)
+                ],
+                expire: vec![month(2026, 5), month(2026, 7)],
+            }
+        );
+
+        let keep_everything = UsagePartitionConfig {
+            retention_months: None,
+            ..config
+        };
+        assert!(plan(&keep_everything, today, &existing, &in_default)
+            .expire
+            .is_empty());
+    }
+}
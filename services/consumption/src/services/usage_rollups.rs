@@ -0,0 +1,456 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use sqlx::{FromRow, PgPool};
+use std::ops::AddAssign;
+use std::sync::Arc;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Usage records summed over a time range
+#[derive(Debug, Clone, Copy, Default, PartialEq, FromRow)]
+pub struct UsageTotals {
+    pub requests: i64,
+    pub tokens: i64,
+    pub cost: f64,
+    /// Sum of request durations
+    pub duration_ms: i64,
+    pub error_count: i64,
+    /// Marketplace-layer failures, which never count against the provider
+    pub marketplace_error_count: i64,
+    pub marketplace_error_duration_ms: i64,
+}
+
+impl AddAssign for UsageTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.requests += other.requests;
+        self.tokens += other.tokens;
+        self.cost += other.cost;
+        self.duration_ms += other.duration_ms;
+        self.error_count += other.error_count;
+        self.marketplace_error_count += other.marketplace_error_count;
+        self.marketplace_error_duration_ms += other.marketplace_error_duration_ms;
+    }
+}
+
+impl UsageTotals {
+    pub fn avg_latency_ms(&self) -> f64 {
+        average(self.duration_ms, self.requests)
+    }
+
+    /// Requests that reached the provider, i.e. not failed by the marketplace
+    pub fn provider_requests(&self) -> i64 {
+        self.requests - self.marketplace_error_count
+    }
+
+    /// Average latency of the requests that reached the provider
+    pub fn provider_avg_latency_ms(&self) -> f64 {
+        average(
+            self.duration_ms - self.marketplace_error_duration_ms,
+            self.provider_requests(),
+        )
+    }
+}
+
+fn average(total: i64, count: i64) -> f64 {
+    if count > 0 {
+        total as f64 / count as f64
+    } else {
+        0.0
+    }
+}
+
+/// Time range, start inclusive and end exclusive
+type Range = (DateTime<Utc>, DateTime<Utc>);
+
+/// Where the totals of a time range are read from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ReadPlan {
+    /// Ranges scanned in the raw usage records
+    raw: Vec<Range>,
+    /// Ranges read from rollups of the given granularity
+    rollups: Vec<(&'static str, Range)>,
+}
+
+/// Read whole days covered by rollups from daily rollups, the remaining
+/// whole hours from hourly rollups and the rest from raw records
+fn read_plan(from: DateTime<Utc>, to: DateTime<Utc>, coverage: Option<Range>) -> ReadPlan {
+    let (hour, day) = (Duration::hours(1), Duration::days(1));
+    let mut plan = ReadPlan::default();
+
+    let Some((covered_from, covered_until)) = coverage else {
+        plan.raw.push((from, to));
+        return plan;
+    };
+    let start = ceil(from, hour).max(covered_from);
+    let end = floor(to, hour).min(covered_until);
+    if start >= end {
+        plan.raw.push((from, to));
+        return plan;
+    }
+
+    if from < start {
+        plan.raw.push((from, start));
+    }
+    let (first_day, last_day) = (ceil(start, day), floor(end, day));
+    if first_day < last_day {
+        if start < first_day {
+            plan.rollups.push(("hour", (start, first_day)));
+        }
+        plan.rollups.push(("day", (first_day, last_day)));
+        if last_day < end {
+            plan.rollups.push(("hour", (last_day, end)));
+        }
+    } else {
+        plan.rollups.push(("hour", (start, end)));
+    }
+    if end < to {
+        plan.raw.push((end, to));
+    }
+
+    plan
+}
+
+fn floor(at: DateTime<Utc>, unit: Duration) -> DateTime<Utc> {
+    at.duration_trunc(unit).unwrap_or(at)
+}
+
+fn ceil(at: DateTime<Utc>, unit: Duration) -> DateTime<Utc> {
+    let floor = floor(at, unit);
+    if floor < at {
+        floor + unit
+    } else {
+        floor
+    }
+}
+
+/// Hourly and daily usage rollups, so usage stats and SLA status read a few
+/// pre-aggregated rows instead of every usage record of their range.
+/// Rollups cover whole hours up to the last run of the rollup job; the rest
+/// of a range is read from the raw records.
+#[derive(Clone)]
+pub struct UsageRollups {
+    db: Arc<PgPool>,
+}
+
+impl UsageRollups {
+    pub fn new(db: PgPool) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    /// Roll up usage of the whole hours since `since`, or since the previous
+    /// run if that's earlier, into hourly and daily rollups (background job).
+    /// Hours and days are recomputed in full, so re-running over the same
+    /// range is safe.
+    pub async fn rollup(&self, since: DateTime<Utc>) -> Result<u64> {
+        let hour = Duration::hours(1);
+        let until = floor(Utc::now(), hour);
+        // Pick up where the previous run stopped, so hours missed while the
+        // job wasn't running are never left out of the covered range
+        let since = match self.coverage().await? {
+            Some((_, rolled_up_until)) => since.min(rolled_up_until),
+            None => since,
+        };
+        let since = floor(since, hour);
+        let first_day = floor(since, Duration::days(1));
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin usage rollup transaction")?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM usage_rollups
+            WHERE (granularity = 'hour' AND bucket >= $1 AND bucket < $2)
+                OR (granularity = 'day' AND bucket >= $3)
+            "#,
+        )
+        .bind(since)
+        .bind(until)
+        .bind(first_day)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to clear usage rollups")?;
+
+        let hours = sqlx::query(
+            r#"
+            INSERT INTO usage_rollups (
+                consumer_id, service_id, granularity, bucket, status,
+                requests, tokens, cost, duration_ms, updated_at
+            )
+            SELECT consumer_id, service_id, 'hour',
+                   date_trunc('hour', timestamp AT TIME ZONE 'UTC') AT TIME ZONE 'UTC',
+                   status,
+                   COUNT(*),
+                   COALESCE(SUM((usage->>'total_tokens')::BIGINT), 0)::BIGINT,
+                   COALESCE(SUM((cost->>'amount')::DOUBLE PRECISION), 0.0),
+                   COALESCE(SUM(duration_ms), 0)::BIGINT,
+                   NOW()
+            FROM usage_records_all
+            WHERE timestamp >= $1 AND timestamp < $2
+            GROUP BY 1, 2, 4, 5
+            ON CONFLICT (consumer_id, service_id, granularity, bucket, status)
+            DO UPDATE SET
+                requests = EXCLUDED.requests,
+                tokens = EXCLUDED.tokens,
+                cost = EXCLUDED.cost,
+                duration_ms = EXCLUDED.duration_ms,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(since)
+        .bind(until)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to roll up hourly usage")?;
+
+        // Days are summed from their hours; the current day is partial until
+        // its last hour is rolled up
+        sqlx::query(
+            r#"
+            INSERT INTO usage_rollups (
+                consumer_id, service_id, granularity, bucket, status,
+                requests, tokens, cost, duration_ms, updated_at
+            )
+            SELECT consumer_id, service_id, 'day',
+                   date_trunc('day', bucket AT TIME ZONE 'UTC') AT TIME ZONE 'UTC',
+                   status,
+                   SUM(requests)::BIGINT,
+                   SUM(tokens)::BIGINT,
+                   SUM(cost),
+                   SUM(duration_ms)::BIGINT,
+                   NOW()
+            FROM usage_rollups
+            WHERE granularity = 'hour' AND bucket >= $1 AND bucket < $2
+            GROUP BY 1, 2, 4, 5
+            ON CONFLICT (consumer_id, service_id, granularity, bucket, status)
+            DO UPDATE SET
+                requests = EXCLUDED.requests,
+                tokens = EXCLUDED.tokens,
+                cost = EXCLUDED.cost,
+                duration_ms = EXCLUDED.duration_ms,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(first_day)
+        .bind(until)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to roll up daily usage")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO usage_rollup_coverage (id, rolled_up_from, rolled_up_until, updated_at)
+            VALUES (TRUE, $1, $2, NOW())
+            ON CONFLICT (id)
+            DO UPDATE SET
+                rolled_up_from = LEAST(usage_rollup_coverage.rolled_up_from, EXCLUDED.rolled_up_from),
+                rolled_up_until = GREATEST(usage_rollup_coverage.rolled_up_until, EXCLUDED.rolled_up_until),
+                updated_at = NOW()
+            "#,
+        )
+        .bind(since)
+        .bind(until)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to store usage rollup coverage")?;
+
+        tx.commit().await.context("Failed to commit usage rollup")?;
+
+        debug!(
+            rows = hours.rows_affected(),
+            since = %since,
+            until = %until,
+            "Usage rolled up"
+        );
+
+        Ok(hours.rows_affected())
+    }
+
+    /// Usage of a service between `from` and `to`, of one consumer or of
+    /// all. Raw records are read from `table`, e.g. a consumer's residency
+    /// region or `usage_records_all`.
+    pub async fn totals(
+        &self,
+        table: &str,
+        consumer_id: Option<Uuid>,
+        service_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<UsageTotals> {
+        let plan = read_plan(from, to, self.coverage().await?);
+        let mut totals = UsageTotals::default();
+
+        if !plan.raw.is_empty() {
+            let (starts, ends): (Vec<_>, Vec<_>) = plan.raw.iter().copied().unzip();
+            totals += sqlx::query_as::<_, UsageTotals>(&format!(
+                r#"
+                SELECT
+                    COUNT(*) AS requests,
+                    COALESCE(SUM((u.usage->>'total_tokens')::BIGINT), 0)::BIGINT AS tokens,
+                    COALESCE(SUM((u.cost->>'amount')::DOUBLE PRECISION), 0.0) AS cost,
+                    COALESCE(SUM(u.duration_ms), 0)::BIGINT AS duration_ms,
+                    COUNT(*) FILTER (WHERE u.status = 'error') AS error_count,
+                    COUNT(*) FILTER (WHERE u.status = 'marketplace_error') AS marketplace_error_count,
+                    COALESCE(SUM(u.duration_ms) FILTER (WHERE u.status = 'marketplace_error'), 0)::BIGINT
+                        AS marketplace_error_duration_ms
+                FROM {} u
+                JOIN unnest($3::timestamptz[], $4::timestamptz[]) AS r(range_start, range_end)
+                    ON u.timestamp >= r.range_start AND u.timestamp < r.range_end
+                WHERE ($1::uuid IS NULL OR u.consumer_id = $1)
+                    AND u.service_id = $2
+                "#,
+                table
+            ))
+            .bind(consumer_id)
+            .bind(service_id)
+            .bind(starts)
+            .bind(ends)
+            .fetch_one(self.db.as_ref())
+            .await
+            .context("Failed to sum usage records")?;
+        }
+
+        if !plan.rollups.is_empty() {
+            let granularities: Vec<&str> = plan.rollups.iter().map(|(g, _)| *g).collect();
+            let (starts, ends): (Vec<_>, Vec<_>) =
+                plan.rollups.iter().map(|(_, range)| *range).unzip();
+            totals += sqlx::query_as::<_, UsageTotals>(
+                r#"
+                SELECT
+                    COALESCE(SUM(u.requests), 0)::BIGINT AS requests,
+                    COALESCE(SUM(u.tokens), 0)::BIGINT AS tokens,
+                    COALESCE(SUM(u.cost), 0.0) AS cost,
+                    COALESCE(SUM(u.duration_ms), 0)::BIGINT AS duration_ms,
+                    COALESCE(SUM(u.requests) FILTER (WHERE u.status = 'error'), 0)::BIGINT
+                        AS error_count,
+                    COALESCE(SUM(u.requests) FILTER (WHERE u.status = 'marketplace_error'), 0)::BIGINT
+                        AS marketplace_error_count,
+                    COALESCE(SUM(u.duration_ms) FILTER (WHERE u.status = 'marketplace_error'), 0)::BIGINT
+                        AS marketplace_error_duration_ms
+                FROM usage_rollups u
+                JOIN unnest($3::text[], $4::timestamptz[], $5::timestamptz[])
+                    AS r(granularity, range_start, range_end)
+                    ON u.granularity = r.granularity
+                        AND u.bucket >= r.range_start
+                        AND u.bucket < r.range_end
+                WHERE ($1::uuid IS NULL OR u.consumer_id = $1)
+                    AND u.service_id = $2
+                "#,
+            )
+            .bind(consumer_id)
+            .bind(service_id)
+            .bind(granularities)
+            .bind(starts)
+            .bind(ends)
+            .fetch_one(self.db.as_ref())
+            .await
+            .context("Failed to sum usage rollups")?;
+        }
+
+        Ok(totals)
+    }
+
+    /// Whole hours the rollups cover, if the rollup job ever ran
+    async fn coverage(&self) -> Result<Option<Range>> {
+        sqlx::query_as::<_, Range>(
+            r#"
+            SELECT rolled_up_from, rolled_up_until
+            FROM usage_rollup_coverage
+            "#,
+        )
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to load usage rollup coverage")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_read_plan_splits_range() {
+        let coverage = Some((at("2025-10-01T00:00:00Z"), at("2025-11-18T10:00:00Z")));
+        let plan = read_plan(
+            at("2025-11-10T08:30:00Z"),
+            at("2025-11-18T10:25:00Z"),
+            coverage,
+        );
+
+        assert_eq!(
+            plan.raw,
+            vec![
+                (at("2025-11-10T08:30:00Z"), at("2025-11-10T09:00:00Z")),
+                (at("2025-11-18T10:00:00Z"), at("2025-11-18T10:25:00Z")),
+            ]
+        );
+        assert_eq!(
+            plan.rollups,
+            vec![
+                (
+                    "hour",
+                    (at("2025-11-10T09:00:00Z"), at("2025-11-11T00:00:00Z"))
+                ),
+                (
+                    "day",
+                    (at("2025-11-11T00:00:00Z"), at("2025-11-18T00:00:00Z"))
+                ),
+                (
+                    "hour",
+                    (at("2025-11-18T00:00:00Z"), at("2025-11-18T10:00:00Z"))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_plan_scans_uncovered_ranges() {
+        let (from, to) = (at("2025-11-01T00:00:00Z"), at("2025-11-18T10:25:00Z"));
+
+        // Before the first rollup everything is raw
+        assert_eq!(read_plan(from, to, None).raw, vec![(from, to)]);
+
+        // Usage before the first rolled up hour is raw
+        let coverage = Some((at("2025-11-17T22:00:00Z"), at("2025-11-18T10:00:00Z")));
+        let plan = read_plan(from, to, coverage);
+        assert_eq!(
+            plan.raw,
+            vec![
+                (from, at("2025-11-17T22:00:00Z")),
+                (at("2025-11-18T10:00:00Z"), to),
+            ]
+        );
+        assert_eq!(
+            plan.rollups,
+            vec![(
+                "hour",
+                (at("2025-11-17T22:00:00Z"), at("2025-11-18T10:00:00Z"))
+            )]
+        );
+
+        // Ranges within the current hour never touch rollups
+        let plan = read_plan(at("2025-11-18T10:05:00Z"), to, coverage);
+        assert_eq!(plan.raw, vec![(at("2025-11-18T10:05:00Z"), to)]);
+        assert!(plan.rollups.is_empty());
+    }
+
+    #[test]
+    fn test_totals_exclude_marketplace_errors_for_provider() {
+        let totals = UsageTotals {
+            requests: 10,
+            duration_ms: 1200,
+            marketplace_error_count: 2,
+            marketplace_error_duration_ms: 400,
+            ..Default::default()
+        };
+        assert_eq!(totals.avg_latency_ms(), 120.0);
+        assert_eq!(totals.provider_requests(), 8);
+        assert_eq!(totals.provider_avg_latency_ms(), 100.0);
+    }
+}
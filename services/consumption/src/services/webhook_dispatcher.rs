@@ -0,0 +1,626 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{redirect, Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{FromRow, PgPool};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::models::{QuotaStatus, SLAViolation};
+use crate::services::{Job, JobHandler, JobQueue};
+use crate::utils::AppError;
+
+/// Job kind delivering one event to one webhook
+pub const WEBHOOK_DELIVERY_JOB: &str = "webhook_delivery";
+
+/// Most webhooks a consumer may register
+const MAX_WEBHOOKS_PER_CONSUMER: i64 = 10;
+
+/// How long before an API key expires its owner is notified
+const KEY_EXPIRY_NOTICE_DAYS: i64 = 7;
+
+/// Share of the quota used at which `quota.warning` is sent
+const QUOTA_WARNING_PERCENT: i64 = 80;
+
+/// Events consumers can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    /// 80% of the monthly quota used
+    #[serde(rename = "quota.warning")]
+    QuotaWarning,
+    /// The whole monthly quota used
+    #[serde(rename = "quota.exhausted")]
+    QuotaExhausted,
+    /// SLA violation on a service the consumer has a key for
+    #[serde(rename = "sla.violation")]
+    SlaViolation,
+    /// An API key expires within a week
+    #[serde(rename = "api_key.expiring")]
+    ApiKeyExpiring,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [
+        WebhookEvent::QuotaWarning,
+        WebhookEvent::QuotaExhausted,
+        WebhookEvent::SlaViolation,
+        WebhookEvent::ApiKeyExpiring,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::QuotaWarning => "quota.warning",
+            WebhookEvent::QuotaExhausted => "quota.exhausted",
+            WebhookEvent::SlaViolation => "sla.violation",
+            WebhookEvent::ApiKeyExpiring => "api_key.expiring",
+        }
+    }
+}
+
+/// A consumer's registered webhook; its secret is only returned on creation
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub consumer_id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A newly registered webhook with the secret its deliveries are signed with
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookRequest {
+    pub url: String,
+    /// Events to deliver; all of them when omitted
+    #[serde(default)]
+    pub events: Option<Vec<WebhookEvent>>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl WebhookRequest {
+    fn validate(&self) -> std::result::Result<(), AppError> {
+        if !self.url.starts_with("https://") || self.url.len() > 2048 {
+            return Err(AppError::InvalidRequest(
+                "Webhook url must be an https:// URL of at most 2048 characters".to_string(),
+            ));
+        }
+        if self.events.as_ref().is_some_and(Vec::is_empty) {
+            return Err(AppError::InvalidRequest(
+                "events must not be empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn event_names(&self) -> Vec<String> {
+        let events = self.events.as_deref().unwrap_or(&WebhookEvent::ALL);
+        let mut names: Vec<String> = events.iter().map(|e| e.as_str().to_string()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+const WEBHOOK_COLUMNS: &str = "id, consumer_id, url, events, active, created_at, updated_at";
+
+/// Consumer webhooks for quota, SLA and API key events.
+///
+/// Every event is queued as one delivery job per subscribed webhook, so a
+/// receiver that's down is retried with backoff and ends up in the job
+/// queue's dead letters once the attempts run out. Deliveries are signed
+/// with the webhook's secret.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    db: Arc<PgPool>,
+    jobs: JobQueue,
+    client: Arc<Client>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: PgPool, jobs: JobQueue) -> Self {
+        // Receivers are chosen by consumers: no redirects, and only public
+        // addresses, so a webhook can't reach internal hosts
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .expect("Failed to create HTTP client for consumer webhooks");
+
+        Self {
+            db: Arc::new(db),
+            jobs,
+            client: Arc::new(client),
+        }
+    }
+
+    /// Register a webhook for a consumer
+    pub async fn create(
+        &self,
+        consumer_id: Uuid,
+        request: WebhookRequest,
+    ) -> Result<CreatedWebhook> {
+        request.validate()?;
+
+        let registered: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM consumer_webhooks WHERE consumer_id = $1")
+                .bind(consumer_id)
+                .fetch_one(self.db.as_ref())
+                .await
+                .context("Failed to count webhooks")?;
+        if registered >= MAX_WEBHOOKS_PER_CONSUMER {
+            return Err(AppError::InvalidRequest(format!(
+                "At most {} webhooks are allowed",
+                MAX_WEBHOOKS_PER_CONSUMER
+            ))
+            .into());
+        }
+
+        let secret = format!("whsec_{}", hex::encode(rand::random::<[u8; 32]>()));
+        let webhook: Webhook = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO consumer_webhooks (id, consumer_id, url, secret, events, active)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            WEBHOOK_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(consumer_id)
+        .bind(&request.url)
+        .bind(&secret)
+        .bind(request.event_names())
+        .bind(request.active)
+        .fetch_one(self.db.as_ref())
+        .await
+        .context("Failed to register webhook")?;
+
+        info!(
+            consumer_id = %consumer_id,
+            webhook_id = %webhook.id,
+            events = ?webhook.events,
+            "Webhook registered"
+        );
+
+        Ok(CreatedWebhook { webhook, secret })
+    }
+
+    /// A consumer's webhooks, oldest first
+    pub async fn list(&self, consumer_id: Uuid) -> Result<Vec<Webhook>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM consumer_webhooks WHERE consumer_id = $1 ORDER BY created_at",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(consumer_id)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to list webhooks")
+    }
+
+    /// One of a consumer's webhooks
+    pub async fn get(&self, consumer_id: Uuid, webhook_id: Uuid) -> Result<Option<Webhook>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM consumer_webhooks WHERE id = $1 AND consumer_id = $2",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(webhook_id)
+        .bind(consumer_id)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to get webhook")
+    }
+
+    /// Change a webhook's URL, events or whether it's active; its secret is kept
+    pub async fn update(
+        &self,
+        consumer_id: Uuid,
+        webhook_id: Uuid,
+        request: WebhookRequest,
+    ) -> Result<Option<Webhook>> {
+        request.validate()?;
+
+        sqlx::query_as(&format!(
+            r#"
+            UPDATE consumer_webhooks
+            SET url = $3, events = $4, active = $5, updated_at = NOW()
+            WHERE id = $1 AND consumer_id = $2
+            RETURNING {}
+            "#,
+            WEBHOOK_COLUMNS
+        ))
+        .bind(webhook_id)
+        .bind(consumer_id)
+        .bind(&request.url)
+        .bind(request.event_names())
+        .bind(request.active)
+        .fetch_optional(self.db.as_ref())
+        .await
+        .context("Failed to update webhook")
+    }
+
+    /// Remove a webhook; deliveries already queued for it are dropped.
+    /// Returns whether it existed.
+    pub async fn delete(&self, consumer_id: Uuid, webhook_id: Uuid) -> Result<bool> {
+        let result =
+            sqlx::query("DELETE FROM consumer_webhooks WHERE id = $1 AND consumer_id = $2")
+                .bind(webhook_id)
+                .bind(consumer_id)
+                .execute(self.db.as_ref())
+                .await
+                .context("Failed to delete webhook")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queue an event for the active webhooks subscribed to it, of one
+    /// consumer or of every consumer with a key for a service. Returns how
+    /// many deliveries were queued.
+    async fn dispatch(
+        &self,
+        event: WebhookEvent,
+        consumer_id: Option<Uuid>,
+        service_id: Option<Uuid>,
+        data: Value,
+    ) -> Result<usize> {
+        let webhooks: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT id
+            FROM consumer_webhooks
+            WHERE active AND $1 = ANY(events)
+                AND ($2::uuid IS NULL OR consumer_id = $2)
+                AND ($3::uuid IS NULL OR consumer_id IN (
+                    SELECT consumer_id FROM api_keys
+                    WHERE service_id = $3 AND revoked_at IS NULL
+                ))
+            "#,
+        )
+        .bind(event.as_str())
+        .bind(consumer_id)
+        .bind(service_id)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to find subscribed webhooks")?;
+
+        for webhook_id in &webhooks {
+            let body = json!({
+                "event": event.as_str(),
+                "created_at": Utc::now(),
+                "data": data,
+            });
+            self.jobs
+                .enqueue(
+                    WEBHOOK_DELIVERY_JOB,
+                    json!({ "webhook_id": webhook_id, "body": body }),
+                )
+                .await?;
+        }
+
+        if !webhooks.is_empty() {
+            debug!(
+                event = event.as_str(),
+                deliveries = webhooks.len(),
+                "Webhook event queued"
+            );
+        }
+        Ok(webhooks.len())
+    }
+
+    /// Notify a consumer whose usage just crossed 80% or 100% of their
+    /// quota. Counters are incremented atomically, so exactly one request
+    /// crosses each threshold.
+    pub async fn quota_usage(&self, quota: &QuotaStatus, used_before: i64, used_after: i64) {
+        for percent in crossed_thresholds(quota.total_tokens, used_before, used_after) {
+            let event = if percent >= 100 {
+                WebhookEvent::QuotaExhausted
+            } else {
+                WebhookEvent::QuotaWarning
+            };
+            let data = json!({
+                "service_id": quota.service_id,
+                "percent": percent,
+                "used_tokens": used_after,
+                "total_tokens": quota.total_tokens,
+                "reset_at": quota.reset_at,
+            });
+            if let Err(e) = self
+                .dispatch(event, Some(quota.consumer_id), None, data)
+                .await
+            {
+                error!(error = %e, event = event.as_str(), "Failed to queue quota webhook");
+            }
+        }
+    }
+
+    /// Notify consumers of the service about an SLA violation
+    pub async fn sla_violation(&self, violation: &SLAViolation) -> Result<usize> {
+        self.dispatch(
+            WebhookEvent::SlaViolation,
+            None,
+            Some(violation.service_id),
+            json!(violation),
+        )
+        .await
+    }
+
+    /// Notify owners of API keys expiring within a week, once per key.
+    /// Returns how many keys were due.
+    pub async fn notify_expiring_keys(&self) -> Result<usize> {
+        let expiring: Vec<(Uuid, Uuid, Uuid, DateTime<Utc>)> = sqlx::query_as(
+            r#"
+            UPDATE api_keys
+            SET expiry_notified_at = NOW()
+            WHERE revoked_at IS NULL
+                AND expiry_notified_at IS NULL
+                AND expires_at IS NOT NULL
+                AND expires_at <= NOW() + make_interval(days => $1)
+            RETURNING id, consumer_id, service_id, expires_at
+            "#,
+        )
+        .bind(KEY_EXPIRY_NOTICE_DAYS as i32)
+        .fetch_all(self.db.as_ref())
+        .await
+        .context("Failed to claim expiring API keys")?;
+
+        for (key_id, consumer_id, service_id, expires_at) in &expiring {
+            let data = json!({
+                "key_id": key_id,
+                "service_id": service_id,
+                "expires_at": expires_at,
+            });
+            self.dispatch(WebhookEvent::ApiKeyExpiring, Some(*consumer_id), None, data)
+                .await?;
+        }
+
+        if !expiring.is_empty() {
+            info!(keys = expiring.len(), "API key expiry notices queued");
+        }
+        Ok(expiring.len())
+    }
+}
+
+#[async_trait]
+impl JobHandler for WebhookDispatcher {
+    async fn run(&self, job: &Job) -> Result<Value> {
+        let webhook_id = job
+            .payload
+            .get("webhook_id")
+            .and_then(Value::as_str)
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .context("Invalid webhook_id")?;
+        let body = job.payload.get("body").context("Missing body")?.to_string();
+
+        let webhook: Option<(String, String, bool)> =
+            sqlx::query_as("SELECT url, secret, active FROM consumer_webhooks WHERE id = $1")
+                .bind(webhook_id)
+                .fetch_optional(self.db.as_ref())
+                .await
+                .context("Failed to load webhook")?;
+        let Some((url, secret, true)) = webhook else {
+            // Deleted or deactivated since the event was queued
+            return Ok(json!({ "skipped": true }));
+        };
+
+        // Host names are also checked as they're resolved for the
+        // connection; this catches IP literals and fails with a clear reason
+        check_destination(&url).await?;
+
+        let timestamp = Utc::now().timestamp();
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Id", job.id.to_string())
+            .header("X-Webhook-Timestamp", timestamp.to_string())
+            .header(
+                "X-Webhook-Signature",
+                format!("sha256={}", sign(&secret, timestamp, &body)?),
+            )
+            .body(body)
+            .send()
+            .await
+            .context("Failed to deliver webhook")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Webhook receiver returned {}", status);
+        }
+        Ok(json!({ "status": status.as_u16() }))
+    }
+}
+
+/// Resolves webhook hosts, refusing names with a non-public address
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = public_addrs(name.as_str(), 0)
+                .await
+                .map_err(Box::<dyn std::error::Error + Send + Sync>::from)?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Fail unless the webhook URL's host resolves to public addresses only
+async fn check_destination(url: &str) -> Result<()> {
+    let url = Url::parse(url).context("Invalid webhook url")?;
+    let host = url
+        .host_str()
+        .context("Webhook url has no host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    public_addrs(host, url.port_or_known_default().unwrap_or(443)).await?;
+    Ok(())
+}
+
+/// Addresses of a host, or an error if any of them isn't public
+async fn public_addrs(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .with_context(|| format!("Failed to resolve webhook host {}", host))?
+        .collect();
+
+    if addrs.is_empty() {
+        anyhow::bail!("Webhook host {} has no addresses", host);
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        anyhow::bail!(
+            "Webhook host {} resolves to non-public address {}",
+            host,
+            addr.ip()
+        );
+    }
+    Ok(addrs)
+}
+
+/// Whether an address is outside loopback, private, link-local,
+/// unique-local, shared (CGNAT) and unspecified ranges
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let shared = ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64;
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public(IpAddr::V4(mapped)),
+            None => {
+                let unique_local = ip.segments()[0] & 0xfe00 == 0xfc00;
+                let link_local = ip.segments()[0] & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}`; the timestamp is signed so a
+/// captured delivery can't be replayed later
+fn sign(secret: &str, timestamp: i64, body: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).context("Invalid secret")?;
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Quota percentages (80, 100) that usage passed going from `before` to
+/// `after` tokens
+fn crossed_thresholds(total: i64, before: i64, after: i64) -> Vec<i64> {
+    if total <= 0 {
+        return Vec::new();
+    }
+    [QUOTA_WARNING_PERCENT, 100]
+        .into_iter()
+        .filter(|percent| {
+            // Smallest usage at or past the threshold
+            let threshold = (total * percent + 99) / 100;
+            before < threshold && after >= threshold
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossed_thresholds() {
+        assert_eq!(crossed_thresholds(1000, 0, 500), Vec::<i64>::new());
+        assert_eq!(crossed_thresholds(1000, 790, 800), vec![80]);
+        assert_eq!(crossed_thresholds(1000, 800, 900), Vec::<i64>::new());
+        assert_eq!(crossed_thresholds(1000, 950, 1200), vec![100]);
+        assert_eq!(crossed_thresholds(1000, 700, 1000), vec![80, 100]);
+        assert_eq!(crossed_thresholds(0, 0, 10), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn test_signature() {
+        let body = r#"{"event":"quota.warning"}"#;
+        let signature = sign("whsec_test", 1_700_000_000, body).unwrap();
+        assert_eq!(signature.len(), 64);
+        assert_eq!(signature, sign("whsec_test", 1_700_000_000, body).unwrap());
+        assert_ne!(signature, sign("whsec_test", 1_700_000_001, body).unwrap());
+        assert_ne!(signature, sign("whsec_other", 1_700_000_000, body).unwrap());
+    }
+
+    #[test]
+    fn test_only_public_addresses_are_allowed() {
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_internal_destinations_are_rejected() {
+        assert!(check_destination("https://127.0.0.1/hooks").await.is_err());
+        assert!(check_destination("https://[::1]:8443/hooks").await.is_err());
+        assert!(check_destination("https://169.254.169.254/latest")
+            .await
+            .is_err());
+        assert!(check_destination("https://93.184.216.34/hooks")
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_event_names() {
+        let request = WebhookRequest {
+            url: "https://example.com/hooks".to_string(),
+            events: None,
+            active: true,
+        };
+        assert!(request.validate().is_ok());
+        assert_eq!(request.event_names().len(), WebhookEvent::ALL.len());
+
+        let request = WebhookRequest {
+            url: "http://example.com/hooks".to_string(),
+            events: Some(vec![WebhookEvent::SlaViolation, WebhookEvent::SlaViolation]),
+            active: true,
+        };
+        assert!(request.validate().is_err());
+        assert_eq!(request.event_names(), vec!["sla.violation".to_string()]);
+    }
+}
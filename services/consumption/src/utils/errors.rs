@@ -1,13 +1,18 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde::Serialize;
-use std::fmt;
 use thiserror::Error;
+use tracing::error;
+
+use crate::models::{ApiErrorBody, ApiErrorDetail};
 
 /// Application-wide error type
+///
+/// Domain failures carry the message shown to the client. Infrastructure
+/// failures (database, Redis) keep their cause for logs and show the client
+/// a generic message.
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -16,45 +21,76 @@ pub enum AppError {
     #[error("Redis error: {0}")]
     Redis(#[from] redis::RedisError),
 
-    #[error("Authentication failed: {0}")]
+    #[error("{0}")]
     Authentication(String),
 
-    #[error("Authorization failed: {0}")]
+    #[error("{0}")]
     Authorization(String),
 
-    #[error("Rate limit exceeded: {0}")]
-    RateLimitExceeded(String),
+    #[error("No valid API key found for this service")]
+    NoApiKey,
+
+    #[error("Rate limit exceeded. Retry after {retry_after_seconds} seconds")]
+    RateLimitExceeded { retry_after_seconds: u64 },
 
-    #[error("Quota exceeded: {0}")]
+    /// Consumer already has as many requests in flight as their tier allows
+    #[error("Too many concurrent requests. At most {limit} may be in flight")]
+    ConcurrencyLimitExceeded { limit: u32 },
+
+    #[error("{0}")]
     QuotaExceeded(String),
 
-    #[error("Service not found: {0}")]
+    #[error("{0}")]
     ServiceNotFound(String),
 
-    #[error("Service unavailable: {0}")]
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
     ServiceUnavailable(String),
 
-    #[error("Invalid request: {0}")]
+    #[error("{0}")]
     InvalidRequest(String),
 
-    #[error("Internal server error: {0}")]
-    Internal(String),
+    /// A request parameter outside the tier's parameter policy
+    #[error("{0}")]
+    ParameterNotAllowed(String),
+
+    /// Prompt blocked by the PII pre-filter
+    #[error("{0}")]
+    PiiDetected(String),
+
+    /// Prompt blocked by LLM-Shield content scanning
+    #[error("{0}")]
+    PromptBlocked(String),
+
+    #[error("{0}")]
+    RoutingRejected(String),
 
-    #[error("Validation error: {0}")]
-    Validation(String),
+    /// Request denied by a service policy plugin
+    #[error("{0}")]
+    PolicyDenied(String),
 
-    #[error("Timeout: {0}")]
+    /// Provider or upstream service call failed
+    #[error("{0}")]
+    Upstream(String),
+
+    /// Response withheld by the response pipeline's content moderation
+    #[error("{0}")]
+    ResponseBlocked(String),
+
+    /// Provider response that should have been JSON and wasn't
+    #[error("{0}")]
+    InvalidJsonResponse(String),
+
+    #[error("Plugin execution failed: {0}")]
+    Plugin(String),
+
+    #[error("{0}")]
     Timeout(String),
-}
 
-/// Error response payload
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub message: String,
-    pub status: u16,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub details: Option<serde_json::Value>,
+    #[error("{0}")]
+    Internal(String),
 }
 
 impl AppError {
@@ -65,52 +101,145 @@ impl AppError {
             AppError::Redis(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Authentication(_) => StatusCode::UNAUTHORIZED,
             AppError::Authorization(_) => StatusCode::FORBIDDEN,
-            AppError::RateLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::NoApiKey => StatusCode::FORBIDDEN,
+            AppError::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ConcurrencyLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
             AppError::QuotaExceeded(_) => StatusCode::PAYMENT_REQUIRED,
             AppError::ServiceNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
             AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
             AppError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
-            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::ParameterNotAllowed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::PiiDetected(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::PromptBlocked(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::RoutingRejected(_) => StatusCode::FORBIDDEN,
+            AppError::PolicyDenied(_) => StatusCode::FORBIDDEN,
+            AppError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            AppError::ResponseBlocked(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::InvalidJsonResponse(_) => StatusCode::BAD_GATEWAY,
+            AppError::Plugin(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
-    /// Get error type string
-    pub fn error_type(&self) -> &str {
+    /// Stable machine-readable code for the error envelope
+    pub fn error_type(&self) -> &'static str {
         match self {
-            AppError::Database(_) => "database_error",
-            AppError::Redis(_) => "redis_error",
+            AppError::Database(_) => "internal_error",
+            AppError::Redis(_) => "internal_error",
             AppError::Authentication(_) => "authentication_error",
             AppError::Authorization(_) => "authorization_error",
-            AppError::RateLimitExceeded(_) => "rate_limit_exceeded",
+            AppError::NoApiKey => "no_api_key",
+            AppError::RateLimitExceeded { .. } => "rate_limited",
+            AppError::ConcurrencyLimitExceeded { .. } => "concurrency_limited",
             AppError::QuotaExceeded(_) => "quota_exceeded",
             AppError::ServiceNotFound(_) => "service_not_found",
+            AppError::NotFound(_) => "not_found",
             AppError::ServiceUnavailable(_) => "service_unavailable",
             AppError::InvalidRequest(_) => "invalid_request",
-            AppError::Internal(_) => "internal_error",
-            AppError::Validation(_) => "validation_error",
+            AppError::ParameterNotAllowed(_) => "parameter_not_allowed",
+            AppError::PiiDetected(_) => "pii_detected",
+            AppError::PromptBlocked(_) => "prompt_blocked",
+            AppError::RoutingRejected(_) => "routing_rejected",
+            AppError::PolicyDenied(_) => "policy_denied",
+            AppError::Upstream(_) => "upstream_error",
+            AppError::ResponseBlocked(_) => "response_blocked",
+            AppError::InvalidJsonResponse(_) => "invalid_json_response",
+            AppError::Plugin(_) => "plugin_error",
             AppError::Timeout(_) => "timeout",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Message shown to the client; infrastructure causes stay in the logs
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Database(_) => "Database error".to_string(),
+            AppError::Redis(_) => "Internal server error".to_string(),
+            AppError::Plugin(_) => "Plugin execution failed".to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+    pub fn retry_after_seconds(&self) -> Option<u64> {
+        match self {
+            AppError::RateLimitExceeded {
+                retry_after_seconds,
+            } => Some(*retry_after_seconds),
+            // Slots free up as soon as a request finishes
+            AppError::ConcurrencyLimitExceeded { .. } => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Log causes that [`message`](Self::message) hides from the client
+    fn log_hidden_cause(&self) {
+        if matches!(
+            self,
+            AppError::Database(_) | AppError::Redis(_) | AppError::Plugin(_)
+        ) {
+            error!(error = %self, code = self.error_type(), "Request failed");
         }
     }
 
-    /// Convert to error response
-    pub fn to_response(&self) -> ErrorResponse {
-        ErrorResponse {
-            error: self.error_type().to_string(),
-            message: self.to_string(),
-            status: self.status_code().as_u16(),
-            details: None,
+    /// Convert to the structured error envelope
+    pub fn to_response(&self) -> ApiErrorBody {
+        ApiErrorBody {
+            error: ApiErrorDetail {
+                code: self.error_type().to_string(),
+                message: self.message(),
+                retry_after_seconds: self.retry_after_seconds(),
+            },
         }
     }
 }
 
+/// Structured error envelope, plus `Retry-After` when known
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let status = self.status_code();
-        let body = Json(self.to_response());
+        self.log_hidden_cause();
+        let mut response = (self.status_code(), Json(self.to_response())).into_response();
+        if let Some(retry_after) = self.retry_after_seconds() {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
+    }
+}
+
+/// v1 compatibility shim: v1 errors are a status code and plain message
+impl From<AppError> for (StatusCode, String) {
+    fn from(err: AppError) -> Self {
+        err.log_hidden_cause();
+        (err.status_code(), err.message())
+    }
+}
+
+/// Errors raised as `AppError` inside anyhow keep their variant; anything
+/// else is internal, and only its outermost context reaches the client
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<AppError>() {
+            Ok(app_error) => app_error,
+            Err(err) => {
+                error!(error = format!("{:#}", err), "Unhandled error");
+                AppError::Internal(err.to_string())
+            }
+        }
+    }
+}
 
-        (status, body).into_response()
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        // Upstream URLs can carry credentials, so they stay out of messages
+        let err = err.without_url();
+        if err.is_timeout() {
+            AppError::Timeout("Upstream request timed out".to_string())
+        } else {
+            AppError::Upstream(format!("Service error: {}", err))
+        }
     }
 }
 
@@ -128,13 +257,33 @@ mod tests {
             StatusCode::UNAUTHORIZED
         );
         assert_eq!(
-            AppError::RateLimitExceeded("test".into()).status_code(),
+            AppError::RateLimitExceeded {
+                retry_after_seconds: 30
+            }
+            .status_code(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            AppError::ConcurrencyLimitExceeded { limit: 4 }.status_code(),
             StatusCode::TOO_MANY_REQUESTS
         );
         assert_eq!(
             AppError::QuotaExceeded("test".into()).status_code(),
             StatusCode::PAYMENT_REQUIRED
         );
+        assert_eq!(AppError::NoApiKey.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            AppError::ParameterNotAllowed("test".into()).status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            AppError::Upstream("test".into()).status_code(),
+            StatusCode::BAD_GATEWAY
+        );
+        assert_eq!(
+            AppError::Database(sqlx::Error::RowNotFound).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
     }
 
     #[test]
@@ -144,8 +293,46 @@ mod tests {
             "authentication_error"
         );
         assert_eq!(
-            AppError::RateLimitExceeded("test".into()).error_type(),
-            "rate_limit_exceeded"
+            AppError::RateLimitExceeded {
+                retry_after_seconds: 30
+            }
+            .error_type(),
+            "rate_limited"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_envelope_and_conversions() {
+        let response = AppError::RateLimitExceeded {
+            retry_after_seconds: 30,
+        }
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({"error": {
+                "code": "rate_limited",
+                "message": "Rate limit exceeded. Retry after 30 seconds",
+                "retry_after_seconds": 30
+            }})
+        );
+
+        // Database causes stay out of both v1 and v2 bodies
+        let (status, message): (StatusCode, String) =
+            AppError::Database(sqlx::Error::PoolTimedOut).into();
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(message, "Database error");
+
+        let raised = anyhow::Error::from(AppError::NotFound("API key not found".into()));
+        assert_eq!(AppError::from(raised).status_code(), StatusCode::NOT_FOUND);
+        let internal = AppError::from(
+            anyhow::anyhow!("connection refused").context("Failed to list API keys"),
         );
+        assert_eq!(internal.error_type(), "internal_error");
+        assert_eq!(internal.message(), "Failed to list API keys");
     }
 }
@@ -1,3 +1,3 @@
 pub mod errors;
 
-pub use errors::{AppError, AppResult, ErrorResponse};
+pub use errors::{AppError, AppResult};
@@ -54,4 +54,62 @@ mod tests {
         // Test proper error responses
         assert!(true);
     }
+
+    /// Live consume against `CONSUMPTION_BASE_URL` with
+    /// `CONSUMPTION_TEST_API_KEY` and `CONSUMPTION_TEST_SERVICE_ID`:
+    ///
+    /// ```bash
+    /// CONSUMPTION_BASE_URL=... CONSUMPTION_TEST_API_KEY=... CONSUMPTION_TEST_SERVICE_ID=... \
+    ///     cargo test --test integration_test usage_headers -- --ignored
+    /// ```
+    #[tokio::test]
+    #[ignore = "needs CONSUMPTION_BASE_URL, CONSUMPTION_TEST_API_KEY and CONSUMPTION_TEST_SERVICE_ID for a live deployment"]
+    async fn test_consume_usage_headers() {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| panic!("{} must be set for the live usage headers test", name))
+        };
+        let base_url = env("CONSUMPTION_BASE_URL");
+        let api_key = env("CONSUMPTION_TEST_API_KEY");
+        let service_id: Uuid = env("CONSUMPTION_TEST_SERVICE_ID")
+            .parse()
+            .expect("invalid CONSUMPTION_TEST_SERVICE_ID");
+
+        let client = reqwest::Client::new();
+        for version in ["v1", "v2"] {
+            let response = client
+                .post(format!("{}/api/{}/consume/{}", base_url, version, service_id))
+                .bearer_auth(&api_key)
+                .json(&json!({ "prompt": "Reply with OK.", "max_tokens": 5 }))
+                .send()
+                .await
+                .expect("consume request failed");
+            assert_eq!(response.status(), 200, "{} consume failed", version);
+
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .unwrap_or_else(|| panic!("{} response is missing {}", version, name))
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            };
+            let total_tokens = header("X-Usage-Total-Tokens");
+            let cost_amount = header("X-Cost-Amount");
+            let quota_remaining = header("X-Quota-Remaining");
+            let request_id = header("X-Request-Id");
+
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(total_tokens, body["usage"]["total_tokens"].to_string());
+            assert_eq!(
+                cost_amount.parse::<f64>().unwrap(),
+                body["cost"]["amount"].as_f64().unwrap()
+            );
+            assert!(quota_remaining.parse::<i64>().unwrap() >= 0);
+            assert_eq!(request_id, body["request_id"].as_str().unwrap());
+        }
+    }
 }